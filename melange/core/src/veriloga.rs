@@ -9,7 +9,8 @@ use libc::c_void;
 use libloading::Library;
 use log::{debug, error, info, warn};
 use openvaf::{
-    AbsPathBuf, CompilationDestination, CompilationTermination, LintLevel, OptLevel, Target,
+    AbsPathBuf, CompilationDestination, CompilationTermination, ErrorFormat, LintLevel, OptLevel,
+    Target,
 };
 
 use crate::devices::DeviceImpl;
@@ -73,6 +74,8 @@ pub fn compile_va(path: &Utf8Path, opts: &Opts) -> Result<Vec<Box<dyn DeviceImpl
         target: Target::host_target()
             .context("openvaf does currently not support this hardware/os")?,
         target_cpu: "native".to_owned(),
+        decryption_keys: Vec::new(),
+        error_format: ErrorFormat::Human,
         dry_run: false,
     };
 
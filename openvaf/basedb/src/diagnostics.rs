@@ -1,8 +1,10 @@
+pub use json::{JsonSink, SarifSink};
 pub use sink::{print_all, ConsoleSink, DiagnosticSink};
 
 use crate::lints::{Lint, LintData, LintLevel, LintSrc};
 use crate::{BaseDB, FileId};
 
+mod json;
 mod preprocessor_error;
 pub mod sink;
 mod syntax_error;
@@ -0,0 +1,307 @@
+//! Machine readable diagnostic output. These sinks are used for `--error-format=json|sarif`
+//! so that tools (like CI pipelines) can process diagnostics without parsing the human
+//! readable reports.
+
+use std::fmt::Display;
+use std::io::{self, Write};
+
+use codespan_reporting::diagnostic::{LabelStyle, Severity};
+use codespan_reporting::files::Files;
+use indexmap::IndexMap;
+use serde_json::{json, Value};
+use vfs::VfsPath;
+
+use crate::diagnostics::sink::FileSrc;
+use crate::diagnostics::{Diagnostic, DiagnosticSink, Label, Report};
+use crate::{BaseDB, FileId};
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+const TOOL_URI: &str = "https://openvaf.semimod.de";
+
+/// Emits every report as a single line JSON object as soon as it is reported:
+///
+/// ```json
+/// {"level":"error","code":"L001","lint":"...","message":"...","spans":[...],"notes":[...]}
+/// ```
+///
+/// Each span contains the file, the (one based) start/end line and column (counted in
+/// characters) and the byte offsets. Spans always point into the source file after
+/// preprocessor source-mapping (not into the expanded source).
+pub struct JsonSink<'a> {
+    error_cnt: usize,
+    db: &'a dyn BaseDB,
+    dst: Box<dyn Write + 'a>,
+    anon_paths: bool,
+}
+
+impl<'a> JsonSink<'a> {
+    pub fn new(db: &'a dyn BaseDB) -> JsonSink<'a> {
+        JsonSink::new_with(db, Box::new(io::stdout()))
+    }
+
+    pub fn new_with(db: &'a dyn BaseDB, dst: Box<dyn Write + 'a>) -> JsonSink<'a> {
+        JsonSink { error_cnt: 0, db, dst, anon_paths: false }
+    }
+
+    /// only print the filename instead of the full path, this is useful for UI tests where we do not want to expose the full path
+    pub fn annonymize_paths(&mut self) {
+        self.anon_paths = true;
+    }
+
+    fn add_lint_report(&mut self, report: Report, lint: Option<&str>) {
+        if report.severity == Severity::Error {
+            self.error_cnt += 1;
+        }
+
+        let files = FileSrc { db: self.db, anon_paths: self.anon_paths };
+        let spans: Vec<_> = report
+            .labels
+            .iter()
+            .map(|label| {
+                let span = Span::new(&files, label);
+                json!({
+                    "file": span.file.to_string(),
+                    "byte_start": label.range.start,
+                    "byte_end": label.range.end,
+                    "line_start": span.start.0,
+                    "column_start": span.start.1,
+                    "line_end": span.end.0,
+                    "column_end": span.end.1,
+                    "is_primary": label.style == LabelStyle::Primary,
+                    "label": non_empty(&label.message),
+                })
+            })
+            .collect();
+
+        let value = json!({
+            "level": level(report.severity),
+            "code": report.code,
+            "lint": lint,
+            "message": report.message,
+            "spans": spans,
+            "notes": report.notes,
+        });
+        writeln!(self.dst, "{value}").expect("failed to emit diagnostic");
+    }
+}
+
+impl DiagnosticSink for JsonSink<'_> {
+    fn add_report(&mut self, report: Report) {
+        self.add_lint_report(report, None)
+    }
+
+    fn add_diagnostic(&mut self, diagnostic: &dyn Diagnostic, root_file: FileId, db: &dyn BaseDB) {
+        if let Some(report) = diagnostic.to_report(root_file, db) {
+            self.add_lint_report(report, lint_name(diagnostic, root_file, db))
+        }
+    }
+
+    fn summary(&mut self, _target_name: &impl Display) -> bool {
+        self.error_cnt != 0
+    }
+}
+
+/// Collects all reports and emits them as a [SARIF 2.1.0](https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html)
+/// log once [`SarifSink::finish`] is called. Lints are listed as rules of the tool, the
+/// code of a report (`L001`) is used as the rule id.
+pub struct SarifSink<'a> {
+    error_cnt: usize,
+    db: &'a dyn BaseDB,
+    dst: Box<dyn Write + 'a>,
+    rules: IndexMap<String, Value, ahash::RandomState>,
+    results: Vec<Value>,
+    anon_paths: bool,
+}
+
+impl<'a> SarifSink<'a> {
+    pub fn new(db: &'a dyn BaseDB) -> SarifSink<'a> {
+        SarifSink::new_with(db, Box::new(io::stdout()))
+    }
+
+    pub fn new_with(db: &'a dyn BaseDB, dst: Box<dyn Write + 'a>) -> SarifSink<'a> {
+        SarifSink {
+            error_cnt: 0,
+            db,
+            dst,
+            rules: IndexMap::default(),
+            results: Vec::new(),
+            anon_paths: false,
+        }
+    }
+
+    /// only print the filename instead of the full path, this is useful for UI tests where we do not want to expose the full path
+    pub fn annonymize_paths(&mut self) {
+        self.anon_paths = true;
+    }
+
+    /// Writes the SARIF log with all reports collected so far.
+    pub fn finish(mut self) -> io::Result<()> {
+        let log = json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": "openvaf",
+                        "informationUri": TOOL_URI,
+                        "rules": self.rules.values().collect::<Vec<_>>(),
+                    }
+                },
+                "columnKind": "unicodeCodePoints",
+                "results": self.results,
+            }]
+        });
+        serde_json::to_writer_pretty(&mut self.dst, &log)?;
+        writeln!(self.dst)
+    }
+
+    fn add_lint_report(&mut self, report: Report, lint: Option<&str>) {
+        if report.severity == Severity::Error {
+            self.error_cnt += 1;
+        }
+
+        let files = FileSrc { db: self.db, anon_paths: self.anon_paths };
+        let mut locations = Vec::new();
+        let mut related_locations = Vec::new();
+        for label in &report.labels {
+            let span = Span::new(&files, label);
+            let mut location = json!({
+                "physicalLocation": {
+                    "artifactLocation": { "uri": file_uri(&span.file) },
+                    "region": {
+                        "startLine": span.start.0,
+                        "startColumn": span.start.1,
+                        "endLine": span.end.0,
+                        "endColumn": span.end.1,
+                    }
+                }
+            });
+            if !label.message.is_empty() {
+                location["message"] = json!({ "text": label.message });
+            }
+            match label.style {
+                LabelStyle::Primary => locations.push(location),
+                LabelStyle::Secondary => related_locations.push(location),
+            }
+        }
+
+        let mut message = report.message;
+        for note in &report.notes {
+            message.push('\n');
+            message.push_str(note);
+        }
+
+        let mut result = json!({
+            "level": sarif_level(report.severity),
+            "message": { "text": message },
+            "locations": locations,
+        });
+        if !related_locations.is_empty() {
+            result["relatedLocations"] = Value::Array(related_locations);
+        }
+        if let Some(code) = report.code {
+            if !self.rules.contains_key(&code) {
+                let mut rule = json!({ "id": code });
+                if let Some(lint) = lint {
+                    rule["name"] = json!(lint);
+                }
+                self.rules.insert(code.clone(), rule);
+            }
+            result["ruleId"] = json!(code);
+        }
+        self.results.push(result);
+    }
+}
+
+impl DiagnosticSink for SarifSink<'_> {
+    fn add_report(&mut self, report: Report) {
+        self.add_lint_report(report, None)
+    }
+
+    fn add_diagnostic(&mut self, diagnostic: &dyn Diagnostic, root_file: FileId, db: &dyn BaseDB) {
+        if let Some(report) = diagnostic.to_report(root_file, db) {
+            self.add_lint_report(report, lint_name(diagnostic, root_file, db))
+        }
+    }
+
+    fn summary(&mut self, _target_name: &impl Display) -> bool {
+        self.error_cnt != 0
+    }
+}
+
+/// The position of a label in a source file. Lines and columns start at one.
+struct Span {
+    file: VfsPath,
+    start: (usize, usize),
+    end: (usize, usize),
+}
+
+impl Span {
+    fn new(files: &FileSrc, label: &Label) -> Span {
+        let location = |byte| {
+            let location = files
+                .location(label.file_id, byte)
+                .expect("labels should always point into a valid file");
+            (location.line_number, location.column_number)
+        };
+        Span {
+            file: files.name(label.file_id).expect("labels should always point into a valid file"),
+            start: location(label.range.start),
+            end: location(label.range.end),
+        }
+    }
+}
+
+/// The name of the lint that controls the level of `diagnostic` (if any).
+fn lint_name(
+    diagnostic: &dyn Diagnostic,
+    root_file: FileId,
+    db: &dyn BaseDB,
+) -> Option<&'static str> {
+    diagnostic.lint(root_file, db).map(|(lint, _)| db.lint_data(lint).name)
+}
+
+/// Files on disk are referred to with `file://` URIs, virtual files with their plain path.
+fn file_uri(path: &VfsPath) -> String {
+    let path = match path.as_path() {
+        Some(path) => path.as_ref().to_string_lossy().replace('\\', "/"),
+        None => return path.to_string(),
+    };
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        // windows paths (C:/...) need an additional slash
+        uri.push('/');
+    }
+    for c in path.chars() {
+        match c {
+            ' ' => uri.push_str("%20"),
+            '%' => uri.push_str("%25"),
+            '#' => uri.push_str("%23"),
+            '?' => uri.push_str("%3F"),
+            c => uri.push(c),
+        }
+    }
+    uri
+}
+
+fn level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Bug => "bug",
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+        Severity::Help => "help",
+    }
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Bug | Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note | Severity::Help => "note",
+    }
+}
+
+fn non_empty(msg: &str) -> Option<&str> {
+    (!msg.is_empty()).then_some(msg)
+}
@@ -1,8 +1,9 @@
 use std::fmt::Write;
+use std::iter::once;
 
 use ahash::AHashSet;
 use stdx::iter::zip;
-use syntax::PreprocessorDiagnostic;
+use syntax::{PreprocessorDiagnostic, ProtectError, TextRange, TextSize};
 use vfs::FileId;
 
 use crate::diagnostics::{to_unified_spans, Diagnostic, Label, LabelStyle, Report};
@@ -41,7 +42,44 @@ impl Diagnostic for PreprocessorDiagnostic {
                     message: "macro not found here".to_owned(),
                 }])
             }
-            PreprocessorDiagnostic::MacroRecursion { .. } => todo!(),
+            PreprocessorDiagnostic::MacroRecursion { span, ref name, ref chain, limit } => {
+                let call = span.to_file_span(&sm);
+                let message = if limit.is_some() {
+                    format!("'`{}' is called here", name)
+                } else {
+                    format!("'`{}' is called recursively here", name)
+                };
+                let mut labels = vec![Label {
+                    style: LabelStyle::Primary,
+                    file_id: call.file,
+                    range: call.range.into(),
+                    message,
+                }];
+                labels.extend(chain.iter().rev().map(|(callee, call_site)| {
+                    let call_site = call_site.to_file_span(&sm);
+                    Label {
+                        style: LabelStyle::Secondary,
+                        file_id: call_site.file,
+                        range: call_site.range.into(),
+                        message: format!("while expanding '`{}'", callee),
+                    }
+                }));
+
+                let chain: Vec<_> = chain
+                    .iter()
+                    .map(|(callee, _)| callee)
+                    .chain(once(name))
+                    .map(|callee| format!("`{}", callee))
+                    .collect();
+                let mut notes = vec![format!("expansion chain: {}", chain.join(" -> "))];
+                if limit.is_some() {
+                    notes.push(
+                        "help: use --macro-expansion-limit to allow more nested macro expansions"
+                            .to_owned(),
+                    );
+                }
+                Report::error().with_labels(labels).with_notes(notes)
+            }
             PreprocessorDiagnostic::FileNotFound { span, .. } => {
                 let labels = if let Some(span) = span {
                     let span = span.to_file_span(&sm);
@@ -83,7 +121,8 @@ impl Diagnostic for PreprocessorDiagnostic {
                     .with_notes(vec!["only UTF-8 files are accepted".to_owned()])
                     .with_notes(vec!["help: use --encode-lossy to use the file as-is".to_owned()])
             }
-            PreprocessorDiagnostic::UnexpectedEof { expected, span } => {
+            PreprocessorDiagnostic::UnexpectedEof { expected, span }
+            | PreprocessorDiagnostic::InvalidDirective { expected, span } => {
                 let span = span.to_file_span(&sm);
                 Report::error().with_labels(vec![Label {
                     style: LabelStyle::Primary,
@@ -250,6 +289,31 @@ impl Diagnostic for PreprocessorDiagnostic {
                     },
                 ])
             }
+            PreprocessorDiagnostic::ProtectedEnvelope { ref error, span } => {
+                let mut span = span.to_file_span(&sm);
+                // only highlight the `pragma protect begin_protected line
+                let text = db.file_text(span.file).unwrap();
+                if let Some(line_end) = text[span.range].find('\n') {
+                    span.range = TextRange::at(
+                        span.range.start(),
+                        TextSize::of(&text[span.range][..line_end]),
+                    );
+                }
+                let notes = match error {
+                    ProtectError::NoMatchingKey { .. } => {
+                        vec!["help: use --decryption-key NAME=FILE to provide a key".to_owned()]
+                    }
+                    _ => Vec::new(),
+                };
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: span.file,
+                        range: span.range.into(),
+                        message: "protected envelope starts here".to_owned(),
+                    }])
+                    .with_notes(notes)
+            }
         };
 
         report.with_message(self.to_string())
@@ -13,6 +13,9 @@ use crate::{BaseDB, FileId};
 
 pub trait DiagnosticSink {
     fn add_report(&mut self, report: Report);
+    /// Finishes a compilation step. Returns `true` if any errors were reported and
+    /// compilation must be aborted.
+    fn summary(&mut self, target_name: &impl Display) -> bool;
     fn add_diagnostic(&mut self, diagnostic: &dyn Diagnostic, root_file: FileId, db: &dyn BaseDB) {
         if let Some(report) = diagnostic.to_report(root_file, db) {
             self.add_report(report)
@@ -30,9 +33,9 @@ pub trait DiagnosticSink {
     }
 }
 
-struct FileSrc<'a> {
-    db: &'a dyn BaseDB,
-    anon_paths: bool,
+pub(super) struct FileSrc<'a> {
+    pub(super) db: &'a dyn BaseDB,
+    pub(super) anon_paths: bool,
 }
 
 impl<'a> Files<'_> for FileSrc<'a> {
@@ -97,31 +100,6 @@ impl<'a> ConsoleSink<'a> {
         ConsoleSink::new_with(db, Box::new(buffer))
     }
 
-    pub fn summary(&mut self, target_name: &impl Display) -> bool {
-        if self.error_cnt != 0 {
-            let warn = if self.warning_cnt != 0 {
-                format!("; {} warning emitted", self.warning_cnt)
-            } else {
-                String::new()
-            };
-            let message = format!(
-                "could not compile `{}` due to {} previous errors{}",
-                target_name, self.error_cnt, warn
-            );
-
-            self.print_simple_message(Severity::Error, message);
-            return true;
-        }
-
-        if self.warning_cnt != 0 {
-            let message = format!("`{}` generated {} warning", target_name, self.warning_cnt);
-            self.print_simple_message(Severity::Warning, message);
-            self.warning_cnt = 0;
-        }
-
-        false
-    }
-
     pub fn print_simple_message(&mut self, severity: Severity, msg: String) {
         emit(
             &mut self.dst,
@@ -189,6 +167,31 @@ impl DiagnosticSink for ConsoleSink<'_> {
         )
         .expect("Span emitting should never fail");
     }
+
+    fn summary(&mut self, target_name: &impl Display) -> bool {
+        if self.error_cnt != 0 {
+            let warn = if self.warning_cnt != 0 {
+                format!("; {} warning emitted", self.warning_cnt)
+            } else {
+                String::new()
+            };
+            let message = format!(
+                "could not compile `{}` due to {} previous errors{}",
+                target_name, self.error_cnt, warn
+            );
+
+            self.print_simple_message(Severity::Error, message);
+            return true;
+        }
+
+        if self.warning_cnt != 0 {
+            let message = format!("`{}` generated {} warning", target_name, self.warning_cnt);
+            self.print_simple_message(Severity::Warning, message);
+            self.warning_cnt = 0;
+        }
+
+        false
+    }
 }
 
 pub fn print_all<'a>(
@@ -567,6 +567,19 @@ impl Diagnostic for SyntaxError {
 
                 Report::error().with_labels(labels)
             }
+            SyntaxError::IllegalArrayDimension { range } => {
+                let FileSpan { range, file: file_id } = parse.to_file_span(range, &sm);
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id,
+                        range: range.into(),
+                        message: "expected [M:N]".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: array bounds must be constant expressions of integer literals (parameters are not supported)".to_owned()
+                    ])
+            }
         };
 
         report.with_message(self.to_string())
@@ -15,6 +15,7 @@ use lints::{Lint, LintData, LintLevel, LintRegistry};
 use parking_lot::RwLock;
 use salsa::Durability;
 use syntax::sourcemap::SourceMap;
+pub use syntax::{DecryptionKey, DEFAULT_MACRO_EXPANSION_LIMIT};
 use syntax::{Parse, Preprocess, SourceFile, SourceProvider, TextRange, TextSize};
 use typed_index_collections::{TiSlice, TiVec};
 pub use vfs::{AbsPathBuf, FileId, FileReadError, Vfs, VfsEntry, VfsPath};
@@ -34,6 +35,10 @@ pub trait BaseDB: VfsStorage + salsa::Database {
     fn include_dirs(&self, root_file: FileId) -> Arc<[VfsPath]>;
     #[salsa::input]
     fn macro_flags(&self, file_root: FileId) -> Arc<[Arc<str>]>;
+    #[salsa::input]
+    fn decryption_keys(&self, root_file: FileId) -> Arc<[DecryptionKey]>;
+    #[salsa::input]
+    fn macro_expansion_limit(&self, root_file: FileId) -> usize;
 
     fn parse(&self, root_file: FileId) -> Parse<SourceFile>;
     fn preprocess(&self, root_file: FileId) -> Preprocess;
@@ -207,6 +212,16 @@ impl SourceProvider for SourceProviderDelegate<'_> {
         self.0.macro_flags(root_file)
     }
 
+    #[inline(always)]
+    fn decryption_keys(&self, root_file: FileId) -> Arc<[DecryptionKey]> {
+        self.0.decryption_keys(root_file)
+    }
+
+    #[inline(always)]
+    fn macro_expansion_limit(&self, root_file: FileId) -> usize {
+        self.0.macro_expansion_limit(root_file)
+    }
+
     #[inline(always)]
     fn file_text(&self, file: FileId) -> Result<Arc<str>, FileReadError> {
         self.0.file_text(file)
@@ -253,6 +268,8 @@ impl dyn BaseDB {
 
         let macro_flags: Vec<_> = STANDARD_FLAGS.iter().map(|x| Arc::from(*x)).collect();
         self.set_macro_flags(root_file, Arc::from(macro_flags));
+        self.set_decryption_keys(root_file, Arc::new([]));
+        self.set_macro_expansion_limit(root_file, DEFAULT_MACRO_EXPANSION_LIMIT);
 
         self.set_plugin_lints(&[]);
         let overwrites: Arc<[_]> = Arc::from(self.empty_global_lint_overwrites().as_ref());
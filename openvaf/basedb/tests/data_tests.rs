@@ -1,4 +1,4 @@
-use basedb::diagnostics::{ConsoleSink, DiagnosticSink};
+use basedb::diagnostics::{ConsoleSink, DiagnosticSink, JsonSink, SarifSink};
 use basedb::{BaseDB, BaseDatabase, VfsPath, VfsStorage};
 use codespan_reporting::term::termcolor::Buffer;
 use expect_test::expect_file;
@@ -42,22 +42,25 @@ impl TestDataBase {
     }
     pub fn parse_and_check(&self) -> (Parse<SourceFile>, String) {
         let root_file = self.root_file();
-        let preprocessor_diagnostics = self.preprocess(root_file).diagnostics;
         let parse = self.parse(root_file);
-        let attr_tree = self.lint_attr_tree(root_file);
 
         let mut buf = Buffer::no_color();
         {
             let mut sink = ConsoleSink::buffer(self, &mut buf);
             sink.annonymize_paths();
-            sink.add_diagnostics(&*preprocessor_diagnostics, root_file, self);
-            sink.add_diagnostics(parse.errors(), root_file, self);
-            sink.add_diagnostics(&*attr_tree.diagnostics, root_file, self);
+            self.add_diagnostics(&mut sink);
         }
         let data = buf.into_inner();
         let diagnostics = String::from_utf8(data).unwrap();
         (parse, diagnostics)
     }
+
+    pub fn add_diagnostics(&self, sink: &mut impl DiagnosticSink) {
+        let root_file = self.root_file();
+        sink.add_diagnostics(&*self.preprocess(root_file).diagnostics, root_file, self);
+        sink.add_diagnostics(self.parse(root_file).errors(), root_file, self);
+        sink.add_diagnostics(&*self.lint_attr_tree(root_file).diagnostics, root_file, self);
+    }
 }
 
 /// This impl tells salsa where to find the salsa runtime.
@@ -98,8 +101,32 @@ fn ast_test(file: &Path) -> Result {
     Ok(())
 }
 
+fn error_format_test(file: &Path) -> Result {
+    let db = TestDataBase::new_from_fs(file);
+
+    let mut buf = Vec::new();
+    {
+        let mut sink = JsonSink::new_with(&db, Box::new(&mut buf));
+        sink.annonymize_paths();
+        db.add_diagnostics(&mut sink);
+    }
+    expect_file![file.with_extension("json")].assert_eq(&String::from_utf8(buf).unwrap());
+
+    let mut buf = Vec::new();
+    {
+        let mut sink = SarifSink::new_with(&db, Box::new(&mut buf));
+        sink.annonymize_paths();
+        db.add_diagnostics(&mut sink);
+        sink.finish().unwrap();
+    }
+    expect_file![file.with_extension("sarif")].assert_eq(&String::from_utf8(buf).unwrap());
+
+    Ok(())
+}
+
 harness! {
     Test::from_dir_filtered("integration", &integration_test, &Path::is_dir, &ignore_dev_tests, &project_root().join("integration_tests")),
     Test::from_dir_filtered("ui", &ui_test, &is_va_file, &ignore_never, &openvaf_test_data("syn_ui")),
-    Test::from_dir_filtered("ast", &ast_test, &is_va_file, &ignore_never, &openvaf_test_data("ast"))
+    Test::from_dir_filtered("ast", &ast_test, &is_va_file, &ignore_never, &openvaf_test_data("ast")),
+    Test::from_dir_filtered("error_format", &error_format_test, &is_va_file, &ignore_never, &openvaf_test_data("error_format"))
 }
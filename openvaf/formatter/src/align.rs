@@ -0,0 +1,60 @@
+//! Consecutive parameter declarations are aligned into columns:
+//!
+//! ```verilog
+//! parameter real    is  = 1e-14 from (0:inf);
+//! parameter real    n   = 1.0   from (0:inf);  // emission coefficient
+//! parameter integer mod = 0     exclude 1;
+//! ```
+
+/// The (byte) offsets of the aligned parts of a parameter declaration within its line.
+pub(crate) type Columns = [Option<usize>; 4];
+
+pub(crate) const NAME: usize = 0;
+pub(crate) const EQ: usize = 1;
+pub(crate) const CONSTRAINT: usize = 2;
+pub(crate) const COMMENT: usize = 3;
+
+/// Aligns groups of parameter declarations on consecutive lines. `params` must be sorted by line.
+pub(crate) fn align_params(lines: &mut [String], params: &[(usize, Columns)]) {
+    let mut start = 0;
+    while start < params.len() {
+        let mut end = start + 1;
+        while end < params.len() && params[end].0 == params[end - 1].0 + 1 {
+            end += 1;
+        }
+        align_group(lines, &mut params[start..end].to_owned());
+        start = end;
+    }
+}
+
+fn align_group(lines: &mut [String], group: &mut [(usize, Columns)]) {
+    if group.len() < 2 {
+        return;
+    }
+
+    // offsets are in bytes but alignment must account for multi byte characters
+    let width = |line: &str, column: usize| line[..column].chars().count();
+    for i in 0..COMMENT + 1 {
+        let target = group
+            .iter()
+            .filter_map(|(line, columns)| Some(width(&lines[*line], columns[i]?)))
+            .max();
+        let target = match target {
+            Some(target) => target,
+            None => continue,
+        };
+
+        for (line, columns) in group.iter_mut() {
+            let column = match columns[i] {
+                Some(column) => column,
+                None => continue,
+            };
+            let line = &mut lines[*line];
+            let padding = target - width(line, column);
+            line.insert_str(column, &" ".repeat(padding));
+            for later in columns[i + 1..].iter_mut().flatten() {
+                *later += padding;
+            }
+        }
+    }
+}
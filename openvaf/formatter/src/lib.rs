@@ -0,0 +1,84 @@
+//! A source code formatter for Verilog-A.
+//!
+//! The formatter operates on the lossless rowan syntax trees of the `syntax` crate. As the
+//! formatted file must preserve compiler directives and macros, each file is parsed on its own
+//! without running the preprocessor. Files that can not be parsed this way
+//! (for example because a macro expands to an incomplete statement) are not formatted.
+//!
+//! The formatter normalizes:
+//!
+//! * indentation (four spaces per level) and the placement of `begin`/`end`,
+//! * the spacing around operators, commas and inside attribute lists,
+//! * the alignment of consecutive parameter declarations (including `from`/`exclude`).
+//!
+//! Comments are preserved and line breaks inside of statements are kept as continuation lines.
+
+use std::fmt::{self, Display};
+
+use syntax::{SyntaxKind, SyntaxNode, TextRange, TextSize};
+
+mod align;
+mod parse;
+mod printer;
+
+#[cfg(test)]
+mod tests;
+
+/// The error that is returned for files that can not be formatted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatError {
+    /// The part of the file that caused the error.
+    pub range: TextRange,
+    pub message: String,
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Formats the Verilog-A source code `src`.
+pub fn format(src: &str) -> Result<String, FormatError> {
+    let root = parse::parse(src)?;
+    let printer::Output { mut lines, params } = printer::print(&root);
+    align::align_params(&mut lines, &params);
+
+    let mut res = lines.join("\n");
+    if !res.is_empty() {
+        res.push('\n');
+    }
+
+    // formatting must never change the meaning of a file
+    let unchanged = match parse::parse(&res) {
+        Ok(formatted) => tokens(&root).eq(tokens(&formatted)),
+        Err(_) => false,
+    };
+    if !unchanged {
+        return Err(FormatError {
+            range: TextRange::empty(TextSize::from(0)),
+            message: "formatting would change the meaning of this file (this is a bug)".to_owned(),
+        });
+    }
+
+    Ok(res)
+}
+
+/// All tokens (and comments) of a syntax tree without whitespace. The indentation of comments
+/// is ignored as the formatter indents multi line block comments.
+fn tokens(root: &SyntaxNode) -> impl Iterator<Item = (SyntaxKind, String)> + '_ {
+    root.descendants_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|token| token.kind() != SyntaxKind::WHITESPACE)
+        .map(|token| {
+            let text = match token.kind() {
+                SyntaxKind::COMMENT => {
+                    token.text().lines().map(str::trim).collect::<Vec<_>>().join("\n")
+                }
+                _ => token.text().trim_end().to_owned(),
+            };
+            (token.kind(), text)
+        })
+}
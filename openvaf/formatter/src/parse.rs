@@ -0,0 +1,255 @@
+//! Parses a single file without running the preprocessor.
+//!
+//! Regular syntax trees are created from the output of the preprocessor: macros are expanded
+//! and compiler directives are removed. The formatter must reproduce the file as it was written
+//! instead. Therefore the file is lexed directly and everything the parser can not handle is
+//! turned into trivia:
+//!
+//! * compiler directives (like `` `ifdef `` or `` `define ``) become a single `COMMENT` token
+//!   that spans the entire directive,
+//! * macro calls that occupy an entire line also become `COMMENT` tokens (these usually
+//!   expand to items or statements),
+//! * all other macro calls are treated as identifiers.
+
+use lexer::tokenize;
+use rowan::GreenNodeBuilder;
+use syntax::{SyntaxKind, SyntaxNode, TextRange, TextSize};
+use tokens::lexer::{Token, TokenKind};
+use tokens::LexerErrorKind;
+
+use crate::FormatError;
+
+#[derive(Clone, Copy)]
+struct RawToken {
+    kind: SyntaxKind,
+    range: TextRange,
+}
+
+pub(crate) fn parse(src: &str) -> Result<SyntaxNode, FormatError> {
+    let tokens = lex(src)?;
+    let parser_tokens: Vec<_> =
+        tokens.iter().map(|token| token.kind).filter(|kind| !kind.is_trivia()).collect();
+
+    let mut builder =
+        TreeBuilder { src, tokens: &tokens, pos: 0, depth: 0, inner: GreenNodeBuilder::new() };
+    for step in parser::parse(&parser_tokens).iter() {
+        match step {
+            parser::Step::Token { kind } => builder.token(kind),
+            parser::Step::Enter { kind } => builder.start_node(kind),
+            parser::Step::Exit => builder.finish_node(),
+            parser::Step::Error { err } => return Err(builder.error(err)),
+        }
+    }
+    Ok(SyntaxNode::new_root(builder.inner.finish()))
+}
+
+fn lex(src: &str) -> Result<Vec<RawToken>, FormatError> {
+    let lexed = tokenize(src);
+    let mut starts = Vec::with_capacity(lexed.len() + 1);
+    let mut offset = TextSize::from(0);
+    for token in &lexed {
+        starts.push(offset);
+        offset += token.len;
+    }
+    starts.push(offset);
+
+    let lexer = Lexer { src, lexed: &lexed, starts: &starts };
+    let mut res = Vec::with_capacity(lexed.len());
+    let mut i = 0;
+    while i < lexed.len() {
+        let range = lexer.range(i);
+        let (kind, end) = match lexed[i].kind {
+            TokenKind::Define { end } => (SyntaxKind::COMMENT, end),
+            TokenKind::IllegalDefine => (SyntaxKind::COMMENT, lexer.line_end(i)),
+            TokenKind::CompilerDirective => lexer.directive(i),
+            kind => match kind.to_syntax(&src[range]) {
+                (Some(kind), None) => (kind, i + 1),
+                (_, Some(err)) => {
+                    let message = match err {
+                        LexerErrorKind::UnterminatedBlockComment => "unterminated block comment",
+                        LexerErrorKind::UnterminatedStr => "unterminated string literal",
+                        LexerErrorKind::UnexpectedToken => "unexpected character",
+                    };
+                    return Err(FormatError { range, message: message.to_owned() });
+                }
+                (None, None) => unreachable!("compiler directives are handled above"),
+            },
+        };
+        res.push(RawToken { kind, range: TextRange::new(starts[i], starts[end]) });
+        i = end;
+    }
+    Ok(res)
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    lexed: &'a [Token],
+    starts: &'a [TextSize],
+}
+
+impl Lexer<'_> {
+    fn range(&self, i: usize) -> TextRange {
+        TextRange::new(self.starts[i], self.starts[i + 1])
+    }
+
+    fn text(&self, i: usize) -> &str {
+        &self.src[self.range(i)]
+    }
+
+    /// Determines the kind of the token that replaces the compiler directive at `i` and the
+    /// index of the first token after the directive.
+    fn directive(&self, i: usize) -> (SyntaxKind, usize) {
+        let end = match self.text(i) {
+            "`else" | "`endif" | "`resetall" | "`undefineall" => i + 1,
+            "`ifdef" | "`ifndef" | "`elsif" | "`undef" | "`include" => {
+                // the argument is the next token on the same line
+                let mut end = i + 1;
+                if self.is_space(end) {
+                    end += 1;
+                }
+                match self.lexed.get(end).map(|token| token.kind) {
+                    Some(
+                        TokenKind::Whitespace
+                        | TokenKind::LineComment
+                        | TokenKind::BlockComment { .. },
+                    )
+                    | None => i + 1,
+                    Some(_) => end + 1,
+                }
+            }
+            "`pragma" if self.line_rest(i + 1).trim_start().starts_with("protect") => {
+                self.protected_envelope(i)
+            }
+            "`timescale" | "`default_discipline" | "`default_transition" | "`pragma" => {
+                self.line_end(i)
+            }
+            _ => return self.macro_call(i),
+        };
+        (SyntaxKind::COMMENT, end)
+    }
+
+    /// Encrypted data can not be lexed, so everything up to (and including) the line that
+    /// ends the protected envelope is kept as it is.
+    fn protected_envelope(&self, i: usize) -> usize {
+        if !self.line_rest(i).contains("begin_protected") {
+            return self.line_end(i);
+        }
+        let start = usize::from(self.starts[i]);
+        let end = match self.src[start..].find("end_protected") {
+            Some(pos) => {
+                let pos = start + pos;
+                self.src[pos..].find('\n').map_or(self.src.len(), |line_end| pos + line_end)
+            }
+            None => self.src.len(),
+        };
+        let end = TextSize::from(end as u32);
+        (i + 1..self.lexed.len()).find(|&j| self.starts[j] >= end).unwrap_or(self.lexed.len())
+    }
+
+    /// Returns the index of the first token after `i` that starts a comment or a new line.
+    fn line_end(&self, i: usize) -> usize {
+        (i + 1..self.lexed.len())
+            .find(|&j| match self.lexed[j].kind {
+                TokenKind::Whitespace => self.text(j).contains('\n'),
+                TokenKind::LineComment | TokenKind::BlockComment { .. } => true,
+                _ => false,
+            })
+            .unwrap_or(self.lexed.len())
+    }
+
+    /// The text from the start of token `i` to the end of its line.
+    fn line_rest(&self, i: usize) -> &str {
+        self.src[usize::from(self.starts[i])..].split('\n').next().unwrap_or("")
+    }
+
+    fn is_space(&self, i: usize) -> bool {
+        i < self.lexed.len()
+            && self.lexed[i].kind == TokenKind::Whitespace
+            && !self.text(i).contains('\n')
+    }
+
+    fn macro_call(&self, i: usize) -> (SyntaxKind, usize) {
+        let mut end = i + 1;
+        if self.lexed.get(end).map(|token| token.kind) == Some(TokenKind::OpenParen) {
+            let mut depth = 0u32;
+            while end < self.lexed.len() {
+                match self.lexed[end].kind {
+                    TokenKind::OpenParen => depth += 1,
+                    TokenKind::CloseParen => depth -= 1,
+                    _ => (),
+                }
+                end += 1;
+                if depth == 0 {
+                    break;
+                }
+            }
+        }
+
+        let line_start = self.src[..usize::from(self.starts[i])].rsplit('\n').next().unwrap_or("");
+        let line_rest = self.line_rest(end).trim();
+        let own_line =
+            line_start.trim().is_empty() && (line_rest.is_empty() || line_rest.starts_with("//"));
+        let kind = if own_line { SyntaxKind::COMMENT } else { SyntaxKind::IDENT };
+        (kind, end)
+    }
+}
+
+struct TreeBuilder<'a> {
+    src: &'a str,
+    tokens: &'a [RawToken],
+    pos: usize,
+    depth: u32,
+    inner: GreenNodeBuilder<'static>,
+}
+
+impl TreeBuilder<'_> {
+    fn token(&mut self, kind: SyntaxKind) {
+        self.eat_trivia();
+        self.do_token(kind);
+    }
+
+    fn start_node(&mut self, kind: SyntaxKind) {
+        // leading trivia is attached to the root node, all other nodes start with a token
+        if self.depth != 0 {
+            self.eat_trivia();
+        }
+        self.depth += 1;
+        self.inner.start_node(raw_kind(kind));
+    }
+
+    fn finish_node(&mut self) {
+        self.depth -= 1;
+        if self.depth == 0 {
+            self.eat_trivia();
+        }
+        self.inner.finish_node();
+    }
+
+    fn error(&self, err: &parser::SyntaxError) -> FormatError {
+        let range = self.tokens[self.pos..]
+            .iter()
+            .find(|token| !token.kind.is_trivia())
+            .map_or_else(|| TextRange::empty(TextSize::of(self.src)), |token| token.range);
+        let parser::SyntaxError::UnexpectedToken { expected, found } = err;
+        FormatError { range, message: format!("unexpected token {found}; expected {expected}") }
+    }
+
+    fn eat_trivia(&mut self) {
+        while let Some(token) = self.tokens.get(self.pos) {
+            if !token.kind.is_trivia() {
+                break;
+            }
+            self.do_token(token.kind);
+        }
+    }
+
+    fn do_token(&mut self, kind: SyntaxKind) {
+        let range = self.tokens[self.pos].range;
+        self.inner.token(raw_kind(kind), &self.src[range]);
+        self.pos += 1;
+    }
+}
+
+fn raw_kind(kind: SyntaxKind) -> rowan::SyntaxKind {
+    rowan::SyntaxKind(kind.into())
+}
@@ -0,0 +1,491 @@
+use std::mem;
+
+use syntax::{Direction, SyntaxKind, SyntaxNode, SyntaxToken, T};
+
+use crate::align::{Columns, COMMENT, CONSTRAINT, EQ, NAME};
+
+const INDENT: &str = "    ";
+
+/// The formatted lines and the columns of all parameter declarations that fit on a single line.
+pub(crate) struct Output {
+    pub(crate) lines: Vec<String>,
+    pub(crate) params: Vec<(usize, Columns)>,
+}
+
+pub(crate) fn print(root: &SyntaxNode) -> Output {
+    let mut printer = Printer {
+        lines: Vec::new(),
+        line: String::new(),
+        line_empty: true,
+        after_comment: false,
+        indent: 0,
+        params: Vec::new(),
+        param: None,
+    };
+
+    let mut prev = None;
+    let mut trivia = Vec::new();
+    for token in root.descendants_with_tokens().filter_map(|it| it.into_token()) {
+        if token.kind().is_trivia() {
+            trivia.push(token);
+            continue;
+        }
+        printer.token(prev.as_ref(), &trivia, &token);
+        trivia.clear();
+        prev = Some(token);
+    }
+    printer.trivia(prev.as_ref(), &trivia, 0);
+
+    let line = printer.line.trim_end();
+    if !line.is_empty() {
+        printer.lines.push(line.to_owned());
+    }
+    Output { lines: printer.lines, params: printer.params }
+}
+
+struct Printer {
+    /// The lines that have been finished already.
+    lines: Vec<String>,
+    line: String,
+    /// Whether only the indentation has been added to `line` so far.
+    line_empty: bool,
+    /// Whether the last text that was added to `line` is a block comment.
+    after_comment: bool,
+    /// The indentation of the item (or statement) that is currently printed. Continuation lines
+    /// are indented by one more level.
+    indent: usize,
+    params: Vec<(usize, Columns)>,
+    /// The parameter declaration that is currently printed.
+    param: Option<(usize, Columns)>,
+}
+
+#[derive(Clone, Copy)]
+enum Place {
+    /// The token always starts a new line.
+    NewLine { indent: usize },
+    /// The token starts a new line if it did so in the original source.
+    MaybeNewLine { indent: usize },
+    /// The token continues the current line. If `keep_break` is set, line breaks before the
+    /// token are preserved (as continuation lines).
+    Inline { keep_break: bool },
+}
+
+impl Printer {
+    fn token(&mut self, prev: Option<&SyntaxToken>, trivia: &[SyntaxToken], token: &SyntaxToken) {
+        let place = place(token);
+        let comment_indent = match place {
+            // comments before `end` belong to the body of the block
+            Place::NewLine { indent } if is_closing_kw(token.kind()) => indent + 1,
+            Place::NewLine { indent } | Place::MaybeNewLine { indent } => indent,
+            Place::Inline { .. } => self.indent + 1,
+        };
+        let (newlines, must_break, allow_blank) = self.trivia(prev, trivia, comment_indent);
+        let blank_line =
+            newlines > 1 && allow_blank && !is_closing_kw(token.kind()) && token.kind() != T![else];
+
+        match place {
+            Place::NewLine { indent } => {
+                self.newline(indent, blank_line);
+                self.indent = indent;
+            }
+            Place::MaybeNewLine { indent } if newlines != 0 || must_break => {
+                self.newline(indent, blank_line);
+                self.indent = indent;
+            }
+            Place::Inline { keep_break } if must_break || (keep_break && newlines != 0) => {
+                // closing delimiters on their own line are aligned with the start of the item
+                let indent = if matches!(token.kind(), T![')'] | T![']'] | T!['}'] | T!["*)"]) {
+                    self.indent
+                } else {
+                    self.indent + 1
+                };
+                self.newline(indent, false);
+            }
+            Place::MaybeNewLine { .. } => self.space(),
+            Place::Inline { .. } => {
+                let after_comment = self.after_comment && !matches!(token.kind(), T![;] | T![,]);
+                if after_comment || prev.map_or(false, |prev| space_between(prev, token)) {
+                    self.space()
+                }
+            }
+        }
+
+        self.record_columns(token);
+        self.push(token.text());
+    }
+
+    /// Prints the comments (and compiler directives) in `trivia`. Returns the number of line
+    /// breaks after the last comment, whether the next token must start a new line and whether a
+    /// blank line may be inserted before the next token.
+    fn trivia(
+        &mut self,
+        prev: Option<&SyntaxToken>,
+        trivia: &[SyntaxToken],
+        indent: usize,
+    ) -> (usize, bool, bool) {
+        let mut newlines = 0;
+        let mut must_break = false;
+        // there are no blank lines at the start of a file or block
+        let mut allow_blank = !prev.map_or(true, opens_body);
+        // the original indentation of the current line (if it only contains trivia so far)
+        let mut line_indent = None;
+        for token in trivia {
+            let text = token.text();
+            if token.kind() == SyntaxKind::WHITESPACE {
+                newlines += text.matches('\n').count();
+                line_indent = text.rfind('\n').map(|pos| text[pos + 1..].chars().count());
+                continue;
+            }
+
+            let directive = text.starts_with('`');
+            if newlines == 0 && !must_break && !directive && !self.line_empty {
+                self.space();
+                // trailing comments of parameter declarations are aligned
+                if let Some((line, columns)) = self.params.last_mut() {
+                    if *line == self.lines.len() {
+                        columns[COMMENT] = Some(self.line.len());
+                    }
+                }
+            } else {
+                self.newline(indent, newlines > 1 && allow_blank);
+            }
+            match line_indent {
+                Some(old_indent) if !directive && text.contains('\n') && self.line_empty => {
+                    let text = reindent(text, old_indent, &INDENT.repeat(indent));
+                    self.push(&text)
+                }
+                _ => self.push(if directive { text.trim_end() } else { text }),
+            }
+            self.after_comment = !directive;
+            must_break = directive || text.starts_with("//");
+            newlines = 0;
+            line_indent = None;
+            allow_blank = true;
+        }
+        (newlines, must_break, allow_blank)
+    }
+
+    fn newline(&mut self, indent: usize, blank_line: bool) {
+        let at_start = self.lines.is_empty() && self.line_empty;
+        if !at_start {
+            let line = mem::take(&mut self.line);
+            self.lines.push(line.trim_end().to_owned());
+            if blank_line {
+                self.lines.push(String::new());
+            }
+        }
+        self.line = INDENT.repeat(indent);
+        self.line_empty = true;
+    }
+
+    fn space(&mut self) {
+        if !self.line_empty {
+            self.line.push(' ');
+        }
+    }
+
+    fn push(&mut self, text: &str) {
+        self.line.push_str(text);
+        self.line_empty = false;
+        self.after_comment = false;
+    }
+
+    /// Records the columns of single line parameter declarations that are aligned later.
+    fn record_columns(&mut self, token: &SyntaxToken) {
+        let parent = match token.parent() {
+            Some(parent) => parent,
+            None => return,
+        };
+        let column = match (token.kind(), parent.kind()) {
+            (T![parameter] | T![localparam], SyntaxKind::PARAM_DECL) => {
+                let params = parent.children().filter(|it| it.kind() == SyntaxKind::PARAM);
+                let has_attrs = parent.children().any(|it| it.kind() == SyntaxKind::ATTR_LIST);
+                if params.count() == 1 && !has_attrs {
+                    self.param = Some((self.lines.len(), Columns::default()));
+                }
+                return;
+            }
+            (T![;], SyntaxKind::PARAM_DECL) => {
+                if let Some((line, columns)) = self.param.take() {
+                    if line == self.lines.len() {
+                        self.params.push((line, columns));
+                    }
+                }
+                return;
+            }
+            (SyntaxKind::IDENT, SyntaxKind::NAME)
+                if parent.parent().map_or(false, |it| it.kind() == SyntaxKind::PARAM) =>
+            {
+                NAME
+            }
+            (T![=], SyntaxKind::PARAM) => EQ,
+            (T![from] | T![exclude], SyntaxKind::CONSTRAINT) => CONSTRAINT,
+            _ => return,
+        };
+
+        if let Some((_, columns)) = &mut self.param {
+            if columns[column].is_none() {
+                columns[column] = Some(self.line.len());
+            }
+        }
+    }
+}
+
+/// Moves the continuation lines of a block comment that started at column `old_indent` to
+/// `new_indent`. Lines that are indented less than the start of the comment are kept as they are.
+fn reindent(comment: &str, old_indent: usize, new_indent: &str) -> String {
+    let mut lines = comment.split('\n');
+    let mut res = lines.next().unwrap_or_default().to_owned();
+    for line in lines {
+        res.push('\n');
+        let indent = line.chars().take_while(|c| *c == ' ' || *c == '\t').count();
+        if line.trim().is_empty() {
+            continue;
+        } else if indent >= old_indent {
+            // the indentation only contains ascii characters
+            res.push_str(new_indent);
+            res.push_str(&line[old_indent..]);
+        } else {
+            res.push_str(line);
+        }
+    }
+    res
+}
+
+fn place(token: &SyntaxToken) -> Place {
+    let parent = match token.parent() {
+        Some(parent) => parent,
+        None => return Place::Inline { keep_break: true },
+    };
+
+    if is_closing_kw(token.kind()) {
+        return Place::NewLine { indent: indent(&parent) };
+    }
+
+    if token.kind() == T![else] {
+        // `end else begin`
+        let then_branch = parent.children().find(|it| is_stmt(it.kind()));
+        return match then_branch {
+            Some(stmt) if stmt.kind() == SyntaxKind::BLOCK_STMT => {
+                Place::Inline { keep_break: false }
+            }
+            _ => Place::NewLine { indent: indent(&parent) },
+        };
+    }
+
+    // find the outermost node that starts with this token
+    let mut node = parent;
+    loop {
+        if node.first_token().as_ref() != Some(token) {
+            break;
+        }
+        match role(&node) {
+            Role::Line => return Place::NewLine { indent: indent(&node) },
+            Role::Nested => return Place::MaybeNewLine { indent: indent(&node) },
+            Role::NestedInline => return Place::Inline { keep_break: false },
+            Role::None => (),
+        }
+        node = match node.parent() {
+            Some(parent) => parent,
+            None => break,
+        }
+    }
+
+    Place::Inline { keep_break: !matches!(token.kind(), T![,] | T![;]) }
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+enum Role {
+    /// An item (or statement) in the body of a declaration/block that is placed on its own line.
+    Line,
+    /// A statement nested inside another statement (like the branches of an if statement).
+    Nested,
+    /// A nested statement that continues the line of the parent statement (`begin` or `else if`).
+    NestedInline,
+    None,
+}
+
+fn role(node: &SyntaxNode) -> Role {
+    let parent = match node.parent() {
+        Some(parent) => parent,
+        None => return Role::None,
+    };
+    let kind = node.kind();
+    let follows =
+        |token: SyntaxKind| node.siblings_with_tokens(Direction::Prev).any(|it| it.kind() == token);
+    let nested = || {
+        let else_if = parent.kind() == SyntaxKind::IF_STMT
+            && kind == SyntaxKind::IF_STMT
+            && follows(T![else]);
+        if kind == SyntaxKind::BLOCK_STMT || else_if {
+            Role::NestedInline
+        } else {
+            Role::Nested
+        }
+    };
+
+    match parent.kind() {
+        SyntaxKind::SOURCE_FILE => Role::Line,
+        SyntaxKind::MODULE_DECL
+        | SyntaxKind::FUNCTION
+        | SyntaxKind::NATURE_DECL
+        | SyntaxKind::DISCIPLINE_DECL
+        | SyntaxKind::PARAMSET_DECL
+            if follows(T![;]) =>
+        {
+            Role::Line
+        }
+        SyntaxKind::BLOCK_STMT | SyntaxKind::GENERATE_FOR
+            if kind != SyntaxKind::BLOCK_SCOPE && follows(T![begin]) =>
+        {
+            Role::Line
+        }
+        SyntaxKind::GENERATE_REGION if follows(T![generate]) => Role::Line,
+        SyntaxKind::CASE_STMT if kind == SyntaxKind::CASE => Role::Line,
+        SyntaxKind::IF_STMT
+        | SyntaxKind::WHILE_STMT
+        | SyntaxKind::FOR_STMT
+        | SyntaxKind::EVENT_STMT
+            if is_stmt(kind) && follows(T![')']) =>
+        {
+            nested()
+        }
+        SyntaxKind::ANALOG_BEHAVIOUR | SyntaxKind::CASE if is_stmt(kind) => nested(),
+        _ => Role::None,
+    }
+}
+
+/// The indentation level of `node`.
+fn indent(node: &SyntaxNode) -> usize {
+    node.ancestors()
+        .filter(|node| match role(node) {
+            // top level items are not indented
+            Role::Line => node.parent().map_or(false, |it| it.kind() != SyntaxKind::SOURCE_FILE),
+            Role::Nested => true,
+            Role::NestedInline | Role::None => false,
+        })
+        .count()
+}
+
+fn space_between(prev: &SyntaxToken, next: &SyntaxToken) -> bool {
+    // escaped identifiers are terminated by whitespace
+    if prev.kind() == SyntaxKind::IDENT && prev.text().starts_with('\\') {
+        return true;
+    }
+
+    match (prev.kind(), next.kind()) {
+        (T!["(*"] | T!["*)"], _) | (_, T!["*)"]) => return true,
+        (_, T![,] | T![;]) | (T!['('] | T!['['] | T!["'{"] | T![.] | T![@] | T![#], _) => {
+            return false
+        }
+        (T![,] | T![;], _) => return true,
+        (_, T![')'] | T![']'] | T!['}'] | T![.]) => return false,
+        _ => (),
+    }
+
+    if is_operator(prev) || is_operator(next) {
+        return true;
+    }
+    if parent_kind(prev) == Some(SyntaxKind::PREFIX_EXPR)
+        || parent_kind(prev) == Some(SyntaxKind::PORT_FLOW)
+        || parent_kind(next) == Some(SyntaxKind::PORT_FLOW) && next.kind() == T![>]
+    {
+        return false;
+    }
+
+    match next.kind() {
+        // calls are glued to the function name, but keywords (`if (`) are not
+        T!['('] => {
+            prev.kind().is_keyword() && !matches!(prev.kind(), T![initial_step] | T![final_step])
+        }
+        T!['['] => !is_index(next),
+        T![:] => !matches!(
+            parent_kind(next),
+            Some(SyntaxKind::RANGE | SyntaxKind::INDEX_EXPR | SyntaxKind::CASE)
+        ),
+        _ if prev.kind() == T![:] => {
+            !matches!(parent_kind(prev), Some(SyntaxKind::RANGE | SyntaxKind::INDEX_EXPR))
+        }
+        _ => true,
+    }
+}
+
+/// Binary operators and assignments are surrounded by spaces.
+fn is_operator(token: &SyntaxToken) -> bool {
+    match token.kind() {
+        T![=] | T![<+] => true,
+        T![?] | T![:] => parent_kind(token) == Some(SyntaxKind::SELECT_EXPR),
+        _ => parent_kind(token) == Some(SyntaxKind::BIN_EXPR),
+    }
+}
+
+/// Whether the `[` starts an index (`x[i]`) or the dimensions of an array (`real x[0:3]`).
+fn is_index(token: &SyntaxToken) -> bool {
+    let parent = match token.parent() {
+        Some(parent) => parent,
+        None => return false,
+    };
+    match parent.kind() {
+        SyntaxKind::INDEX_EXPR => true,
+        SyntaxKind::RANGE => parent
+            .parent()
+            .map_or(false, |it| matches!(it.kind(), SyntaxKind::VAR | SyntaxKind::PARAM)),
+        _ => false,
+    }
+}
+
+/// Whether `token` is the last token before the body of a declaration or block.
+fn opens_body(token: &SyntaxToken) -> bool {
+    match token.kind() {
+        T![begin] | T![generate] => true,
+        T![;] => matches!(
+            parent_kind(token),
+            Some(
+                SyntaxKind::MODULE_DECL
+                    | SyntaxKind::FUNCTION
+                    | SyntaxKind::NATURE_DECL
+                    | SyntaxKind::DISCIPLINE_DECL
+                    | SyntaxKind::PARAMSET_DECL
+            )
+        ),
+        T![')'] => parent_kind(token) == Some(SyntaxKind::CASE_STMT),
+        SyntaxKind::IDENT => token
+            .parent()
+            .and_then(|name| name.parent())
+            .map_or(false, |it| it.kind() == SyntaxKind::BLOCK_SCOPE),
+        _ => false,
+    }
+}
+
+fn parent_kind(token: &SyntaxToken) -> Option<SyntaxKind> {
+    token.parent().map(|parent| parent.kind())
+}
+
+fn is_closing_kw(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        T![end]
+            | T![endmodule]
+            | T![endfunction]
+            | T![endnature]
+            | T![enddiscipline]
+            | T![endparamset]
+            | T![endcase]
+            | T![endgenerate]
+    )
+}
+
+fn is_stmt(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::EMPTY_STMT
+            | SyntaxKind::ASSIGN_STMT
+            | SyntaxKind::EXPR_STMT
+            | SyntaxKind::IF_STMT
+            | SyntaxKind::WHILE_STMT
+            | SyntaxKind::FOR_STMT
+            | SyntaxKind::CASE_STMT
+            | SyntaxKind::EVENT_STMT
+            | SyntaxKind::BLOCK_STMT
+    )
+}
@@ -0,0 +1,244 @@
+use expect_test::{expect, Expect};
+
+use crate::format;
+
+fn check(src: &str, expect: Expect) {
+    let formatted = format(src).unwrap();
+    expect.assert_eq(&formatted);
+    assert_eq!(format(&formatted).unwrap(), formatted, "formatting is not idempotent");
+}
+
+#[test]
+fn indentation() {
+    check(
+        r#"
+module test(a,b);
+inout a,b;
+electrical a,b;
+analog begin
+if(V(a,b)>0)
+begin
+I(a,b)<+V(a,b)/1k;
+end
+else if (V(a,b) < -1) I(a,b) <+ 0;
+else
+I(a,b)<+-V(a,b);
+case(1)
+0,1: x=1;
+default:begin x=2; end
+endcase
+end
+endmodule
+"#,
+        expect![[r#"
+            module test(a, b);
+                inout a, b;
+                electrical a, b;
+                analog begin
+                    if (V(a, b) > 0) begin
+                        I(a, b) <+ V(a, b) / 1k;
+                    end else if (V(a, b) < -1) I(a, b) <+ 0;
+                    else
+                        I(a, b) <+ -V(a, b);
+                    case (1)
+                        0, 1: x = 1;
+                        default: begin
+                            x = 2;
+                        end
+                    endcase
+                end
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn operator_spacing() {
+    check(
+        r#"
+module test;
+real x[0:3], y;
+analog begin
+x[0]=-(1+2)*3**2;
+y = x[1]>0?$abs(x[1]):ddt( x[2] , 1e-3 );
+@(initial_step("static","ac")) y=1;
+for(i=0;i<4;i=i+1) y=y+x[i];
+end
+endmodule
+"#,
+        expect![[r#"
+            module test;
+                real x[0:3], y;
+                analog begin
+                    x[0] = -(1 + 2) * 3 ** 2;
+                    y = x[1] > 0 ? $abs(x[1]) : ddt(x[2], 1e-3);
+                    @(initial_step("static", "ac")) y = 1;
+                    for (i = 0; i < 4; i = i + 1) y = y + x[i];
+                end
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn parameter_alignment() {
+    check(
+        r#"
+module test;
+parameter real is=1e-14 from(0:inf); // saturation current
+parameter real n = 1.0 from [1:10];
+parameter integer level=1 exclude 0; // model level
+localparam vt = 0.0258;
+
+parameter real a=1, b=2;
+parameter real long_name = 2.5;
+endmodule
+"#,
+        expect![[r#"
+            module test;
+                parameter real    is    = 1e-14 from (0:inf); // saturation current
+                parameter real    n     = 1.0   from [1:10];
+                parameter integer level = 1     exclude 0;    // model level
+                localparam        vt    = 0.0258;
+
+                parameter real a = 1, b = 2;
+                parameter real long_name = 2.5;
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn attributes() {
+    check(
+        r#"
+module test;
+(*desc="saturation current",units="A"*)parameter real is=1e-14;
+(* desc = "temperature" *) real t;
+endmodule
+"#,
+        expect![[r#"
+            module test;
+                (* desc = "saturation current", units = "A" *) parameter real is = 1e-14;
+                (* desc = "temperature" *) real t;
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn comments() {
+    check(
+        r#"
+// a diode
+
+
+module diode(a, c); // ports
+/* block
+   comment */
+inout a, c;
+
+
+
+electrical a, c;
+analog begin
+// contribution
+I(a, c) <+ 1e-14 * (exp(V(a, c) / 0.0258) - 1) /* shockley */ ;
+// trailing comment in block
+end
+endmodule
+// end of file
+"#,
+        expect![[r#"
+            // a diode
+
+            module diode(a, c); // ports
+                /* block
+                   comment */
+                inout a, c;
+
+                electrical a, c;
+                analog begin
+                    // contribution
+                    I(a, c) <+ 1e-14 * (exp(V(a, c) / 0.0258) - 1) /* shockley */;
+                    // trailing comment in block
+                end
+            endmodule
+            // end of file
+        "#]],
+    );
+}
+
+#[test]
+fn directives() {
+    check(
+        r#"
+`include "disciplines.vams"
+`define VT 0.0258
+`define EXP(x) exp(x)
+module test(a, c);
+electrical a, c;
+`ifdef NOISE
+parameter real kf = 0;
+`else
+parameter real kf = 1;
+`endif
+analog begin
+`DECLARE_VARS
+I(a, c) <+ `EXP(V(a, c) / `VT);
+end
+endmodule
+"#,
+        expect![[r#"
+            `include "disciplines.vams"
+            `define VT 0.0258
+            `define EXP(x) exp(x)
+            module test(a, c);
+                electrical a, c;
+                `ifdef NOISE
+                parameter real kf = 0;
+                `else
+                parameter real kf = 1;
+                `endif
+                analog begin
+                    `DECLARE_VARS
+                    I(a, c) <+ `EXP(V(a, c) / `VT);
+                end
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn continuation_lines() {
+    check(
+        r#"
+module test;
+analog begin
+x = a
++ b
++ c;
+$strobe("%g",
+x);
+end
+endmodule
+"#,
+        expect![[r#"
+            module test;
+                analog begin
+                    x = a
+                        + b
+                        + c;
+                    $strobe("%g",
+                        x);
+                end
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn syntax_error() {
+    let err = format("module test;\nanalog begin\nx = ;\nend\nendmodule\n").unwrap_err();
+    assert_eq!(&"module test;\nanalog begin\nx = ;"[err.range], ";");
+}
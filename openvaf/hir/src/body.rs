@@ -4,7 +4,10 @@ use hir_def::db::HirDefDB;
 use hir_def::DefWithBodyId;
 use hir_ty::db::HirTyDB;
 use hir_ty::inference;
+use hir_ty::noise_table::read_noise_table_file;
+use hir_ty::table_model::{TableModel, TableModelArgs};
 use hir_ty::types::{Signature, Ty};
+use syntax::name::AsIdent;
 
 pub use hir_def::expr::Event;
 pub use hir_def::{expr::CaseCond, BuiltIn, Case, ExprId, Literal, ParamSysFun, StmtId, Type};
@@ -62,6 +65,7 @@ impl<'a> BodyRef<'a> {
             }
             Ty::FunctionVar { fun, .. } => Ref::FunctionReturn(Function { id: fun }),
             Ty::NatureAttr(_, id) => Ref::NatureAttr(NatureAttribute { id }),
+            Ty::RealNet(id) => Ref::RealNet(Node { id }),
 
             ref it => {
                 if let Some(&inference::ResolvedFun::Param(param)) =
@@ -90,6 +94,34 @@ impl<'a> BodyRef<'a> {
         Node { id }
     }
 
+    /// Whether the node referenced by `expr` was aliased to a different node with
+    /// `$analog_node_alias`/`$analog_port_alias`.
+    pub fn is_aliased_node(&self, expr: ExprId, db: &CompilationDB) -> bool {
+        let node = self.into_node(expr);
+        match &self.body.exprs[expr] {
+            hir_def::Expr::Path { path, .. } => {
+                path.as_ident().map_or(false, |name| name != db.node_data(node.id).name)
+            }
+            _ => false,
+        }
+    }
+
+    /// Reads the data of a `$table_model` call
+    pub fn table_model(&self, db: &CompilationDB, args: &[ExprId]) -> TableModel {
+        let args = TableModelArgs::new(self.body, self.infere, args)
+            .expect("invalid HIR: malformed $table_model call");
+        let root_file = db.compilation_unit().root_file;
+        TableModel::new(db, root_file, self.body, &args)
+            .unwrap_or_else(|(_, err)| panic!("invalid HIR: {err}"))
+    }
+
+    /// The (frequency, power) pairs of a `noise_table` file.
+    pub fn noise_table_file(&self, db: &CompilationDB, file: ExprId) -> Box<[(f64, f64)]> {
+        let root_file = db.compilation_unit().root_file;
+        read_noise_table_file(db, root_file, self.body, file)
+            .unwrap_or_else(|err| panic!("invalid HIR: {err}"))
+    }
+
     pub fn into_port_flow(&self, expr: ExprId) -> Node {
         let id = self.infere.expr_types[expr].unwrap_port_flow();
         Node { id }
@@ -132,6 +164,7 @@ impl<'a> BodyRef<'a> {
                 Expr::Call { fun, args }
             }
             hir_def::Expr::Array(ref args) => Expr::Array(args),
+            hir_def::Expr::Index { base, index } => Expr::Index { base, index },
             hir_def::Expr::Literal(ref literal) => Expr::Literal(literal),
             _ => panic!("invalid HIR: {:?}", self.body.exprs[expr]),
         }
@@ -157,6 +190,13 @@ impl<'a> BodyRef<'a> {
                     inference::AssignDst::Var(id) => {
                         Stmt::Assignment { lhs: AssignmentLhs::Variable(Variable { id }), rhs: val }
                     }
+                    inference::AssignDst::RealNet(id) => {
+                        Stmt::Assignment { lhs: AssignmentLhs::RealNet(Node { id }), rhs: val }
+                    }
+                    inference::AssignDst::ArrayElement { var, index } => Stmt::Assignment {
+                        lhs: AssignmentLhs::ArrayElement { var: Variable { id: var }, index },
+                        rhs: val,
+                    },
                     inference::AssignDst::FunVar { fun, arg: None } => Stmt::Assignment {
                         lhs: AssignmentLhs::FunctionReturn(Function { id: fun }),
                         rhs: val,
@@ -194,8 +234,14 @@ impl<'a> BodyRef<'a> {
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum AssignmentLhs {
     Variable(Variable),
+    ArrayElement {
+        var: Variable,
+        index: ExprId,
+    },
     FunctionReturn(Function),
     FunctionArg(FunctionArg),
+    /// A `wreal` net or a net with a discrete discipline
+    RealNet(Node),
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -235,6 +281,7 @@ pub enum Expr<'a> {
     Select { cond: ExprId, then_val: ExprId, else_val: ExprId },
     Call { fun: ResolvedFun, args: &'a [ExprId] },
     Array(&'a [ExprId]),
+    Index { base: ExprId, index: ExprId },
     Literal(&'a Literal),
 }
 impl Expr<'_> {
@@ -251,6 +298,7 @@ impl Expr<'_> {
             Expr::Read(Ref::Variable(var)) => AssignmentLhs::Variable(var),
             Expr::Read(Ref::FunctionArg(arg)) => AssignmentLhs::FunctionArg(arg),
             Expr::Read(Ref::FunctionReturn(fun)) => AssignmentLhs::FunctionReturn(fun),
+            Expr::Read(Ref::RealNet(node)) => AssignmentLhs::RealNet(node),
             _ => panic!("{self:?} is not a lhs reference"),
         }
     }
@@ -264,6 +312,7 @@ pub enum Ref {
     FunctionReturn(Function),
     NatureAttr(NatureAttribute),
     ParamSysFun(ParamSysFun),
+    RealNet(Node),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
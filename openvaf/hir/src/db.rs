@@ -7,7 +7,10 @@ use std::{fs, io};
 use anyhow::{bail, Result};
 use basedb::lints::{Lint, LintLevel};
 use basedb::AbsPathBuf;
-use basedb::{BaseDB, BaseDatabase, FileId, Vfs, VfsPath, VfsStorage, STANDARD_FLAGS};
+use basedb::{
+    BaseDB, BaseDatabase, FileId, Vfs, VfsPath, VfsStorage, DEFAULT_MACRO_EXPANSION_LIMIT,
+    STANDARD_FLAGS,
+};
 use hir_def::db::{HirDefDB, HirDefDatabase, InternDatabase};
 use hir_ty::db::HirTyDatabase;
 use parking_lot::RwLock;
@@ -82,8 +85,27 @@ impl CompilationDB {
         let root_file = vfs.ensure_file_id(root_file);
         vfs.set_file_contents(root_file, contents.into());
 
-        let mut res =
-            Self { storage: salsa::Storage::default(), vfs: Arc::new(RwLock::new(vfs)), root_file };
+        CompilationDB::with_vfs(
+            Arc::new(RwLock::new(vfs)),
+            root_file,
+            include_dirs,
+            macro_flags,
+            lints,
+        )
+    }
+
+    /// Creates a database for the compilation unit `root_file` that reads its sources from a
+    /// (possibly shared) `vfs`. The standard library must have been inserted into `vfs`.
+    /// Sharing a vfs between multiple databases ensures that a file has the same [`FileId`]
+    /// in every database (this is used by the language server).
+    pub fn with_vfs<'a>(
+        vfs: Arc<RwLock<Vfs>>,
+        root_file: FileId,
+        include_dirs: impl Iterator<Item = Result<VfsPath>>,
+        macro_flags: impl Iterator<Item = &'a str>,
+        lints: impl Iterator<Item = (&'a str, LintLevel)>,
+    ) -> Result<Self> {
+        let mut res = Self { storage: salsa::Storage::default(), vfs, root_file };
 
         let include_dirs: Result<Arc<[_]>> =
             once(Ok(VfsPath::new_virtual_path("/std".to_owned()))).chain(include_dirs).collect();
@@ -92,6 +114,8 @@ impl CompilationDB {
         let macro_flags: Arc<[_]> =
             STANDARD_FLAGS.into_iter().chain(macro_flags).map(Arc::from).collect();
         res.set_macro_flags(root_file, macro_flags);
+        res.set_decryption_keys(root_file, Arc::new([]));
+        res.set_macro_expansion_limit(root_file, DEFAULT_MACRO_EXPANSION_LIMIT);
 
         res.set_plugin_lints(&[]);
         let mut overwrites = res.empty_global_lint_overwrites();
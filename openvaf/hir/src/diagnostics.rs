@@ -64,6 +64,21 @@ pub(crate) fn collect(db: &CompilationDB, root_file: FileId, sink: &mut impl Dia
             )
         }
 
+        // the values assigned by a paramset are not declared in any scope
+        if let ScopeOrigin::Paramset(paramset) = def_map[*child].origin {
+            for &(_, param) in &db.paramset_data(paramset).overrides {
+                collect_body_diagnostcs(
+                    db,
+                    sink,
+                    param.into(),
+                    &parse,
+                    &sm,
+                    root_file,
+                    &ast_id_map,
+                );
+            }
+        }
+
         collect_scope(db, &def_map, &parse, &sm, &ast_id_map, root_file, sink, *child)
     }
 }
@@ -24,7 +24,7 @@ use hir_def::NatureAttrId;
 use hir_def::NatureId;
 use hir_def::{
     AliasParamId, BlockId, BlockLoc, BranchId, FunctionId, Lookup, ModuleId, ModuleLoc, NodeId,
-    ParamId, VarId,
+    ParamId, ParamsetId, VarId,
 };
 use hir_ty::db::HirTyDB as HirDatabase;
 use hir_ty::inference;
@@ -38,30 +38,47 @@ pub use hir_def::expr::CaseCond;
 pub use hir_def::nameres::diagnostics::PathResolveError;
 pub use hir_def::{BuiltIn, Case, Literal, ParamSysFun, Path, Type};
 pub use hir_ty::builtin;
+pub use hir_ty::table_model::{
+    DimensionControl, Extrapolation, Interpolation, TableData, TableModel,
+};
 pub use rec_declarations::RecDeclarations;
 pub use syntax::name::Name;
 
 pub use crate::attributes::AstCache;
 pub use crate::body::{
-    AssignmentLhs, Body, BodyRef, ContributeKind, Expr, ExprId, Ref, ResolvedFun, Stmt, StmtId,
+    AssignmentLhs, Body, BodyRef, ContributeKind, Event, Expr, ExprId, Ref, ResolvedFun, Stmt,
+    StmtId,
 };
 pub use crate::db::CompilationDB;
+pub use crate::semantics::{Definition, Semantics};
 
 mod attributes;
 mod body;
 mod db;
 pub mod diagnostics;
 mod rec_declarations;
+mod semantics;
 
 pub mod signatures {
     pub use hir_ty::builtin::{
-        ABSDELAY_MAX, ABS_INT, ABS_REAL, DDX_POT, IDTMOD_IC, IDTMOD_IC_MODULUS,
-        IDTMOD_IC_MODULUS_OFFSET, IDTMOD_IC_MODULUS_OFFSET_NATURE, IDTMOD_IC_MODULUS_OFFSET_TOL,
-        IDTMOD_NO_IC, IDT_IC, IDT_IC_ASSERT, IDT_IC_ASSERT_NATURE, IDT_IC_ASSERT_TOL, IDT_NO_IC,
-        LIMIT_BUILTIN_FUNCTION, MAX_INT, MAX_REAL, NATURE_ACCESS_BRANCH, NATURE_ACCESS_NODES,
-        NATURE_ACCESS_NODE_GND, NATURE_ACCESS_PORT_FLOW, SIMPARAM_DEFAULT, SIMPARAM_NO_DEFAULT,
+        ABSDELAY_MAX, ABS_INT, ABS_REAL, ARANDOM_NO_SEED, ARANDOM_SEED, ARANDOM_SEED_NAME,
+        ARNADOM_CONST_SEED, ARNADOM_CONST_SEED_NAME, DDX_POT, DIST_1_ARG_CONST_NAME,
+        DIST_1_ARG_CONST_SEED, DIST_1_ARG_CONST_SEED_NAME, DIST_1_ARG_SEED, DIST_2_ARG_CONST_NAME,
+        DIST_2_ARG_CONST_SEED, DIST_2_ARG_CONST_SEED_NAME, DIST_2_ARG_SEED, IDTMOD_IC,
+        IDTMOD_IC_MODULUS, IDTMOD_IC_MODULUS_OFFSET, IDTMOD_IC_MODULUS_OFFSET_NATURE,
+        IDTMOD_IC_MODULUS_OFFSET_TOL, IDTMOD_NO_IC, IDT_IC, IDT_IC_ASSERT, IDT_IC_ASSERT_NATURE,
+        IDT_IC_ASSERT_TOL, IDT_NO_IC, LIMIT_BUILTIN_FUNCTION, MAX_INT, MAX_REAL,
+        NATURE_ACCESS_BRANCH, NATURE_ACCESS_NODES, NATURE_ACCESS_NODE_GND, NATURE_ACCESS_PORT_FLOW,
+        RANDOM_NO_SEED, RANDOM_SEED, RDIST_1_ARG_CONST_NAME, RDIST_1_ARG_CONST_SEED,
+        RDIST_1_ARG_CONST_SEED_NAME, RDIST_1_ARG_SEED, RDIST_2_ARG_CONST_NAME,
+        RDIST_2_ARG_CONST_SEED, RDIST_2_ARG_CONST_SEED_NAME, RDIST_2_ARG_SEED, SIMPARAM_DEFAULT,
+        SIMPARAM_NO_DEFAULT, SIMPROBE_DEFAULT, SIMPROBE_NO_DEFAULT, VALUE_PLUSARGS_INT,
+        VALUE_PLUSARGS_REAL, VALUE_PLUSARGS_STR,
+    };
+    pub use hir_ty::types::{
+        BuiltinInfo, SignatureData, TyRequirement, BOOL_EQ, INT_EQ, INT_OP, REAL_EQ, REAL_OP,
+        STR_EQ,
     };
-    pub use hir_ty::types::{BOOL_EQ, INT_EQ, INT_OP, REAL_EQ, REAL_OP, STR_EQ};
 }
 
 /// A root file represents a compilation root file.
@@ -111,6 +128,21 @@ impl CompilationUnit {
             .collect()
     }
 
+    pub fn paramsets(self, db: &CompilationDB) -> Vec<Paramset> {
+        let root_def_map = db.def_map(self.root_file);
+        root_def_map[root_def_map.entry()]
+            .declarations
+            .iter()
+            .filter_map(|(_, def)| {
+                if let ScopeDefItem::ParamsetId(id) = *def {
+                    Some(Paramset { id })
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
     pub fn ast(&self, db: &CompilationDB) -> attributes::AstCache {
         attributes::AstCache::new(db, self.root_file)
     }
@@ -185,6 +217,51 @@ impl Module {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Paramset {
+    id: ParamsetId,
+}
+
+stdx::impl_debug! {
+    match Paramset{
+        Paramset{ id } => "{id:?}";
+    }
+}
+
+impl Paramset {
+    pub fn name(self, db: &CompilationDB) -> String {
+        db.paramset_data(self.id).name.to_string()
+    }
+
+    pub fn uuid(self, _db: &CompilationDB) -> u32 {
+        self.id.as_intern_id().as_u32()
+    }
+
+    /// The module instantiated by this paramset. `None` if the module could not be resolved.
+    pub fn module(self, db: &CompilationDB) -> Option<Module> {
+        db.paramset_data(self.id).module.map(|id| Module { id })
+    }
+
+    /// The module parameters assigned by this paramset. The second parameter of each pair
+    /// is not declared in any scope, its default value is the expression assigned to the module
+    /// parameter.
+    pub fn overrides(self, db: &CompilationDB) -> Vec<(Parameter, Parameter)> {
+        db.paramset_data(self.id)
+            .overrides
+            .iter()
+            .map(|&(dst, src)| (Parameter { id: dst }, Parameter { id: src }))
+            .collect()
+    }
+
+    pub fn rec_declarations(self, db: &CompilationDB) -> RecDeclarations<'_> {
+        RecDeclarations::new(Scope::Paramset(self), db)
+    }
+
+    pub fn get_attr(&self, db: &CompilationDB, ast: &AstCache, name: &str) -> Option<ast::Attr> {
+        ast.resolve_attribute(name, self.id.lookup(db).ast_id(db).erased())
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Block {
     id: BlockId,
@@ -301,6 +378,7 @@ impl From<inference::BranchWrite> for BranchWrite {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Scope {
     Module(Module),
+    Paramset(Paramset),
     Block(Block),
     Function(Function),
 }
@@ -312,6 +390,10 @@ impl Scope {
                 let id = module.lookup(db);
                 (id.scope.local_scope, id.def_map(db))
             }
+            Scope::Paramset(paramset) => {
+                let id = paramset.id.lookup(db);
+                (id.scope.local_scope, id.def_map(db))
+            }
             Scope::Block(block) => {
                 let def_map = db.block_def_map(block.id).expect("block is named");
                 (def_map.entry(), def_map)
@@ -334,6 +416,7 @@ impl Scope {
                     unreachable!("Root scope can not be a child scope")
                 }
                 hir_def::nameres::ScopeOrigin::Module(id) => Scope::Module(Module { id }),
+                hir_def::nameres::ScopeOrigin::Paramset(id) => Scope::Paramset(Paramset { id }),
                 hir_def::nameres::ScopeOrigin::Block(id) => Scope::Block(Block { id }),
                 hir_def::nameres::ScopeOrigin::Function(id) => Scope::Function(Function { id }),
             })
@@ -360,12 +443,14 @@ impl Scope {
                     ScopeDefItem::FunctionId(id) => ScopeDef::Function(Function { id }),
                     // implementation details
                     ScopeDefItem::BuiltIn(_)
+                    | ScopeDefItem::ParamsetId(_)
                     | ScopeDefItem::NatureId(_)
                     | ScopeDefItem::NatureAccess(_)
                     | ScopeDefItem::DisciplineId(_)
                     | ScopeDefItem::ParamSysFun(_)
                     | ScopeDefItem::FunctionReturn(_)
                     | ScopeDefItem::FunctionArgId(_)
+                    | ScopeDefItem::NodeBusId(_)
                     | ScopeDefItem::NatureAttrId(_) => return None,
                 };
                 Some((name.to_owned(), res))
@@ -391,10 +476,18 @@ impl Node {
         db.node_data(self.id).name.clone().into()
     }
 
+    /// Returns the discipline of this node. `wreal` nets have no discipline.
     #[inline]
-    pub fn discipline(self, db: &CompilationDB) -> Discipline {
-        let id = db.node_discipline(self.id).unwrap();
-        Discipline { id }
+    pub fn discipline(self, db: &CompilationDB) -> Option<Discipline> {
+        let id = db.node_discipline(self.id)?;
+        Some(Discipline { id })
+    }
+
+    /// Whether this node carries a real value (`wreal` nets and discrete disciplines).
+    /// Real nets are mapped to terminals whose potential is the value of the net.
+    #[inline]
+    pub fn is_real(self, db: &CompilationDB) -> bool {
+        db.node_is_real(self.id)
     }
 
     #[inline]
@@ -438,6 +531,11 @@ impl Variable {
         db.var_data(self.id).ty.clone()
     }
 
+    /// The lower bound of the array dimension, indices are relative to this bound.
+    pub fn array_offset(self, db: &CompilationDB) -> i32 {
+        db.var_data(self.id).array_offset
+    }
+
     pub fn init(self, db: &CompilationDB) -> Body {
         Body::new(self.id.into(), db)
     }
@@ -473,6 +571,11 @@ impl Parameter {
         db.param_ty(self.id)
     }
 
+    /// The lower bound of the array dimension, indices are relative to this bound.
+    pub fn array_offset(self, db: &CompilationDB) -> i32 {
+        db.param_data(self.id).array_offset
+    }
+
     pub fn get_attr(&self, db: &CompilationDB, ast: &AstCache, name: &str) -> Option<ast::Attr> {
         ast.resolve_attribute(name, self.id.lookup(db).ast_id(db).erased())
     }
@@ -602,6 +705,13 @@ impl Nature {
     pub fn units(self, db: &CompilationDB) -> String {
         db.nature_data(self.id).units.clone().unwrap_or_default()
     }
+
+    /// The name of the access function (like `V` or `I`) of this nature.
+    pub fn access(self, db: &CompilationDB) -> Option<String> {
+        let loc = self.id.lookup(db);
+        let (name, _) = loc.item_tree(db)[loc.id].access.clone()?;
+        Some(name.to_string())
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
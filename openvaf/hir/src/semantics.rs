@@ -0,0 +1,490 @@
+//! Maps the syntax tree back to HIR. This is the foundation of IDE features like
+//! go-to-definition and find-references.
+//!
+//! The syntax tree of a compilation unit is built from the preprocessed token stream.
+//! Its text ranges are therefore mapped through the [`SourceMap`] before they are shown
+//! to the user. Text that was decrypted from a protected envelope is never mapped to a
+//! file so that it can not be revealed by an editor.
+
+use std::sync::Arc;
+
+use basedb::{BaseDB, FileId};
+use hir_def::db::HirDefDB;
+use hir_def::nameres::{builtin_name, DefMapSource, ResolvedPath, ScopeDefItem};
+use hir_def::{DefWithBodyId, FunctionArgLoc, Intern, Lookup, NatureAttrLoc, Path, ScopeId, Type};
+use hir_ty::db::HirTyDB;
+use syntax::ast;
+use syntax::name::{AsName, Name};
+use syntax::sourcemap::{FileSpan, MacroDecl, SourceMap};
+use syntax::{match_ast, AstNode, AstPtr, Parse, SourceFile, SyntaxKind, SyntaxNode, SyntaxToken};
+use syntax::{TextRange, TextSize};
+
+use crate::{
+    AliasParameter, AstCache, Block, Branch, BuiltIn, CompilationDB, Discipline, Function,
+    FunctionArg, Module, Nature, NatureAttribute, Node, ParamSysFun, Parameter, Paramset, Variable,
+};
+
+/// An item that can be referred to by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Definition {
+    Module(Module),
+    Paramset(Paramset),
+    Block(Block),
+    Nature(Nature),
+    /// The access function (like `V` or `I`) of a nature.
+    NatureAccess(Nature),
+    NatureAttribute(NatureAttribute),
+    Discipline(Discipline),
+    Node(Node),
+    Variable(Variable),
+    Parameter(Parameter),
+    AliasParameter(AliasParameter),
+    Branch(Branch),
+    Function(Function),
+    FunctionArg(FunctionArg),
+    BuiltIn(BuiltIn),
+    ParamSysFun(ParamSysFun),
+}
+
+impl Definition {
+    pub(crate) fn from_scope_def(item: ScopeDefItem, db: &CompilationDB) -> Option<Definition> {
+        let res = match item {
+            ScopeDefItem::ModuleId(id) => Definition::Module(Module { id }),
+            ScopeDefItem::ParamsetId(id) => Definition::Paramset(Paramset { id }),
+            ScopeDefItem::BlockId(id) => Definition::Block(Block { id }),
+            ScopeDefItem::NatureId(id) => Definition::Nature(Nature { id }),
+            ScopeDefItem::NatureAccess(access) => {
+                Definition::NatureAccess(Nature { id: access.0.lookup(db).nature })
+            }
+            ScopeDefItem::DisciplineId(id) => Definition::Discipline(Discipline { id }),
+            ScopeDefItem::NodeId(id) => Definition::Node(Node { id }),
+            ScopeDefItem::VarId(id) => Definition::Variable(Variable { id }),
+            ScopeDefItem::ParamId(id) => Definition::Parameter(Parameter { id }),
+            ScopeDefItem::ParamSysFun(param) => Definition::ParamSysFun(param),
+            ScopeDefItem::AliasParamId(id) => Definition::AliasParameter(AliasParameter { id }),
+            ScopeDefItem::BranchId(id) => Definition::Branch(Branch { id }),
+            ScopeDefItem::FunctionId(id) | ScopeDefItem::FunctionReturn(id) => {
+                Definition::Function(Function { id })
+            }
+            ScopeDefItem::FunctionArgId(id) => {
+                let loc = id.lookup(db);
+                Definition::FunctionArg(FunctionArg { fun_id: loc.fun, arg_id: loc.id })
+            }
+            ScopeDefItem::BuiltIn(builtin) => Definition::BuiltIn(builtin),
+            ScopeDefItem::NatureAttrId(id) => Definition::NatureAttribute(NatureAttribute { id }),
+            // vector nets are not exposed by HIR yet
+            ScopeDefItem::NodeBusId(_) => return None,
+        };
+        Some(res)
+    }
+
+    pub(crate) fn to_scope_def(self, db: &CompilationDB) -> Option<ScopeDefItem> {
+        let res = match self {
+            Definition::Module(module) => module.id.into(),
+            Definition::Paramset(paramset) => paramset.id.into(),
+            Definition::Block(block) => block.id.into(),
+            Definition::Nature(nature) => nature.id.into(),
+            Definition::NatureAccess(nature) => {
+                let loc = nature.id.lookup(db);
+                let (_, attr) = loc.item_tree(db)[loc.id].access.clone()?;
+                let id = NatureAttrLoc { nature: nature.id, id: attr }.intern(db);
+                ScopeDefItem::NatureAccess(id.into())
+            }
+            Definition::NatureAttribute(attr) => attr.id.into(),
+            Definition::Discipline(discipline) => discipline.id.into(),
+            Definition::Node(node) => node.id.into(),
+            Definition::Variable(var) => var.id.into(),
+            Definition::Parameter(param) => param.id.into(),
+            Definition::AliasParameter(alias) => alias.id.into(),
+            Definition::Branch(branch) => branch.id.into(),
+            Definition::Function(fun) => fun.id.into(),
+            Definition::FunctionArg(arg) => {
+                FunctionArgLoc { fun: arg.fun_id, id: arg.arg_id }.intern(db).into()
+            }
+            Definition::BuiltIn(builtin) => builtin.into(),
+            Definition::ParamSysFun(param) => param.into(),
+        };
+        Some(res)
+    }
+
+    /// The name that is used to refer to this definition in the source code.
+    pub fn name(self, db: &CompilationDB) -> String {
+        match self {
+            Definition::Module(module) => module.name(db),
+            Definition::Paramset(paramset) => paramset.name(db),
+            Definition::Block(block) => block.name(db),
+            Definition::Nature(nature) => nature.name(db),
+            Definition::NatureAccess(nature) => nature.access(db).unwrap_or_default(),
+            Definition::NatureAttribute(attr) => attr.name(db),
+            Definition::Discipline(discipline) => discipline.name(db),
+            Definition::Node(node) => node.name(db).to_string(),
+            Definition::Variable(var) => var.name(db).to_string(),
+            Definition::Parameter(param) => param.name(db),
+            Definition::AliasParameter(alias) => alias.name(db),
+            Definition::Branch(branch) => branch.name(db),
+            Definition::Function(fun) => fun.name(db),
+            Definition::FunctionArg(arg) => arg.name(db),
+            Definition::BuiltIn(builtin) => builtin_name(builtin.into())
+                .map_or_else(|| format!("{:?}", builtin), |name| name.to_string()),
+            Definition::ParamSysFun(param) => format!("${:?}", param),
+        }
+    }
+
+    /// A short description of the kind of this definition (like `parameter`).
+    pub fn kind_name(self) -> &'static str {
+        match self {
+            Definition::Module(_) => "module",
+            Definition::Paramset(_) => "paramset",
+            Definition::Block(_) => "block scope",
+            Definition::Nature(_) => "nature",
+            Definition::NatureAccess(_) => "nature access function",
+            Definition::NatureAttribute(_) => "nature attribute",
+            Definition::Discipline(_) => "discipline",
+            Definition::Node(_) => "node",
+            Definition::Variable(_) => "variable",
+            Definition::Parameter(_) => "parameter",
+            Definition::AliasParameter(_) => "alias parameter",
+            Definition::Branch(_) => "branch",
+            Definition::Function(_) => "function",
+            Definition::FunctionArg(_) => "function argument",
+            Definition::BuiltIn(_) => "builtin function",
+            Definition::ParamSysFun(_) => "hierarchical parameter system function",
+        }
+    }
+
+    /// The syntax node that declares this definition (inside the syntax tree of the
+    /// compilation unit). Builtins are not declared in the source code and return `None`.
+    pub fn source(self, db: &CompilationDB) -> Option<SyntaxNode> {
+        let ast_id = self.to_scope_def(db)?.ast_id(db)?;
+        let root_file = db.compilation_unit().root_file();
+        let ptr = db.ast_id_map(root_file).get_syntax(ast_id);
+        Some(ptr.to_node(db.parse(root_file).tree().syntax()))
+    }
+
+    /// Returns the value of the string attribute `name` (like `desc` or `units`) that is
+    /// attached to the declaration of this definition.
+    pub fn attr(self, db: &CompilationDB, name: &str) -> Option<String> {
+        let ast_id = self.to_scope_def(db)?.ast_id(db)?;
+        let ast = AstCache::new(db, db.compilation_unit().root_file());
+        ast.resolve_attribute(name, ast_id)?.val()?.as_str_literal()
+    }
+}
+
+/// Resolves the syntax tree of a compilation unit to HIR.
+pub struct Semantics<'a> {
+    db: &'a CompilationDB,
+    root_file: FileId,
+    parse: Parse<SourceFile>,
+    sm: Arc<SourceMap>,
+}
+
+impl<'a> Semantics<'a> {
+    pub fn new(db: &'a CompilationDB) -> Semantics<'a> {
+        let root_file = db.compilation_unit().root_file();
+        Semantics { db, root_file, parse: db.parse(root_file), sm: db.sourcemap(root_file) }
+    }
+
+    pub fn db(&self) -> &'a CompilationDB {
+        self.db
+    }
+
+    pub fn root_file(&self) -> FileId {
+        self.root_file
+    }
+
+    /// The syntax tree of the (preprocessed) compilation unit.
+    pub fn tree(&self) -> SourceFile {
+        self.parse.tree()
+    }
+
+    /// Maps a range of the syntax tree to the file it was read from.
+    /// Returns `None` for decrypted source code.
+    pub fn file_span(&self, range: TextRange) -> Option<FileSpan> {
+        let span = self.parse.to_ctx_span(range, &self.sm);
+        if self.sm.is_protected(span.ctx) {
+            return None;
+        }
+        Some(span.to_file_span(&self.sm))
+    }
+
+    /// Returns whether `file` was read while preprocessing this compilation unit.
+    pub fn contains_file(&self, file: FileId) -> bool {
+        self.sm.files().any(|it| it == file)
+    }
+
+    /// All non-trivia tokens of the syntax tree that were read from `file` (in the order
+    /// they appear in the preprocessed token stream).
+    pub fn tokens_in_file(
+        &self,
+        file: FileId,
+    ) -> impl Iterator<Item = (SyntaxToken, FileSpan)> + '_ {
+        self.tree()
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|token| !token.kind().is_trivia())
+            .filter_map(move |token| {
+                let span = self.file_span(token.text_range())?;
+                (span.file == file).then_some((token, span))
+            })
+    }
+
+    /// Finds the token at `offset` in `file`. Identifiers are preferred over punctuation
+    /// if the offset lies between two tokens.
+    pub fn token_at(&self, file: FileId, offset: TextSize) -> Option<SyntaxToken> {
+        self.tokens_in_file(file)
+            .filter(|(_, span)| span.range.contains_inclusive(offset))
+            .map(|(token, _)| token)
+            .max_by_key(is_ident)
+    }
+
+    /// Finds the last token in `file` that starts before `offset`.
+    pub fn token_before(&self, file: FileId, offset: TextSize) -> Option<SyntaxToken> {
+        self.tokens_in_file(file)
+            .filter(|(_, span)| span.range.start() < offset)
+            .max_by_key(|(_, span)| span.range.start())
+            .map(|(token, _)| token)
+    }
+
+    /// All definitions that can be referred to by a simple name inside `scope`.
+    pub fn visible_definitions(&self, scope: ScopeId) -> Vec<(Name, Definition)> {
+        scope
+            .def_map(self.db)
+            .visible_names_in_scope(scope.local_scope, self.db)
+            .into_iter()
+            .filter_map(|(name, item)| Some((name, Definition::from_scope_def(item, self.db)?)))
+            .collect()
+    }
+
+    /// The macros defined in this compilation unit (and the location of their definition).
+    /// Macros that were defined inside a protected envelope are not returned.
+    pub fn macros(&self) -> Vec<(&MacroDecl, Option<FileSpan>)> {
+        self.sm
+            .macros()
+            .iter()
+            .filter_map(|decl| match decl.head {
+                Some(head) if self.sm.is_protected(head.ctx) => None,
+                Some(head) => Some((decl, Some(head.to_file_span(&self.sm)))),
+                None => Some((decl, None)),
+            })
+            .collect()
+    }
+
+    /// Resolves an identifier to the item it refers to (or declares).
+    pub fn resolve_token(&self, token: &SyntaxToken) -> Option<Definition> {
+        if !is_ident(token) {
+            return None;
+        }
+
+        let parent = token.parent()?;
+        match_ast! {
+            match parent {
+                ast::Path(path) => {
+                    let path = Path::resolve(path)?;
+                    self.resolve_path(&parent, &path)
+                },
+                ast::NameRef(name_ref) => {
+                    self.resolve_path(&parent, &Path::new_ident(name_ref.as_name()))
+                },
+                ast::SysFun(sysfun) => {
+                    self.resolve_path(&parent, &Path::new_ident(sysfun.as_name()))
+                },
+                ast::Name(name) => self.resolve_name(name),
+                _ => None,
+            }
+        }
+    }
+
+    fn resolve_name(&self, name: ast::Name) -> Option<Definition> {
+        let decl = name.syntax().parent()?;
+        let name = name.as_name();
+        match_ast! {
+            match decl {
+                // `.port(..)` and `#(.param(..))` refer to the instantiated module
+                ast::InstArg(arg) => {
+                    let inst = arg.syntax().ancestors().find_map(ast::ModuleInst::cast)?;
+                    let module = Path::new_ident(inst.module()?.as_name());
+                    let module = self.resolve_path(inst.syntax(), &module)?;
+                    self.resolve_in_module(module, &name)
+                },
+                // `.param = ..` inside a paramset overrides a parameter of the module
+                ast::Param(param) => {
+                    match param.syntax().parent().and_then(ast::ParamsetStmt::cast) {
+                        Some(stmt) => {
+                            let paramset =
+                                stmt.syntax().ancestors().find_map(ast::ParamsetDecl::cast)?;
+                            let module = Path::new_ident(paramset.module()?.as_name());
+                            let module = self.resolve_path(paramset.syntax(), &module)?;
+                            self.resolve_in_module(module, &name)
+                        }
+                        None => self.resolve_path(&decl, &Path::new_ident(name)),
+                    }
+                },
+                // attribute names do not refer to any item
+                ast::Attr(_attr) => None,
+                ast::NatureAttr(_attr) => None,
+                _ => self.resolve_path(&decl, &Path::new_ident(name)),
+            }
+        }
+    }
+
+    fn resolve_in_module(&self, module: Definition, name: &Name) -> Option<Definition> {
+        let module = match module {
+            Definition::Module(module) => module,
+            _ => return None,
+        };
+        let scope = module.id.lookup(self.db).scope;
+        let item = *scope.def_map(self.db)[scope.local_scope].declarations.get(name)?;
+        Definition::from_scope_def(item, self.db)
+    }
+
+    fn resolve_path(&self, node: &SyntaxNode, path: &Path) -> Option<Definition> {
+        let scope = self.scope_at(node);
+        match scope.resolve_path(self.db, path).ok()? {
+            ResolvedPath::ScopeDefItem(item) => Definition::from_scope_def(item, self.db),
+            ResolvedPath::FlowAttriubte { .. } | ResolvedPath::PotentialAttribute { .. } => None,
+        }
+    }
+
+    /// Finds the innermost scope that contains `node`.
+    pub fn scope_at(&self, node: &SyntaxNode) -> ScopeId {
+        let db = self.db;
+        let mut scope = ScopeId::root(self.root_file);
+        let mut ancestors: Vec<_> = node.ancestors().collect();
+        ancestors.reverse();
+
+        for ancestor in ancestors {
+            let name = match_ast! {
+                match ancestor {
+                    ast::ModuleDecl(module) => module.name(),
+                    ast::ParamsetDecl(paramset) => paramset.name(),
+                    ast::Function(fun) => fun.name(),
+                    ast::BlockStmt(block) => block.block_scope().and_then(|it| it.name()),
+                    _ => None,
+                }
+            };
+            let name = match name {
+                Some(name) => name.as_name(),
+                None => continue,
+            };
+            let item = match scope.def_map(db)[scope.local_scope].declarations.get(&name) {
+                Some(item) => *item,
+                None => continue,
+            };
+
+            scope = match item {
+                ScopeDefItem::ModuleId(module) => module.lookup(db).scope,
+                ScopeDefItem::ParamsetId(paramset) => paramset.lookup(db).scope,
+                ScopeDefItem::FunctionId(fun) => ScopeId {
+                    root_file: self.root_file,
+                    local_scope: db.function_def_map(fun).entry(),
+                    src: DefMapSource::Function(fun),
+                },
+                // blocks without declarations do not have their own scope
+                ScopeDefItem::BlockId(block) => match db.block_def_map(block) {
+                    Some(def_map) => ScopeId {
+                        root_file: self.root_file,
+                        local_scope: def_map.entry(),
+                        src: DefMapSource::Block(block),
+                    },
+                    None => continue,
+                },
+                _ => continue,
+            };
+        }
+
+        scope
+    }
+
+    /// Returns the span of the entire declaration of `def` and the span of the declared name.
+    pub fn definition_spans(&self, def: Definition) -> Option<(FileSpan, FileSpan)> {
+        let source = def.source(self.db)?;
+        let name = def.name(self.db);
+        let name_range = source
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|token| is_ident(token) && *token.as_name() == *name)
+            .map_or_else(|| source.text_range(), |token| token.text_range());
+
+        let full = self.file_span(source.text_range())?;
+        let focus = self.file_span(name_range)?;
+        Some((full, focus))
+    }
+
+    /// Finds all identifiers that refer to (or declare) `def`.
+    pub fn references(&self, def: Definition) -> Vec<(SyntaxToken, FileSpan)> {
+        self.reference_tokens(def)
+            .into_iter()
+            .filter_map(|token| {
+                let span = self.file_span(token.text_range())?;
+                Some((token, span))
+            })
+            .collect()
+    }
+
+    /// Finds all identifiers that refer to (or declare) `def`. In contrast to
+    /// [`references`](Semantics::references) this includes identifiers that were decrypted
+    /// from a protected envelope.
+    pub fn reference_tokens(&self, def: Definition) -> Vec<SyntaxToken> {
+        let name = def.name(self.db);
+        self.tree()
+            .syntax()
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|token| is_ident(token) && *token.as_name() == *name)
+            .filter(|token| self.resolve_token(token) == Some(def))
+            .collect()
+    }
+
+    /// Returns the type that was inferred for `expr`. Only expressions inside analog blocks
+    /// and analog functions have a type.
+    pub fn expr_type(&self, expr: &ast::Expr) -> Option<Type> {
+        let mut expr = expr.clone();
+        // parenthesis are removed during lowering
+        while let ast::Expr::ParenExpr(paren) = expr {
+            expr = paren.expr()?;
+        }
+
+        let body = self.body_at(expr.syntax())?;
+        let id = *self.db.body_source_map(body).expr_map.get(&AstPtr::new(&expr))?;
+        self.db.inference_result(body).expr_types[id].to_value()
+    }
+
+    /// Finds the body (analog block or analog function) that contains `node`.
+    fn body_at(&self, node: &SyntaxNode) -> Option<DefWithBodyId> {
+        let root = ScopeId::root(self.root_file);
+        let def_map = root.def_map(self.db);
+        let root_decls = &def_map[root.local_scope].declarations;
+        let mut initial = None;
+        for ancestor in node.ancestors() {
+            match_ast! {
+                match ancestor {
+                    ast::AnalogBehaviour(analog) => {
+                        initial = Some(analog.initial_token().is_some());
+                    },
+                    ast::Function(fun) => {
+                        let fun = Path::new_ident(fun.name()?.as_name());
+                        return match self.resolve_path(&ancestor, &fun)? {
+                            Definition::Function(fun) => Some(DefWithBodyId::FunctionId(fun.id)),
+                            _ => None,
+                        };
+                    },
+                    ast::ModuleDecl(module) => {
+                        return match root_decls.get(&module.name()?.as_name())? {
+                            ScopeDefItem::ModuleId(module) => {
+                                Some(DefWithBodyId::ModuleId { initial: initial?, module: *module })
+                            }
+                            _ => None,
+                        };
+                    },
+                    _ => (),
+                }
+            }
+        }
+        None
+    }
+}
+
+fn is_ident(token: &SyntaxToken) -> bool {
+    matches!(token.kind(), SyntaxKind::IDENT | SyntaxKind::SYSFUN)
+}
@@ -11,7 +11,7 @@ use syntax::{ast, AstNode, AstPtr};
 
 use crate::db::HirDefDB;
 use crate::item_tree::{DisciplineAttr, ItemTreeId, ItemTreeNode, NatureAttr};
-use crate::nameres::{DefMapSource, LocalScopeId};
+use crate::nameres::{DefMapSource, LocalScopeId, ScopeOrigin};
 use crate::{
     DefWithBodyId, DisciplineAttrLoc, DisciplineLoc, Expr, ExprId, FunctionLoc, Literal, Lookup,
     ModuleLoc, NatureAttrLoc, NatureLoc, ParamId, ParamLoc, ScopeId, Stmt, StmtId, Type, VarLoc,
@@ -70,9 +70,10 @@ impl Body {
             DefWithBodyId::ModuleId { initial, module } => {
                 let ModuleLoc { scope, id: item_tree } = module.lookup(db);
 
-                let ast_id = tree[item_tree].ast_id();
-                let ast = ast_id_map.get(ast_id).to_node(ast.syntax());
-                let curr_scope = (scope, ast_id.into());
+                let module = &tree[item_tree];
+                let root = ast.syntax();
+                let ast = ast_id_map.get(module.ast_id).to_node(root);
+                let curr_scope = (scope, module.ast_id.into());
 
                 let mut ctx = LowerCtx {
                     db,
@@ -81,12 +82,28 @@ impl Body {
                     ast_id_map: &ast_id_map,
                     curr_scope,
                     registry: &registry,
+                    generate: None,
+                    module: Some(module),
+                    genvars: Vec::new(),
                 };
-                body.entry_stmts = if initial {
+                let mut entry_stmts: Vec<_> = if initial {
                     ast.analog_initial_behaviour().map(|stmt| ctx.collect_stmt(stmt)).collect()
                 } else {
                     ast.analog_behaviour().map(|stmt| ctx.collect_stmt(stmt)).collect()
                 };
+
+                // analog blocks inside generate loops are lowered once for every iteration
+                for &(block, behaviour) in &module.generate_analog_behaviour {
+                    let behaviour = ast_id_map.get(behaviour).to_node(root);
+                    if behaviour.initial_token().is_some() != initial {
+                        continue;
+                    }
+                    if let Some(stmt) = behaviour.stmt() {
+                        ctx.generate = Some(&module.generate_blocks[block]);
+                        entry_stmts.push(ctx.collect_stmt(stmt));
+                    }
+                }
+                body.entry_stmts = entry_stmts.into_boxed_slice();
             }
 
             DefWithBodyId::FunctionId(id) => {
@@ -110,6 +127,9 @@ impl Body {
                     ast_id_map: &ast_id_map,
                     curr_scope,
                     registry: &registry,
+                    generate: None,
+                    module: None,
+                    genvars: Vec::new(),
                 };
                 body.entry_stmts = ast.body().map(|stmt| ctx.collect_stmt(stmt)).collect();
             }
@@ -127,17 +147,28 @@ impl Body {
                     ast_id_map: &ast_id_map,
                     curr_scope,
                     registry: &registry,
+                    generate: None,
+                    module: None,
+                    genvars: Vec::new(),
                 };
 
                 let expr = if let Some(expr) = ast.default() {
                     ctx.collect_expr(expr)
                 } else {
-                    let default_val = match db.var_data(var).ty {
+                    let default_val = |ty: &Type| match ty {
                         Type::Real => Literal::Float(Ieee64::with_float(0.0)),
                         Type::Integer => Literal::Int(0),
-                        _ => unreachable!("invalid var type (TODO arrays)"),
+                        _ => unreachable!("invalid var type"),
                     };
-                    ctx.alloc_expr_desugared(Expr::Literal(default_val))
+                    match db.var_data(var).ty {
+                        Type::Array { ref ty, len } => {
+                            let vals = (0..len)
+                                .map(|_| ctx.alloc_expr_desugared(Expr::Literal(default_val(ty))))
+                                .collect();
+                            ctx.alloc_expr_desugared(Expr::Array(vals))
+                        }
+                        ref ty => ctx.alloc_expr_desugared(Expr::Literal(default_val(ty))),
+                    }
                 };
                 let stmt = ctx.alloc_stmt_desugared(Stmt::Expr(expr));
                 body.entry_stmts = vec![stmt].into_boxed_slice();
@@ -160,6 +191,9 @@ impl Body {
                     ast_id_map: &ast_id_map,
                     curr_scope,
                     registry: &registry,
+                    generate: None,
+                    module: None,
+                    genvars: Vec::new(),
                 };
                 let expr = ctx.collect_opt_expr(ast.val());
                 let stmt = ctx.alloc_stmt_desugared(Stmt::Expr(expr));
@@ -182,6 +216,9 @@ impl Body {
                     ast_id_map: &ast_id_map,
                     curr_scope,
                     registry: &registry,
+                    generate: None,
+                    module: None,
+                    genvars: Vec::new(),
                 };
                 let expr = ctx.collect_opt_expr(ast.val());
                 let stmt = ctx.alloc_stmt_desugared(Stmt::Expr(expr));
@@ -202,11 +239,22 @@ impl Body {
 
         let tree = db.item_tree(root_file);
         let ast_id_map = db.ast_id_map(root_file);
-        let ast = db.parse(root_file).tree();
+        let root = db.parse(root_file).tree();
 
         let ParamLoc { id: item_tree, scope } = id.lookup(db);
-        let ast_id = tree[item_tree].ast_id();
-        let ast = ast_id_map.get(ast_id).to_node(ast.syntax());
+        let param = &tree[item_tree];
+        let ast_id = param.ast_id;
+        let ast = ast_id_map.get(ast_id).to_node(root.syntax());
+
+        // parameters declared in generate loops and module instances are lowered like the
+        // analog behaviour of the block they were declared in
+        let module =
+            param.generate.and_then(|_| match scope.def_map(db)[scope.local_scope].origin {
+                ScopeOrigin::Module(module) => Some(&tree[module.lookup(db).id]),
+                _ => None,
+            });
+        let generate =
+            module.zip(param.generate).map(|(module, block)| &module.generate_blocks[block]);
 
         let registry = db.lint_registry();
         let mut ctx = LowerCtx {
@@ -216,9 +264,24 @@ impl Body {
             ast_id_map: &ast_id_map,
             curr_scope: (scope, ast_id.into()),
             registry: &registry,
+            generate,
+            module,
+            genvars: Vec::new(),
         };
 
-        let default = ctx.collect_opt_expr(ast.default());
+        let default = match &param.value {
+            // the value assigned by a module instance is an expression in the parent module
+            Some(value) => {
+                let arg = ast_id_map.get(value.ast_id).to_node(root.syntax());
+                ctx.generate = module
+                    .zip(value.generate)
+                    .map(|(module, block)| &module.generate_blocks[block]);
+                let default = ctx.collect_opt_expr(arg.expr());
+                ctx.generate = generate;
+                default
+            }
+            None => ctx.collect_opt_expr(ast.default()),
+        };
         let mut entry_stmts = vec![ctx.alloc_stmt_desugared(Stmt::Expr(default))];
 
         let bounds = ast
@@ -2,16 +2,21 @@ use std::mem;
 
 use basedb::lints::LintRegistry;
 use basedb::{AstIdMap, ErasedAstId, LintAttrs};
+use stdx::Ieee64;
 use syntax::ast::{self, ArgListOwner, AttrIter, AttrsOwner, FunctionRef};
-use syntax::name::AsName;
-use syntax::AstPtr;
+use syntax::name::{kw, AsIdent, AsName, Name};
+use syntax::{AstNode, AstPtr};
 
 // use tracing::debug;
 use super::{Body, BodySourceMap};
 use crate::db::HirDefDB;
 use crate::expr::{CaseCond, Event, GlobalEvent};
+use crate::item_tree::lower::{eval_const_expr, genvar_assign_target, genvar_loop_iterations};
+use crate::item_tree::Module;
 use crate::nameres::DefMapSource;
-use crate::{BlockLoc, Case, Expr, ExprId, Intern, Literal, Path, ScopeId, Stmt, StmtId};
+use crate::{
+    BlockLoc, Case, Expr, ExprId, GenerateBlock, Intern, Literal, Path, ScopeId, Stmt, StmtId,
+};
 
 pub(super) struct LowerCtx<'a> {
     pub(super) db: &'a dyn HirDefDB,
@@ -20,6 +25,12 @@ pub(super) struct LowerCtx<'a> {
     pub(super) ast_id_map: &'a AstIdMap,
     pub(super) curr_scope: (ScopeId, ErasedAstId),
     pub(super) registry: &'a LintRegistry,
+    /// The generate loop iteration the body is lowered for.
+    pub(super) generate: Option<&'a GenerateBlock>,
+    /// The module whose analog behaviour is lowered.
+    pub(super) module: Option<&'a Module>,
+    /// The values of the genvars of the analog for loops that are currently unrolled.
+    pub(super) genvars: Vec<(Name, i32)>,
 }
 
 impl LowerCtx<'_> {
@@ -55,17 +66,30 @@ impl LowerCtx<'_> {
                 Expr::Array(vals)
             }
 
+            ast::Expr::IndexExpr(e) => {
+                if let Some(node) = self.bus_element(e) {
+                    Expr::Path { path: Path::new_ident(node), port: false }
+                } else {
+                    let base = self.collect_opt_expr(e.base());
+                    let index = self.collect_opt_expr(e.index());
+                    Expr::Index { base, index }
+                }
+            }
+
             ast::Expr::Call(call) => {
                 let fun = call.function_ref().and_then(|fun| match fun {
                     FunctionRef::Path(path) => Path::resolve(path),
                     FunctionRef::SysFun(fun) => Some(Path::new_ident(fun.as_name())),
                 });
 
-                let args = if let Some(args) = call.arg_list().map(|list| list.args()) {
+                let mut args = if let Some(args) = call.arg_list().map(|list| list.args()) {
                     args.map(|arg| self.collect_expr(arg)).collect()
                 } else {
                     vec![]
                 };
+                if let Some(fun) = fun.as_ref().and_then(|fun| fun.as_ident()) {
+                    self.default_transition(call, &fun, &mut args);
+                }
 
                 Expr::Call { fun, args }
             }
@@ -81,7 +105,11 @@ impl LowerCtx<'_> {
             // BLOCK
             ast::Expr::PathExpr(path) => {
                 if let Some(path) = path.path().and_then(Path::resolve) {
-                    Expr::Path { path, port: false }
+                    let genvar = path.as_ident().and_then(|name| self.genvar(&name));
+                    match genvar {
+                        Some(val) => Expr::Literal(Literal::Int(val)),
+                        None => Expr::Path { path: self.local_path(path), port: false },
+                    }
                 } else {
                     return self.missing_expr();
                 }
@@ -89,7 +117,7 @@ impl LowerCtx<'_> {
 
             ast::Expr::PortFlow(port_flow) => {
                 if let Some(path) = port_flow.port().and_then(Path::resolve) {
-                    Expr::Path { path, port: true }
+                    Expr::Path { path: self.local_path(path), port: true }
                 } else {
                     return self.missing_expr();
                 }
@@ -100,6 +128,76 @@ impl LowerCtx<'_> {
         self.alloc_expr(e, AstPtr::new(&expr))
     }
 
+    fn local_path(&self, path: Path) -> Path {
+        match self.generate {
+            Some(scope) => scope.local_path(path),
+            None => path,
+        }
+    }
+
+    fn genvar(&self, name: &Name) -> Option<i32> {
+        self.genvars
+            .iter()
+            .rev()
+            .find(|(genvar, _)| genvar == name)
+            .map(|(_, val)| *val)
+            .or_else(|| self.generate?.genvar(name))
+    }
+
+    /// The values of the parameters and genvars that are visible to constant expressions.
+    fn consts(&self) -> Vec<(Name, i32)> {
+        let mut consts = match self.generate {
+            Some(scope) => {
+                let mut consts = scope.consts.clone();
+                consts.extend(scope.genvars.iter().cloned());
+                consts
+            }
+            None => self.module.map_or_else(Vec::new, |module| module.const_params.clone()),
+        };
+        consts.extend(self.genvars.iter().cloned());
+        consts
+    }
+
+    /// Resolves an element of a vector net indexed with a constant expression (`n[i+1]`) to the
+    /// name of the node of that element.
+    fn bus_element(&self, expr: &ast::IndexExpr) -> Option<Name> {
+        let module = self.module?;
+        let path = match expr.base()? {
+            ast::Expr::PathExpr(path) => Path::resolve(path.path()?)?,
+            _ => return None,
+        };
+        let name = self.local_path(path).as_ident()?;
+        let bus = module.buses.iter().find(|bus| bus.name == name)?;
+        let idx = eval_const_expr(expr.index(), &self.consts()).ok()?;
+        let node = bus.node(idx)?;
+        Some(module.nodes[node].name.clone())
+    }
+
+    /// Unrolls an analog for loop over a genvar.
+    fn unroll_genvar_loop(&mut self, stmt: &ast::ForStmt) -> Option<Stmt> {
+        let module = self.module?;
+        genvar_assign_target(&stmt.init()?, &module.genvars)?;
+        let (genvar, iterations) = genvar_loop_iterations(
+            stmt.init(),
+            stmt.condition(),
+            stmt.incr(),
+            &module.genvars,
+            self.consts(),
+        )
+        .ok()?;
+
+        let body = iterations
+            .into_iter()
+            .map(|val| {
+                self.genvars.push((genvar.clone(), val));
+                let stmt = self.collect_opt_stmt(stmt.for_body());
+                self.genvars.pop();
+                stmt
+            })
+            .collect();
+        Some(Stmt::Block { body })
+    }
+
     pub fn collect_opt_stmt(&mut self, stmt: Option<ast::Stmt>) -> StmtId {
         match stmt {
             Some(stmt) => self.collect_stmt(stmt),
@@ -137,13 +235,16 @@ impl LowerCtx<'_> {
                 let body = self.collect_opt_stmt(stmt.body());
                 Stmt::WhileLoop { cond, body }
             }
-            ast::Stmt::ForStmt(stmt) => {
-                let cond = self.collect_opt_expr(stmt.condition());
-                let init = self.collect_opt_stmt(stmt.init());
-                let incr = self.collect_opt_stmt(stmt.incr());
-                let body = self.collect_opt_stmt(stmt.for_body());
-                Stmt::ForLoop { init, cond, incr, body }
-            }
+            ast::Stmt::ForStmt(stmt) => match self.unroll_genvar_loop(stmt) {
+                Some(unrolled) => unrolled,
+                None => {
+                    let cond = self.collect_opt_expr(stmt.condition());
+                    let init = self.collect_opt_stmt(stmt.init());
+                    let incr = self.collect_opt_stmt(stmt.incr());
+                    let body = self.collect_opt_stmt(stmt.for_body());
+                    Stmt::ForLoop { init, cond, incr, body }
+                }
+            },
             ast::Stmt::CaseStmt(stmt) => self.collect_case_stmt(stmt),
             ast::Stmt::EventStmt(stmt) => return self.collect_event_stmt(stmt),
             ast::Stmt::BlockStmt(stmt) => self.collect_block(stmt),
@@ -152,18 +253,19 @@ impl LowerCtx<'_> {
     }
 
     fn collect_event_stmt(&mut self, event_stmt: &ast::EventStmt) -> StmtId {
-        let kind = if event_stmt.initial_step_token().is_some() {
-            GlobalEvent::InitialStep
+        let event = if event_stmt.initial_step_token().is_some() {
+            let phases = event_stmt.sim_phases().map(|lit| lit.unescaped_value()).collect();
+            Event::Global { kind: GlobalEvent::InitialStep, phases }
         } else if event_stmt.final_step_token().is_some() {
-            GlobalEvent::FinalStep
+            let phases = event_stmt.sim_phases().map(|lit| lit.unescaped_value()).collect();
+            Event::Global { kind: GlobalEvent::FinalStep, phases }
+        } else if let Some(event) = event_stmt.event() {
+            Event::Monitored(self.collect_expr(event))
         } else {
             return self.collect_opt_stmt(event_stmt.stmt());
         };
 
-        let phases = event_stmt.sim_phases().map(|lit| lit.unescaped_value()).collect();
-        let event = Event::Global { kind, phases };
         let stmt = Stmt::EventControl { event, body: self.collect_opt_stmt(event_stmt.stmt()) };
-
         self.alloc_stmt(stmt, AstPtr::new(event_stmt).cast().unwrap(), event_stmt.attrs())
     }
 
@@ -213,6 +315,33 @@ impl LowerCtx<'_> {
         Stmt::Block { body }
     }
 
+    /// `` `default_transition `` provides the rise/fall time of `transition` and the
+    /// transition time of the `zi` filters when they are omitted.
+    fn default_transition(&mut self, call: &ast::Call, fun: &Name, args: &mut Vec<ExprId>) {
+        let (min_args, pos) = match fun {
+            fun if *fun == kw::transition => (1, 2),
+            fun if [kw::zi_nd, kw::zi_np, kw::zi_zd, kw::zi_zp].contains(fun) => (4, 4),
+            _ => return,
+        };
+        if !(min_args..=pos).contains(&args.len()) {
+            return;
+        }
+        let root_file = self.curr_scope.0.root_file;
+        let parse = self.db.parse(root_file);
+        let default = match parse.directives(call.syntax().text_range().start()).default_transition
+        {
+            Some(default) => default,
+            None => return,
+        };
+        // the delay of transition defaults to zero
+        while args.len() < pos {
+            let zero = Literal::Float(Ieee64::with_float(0.0));
+            args.push(self.alloc_expr_desugared(Expr::Literal(zero)));
+        }
+        let default = Literal::Float(Ieee64::with_float(default));
+        args.push(self.alloc_expr_desugared(Expr::Literal(default)));
+    }
+
     fn alloc_expr(&mut self, expr: Expr, ptr: AstPtr<ast::Expr>) -> ExprId {
         let id = self.make_expr(expr, Some(ptr.clone()));
         self.source_map.expr_map.insert(ptr, id);
@@ -3,7 +3,7 @@ use std::fmt::Write;
 
 use super::Body;
 use crate::db::HirDefDB;
-use crate::expr::CaseCond;
+use crate::expr::{CaseCond, Event};
 use crate::nameres::DefMapSource;
 use crate::{Expr, ExprId, Lookup, Stmt, StmtId};
 
@@ -64,6 +64,12 @@ impl Printer<'_> {
                 self.pretty_print_expr(e);
                 wln!(self, ";");
             }
+            Stmt::EventControl { event: Event::Monitored(event), body } => {
+                w!(self, "@(");
+                self.pretty_print_expr(event);
+                wln!(self, ")");
+                self.pretty_print_stmt(body)
+            }
             Stmt::EventControl { ref event, body } => {
                 wln!(self, "@({:?})", event);
                 self.pretty_print_stmt(body)
@@ -183,6 +189,12 @@ impl Printer<'_> {
                 }
                 w!(self, "}}");
             }
+            Expr::Index { base, index } => {
+                self.pretty_print_expr(base);
+                w!(self, "[");
+                self.pretty_print_expr(index);
+                w!(self, "]");
+            }
             Expr::Literal(ref lit) => w!(self, "{:?}", lit),
         }
     }
@@ -6,9 +6,11 @@ use typed_index_collections::TiSlice;
 
 use crate::db::HirDefDB;
 use crate::item_tree::{self, BranchKind, DisciplineAttrKind, Domain, NatureRef};
+use crate::nameres::ScopeDefItem;
 use crate::{
     AliasParamId, BranchId, DisciplineId, FunctionId, Intern, ItemTree, LocalFunctionArgId,
-    LocalNatureAttrId, Lookup, ModuleId, NatureId, NodeId, NodeLoc, ParamId, Path, Type, VarId,
+    LocalNatureAttrId, Lookup, ModuleId, NatureId, NodeId, NodeLoc, ParamId, ParamLoc, ParamsetId,
+    Path, Type, VarId,
 };
 
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
@@ -121,13 +123,19 @@ impl NatureData {
 pub struct VarData {
     pub name: Name,
     pub ty: Type,
+    /// The lower bound of the array dimension, indices are relative to this bound.
+    pub array_offset: i32,
 }
 
 impl VarData {
     pub fn var_data_query(db: &dyn HirDefDB, id: VarId) -> Arc<VarData> {
         let loc = id.lookup(db);
         let var = &loc.item_tree(db)[loc.id];
-        Arc::new(VarData { name: var.name.clone(), ty: var.ty.clone() })
+        Arc::new(VarData {
+            name: var.name.clone(),
+            ty: var.ty.clone(),
+            array_offset: var.array_offset,
+        })
     }
 }
 
@@ -135,13 +143,19 @@ impl VarData {
 pub struct ParamData {
     pub name: Name,
     pub ty: Option<Type>,
+    /// The lower bound of the array dimension, indices are relative to this bound.
+    pub array_offset: i32,
 }
 
 impl ParamData {
     pub fn param_data_query(db: &dyn HirDefDB, id: ParamId) -> Arc<ParamData> {
         let loc = id.lookup(db);
         let param = &loc.item_tree(db)[loc.id];
-        Arc::new(ParamData { name: param.name.clone(), ty: param.ty.clone() })
+        Arc::new(ParamData {
+            name: param.name.clone(),
+            ty: param.ty.clone(),
+            array_offset: param.array_offset,
+        })
     }
 }
 
@@ -152,6 +166,8 @@ pub struct NodeData {
     pub is_input: bool,
     pub is_output: bool,
     pub is_gnd: bool,
+    /// Declared with the `wreal` net type
+    pub is_real: bool,
 }
 
 impl NodeData {
@@ -168,6 +184,7 @@ impl NodeData {
             is_input,
             is_output,
             is_gnd: node.is_gnd(&tree),
+            is_real: node.is_real(&tree),
         })
     }
 
@@ -245,8 +262,64 @@ impl ModuleData {
         let num_ports = item_tree[loc.id].num_ports;
         let num_nodes = item_tree[loc.id].nodes.len() as u32;
         let ports = (0..num_ports).map(|id| NodeLoc { module, id: id.into() }.intern(db)).collect();
-        let internal_nodes =
-            (num_ports..num_nodes).map(|id| NodeLoc { module, id: id.into() }.intern(db)).collect();
+        let def_map = loc.def_map(db);
+        let declarations = &def_map[loc.scope.local_scope].declarations;
+        let nodes = &item_tree[loc.id].nodes;
+        let internal_nodes = (num_ports..num_nodes)
+            .map(|id| NodeLoc { module, id: id.into() }.intern(db))
+            // nodes aliased to another node are not part of the module anymore
+            .filter(|&node| {
+                let name = &nodes[node.lookup(db).id].name;
+                !matches!(declarations.get(name), Some(&ScopeDefItem::NodeId(decl)) if decl != node)
+            })
+            .collect();
         Arc::new(ModuleData { name: item_tree[loc.id].name.clone(), ports, internal_nodes })
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParamsetData {
+    pub name: Name,
+    pub module: Option<ModuleId>,
+    /// The module parameters assigned by the paramset together with the parameters that hold
+    /// the assigned value. Assignments to unknown parameters are omitted.
+    pub overrides: Vec<(ParamId, ParamId)>,
+}
+
+impl ParamsetData {
+    pub fn paramset_data_query(db: &dyn HirDefDB, paramset: ParamsetId) -> Arc<ParamsetData> {
+        let loc = paramset.lookup(db);
+        let item_tree = loc.item_tree(db);
+        let def_map = loc.def_map(db);
+        let paramset = &item_tree[loc.id];
+
+        let module = paramset.module.as_ref().and_then(|name| {
+            let module = def_map.resolve_local_item_in_scope::<ModuleId>(def_map.root(), name);
+            Some((module.ok()?, name))
+        });
+
+        let overrides = match module {
+            Some((module, module_name)) => {
+                let module_scope = module.lookup(db).scope.local_scope;
+                paramset
+                    .overrides
+                    .iter()
+                    .filter_map(|&id| {
+                        let dst = def_map
+                            .resolve_declared_param(module_scope, module_name, &item_tree[id].name)
+                            .ok()?;
+                        let src = ParamLoc { scope: loc.scope, id }.intern(db);
+                        Some((dst, src))
+                    })
+                    .collect()
+            }
+            None => Vec::new(),
+        };
+
+        Arc::new(ParamsetData {
+            name: paramset.name.clone(),
+            module: module.map(|(module, _)| module),
+            overrides,
+        })
+    }
+}
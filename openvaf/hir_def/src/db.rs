@@ -6,7 +6,7 @@ use stdx::Upcast;
 use crate::body::{Body, BodySourceMap, ParamExprs};
 use crate::data::{
     AliasParamData, BranchData, DisciplineData, FunctionData, ModuleData, NatureData, NodeData,
-    ParamData, VarData,
+    ParamData, ParamsetData, VarData,
 };
 use crate::item_tree::ItemTree;
 use crate::nameres::{DefMap, ScopeOrigin};
@@ -14,7 +14,8 @@ use crate::{
     AliasParamId, AliasParamLoc, BlockId, BlockLoc, BranchId, BranchLoc, DefWithBodyId,
     DisciplineAttrId, DisciplineAttrLoc, DisciplineId, DisciplineLoc, FunctionArgId,
     FunctionArgLoc, FunctionId, FunctionLoc, ModuleId, ModuleLoc, NatureAttrId, NatureAttrLoc,
-    NatureId, NatureLoc, NodeId, NodeLoc, ParamId, ParamLoc, VarId, VarLoc,
+    NatureId, NatureLoc, NodeBusId, NodeBusLoc, NodeId, NodeLoc, ParamId, ParamLoc, ParamsetId,
+    ParamsetLoc, VarId, VarLoc,
 };
 
 #[salsa::query_group(InternDatabase)]
@@ -22,6 +23,8 @@ pub trait InternDB: BaseDB {
     #[salsa::interned]
     fn intern_module(&self, loc: ModuleLoc) -> ModuleId;
     #[salsa::interned]
+    fn intern_paramset(&self, loc: ParamsetLoc) -> ParamsetId;
+    #[salsa::interned]
     fn intern_param(&self, loc: ParamLoc) -> ParamId;
     #[salsa::interned]
     fn intern_var(&self, loc: VarLoc) -> VarId;
@@ -42,6 +45,8 @@ pub trait InternDB: BaseDB {
     #[salsa::interned]
     fn intern_node(&self, loc: NodeLoc) -> NodeId;
     #[salsa::interned]
+    fn intern_node_bus(&self, loc: NodeBusLoc) -> NodeBusId;
+    #[salsa::interned]
     fn intern_function_arg(&self, loc: FunctionArgLoc) -> FunctionArgId;
     #[salsa::interned]
     fn intern_alias_param(&self, loc: AliasParamLoc) -> AliasParamId;
@@ -102,6 +107,9 @@ pub trait HirDefDB: InternDB + Upcast<dyn BaseDB> {
     #[salsa::invoke(ModuleData::module_data_query)]
     fn module_data(&self, module: ModuleId) -> Arc<ModuleData>;
 
+    #[salsa::invoke(ParamsetData::paramset_data_query)]
+    fn paramset_data(&self, paramset: ParamsetId) -> Arc<ParamsetData>;
+
     #[salsa::transparent]
     fn find_module(&self, root_file: FileId) -> ModuleId;
 }
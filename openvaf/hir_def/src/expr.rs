@@ -82,6 +82,10 @@ pub enum Expr {
         args: Vec<ExprId>,
     },
     Array(Vec<ExprId>),
+    Index {
+        base: ExprId,
+        index: ExprId,
+    },
     Literal(Literal),
 }
 
@@ -95,6 +99,10 @@ impl Expr {
                 f(rhs);
             }
             Expr::UnaryOp { expr, .. } => f(expr),
+            Expr::Index { base, index } => {
+                f(base);
+                f(index);
+            }
             Expr::Select { cond, then_val, else_val } => {
                 f(cond);
                 f(then_val);
@@ -143,7 +151,12 @@ pub enum GlobalEvent {
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
 #[non_exhaustive]
 pub enum Event {
-    Global { kind: GlobalEvent, phases: Vec<String> },
+    Global {
+        kind: GlobalEvent,
+        phases: Vec<String>,
+    },
+    /// An event function like `cross`, `above` or `timer`
+    Monitored(ExprId),
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -162,8 +175,12 @@ impl Stmt {
     #[inline]
     pub fn walk_child_exprs(&self, mut f: impl FnMut(ExprId)) {
         match *self {
-            Stmt::Empty | Stmt::Missing | Stmt::Block { .. } | Stmt::EventControl { .. } => (),
-            Stmt::If { cond: expr, .. }
+            Stmt::Empty
+            | Stmt::Missing
+            | Stmt::Block { .. }
+            | Stmt::EventControl { event: Event::Global { .. }, .. } => (),
+            Stmt::EventControl { event: Event::Monitored(expr), .. }
+            | Stmt::If { cond: expr, .. }
             | Stmt::ForLoop { cond: expr, .. }
             | Stmt::WhileLoop { cond: expr, .. }
             | Stmt::Expr(expr) => f(expr),
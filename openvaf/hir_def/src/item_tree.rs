@@ -10,7 +10,7 @@
 //! In general, any item in the `ItemTree` stores its `AstId`, which allows mapping it back to its
 //! surface syntax.
 
-mod lower;
+pub(crate) mod lower;
 mod pretty;
 
 use std::fmt::Debug;
@@ -29,7 +29,8 @@ use typed_index_collections::TiVec;
 
 use crate::db::HirDefDB;
 use crate::{
-    LocalDisciplineAttrId, LocalFunctionArgId, LocalNatureAttrId, LocalNodeId, Path, Type,
+    LocalDisciplineAttrId, LocalFunctionArgId, LocalNatureAttrId, LocalNodeBusId, LocalNodeId,
+    Path, Type,
 };
 
 /// The item tree of a source file.
@@ -48,9 +49,9 @@ impl Default for ItemTree {
 
 impl ItemTree {
     pub(crate) fn file_item_tree_query(db: &dyn HirDefDB, file: FileId) -> Arc<ItemTree> {
-        let syntax_tree = db.parse(file).tree();
-        let ctx = lower::Ctx::new(db, file);
-        let mut item_tree = ctx.lower_root_items(&syntax_tree);
+        let parse = db.parse(file);
+        let ctx = lower::Ctx::new(db, file, &parse);
+        let mut item_tree = ctx.lower_root_items(&parse.tree());
         item_tree.shrink_to_fit();
         Arc::new(item_tree)
     }
@@ -69,6 +70,7 @@ impl ItemTree {
             ports,
             branches,
             functions,
+            paramsets,
         } = &mut self.data;
         modules.shrink_to_fit();
         disciplines.shrink_to_fit();
@@ -81,6 +83,7 @@ impl ItemTree {
         ports.shrink_to_fit();
         branches.shrink_to_fit();
         functions.shrink_to_fit();
+        paramsets.shrink_to_fit();
         nature_attrs.shrink_to_fit();
         discipline_attrs.shrink_to_fit();
     }
@@ -105,6 +108,7 @@ pub(crate) struct ItemTreeData {
     pub ports: Arena<Port>,
     pub branches: Arena<Branch>,
     pub functions: Arena<Function>,
+    pub paramsets: Arena<Paramset>,
 }
 
 /// Trait implemented by all item nodes in the item tree.
@@ -132,12 +136,14 @@ pub enum RootItem {
     Module(ItemTreeId<Module>),
     Nature(ItemTreeId<Nature>),
     Discipline(ItemTreeId<Discipline>),
+    Paramset(ItemTreeId<Paramset>),
 }
 
 impl_from_typed! (
     Module(ItemTreeId<Module>),
     Nature(ItemTreeId<Nature>),
-    Discipline(ItemTreeId<Discipline>) for RootItem
+    Discipline(ItemTreeId<Discipline>),
+    Paramset(ItemTreeId<Paramset>) for RootItem
 );
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
@@ -214,6 +220,7 @@ macro_rules! item_tree_nodes {
 
 item_tree_nodes! {
     Module in modules -> ast::ModuleDecl,
+    Paramset in paramsets -> ast::ParamsetDecl,
     Discipline in disciplines -> ast::DisciplineDecl,
     Nature in natures -> ast::NatureDecl,
 
@@ -232,8 +239,25 @@ item_tree_nodes! {
 pub struct Module {
     pub name: Name,
     pub nodes: TiVec<LocalNodeId, Node>,
+    pub buses: TiVec<LocalNodeBusId, NodeBus>,
     pub num_ports: u32,
     pub items: Vec<ModuleItem>,
+    pub genvars: Vec<Name>,
+    /// Module parameters whose default value is a constant integer expression. Structural
+    /// constructs (generate loops, vector nets) are elaborated with these default values.
+    pub const_params: Vec<(Name, i32)>,
+    pub generate_blocks: Arena<GenerateBlock>,
+    /// Analog blocks inside generate loops in the order they appear in the unrolled source.
+    pub generate_analog_behaviour: Vec<(Idx<GenerateBlock>, AstId<ast::AnalogBehaviour>)>,
+    pub generate_errors: Vec<GenerateError>,
+    pub net_range_errors: Vec<NetRangeError>,
+    /// Instances of other modules. These are flattened into this module: the items of the
+    /// instantiated module are lowered in a generate block named after the instance.
+    pub instances: Vec<Instance>,
+    pub instance_errors: Vec<InstanceError>,
+    /// Nodes aliased with `$analog_node_alias`/`$analog_port_alias` in the analog blocks of
+    /// this module.
+    pub node_aliases: Vec<NodeAlias>,
     pub ast_id: AstId<ast::ModuleDecl>,
 }
 
@@ -245,6 +269,7 @@ pub enum ModuleItem {
     Variable(ItemTreeId<Var>),
     Branch(ItemTreeId<Branch>),
     Node(LocalNodeId),
+    NodeBus(LocalNodeBusId),
     Function(ItemTreeId<Function>),
 }
 
@@ -255,14 +280,168 @@ impl_from_typed! (
     Variable(ItemTreeId<Var>),
     Branch(ItemTreeId<Branch>),
     Node(LocalNodeId),
+    NodeBus(LocalNodeBusId),
     Function(ItemTreeId<Function>) for ModuleItem
 );
 
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Paramset {
+    pub name: Name,
+    /// The module this paramset instantiates.
+    pub module: Option<Name>,
+    pub params: Vec<ItemTreeId<Param>>,
+    /// The module parameters assigned by paramset statements (`.w = l;`). Each assignment is
+    /// represented as a parameter (that is not declared in any scope) whose default value is the
+    /// assigned expression.
+    pub overrides: Vec<ItemTreeId<Param>>,
+    pub ast_id: AstId<ast::ParamsetDecl>,
+}
+
+/// A single unrolled iteration of a generate loop or a module instance.
+///
+/// Items declared inside the block are declared in the module scope with the hierarchical name of
+/// the block as prefix (`blk[1].n`). References to genvars are replaced with their value
+/// while the body is lowered.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct GenerateBlock {
+    /// The hierarchical name of this block (`blk[1]`).
+    pub name: Name,
+    /// The value of every genvar (including those of enclosing loops) during this iteration.
+    pub genvars: Vec<(Name, i32)>,
+    /// The names declared inside the block and the hierarchical names they are declared with.
+    /// For module instances this also maps the connected ports to the nodes of the parent.
+    pub locals: Vec<(Name, Name)>,
+    /// The parameters with a constant integer value that are visible inside the block.
+    pub consts: Vec<(Name, i32)>,
+    /// The `GenerateFor` loop or `ModuleInst` this block was created for.
+    pub ast_id: ErasedAstId,
+}
+
+impl GenerateBlock {
+    pub fn genvar(&self, name: &Name) -> Option<i32> {
+        self.genvars.iter().rev().find(|(genvar, _)| genvar == name).map(|(_, val)| *val)
+    }
+
+    pub fn local_name(&self, name: &Name) -> Option<&Name> {
+        self.locals.iter().rev().find(|(local, _)| local == name).map(|(_, decl)| decl)
+    }
+
+    /// Maps a path that refers to an item declared inside the loop to its hierarchical name.
+    pub fn local_path(&self, mut path: Path) -> Path {
+        if !path.is_root_path {
+            if let Some(name) = path.segments.first_mut() {
+                if let Some(local) = self.local_name(name) {
+                    *name = local.clone();
+                }
+            }
+        }
+        path
+    }
+}
+
+/// A call to `$analog_node_alias(alias, "target")` or `$analog_port_alias(alias, "target")`.
+/// The alias is resolved to the target node during name resolution.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct NodeAlias {
+    pub alias: Name,
+    pub target: Name,
+    /// `$analog_port_alias` only accepts port nodes as target.
+    pub is_port: bool,
+    /// The analog block that contains the call.
+    pub ast_id: AstId<ast::AnalogBehaviour>,
+    /// The position of the call among all calls in the analog block.
+    pub call: u32,
+}
+
+/// An instance of another module (`child #(.w(2)) c1 (a, b);`).
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Instance {
+    pub name: Name,
+    /// The name of the instantiated module.
+    pub module: Name,
+    /// The generate block the items of the instantiated module are lowered in.
+    pub block: Idx<GenerateBlock>,
+    pub ast_id: AstId<ast::ModuleInst>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct InstanceError {
+    pub kind: InstanceErrorKind,
+    pub ast_id: AstId<ast::ModuleInst>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum InstanceErrorKind {
+    UnknownModule {
+        name: Name,
+    },
+    /// The module (indirectly) instantiates itself.
+    RecursiveInstance {
+        name: Name,
+    },
+    UnknownPort {
+        name: Name,
+    },
+    UnknownParam {
+        name: Name,
+    },
+    TooManyPorts {
+        expected: usize,
+    },
+    TooManyParams {
+        expected: usize,
+    },
+    /// A port is connected to an expression that is not a node.
+    InvalidConnection {
+        port: Name,
+    },
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct GenerateError {
+    pub kind: GenerateErrorKind,
+    pub ast_id: AstId<ast::GenerateFor>,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum GenerateErrorKind {
+    /// The loop initialization/increment is not an assignment to a genvar.
+    ExpectedGenvarAssign,
+    NotAGenvar {
+        name: Name,
+    },
+    /// The loop bounds are not constant expressions of literals, genvars and parameters.
+    NotConstant,
+    IterationLimit {
+        limit: u32,
+    },
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct NetRangeError {
+    pub kind: NetRangeErrorKind,
+    /// The net or port declaration with the invalid range.
+    pub ast_id: ErasedAstId,
+}
+
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub enum NetRangeErrorKind {
+    /// The bounds are not constant expressions of literals, genvars and parameters.
+    NotConstant,
+    TooWide {
+        limit: u32,
+    },
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Port {
     pub name: Name,
     pub discipline: Option<Name>,
+    /// The `` `default_discipline `` in effect at the declaration
+    pub default_discipline: Option<Name>,
     pub is_gnd: bool,
+    /// Declared with the `wreal` net type
+    pub is_real: bool,
     pub is_input: bool,
     pub is_output: bool,
 
@@ -274,7 +453,11 @@ pub struct Port {
 pub struct Net {
     pub name: Name,
     pub discipline: Option<Name>,
+    /// The `` `default_discipline `` in effect at the declaration
+    pub default_discipline: Option<Name>,
     pub is_gnd: bool,
+    /// Declared with the `wreal` net type
+    pub is_real: bool,
 
     pub name_idx: usize,
     pub ast_id: AstId<ast::NetDecl>,
@@ -284,6 +467,8 @@ pub struct Net {
 pub struct Var {
     pub name: Name,
     pub ty: Type,
+    /// The lower bound of the array dimension (`1` for `x[1:8]`).
+    pub array_offset: i32,
     pub ast_id: AstId<ast::Var>,
 }
 
@@ -291,10 +476,24 @@ pub struct Var {
 pub struct Param {
     pub name: Name,
     pub ty: Option<Type>,
+    /// The lower bound of the array dimension (`1` for `p[1:8]`).
+    pub array_offset: i32,
     pub is_local: bool,
+    /// The generate block (or module instance) the parameter was declared in.
+    pub generate: Option<Idx<GenerateBlock>>,
+    /// The value assigned to the parameter by the module instance it was declared in.
+    pub value: Option<ParamOverride>,
     pub ast_id: AstId<ast::Param>,
 }
 
+/// A parameter override of a module instance (`#(.w(2))`).
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ParamOverride {
+    pub ast_id: AstId<ast::InstArg>,
+    /// The generate block of the parent module the instance was declared in.
+    pub generate: Option<Idx<GenerateBlock>>,
+}
+
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
 pub struct AliasParam {
     pub name: Name,
@@ -442,6 +641,13 @@ impl NodeTypeDecl {
         }
     }
 
+    pub fn default_discipline(self, tree: &ItemTree) -> &Option<Name> {
+        match self {
+            NodeTypeDecl::Net(net) => &tree[net].default_discipline,
+            NodeTypeDecl::Port(port) => &tree[port].default_discipline,
+        }
+    }
+
     pub fn discipline_src(self, db: &dyn HirDefDB, root_file: FileId) -> Option<NameRef> {
         let ast_id_map = db.ast_id_map(root_file);
         let tree = db.item_tree(root_file);
@@ -461,6 +667,13 @@ impl NodeTypeDecl {
         }
     }
 
+    pub fn is_real(self, tree: &ItemTree) -> bool {
+        match self {
+            NodeTypeDecl::Net(net) => tree[net].is_real,
+            NodeTypeDecl::Port(port) => tree[port].is_real,
+        }
+    }
+
     pub fn direction(self, tree: &ItemTree) -> Option<(bool, bool)> {
         match self {
             NodeTypeDecl::Port(port) => Some((tree[port].is_input, tree[port].is_output)),
@@ -490,6 +703,30 @@ impl NodeTypeDecl {
     }
 }
 
+/// A vector net or port (`electrical [0:3] n;`).
+///
+/// Every element of a bus is a separate node that is named after its index (`n[2]`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeBus {
+    pub name: Name,
+    pub msb: i32,
+    pub lsb: i32,
+    /// The elements in the order they were declared in (from `msb` to `lsb`).
+    pub nodes: Vec<LocalNodeId>,
+    pub ast_id: ErasedAstId,
+}
+
+impl NodeBus {
+    pub fn node(&self, idx: i32) -> Option<LocalNodeId> {
+        let offset = if self.msb <= self.lsb {
+            idx.checked_sub(self.msb)
+        } else {
+            self.msb.checked_sub(idx)
+        };
+        self.nodes.get(usize::try_from(offset?).ok()?).copied()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Node {
     pub name: Name,
@@ -515,7 +752,20 @@ impl Node {
         self.decls.iter().any(|decl| decl.is_gnd(tree))
     }
 
+    pub fn is_real(&self, tree: &ItemTree) -> bool {
+        self.decls.iter().any(|decl| decl.is_real(tree))
+    }
+
+    /// Returns the discipline of the node. If no declaration specifies a discipline,
+    /// the `` `default_discipline `` of the first declaration is used instead.
     pub fn discipline(&self, tree: &ItemTree) -> Option<Name> {
-        self.decls.iter().find_map(|decl| decl.discipline(tree).clone())
+        self.decls
+            .iter()
+            .find_map(|decl| decl.discipline(tree).clone())
+            .or_else(|| self.default_discipline(tree))
+    }
+
+    pub fn default_discipline(&self, tree: &ItemTree) -> Option<Name> {
+        self.decls.iter().find_map(|decl| decl.default_discipline(tree).clone())
     }
 }
@@ -1,23 +1,25 @@
 use std::mem;
 use std::sync::Arc;
 
-use arena::IdxRange;
-use basedb::{AstId, AstIdMap, FileId};
-use syntax::ast::{self, ParamRef, PathSegmentKind};
-use syntax::name::{kw, AsIdent, AsName};
-use syntax::{match_ast, AstNode, WalkEvent};
+use arena::{Arena, Idx, IdxRange};
+use basedb::{AstId, AstIdMap, ErasedAstId, FileId};
+use syntax::ast::{self, ArgListOwner, BinaryOp, ParamRef, PathSegmentKind, UnaryOp};
+use syntax::name::{kw, kw_comp, sysfun, AsIdent, AsName, Name};
+use syntax::{match_ast, AstNode, Parse, SyntaxNode, WalkEvent};
 use typed_index_collections::TiVec;
 
 use super::{
     Block, Branch, BranchKind, Discipline, DisciplineAttr, DisciplineAttrKind, Domain, Function,
-    FunctionArg, FunctionItem, ItemTree, ItemTreeId, Module, ModuleItem, Nature, NatureAttr,
-    NatureRef, NatureRefKind, Net, Node, Param, Port, RootItem, Var,
+    FunctionArg, FunctionItem, GenerateBlock, GenerateError, GenerateErrorKind, Instance,
+    InstanceError, InstanceErrorKind, ItemTree, ItemTreeId, Module, ModuleItem, Nature, NatureAttr,
+    NatureRef, NatureRefKind, Net, NetRangeError, NetRangeErrorKind, Node, NodeAlias, NodeBus,
+    NodeTypeDecl, Param, ParamOverride, Paramset, Port, RootItem, Var,
 };
 // use tracing::trace;
 use crate::db::HirDefDB;
 use crate::item_tree::AliasParam;
 use crate::types::AsType;
-use crate::{LocalFunctionArgId, LocalNodeId, Path, Type};
+use crate::{LocalFunctionArgId, LocalNodeBusId, LocalNodeId, Path, Type};
 
 fn is_input(direction: &Option<ast::Direction>) -> bool {
     direction.as_ref().map_or(false, |it| it.input_token().is_some() || it.inout_token().is_some())
@@ -27,17 +29,85 @@ fn is_output(direction: &Option<ast::Direction>) -> bool {
     direction.as_ref().map_or(false, |it| it.output_token().is_some() || it.inout_token().is_some())
 }
 
-pub(super) struct Ctx {
+/// Generate loops with more iterations are rejected to avoid hanging on loops that never end.
+const MAX_GENERATE_ITERATIONS: u32 = 1 << 16;
+/// Vector nets with more elements are rejected as every element is a separate node.
+const MAX_BUS_WIDTH: u32 = 1 << 16;
+
+pub(super) struct Ctx<'a> {
     tree: ItemTree,
     source_ast_id_map: Arc<AstIdMap>,
+    parse: &'a Parse<ast::SourceFile>,
+    generate: GenerateCtx,
+    /// The parameters of the module that is currently being lowered which have a constant
+    /// integer default value.
+    const_params: Vec<(Name, i32)>,
+    buses: TiVec<LocalNodeBusId, NodeBus>,
+    net_range_errors: Vec<NetRangeError>,
+    node_aliases: Vec<NodeAlias>,
+    /// All modules of the file, these are required to lower module instances.
+    modules: Vec<ast::ModuleDecl>,
+    /// The modules that are currently being elaborated (outermost first). Used to detect
+    /// modules that instantiate themselves.
+    elaborating: Vec<Name>,
+    /// The module instance that is currently being lowered.
+    instance: Option<InstanceCtx>,
+}
+
+struct InstanceCtx {
+    block: Idx<GenerateBlock>,
+    /// The ports of the instantiated module that are connected to a node of the parent.
+    connected: Vec<Name>,
+    overrides: Vec<(Name, ParamOverride)>,
+}
+
+/// The generate constructs of the module that is currently being lowered.
+#[derive(Default)]
+struct GenerateCtx {
+    genvars: Vec<Name>,
+    /// The loop iteration that is currently being unrolled.
+    scope: Option<Idx<GenerateBlock>>,
+    blocks: Arena<GenerateBlock>,
+    analog_behaviour: Vec<(Idx<GenerateBlock>, AstId<ast::AnalogBehaviour>)>,
+    errors: Vec<GenerateError>,
+    instances: Vec<Instance>,
+    instance_errors: Vec<InstanceError>,
+    /// Unnamed loops are numbered by their position in the source so that loops nested
+    /// inside other loops receive the same name in every iteration.
+    unnamed_loops: Vec<AstId<ast::GenerateFor>>,
+}
+
+impl GenerateCtx {
+    fn scope(&self) -> Option<&GenerateBlock> {
+        self.scope.map(|scope| &self.blocks[scope])
+    }
 }
 
-impl Ctx {
-    pub(super) fn new(db: &dyn HirDefDB, file: FileId) -> Self {
-        Self { tree: ItemTree::default(), source_ast_id_map: db.ast_id_map(file) }
+impl<'a> Ctx<'a> {
+    pub(super) fn new(db: &dyn HirDefDB, file: FileId, parse: &'a Parse<ast::SourceFile>) -> Self {
+        Self {
+            tree: ItemTree::default(),
+            source_ast_id_map: db.ast_id_map(file),
+            parse,
+            generate: GenerateCtx::default(),
+            const_params: Vec::new(),
+            buses: TiVec::new(),
+            net_range_errors: Vec::new(),
+            node_aliases: Vec::new(),
+            modules: Vec::new(),
+            elaborating: Vec::new(),
+            instance: None,
+        }
     }
 
     pub(super) fn lower_root_items(mut self, file: &ast::SourceFile) -> ItemTree {
+        self.modules = file
+            .items()
+            .filter_map(|item| match item {
+                ast::Item::ModuleDecl(module) => Some(module),
+                _ => None,
+            })
+            .collect();
         self.tree.top_level = file.items().filter_map(|it| self.lower_root_item(it)).collect();
         self.tree
     }
@@ -47,10 +117,45 @@ impl Ctx {
             ast::Item::DisciplineDecl(discipline) => self.lower_discipline(discipline)?.into(),
             ast::Item::NatureDecl(nature) => self.lower_nature(nature)?.into(),
             ast::Item::ModuleDecl(module) => self.lower_module(module)?.into(),
+            ast::Item::ParamsetDecl(paramset) => self.lower_paramset(paramset)?.into(),
         };
         Some(item)
     }
 
+    fn lower_paramset(&mut self, decl: ast::ParamsetDecl) -> Option<ItemTreeId<Paramset>> {
+        let name = decl.name()?.as_name();
+        let ast_id = self.source_ast_id_map.ast_id(&decl);
+        let module = decl.module().map(|it| it.as_name());
+
+        let mut params = Vec::new();
+        let mut overrides = Vec::new();
+        for item in decl.paramset_items() {
+            match item {
+                ast::ParamsetItem::ParamDecl(decl) => self.lower_param(decl, &mut params),
+                ast::ParamsetItem::ParamsetStmt(stmt) => {
+                    if let Some(param) = stmt.param() {
+                        if let Some(name) = param.name() {
+                            let ast_id = self.source_ast_id_map.ast_id(&param);
+                            let param = Param {
+                                name: name.as_name(),
+                                ty: None,
+                                array_offset: 0,
+                                is_local: true,
+                                generate: None,
+                                value: None,
+                                ast_id,
+                            };
+                            overrides.push(self.tree.data.parameters.push_and_get_key(param));
+                        }
+                    }
+                }
+            }
+        }
+
+        let res = Paramset { name, module, params, overrides, ast_id };
+        Some(self.tree.data.paramsets.push_and_get_key(res))
+    }
+
     fn lower_discipline(&mut self, decl: ast::DisciplineDecl) -> Option<ItemTreeId<Discipline>> {
         use kw::raw as kw;
         let name = decl.name()?.as_name();
@@ -228,16 +333,45 @@ impl Ctx {
         let name = decl.name()?.as_name();
         let ast_id = self.source_ast_id_map.ast_id(&decl);
 
+        self.const_params.clear();
+        collect_const_params(decl.module_items(), &[], &mut self.const_params);
+        self.elaborating = vec![name.clone()];
+
         let mut nodes = TiVec::new();
         let mut items = Vec::new();
         if let Some(ports) = decl.module_ports() {
-            self.lower_module_ports(ports, &mut nodes, &mut items);
+            self.lower_module_ports(ports, &decl, &mut nodes, &mut items);
         }
 
         let num_ports = nodes.len() as u32;
         self.lower_module_items(decl.module_items(), &mut nodes, &mut items);
 
-        let res = Module { name, nodes, items, ast_id, num_ports };
+        let GenerateCtx {
+            genvars,
+            blocks,
+            analog_behaviour,
+            errors,
+            instances,
+            instance_errors,
+            ..
+        } = mem::take(&mut self.generate);
+        let res = Module {
+            name,
+            nodes,
+            buses: mem::take(&mut self.buses),
+            items,
+            genvars,
+            const_params: mem::take(&mut self.const_params),
+            generate_blocks: blocks,
+            generate_analog_behaviour: analog_behaviour,
+            generate_errors: errors,
+            net_range_errors: mem::take(&mut self.net_range_errors),
+            instances,
+            instance_errors,
+            node_aliases: mem::take(&mut self.node_aliases),
+            ast_id,
+            num_ports,
+        };
         Some(self.tree.data.modules.push_and_get_key(res))
     }
 
@@ -258,22 +392,486 @@ impl Ctx {
                     self.lower_net_decl(decl, nodes, dst);
                 }
                 ast::ModuleItem::AnalogBehaviour(behaviour) => {
-                    if let Some(stmt) = behaviour.stmt() {
-                        self.lower_stmt(stmt, dst);
+                    self.lower_node_aliases(&behaviour);
+                    match self.generate.scope {
+                        Some(scope) => {
+                            let ast_id = self.source_ast_id_map.ast_id(&behaviour);
+                            self.generate.analog_behaviour.push((scope, ast_id));
+                            // named blocks inside generate loops are not accessible from
+                            // outside the loop so they are not declared in the module
+                            if let Some(stmt) = behaviour.stmt() {
+                                self.lower_stmt::<ModuleItem>(stmt, &mut Vec::new());
+                            }
+                        }
+                        None => {
+                            if let Some(stmt) = behaviour.stmt() {
+                                self.lower_stmt(stmt, dst);
+                            }
+                        }
                     }
                 }
                 ast::ModuleItem::VarDecl(var) => {
+                    let start = dst.len();
                     self.lower_var(var, dst);
+                    self.rename_generate_locals(&dst[start..]);
                 }
                 ast::ModuleItem::ParamDecl(param) => {
+                    let start = dst.len();
                     self.lower_param(param, dst);
+                    self.rename_generate_locals(&dst[start..]);
                 }
                 ast::ModuleItem::Function(fun) => {
-                    self.lower_fun(fun, dst);
+                    // functions can not refer to genvars so they are only declared once
+                    let ast_id = self.source_ast_id_map.ast_id(&fun);
+                    let declared = dst.iter().any(|item| {
+                        matches!(item, ModuleItem::Function(id) if self.tree[*id].ast_id == ast_id)
+                    });
+                    if !declared {
+                        self.lower_fun(fun, dst);
+                    }
                 }
                 ast::ModuleItem::BranchDecl(branch) => self.lower_branch(branch, dst),
-                ast::ModuleItem::AliasParam(alias) => self.lower_alias_param(alias, dst),
+                ast::ModuleItem::AliasParam(alias) => {
+                    let start = dst.len();
+                    self.lower_alias_param(alias, dst);
+                    self.rename_generate_locals(&dst[start..]);
+                }
+                ast::ModuleItem::GenvarDecl(decl) => {
+                    self.generate.genvars.extend(decl.names().map(|name| name.as_name()));
+                }
+                ast::ModuleItem::GenerateRegion(region) => {
+                    self.lower_module_items(region.module_items(), nodes, dst)
+                }
+                ast::ModuleItem::GenerateFor(generate) => {
+                    self.lower_generate_for(generate, nodes, dst)
+                }
+                ast::ModuleItem::ModuleInst(inst) => self.lower_module_inst(inst, nodes, dst),
+            };
+        }
+    }
+
+    fn lower_generate_for(
+        &mut self,
+        generate: ast::GenerateFor,
+        nodes: &mut TiVec<LocalNodeId, Node>,
+        dst: &mut Vec<ModuleItem>,
+    ) {
+        let ast_id = self.source_ast_id_map.ast_id(&generate);
+        let loop_name = match generate.block_scope().and_then(|scope| scope.name()) {
+            Some(name) => name.as_name(),
+            None => {
+                let pos = match self.generate.unnamed_loops.iter().position(|&it| it == ast_id) {
+                    Some(pos) => pos,
+                    None => {
+                        self.generate.unnamed_loops.push(ast_id);
+                        self.generate.unnamed_loops.len() - 1
+                    }
+                };
+                Name::resolve(&format!("genblk{}", pos + 1))
+            }
+        };
+
+        let (genvar, iterations) = match self.generate_loop_iterations(&generate) {
+            Ok(res) => res,
+            Err(kind) => {
+                // nested loops are unrolled once per iteration of the parent loop
+                if self.generate.errors.iter().all(|err| err.ast_id != ast_id) {
+                    self.generate.errors.push(GenerateError { kind, ast_id });
+                }
+                return;
+            }
+        };
+
+        let parent = self.generate.scope;
+        for val in iterations {
+            let (name, mut genvars, mut locals, consts) = match self.generate.scope() {
+                Some(parent) => (
+                    format!("{}.{}[{}]", parent.name, loop_name, val),
+                    parent.genvars.clone(),
+                    parent.locals.clone(),
+                    parent.consts.clone(),
+                ),
+                None => (
+                    format!("{}[{}]", loop_name, val),
+                    Vec::new(),
+                    Vec::new(),
+                    self.const_params.clone(),
+                ),
+            };
+            let name = Name::resolve(&name);
+            genvars.push((genvar.clone(), val));
+            collect_generate_locals(generate.module_items(), &name, &mut locals);
+
+            let block = GenerateBlock { name, genvars, locals, consts, ast_id: ast_id.into() };
+            self.generate.scope = Some(self.generate.blocks.push_and_get_key(block));
+            self.lower_module_items(generate.module_items(), nodes, dst);
+            self.generate.scope = parent;
+        }
+    }
+
+    /// Evaluates the loop header of a generate loop and returns the genvar and the values it
+    /// takes in each iteration.
+    fn generate_loop_iterations(
+        &self,
+        generate: &ast::GenerateFor,
+    ) -> Result<(Name, Vec<i32>), GenerateErrorKind> {
+        genvar_loop_iterations(
+            generate.init(),
+            generate.condition(),
+            generate.incr(),
+            &self.generate.genvars,
+            self.consts(),
+        )
+    }
+
+    /// The values of the parameters and genvars that are visible to constant expressions.
+    fn consts(&self) -> Vec<(Name, i32)> {
+        match self.generate.scope() {
+            Some(scope) => {
+                let mut consts = scope.consts.clone();
+                consts.extend(scope.genvars.iter().cloned());
+                consts
+            }
+            None => self.const_params.clone(),
+        }
+    }
+
+    /// Flattens a module instance into the module that is currently being lowered. The items
+    /// of the instantiated module are lowered in a generate block named after the instance.
+    fn lower_module_inst(
+        &mut self,
+        inst: ast::ModuleInst,
+        nodes: &mut TiVec<LocalNodeId, Node>,
+        dst: &mut Vec<ModuleItem>,
+    ) {
+        let ast_id = self.source_ast_id_map.ast_id(&inst);
+        let (name, module_name) = match (inst.name(), inst.module()) {
+            (Some(name), Some(module)) => (name.as_name(), module.as_name()),
+            _ => return,
+        };
+
+        let module = self
+            .modules
+            .iter()
+            .find(|module| module.name().map_or(false, |name| name.as_name() == module_name))
+            .cloned();
+        let module = match module {
+            Some(module) => module,
+            None => {
+                self.instance_error(InstanceErrorKind::UnknownModule { name: module_name }, ast_id);
+                return;
+            }
+        };
+        if self.elaborating.contains(&module_name) {
+            self.instance_error(InstanceErrorKind::RecursiveInstance { name: module_name }, ast_id);
+            return;
+        }
+
+        let ports = module_ports(&module);
+        let mut connections = Vec::new();
+        for (i, arg) in inst.inst_ports().into_iter().flat_map(|ports| ports.args()).enumerate() {
+            let port = match arg.name() {
+                Some(port) if ports.contains(&port.as_name()) => port.as_name(),
+                Some(port) => {
+                    let kind = InstanceErrorKind::UnknownPort { name: port.as_name() };
+                    self.instance_error(kind, ast_id);
+                    continue;
+                }
+                None => match ports.get(i) {
+                    Some(port) => port.clone(),
+                    None => {
+                        let kind = InstanceErrorKind::TooManyPorts { expected: ports.len() };
+                        self.instance_error(kind, ast_id);
+                        break;
+                    }
+                },
+            };
+            // `.p()` leaves the port unconnected
+            if let Some(expr) = arg.expr() {
+                match self.node_path(expr).and_then(|path| path.as_ident()) {
+                    Some(node) => connections.push((port, node)),
+                    None => {
+                        self.instance_error(InstanceErrorKind::InvalidConnection { port }, ast_id)
+                    }
+                }
+            }
+        }
+
+        let params = module_params(&module);
+        let parent_consts = self.consts();
+        let mut overrides = Vec::new();
+        let mut const_overrides = Vec::new();
+        for (i, arg) in inst.inst_params().into_iter().flat_map(|params| params.args()).enumerate()
+        {
+            let param = match arg.name() {
+                Some(param) if params.contains(&param.as_name()) => param.as_name(),
+                Some(param) => {
+                    let kind = InstanceErrorKind::UnknownParam { name: param.as_name() };
+                    self.instance_error(kind, ast_id);
+                    continue;
+                }
+                None => match params.get(i) {
+                    Some(param) => param.clone(),
+                    None => {
+                        let kind = InstanceErrorKind::TooManyParams { expected: params.len() };
+                        self.instance_error(kind, ast_id);
+                        break;
+                    }
+                },
+            };
+            if arg.expr().is_some() {
+                const_overrides
+                    .push((param.clone(), eval_const_expr(arg.expr(), &parent_consts).ok()));
+                let value = ParamOverride {
+                    ast_id: self.source_ast_id_map.ast_id(&arg),
+                    generate: self.generate.scope,
+                };
+                overrides.push((param, value));
+            }
+        }
+
+        let block_name = match self.generate.scope() {
+            Some(parent) => format!("{}.{}", parent.name, name),
+            None => name.to_string(),
+        };
+        let block_name = Name::resolve(&block_name);
+        let mut locals = Vec::new();
+        collect_generate_locals(module.module_items(), &block_name, &mut locals);
+        for port in &ports {
+            let node = match connections.iter().find(|(connected, _)| connected == port) {
+                Some((_, node)) => node.clone(),
+                None => Name::resolve(&format!("{}.{}", block_name, port)),
+            };
+            locals.push((port.clone(), node));
+        }
+        let mut consts = Vec::new();
+        collect_const_params(module.module_items(), &const_overrides, &mut consts);
+
+        let block = GenerateBlock {
+            name: block_name,
+            genvars: Vec::new(),
+            locals,
+            consts,
+            ast_id: ast_id.into(),
+        };
+        let block = self.generate.blocks.push_and_get_key(block);
+        let instance = InstanceCtx {
+            block,
+            connected: connections.into_iter().map(|(port, _)| port).collect(),
+            overrides,
+        };
+
+        let parent_scope = self.generate.scope.replace(block);
+        let parent_instance = self.instance.replace(instance);
+        self.elaborating.push(module_name.clone());
+
+        for port in module.module_ports().into_iter().flat_map(|ports| ports.ports()) {
+            if let ast::ModulePortKind::PortDecl(decl) = port.kind() {
+                self.lower_port_decl(decl, nodes, dst);
+            }
+        }
+        self.lower_module_items(module.module_items(), nodes, dst);
+
+        self.elaborating.pop();
+        self.instance = parent_instance;
+        self.generate.scope = parent_scope;
+        self.generate.instances.push(Instance { name, module: module_name, block, ast_id });
+    }
+
+    fn instance_error(&mut self, kind: InstanceErrorKind, ast_id: AstId<ast::ModuleInst>) {
+        // instances in generate loops are lowered once per iteration
+        let err = InstanceError { kind, ast_id };
+        if !self.generate.instance_errors.contains(&err) {
+            self.generate.instance_errors.push(err);
+        }
+    }
+
+    /// Whether `name` is a port of the module instance that is currently being lowered which
+    /// is connected to a node of the parent module.
+    fn is_connected_port(&self, name: &Name) -> bool {
+        match &self.instance {
+            Some(instance) => {
+                self.generate.scope == Some(instance.block) && instance.connected.contains(name)
+            }
+            None => false,
+        }
+    }
+
+    /// Evaluates the range of a vector net declaration.
+    fn net_range(&mut self, range: Option<ast::Range>, ast_id: ErasedAstId) -> Option<(i32, i32)> {
+        let range = range?;
+        let consts = self.consts();
+        let bounds = eval_const_expr(range.start(), &consts)
+            .and_then(|msb| Ok((msb, eval_const_expr(range.end(), &consts)?)));
+        let kind = match bounds {
+            Ok((msb, lsb))
+                if (i64::from(msb) - i64::from(lsb)).abs() < i64::from(MAX_BUS_WIDTH) =>
+            {
+                return Some((msb, lsb))
+            }
+            Ok(_) => NetRangeErrorKind::TooWide { limit: MAX_BUS_WIDTH },
+            Err(_) => NetRangeErrorKind::NotConstant,
+        };
+
+        // declarations in generate loops are lowered once per iteration
+        if self.net_range_errors.iter().all(|err| err.ast_id != ast_id) {
+            self.net_range_errors.push(NetRangeError { kind, ast_id });
+        }
+        None
+    }
+
+    /// Declares the node(s) of a single name in a net/port declaration. Vector nets declare a
+    /// separate node for every element.
+    #[allow(clippy::too_many_arguments)]
+    fn declare_nodes(
+        &mut self,
+        name: Name,
+        range: Option<(i32, i32)>,
+        is_port: bool,
+        ast_id: ErasedAstId,
+        nodes: &mut TiVec<LocalNodeId, Node>,
+        dst: &mut Vec<ModuleItem>,
+        mut decl: impl FnMut(&mut Self, Name) -> Option<NodeTypeDecl>,
+    ) {
+        let (msb, lsb) = match range {
+            Some(range) => range,
+            None => {
+                let decl = decl(self, name.clone());
+                declare_node(name, decl, is_port, ast_id, nodes, dst);
+                return;
+            }
+        };
+
+        let indices: Vec<_> =
+            if msb <= lsb { (msb..=lsb).collect() } else { (lsb..=msb).rev().collect() };
+        let mut elements = Vec::with_capacity(indices.len());
+        for idx in indices {
+            let name = bus_element(&name, idx);
+            let decl = decl(self, name.clone());
+            elements.push(declare_node(name, decl, is_port, ast_id, nodes, dst));
+        }
+
+        // nets are commonly declared twice (once with direction and once with discipline)
+        if self.buses.iter().all(|bus| bus.name != name) {
+            let bus = NodeBus { name, msb, lsb, nodes: elements, ast_id };
+            let bus = self.buses.push_and_get_key(bus);
+            dst.push(bus.into());
+        }
+    }
+
+    /// Lowers a reference to a node in a branch declaration.
+    fn node_path(&self, expr: ast::Expr) -> Option<Path> {
+        match expr {
+            ast::Expr::IndexExpr(expr) => {
+                let bus = self.local_path(Path::resolve(expr.base()?.as_path()?)?).as_ident()?;
+                let idx = eval_const_expr(expr.index(), &self.consts()).ok()?;
+                Some(Path::new_ident(bus_element(&bus, idx)))
+            }
+            expr => Some(self.local_path(Path::resolve(expr.as_path()?)?)),
+        }
+    }
+
+    /// Items declared inside a generate loop are declared with their hierarchical name.
+    fn lower_node_aliases(&mut self, behaviour: &ast::AnalogBehaviour) {
+        let ast_id = self.source_ast_id_map.ast_id(behaviour);
+        let calls = behaviour.syntax().descendants().filter_map(ast::Call::cast);
+        for (i, call) in calls.enumerate() {
+            let is_port = match call.function_ref() {
+                Some(ast::FunctionRef::SysFun(fun)) => {
+                    let name = fun.as_name();
+                    if name == sysfun::analog_node_alias {
+                        false
+                    } else if name == sysfun::analog_port_alias {
+                        true
+                    } else {
+                        continue;
+                    }
+                }
+                _ => continue,
+            };
+
+            let mut args = match call.arg_list() {
+                Some(args) => args.args(),
+                None => continue,
+            };
+            let alias = match args.next() {
+                Some(ast::Expr::PathExpr(expr)) => expr.path().and_then(Path::resolve),
+                _ => None,
+            };
+            let alias = match alias.and_then(|path| path.as_ident()) {
+                Some(alias) => self.local_name(alias),
+                None => continue,
+            };
+            let target = match args.next().and_then(|arg| arg.as_str_literal()) {
+                Some(target) => target,
+                None => continue,
+            };
+
+            // the target is a string so names inside generate blocks and instances
+            // have to be mapped manually
+            let target = match target.split_once('.') {
+                Some((head, tail)) => {
+                    format!("{}.{}", self.local_name(Name::resolve(head)), tail)
+                }
+                None => self.local_name(Name::resolve(&target)).to_string(),
+            };
+            let target = Name::resolve(&target);
+            let duplicate = self
+                .node_aliases
+                .iter()
+                .any(|it| it.alias == alias && it.target == target && it.is_port == is_port);
+            if !duplicate {
+                let call = i as u32;
+                self.node_aliases.push(NodeAlias { alias, target, is_port, ast_id, call })
+            }
+        }
+    }
+
+    fn local_name(&self, name: Name) -> Name {
+        match self.generate.scope().and_then(|scope| scope.local_name(&name)) {
+            Some(local) => local.clone(),
+            None => name,
+        }
+    }
+
+    fn local_path(&self, path: Path) -> Path {
+        match self.generate.scope() {
+            Some(scope) => scope.local_path(path),
+            None => path,
+        }
+    }
+
+    fn rename_generate_locals(&mut self, items: &[ModuleItem]) {
+        let (scope_id, scope) = match self.generate.scope {
+            Some(scope) => (scope, &self.generate.blocks[scope]),
+            None => return,
+        };
+        let overrides: &[_] = match &self.instance {
+            Some(instance) if instance.block == scope_id => instance.overrides.as_slice(),
+            _ => &[],
+        };
+        for item in items {
+            let name = match *item {
+                ModuleItem::Variable(var) => &mut self.tree.data.variables[var].name,
+                ModuleItem::Parameter(param) => {
+                    let param = &mut self.tree.data.parameters[param];
+                    param.generate = Some(scope_id);
+                    if !param.is_local {
+                        param.value = overrides
+                            .iter()
+                            .find(|(name, _)| *name == param.name)
+                            .map(|(_, value)| value.clone());
+                    }
+                    &mut param.name
+                }
+                ModuleItem::AliasParameter(param) => {
+                    &mut self.tree.data.alias_parameters[param].name
+                }
+                _ => continue,
             };
+            if let Some(local) = scope.local_name(name) {
+                *name = local.clone();
+            }
         }
     }
 
@@ -336,22 +934,23 @@ impl Ctx {
     fn lower_branch(&mut self, decl: ast::BranchDecl, dst: &mut Vec<ModuleItem>) {
         let ast_id = self.source_ast_id_map.ast_id(&decl);
         let kind = decl
-            .branch_kind()
-            .and_then(|kind| {
-                let res = match kind {
-                    ast::BranchKind::PortFlow(flow) => {
-                        BranchKind::PortFlow(Path::resolve(flow.port()?)?)
-                    }
-                    ast::BranchKind::NodeGnd(path) => BranchKind::NodeGnd(Path::resolve(path)?),
-                    ast::BranchKind::Nodes(hi, lo) => {
-                        BranchKind::Nodes(Path::resolve(hi)?, Path::resolve(lo)?)
+            .arg_list()
+            .and_then(|args| {
+                let mut args = args.args();
+                let hi = args.next()?;
+                let res = match (hi, args.next()) {
+                    (hi, Some(lo)) => BranchKind::Nodes(self.node_path(hi)?, self.node_path(lo)?),
+                    (ast::Expr::PortFlow(flow), None) => {
+                        BranchKind::PortFlow(self.local_path(Path::resolve(flow.port()?)?))
                     }
+                    (node, None) => BranchKind::NodeGnd(self.node_path(node)?),
                 };
                 Some(res)
             })
             .unwrap_or(BranchKind::Missing);
         for (name_idx, name) in decl.names().enumerate() {
-            let branch = Branch { name: name.as_name(), kind: kind.clone(), ast_id, name_idx };
+            let name = self.local_name(name.as_name());
+            let branch = Branch { name, kind: kind.clone(), ast_id, name_idx };
             let id = self.tree.data.branches.push_and_get_key(branch);
             dst.push(id.into());
         }
@@ -360,6 +959,7 @@ impl Ctx {
     fn lower_module_ports(
         &mut self,
         ports: ast::ModulePorts,
+        module: &ast::ModuleDecl,
         nodes: &mut TiVec<LocalNodeId, Node>,
         dst: &mut Vec<ModuleItem>,
     ) {
@@ -368,15 +968,26 @@ impl Ctx {
             match port.kind() {
                 ast::ModulePortKind::Name(name) => {
                     let name = name.as_name();
-                    if nodes.iter().all(|node| node.name != name) {
-                        let node = nodes.push_and_get_key(Node {
-                            name,
-                            is_port: true,
-                            ast_id: ast_id.into(),
-                            decls: Vec::new(),
-                        });
-                        dst.push(node.into())
+                    if nodes.iter().any(|node| node.name == name)
+                        || self.buses.iter().any(|bus| bus.name == name)
+                    {
+                        continue;
                     }
+
+                    // the elements of vector ports declared in the module body
+                    // must be created here to preserve the port order
+                    let range = module
+                        .module_items()
+                        .filter_map(|item| match item {
+                            ast::ModuleItem::BodyPortDecl(port) => port.port_decl(),
+                            _ => None,
+                        })
+                        .find(|decl| decl.names().any(|it| it.as_name() == name))
+                        .and_then(|decl| {
+                            let ast_id = self.source_ast_id_map.ast_id(&decl);
+                            self.net_range(decl.range(), ast_id.into())
+                        });
+                    self.declare_nodes(name, range, true, ast_id.into(), nodes, dst, |_, _| None);
                 }
                 ast::ModulePortKind::PortDecl(decl) => {
                     self.lower_port_decl(decl, nodes, dst);
@@ -395,32 +1006,43 @@ impl Ctx {
         let direction = decl.direction();
 
         let is_gnd = decl.net_type_token().map_or(false, |it| it.text() == kw::raw::ground);
+        let is_real = decl.net_type_token().map_or(false, |it| it.text() == kw_comp::raw::wreal);
+        let default_discipline = self.default_discipline(decl.syntax(), is_real);
         let ast_id = self.source_ast_id_map.ast_id(&decl);
+        let range = self.net_range(decl.range(), ast_id.into());
+        // the ports of module instances are either connected to a node of the parent
+        // or become internal nodes
+        let is_port = self.instance.is_none();
         for (name_idx, name) in decl.names().enumerate() {
             let name = name.as_name();
-            let id = self.tree.data.ports.push_and_get_key(Port {
-                name: name.clone(),
-                discipline: discipline.clone(),
-                is_input: is_input(&direction),
-                is_output: is_output(&direction),
-                ast_id,
-                name_idx,
-                is_gnd,
+            if self.is_connected_port(&name) {
+                continue;
+            }
+            let name = self.local_name(name);
+            self.declare_nodes(name, range, is_port, ast_id.into(), nodes, dst, |ctx, name| {
+                let port = ctx.tree.data.ports.push_and_get_key(Port {
+                    name,
+                    discipline: discipline.clone(),
+                    default_discipline: default_discipline.clone(),
+                    is_input: is_port && is_input(&direction),
+                    is_output: is_port && is_output(&direction),
+                    ast_id,
+                    name_idx,
+                    is_gnd,
+                    is_real,
+                });
+                Some(port.into())
             });
+        }
+    }
 
-            match nodes.iter_mut().find(|node| node.name == name) {
-                Some(node) => node.decls.push(id.into()),
-                None => {
-                    let node = nodes.push_and_get_key(Node {
-                        name,
-                        is_port: true,
-                        ast_id: ast_id.into(),
-                        decls: vec![id.into()],
-                    });
-                    dst.push(node.into())
-                }
-            }
+    /// `wreal` nets have no discipline and are therefore not affected by `` `default_discipline ``
+    fn default_discipline(&self, decl: &SyntaxNode, is_real: bool) -> Option<Name> {
+        if is_real {
+            return None;
         }
+        let discipline = self.parse.directives(decl.text_range().start()).default_discipline?;
+        Some(Name::resolve(&discipline))
     }
 
     fn lower_net_decl(
@@ -433,28 +1055,27 @@ impl Ctx {
         let ast_id = self.source_ast_id_map.ast_id(&decl);
 
         let is_gnd = decl.net_type_token().map_or(false, |it| it.text() == kw::raw::ground);
+        let is_real = decl.net_type_token().map_or(false, |it| it.text() == kw_comp::raw::wreal);
+        let default_discipline = self.default_discipline(decl.syntax(), is_real);
+        let range = self.net_range(decl.range(), ast_id.into());
         for (name_idx, name) in decl.names().enumerate() {
             let name = name.as_name();
-            let id = self.tree.data.nets.push_and_get_key(Net {
-                name: name.clone(),
-                discipline: discipline.clone(),
-                ast_id,
-                is_gnd,
-                name_idx,
-            });
-
-            match nodes.iter_mut().find(|node| node.name == name) {
-                Some(node) => node.decls.push(id.into()),
-                None => {
-                    let node = nodes.push_and_get_key(Node {
-                        name,
-                        is_port: false,
-                        ast_id: ast_id.into(),
-                        decls: vec![id.into()],
-                    });
-                    dst.push(node.into());
-                }
+            if self.is_connected_port(&name) {
+                continue;
             }
+            let name = self.local_name(name);
+            self.declare_nodes(name, range, false, ast_id.into(), nodes, dst, |ctx, name| {
+                let net = ctx.tree.data.nets.push_and_get_key(Net {
+                    name,
+                    discipline: discipline.clone(),
+                    default_discipline: default_discipline.clone(),
+                    ast_id,
+                    is_gnd,
+                    is_real,
+                    name_idx,
+                });
+                Some(net.into())
+            });
         }
     }
 
@@ -533,10 +1154,15 @@ impl Ctx {
         let ty = decl.ty().as_type();
         for var in decl.vars() {
             if let Some(name) = var.name() {
+                let (ty, array_offset) = match var.range().and_then(|range| range.array_dim()) {
+                    Some((offset, len)) => (ty.clone().to_dim(&[len]), offset),
+                    None => (ty.clone(), 0),
+                };
                 let var = Var {
                     name: name.as_name(),
                     ast_id: self.source_ast_id_map.ast_id(&var),
-                    ty: ty.clone(),
+                    ty,
+                    array_offset,
                 };
                 let id = self.tree.data.variables.push_and_get_key(var);
                 dst.push(id.into())
@@ -549,10 +1175,20 @@ impl Ctx {
         for param in decl.paras() {
             if let Some(name) = param.name() {
                 let ast_id = self.source_ast_id_map.ast_id(&param);
+                let (ty, array_offset) =
+                    match (&ty, param.range().and_then(|range| range.array_dim())) {
+                        (Some(ty), Some((offset, len))) => {
+                            (Some(ty.clone().to_dim(&[len])), offset)
+                        }
+                        _ => (ty.clone(), 0),
+                    };
                 let param = Param {
                     name: name.as_name(),
+                    array_offset,
                     is_local: decl.localparam_token().is_some(),
-                    ty: ty.clone(),
+                    ty,
+                    generate: None,
+                    value: None,
                     ast_id,
                 };
                 let id = self.tree.data.parameters.push_and_get_key(param);
@@ -571,7 +1207,7 @@ impl Ctx {
 
         if let (Some(name), Some(src)) = (name, src) {
             let src = match src {
-                ParamRef::Path(path) => Path::resolve(path),
+                ParamRef::Path(path) => Path::resolve(path).map(|path| self.local_path(path)),
                 ParamRef::SysFun(fun) => Some(Path::new_ident(fun.as_name())),
             };
             let param = AliasParam {
@@ -584,3 +1220,281 @@ impl Ctx {
         }
     }
 }
+
+/// Collects the names declared by the items of a generate loop.
+fn collect_generate_locals(
+    items: ast::AstChildren<ast::ModuleItem>,
+    prefix: &Name,
+    dst: &mut Vec<(Name, Name)>,
+) {
+    let insert = |dst: &mut Vec<(Name, Name)>, name: Option<ast::Name>| {
+        if let Some(name) = name {
+            let name = name.as_name();
+            let local = Name::resolve(&format!("{}.{}", prefix, name));
+            dst.push((name, local));
+        }
+    };
+    for item in items {
+        match item {
+            ast::ModuleItem::NetDecl(decl) => decl.names().for_each(|name| insert(dst, Some(name))),
+            ast::ModuleItem::BranchDecl(decl) => {
+                decl.names().for_each(|name| insert(dst, Some(name)))
+            }
+            ast::ModuleItem::VarDecl(decl) => decl.vars().for_each(|var| insert(dst, var.name())),
+            ast::ModuleItem::ParamDecl(decl) => {
+                decl.paras().for_each(|param| insert(dst, param.name()))
+            }
+            ast::ModuleItem::AliasParam(alias) => insert(dst, alias.name()),
+            ast::ModuleItem::GenerateRegion(region) => {
+                collect_generate_locals(region.module_items(), prefix, dst)
+            }
+            _ => (),
+        }
+    }
+}
+
+/// The names of the ports of a module in the order they are declared in the module header.
+fn module_ports(module: &ast::ModuleDecl) -> Vec<Name> {
+    let mut ports = Vec::new();
+    for port in module.module_ports().into_iter().flat_map(|ports| ports.ports()) {
+        match port.kind() {
+            ast::ModulePortKind::Name(name) => ports.push(name.as_name()),
+            ast::ModulePortKind::PortDecl(decl) => {
+                ports.extend(decl.names().map(|name| name.as_name()))
+            }
+        }
+    }
+    ports
+}
+
+/// The names of the parameters of a module that can be overwritten by a module instance in the
+/// order they are declared in.
+fn module_params(module: &ast::ModuleDecl) -> Vec<Name> {
+    fn collect(items: ast::AstChildren<ast::ModuleItem>, dst: &mut Vec<Name>) {
+        for item in items {
+            match item {
+                ast::ModuleItem::ParamDecl(decl) if decl.localparam_token().is_none() => {
+                    dst.extend(decl.paras().filter_map(|param| Some(param.name()?.as_name())))
+                }
+                ast::ModuleItem::GenerateRegion(region) => collect(region.module_items(), dst),
+                _ => (),
+            }
+        }
+    }
+    let mut params = Vec::new();
+    collect(module.module_items(), &mut params);
+    params
+}
+
+fn declare_node(
+    name: Name,
+    decl: Option<NodeTypeDecl>,
+    is_port: bool,
+    ast_id: ErasedAstId,
+    nodes: &mut TiVec<LocalNodeId, Node>,
+    dst: &mut Vec<ModuleItem>,
+) -> LocalNodeId {
+    match nodes.iter_enumerated().find(|(_, node)| node.name == name) {
+        Some((id, _)) => {
+            nodes[id].decls.extend(decl);
+            id
+        }
+        None => {
+            let node = nodes.push_and_get_key(Node {
+                name,
+                is_port,
+                ast_id,
+                decls: decl.into_iter().collect(),
+            });
+            dst.push(node.into());
+            node
+        }
+    }
+}
+
+/// The name of the node of a vector net element (`n[2]`).
+fn bus_element(bus: &Name, idx: i32) -> Name {
+    Name::resolve(&format!("{}[{}]", bus, idx))
+}
+
+/// Collects the module parameters whose value is a constant integer expression. `overrides`
+/// contains the values assigned by a module instance (`None` if the value is not constant).
+fn collect_const_params(
+    items: ast::AstChildren<ast::ModuleItem>,
+    overrides: &[(Name, Option<i32>)],
+    dst: &mut Vec<(Name, i32)>,
+) {
+    for item in items {
+        match item {
+            ast::ModuleItem::ParamDecl(decl) => {
+                if decl.ty().map_or(false, |ty| ty.integer_token().is_none()) {
+                    continue;
+                }
+                for param in decl.paras() {
+                    if param.range().is_some() {
+                        continue;
+                    }
+                    let name = match param.name() {
+                        Some(name) => name.as_name(),
+                        None => continue,
+                    };
+                    let val = match overrides.iter().find(|(param, _)| *param == name) {
+                        Some((_, val)) if decl.localparam_token().is_none() => *val,
+                        _ => eval_const_expr(param.default(), dst).ok(),
+                    };
+                    if let Some(val) = val {
+                        dst.push((name, val))
+                    }
+                }
+            }
+            ast::ModuleItem::GenerateRegion(region) => {
+                collect_const_params(region.module_items(), overrides, dst)
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Evaluates the header of a loop over a genvar and returns the genvar and the values it takes
+/// in each iteration.
+pub(crate) fn genvar_loop_iterations(
+    init: Option<ast::Stmt>,
+    cond: Option<ast::Expr>,
+    incr: Option<ast::Stmt>,
+    genvars: &[Name],
+    mut consts: Vec<(Name, i32)>,
+) -> Result<(Name, Vec<i32>), GenerateErrorKind> {
+    let (genvar, init) = genvar_assign(init, genvars)?;
+    let (incr_genvar, incr) = genvar_assign(incr, genvars)?;
+    if incr_genvar != genvar {
+        return Err(GenerateErrorKind::ExpectedGenvarAssign);
+    }
+
+    let mut val = eval_const_expr(Some(init), &consts)?;
+    consts.push((genvar.clone(), val));
+
+    let mut iterations = Vec::new();
+    loop {
+        consts.last_mut().unwrap().1 = val;
+        if eval_const_expr(cond.clone(), &consts)? == 0 {
+            break;
+        }
+        if iterations.len() as u32 == MAX_GENERATE_ITERATIONS {
+            return Err(GenerateErrorKind::IterationLimit { limit: MAX_GENERATE_ITERATIONS });
+        }
+        iterations.push(val);
+        val = eval_const_expr(Some(incr.clone()), &consts)?;
+    }
+
+    Ok((genvar, iterations))
+}
+
+/// Returns the genvar a loop initialization/increment assigns to (if any).
+pub(crate) fn genvar_assign_target(stmt: &ast::Stmt, genvars: &[Name]) -> Option<Name> {
+    let assign = match stmt {
+        ast::Stmt::AssignStmt(stmt) => stmt.assign()?,
+        _ => return None,
+    };
+    let name = match assign.lval()? {
+        ast::Expr::PathExpr(path) => path.path().and_then(Path::resolve)?.as_ident()?,
+        _ => return None,
+    };
+    if genvars.contains(&name) {
+        Some(name)
+    } else {
+        None
+    }
+}
+
+fn genvar_assign(
+    stmt: Option<ast::Stmt>,
+    genvars: &[Name],
+) -> Result<(Name, ast::Expr), GenerateErrorKind> {
+    let assign = match stmt {
+        Some(ast::Stmt::AssignStmt(stmt)) => stmt.assign(),
+        _ => None,
+    };
+    let assign = assign
+        .filter(|assign| assign.op() == Some(ast::AssignOp::Assign))
+        .ok_or(GenerateErrorKind::ExpectedGenvarAssign)?;
+    let name = match assign.lval() {
+        Some(ast::Expr::PathExpr(path)) => path.path().and_then(Path::resolve),
+        _ => None,
+    }
+    .and_then(|path| path.as_ident())
+    .ok_or(GenerateErrorKind::ExpectedGenvarAssign)?;
+
+    if !genvars.contains(&name) {
+        return Err(GenerateErrorKind::NotAGenvar { name });
+    }
+
+    let val = assign.rval().ok_or(GenerateErrorKind::NotConstant)?;
+    Ok((name, val))
+}
+
+/// Evaluates a constant integer expression that may only refer to the genvars and parameters
+/// in `consts`.
+pub(crate) fn eval_const_expr(
+    expr: Option<ast::Expr>,
+    consts: &[(Name, i32)],
+) -> Result<i32, GenerateErrorKind> {
+    let eval = |expr| eval_const_expr(expr, consts);
+    let res = match expr.ok_or(GenerateErrorKind::NotConstant)? {
+        ast::Expr::Literal(lit) => match lit.kind() {
+            ast::LiteralKind::IntNumber(num) => Some(num.value()),
+            _ => None,
+        },
+        ast::Expr::ParenExpr(expr) => Some(eval(expr.expr())?),
+        ast::Expr::PathExpr(path) => {
+            let name = path.path().and_then(Path::resolve).and_then(|path| path.as_ident());
+            name.and_then(|name| {
+                consts.iter().rev().find(|(genvar, _)| *genvar == name).map(|(_, val)| *val)
+            })
+        }
+        ast::Expr::PrefixExpr(expr) => {
+            let val = eval(expr.expr())?;
+            match expr.op_kind() {
+                Some(UnaryOp::BitNegate) => Some(!val),
+                Some(UnaryOp::Not) => Some((val == 0) as i32),
+                Some(UnaryOp::Neg) => val.checked_neg(),
+                Some(UnaryOp::Identity) => Some(val),
+                None => None,
+            }
+        }
+        ast::Expr::BinExpr(expr) => {
+            let lhs = eval(expr.lhs())?;
+            let rhs = eval(expr.rhs())?;
+            match expr.op_kind() {
+                Some(BinaryOp::BooleanOr) => Some((lhs != 0 || rhs != 0) as i32),
+                Some(BinaryOp::BooleanAnd) => Some((lhs != 0 && rhs != 0) as i32),
+                Some(BinaryOp::EqualityTest) => Some((lhs == rhs) as i32),
+                Some(BinaryOp::NegatedEqualityTest) => Some((lhs != rhs) as i32),
+                Some(BinaryOp::LesserEqualTest) => Some((lhs <= rhs) as i32),
+                Some(BinaryOp::GreaterEqualTest) => Some((lhs >= rhs) as i32),
+                Some(BinaryOp::LesserTest) => Some((lhs < rhs) as i32),
+                Some(BinaryOp::GreaterTest) => Some((lhs > rhs) as i32),
+                Some(BinaryOp::Addition) => lhs.checked_add(rhs),
+                Some(BinaryOp::Subtraction) => lhs.checked_sub(rhs),
+                Some(BinaryOp::Multiplication) => lhs.checked_mul(rhs),
+                Some(BinaryOp::Division) => lhs.checked_div(rhs),
+                Some(BinaryOp::Remainder) => lhs.checked_rem(rhs),
+                Some(BinaryOp::LeftShift) => {
+                    u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_shl(rhs))
+                }
+                Some(BinaryOp::RightShift) => {
+                    u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_shr(rhs))
+                }
+                Some(BinaryOp::BitwiseXor) => Some(lhs ^ rhs),
+                Some(BinaryOp::BitwiseEq) => Some(!(lhs ^ rhs)),
+                Some(BinaryOp::BitwiseOr) => Some(lhs | rhs),
+                Some(BinaryOp::BitwiseAnd) => Some(lhs & rhs),
+                Some(BinaryOp::Power) => {
+                    u32::try_from(rhs).ok().and_then(|rhs| lhs.checked_pow(rhs))
+                }
+                None => None,
+            }
+        }
+        _ => None,
+    };
+    res.ok_or(GenerateErrorKind::NotConstant)
+}
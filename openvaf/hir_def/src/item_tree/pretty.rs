@@ -5,7 +5,7 @@ use syntax::ast;
 
 use super::{
     BlockScopeItem, Discipline, Function, FunctionItem, ItemTreeId, Module, ModuleItem, Nature,
-    Param, Var,
+    Param, Paramset, Var,
 };
 use crate::ItemTree;
 
@@ -64,6 +64,20 @@ impl<'a> Printer<'a> {
             wln!(self, "module {}", module.name);
             self.indented(|s| s.print_module(module))
         }
+
+        for paramset in &self.tree.data.paramsets {
+            wln!(self, "paramset {} of {:?}", paramset.name, paramset.module);
+            self.indented(|s| s.print_paramset(paramset))
+        }
+    }
+
+    fn print_paramset(&mut self, paramset: &Paramset) {
+        for param in &paramset.params {
+            self.print_parameter(*param)
+        }
+        for param in &paramset.overrides {
+            wln!(self, ".{}", self.tree[*param].name);
+        }
     }
 
     fn print_nature_attrs(&mut self, nature: &Nature) {
@@ -115,6 +129,10 @@ impl<'a> Printer<'a> {
                         node.discipline(self.tree),
                     );
                 }
+                ModuleItem::NodeBus(bus) => {
+                    let bus = &module.buses[bus];
+                    wln!(self, "bus {}[{}:{}]", bus.name, bus.msb, bus.lsb);
+                }
                 ModuleItem::Function(function) => {
                     let function = &self.tree[function];
                     wln!(self, "function {}", function.name);
@@ -126,6 +144,31 @@ impl<'a> Printer<'a> {
                 }
             }
         }
+
+        for instance in &module.instances {
+            wln!(
+                self,
+                "instance {} of {}",
+                module.generate_blocks[instance.block].name,
+                instance.module
+            );
+        }
+
+        for alias in &module.node_aliases {
+            let kind = if alias.is_port { "port" } else { "node" };
+            wln!(self, "{}alias {} = {}", kind, alias.alias, alias.target);
+        }
+
+        for (id, block) in module.generate_blocks.iter_enumerated() {
+            if module.instances.iter().any(|instance| instance.block == id) {
+                continue;
+            }
+            w!(self, "generate {}", block.name);
+            for (genvar, val) in &block.genvars {
+                w!(self, " {}={}", genvar, val);
+            }
+            wln!(self);
+        }
     }
 
     fn print_function(&mut self, function: &Function) {
@@ -27,8 +27,10 @@ pub use crate::data::FunctionArg;
 use crate::db::HirDefDB;
 pub use crate::expr::{Case, Expr, ExprId, Literal, Stmt, StmtId};
 pub use crate::item_tree::{
-    AliasParam, Branch, BranchKind, Discipline, DisciplineAttr, Function, ItemTree, ItemTreeId,
-    ItemTreeNode, Module, Nature, NatureAttr, NatureRef, NatureRefKind, NodeTypeDecl, Param, Var,
+    AliasParam, Branch, BranchKind, Discipline, DisciplineAttr, Domain, Function, GenerateBlock,
+    GenerateError, GenerateErrorKind, Instance, InstanceError, InstanceErrorKind, ItemTree,
+    ItemTreeId, ItemTreeNode, Module, Nature, NatureAttr, NatureRef, NatureRefKind, NetRangeError,
+    NetRangeErrorKind, NodeBus, NodeTypeDecl, Param, ParamOverride, Paramset, RootItem, Var,
 };
 use crate::nameres::ScopeDefItem;
 pub use crate::path::Path;
@@ -219,6 +221,9 @@ impl_intern!(BlockId, BlockLoc, intern_block, lookup_intern_block);
 pub type ModuleLoc = ItemLoc<Module>;
 impl_intern!(ModuleId, ModuleLoc, intern_module, lookup_intern_module);
 
+pub type ParamsetLoc = ItemLoc<Paramset>;
+impl_intern!(ParamsetId, ParamsetLoc, intern_paramset, lookup_intern_paramset);
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct DisciplineLoc {
     pub root_file: FileId,
@@ -299,6 +304,7 @@ pub struct NodeLoc {
 }
 
 pub type LocalNodeId = Idx<Node>;
+pub type LocalNodeBusId = Idx<NodeBus>;
 
 #[derive(Clone, Copy, PartialEq, PartialOrd, Eq, Hash)]
 pub struct NodeId(salsa::InternId);
@@ -330,11 +336,36 @@ impl NodeLoc {
     pub fn discipline_ast_id(self, db: &dyn HirDefDB) -> Option<ErasedAstId> {
         let loc = self.module.lookup(db);
         let tree = loc.item_tree(db);
-        let decls = &tree[loc.id].nodes[self.id].decls;
-        decls.iter().find(|decl| decl.discipline(&tree).is_some()).map(|it| it.ast_id(&tree))
+        let node = &tree[loc.id].nodes[self.id];
+        let decl = node.decls.iter().find(|decl| decl.discipline(&tree).is_some());
+        match decl {
+            Some(decl) => Some(decl.ast_id(&tree)),
+            // the `default_discipline applies to the (first) declaration of the node
+            None => node.default_discipline(&tree).map(|_| node.ast_id),
+        }
     }
 }
 
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct NodeBusLoc {
+    pub module: ModuleId,
+    pub id: LocalNodeBusId,
+}
+
+impl NodeBusLoc {
+    pub fn ast_id(self, db: &dyn HirDefDB) -> ErasedAstId {
+        let loc = self.module.lookup(db);
+        loc.item_tree(db)[loc.id].buses[self.id].ast_id
+    }
+
+    pub fn bus(self, db: &dyn HirDefDB) -> NodeBus {
+        let loc = self.module.lookup(db);
+        loc.item_tree(db)[loc.id].buses[self.id].clone()
+    }
+}
+
+impl_intern!(NodeBusId, NodeBusLoc, intern_node_bus, lookup_intern_node_bus);
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct FunctionArgLoc {
     pub fun: FunctionId,
@@ -15,7 +15,7 @@ use crate::db::HirDefDB;
 use crate::nameres::diagnostics::PathResolveError;
 use crate::{
     AliasParamId, BlockId, BranchId, DisciplineId, FunctionArgId, FunctionId, Lookup, ModuleId,
-    NatureAttrId, NatureId, NodeId, ParamId, VarId,
+    NatureAttrId, NatureId, NodeBusId, NodeId, ParamId, ParamsetId, VarId,
 };
 
 mod collect;
@@ -74,11 +74,13 @@ impl From<NatureAttrId> for NatureAccess {
 #[derive(Debug, Hash, Clone, Copy, PartialEq, Eq)]
 pub enum ScopeDefItem {
     ModuleId(ModuleId),
+    ParamsetId(ParamsetId),
     BlockId(BlockId),
     NatureId(NatureId),
     NatureAccess(NatureAccess),
     DisciplineId(DisciplineId),
     NodeId(NodeId),
+    NodeBusId(NodeBusId),
     VarId(VarId),
     ParamId(ParamId),
     ParamSysFun(ParamSysFun),
@@ -95,6 +97,7 @@ impl ScopeDefItem {
     pub fn ast_id(&self, db: &dyn HirDefDB) -> Option<ErasedAstId> {
         let id: ErasedAstId = match self {
             ScopeDefItem::ModuleId(module) => module.lookup(db).ast_id(db).into(),
+            ScopeDefItem::ParamsetId(paramset) => paramset.lookup(db).ast_id(db).into(),
             ScopeDefItem::BlockId(block) => block.lookup(db).ast.into(),
             ScopeDefItem::NatureId(nature) => nature.lookup(db).ast_id(db).into(),
             ScopeDefItem::NatureAccess(access) => access.0.lookup(db).ast_id(db).into(),
@@ -107,6 +110,7 @@ impl ScopeDefItem {
             }
             ScopeDefItem::FunctionArgId(arg) => arg.lookup(db).ast_id(db).into(),
             ScopeDefItem::NodeId(node) => node.lookup(db).ast_id(db),
+            ScopeDefItem::NodeBusId(bus) => bus.lookup(db).ast_id(db),
             ScopeDefItem::BuiltIn(_) | ScopeDefItem::ParamSysFun(_) => return None,
             ScopeDefItem::AliasParamId(id) => id.lookup(db).ast_id(db).into(),
             ScopeDefItem::NatureAttrId(id) => id.lookup(db).ast_id(db).into(),
@@ -127,6 +131,12 @@ impl ScopeDefItem {
                 .name()?
                 .syntax()
                 .text_range(),
+            ScopeDefItem::ParamsetId(paramset) => ast_id_map
+                .get(paramset.lookup(db).ast_id(db))
+                .to_node(parse.tree().syntax())
+                .name()?
+                .syntax()
+                .text_range(),
             ScopeDefItem::BlockId(block) => ast_id_map
                 .get(block.lookup(db).ast)
                 .to_node(parse.tree().syntax())
@@ -183,6 +193,9 @@ impl ScopeDefItem {
                     .text_range()
             }
             ScopeDefItem::NodeId(node) => ast_id_map.get_syntax(node.lookup(db).ast_id(db)).range(),
+            ScopeDefItem::NodeBusId(bus) => {
+                ast_id_map.get_syntax(bus.lookup(db).ast_id(db)).range()
+            }
             ScopeDefItem::BuiltIn(_) | ScopeDefItem::ParamSysFun(_) => return None,
             ScopeDefItem::AliasParamId(id) => ast_id_map.get(id.lookup(db).ast_id(db)).range(),
             ScopeDefItem::NatureAttrId(id) => ast_id_map.get(id.lookup(db).ast_id(db)).range(),
@@ -194,11 +207,13 @@ impl ScopeDefItem {
 
 impl_from! {
     ModuleId,
+    ParamsetId,
     BlockId,
     NatureId,
     NatureAccess,
     DisciplineId,
     NodeId,
+    NodeBusId,
     VarId,
     ParamId,
     BranchId,
@@ -234,12 +249,14 @@ macro_rules! scope_item_kinds {
 
 scope_item_kinds! {
     ModuleId => "module",
+    ParamsetId => "paramset",
     BlockId => "block scope",
     NatureId => "nature",
     DisciplineId => "discipline",
     NatureAccess => "nature access function",
     NatureAttrId => "nature attribute",
     NodeId => "node",
+    NodeBusId => "vector net",
     VarId => "variable",
     ParamId => "parameter",
     ParamSysFun => "hierarchical parameter system function",
@@ -254,6 +271,7 @@ scope_item_kinds! {
 pub enum ScopeOrigin {
     Root,
     Module(ModuleId),
+    Paramset(ParamsetId),
     // Nature(NatureId),
     // Discipline(DisciplineId),
     Block(BlockId),
@@ -264,6 +282,7 @@ pub type LocalScopeId = Idx<Scope>;
 
 impl_from_typed! {
     Module(ModuleId),
+    Paramset(ParamsetId),
     // Nature(NatureId),
     // Discipline(DisciplineId),
     Block(BlockId),
@@ -277,6 +296,11 @@ static BUILTIN_SCOPE: Lazy<IndexMap<Name, ScopeDefItem, ahash::RandomState>> = L
     scope
 });
 
+/// Returns the name that `item` is declared with in the builtin scope (if any).
+pub fn builtin_name(item: ScopeDefItem) -> Option<&'static Name> {
+    BUILTIN_SCOPE.iter().find(|(_, &builtin)| builtin == item).map(|(name, _)| name)
+}
+
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub struct Scope {
     pub origin: ScopeOrigin,
@@ -315,6 +339,44 @@ impl DefMap {
         }
     }
 
+    /// Returns all names that can be referred to (without a hierarchical path) from `scope`.
+    /// The scopes are searched in the same order as [`DefMap::resolve_normal_path_in_scope`]
+    /// so a name that is shadowed by an inner scope is only returned once.
+    pub fn visible_names_in_scope(
+        &self,
+        mut scope: LocalScopeId,
+        db: &dyn HirDefDB,
+    ) -> Vec<(Name, ScopeDefItem)> {
+        let mut res: IndexMap<Name, ScopeDefItem, ahash::RandomState> = IndexMap::default();
+        let mut arc;
+        let mut current_map = self;
+
+        loop {
+            for (name, decl) in &current_map.scopes[scope].declarations {
+                res.entry(name.clone()).or_insert(*decl);
+            }
+
+            match current_map[scope].parent {
+                Some(parent) => scope = parent,
+                None => match current_map.src {
+                    DefMapSource::Block(block) => {
+                        let block = block.lookup(db);
+                        arc = block.parent.def_map(db);
+                        current_map = &*arc;
+                        scope = block.parent.local_scope;
+                    }
+                    DefMapSource::Root | DefMapSource::Function(_) => break,
+                },
+            }
+        }
+
+        for (name, builtin) in BUILTIN_SCOPE.iter() {
+            res.entry(name.clone()).or_insert(*builtin);
+        }
+
+        res.into_iter().collect()
+    }
+
     pub fn resolve_local_item_in_scope<T: ScopeDefItemKind>(
         &self,
         scope: LocalScopeId,
@@ -328,6 +390,27 @@ impl DefMap {
         })
     }
 
+    /// Resolves a parameter declared directly in `scope`. Parent scopes are not searched. This is
+    /// used to resolve the module parameters assigned by a paramset.
+    pub fn resolve_declared_param(
+        &self,
+        scope: LocalScopeId,
+        scope_name: &Name,
+        name: &Name,
+    ) -> Result<ParamId, PathResolveError> {
+        match self.scopes[scope].declarations.get(name) {
+            Some(ScopeDefItem::ParamId(param)) => Ok(*param),
+            Some(found) => Err(PathResolveError::ExpectedItemKind {
+                name: name.clone(),
+                expected: ParamId::NAME,
+                found: (*found).into(),
+            }),
+            None => {
+                Err(PathResolveError::NotFoundIn { name: name.clone(), scope: scope_name.clone() })
+            }
+        }
+    }
+
     pub fn resolve_normal_item_path_in_scope<T: ScopeDefItemKind>(
         &self,
         scope: LocalScopeId,
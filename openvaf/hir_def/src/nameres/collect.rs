@@ -6,22 +6,26 @@ use indexmap::IndexMap;
 use syntax::ast;
 use syntax::name::Name;
 
-use super::diagnostics::DefDiagnostic;
+use super::diagnostics::{DefDiagnostic, NodeAliasError};
 use super::{DefMap, DefMapSource, LocalScopeId, Scope, ScopeDefItem, ScopeOrigin};
 use crate::builtin::insert_modulle_builtin_scope;
 use crate::db::HirDefDB;
 use crate::item_tree::{
     BlockScopeItem, Function, FunctionItem, ItemTree, ItemTreeId, ItemTreeNode, Module, ModuleItem,
-    RootItem,
+    Paramset, RootItem,
 };
 use crate::{
     BlockId, BlockLoc, DisciplineLoc, FunctionArgLoc, FunctionId, FunctionLoc, Intern, ItemLoc,
-    Lookup, ModuleLoc, NatureAttrLoc, NatureLoc, NodeLoc, ScopeId,
+    Lookup, ModuleId, ModuleLoc, NatureAttrLoc, NatureLoc, NodeBusLoc, NodeId, NodeLoc,
+    ParamsetLoc, ScopeId,
 };
 
 pub fn collect_root_def_map(db: &dyn HirDefDB, root_file: FileId) -> Arc<DefMap> {
     let tree = &db.item_tree(root_file);
-    let scope_cnt = tree.data.natures.len() + tree.data.disciplines.len() + tree.data.modules.len();
+    let scope_cnt = tree.data.natures.len()
+        + tree.data.disciplines.len()
+        + tree.data.modules.len()
+        + tree.data.paramsets.len();
 
     let mut collector = DefCollector {
         map: DefMap {
@@ -225,6 +229,7 @@ impl DefCollector<'_> {
         for item in &*self.tree.top_level {
             match *item {
                 RootItem::Module(module) => self.collect_module(module, root_scope),
+                RootItem::Paramset(paramset) => self.collect_paramset(paramset, root_scope),
                 RootItem::Nature(nature) => {
                     let id = NatureLoc { root_file: self.root_file, id: nature }.intern(self.db);
                     self.insert_decl(root_scope, self.tree[nature].name.clone(), id);
@@ -247,6 +252,14 @@ impl DefCollector<'_> {
                 }
             }
         }
+
+        // paramsets may appear before the module they instantiate so they can only be checked
+        // once all root items have been collected
+        for item in &*self.tree.top_level {
+            if let RootItem::Paramset(paramset) = *item {
+                self.check_paramset(paramset)
+            }
+        }
     }
 
     fn collect_module(&mut self, item_tree: ItemTreeId<Module>, parent_scope: LocalScopeId) {
@@ -258,6 +271,22 @@ impl DefCollector<'_> {
         self.insert_scope(parent_scope, scope, module.name.clone(), module_id);
         insert_modulle_builtin_scope(&mut self.map.scopes[scope].declarations);
 
+        let diagnostics = module
+            .generate_errors
+            .iter()
+            .cloned()
+            .map(DefDiagnostic::InvalidGenerateLoop)
+            .chain(module.net_range_errors.iter().cloned().map(DefDiagnostic::InvalidNetRange))
+            .chain(
+                module.instance_errors.iter().cloned().map(DefDiagnostic::InvalidModuleInstance),
+            );
+        for diag in diagnostics {
+            // instantiated modules are also lowered on their own
+            if !self.map.diagnostics.contains(&diag) {
+                self.map.diagnostics.push(diag)
+            }
+        }
+
         for item in &module.items {
             match *item {
                 ModuleItem::Scope(ast) => self.collect_block_scope(scope, ast),
@@ -267,6 +296,11 @@ impl DefCollector<'_> {
                     module.nodes[id].name.clone(),
                     NodeLoc { module: module_id, id }.intern(self.db),
                 ),
+                ModuleItem::NodeBus(id) => self.insert_decl(
+                    scope,
+                    module.buses[id].name.clone(),
+                    NodeBusLoc { module: module_id, id }.intern(self.db),
+                ),
                 ModuleItem::Branch(id) => {
                     self.insert_item_decl(scope, self.tree[id].name.clone(), id)
                 }
@@ -284,6 +318,88 @@ impl DefCollector<'_> {
                 }
             }
         }
+
+        self.resolve_node_aliases(module, scope);
+    }
+
+    /// `$analog_node_alias`/`$analog_port_alias` turn the alias into another name for the
+    /// target node. Aliases that can not be resolved are reported and otherwise ignored (the
+    /// call evaluates to zero).
+    fn resolve_node_aliases(&mut self, module: &Module, scope: LocalScopeId) {
+        for alias in &module.node_aliases {
+            let declarations = &self.map.scopes[scope].declarations;
+            let alias_node = match declarations.get(&alias.alias) {
+                Some(&ScopeDefItem::NodeId(node)) => node,
+                // the first argument must be a node, this is checked during type inference
+                _ => continue,
+            };
+            let is_port = |node: NodeId| module.nodes[node.lookup(self.db).id].is_port;
+            let err = match declarations.get(&alias.target).cloned() {
+                Some(ScopeDefItem::NodeId(target)) if target == alias_node => continue,
+                // ports can not be aliased as they are connected to the outside
+                Some(ScopeDefItem::NodeId(_)) if is_port(alias_node) => {
+                    NodeAliasError::AliasIsPort { alias: alias.alias.clone() }
+                }
+                Some(ScopeDefItem::NodeId(target)) if alias.is_port && !is_port(target) => {
+                    NodeAliasError::TargetNotAPort { target: alias.target.clone() }
+                }
+                Some(ScopeDefItem::NodeId(target)) => {
+                    self.map.scopes[scope].declarations.insert(alias.alias.clone(), target.into());
+                    continue;
+                }
+                _ => NodeAliasError::UnknownTarget { target: alias.target.clone() },
+            };
+            self.map.diagnostics.push(DefDiagnostic::InvalidNodeAlias {
+                ast_id: alias.ast_id,
+                call: alias.call,
+                err,
+            });
+        }
+    }
+
+    fn collect_paramset(&mut self, item_tree: ItemTreeId<Paramset>, parent_scope: LocalScopeId) {
+        let paramset_id = ParamsetLoc { id: item_tree, scope: self.next_scope() }.intern(self.db);
+
+        let scope = self.new_scope(ScopeOrigin::Paramset(paramset_id), parent_scope);
+        let paramset = &self.tree[item_tree];
+
+        self.insert_scope(parent_scope, scope, paramset.name.clone(), paramset_id);
+
+        for &param in &paramset.params {
+            self.insert_item_decl(scope, self.tree[param].name.clone(), param)
+        }
+    }
+
+    fn check_paramset(&mut self, item_tree: ItemTreeId<Paramset>) {
+        let paramset = &self.tree[item_tree];
+        let module_name = match &paramset.module {
+            Some(name) => name,
+            None => return,
+        };
+
+        let module =
+            match self.map.resolve_local_item_in_scope::<ModuleId>(self.map.root(), module_name) {
+                Ok(module) => module,
+                Err(err) => {
+                    self.map.diagnostics.push(DefDiagnostic::UnresolvedParamsetModule {
+                        ast_id: paramset.ast_id,
+                        err,
+                    });
+                    return;
+                }
+            };
+
+        let module_scope = module.lookup(self.db).scope.local_scope;
+        for &param in &paramset.overrides {
+            let param = &self.tree[param];
+            if let Err(err) =
+                self.map.resolve_declared_param(module_scope, module_name, &param.name)
+            {
+                self.map
+                    .diagnostics
+                    .push(DefDiagnostic::InvalidParamsetAssignment { ast_id: param.ast_id, err });
+            }
+        }
     }
 
     fn collect_block_scope(&mut self, scope: LocalScopeId, ast: AstId<ast::BlockStmt>) {
@@ -1,13 +1,18 @@
 use std::ops::Deref;
 
 use basedb::diagnostics::{Diagnostic, Label, LabelStyle, Report};
-use basedb::{AstIdMap, BaseDB, FileId};
+use basedb::{AstId, AstIdMap, BaseDB, FileId};
 use stdx::{impl_display, pretty};
+use syntax::ast;
 use syntax::name::Name;
 use syntax::sourcemap::{FileSpan, SourceMap};
-use syntax::{Parse, SourceFile};
+use syntax::{AstNode, Parse, SourceFile};
 
 use crate::db::HirDefDB;
+use crate::{
+    GenerateError, GenerateErrorKind, InstanceError, InstanceErrorKind, NetRangeError,
+    NetRangeErrorKind,
+};
 
 use super::{ResolvedPath, ScopeDefItem};
 
@@ -45,9 +50,30 @@ impl PathResolveError {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeAliasError {
+    UnknownTarget { target: Name },
+    AliasIsPort { alias: Name },
+    TargetNotAPort { target: Name },
+}
+
+impl_display! {
+    match NodeAliasError{
+        NodeAliasError::UnknownTarget{target} => "node alias target '{}' is not a node", target;
+        NodeAliasError::AliasIsPort{alias} => "the port '{}' can not be aliased", alias;
+        NodeAliasError::TargetNotAPort{target} => "$analog_port_alias target '{}' is not a port", target;
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum DefDiagnostic {
     AlreadyDeclared { old: ScopeDefItem, new: ScopeDefItem, name: Name },
+    InvalidGenerateLoop(GenerateError),
+    InvalidNetRange(NetRangeError),
+    InvalidModuleInstance(InstanceError),
+    UnresolvedParamsetModule { ast_id: AstId<ast::ParamsetDecl>, err: PathResolveError },
+    InvalidParamsetAssignment { ast_id: AstId<ast::Param>, err: PathResolveError },
+    InvalidNodeAlias { ast_id: AstId<ast::AnalogBehaviour>, call: u32, err: NodeAliasError },
 }
 
 pub struct DefDiagnosticWrapped<'a> {
@@ -87,6 +113,183 @@ impl Diagnostic for DefDiagnosticWrapped<'_> {
                     .with_message(format!("'{}' was already declared in this scope", name))
                     .with_labels(labels)
             }
+            DefDiagnostic::InvalidGenerateLoop(GenerateError { kind, ast_id }) => {
+                let generate = self.ast_id_map.get(*ast_id).to_node(self.parse.tree().syntax());
+                let range = match (generate.for_token(), generate.r_paren_token()) {
+                    (Some(start), Some(end)) => start.text_range().cover(end.text_range()),
+                    _ => generate.syntax().text_range(),
+                };
+                let FileSpan { range, file } = self.parse.to_file_span(range, self.sm);
+
+                let (message, label) = match kind {
+                    GenerateErrorKind::ExpectedGenvarAssign => (
+                        "generate loop must assign a genvar in its initialization and increment"
+                            .to_owned(),
+                        "expected an assignment to a genvar".to_owned(),
+                    ),
+                    GenerateErrorKind::NotAGenvar { name } => (
+                        format!("'{}' is not a genvar", name),
+                        format!("help: declare '{}' with 'genvar {};'", name, name),
+                    ),
+                    GenerateErrorKind::NotConstant => (
+                        "generate loop could not be evaluated".to_owned(),
+                        "expected constant integer expressions of literals, genvars and parameters"
+                            .to_owned(),
+                    ),
+                    GenerateErrorKind::IterationLimit { limit } => (
+                        format!("generate loop exceeds {} iterations", limit),
+                        "too many iterations".to_owned(),
+                    ),
+                };
+
+                Report::error().with_message(message).with_labels(vec![Label {
+                    style: LabelStyle::Primary,
+                    file_id: file,
+                    range: range.into(),
+                    message: label,
+                }])
+            }
+            DefDiagnostic::InvalidNetRange(NetRangeError { kind, ast_id }) => {
+                let decl = self.ast_id_map.get_syntax(*ast_id).to_node(self.parse.tree().syntax());
+                let range = match decl.children().find_map(ast::Range::cast) {
+                    Some(range) => range.syntax().text_range(),
+                    None => decl.text_range(),
+                };
+                let FileSpan { range, file } = self.parse.to_file_span(range, self.sm);
+
+                let (message, label) = match kind {
+                    NetRangeErrorKind::NotConstant => (
+                        "range of vector net could not be evaluated".to_owned(),
+                        "expected constant integer expressions of literals, genvars and parameters"
+                            .to_owned(),
+                    ),
+                    NetRangeErrorKind::TooWide { limit } => (
+                        format!("vector net exceeds {} elements", limit),
+                        "too many elements".to_owned(),
+                    ),
+                };
+
+                Report::error().with_message(message).with_labels(vec![Label {
+                    style: LabelStyle::Primary,
+                    file_id: file,
+                    range: range.into(),
+                    message: label,
+                }])
+            }
+            DefDiagnostic::InvalidModuleInstance(InstanceError { kind, ast_id }) => {
+                let inst = self.ast_id_map.get(*ast_id).to_node(self.parse.tree().syntax());
+                let range = match kind {
+                    InstanceErrorKind::UnknownModule { .. }
+                    | InstanceErrorKind::RecursiveInstance { .. } => {
+                        inst.module().map(|it| it.syntax().text_range())
+                    }
+                    InstanceErrorKind::UnknownPort { .. }
+                    | InstanceErrorKind::TooManyPorts { .. }
+                    | InstanceErrorKind::InvalidConnection { .. } => {
+                        inst.inst_ports().map(|it| it.syntax().text_range())
+                    }
+                    InstanceErrorKind::UnknownParam { .. }
+                    | InstanceErrorKind::TooManyParams { .. } => {
+                        inst.inst_params().map(|it| it.syntax().text_range())
+                    }
+                };
+                let range = range.unwrap_or_else(|| inst.syntax().text_range());
+                let FileSpan { range, file } = self.parse.to_file_span(range, self.sm);
+
+                let (message, label) = match kind {
+                    InstanceErrorKind::UnknownModule { name } => {
+                        (format!("module '{}' was not found", name), "not found".to_owned())
+                    }
+                    InstanceErrorKind::RecursiveInstance { name } => (
+                        format!("module '{}' instantiates itself", name),
+                        "recursive module instance".to_owned(),
+                    ),
+                    InstanceErrorKind::UnknownPort { name } => (
+                        format!("module instance connects unknown port '{}'", name),
+                        "unknown port".to_owned(),
+                    ),
+                    InstanceErrorKind::UnknownParam { name } => (
+                        format!("module instance overwrites unknown parameter '{}'", name),
+                        "unknown parameter".to_owned(),
+                    ),
+                    InstanceErrorKind::TooManyPorts { expected } => (
+                        "module instance connects too many ports".to_owned(),
+                        format!("expected at most {} ports", expected),
+                    ),
+                    InstanceErrorKind::TooManyParams { expected } => (
+                        "module instance overwrites too many parameters".to_owned(),
+                        format!("expected at most {} parameters", expected),
+                    ),
+                    InstanceErrorKind::InvalidConnection { port } => (
+                        format!("port '{}' must be connected to a node", port),
+                        "expected a node".to_owned(),
+                    ),
+                };
+
+                Report::error().with_message(message).with_labels(vec![Label {
+                    style: LabelStyle::Primary,
+                    file_id: file,
+                    range: range.into(),
+                    message: label,
+                }])
+            }
+            DefDiagnostic::UnresolvedParamsetModule { ast_id, err } => {
+                let paramset = self.ast_id_map.get(*ast_id).to_node(self.parse.tree().syntax());
+                let range = match paramset.module() {
+                    Some(module) => module.syntax().text_range(),
+                    None => paramset.syntax().text_range(),
+                };
+                let FileSpan { range, file } = self.parse.to_file_span(range, self.sm);
+                Report::error().with_message(err.to_string()).with_labels(vec![Label {
+                    style: LabelStyle::Primary,
+                    file_id: file,
+                    range: range.into(),
+                    message: err.message(),
+                }])
+            }
+            DefDiagnostic::InvalidParamsetAssignment { ast_id, err } => {
+                let param = self.ast_id_map.get(*ast_id).to_node(self.parse.tree().syntax());
+                let range = match param.name() {
+                    Some(name) => name.syntax().text_range(),
+                    None => param.syntax().text_range(),
+                };
+                let FileSpan { range, file } = self.parse.to_file_span(range, self.sm);
+                Report::error()
+                    .with_message(format!("invalid paramset assignment: {}", err))
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: err.message(),
+                    }])
+            }
+            DefDiagnostic::InvalidNodeAlias { ast_id, call, err } => {
+                let behaviour = self.ast_id_map.get(*ast_id).to_node(self.parse.tree().syntax());
+                let call = behaviour
+                    .syntax()
+                    .descendants()
+                    .filter_map(ast::Call::cast)
+                    .nth(*call as usize)
+                    .unwrap();
+                let FileSpan { range, file } =
+                    self.parse.to_file_span(call.syntax().text_range(), self.sm);
+                let label = match err {
+                    NodeAliasError::UnknownTarget { .. } => "not found",
+                    NodeAliasError::AliasIsPort { .. } => "ports are connected to the outside",
+                    NodeAliasError::TargetNotAPort { .. } => "expected a port",
+                };
+                Report::warning()
+                    .with_message(err.to_string())
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: label.to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "info: the alias is not resolved and the call evaluates to 0".to_owned()
+                    ])
+            }
         }
     }
 }
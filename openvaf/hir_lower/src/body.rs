@@ -79,6 +79,22 @@ impl<'c1, 'c2> BodyLoweringCtx<'_, 'c1, 'c2> {
         }
         then_vals
     }
+
+    /// Like [`lower_multi_select`](Self::lower_multi_select) but for a number of values that
+    /// is only known at runtime. Both branches must produce the same number of values.
+    pub fn lower_vec_select(
+        &mut self,
+        cond: Value,
+        lower_body: impl FnMut(BodyLoweringCtx<'_, 'c1, 'c2>, bool) -> Vec<Value>,
+    ) -> Vec<Value> {
+        let ((then_bb, mut then_vals), (else_bb, else_vals)) =
+            self.lower_cond_with(cond, lower_body);
+        debug_assert_eq!(then_vals.len(), else_vals.len());
+        for (then_val, else_val) in zip(&mut then_vals, else_vals) {
+            *then_val = self.ctx.ins().phi(&[(then_bb, *then_val), (else_bb, else_val)]);
+        }
+        then_vals
+    }
 }
 
 impl LoweringCtx<'_, '_> {
@@ -86,4 +102,9 @@ impl LoweringCtx<'_, '_> {
     pub fn lower_expr_body(&mut self, body: BodyRef, i: usize) -> Value {
         BodyLoweringCtx { ctx: self, body, path: "" }.lower_expr(body.get_entry_expr(i))
     }
+
+    /// Lowers a body that evaluates to an array (one value per element)
+    pub fn lower_array_expr_body(&mut self, body: BodyRef, i: usize) -> Vec<Value> {
+        BodyLoweringCtx { ctx: self, body, path: "" }.lower_array_expr(body.get_entry_expr(i))
+    }
 }
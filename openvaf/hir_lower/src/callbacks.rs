@@ -1,10 +1,9 @@
-use hir::{Node, Parameter};
+use hir::{Node, Parameter, Type};
 use lasso::Spur;
-use mir::{FunctionSignature, Param};
-use stdx::Ieee64;
+use mir::{FunctionSignature, Ieee64, Param};
 
 use crate::fmt::{DisplayKind, FmtArg};
-use crate::LimitState;
+use crate::{DelayLine, LimitState, OperatorState, RandomSeed};
 
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
 pub enum ParamInfoKind {
@@ -15,24 +14,95 @@ pub enum ParamInfoKind {
     MaxExclusive,
 }
 
+/// The distribution of the `$dist_*`/`$rdist_*` functions
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum Distribution {
+    Uniform,
+    Normal,
+    Exponential,
+    Poisson,
+    ChiSquare,
+    T,
+    Erlang,
+}
+
+impl Distribution {
+    pub fn num_args(self) -> usize {
+        match self {
+            Distribution::Uniform | Distribution::Normal | Distribution::Erlang => 2,
+            Distribution::Exponential
+            | Distribution::Poisson
+            | Distribution::ChiSquare
+            | Distribution::T => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum RandomKind {
+    /// `$random`: returns the next value of the seed
+    Random,
+    /// `$arandom`: `global` is false if the `"instance"` type string was passed
+    Arandom { global: bool },
+    /// `$dist_*` (`real = false`) and `$rdist_*` (`real = true`). `global` is false if the
+    /// `"instance"` type string was passed to `$rdist_*`
+    Dist { dist: Distribution, real: bool, global: bool },
+}
+
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
 pub enum CallBackKind {
     Print { kind: DisplayKind, arg_tys: Box<[FmtArg]> },
     SimParam,
     SimParamOpt,
     SimParamStr,
+    // the arguments are the instance and the name of the probed quantity
+    SimProbe,
+    // same as `SimProbe` with the default value as an additional argument
+    SimProbeOpt,
+    // the argument is the plusarg to search for, text after the first `%` is ignored
+    TestPlusArgs,
+    // the arguments are the format string and the current value of the output variable,
+    // returns the value of the plusarg (or the current value if it was not found)
+    ValuePlusArgs(Type),
+    // the argument is the format string, returns whether the plusarg was found and its value
+    // could be parsed
+    ParsePlusArgs(Type),
     Derivative(Param),
     NodeDerivative(Node),
     ParamInfo(ParamInfoKind, Parameter),
     CollapseHint(Node, Option<Node>),
     LimDiscontinuity,
+    // informs the simulator that the output of the model changes abruptly at the current time
+    Discontinuity,
     Analysis,
     BuiltinLimit { name: Spur, num_args: u32 },
     StoreLimit(LimitState),
+    StoreOperatorState(OperatorState),
     TimeDerivative,
     WhiteNoise { name: Spur, idx: u32 },
     FlickerNoise { name: Spur, idx: u32 },
-    NoiseTable(Box<NoiseTable>),
+    // the arguments are the (frequency, power) pairs of the table
+    NoiseTable { name: Spur, idx: u32, log: bool, len: u32 },
+    // the arguments are the filter input followed by the coefficients
+    // of the numerator and denominator (ascending powers of s)
+    LaplaceFilter { num_len: u32, den_len: u32 },
+    // the arguments are the input, the delay and the maximum delay
+    Absdelay(DelayLine),
+    // the argument is the input of a `$table_model` dimension, returns the region of the table
+    // that contains the input: `0` below the first breakpoint, `i` from breakpoint `i - 1` up to
+    // breakpoint `i` and `len` above the last breakpoint (which still belongs to region `len - 1`)
+    TableLookup(Box<[Ieee64]>),
+    // the argument is an index into the coefficients of a `$table_model` dimension
+    TableValue(Box<[Ieee64]>),
+    // the arguments are the file name and the mode (empty for multi channel descriptors)
+    FileOpen,
+    FileClose,
+    FileFlush,
+    // the arguments are the seed followed by the parameters of the distribution
+    // (always two, unused parameters are zero)
+    Random { kind: RandomKind, seed: RandomSeed },
+    // returns the seed after the last call to the random number generator
+    ReadRandomSeed(RandomSeed),
 }
 
 impl CallBackKind {
@@ -56,6 +126,36 @@ impl CallBackKind {
                 returns: 1,
                 has_sideeffects: false,
             },
+            CallBackKind::SimProbe => FunctionSignature {
+                name: "simprobe".to_owned(),
+                params: 2,
+                returns: 1,
+                has_sideeffects: false,
+            },
+            CallBackKind::SimProbeOpt => FunctionSignature {
+                name: "simprobe_opt".to_owned(),
+                params: 3,
+                returns: 1,
+                has_sideeffects: false,
+            },
+            CallBackKind::TestPlusArgs => FunctionSignature {
+                name: "test_plusargs".to_owned(),
+                params: 1,
+                returns: 1,
+                has_sideeffects: false,
+            },
+            CallBackKind::ValuePlusArgs(ty) => FunctionSignature {
+                name: format!("value_plusargs_{}", ty),
+                params: 2,
+                returns: 1,
+                has_sideeffects: false,
+            },
+            CallBackKind::ParsePlusArgs(ty) => FunctionSignature {
+                name: format!("parse_plusargs_{}", ty),
+                params: 1,
+                returns: 1,
+                has_sideeffects: false,
+            },
             CallBackKind::Derivative(param) => FunctionSignature {
                 name: format!("ddx_{}", param),
                 params: 1,
@@ -82,7 +182,7 @@ impl CallBackKind {
             },
             CallBackKind::Print { kind, arg_tys: args } => FunctionSignature {
                 name: format!("{:?})", kind),
-                params: args.len() as u16 + 1,
+                params: args.len() as u16 + 1 + kind.is_file() as u16,
                 returns: 0,
                 has_sideeffects: true,
             },
@@ -98,12 +198,24 @@ impl CallBackKind {
                 returns: 1,
                 has_sideeffects: false,
             },
+            CallBackKind::StoreOperatorState(state) => FunctionSignature {
+                name: format!("$store[{state:?}]"),
+                params: 1,
+                returns: 0,
+                has_sideeffects: true,
+            },
             CallBackKind::LimDiscontinuity => FunctionSignature {
                 name: "$discontinuty[-1]".to_owned(),
                 params: 0,
                 returns: 0,
                 has_sideeffects: true,
             },
+            CallBackKind::Discontinuity => FunctionSignature {
+                name: "$discontinuity".to_owned(),
+                params: 0,
+                returns: 0,
+                has_sideeffects: true,
+            },
             CallBackKind::Analysis => FunctionSignature {
                 name: "analysis".to_owned(),
                 params: 1,
@@ -128,17 +240,66 @@ impl CallBackKind {
                 returns: 1,
                 has_sideeffects: false,
             },
-            CallBackKind::NoiseTable(table) => FunctionSignature {
-                name: format!(
-                    "table_noise{}({:?}, {:?})",
-                    if table.log { "lob" } else { "" },
-                    table.name,
-                    &table.vals
-                ),
+            CallBackKind::NoiseTable { name, log, len, .. } => FunctionSignature {
+                name: format!("table_noise{}({name:?})", if *log { "_log" } else { "" }),
+                params: 2 * *len as u16,
+                returns: 1,
+                has_sideeffects: false,
+            },
+            CallBackKind::LaplaceFilter { num_len, den_len } => FunctionSignature {
+                name: format!("laplace_nd[{num_len}, {den_len}]"),
+                params: (1 + num_len + den_len) as u16,
+                returns: 1,
+                has_sideeffects: false,
+            },
+            CallBackKind::Absdelay(line) => FunctionSignature {
+                name: format!("absdelay[{line:?}]"),
+                params: 3,
+                returns: 1,
+                has_sideeffects: true,
+            },
+            CallBackKind::TableLookup(breakpoints) => FunctionSignature {
+                name: format!("table_lookup[{}]", breakpoints.len()),
                 params: 1,
                 returns: 1,
                 has_sideeffects: false,
             },
+            CallBackKind::TableValue(vals) => FunctionSignature {
+                name: format!("table_value[{}]", vals.len()),
+                params: 1,
+                returns: 1,
+                has_sideeffects: false,
+            },
+            CallBackKind::FileOpen => FunctionSignature {
+                name: "$fopen".to_owned(),
+                params: 2,
+                returns: 1,
+                has_sideeffects: true,
+            },
+            CallBackKind::FileClose => FunctionSignature {
+                name: "$fclose".to_owned(),
+                params: 1,
+                returns: 0,
+                has_sideeffects: true,
+            },
+            CallBackKind::FileFlush => FunctionSignature {
+                name: "$fflush".to_owned(),
+                params: 1,
+                returns: 0,
+                has_sideeffects: true,
+            },
+            CallBackKind::Random { kind, seed } => FunctionSignature {
+                name: format!("{kind:?}[{seed:?}]"),
+                params: if matches!(kind, RandomKind::Dist { .. }) { 3 } else { 1 },
+                returns: 1,
+                has_sideeffects: true,
+            },
+            CallBackKind::ReadRandomSeed(seed) => FunctionSignature {
+                name: format!("read[{seed:?}]"),
+                params: 0,
+                returns: 1,
+                has_sideeffects: true,
+            },
         }
     }
     pub fn is_noise(&self) -> bool {
@@ -146,7 +307,7 @@ impl CallBackKind {
             self,
             CallBackKind::WhiteNoise { .. }
                 | CallBackKind::FlickerNoise { .. }
-                | CallBackKind::NoiseTable(_)
+                | CallBackKind::NoiseTable { .. }
         )
     }
 
@@ -156,10 +317,27 @@ impl CallBackKind {
             CallBackKind::SimParam
                 | CallBackKind::SimParamOpt
                 | CallBackKind::StoreLimit(_)
+                | CallBackKind::StoreOperatorState(_)
                 | CallBackKind::Analysis
                 | CallBackKind::SimParamStr
+                | CallBackKind::SimProbe
+                | CallBackKind::SimProbeOpt
+                | CallBackKind::TestPlusArgs
+                | CallBackKind::ValuePlusArgs(_)
+                | CallBackKind::ParsePlusArgs(_)
                 | CallBackKind::LimDiscontinuity
+                | CallBackKind::Discontinuity
                 | CallBackKind::BuiltinLimit { .. }
+                | CallBackKind::LaplaceFilter { .. }
+                | CallBackKind::Absdelay(_)
+                // files are stored in the instance data so all file io happens during eval
+                | CallBackKind::FileOpen
+                | CallBackKind::FileClose
+                | CallBackKind::FileFlush
+                | CallBackKind::Print { kind: DisplayKind::File | DisplayKind::FileStrobe { .. }, .. }
+                // seeds are stored in the instance data
+                | CallBackKind::Random { .. }
+                | CallBackKind::ReadRandomSeed(_)
         )
     }
 
@@ -171,30 +349,3 @@ impl CallBackKind {
         !matches!(self, CallBackKind::Print { .. })
     }
 }
-
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
-pub struct NoiseTable {
-    pub name: Spur,
-    pub log: bool,
-    pub vals: Box<[(Ieee64, Ieee64)]>,
-    idx: u32,
-}
-
-impl NoiseTable {
-    // TODO: read from disk
-    pub fn new(
-        vals: impl IntoIterator<Item = (f64, f64)>,
-        log: bool,
-        name: Spur,
-        idx: u32,
-    ) -> Self {
-        let mut vals: Vec<(Ieee64, Ieee64)> = if log {
-            vals.into_iter().map(|(f, pwr)| (f.into(), pwr.into())).collect()
-        } else {
-            vals.into_iter().map(|(f, pwr)| (f.log10().into(), pwr.into())).collect()
-        };
-        vals.sort_unstable_by(|(f1, _), (f2, _)| f1.partial_cmp(f2).unwrap());
-        vals.dedup_by_key(|(f, _)| *f);
-        Self { name, log, vals: vals.into_boxed_slice(), idx }
-    }
-}
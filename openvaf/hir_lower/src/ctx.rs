@@ -8,8 +8,8 @@ use mir_build::{FuncInstBuilder, FunctionBuilder, Place};
 use typed_indexmap::TiSet;
 
 use crate::{
-    CallBackKind, HirInterner, ImplicitEquation, ImplicitEquationKind, LimitState, ParamKind,
-    PlaceKind,
+    CallBackKind, HirInterner, ImplicitEquation, ImplicitEquationKind, LimitState, OperatorState,
+    OperatorStateKind, ParamKind, PlaceKind,
 };
 
 pub struct LoweringCtx<'a, 'c> {
@@ -76,10 +76,14 @@ impl<'a, 'c> LoweringCtx<'a, 'c> {
                 PlaceKind::FunctionReturn { .. }
                 | PlaceKind::FunctionArg { .. }
                 | PlaceKind::Param(_)
+                | PlaceKind::ParamElement { .. }
                 | PlaceKind::ParamMin(_)
                 | PlaceKind::ParamMax(_) => return place,
 
                 PlaceKind::Var(var) => self.use_param(ParamKind::HiddenState(var)),
+                PlaceKind::VarElement { var, idx } => {
+                    self.use_param(ParamKind::HiddenStateElement { var, idx })
+                }
                 PlaceKind::ImplicitResidual { .. } | PlaceKind::Contribute { .. } => F_ZERO,
                 PlaceKind::CollapseImplicitEquation(_) => TRUE,
                 PlaceKind::IsVoltageSrc(_) => FALSE,
@@ -138,8 +142,13 @@ impl<'a, 'c> LoweringCtx<'a, 'c> {
     }
 
     pub fn call(&mut self, kind: CallBackKind, args: &[Value]) -> Inst {
-        let tracked = !self.no_equations && kind.tracked();
         let func = self.dec_callback(kind);
+        self.call_declared(func, args)
+    }
+
+    /// Calls a callback that was already declared with [`LoweringCtx::dec_callback`].
+    pub fn call_declared(&mut self, func: FuncRef, args: &[Value]) -> Inst {
+        let tracked = !self.no_equations && self.intern.callbacks[func].tracked();
         let res = self.func.ins().call(func, args);
         if tracked {
             self.intern.callback_uses[func].push(res)
@@ -220,6 +229,21 @@ impl<'a, 'c> LoweringCtx<'a, 'c> {
         val
     }
 
+    /// Allocates a new slot in the state vector for an analog operator that needs to
+    /// remember a value between timesteps. Returns the slot and its value at the last
+    /// accepted timestep.
+    ///
+    /// The new value of the state *must* be stored with `store_operator_state`
+    pub fn operator_state(&mut self, kind: OperatorStateKind) -> (OperatorState, Value) {
+        let state = self.intern.operator_states.push_and_get_key(kind);
+        let prev_val = self.use_param(ParamKind::PrevOperatorState(state));
+        (state, prev_val)
+    }
+
+    pub fn store_operator_state(&mut self, state: OperatorState, val: Value) {
+        self.call(CallBackKind::StoreOperatorState(state), &[val]);
+    }
+
     pub fn implicit_eqation(&mut self, kind: ImplicitEquationKind) -> (ImplicitEquation, Value) {
         let equation = self.intern.implicit_equations.push_and_get_key(kind);
         let place = self.dec_place(PlaceKind::CollapseImplicitEquation(equation));
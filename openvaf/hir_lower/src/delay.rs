@@ -0,0 +1,85 @@
+//! Lowering of the `absdelay` analog operator.
+//!
+//! During transient analysis `absdelay(x, td, maxdelay)` is evaluated with a delay line: the
+//! [`CallBackKind::Absdelay`] callback records the input at every timestep in a history
+//! buffer and returns the linearly interpolated value at `abstime - td`. The buffer is
+//! referenced by the instance data and grows as required, so no history within `maxdelay` is
+//! ever discarded (running out of memory is a fatal error). Before the first timestep (and
+//! for `td = 0`) the input is passed through.
+//!
+//! Outside of transient analysis the delay line can not be used because the small signal
+//! response of a callback is zero. OSDI only supports small signal responses that are
+//! rational in `jω`, so the exact phase `exp(-jωtd)` can not be represented. Instead
+//! `absdelay` is replaced by the `(4, 4)` padé approximant of `exp(-s td)`, realised as a
+//! [`CallBackKind::LaplaceFilter`]. This filter passes the input through at DC (with the
+//! correct derivative) and has unit magnitude in AC and noise analysis. Its phase matches
+//! `exp(-jωtd)` closely (error below 1%) for `ω td < 4`. Because larger errors are possible,
+//! the `approximate_small_signal` lint warns about every use of `absdelay`.
+//!
+//! If `maxdelay` is omitted, changes of `td` during transient analysis are ignored (the value
+//! from the operating point is used). Otherwise `td` may change but is limited to `maxdelay`.
+
+use hir::ExprId;
+use mir::builder::InstBuilder;
+use mir::{Value, F_ONE};
+
+use crate::body::BodyLoweringCtx;
+use crate::{CallBackKind, DelayLine, OperatorStateKind, ParamKind};
+
+/// Coefficients `c_k = (2n-k)! n! / ((2n)! k! (n-k)!)` of the `(n, n)` padé approximant of
+/// `exp(-s td) ≈ Σ c_k (-s td)^k / Σ c_k (s td)^k` for `n = 4`.
+const PADE_COEFFS: [f64; 5] = [1.0, 1.0 / 2.0, 3.0 / 28.0, 1.0 / 84.0, 1.0 / 1680.0];
+
+impl BodyLoweringCtx<'_, '_, '_> {
+    pub(crate) fn lower_absdelay(&mut self, args: &[ExprId]) -> Value {
+        let input = self.lower_expr(args[0]);
+        if self.ctx.no_equations {
+            return input;
+        }
+
+        let mut delay = self.lower_expr(args[1]);
+        let transient = self.ctx.use_param(ParamKind::TransientAnalysis);
+        let max_delay = if let Some(&max_delay) = args.get(2) {
+            let max_delay = self.lower_expr(max_delay);
+            let exceeds_max = self.ctx.ins().fgt(delay, max_delay);
+            delay = self.lower_select_with(exceeds_max, |_| max_delay, |_| delay);
+            max_delay
+        } else {
+            // the delay is fixed at the operating point
+            let (state, prev_delay) = self.ctx.operator_state(OperatorStateKind::Absdelay);
+            delay = self.lower_select_with(transient, |_| prev_delay, |_| delay);
+            self.ctx.store_operator_state(state, delay);
+            delay
+        };
+
+        // both the delay line and the filter are always evaluated so that the history
+        // contains the operating point and the implicit equations of the filter are never
+        // collapsed
+        let line = DelayLine::from(self.ctx.intern.num_delay_lines);
+        self.ctx.intern.num_delay_lines += 1;
+        let delayed = self.ctx.call1(CallBackKind::Absdelay(line), &[input, delay, max_delay]);
+
+        let mut num = Vec::with_capacity(PADE_COEFFS.len());
+        let mut den = Vec::with_capacity(PADE_COEFFS.len());
+        let mut delay_pow = F_ONE;
+        for (k, coeff) in PADE_COEFFS.into_iter().enumerate() {
+            if k != 0 {
+                delay_pow = self.ctx.ins().fmul(delay_pow, delay);
+            }
+            let coeff = self.ctx.fconst(coeff);
+            let den_coeff = self.ctx.ins().fmul(coeff, delay_pow);
+            let num_coeff = if k % 2 == 0 { den_coeff } else { self.ctx.ins().fneg(den_coeff) };
+            num.push(num_coeff);
+            den.push(den_coeff);
+        }
+
+        let kind =
+            CallBackKind::LaplaceFilter { num_len: num.len() as u32, den_len: den.len() as u32 };
+        let mut call_args = vec![input];
+        call_args.extend_from_slice(&num);
+        call_args.extend_from_slice(&den);
+        let approx = self.ctx.call1(kind, &call_args);
+
+        self.lower_select_with(transient, |_| delayed, |_| approx)
+    }
+}
@@ -0,0 +1,393 @@
+//! Lowering of monitored event controls (`@(cross(..))`, `@(above(..))`, `@(timer(..))`)
+//! and of the `last_crossing` analog operator.
+//!
+//! Events are detected by comparing against values from the last accepted timestep which are
+//! kept in the state vector (just like for `transition`). Events never fire during DC analysis
+//! with the exception of `above` which fires whenever its argument is not negative.
+//!
+//! * `cross(x, dir, time_tol, expr_tol)` fires when `x` changes sign between two timesteps.
+//!   `dir` restricts the event to rising (`+1`) or falling (`-1`) crossings.
+//! * `above(x, time_tol, expr_tol)` fires when `x` crosses zero from below.
+//! * `timer(start, period, time_tol)` fires at `start` and every `period` afterwards.
+//!
+//! `$bound_step` is used to place a timestep close to the expected crossing (extrapolated
+//! linearly) or exactly at the next timer event. When an event fires during transient analysis
+//! the simulator is informed with `$discontinuity` because the body of the event usually
+//! changes the behaviour of the model abruptly.
+//!
+//! Variables assigned in the body of a monitored event must keep their value until the
+//! event fires again. These values are also saved in the state vector and restored
+//! at the event control during transient analysis.
+
+use hir::{AssignmentLhs, BuiltIn, Event, Expr, ExprId, ResolvedFun, Stmt, StmtId, Type};
+use mir::builder::InstBuilder;
+use mir::{Value, FALSE, F_N_ONE, F_ONE, F_ZERO, INFINITY, TRUE, ZERO};
+
+use crate::body::BodyLoweringCtx;
+use crate::{OperatorStateKind, ParamKind, PlaceKind};
+
+/// The sign change of a value between the last accepted and the current timestep
+struct Crossing {
+    prev_val: Value,
+    prev_time: Value,
+    time: Value,
+    rising: Value,
+    falling: Value,
+}
+
+impl BodyLoweringCtx<'_, '_, '_> {
+    pub(crate) fn lower_event_control(&mut self, event: &Event, body: StmtId) {
+        let event = match *event {
+            Event::Monitored(event) => event,
+            // TODO handle global events properly
+            _ => {
+                self.lower_stmt(body);
+                return;
+            }
+        };
+
+        let fired = self.lower_event(event);
+        if self.ctx.no_equations {
+            self.lower_cond_with(fired, |mut ctx, fired| {
+                if fired {
+                    ctx.lower_stmt(body)
+                }
+            });
+            return;
+        }
+
+        let mut held_vars = Vec::new();
+        self.collect_held_vars(body, &mut held_vars);
+        let held_vars: Vec<_> = held_vars
+            .into_iter()
+            .map(|(place, is_integer)| {
+                let (state, prev_val) = self.ctx.operator_state(OperatorStateKind::HeldVariable);
+                (place, is_integer, state, prev_val)
+            })
+            .collect();
+
+        // during transient analysis variables keep the value from the last event
+        let enable_integration = self.ctx.use_param(ParamKind::EnableIntegration);
+        self.lower_cond_with(enable_integration, |ctx, transient| {
+            if transient {
+                for &(place, is_integer, _, mut val) in &held_vars {
+                    if is_integer {
+                        val = ctx.ctx.ins().ficast(val);
+                    }
+                    ctx.ctx.def_place(place, val);
+                }
+            }
+        });
+
+        self.lower_cond_with(fired, |mut ctx, fired| {
+            if fired {
+                ctx.lower_cond_with(enable_integration, |mut ctx, transient| {
+                    if transient {
+                        ctx.discontinuity()
+                    }
+                });
+                ctx.lower_stmt(body)
+            }
+        });
+
+        for (place, is_integer, state, _) in held_vars {
+            let mut val = self.ctx.use_place(place);
+            if is_integer {
+                val = self.ctx.ins().ifcast(val);
+            }
+            self.ctx.store_operator_state(state, val);
+        }
+    }
+
+    /// Lowers an event function and returns whether the event fired
+    fn lower_event(&mut self, event: ExprId) -> Value {
+        match self.body.get_expr(event) {
+            Expr::Call { fun: ResolvedFun::BuiltIn(BuiltIn::cross), args } => {
+                self.lower_cross(args)
+            }
+            Expr::Call { fun: ResolvedFun::BuiltIn(BuiltIn::above), args } => {
+                self.lower_above(args)
+            }
+            Expr::Call { fun: ResolvedFun::BuiltIn(BuiltIn::timer), args } => {
+                self.lower_timer(args)
+            }
+            expr => unreachable!("invalid event {expr:?}"),
+        }
+    }
+
+    fn lower_cross(&mut self, args: &[ExprId]) -> Value {
+        let val = self.lower_expr(args[0]);
+        let dir = args.get(1).map(|&arg| self.lower_expr(arg));
+        let time_tol = args.get(2).map(|&arg| self.lower_expr(arg));
+        let expr_tol = args.get(3).map(|&arg| self.lower_expr(arg));
+        if self.ctx.no_equations {
+            return FALSE;
+        }
+
+        let crossing = self.zero_crossing(val, OperatorStateKind::Cross);
+        self.bound_crossing_step(&crossing, val, time_tol, expr_tol);
+        self.crossing_in_direction(&crossing, dir)
+    }
+
+    fn lower_above(&mut self, args: &[ExprId]) -> Value {
+        let val = self.lower_expr(args[0]);
+        let time_tol = args.get(1).map(|&arg| self.lower_expr(arg));
+        let expr_tol = args.get(2).map(|&arg| self.lower_expr(arg));
+        if self.ctx.no_equations {
+            return self.ctx.ins().fge(val, F_ZERO);
+        }
+
+        let crossing = self.zero_crossing(val, OperatorStateKind::Above);
+        self.bound_crossing_step(&crossing, val, time_tol, expr_tol);
+        let enable_integration = self.ctx.use_param(ParamKind::EnableIntegration);
+        self.lower_select_with(
+            enable_integration,
+            |_| crossing.rising,
+            |ctx| ctx.ctx.ins().fge(val, F_ZERO),
+        )
+    }
+
+    fn lower_timer(&mut self, args: &[ExprId]) -> Value {
+        let start = self.lower_expr(args[0]);
+        let period = args.get(1).map(|&arg| self.lower_expr(arg));
+        let time_tol = args.get(2).map_or(F_ZERO, |&arg| self.lower_expr(arg));
+        if self.ctx.no_equations {
+            return FALSE;
+        }
+
+        let (next_state, prev_next) = self.ctx.operator_state(OperatorStateKind::Timer);
+        let time = self.ctx.use_param(ParamKind::Abstime);
+        let enable_integration = self.ctx.use_param(ParamKind::EnableIntegration);
+        let [fired, next] = self.lower_multi_select(enable_integration, |mut ctx, transient| {
+            if !transient {
+                return [FALSE, start];
+            }
+
+            let event_time = ctx.ctx.ins().fsub(prev_next, time_tol);
+            let fired = ctx.ctx.ins().fge(time, event_time);
+            let next = ctx.lower_select_with(
+                fired,
+                |mut ctx| match period {
+                    // skip all periods that have already passed
+                    Some(period) => {
+                        let elapsed = ctx.ctx.ins().fsub(time, prev_next);
+                        let periods = ctx.ctx.ins().fdiv(elapsed, period);
+                        let periods = ctx.ctx.ins().floor(periods);
+                        // time_tol may cause the event to fire slightly early
+                        let early = ctx.ctx.ins().flt(periods, F_ZERO);
+                        let periods = ctx.lower_select_with(early, |_| F_ZERO, |_| periods);
+                        let periods = ctx.ctx.ins().fadd(periods, F_ONE);
+                        let offset = ctx.ctx.ins().fmul(periods, period);
+                        ctx.ctx.ins().fadd(prev_next, offset)
+                    }
+                    None => INFINITY,
+                },
+                |_| prev_next,
+            );
+            let step = ctx.ctx.ins().fsub(next, time);
+            ctx.bound_step(step);
+            [fired, next]
+        });
+
+        self.ctx.store_operator_state(next_state, next);
+        fired
+    }
+
+    pub(crate) fn lower_last_crossing(&mut self, args: &[ExprId]) -> Value {
+        let val = self.lower_expr(args[0]);
+        let dir = args.get(1).map(|&arg| self.lower_expr(arg));
+        if self.ctx.no_equations {
+            return F_N_ONE;
+        }
+
+        let (last_state, prev_last) = self.ctx.operator_state(OperatorStateKind::LastCrossing);
+        let crossing = self.zero_crossing(val, OperatorStateKind::LastCrossing);
+        let crossed = self.crossing_in_direction(&crossing, dir);
+        let enable_integration = self.ctx.use_param(ParamKind::EnableIntegration);
+        let res = self.lower_select_with(
+            enable_integration,
+            |mut ctx| {
+                ctx.lower_select_with(
+                    crossed,
+                    |ctx| {
+                        // linear interpolation between the two timesteps
+                        let prev_val = crossing.prev_val;
+                        let timestep = ctx.ctx.ins().fsub(crossing.time, crossing.prev_time);
+                        let delta = ctx.ctx.ins().fsub(prev_val, val);
+                        let fraction = ctx.ctx.ins().fdiv(prev_val, delta);
+                        let offset = ctx.ctx.ins().fmul(timestep, fraction);
+                        ctx.ctx.ins().fadd(crossing.prev_time, offset)
+                    },
+                    |_| prev_last,
+                )
+            },
+            // negative before the first crossing
+            |_| F_N_ONE,
+        );
+        self.ctx.store_operator_state(last_state, res);
+        res
+    }
+
+    /// Detects whether `val` changed its sign since the last accepted timestep.
+    /// Crossings are only detected during transient analysis.
+    fn zero_crossing(&mut self, val: Value, kind: OperatorStateKind) -> Crossing {
+        let (val_state, prev_val) = self.ctx.operator_state(kind);
+        let (time_state, prev_time) = self.ctx.operator_state(kind);
+        let time = self.ctx.use_param(ParamKind::Abstime);
+        self.ctx.store_operator_state(val_state, val);
+        self.ctx.store_operator_state(time_state, time);
+
+        let enable_integration = self.ctx.use_param(ParamKind::EnableIntegration);
+        let [rising, falling] =
+            self.lower_multi_select(enable_integration, |mut ctx, transient| {
+                if !transient {
+                    return [FALSE, FALSE];
+                }
+                let was_neg = ctx.ctx.ins().flt(prev_val, F_ZERO);
+                let rising =
+                    ctx.lower_select_with(was_neg, |ctx| ctx.ctx.ins().fge(val, F_ZERO), |_| FALSE);
+                let was_pos = ctx.ctx.ins().fgt(prev_val, F_ZERO);
+                let falling =
+                    ctx.lower_select_with(was_pos, |ctx| ctx.ctx.ins().fle(val, F_ZERO), |_| FALSE);
+                [rising, falling]
+            });
+
+        Crossing { prev_val, prev_time, time, rising, falling }
+    }
+
+    /// Selects the crossings matching `dir`: rising (`dir > 0`), falling (`dir < 0`) or both
+    fn crossing_in_direction(&mut self, crossing: &Crossing, dir: Option<Value>) -> Value {
+        let (rising, falling) = (crossing.rising, crossing.falling);
+        let any = self.lower_select_with(rising, |_| TRUE, |_| falling);
+        let dir = match dir {
+            Some(dir) => dir,
+            None => return any,
+        };
+
+        let is_rising = self.ctx.ins().igt(dir, ZERO);
+        self.lower_select_with(
+            is_rising,
+            |_| rising,
+            |mut ctx| {
+                let is_falling = ctx.ctx.ins().ilt(dir, ZERO);
+                ctx.lower_select_with(is_falling, |_| falling, |_| any)
+            },
+        )
+    }
+
+    /// Bounds the timestep to the (linearly extrapolated) time at which `val` reaches zero,
+    /// but never below `time_tol`. No bound is placed once `|val|` is within `expr_tol`.
+    fn bound_crossing_step(
+        &mut self,
+        crossing: &Crossing,
+        val: Value,
+        time_tol: Option<Value>,
+        expr_tol: Option<Value>,
+    ) {
+        let timestep = self.ctx.ins().fsub(crossing.time, crossing.prev_time);
+        let delta = self.ctx.ins().fsub(val, crossing.prev_val);
+
+        // val approaches zero if it moves towards zero
+        let enable_integration = self.ctx.use_param(ParamKind::EnableIntegration);
+        let mut approaching = self.lower_select_with(
+            enable_integration,
+            |mut ctx| {
+                let moved = ctx.ctx.ins().fgt(timestep, F_ZERO);
+                ctx.lower_select_with(
+                    moved,
+                    |ctx| {
+                        let dir = ctx.ctx.ins().fmul(val, delta);
+                        ctx.ctx.ins().flt(dir, F_ZERO)
+                    },
+                    |_| FALSE,
+                )
+            },
+            |_| FALSE,
+        );
+
+        if let Some(expr_tol) = expr_tol {
+            approaching = self.lower_select_with(
+                approaching,
+                |mut ctx| {
+                    let above = ctx.ctx.ins().fgt(val, expr_tol);
+                    ctx.lower_select_with(
+                        above,
+                        |_| TRUE,
+                        |ctx| {
+                            let neg_tol = ctx.ctx.ins().fneg(expr_tol);
+                            ctx.ctx.ins().flt(val, neg_tol)
+                        },
+                    )
+                },
+                |_| FALSE,
+            )
+        }
+
+        self.lower_cond_with(approaching, |mut ctx, approaching| {
+            if !approaching {
+                return;
+            }
+            let step = ctx.ctx.ins().fmul(val, timestep);
+            let step = ctx.ctx.ins().fdiv(step, delta);
+            let mut step = ctx.ctx.ins().fneg(step);
+            if let Some(time_tol) = time_tol {
+                let below_tol = ctx.ctx.ins().flt(step, time_tol);
+                step = ctx.lower_select_with(below_tol, |_| time_tol, |_| step);
+            }
+            ctx.bound_step(step)
+        });
+    }
+
+    /// Collects all (real and integer) variables that are assigned within `stmt`
+    /// and whether they are integers. Arrays are collected element-wise.
+    fn collect_held_vars(&self, stmt: StmtId, dst: &mut Vec<(PlaceKind, bool)>) {
+        let stmt = if let Some(stmt) = self.body.get_stmt(stmt) { stmt } else { return };
+        match stmt {
+            Stmt::Assignment {
+                lhs: AssignmentLhs::Variable(var) | AssignmentLhs::ArrayElement { var, .. },
+                ..
+            } => {
+                let places: Vec<_> = match var.ty(self.ctx.db) {
+                    ty @ (Type::Real | Type::Integer) => {
+                        vec![(PlaceKind::Var(var), ty == Type::Integer)]
+                    }
+                    Type::Array { ty, len } if matches!(*ty, Type::Real | Type::Integer) => {
+                        let is_integer = *ty == Type::Integer;
+                        (0..len)
+                            .map(|idx| (PlaceKind::VarElement { var, idx }, is_integer))
+                            .collect()
+                    }
+                    _ => Vec::new(),
+                };
+                for place in places {
+                    if !dst.contains(&place) {
+                        dst.push(place)
+                    }
+                }
+            }
+            Stmt::Block { body } => {
+                for &stmt in body {
+                    self.collect_held_vars(stmt, dst)
+                }
+            }
+            Stmt::If { then_branch, else_branch, .. } => {
+                self.collect_held_vars(then_branch, dst);
+                self.collect_held_vars(else_branch, dst);
+            }
+            Stmt::ForLoop { init, incr, body, .. } => {
+                self.collect_held_vars(init, dst);
+                self.collect_held_vars(incr, dst);
+                self.collect_held_vars(body, dst);
+            }
+            Stmt::WhileLoop { body, .. } | Stmt::EventControl { body, .. } => {
+                self.collect_held_vars(body, dst)
+            }
+            Stmt::Case { case_arms, .. } => {
+                for arm in case_arms {
+                    self.collect_held_vars(arm.body, dst)
+                }
+            }
+            Stmt::Expr(_) | Stmt::Contribute { .. } | Stmt::Assignment { .. } => (),
+        }
+    }
+}
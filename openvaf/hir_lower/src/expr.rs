@@ -1,5 +1,6 @@
 use hir::builtin::{
-    FLICKER_NOISE_NAME, NOISE_TABLE_FILE_NAME, NOISE_TABLE_INLINE_NAME, WHITE_NOISE_NAME,
+    FLICKER_NOISE_NAME, NOISE_TABLE_FILE_NAME, NOISE_TABLE_INLINE, NOISE_TABLE_INLINE_NAME,
+    WHITE_NOISE_NAME,
 };
 use hir::signatures::{
     ABS_INT, ABS_REAL, BOOL_EQ, DDX_POT, IDTMOD_IC, IDTMOD_IC_MODULUS, IDTMOD_IC_MODULUS_OFFSET,
@@ -7,20 +8,19 @@ use hir::signatures::{
     IDT_IC_ASSERT, IDT_IC_ASSERT_NATURE, IDT_IC_ASSERT_TOL, IDT_NO_IC, INT_EQ, INT_OP,
     LIMIT_BUILTIN_FUNCTION, MAX_INT, MAX_REAL, NATURE_ACCESS_BRANCH, NATURE_ACCESS_NODES,
     NATURE_ACCESS_NODE_GND, NATURE_ACCESS_PORT_FLOW, REAL_EQ, REAL_OP, SIMPARAM_DEFAULT,
-    SIMPARAM_NO_DEFAULT, STR_EQ,
+    SIMPARAM_NO_DEFAULT, SIMPROBE_DEFAULT, SIMPROBE_NO_DEFAULT, STR_EQ, VALUE_PLUSARGS_INT,
+    VALUE_PLUSARGS_REAL, VALUE_PLUSARGS_STR,
 };
 use hir::{Body, BuiltIn, Expr, ExprId, Literal, ParamSysFun, Ref, ResolvedFun, Type};
 use mir::builder::InstBuilder;
-use mir::{Opcode, Value, FALSE, F_ZERO, GRAVESTONE, INFINITY, TRUE, ZERO};
+use mir::{Opcode, Value, FALSE, F_ZERO, GRAVESTONE, INFINITY, ONE, TRUE, ZERO};
 use mir_build::RetBuilder;
 use stdx::iter::zip;
 use syntax::ast::{BinaryOp, UnaryOp};
 
 use crate::body::BodyLoweringCtx;
 use crate::fmt::DisplayKind;
-use crate::{
-    CallBackKind, CurrentKind, IdtKind, ImplicitEquationKind, NoiseTable, ParamKind, PlaceKind,
-};
+use crate::{CallBackKind, CurrentKind, IdtKind, ImplicitEquationKind, ParamKind, PlaceKind};
 
 impl BodyLoweringCtx<'_, '_, '_> {
     pub fn lower_expr(&mut self, expr: ExprId) -> Value {
@@ -38,6 +38,9 @@ impl BodyLoweringCtx<'_, '_, '_> {
             }
             Expr::Read(Ref::FunctionArg(fun)) => self.ctx.use_place(PlaceKind::FunctionArg(fun)),
             Expr::Read(Ref::NatureAttr(attr)) => self.lower_body(attr.value(self.ctx.db), 0),
+            Expr::Read(Ref::RealNet(node)) => {
+                self.ctx.use_param(ParamKind::Voltage { hi: node, lo: None })
+            }
             Expr::BinaryOp { lhs, rhs, op } => self.lower_bin_op(expr, lhs, rhs, op),
             Expr::UnaryOp { expr: arg, op } => self.lower_unary_op(expr, arg, op),
             Expr::Select { cond, then_val, else_val } => {
@@ -53,7 +56,8 @@ impl BodyLoweringCtx<'_, '_, '_> {
                 ResolvedFun::User { func, limit } => self.lower_user_fun(func, limit, args),
                 ResolvedFun::BuiltIn(builtin) => self.lower_builtin(expr, builtin, args),
             },
-            Expr::Array(vals) => self.lower_array(expr, vals),
+            Expr::Array(_) => unreachable!("arrays are lowered element-wise"),
+            Expr::Index { base, index } => self.lower_index(expr, base, index),
             Expr::Literal(lit) => match *lit {
                 Literal::String(ref str) => self.ctx.sconst(str),
                 Literal::Int(val) => self.ctx.iconst(val),
@@ -101,9 +105,115 @@ impl BodyLoweringCtx<'_, '_, '_> {
         }
     }
 
-    fn lower_array(&mut self, _expr: ExprId, _args: &[ExprId]) -> Value {
-        todo!("arrays")
+    /// Lowers an array valued expression. Arrays are scalarized during lowering
+    /// so this returns a separate value for each element.
+    pub(crate) fn lower_array_expr(&mut self, expr: ExprId) -> Vec<Value> {
+        let len = array_len(&self.body.expr_type(expr));
+        let mut vals: Vec<Value> = match self.body.get_expr(expr) {
+            Expr::Array(args) => args.iter().map(|arg| self.lower_expr(*arg)).collect(),
+            Expr::Read(Ref::Variable(var)) => {
+                (0..len).map(|idx| self.ctx.use_place(PlaceKind::VarElement { var, idx })).collect()
+            }
+            Expr::Read(Ref::Parameter(param)) => (0..len)
+                .map(|idx| self.ctx.use_param(ParamKind::ParamElement { param, idx }))
+                .collect(),
+            Expr::Select { cond, then_val, else_val } => {
+                let cond = self.lower_expr(cond);
+                let ((then_bb, then_vals), (else_bb, else_vals)) = self
+                    .lower_cond_with(cond, |mut ctx, then| {
+                        ctx.lower_array_expr(if then { then_val } else { else_val })
+                    });
+                zip(then_vals, else_vals)
+                    .map(|(then_val, else_val)| {
+                        self.ctx.ins().phi(&[(then_bb, then_val), (else_bb, else_val)])
+                    })
+                    .collect()
+            }
+            expr => unreachable!("invalid array expression {expr:?}"),
+        };
+
+        if let Some((Type::Array { ty: src, .. }, Type::Array { ty: dst, .. })) =
+            self.body.needs_cast(expr)
+        {
+            for val in &mut vals {
+                *val = self.ctx.insert_cast(*val, &src, dst);
+            }
+        }
+        vals
+    }
+
+    fn lower_index(&mut self, expr: ExprId, base: ExprId, index: ExprId) -> Value {
+        let len = array_len(&self.body.expr_type(base));
+        let offset = match self.body.get_expr(base) {
+            Expr::Read(Ref::Variable(var)) => var.array_offset(self.ctx.db),
+            Expr::Read(Ref::Parameter(param)) => param.array_offset(self.ctx.db),
+            _ => 0,
+        };
+
+        // constant indices (the common case) directly access the element
+        if let Some(&Literal::Int(idx)) = self.body.as_literal(index) {
+            if let Some(idx) = idx.checked_sub(offset).filter(|idx| (0..len as i32).contains(idx)) {
+                let idx = idx as u32;
+                match self.body.get_expr(base) {
+                    Expr::Read(Ref::Variable(var)) => {
+                        return self.ctx.use_place(PlaceKind::VarElement { var, idx })
+                    }
+                    Expr::Read(Ref::Parameter(param)) => {
+                        return self.ctx.use_param(ParamKind::ParamElement { param, idx })
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        // dynamic indices are lowered to a chain of selects,
+        // out of bounds accesses abort the evaluation with a fatal error
+        let vals = self.lower_array_expr(base);
+        let index = self.lower_expr(index);
+        self.check_index(index, offset, len);
+        let mut res = match self.body.expr_type(expr) {
+            Type::Real => F_ZERO,
+            Type::Integer => ZERO,
+            Type::Bool => FALSE,
+            Type::String => self.ctx.sconst(""),
+            ty => unreachable!("invalid array element type {ty}"),
+        };
+        for (idx, val) in vals.into_iter().enumerate().rev() {
+            let idx = self.ctx.iconst(offset + idx as i32);
+            let is_idx = self.ctx.ins().ieq(index, idx);
+            let else_val = res;
+            res = self.ctx.make_select(is_idx, |_, then| if then { val } else { else_val });
+        }
+        res
+    }
+
+    /// Reports a fatal error and stops the evaluation if `index` is not a valid index
+    /// into an array of length `len` whose first element has the index `offset`.
+    pub(crate) fn check_index(&mut self, index: Value, offset: i32, len: u32) {
+        let lo = self.ctx.iconst(offset);
+        let below = self.ctx.ins().ilt(index, lo);
+        let hi = self.ctx.iconst(offset + len as i32);
+        let out_of_bounds =
+            self.ctx
+                .make_select(below, |ctx, then| if then { TRUE } else { ctx.ins().ige(index, hi) });
+        self.ctx.make_cond(out_of_bounds, |ctx, then| {
+            if then {
+                let msg = format!("array index %d is out of bounds for an array of length {len}\n");
+                let fmt = ctx.sconst(&msg);
+                let kind = CallBackKind::Print {
+                    kind: DisplayKind::Fatal,
+                    arg_tys: vec![Type::Integer.into()].into_boxed_slice(),
+                };
+                ctx.call(kind, &[fmt, index]);
+                ctx.ins().ret();
+
+                let unreachable_bb = ctx.create_block();
+                ctx.switch_to_block(unreachable_bb);
+                ctx.seal_block(unreachable_bb);
+            }
+        });
     }
+
     fn lower_bin_op(&mut self, expr: ExprId, lhs: ExprId, rhs: ExprId, op: BinaryOp) -> Value {
         let signature = self.body.get_call_signature(expr);
         let op = match op {
@@ -443,6 +553,41 @@ impl BodyLoweringCtx<'_, '_, '_> {
                 self.ctx.seal_block(unreachable_bb);
                 GRAVESTONE
             }
+            BuiltIn::fwrite => {
+                self.ins_display(DisplayKind::File, false, args);
+                GRAVESTONE
+            }
+            BuiltIn::fdisplay | BuiltIn::fdebug => {
+                self.ins_display(DisplayKind::File, true, args);
+                GRAVESTONE
+            }
+            BuiltIn::fstrobe | BuiltIn::fmonitor => {
+                let slot = self.ctx.intern.num_strobes;
+                self.ctx.intern.num_strobes += 1;
+                let kind = DisplayKind::FileStrobe { slot, monitor: builtin == BuiltIn::fmonitor };
+                self.ins_display(kind, true, args);
+                GRAVESTONE
+            }
+            BuiltIn::fopen => {
+                let name = self.lower_expr(args[0]);
+                // without a mode a multi channel descriptor is opened
+                let mode = match args.get(1) {
+                    Some(&mode) => self.lower_expr(mode),
+                    None => self.ctx.sconst(""),
+                };
+                self.ctx.call1(CallBackKind::FileOpen, &[name, mode])
+            }
+            BuiltIn::fclose => {
+                let fd = self.lower_expr(args[0]);
+                self.ctx.call(CallBackKind::FileClose, &[fd]);
+                ZERO
+            }
+            BuiltIn::fflush => {
+                // a descriptor of zero flushes all files
+                let fd = args.first().map_or(ZERO, |&arg| self.lower_expr(arg));
+                self.ctx.call(CallBackKind::FileFlush, &[fd]);
+                ZERO
+            }
             BuiltIn::analysis => {
                 let arg = self.lower_expr(args[0]);
                 self.ctx.call1(CallBackKind::Analysis, &[arg])
@@ -502,8 +647,19 @@ impl BodyLoweringCtx<'_, '_, '_> {
                     self.ctx.func.interner.get_or_intern(name)
                 };
                 let log = builtin == BuiltIn::noise_table_log;
-                let noise_table = NoiseTable::new([(0.0, 0.0)], log, name, idx);
-                self.ctx.call1(CallBackKind::NoiseTable(Box::new(noise_table)), &[])
+                let vals = if matches!(signature, NOISE_TABLE_INLINE | NOISE_TABLE_INLINE_NAME) {
+                    self.lower_array_expr(args[0])
+                } else {
+                    let table = self.body.noise_table_file(self.ctx.db, args[0]);
+                    table
+                        .iter()
+                        .flat_map(|&(freq, pwr)| [self.ctx.fconst(freq), self.ctx.fconst(pwr)])
+                        .collect()
+                };
+                // the table consists of (frequency, power) pairs (checked during validation)
+                let len = vals.len() / 2;
+                let kind = CallBackKind::NoiseTable { name, idx, log, len: len as u32 };
+                self.ctx.call1(kind, &vals)
             }
 
             BuiltIn::abstime => self.ctx.use_param(ParamKind::Abstime),
@@ -516,6 +672,15 @@ impl BodyLoweringCtx<'_, '_, '_> {
                 self.ctx.call1(CallBackKind::TimeDerivative, &[arg])
             }
 
+            BuiltIn::laplace_nd
+            | BuiltIn::laplace_np
+            | BuiltIn::laplace_zd
+            | BuiltIn::laplace_zp
+            | BuiltIn::zi_nd
+            | BuiltIn::zi_np
+            | BuiltIn::zi_zd
+            | BuiltIn::zi_zp => self.lower_filter(builtin, args),
+
             BuiltIn::idt | BuiltIn::idtmod if self.ctx.no_equations => {
                 match signature {
                     IDT_NO_IC => F_ZERO, // fair enough approximation
@@ -618,6 +783,43 @@ impl BodyLoweringCtx<'_, '_, '_> {
                 let arg0 = self.lower_expr(args[0]);
                 self.ctx.call1(CallBackKind::SimParamStr, &[arg0])
             }
+            BuiltIn::simprobe => {
+                let inst = self.lower_expr(args[0]);
+                let name = self.lower_expr(args[1]);
+                match_signature! {signature:
+                    SIMPROBE_NO_DEFAULT => self.ctx.call1(CallBackKind::SimProbe, &[inst, name]),
+                    SIMPROBE_DEFAULT => {
+                        let default = self.lower_expr(args[2]);
+                        self.ctx.call1(CallBackKind::SimProbeOpt, &[inst, name, default])
+                    }
+                }
+            }
+            BuiltIn::test_plusargs => {
+                let arg0 = self.lower_expr(args[0]);
+                self.ctx.call1(CallBackKind::TestPlusArgs, &[arg0])
+            }
+            BuiltIn::value_plusargs => {
+                let ty = match_signature! {signature:
+                    VALUE_PLUSARGS_INT => Type::Integer,
+                    VALUE_PLUSARGS_REAL => Type::Real,
+                    VALUE_PLUSARGS_STR => Type::String
+                };
+                let fmt = self.lower_expr(args[0]);
+                let old_val = self.lower_expr(args[1]);
+                // the variable keeps its value if the plusarg was not found
+                let val = self.ctx.call1(CallBackKind::ValuePlusArgs(ty.clone()), &[fmt, old_val]);
+                let dst = self.body.get_expr(args[1]).as_assignment_lhs();
+                self.ctx.def_place(dst.into(), val);
+                self.ctx.call1(CallBackKind::ParsePlusArgs(ty), &[fmt])
+            }
+            BuiltIn::analog_node_alias | BuiltIn::analog_port_alias => {
+                // aliases are resolved during name resolution
+                if self.body.is_aliased_node(args[0], self.ctx.db) {
+                    ONE
+                } else {
+                    ZERO
+                }
+            }
             BuiltIn::param_given => self
                 .ctx
                 .use_param(ParamKind::ParamGiven { param: self.body.into_parameter(args[0]) }),
@@ -657,42 +859,35 @@ impl BodyLoweringCtx<'_, '_, '_> {
                 if self.ctx.inside_lim && Some(&Literal::Int(-1)) == self.body.as_literal(args[0]) {
                     self.ctx.call(CallBackKind::LimDiscontinuity, &[]);
                 } else {
-                    // TODO implement support for discontinuity?
+                    // the degree of the discontinuity is not passed to the simulator
+                    self.ctx.call(CallBackKind::Discontinuity, &[]);
                 }
                 GRAVESTONE
             }
             BuiltIn::finish | BuiltIn::stop => GRAVESTONE,
 
-            /* TODO: absdealy
-            BuiltIn::absdelay => {
-                let arg = self.lower_expr(args[0]);
-                let mut delay = self.lower_expr(args[1]);
-                let (eq1, res) = self.ctx.implicit_eqation(ImplicitEquationKind::Absdelay);
-                let (eq2, intermediate) = self.ctx.implicit_eqation(ImplicitEquationKind::Absdelay);
-                if signature == ABSDELAY_MAX {
-                    let max_delay = self.lower_expr(args[2]);
-                    let use_delay = self.ctx.ins().fle(delay, max_delay);
-                    delay = self.lower_select_with(use_delay, |_| delay, |_| max_delay);
-                } else {
-                    delay = self.ctx.call1(CallBackKind::StoreDelayTime(eq1), &[delay]);
-                }
-
-                let mut resist_val = self.ctx.ins().fsub(res, arg);
-                resist_val = self.ctx.ins().fdiv(resist_val, delay);
-                self.ctx.def_resist_residual(resist_val, eq1);
-                self.ctx.def_react_residual(intermediate, eq1);
-
-                let mut resist_val = self.ctx.ins().fsub(res, intermediate);
-                resist_val = self.ctx.ins().fdiv(resist_val, delay);
-                self.ctx.def_resist_residual(resist_val, eq2);
-                let react_val = self.ctx.ins().fdiv(res, F_THREE);
-                self.ctx.def_react_residual(react_val, eq2);
-
-                res
-            }*/
-            BuiltIn::slew | BuiltIn::transition | BuiltIn::limit | BuiltIn::absdelay => {
-                self.lower_expr(args[0])
-            }
+            BuiltIn::transition => self.lower_transition(args),
+            BuiltIn::slew => self.lower_slew(args),
+            BuiltIn::last_crossing => self.lower_last_crossing(args),
+            BuiltIn::absdelay => self.lower_absdelay(args),
+            BuiltIn::table_model => self.lower_table_model(args),
+            BuiltIn::random
+            | BuiltIn::arandom
+            | BuiltIn::dist_uniform
+            | BuiltIn::dist_normal
+            | BuiltIn::dist_exponential
+            | BuiltIn::dist_poisson
+            | BuiltIn::dist_chi_square
+            | BuiltIn::dist_t
+            | BuiltIn::dist_erlang
+            | BuiltIn::rdist_uniform
+            | BuiltIn::rdist_normal
+            | BuiltIn::rdist_exponential
+            | BuiltIn::rdist_poisson
+            | BuiltIn::rdist_chi_square
+            | BuiltIn::rdist_t
+            | BuiltIn::rdist_erlang => self.lower_random(expr, builtin, args),
+            BuiltIn::limit => self.lower_expr(args[0]),
 
             _ => unreachable!(),
         }
@@ -772,3 +967,11 @@ impl BodyLoweringCtx<'_, '_, '_> {
         BodyLoweringCtx { ctx: self.ctx, body: body.borrow(), path: self.path }.lower_expr(expr)
     }
 }
+
+pub(crate) fn array_len(ty: &Type) -> u32 {
+    match *ty {
+        Type::Array { len, .. } => len,
+        Type::EmptyArray => 0,
+        ref ty => unreachable!("expected array found {ty}"),
+    }
+}
@@ -0,0 +1,386 @@
+//! Lowering of the `laplace_*` and `zi_*` analog filters.
+//!
+//! Laplace filters are lowered to a transfer function `N(s)/D(s)` (coefficients in ascending
+//! powers of `s`). The resulting polynomials are passed to a [`CallBackKind::LaplaceFilter`]
+//! callback. The state space realisation (implicit equations) of that callback is created in
+//! the topology construction of `sim_back`.
+//!
+//! The z-domain filters are evaluated as sampled systems during transient analysis: the input
+//! is sampled every `T` starting at `t0`, the difference equation is evaluated with the input
+//! and output history kept in the state vector and the output is held between samples (zero
+//! order hold). If a transition time is specified, the held output is smoothed with
+//! `transition`. `$bound_step` ensures that the simulator places a timestep at each sample and
+//! a change of the output is signaled with `$discontinuity`.
+//!
+//! Outside of transient analysis the simulator can not represent a sampled system. Here the
+//! z-domain filter is mapped to the laplace domain with the bilinear transform
+//! `z⁻¹ = (2 - sT)/(2 + sT)`. This yields the exact DC response and a good approximation of
+//! the small signal response below the nyquist frequency.
+
+use std::cmp::max;
+
+use hir::{BuiltIn, ExprId};
+use mir::builder::InstBuilder;
+use mir::{Value, FALSE, F_ONE, F_TWO, F_ZERO};
+use stdx::iter::zip;
+
+use crate::body::BodyLoweringCtx;
+use crate::{CallBackKind, OperatorStateKind, ParamKind};
+
+/// Relative tolerance (in periods) used to decide whether a sample time was reached
+const SAMPLE_TOL: f64 = 1e-9;
+
+impl BodyLoweringCtx<'_, '_, '_> {
+    pub(crate) fn lower_filter(&mut self, filter: BuiltIn, args: &[ExprId]) -> Value {
+        let is_zi =
+            matches!(filter, BuiltIn::zi_nd | BuiltIn::zi_np | BuiltIn::zi_zd | BuiltIn::zi_zp);
+
+        let mut num = self.lower_array_expr(args[1]);
+        if matches!(
+            filter,
+            BuiltIn::laplace_zd | BuiltIn::laplace_zp | BuiltIn::zi_zd | BuiltIn::zi_zp
+        ) {
+            num = self.expand_roots(&num, is_zi);
+        }
+
+        let mut den = self.lower_array_expr(args[2]);
+        if matches!(
+            filter,
+            BuiltIn::laplace_np | BuiltIn::laplace_zp | BuiltIn::zi_np | BuiltIn::zi_zp
+        ) {
+            den = self.expand_roots(&den, is_zi);
+        }
+
+        // empty coefficients are rejected during validation
+        debug_assert!(!num.is_empty() && !den.is_empty());
+
+        let input = self.lower_expr(args[0]);
+        if !is_zi {
+            return self.laplace_filter(input, &num, &den);
+        }
+
+        let period = self.lower_expr(args[3]);
+        let transition = args.get(4).map(|&arg| self.lower_expr(arg));
+        let first_sample = args.get(5).map_or(F_ZERO, |&arg| self.lower_expr(arg));
+        self.zi_filter(input, &num, &den, period, transition, first_sample)
+    }
+
+    fn laplace_filter(&mut self, input: Value, num: &[Value], den: &[Value]) -> Value {
+        if self.ctx.no_equations {
+            // without equations only the DC gain N(0)/D(0) remains
+            let gain = self.ctx.ins().fdiv(num[0], den[0]);
+            return self.ctx.ins().fmul(input, gain);
+        }
+
+        let kind =
+            CallBackKind::LaplaceFilter { num_len: num.len() as u32, den_len: den.len() as u32 };
+        let mut call_args = vec![input];
+        call_args.extend_from_slice(num);
+        call_args.extend_from_slice(den);
+        self.ctx.call1(kind, &call_args)
+    }
+
+    /// Lowers a z-domain filter with the coefficients `num` and `den` (in ascending powers of
+    /// `z⁻¹`) that samples `input` every `period` starting at `first_sample`
+    fn zi_filter(
+        &mut self,
+        input: Value,
+        num: &[Value],
+        den: &[Value],
+        period: Value,
+        transition: Option<Value>,
+        first_sample: Value,
+    ) -> Value {
+        let (approx_num, approx_den) = self.bilinear_transform(num, den, period);
+        let approx = self.laplace_filter(input, &approx_num, &approx_den);
+        if self.ctx.no_equations {
+            return approx;
+        }
+
+        // the state contains the time of the next sample, the previous inputs x[k-1], x[k-2], ..
+        // and the previous outputs y[k-1], y[k-2], ... The last output is always stored
+        // because it is held until the next sample.
+        let num_inputs = num.len() - 1;
+        let num_outputs = max(den.len() - 1, 1);
+        let (states, prev_vals): (Vec<_>, Vec<_>) = (0..1 + num_inputs + num_outputs)
+            .map(|_| self.ctx.operator_state(OperatorStateKind::ZiFilter))
+            .unzip();
+
+        let time = self.ctx.use_param(ParamKind::Abstime);
+        let transient = self.ctx.use_param(ParamKind::TransientAnalysis);
+        let enable_integration = self.ctx.use_param(ParamKind::EnableIntegration);
+        let sampled = self.lower_select_with(transient, |_| enable_integration, |_| FALSE);
+        let vals = self.lower_vec_select(sampled, |mut ctx, sampled| {
+            if sampled {
+                ctx.zi_sample(input, num, den, period, time, &prev_vals)
+            } else {
+                ctx.zi_steady_state(input, num, den, period, time, first_sample, &prev_vals)
+            }
+        });
+        for (state, val) in zip(states, &vals) {
+            self.ctx.store_operator_state(state, *val);
+        }
+
+        let held = vals[1 + num_inputs];
+        let output = match transition {
+            Some(transition) => self.transition(held, F_ZERO, transition, transition),
+            None => held,
+        };
+        self.lower_select_with(sampled, |_| output, |_| approx)
+    }
+
+    /// Evaluates the next sample of a z-domain filter once its sample time is reached
+    /// and returns the new state.
+    fn zi_sample(
+        &mut self,
+        input: Value,
+        num: &[Value],
+        den: &[Value],
+        period: Value,
+        time: Value,
+        prev_vals: &[Value],
+    ) -> Vec<Value> {
+        let (&prev_next, history) = prev_vals.split_first().unwrap();
+        let (prev_inputs, prev_outputs) = history.split_at(num.len() - 1);
+
+        // a small tolerance ensures that sample times which were only missed due to
+        // round-off errors are not skipped
+        let elapsed = self.ctx.ins().fsub(time, prev_next);
+        let elapsed = self.ctx.ins().fdiv(elapsed, period);
+        let tol = self.ctx.fconst(SAMPLE_TOL);
+        let elapsed = self.ctx.ins().fadd(elapsed, tol);
+        let fired = self.ctx.ins().fge(elapsed, F_ZERO);
+        self.lower_vec_select(fired, |mut ctx, fired| {
+            if !fired {
+                let step = ctx.ctx.ins().fsub(prev_next, time);
+                ctx.bound_step(step);
+                return prev_vals.to_vec();
+            }
+
+            // y[k] = (Σ n_i x[k-i] - Σ_{i>0} d_i y[k-i]) / d_0
+            let mut acc = ctx.fmul(num[0], input);
+            for (&coeff, &val) in zip(&num[1..], prev_inputs) {
+                let val = ctx.fmul(coeff, val);
+                acc = ctx.fadd(acc, val);
+            }
+            for (&coeff, &val) in zip(&den[1..], prev_outputs) {
+                let val = ctx.fmul(coeff, val);
+                acc = ctx.fsub(acc, val);
+            }
+            let output = ctx.ctx.ins().fdiv(acc, den[0]);
+            let changed = ctx.ctx.ins().fne(output, prev_outputs[0]);
+            ctx.lower_cond_with(changed, |mut ctx, changed| {
+                if changed {
+                    ctx.discontinuity()
+                }
+            });
+
+            let periods = ctx.ctx.ins().floor(elapsed);
+            let periods = ctx.ctx.ins().fadd(periods, F_ONE);
+            let next = ctx.ctx.ins().fmul(periods, period);
+            let next = ctx.ctx.ins().fadd(prev_next, next);
+            let step = ctx.ctx.ins().fsub(next, time);
+            ctx.bound_step(step);
+
+            let mut res = vec![next];
+            if !prev_inputs.is_empty() {
+                res.push(input);
+                res.extend_from_slice(&prev_inputs[..prev_inputs.len() - 1]);
+            }
+            res.push(output);
+            res.extend_from_slice(&prev_outputs[..prev_outputs.len() - 1]);
+            res
+        })
+    }
+
+    /// Returns the state of a z-domain filter whose input has been constant forever.
+    #[allow(clippy::too_many_arguments)]
+    fn zi_steady_state(
+        &mut self,
+        input: Value,
+        num: &[Value],
+        den: &[Value],
+        period: Value,
+        time: Value,
+        first_sample: Value,
+        prev_vals: &[Value],
+    ) -> Vec<Value> {
+        // the DC gain N(1)/D(1)
+        let num_sum = num.iter().fold(F_ZERO, |acc, &val| self.fadd(acc, val));
+        let den_sum = den.iter().fold(F_ZERO, |acc, &val| self.fadd(acc, val));
+        let output = self.ctx.ins().fmul(input, num_sum);
+        let output = self.ctx.ins().fdiv(output, den_sum);
+
+        let started = self.ctx.ins().fgt(time, first_sample);
+        let next = self.lower_select_with(
+            started,
+            |ctx| {
+                let elapsed = ctx.ctx.ins().fsub(time, first_sample);
+                let periods = ctx.ctx.ins().fdiv(elapsed, period);
+                let periods = ctx.ctx.ins().floor(periods);
+                let periods = ctx.ctx.ins().fadd(periods, F_ONE);
+                let next = ctx.ctx.ins().fmul(periods, period);
+                ctx.ctx.ins().fadd(first_sample, next)
+            },
+            |_| first_sample,
+        );
+
+        // the state consists of the next sample time, num.len() - 1 inputs and the outputs
+        let num_outputs = prev_vals.len() - num.len();
+        let mut res = vec![next];
+        res.extend((1..num.len()).map(|_| input));
+        res.extend((0..num_outputs).map(|_| output));
+        res
+    }
+
+    /// Expands a list of complex roots `[re0, im0, re1, im1, ..]` into the (real) coefficients
+    /// of the polynomial with these roots. For z-domain filters each root `r` contributes
+    /// a factor `1 - z⁻¹r`. For laplace filters each root contributes a factor `1 - s/r`
+    /// (or `s` if `r` is zero).
+    fn expand_roots(&mut self, roots: &[Value], is_zi: bool) -> Vec<Value> {
+        let mut re = vec![F_ONE];
+        let mut im = vec![F_ZERO];
+        for root in roots.chunks_exact(2) {
+            let (root_re, root_im) = (root[0], root[1]);
+            // the factor a + bx
+            let [a_re, a_im, b_re, b_im] = if is_zi {
+                let b_re = self.ctx.ins().fneg(root_re);
+                let b_im = self.ctx.ins().fneg(root_im);
+                [F_ONE, F_ZERO, b_re, b_im]
+            } else {
+                let re_sq = self.ctx.ins().fmul(root_re, root_re);
+                let im_sq = self.ctx.ins().fmul(root_im, root_im);
+                let abs_sq = self.ctx.ins().fadd(re_sq, im_sq);
+                let is_zero = self.ctx.ins().feq(abs_sq, F_ZERO);
+                self.lower_multi_select(is_zero, |ctx, is_zero| {
+                    if is_zero {
+                        [F_ZERO, F_ZERO, F_ONE, F_ZERO]
+                    } else {
+                        // -1/r = -conj(r)/|r|²
+                        let b_re = ctx.ctx.ins().fdiv(root_re, abs_sq);
+                        let b_re = ctx.ctx.ins().fneg(b_re);
+                        let b_im = ctx.ctx.ins().fdiv(root_im, abs_sq);
+                        [F_ONE, F_ZERO, b_re, b_im]
+                    }
+                })
+            };
+
+            // p(x)(a + bx) = a*p[k] + b*p[k-1]
+            let mut new_re = Vec::with_capacity(re.len() + 1);
+            let mut new_im = Vec::with_capacity(re.len() + 1);
+            for k in 0..=re.len() {
+                let (mut res_re, mut res_im) = (F_ZERO, F_ZERO);
+                if k < re.len() {
+                    (res_re, res_im) =
+                        self.complex_mul_add((res_re, res_im), (a_re, a_im), (re[k], im[k]));
+                }
+                if k > 0 {
+                    (res_re, res_im) = self.complex_mul_add(
+                        (res_re, res_im),
+                        (b_re, b_im),
+                        (re[k - 1], im[k - 1]),
+                    );
+                }
+                new_re.push(res_re);
+                new_im.push(res_im);
+            }
+            re = new_re;
+            im = new_im;
+        }
+
+        // the imaginary parts cancel out if the roots are complex conjugate pairs
+        re
+    }
+
+    /// Returns `acc + lhs * rhs` for complex numbers
+    fn complex_mul_add(
+        &mut self,
+        (acc_re, acc_im): (Value, Value),
+        (lhs_re, lhs_im): (Value, Value),
+        (rhs_re, rhs_im): (Value, Value),
+    ) -> (Value, Value) {
+        let re1 = self.fmul(lhs_re, rhs_re);
+        let re2 = self.fmul(lhs_im, rhs_im);
+        let re = self.fsub(re1, re2);
+        let im1 = self.fmul(lhs_re, rhs_im);
+        let im2 = self.fmul(lhs_im, rhs_re);
+        let im = self.fadd(im1, im2);
+        (self.fadd(acc_re, re), self.fadd(acc_im, im))
+    }
+
+    /// Maps a z-domain transfer function (coefficients in ascending powers of `z⁻¹`)
+    /// to the laplace domain by substituting `z⁻¹ = (2 - sT)/(2 + sT)` and multiplying
+    /// both numerator and denominator with `(2 + sT)ⁿ`.
+    fn bilinear_transform(
+        &mut self,
+        num: &[Value],
+        den: &[Value],
+        period: Value,
+    ) -> (Vec<Value>, Vec<Value>) {
+        let order = max(num.len(), den.len()) - 1;
+        let neg_period = self.ctx.ins().fneg(period);
+
+        // (2 - sT)^k and (2 + sT)^k for k in 0..=order
+        let mut falling = vec![vec![F_ONE]];
+        let mut rising = vec![vec![F_ONE]];
+        for k in 1..=order {
+            let next = self.poly_mul(&falling[k - 1], &[F_TWO, neg_period]);
+            falling.push(next);
+            let next = self.poly_mul(&rising[k - 1], &[F_TWO, period]);
+            rising.push(next);
+        }
+
+        let mut transform = |coeffs: &[Value]| {
+            let mut res = vec![F_ZERO; order + 1];
+            for (k, &coeff) in coeffs.iter().enumerate() {
+                let term = self.poly_mul(&falling[k], &rising[order - k]);
+                for (dst, val) in res.iter_mut().zip(term) {
+                    let val = self.fmul(coeff, val);
+                    *dst = self.fadd(*dst, val);
+                }
+            }
+            res
+        };
+
+        let num = transform(num);
+        let den = transform(den);
+        (num, den)
+    }
+
+    fn poly_mul(&mut self, lhs: &[Value], rhs: &[Value]) -> Vec<Value> {
+        let mut res = vec![F_ZERO; lhs.len() + rhs.len() - 1];
+        for (i, &lhs) in lhs.iter().enumerate() {
+            for (j, &rhs) in rhs.iter().enumerate() {
+                let val = self.fmul(lhs, rhs);
+                res[i + j] = self.fadd(res[i + j], val);
+            }
+        }
+        res
+    }
+
+    /// `fmul` that avoids creating instructions for trivial (zero/one) operands
+    fn fmul(&mut self, lhs: Value, rhs: Value) -> Value {
+        match (lhs, rhs) {
+            (F_ZERO, _) | (_, F_ZERO) => F_ZERO,
+            (F_ONE, val) | (val, F_ONE) => val,
+            _ => self.ctx.ins().fmul(lhs, rhs),
+        }
+    }
+
+    /// `fadd` that avoids creating instructions for zero operands
+    fn fadd(&mut self, lhs: Value, rhs: Value) -> Value {
+        match (lhs, rhs) {
+            (F_ZERO, val) | (val, F_ZERO) => val,
+            _ => self.ctx.ins().fadd(lhs, rhs),
+        }
+    }
+
+    /// `fsub` that avoids creating instructions for zero operands
+    fn fsub(&mut self, lhs: Value, rhs: Value) -> Value {
+        match (lhs, rhs) {
+            (val, F_ZERO) => val,
+            (F_ZERO, val) => self.ctx.ins().fneg(val),
+            _ => self.ctx.ins().fsub(lhs, rhs),
+        }
+    }
+}
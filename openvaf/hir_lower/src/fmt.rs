@@ -13,6 +13,21 @@ pub enum DisplayKind {
     Error,
     Fatal,
     Monitor,
+    /// Output to the files/channels of a file descriptor (passed as the first argument)
+    File,
+    /// Output of `$fstrobe` (or `$fmonitor` if `monitor` is set) to a file descriptor (passed as
+    /// the first argument). The message is only written at the end of the timestep, until then
+    /// it is kept in the strobe `slot` of the instance.
+    FileStrobe {
+        slot: u32,
+        monitor: bool,
+    },
+}
+
+impl DisplayKind {
+    pub fn is_file(self) -> bool {
+        matches!(self, DisplayKind::File | DisplayKind::FileStrobe { .. })
+    }
 }
 
 #[derive(Debug, Clone, Hash, Eq, PartialEq)]
@@ -35,9 +50,15 @@ pub struct FmtArg {
 }
 
 impl BodyLoweringCtx<'_, '_, '_> {
-    pub fn ins_display(&mut self, kind: DisplayKind, newline: bool, args: &[ExprId]) {
+    pub fn ins_display(&mut self, kind: DisplayKind, newline: bool, mut args: &[ExprId]) {
         let mut fmt_lit = String::new();
         let mut call_args = vec![GRAVESTONE];
+        if kind.is_file() {
+            let fd = self.lower_expr(args[0]);
+            call_args.insert(0, fd);
+            args = &args[1..];
+        }
+        let fmt_pos = call_args.len() - 1;
         let mut arg_tys = Vec::new();
 
         let mut i = 0;
@@ -149,7 +170,7 @@ impl BodyLoweringCtx<'_, '_, '_> {
             fmt_lit.push('\n');
         }
 
-        call_args[0] = self.ctx.sconst(&fmt_lit);
+        call_args[fmt_pos] = self.ctx.sconst(&fmt_lit);
         self.ctx
             .call(CallBackKind::Print { kind, arg_tys: arg_tys.into_boxed_slice() }, &call_args);
     }
@@ -15,7 +15,7 @@ use stdx::{impl_debug_display, impl_idx_from};
 use typed_index_collections::TiVec;
 use typed_indexmap::{map, TiMap, TiSet};
 
-pub use callbacks::{CallBackKind, NoiseTable, ParamInfoKind};
+pub use callbacks::{CallBackKind, Distribution, ParamInfoKind, RandomKind};
 
 use crate::body::BodyLoweringCtx;
 use crate::ctx::LoweringCtx;
@@ -33,17 +33,39 @@ macro_rules! match_signature {
 mod body;
 mod callbacks;
 mod ctx;
+mod delay;
+mod event;
 mod expr;
+mod filter;
 pub mod fmt;
 mod parameters;
+mod random;
 mod state;
 mod stmt;
+mod table_model;
+mod transition;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ImplicitEquationKind {
     Ddt,
     NoiseSrc,
     Idt(IdtKind),
+    LaplaceFilter,
+}
+
+/// The analog operator that owns a slot of the state vector
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OperatorStateKind {
+    Transition,
+    Slew,
+    Cross,
+    Above,
+    Timer,
+    LastCrossing,
+    Absdelay,
+    ZiFilter,
+    /// A variable assigned within a monitored event that holds its value between events
+    HeldVariable,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -76,18 +98,41 @@ impl TryFrom<CurrentKind> for BranchWrite {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum ParamKind {
     Param(Parameter),
+    /// A single element of an array valued parameter
+    ParamElement {
+        param: Parameter,
+        idx: u32,
+    },
     Abstime,
     EnableIntegration,
+    /// Whether the current evaluation belongs to a transient analysis
+    TransientAnalysis,
     EnableLim,
     PrevState(LimitState),
     NewState(LimitState),
-    Voltage { hi: Node, lo: Option<Node> },
+    /// The value of an operator state at the last accepted timestep
+    PrevOperatorState(OperatorState),
+    Voltage {
+        hi: Node,
+        lo: Option<Node>,
+    },
     Current(CurrentKind),
     Temperature,
-    ParamGiven { param: Parameter },
-    PortConnected { port: Node },
+    ParamGiven {
+        param: Parameter,
+    },
+    PortConnected {
+        port: Node,
+    },
     ParamSysFun(ParamSysFun),
     HiddenState(Variable),
+    HiddenStateElement {
+        var: Variable,
+        idx: u32,
+    },
+    /// Whether the hidden state has been stored by a previous evaluation.
+    /// Before that hidden state variables are set to their initial value.
+    HiddenStateInitialized,
     ImplicitUnknown(ImplicitEquation),
 }
 
@@ -107,9 +152,13 @@ impl ParamKind {
                 | ParamKind::ImplicitUnknown(_)
                 | ParamKind::Abstime
                 | ParamKind::EnableIntegration
+                | ParamKind::TransientAnalysis
                 | ParamKind::HiddenState(_)
+                | ParamKind::HiddenStateElement { .. }
+                | ParamKind::HiddenStateInitialized
                 | ParamKind::PrevState(_)
                 | ParamKind::NewState(_)
+                | ParamKind::PrevOperatorState(_)
                 | ParamKind::EnableLim
         )
     }
@@ -153,6 +202,11 @@ impl IdtKind {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PlaceKind {
     Var(Variable),
+    /// Arrays are scalarized during lowering so that each element becomes its own place
+    VarElement {
+        var: Variable,
+        idx: u32,
+    },
     FunctionReturn(hir::Function),
     FunctionArg(hir::FunctionArg),
     Contribute {
@@ -168,6 +222,10 @@ pub enum PlaceKind {
     IsVoltageSrc(BranchWrite),
     /// A parameter during param initiliztion is mutable (write default in case its not given)
     Param(Parameter),
+    ParamElement {
+        param: Parameter,
+        idx: u32,
+    },
     ParamMin(Parameter),
     ParamMax(Parameter),
     BoundStep,
@@ -177,6 +235,7 @@ impl PlaceKind {
     pub fn ty(&self, db: &CompilationDB) -> Type {
         match *self {
             PlaceKind::Var(var) => var.ty(db),
+            PlaceKind::VarElement { var, .. } => var.ty(db).base_type().clone(),
             PlaceKind::FunctionReturn(fun) => fun.return_ty(db),
             PlaceKind::FunctionArg(arg) => arg.ty(db),
 
@@ -186,6 +245,7 @@ impl PlaceKind {
             PlaceKind::ParamMin(param) | PlaceKind::ParamMax(param) | PlaceKind::Param(param) => {
                 param.ty(db)
             }
+            PlaceKind::ParamElement { param, .. } => param.ty(db).base_type().clone(),
             PlaceKind::IsVoltageSrc(_) | PlaceKind::CollapseImplicitEquation(_) => Type::Bool,
         }
     }
@@ -193,6 +253,26 @@ impl PlaceKind {
     pub fn is_init_only(&self) -> bool {
         matches!(self, Self::CollapseImplicitEquation(_))
     }
+
+    /// The places that store the value of `var`. Arrays are scalarized so
+    /// that there is one place for every element.
+    pub fn var_places(db: &CompilationDB, var: Variable) -> Vec<PlaceKind> {
+        match var.ty(db) {
+            Type::Array { len, .. } => {
+                (0..len).map(|idx| PlaceKind::VarElement { var, idx }).collect()
+            }
+            _ => vec![PlaceKind::Var(var)],
+        }
+    }
+
+    /// The parameter that holds the value of a variable place at the start of the evaluation
+    pub fn hidden_state(&self) -> Option<ParamKind> {
+        match *self {
+            PlaceKind::Var(var) => Some(ParamKind::HiddenState(var)),
+            PlaceKind::VarElement { var, idx } => Some(ParamKind::HiddenStateElement { var, idx }),
+            _ => None,
+        }
+    }
 }
 
 impl From<hir::AssignmentLhs> for PlaceKind {
@@ -201,6 +281,12 @@ impl From<hir::AssignmentLhs> for PlaceKind {
             hir::AssignmentLhs::Variable(var) => PlaceKind::Var(var),
             hir::AssignmentLhs::FunctionReturn(fun) => PlaceKind::FunctionReturn(fun),
             hir::AssignmentLhs::FunctionArg(arg) => PlaceKind::FunctionArg(arg),
+            hir::AssignmentLhs::ArrayElement { .. } => {
+                unreachable!("array elements are assigned element-wise")
+            }
+            hir::AssignmentLhs::RealNet(_) => {
+                unreachable!("real nets are driven by a voltage source")
+            }
         }
     }
 }
@@ -219,6 +305,27 @@ impl_debug_display! {
     match LimitState {LimitState(i) => "lim_state{}", i;}
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OperatorState(u32);
+impl_idx_from!(OperatorState(u32));
+impl_debug_display! {
+    match OperatorState {OperatorState(i) => "op_state{}", i;}
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct DelayLine(u32);
+impl_idx_from!(DelayLine(u32));
+impl_debug_display! {
+    match DelayLine {DelayLine(i) => "delay_line{}", i;}
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RandomSeed(u32);
+impl_idx_from!(RandomSeed(u32));
+impl_debug_display! {
+    match RandomSeed {RandomSeed(i) => "random_seed{}", i;}
+}
+
 /// A mapping between abstractions used in the MIR and the corresponding
 /// information from the HIR. This allows the MIR to remain independent of the frontend/HIR
 #[derive(Debug, PartialEq, Default, Clone)]
@@ -230,6 +337,16 @@ pub struct HirInterner {
     pub tagged_reads: IndexMap<Value, Variable, ahash::RandomState>,
     pub implicit_equations: TiVec<ImplicitEquation, ImplicitEquationKind>,
     pub lim_state: TiMap<LimitState, Value, Vec<(Value, bool)>>,
+    /// State slots of analog operators (like `transition`) that need to remember values
+    /// between timesteps. These are placed behind the limit states in the state vector.
+    pub operator_states: TiVec<OperatorState, OperatorStateKind>,
+    /// Number of `absdelay` history buffers that need to be stored in the instance data
+    pub num_delay_lines: u32,
+    /// Number of seeds of random number generators (one per call) stored in the instance data
+    pub num_random_seeds: u32,
+    /// Number of `$fstrobe`/`$fmonitor` calls whose pending message is stored in the instance
+    /// data
+    pub num_strobes: u32,
 }
 
 pub type LiveParams<'a> = FilterMap<
@@ -494,7 +611,9 @@ impl<'a> MirBuilder<'a> {
         body_ctx.lower_entry_stmts();
 
         for var in self.required_vars {
-            ctx.dec_place(PlaceKind::Var(var));
+            for place in PlaceKind::var_places(self.db, var) {
+                ctx.dec_place(place);
+            }
         }
         let is_output = self.is_output;
         ctx.intern.outputs = ctx
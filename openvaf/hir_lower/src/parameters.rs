@@ -1,11 +1,13 @@
 use std::f64::NEG_INFINITY;
 use std::mem::replace;
 
+use ahash::AHashMap;
 use hir::{CompilationDB, ConstraintValue, ParamConstraint, Parameter, Type};
 use lasso::Rodeo;
 use mir::builder::InstBuilder;
 use mir::{Block, FuncRef, Function, Opcode, Value, FALSE, GRAVESTONE, INFINITY};
 use mir_build::{FunctionBuilder, FunctionBuilderContext};
+use stdx::iter::zip;
 use stdx::packed_option::ReservedValue;
 use syntax::ast::ConstraintKind;
 
@@ -45,6 +47,9 @@ impl CmpOps {
 }
 
 impl HirInterner {
+    /// Initializes `params` with their default value if they are not given. Parameters in
+    /// `defaults` use the value of the mapped parameter as their default instead (paramsets).
+    #[allow(clippy::too_many_arguments)]
     pub fn insert_param_init(
         &mut self,
         db: &CompilationDB,
@@ -53,8 +58,10 @@ impl HirInterner {
         build_min_max: bool,
         build_stores: bool,
         params: &[Parameter],
+        defaults: &AHashMap<Parameter, Parameter>,
     ) {
-        let mut default_vals = if build_stores { vec![GRAVESTONE; params.len()] } else { vec![] };
+        // (param, output, default value) of every scalar (element) that was initialized
+        let mut slots = Vec::with_capacity(params.len());
 
         let f_neg_inf = func.dfg.fconst(NEG_INFINITY.into());
         let f_inf = INFINITY;
@@ -65,7 +72,13 @@ impl HirInterner {
         let (builder, term) = FunctionBuilder::edit(func, literals, &mut ctx, false);
         let mut ctx = LoweringCtx::new(db, builder, true, self);
 
-        for (i, param) in params.iter().copied().enumerate() {
+        for param in params.iter().copied() {
+            if let Type::Array { len, .. } = param.ty(db) {
+                let default = defaults.get(&param).copied();
+                ctx.insert_array_param_init(param, len, default, build_stores, &mut slots);
+                continue;
+            }
+
             let mut param_val = ctx.use_param(ParamKind::Param(param));
             let param_given = ctx.use_param(ParamKind::ParamGiven { param });
 
@@ -76,10 +89,12 @@ impl HirInterner {
             let body = param.init(db);
             let ty = param.ty(db);
             let bounds = param.bounds(db);
+            let default = defaults.get(&param).copied();
 
             let ops = CmpOps::from_ty(&ty);
             let invalid = ctx.dec_callback(CallBackKind::ParamInfo(ParamInfoKind::Invalid, param));
 
+            let mut stored_default = GRAVESTONE;
             let (then_src, else_src) = ctx.make_cond(param_given, |ctx, param_given| {
                 if param_given {
                     if build_stores {
@@ -107,7 +122,18 @@ impl HirInterner {
                     }
                     param_val
                 } else {
-                    let default_val = ctx.lower_expr_body(body.borrow(), 0);
+                    let default_val = match default {
+                        Some(default) => {
+                            let val = ctx.lower_expr_body(default.init(db).borrow(), 0);
+                            let default_ty = default.ty(db);
+                            if default_ty == ty {
+                                val
+                            } else {
+                                ctx.insert_cast(val, &default_ty, &ty)
+                            }
+                        }
+                        None => ctx.lower_expr_body(body.borrow(), 0),
+                    };
                     if build_stores {
                         let exit = ctx.create_block();
                         let mut ctx = BodyLoweringCtx { ctx, body: body.borrow(), path: "" };
@@ -130,7 +156,7 @@ impl HirInterner {
                             exit,
                         );
                         ctx.ctx.switch_to_block(exit);
-                        default_vals[i] = ctx.ctx.ins().optbarrier(default_val);
+                        stored_default = ctx.ctx.ins().optbarrier(default_val);
                     }
                     default_val
                 }
@@ -144,6 +170,7 @@ impl HirInterner {
             // correct value. After code generation is complete we swap these two again
             ctx.def_param(ParamKind::Param(param), new_val);
             ctx.def_output(PlaceKind::Param(param), param_val);
+            slots.push((ParamKind::Param(param), PlaceKind::Param(param), stored_default));
             param_val = new_val;
 
             if !build_min_max {
@@ -304,11 +331,110 @@ impl HirInterner {
         ctx.ensured_sealed();
         ctx.func.func.layout.append_inst_to_bb(term, ctx.current_block());
 
-        for (i, param) in params.iter().copied().enumerate() {
-            let val = &mut self.params.raw[&ParamKind::Param(param)];
-            let output_val = if build_stores { default_vals[i] } else { *val };
-            *val = replace(&mut self.outputs[&PlaceKind::Param(param)], Some(output_val).into())
-                .unwrap_unchecked();
+        for (param, output, default_val) in slots {
+            let val = &mut self.params.raw[&param];
+            let output_val = if build_stores { default_val } else { *val };
+            *val = replace(&mut self.outputs[&output], Some(output_val).into()).unwrap_unchecked();
+        }
+    }
+}
+
+impl LoweringCtx<'_, '_> {
+    /// Array parameters are initialized element-wise. Each element is bound
+    /// as a separate parameter (and output) and checked against the bounds
+    /// of the parameter. Min/max values are not computed for arrays.
+    fn insert_array_param_init(
+        &mut self,
+        param: Parameter,
+        len: u32,
+        default: Option<Parameter>,
+        build_stores: bool,
+        slots: &mut Vec<(ParamKind, PlaceKind, Value)>,
+    ) {
+        let param_vals: Vec<_> =
+            (0..len).map(|idx| self.use_param(ParamKind::ParamElement { param, idx })).collect();
+        let param_given = self.use_param(ParamKind::ParamGiven { param });
+
+        // create temporaries to hold onto the uses
+        let new_vals: Vec<_> = param_vals
+            .iter()
+            .map(|&param_val| {
+                let new_val = self.func.make_param(0u32.into());
+                self.dfg_mut().replace_uses(param_val, new_val);
+                new_val
+            })
+            .collect();
+
+        let body = param.init(self.db);
+        let bounds = param.bounds(self.db);
+        let ty = param.ty(self.db);
+        let ops = CmpOps::from_ty(&ty);
+        let invalid = self.dec_callback(CallBackKind::ParamInfo(ParamInfoKind::Invalid, param));
+
+        let mut default_vals = vec![GRAVESTONE; len as usize];
+        let ((then_bb, then_vals), (else_bb, else_vals)) =
+            self.make_cond(param_given, |ctx, param_given| {
+                let vals = if param_given {
+                    param_vals.clone()
+                } else if let Some(default) = default {
+                    let vals = ctx.lower_array_expr_body(default.init(ctx.db).borrow(), 0);
+                    let default_ty = default.ty(ctx.db);
+                    let (src, dst) = (default_ty.base_type(), ty.base_type());
+                    if src == dst {
+                        vals
+                    } else {
+                        vals.into_iter().map(|val| ctx.insert_cast(val, src, dst)).collect()
+                    }
+                } else {
+                    ctx.lower_array_expr_body(body.borrow(), 0)
+                };
+                if build_stores {
+                    let mut ctx = BodyLoweringCtx { ctx, body: body.borrow(), path: "" };
+                    for &val in &vals {
+                        let exit = ctx.ctx.create_block();
+                        ctx.check_param(
+                            val,
+                            &bounds,
+                            &[],
+                            ConstraintKind::From,
+                            ops,
+                            invalid,
+                            exit,
+                        );
+                        ctx.check_param(
+                            val,
+                            &bounds,
+                            &[],
+                            ConstraintKind::Exclude,
+                            ops,
+                            invalid,
+                            exit,
+                        );
+                        ctx.ctx.switch_to_block(exit);
+                    }
+                    if !param_given {
+                        for (dst, &val) in zip(&mut default_vals, &vals) {
+                            *dst = ctx.ctx.ins().optbarrier(val);
+                        }
+                    }
+                }
+                vals
+            });
+
+        for (idx, (param_val, new_val)) in zip(param_vals, new_vals).enumerate() {
+            self.ins()
+                .with_result(new_val)
+                .phi(&[(then_bb, then_vals[idx]), (else_bb, else_vals[idx])]);
+
+            // see insert_param_init for why these are swapped
+            let idx = idx as u32;
+            self.def_param(ParamKind::ParamElement { param, idx }, new_val);
+            self.def_output(PlaceKind::ParamElement { param, idx }, param_val);
+            slots.push((
+                ParamKind::ParamElement { param, idx },
+                PlaceKind::ParamElement { param, idx },
+                default_vals[idx as usize],
+            ));
         }
     }
 }
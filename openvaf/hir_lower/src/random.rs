@@ -0,0 +1,138 @@
+//! Lowering of `$random`, `$arandom` and the `$dist_*`/`$rdist_*` functions.
+//!
+//! Every call has its own seed ([`RandomSeed`]) stored in the instance data. The
+//! [`CallBackKind::Random`] callback starts the random number generator from the seed that is
+//! passed as its first argument, stores the updated seed and returns the random value. The
+//! updated seed can be read with [`CallBackKind::ReadRandomSeed`]:
+//!
+//! * If the seed is a variable it is an inout argument: the updated seed is written back to the
+//!   variable after the call.
+//! * If the seed is a parameter (or a literal) the generator always starts from the same seed
+//!   so the result only depends on the seed.
+//! * Without a seed the seed stored in the instance data is used so that each call produces
+//!   the next value of a random sequence.
+//!
+//! The type string of `$arandom` and the `$rdist_*` functions selects whether all instances
+//! produce the same value (`"global"`, the default) or a separate value (`"instance"`). For
+//! `$arandom` the choice is passed to the simulator. Otherwise (and if the simulator does not
+//! provide `$arandom`) a unique id of the instance is mixed into the seed for `"instance"`.
+
+use hir::signatures::{
+    ARANDOM_NO_SEED, ARANDOM_SEED, ARANDOM_SEED_NAME, ARNADOM_CONST_SEED, ARNADOM_CONST_SEED_NAME,
+    DIST_1_ARG_CONST_NAME, DIST_1_ARG_CONST_SEED, DIST_1_ARG_CONST_SEED_NAME, DIST_1_ARG_SEED,
+    DIST_2_ARG_CONST_NAME, DIST_2_ARG_CONST_SEED, DIST_2_ARG_CONST_SEED_NAME, DIST_2_ARG_SEED,
+    RANDOM_NO_SEED, RANDOM_SEED, RDIST_1_ARG_CONST_NAME, RDIST_1_ARG_CONST_SEED,
+    RDIST_1_ARG_CONST_SEED_NAME, RDIST_1_ARG_SEED, RDIST_2_ARG_CONST_NAME, RDIST_2_ARG_CONST_SEED,
+    RDIST_2_ARG_CONST_SEED_NAME, RDIST_2_ARG_SEED,
+};
+use hir::{BuiltIn, ExprId, Literal};
+use mir::{Value, F_ZERO, ZERO};
+
+use crate::body::BodyLoweringCtx;
+use crate::{CallBackKind, Distribution, RandomKind, RandomSeed};
+
+impl BodyLoweringCtx<'_, '_, '_> {
+    pub(crate) fn lower_random(
+        &mut self,
+        expr: ExprId,
+        builtin: BuiltIn,
+        args: &[ExprId],
+    ) -> Value {
+        let signature = self.body.get_call_signature(expr);
+        let (kind, seed_is_var) = match builtin {
+            BuiltIn::random => {
+                let seed_is_var = match_signature!(signature:
+                    RANDOM_NO_SEED => false,
+                    RANDOM_SEED => true
+                );
+                (RandomKind::Random, seed_is_var)
+            }
+            BuiltIn::arandom => {
+                let seed_is_var = match_signature!(signature:
+                    ARANDOM_NO_SEED | ARNADOM_CONST_SEED | ARNADOM_CONST_SEED_NAME => false,
+                    ARANDOM_SEED | ARANDOM_SEED_NAME => true
+                );
+                let global = self.is_global_random(args.get(1));
+                (RandomKind::Arandom { global }, seed_is_var)
+            }
+            _ => {
+                let (dist, real) = match builtin {
+                    BuiltIn::dist_uniform => (Distribution::Uniform, false),
+                    BuiltIn::dist_normal => (Distribution::Normal, false),
+                    BuiltIn::dist_exponential => (Distribution::Exponential, false),
+                    BuiltIn::dist_poisson => (Distribution::Poisson, false),
+                    BuiltIn::dist_chi_square => (Distribution::ChiSquare, false),
+                    BuiltIn::dist_t => (Distribution::T, false),
+                    BuiltIn::dist_erlang => (Distribution::Erlang, false),
+                    BuiltIn::rdist_uniform => (Distribution::Uniform, true),
+                    BuiltIn::rdist_normal => (Distribution::Normal, true),
+                    BuiltIn::rdist_exponential => (Distribution::Exponential, true),
+                    BuiltIn::rdist_poisson => (Distribution::Poisson, true),
+                    BuiltIn::rdist_chi_square => (Distribution::ChiSquare, true),
+                    BuiltIn::rdist_t => (Distribution::T, true),
+                    BuiltIn::rdist_erlang => (Distribution::Erlang, true),
+                    _ => unreachable!(),
+                };
+                let seed_is_var = match (real, dist.num_args()) {
+                    (true, 1) => match_signature!(signature:
+                        RDIST_1_ARG_SEED | RDIST_1_ARG_CONST_NAME => true,
+                        RDIST_1_ARG_CONST_SEED | RDIST_1_ARG_CONST_SEED_NAME => false
+                    ),
+                    (true, _) => match_signature!(signature:
+                        RDIST_2_ARG_SEED | RDIST_2_ARG_CONST_NAME => true,
+                        RDIST_2_ARG_CONST_SEED | RDIST_2_ARG_CONST_SEED_NAME => false
+                    ),
+                    (false, 1) => match_signature!(signature:
+                        DIST_1_ARG_SEED | DIST_1_ARG_CONST_NAME => true,
+                        DIST_1_ARG_CONST_SEED | DIST_1_ARG_CONST_SEED_NAME => false
+                    ),
+                    (false, _) => match_signature!(signature:
+                        DIST_2_ARG_SEED | DIST_2_ARG_CONST_NAME => true,
+                        DIST_2_ARG_CONST_SEED | DIST_2_ARG_CONST_SEED_NAME => false
+                    ),
+                };
+                // only `$rdist_*` accepts a type string (as the last argument)
+                let global = !real || self.is_global_random(args.last());
+                (RandomKind::Dist { dist, real, global }, seed_is_var)
+            }
+        };
+
+        let seed = RandomSeed::from(self.ctx.intern.num_random_seeds);
+        self.ctx.intern.num_random_seeds += 1;
+
+        let seed_val = match args.first() {
+            Some(&arg) => self.lower_expr(arg),
+            None => self.ctx.call1(CallBackKind::ReadRandomSeed(seed), &[]),
+        };
+        let mut call_args = vec![seed_val];
+        if let RandomKind::Dist { dist, real, .. } = kind {
+            for i in 0..2 {
+                let arg = if i < dist.num_args() {
+                    self.lower_expr(args[i + 1])
+                } else if real {
+                    F_ZERO
+                } else {
+                    ZERO
+                };
+                call_args.push(arg);
+            }
+        }
+        let res = self.ctx.call1(CallBackKind::Random { kind, seed }, &call_args);
+
+        if seed_is_var {
+            let new_seed = self.ctx.call1(CallBackKind::ReadRandomSeed(seed), &[]);
+            let dst = self.body.get_expr(args[0]).as_assignment_lhs();
+            self.ctx.def_place(dst.into(), new_seed);
+        }
+
+        res
+    }
+
+    /// Whether the type string `arg` of a random number generator is not `"instance"`
+    fn is_global_random(&self, arg: Option<&ExprId>) -> bool {
+        match arg.and_then(|&arg| self.body.as_literal(arg)) {
+            Some(Literal::String(ty)) => &**ty != "instance",
+            _ => true,
+        }
+    }
+}
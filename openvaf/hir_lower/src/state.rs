@@ -1,4 +1,5 @@
-use hir::CompilationDB;
+use ahash::AHashMap;
+use hir::{CompilationDB, Variable};
 use lasso::Rodeo;
 use mir::Function;
 use mir_build::{FunctionBuilder, FunctionBuilderContext};
@@ -7,21 +8,52 @@ use crate::ctx::LoweringCtx;
 use crate::{HirInterner, ParamKind};
 
 impl HirInterner {
+    /// Replaces the value variables have at the start of the evaluation with their initial value.
+    ///
+    /// Variables for which `is_hidden_state` returns true keep their value between evaluations.
+    /// They are only set to their initial value if no hidden state has been stored yet
+    /// (see [`ParamKind::HiddenStateInitialized`]).
     pub fn insert_var_init(
         &mut self,
         db: &CompilationDB,
         func: &mut Function,
         literals: &mut Rodeo,
+        is_hidden_state: &dyn Fn(Variable) -> bool,
     ) {
         let mut ctx = FunctionBuilderContext::default();
         let (builder, term) = FunctionBuilder::edit(func, literals, &mut ctx, false);
         let mut ctx = LoweringCtx::new(db, builder, true, self);
+        let mut array_inits = AHashMap::new();
         for (kind, param) in ctx.intern.params.clone().iter() {
-            if let ParamKind::HiddenState(var) = *kind {
-                if ctx.dfg().value_dead(*param) {
-                    continue;
+            let var = match *kind {
+                ParamKind::HiddenState(var) | ParamKind::HiddenStateElement { var, .. } => var,
+                _ => continue,
+            };
+            if ctx.dfg().value_dead(*param) {
+                continue;
+            }
+
+            let mut val = match *kind {
+                ParamKind::HiddenStateElement { idx, .. } => {
+                    let vals: &Vec<_> = array_inits
+                        .entry(var)
+                        .or_insert_with(|| ctx.lower_array_expr_body(var.init(db).borrow(), 0));
+                    vals[idx as usize]
+                }
+                _ => ctx.lower_expr_body(var.init(db).borrow(), 0),
+            };
+
+            if is_hidden_state(var) {
+                // the select itself reads the hidden state so only the uses
+                // that existed before are replaced
+                let uses: Vec<_> = ctx.dfg().uses(*param).collect();
+                let initialized = ctx.use_param(ParamKind::HiddenStateInitialized);
+                let init = val;
+                val = ctx.make_select(initialized, |_, stored| if stored { *param } else { init });
+                for use_ in uses {
+                    ctx.dfg_mut().use_set_value(use_, val);
                 }
-                let val = ctx.lower_expr_body(var.init(db).borrow(), 0);
+            } else {
                 ctx.dfg_mut().replace_uses(*param, val);
             }
         }
@@ -1,8 +1,13 @@
-use hir::{BranchWrite, Case, CaseCond, ContributeKind, ExprId, Node, Stmt, StmtId, Type};
+use hir::{
+    AssignmentLhs, BranchWrite, Case, CaseCond, ContributeKind, ExprId, Literal, Node, Stmt,
+    StmtId, Type, Variable,
+};
 use mir::builder::InstBuilder;
-use mir::{Opcode, F_ZERO};
+use mir::{Opcode, Value, FALSE, F_ZERO, TRUE};
+use stdx::iter::zip;
 
 use crate::body::BodyLoweringCtx;
+use crate::expr::array_len;
 use crate::{CallBackKind, CurrentKind, ParamKind, PlaceKind};
 
 impl BodyLoweringCtx<'_, '_, '_> {
@@ -17,9 +22,20 @@ impl BodyLoweringCtx<'_, '_, '_> {
             Stmt::Expr(expr) => {
                 self.lower_expr(expr);
             }
-            Stmt::EventControl { body, .. } => {
-                // TODO handle porperly
-                self.lower_stmt(body);
+            Stmt::EventControl { event, body } => self.lower_event_control(event, body),
+            Stmt::Assignment { lhs: AssignmentLhs::ArrayElement { var, index }, rhs } => {
+                self.lower_element_assignment(var, index, rhs)
+            }
+            Stmt::Assignment { lhs: AssignmentLhs::RealNet(node), rhs } => {
+                self.drive_real_net(node, rhs)
+            }
+            Stmt::Assignment { lhs: AssignmentLhs::Variable(var), rhs }
+                if matches!(var.ty(self.ctx.db), Type::Array { .. }) =>
+            {
+                let vals = self.lower_array_expr(rhs);
+                for (idx, val) in vals.into_iter().enumerate() {
+                    self.ctx.def_place(PlaceKind::VarElement { var, idx: idx as u32 }, val);
+                }
             }
             Stmt::Assignment { lhs, rhs } => {
                 let val_ = self.lower_expr(rhs);
@@ -54,16 +70,61 @@ impl BodyLoweringCtx<'_, '_, '_> {
         }
     }
 
+    fn lower_element_assignment(&mut self, var: Variable, index: ExprId, rhs: ExprId) {
+        let len = array_len(&var.ty(self.ctx.db));
+        let offset = var.array_offset(self.ctx.db);
+        let val = self.lower_expr(rhs);
+
+        // constant indices (the common case) directly write the element,
+        // constant out of bounds indices are rejected during type checking
+        if let Some(&Literal::Int(idx)) = self.body.as_literal(index) {
+            if let Some(idx) = idx.checked_sub(offset).filter(|idx| (0..len as i32).contains(idx)) {
+                self.ctx.def_place(PlaceKind::VarElement { var, idx: idx as u32 }, val);
+                return;
+            }
+        }
+
+        let index = self.lower_expr(index);
+        self.check_index(index, offset, len);
+        for idx in 0..len {
+            let idx_ = self.ctx.iconst(offset + idx as i32);
+            let is_idx = self.ctx.ins().ieq(index, idx_);
+            self.ctx.make_cond(is_idx, |ctx, then| {
+                if then {
+                    ctx.def_place(PlaceKind::VarElement { var, idx }, val)
+                }
+            });
+        }
+    }
+
+    /// Lowers `val == discr` for case statements,
+    /// arrays are equal if all of their elements are equal
+    fn lower_case_cond(&mut self, op: Opcode, vals: &[Value], discr: &[Value]) -> Value {
+        let mut res: Option<Value> = None;
+        for (&val, &discr) in zip(vals, discr) {
+            let is_eq = self.ctx.ins().binary1(op, val, discr);
+            res = Some(match res {
+                Some(prev) => {
+                    self.ctx.make_select(prev, |_, then| if then { is_eq } else { FALSE })
+                }
+                None => is_eq,
+            });
+        }
+        res.unwrap_or(TRUE)
+    }
+
     fn lower_case(&mut self, discr: ExprId, case_arms: &[Case]) {
-        let discr_op = match self.body.expr_type(discr) {
+        let discr_ty = self.body.expr_type(discr);
+        let discr_op = match discr_ty.base_type() {
             Type::Real => Opcode::Feq,
             Type::Integer => Opcode::Ieq,
             Type::Bool => Opcode::Beq,
             Type::String => Opcode::Seq,
-            Type::Array { .. } => todo!(),
             ty => unreachable!("Invalid type {}", ty),
         };
-        let discr = self.lower_expr(discr);
+        let is_array = matches!(discr_ty, Type::Array { .. });
+        let discr =
+            if is_array { self.lower_array_expr(discr) } else { vec![self.lower_expr(discr)] };
         let end = self.ctx.create_block();
 
         for Case { cond, body } in case_arms {
@@ -82,11 +143,15 @@ impl BodyLoweringCtx<'_, '_, '_> {
                 self.ctx.ensured_sealed();
 
                 // Lower the condition (val == discriminant)
-                let val_ = self.lower_expr(*val);
+                let val_ = if is_array {
+                    self.lower_array_expr(*val)
+                } else {
+                    vec![self.lower_expr(*val)]
+                };
 
                 let old_loc = self.ctx.get_srcloc();
                 self.ctx.set_srcloc(mir::SourceLoc::new(u32::from(*val) as i32 + 1));
-                let cond = self.ctx.ins().binary1(discr_op, val_, discr);
+                let cond = self.lower_case_cond(discr_op, &val_, &discr);
                 self.ctx.set_srcloc(old_loc);
 
                 // Create the next block
@@ -141,6 +206,22 @@ impl BodyLoweringCtx<'_, '_, '_> {
         self.ctx.switch_to_block(loop_end);
     }
 
+    /// Real nets are mapped to terminals. Assigning a real net drives the terminal with an ideal
+    /// voltage source. Unlike a contribution, the assigned value replaces the previous value.
+    fn drive_real_net(&mut self, node: Node, rhs: ExprId) {
+        let write = BranchWrite::Unnamed { hi: node, lo: None };
+        self.ctx.def_place(PlaceKind::IsVoltageSrc(write), TRUE);
+        self.ctx.def_place(
+            PlaceKind::Contribute { dst: write, reactive: false, voltage_src: false },
+            F_ZERO,
+        );
+        let val = self.lower_expr(rhs);
+        self.ctx.def_place(
+            PlaceKind::Contribute { dst: write, reactive: false, voltage_src: true },
+            val,
+        );
+    }
+
     fn contribute(&mut self, voltage_src: bool, mut write: BranchWrite, rhs: ExprId) {
         let mut negate = false;
         if let BranchWrite::Unnamed { hi, lo } = &mut write {
@@ -0,0 +1,284 @@
+//! Lowering of `$table_model`.
+//!
+//! The table data is read at compile time (see [`hir::TableModel`]). Each dimension is split
+//! into regions by its data points and the interpolant is a polynomial in every region. The
+//! coefficients of these polynomials are calculated at compile time and stored in a constant
+//! table. At runtime the region that contains the input is looked up with a binary search
+//! ([`CallBackKind::TableLookup`]) and the coefficients of that region are read from the table
+//! ([`CallBackKind::TableValue`]). The region and the coefficients are piecewise constant, so
+//! the derivatives are obtained by `mir_autodiff` from the polynomial itself.
+//!
+//! Multi-dimensional tables are interpolated one dimension at a time: every isoline of the
+//! last dimension is interpolated first and the results are used as the data points of the
+//! previous dimension. All interpolation methods are linear in the values of the data points,
+//! so the polynomials of such a dimension are calculated for each data point separately
+//! (with a value of one) and are weighted with the isolines at runtime.
+
+use std::iter::once;
+
+use hir::{DimensionControl, ExprId, Extrapolation, Interpolation, TableData, Type};
+use mir::builder::InstBuilder;
+use mir::{Ieee64, Value};
+use mir_build::RetBuilder;
+
+use crate::body::BodyLoweringCtx;
+use crate::ctx::LoweringCtx;
+use crate::fmt::DisplayKind;
+use crate::CallBackKind;
+
+impl BodyLoweringCtx<'_, '_, '_> {
+    pub(crate) fn lower_table_model(&mut self, args: &[ExprId]) -> Value {
+        let table = self.body.table_model(self.ctx.db, args);
+        let inputs: Vec<_> = table.inputs.iter().map(|&input| self.lower_expr(input)).collect();
+        self.ctx.interpolate_table(&table.data, &inputs, &table.dimensions)
+    }
+}
+
+impl LoweringCtx<'_, '_> {
+    fn interpolate_table(
+        &mut self,
+        data: &TableData,
+        inputs: &[Value],
+        dimensions: &[DimensionControl],
+    ) -> Value {
+        let (x, control, xs) = (inputs[0], dimensions[0], data.xs());
+        let degree = degree(control.interpolation);
+        match data {
+            TableData::Values { ys, .. } => {
+                if xs.len() == 1 {
+                    return self.fconst(ys[0]);
+                }
+                self.check_range(x, xs, control);
+                let table: Vec<_> = region_polynomials(xs, ys, control)
+                    .into_iter()
+                    .flat_map(|(x0, coeffs)| once(x0).chain(coeffs.into_iter().take(degree + 1)))
+                    .collect();
+                self.eval_regions(x, &breakpoints(xs, control), &table, None, degree)
+            }
+            TableData::Isolines { isolines, .. } => {
+                let ys: Vec<_> = isolines
+                    .iter()
+                    .map(|isoline| self.interpolate_table(isoline, &inputs[1..], &dimensions[1..]))
+                    .collect();
+                if ys.len() == 1 {
+                    return ys[0];
+                }
+                self.check_range(x, xs, control);
+                let mut unit = vec![0f64; xs.len()];
+                let columns: Vec<_> = (0..xs.len())
+                    .map(|i| {
+                        unit[i] = 1.0;
+                        let polynomials = region_polynomials(xs, &unit, control);
+                        unit[i] = 0.0;
+                        polynomials
+                    })
+                    .collect();
+                let mut table = Vec::new();
+                for region in 0..columns[0].len() {
+                    table.push(columns[0][region].0);
+                    for column in &columns {
+                        table.extend_from_slice(&column[region].1[..=degree]);
+                    }
+                }
+                self.eval_regions(x, &breakpoints(xs, control), &table, Some(&ys), degree)
+            }
+        }
+    }
+
+    /// Evaluates the polynomial of the region that contains `x`. For every region `table`
+    /// contains `x0` followed by the coefficients of each column (in ascending order of
+    /// `t = x - x0`). Without `columns` there is only a single column. Otherwise the columns
+    /// are weighted with `columns` and summed up.
+    fn eval_regions(
+        &mut self,
+        x: Value,
+        breakpoints: &[f64],
+        table: &[f64],
+        columns: Option<&[Value]>,
+        degree: usize,
+    ) -> Value {
+        let to_ieee =
+            |vals: &[f64]| -> Box<[Ieee64]> { vals.iter().map(|&val| val.into()).collect() };
+        let region = self.call1(CallBackKind::TableLookup(to_ieee(breakpoints)), &[x]);
+        let num_columns = columns.map_or(1, |columns| columns.len());
+        let stride = self.iconst((1 + num_columns * (degree + 1)) as i32);
+        let start = self.ins().imul(region, stride);
+
+        let table = self.dec_callback(CallBackKind::TableValue(to_ieee(table)));
+        let read = |ctx: &mut Self, offset: usize| {
+            let offset = ctx.iconst(offset as i32);
+            let idx = ctx.ins().iadd(start, offset);
+            let inst = ctx.call_declared(table, &[idx]);
+            ctx.dfg().first_result(inst)
+        };
+
+        let x0 = read(self, 0);
+        let t = self.ins().fsub(x, x0);
+        let mut res = None;
+        for column in 0..num_columns {
+            let offset = 1 + column * (degree + 1);
+            let mut val = read(self, offset + degree);
+            for i in (0..degree).rev() {
+                val = self.ins().fmul(t, val);
+                let coeff = read(self, offset + i);
+                val = self.ins().fadd(coeff, val);
+            }
+            if let Some(columns) = columns {
+                val = self.ins().fmul(columns[column], val);
+            }
+            res = Some(match res {
+                Some(res) => self.ins().fadd(res, val),
+                None => val,
+            });
+        }
+        res.unwrap()
+    }
+
+    /// Inputs outside of the table abort the evaluation with a fatal error
+    /// if the extrapolation method is `E`.
+    fn check_range(&mut self, x: Value, xs: &[f64], control: DimensionControl) {
+        if control.lower == Extrapolation::Error {
+            let start = self.fconst(xs[0]);
+            let below = self.ins().flt(x, start);
+            self.report_out_of_range(below, x, xs);
+        }
+        if control.upper == Extrapolation::Error {
+            let end = self.fconst(xs[xs.len() - 1]);
+            let above = self.ins().fgt(x, end);
+            self.report_out_of_range(above, x, xs);
+        }
+    }
+
+    fn report_out_of_range(&mut self, cond: Value, x: Value, xs: &[f64]) {
+        self.make_cond(cond, |ctx, out_of_range| {
+            if out_of_range {
+                let msg = format!(
+                    "$table_model: input %g is outside of the table range [{}, {}]\n",
+                    xs[0],
+                    xs[xs.len() - 1]
+                );
+                let fmt = ctx.sconst(&msg);
+                let kind = CallBackKind::Print {
+                    kind: DisplayKind::Fatal,
+                    arg_tys: vec![Type::Real.into()].into_boxed_slice(),
+                };
+                ctx.call(kind, &[fmt, x]);
+                ctx.ins().ret();
+
+                let unreachable_bb = ctx.create_block();
+                ctx.switch_to_block(unreachable_bb);
+                ctx.seal_block(unreachable_bb);
+            }
+        });
+    }
+}
+
+fn degree(interpolation: Interpolation) -> usize {
+    match interpolation {
+        Interpolation::Discrete => 0,
+        Interpolation::Linear => 1,
+        Interpolation::Quadratic => 2,
+        Interpolation::Cubic => 3,
+    }
+}
+
+/// The borders between the regions returned by [`region_polynomials`].
+fn breakpoints(xs: &[f64], control: DimensionControl) -> Vec<f64> {
+    if control.interpolation == Interpolation::Discrete {
+        // the closest point is used so the regions are split at the midpoints
+        xs.windows(2).map(|it| 0.5 * (it[0] + it[1])).collect()
+    } else {
+        xs.to_owned()
+    }
+}
+
+/// Returns `(x0, [c0, c1, c2, c3])` for each region: the interpolant in that region is
+/// `c0 + c1 * t + c2 * t^2 + c3 * t^3` with `t = x - x0`.
+///
+/// Region `0` lies below the table, region `n` above the table and region `i` (in between)
+/// is the segment from `xs[i - 1]` to `xs[i]`. Discrete interpolation instead uses the value
+/// of data point `i` in region `i`.
+fn region_polynomials(xs: &[f64], ys: &[f64], control: DimensionControl) -> Vec<(f64, [f64; 4])> {
+    let n = xs.len();
+    if control.interpolation == Interpolation::Discrete {
+        return ys.iter().map(|&y| (0.0, [y, 0.0, 0.0, 0.0])).collect();
+    }
+
+    let segments = segment_polynomials(xs, ys, control.interpolation);
+
+    let lower = match control.lower {
+        Extrapolation::Linear => [ys[0], segments[0][1], 0.0, 0.0],
+        _ => [ys[0], 0.0, 0.0, 0.0],
+    };
+    let upper = match control.upper {
+        Extrapolation::Linear => {
+            let [_, b, c, d] = segments[n - 2];
+            let h = xs[n - 1] - xs[n - 2];
+            [ys[n - 1], b + h * (2.0 * c + 3.0 * d * h), 0.0, 0.0]
+        }
+        _ => [ys[n - 1], 0.0, 0.0, 0.0],
+    };
+
+    once((xs[0], lower))
+        .chain(xs.iter().copied().zip(segments))
+        .chain(once((xs[n - 1], upper)))
+        .collect()
+}
+
+/// The polynomial between `xs[i]` and `xs[i + 1]` for each segment `i` (with `t = x - xs[i]`).
+fn segment_polynomials(xs: &[f64], ys: &[f64], interpolation: Interpolation) -> Vec<[f64; 4]> {
+    let n = xs.len();
+    let slope = |i: usize| (ys[i + 1] - ys[i]) / (xs[i + 1] - xs[i]);
+    match interpolation {
+        Interpolation::Linear => (0..n - 1).map(|i| [ys[i], slope(i), 0.0, 0.0]).collect(),
+        Interpolation::Quadratic => (0..n - 1)
+            .map(|i| {
+                // the polynomial passes through the points j, j + 1 and j + 2
+                // Newton form: y_j + (x - x_j) * (f01 + (x - x_j+1) * f012)
+                let j = i.min(n - 3);
+                let (f01, f12) = (slope(j), slope(j + 1));
+                let f012 = (f12 - f01) / (xs[j + 2] - xs[j]);
+                let dx0 = xs[i] - xs[j];
+                let dx1 = xs[i] - xs[j + 1];
+                [ys[j] + dx0 * (f01 + dx1 * f012), f01 + (dx0 + dx1) * f012, f012, 0.0]
+            })
+            .collect(),
+        Interpolation::Cubic => {
+            let curvature = spline_curvature(xs, ys);
+            (0..n - 1)
+                .map(|i| {
+                    let h = xs[i + 1] - xs[i];
+                    let (m0, m1) = (curvature[i], curvature[i + 1]);
+                    [ys[i], slope(i) - h * (2.0 * m0 + m1) / 6.0, 0.5 * m0, (m1 - m0) / (6.0 * h)]
+                })
+                .collect()
+        }
+        Interpolation::Discrete => unreachable!(),
+    }
+}
+
+/// Calculates the second derivatives of a natural cubic spline at each data point
+/// by solving the tridiagonal system with the Thomas algorithm.
+fn spline_curvature(xs: &[f64], ys: &[f64]) -> Vec<f64> {
+    let n = xs.len();
+    let h: Vec<_> = xs.windows(2).map(|it| it[1] - it[0]).collect();
+    let slopes: Vec<_> = (0..n - 1).map(|i| (ys[i + 1] - ys[i]) / h[i]).collect();
+
+    // row i: h[i - 1] * m[i - 1] + 2 * (h[i - 1] + h[i]) * m[i] + h[i] * m[i + 1]
+    //          = 6 * (slopes[i] - slopes[i - 1])
+    // with m[0] = m[n - 1] = 0
+    let mut upper = vec![0f64; n];
+    let mut rhs = vec![0f64; n];
+    for i in 1..n - 1 {
+        let diag = 2.0 * (h[i - 1] + h[i]) - h[i - 1] * upper[i - 1];
+        upper[i] = h[i] / diag;
+        rhs[i] = (6.0 * (slopes[i] - slopes[i - 1]) - h[i - 1] * rhs[i - 1]) / diag;
+    }
+
+    let mut curvature = vec![0f64; n];
+    for i in (1..n - 1).rev() {
+        curvature[i] = rhs[i] - upper[i] * curvature[i + 1];
+    }
+
+    curvature
+}
@@ -0,0 +1,233 @@
+//! Lowering of the `transition` and `slew` analog operators.
+//!
+//! Both operators need to remember values from the last accepted timestep. These values are
+//! kept in the state vector of the simulator: the previous value of a state is read with
+//! [`ParamKind::PrevOperatorState`] and the new value is written with
+//! [`CallBackKind::StoreOperatorState`](crate::CallBackKind::StoreOperatorState).
+//!
+//! During DC and IC analysis (when integration is disabled) both operators simply pass their
+//! input through. Their state is reset to that value so that a subsequent transient analysis
+//! starts from the operating point.
+//!
+//! During transient analysis the output is piecewise linear:
+//!
+//! * `transition(x, td, rise, fall)` starts a new ramp whenever `x` differs from the target of
+//!   the previous ramp. The ramp starts `td` after the change at the current output and reaches
+//!   `x` after `rise` (or `fall` if the output decreases). A missing rise time is replaced with
+//!   `` `default_transition `` during body lowering, without that directive (or for a zero rise
+//!   time) a step is produced. The fall time defaults to the rise time and the tolerance is
+//!   ignored.
+//! * `slew(x, max_pos, max_neg)` follows `x` but the slope of the output is limited to
+//!   `[max_neg, max_pos]`. If `max_neg` is omitted it defaults to `-max_pos`. Without any
+//!   slew rate the input is passed through unchanged.
+//!
+//! `$bound_step` is used to force the simulator to place timesteps at the corners of these
+//! ramps. Whenever the target of `transition` or the input of `slew` changes, the simulator is
+//! informed with `$discontinuity` so that it can restart the integration at the new corner.
+
+use hir::ExprId;
+use mir::builder::InstBuilder;
+use mir::{Value, F_ZERO};
+
+use crate::body::BodyLoweringCtx;
+use crate::{CallBackKind, OperatorStateKind, ParamKind, PlaceKind};
+
+impl BodyLoweringCtx<'_, '_, '_> {
+    pub(crate) fn lower_transition(&mut self, args: &[ExprId]) -> Value {
+        let input = self.lower_expr(args[0]);
+        if self.ctx.no_equations {
+            return input;
+        }
+
+        let delay = args.get(1).map_or(F_ZERO, |&arg| self.lower_expr(arg));
+        let rise = args.get(2).map_or(F_ZERO, |&arg| self.lower_expr(arg));
+        let fall = args.get(3).map_or(rise, |&arg| self.lower_expr(arg));
+        self.transition(input, delay, rise, fall)
+    }
+
+    /// Lowers the transient behaviour of `transition(input, delay, rise, fall)`,
+    /// also used to smooth the output of other operators
+    pub(crate) fn transition(
+        &mut self,
+        input: Value,
+        delay: Value,
+        rise: Value,
+        fall: Value,
+    ) -> Value {
+        // the current ramp goes from `start` (at `start_time`) to `target`
+        let (target_state, prev_target) = self.ctx.operator_state(OperatorStateKind::Transition);
+        let (start_state, prev_start) = self.ctx.operator_state(OperatorStateKind::Transition);
+        let (time_state, prev_time) = self.ctx.operator_state(OperatorStateKind::Transition);
+
+        let time = self.ctx.use_param(ParamKind::Abstime);
+        let enable_integration = self.ctx.use_param(ParamKind::EnableIntegration);
+        let [start, start_time, res] =
+            self.lower_multi_select(enable_integration, |mut ctx, transient| {
+                if !transient {
+                    return [input, time, input];
+                }
+
+                let changed = ctx.ctx.ins().fne(input, prev_target);
+                let [start, start_time] = ctx.lower_multi_select(changed, |mut ctx, changed| {
+                    if changed {
+                        ctx.discontinuity();
+                        // the new ramp starts at the current value of the previous ramp
+                        let duration = ctx.ramp_duration(prev_start, prev_target, rise, fall);
+                        let start = ctx.ramp(prev_start, prev_target, prev_time, duration, time);
+                        [start, ctx.ctx.ins().fadd(time, delay)]
+                    } else {
+                        [prev_start, prev_time]
+                    }
+                });
+
+                let duration = ctx.ramp_duration(start, input, rise, fall);
+                let res = ctx.ramp(start, input, start_time, duration, time);
+
+                // the next corner is either the start or the end of the ramp
+                let end_time = ctx.ctx.ins().fadd(start_time, duration);
+                let delayed = ctx.ctx.ins().flt(time, start_time);
+                let corner = ctx.lower_select_with(delayed, |_| start_time, |_| end_time);
+                let step = ctx.ctx.ins().fsub(corner, time);
+                let inside_ramp = ctx.ctx.ins().fgt(step, F_ZERO);
+                ctx.lower_cond_with(inside_ramp, |mut ctx, inside_ramp| {
+                    if inside_ramp {
+                        ctx.bound_step(step)
+                    }
+                });
+
+                [start, start_time, res]
+            });
+
+        self.ctx.store_operator_state(target_state, input);
+        self.ctx.store_operator_state(start_state, start);
+        self.ctx.store_operator_state(time_state, start_time);
+        res
+    }
+
+    pub(crate) fn lower_slew(&mut self, args: &[ExprId]) -> Value {
+        let input = self.lower_expr(args[0]);
+        if self.ctx.no_equations || args.len() == 1 {
+            return input;
+        }
+
+        let max_rise = self.lower_expr(args[1]);
+        let max_fall = if let Some(&arg) = args.get(2) {
+            self.lower_expr(arg)
+        } else {
+            self.ctx.ins().fneg(max_rise)
+        };
+
+        let (out_state, prev_out) = self.ctx.operator_state(OperatorStateKind::Slew);
+        let (time_state, prev_time) = self.ctx.operator_state(OperatorStateKind::Slew);
+        let (input_state, prev_input) = self.ctx.operator_state(OperatorStateKind::Slew);
+
+        let time = self.ctx.use_param(ParamKind::Abstime);
+        let enable_integration = self.ctx.use_param(ParamKind::EnableIntegration);
+        let res = self.lower_select_with(
+            enable_integration,
+            |mut ctx| {
+                let changed = ctx.ctx.ins().fne(input, prev_input);
+                ctx.lower_cond_with(changed, |mut ctx, changed| {
+                    if changed {
+                        ctx.discontinuity()
+                    }
+                });
+                let timestep = ctx.ctx.ins().fsub(time, prev_time);
+                let delta = ctx.ctx.ins().fsub(input, prev_out);
+                ctx.slew_limit(prev_out, delta, timestep, max_rise, max_fall, input)
+            },
+            |_| input,
+        );
+
+        self.ctx.store_operator_state(out_state, res);
+        self.ctx.store_operator_state(time_state, time);
+        self.ctx.store_operator_state(input_state, input);
+        res
+    }
+
+    /// Limits the change `delta` of the output during `timestep` to the range
+    /// `[max_fall * timestep, max_rise * timestep]`
+    fn slew_limit(
+        &mut self,
+        prev_out: Value,
+        delta: Value,
+        timestep: Value,
+        max_rise: Value,
+        max_fall: Value,
+        input: Value,
+    ) -> Value {
+        let max_delta = self.ctx.ins().fmul(max_rise, timestep);
+        let min_delta = self.ctx.ins().fmul(max_fall, timestep);
+        let rising = self.ctx.ins().fgt(delta, max_delta);
+        self.lower_select_with(
+            rising,
+            |mut ctx| ctx.slew(prev_out, delta, max_delta, max_rise),
+            |mut ctx| {
+                let falling = ctx.ctx.ins().flt(delta, min_delta);
+                ctx.lower_select_with(
+                    falling,
+                    |mut ctx| ctx.slew(prev_out, delta, min_delta, max_fall),
+                    |_| input,
+                )
+            },
+        )
+    }
+
+    /// Returns the output of a slew rate limited ramp and bounds the timestep
+    /// so that the simulator hits the point where the ramp reaches the input.
+    fn slew(&mut self, prev_out: Value, delta: Value, max_delta: Value, rate: Value) -> Value {
+        let remaining = self.ctx.ins().fsub(delta, max_delta);
+        let step = self.ctx.ins().fdiv(remaining, rate);
+        self.bound_step(step);
+        self.ctx.ins().fadd(prev_out, max_delta)
+    }
+
+    fn ramp_duration(&mut self, start: Value, target: Value, rise: Value, fall: Value) -> Value {
+        let rising = self.ctx.ins().fgt(target, start);
+        self.lower_select_with(rising, |_| rise, |_| fall)
+    }
+
+    /// Evaluates a linear ramp from `start` to `target` that begins at `start_time`
+    fn ramp(
+        &mut self,
+        start: Value,
+        target: Value,
+        start_time: Value,
+        duration: Value,
+        time: Value,
+    ) -> Value {
+        let started = self.ctx.ins().fge(time, start_time);
+        self.lower_select_with(
+            started,
+            |mut ctx| {
+                let end_time = ctx.ctx.ins().fadd(start_time, duration);
+                let finished = ctx.ctx.ins().fge(time, end_time);
+                ctx.lower_select_with(
+                    finished,
+                    |_| target,
+                    |ctx| {
+                        let elapsed = ctx.ctx.ins().fsub(time, start_time);
+                        let fraction = ctx.ctx.ins().fdiv(elapsed, duration);
+                        let delta = ctx.ctx.ins().fsub(target, start);
+                        let delta = ctx.ctx.ins().fmul(delta, fraction);
+                        ctx.ctx.ins().fadd(start, delta)
+                    },
+                )
+            },
+            |_| start,
+        )
+    }
+
+    /// Informs the simulator that the output changes abruptly at the current time
+    pub(crate) fn discontinuity(&mut self) {
+        self.ctx.call(CallBackKind::Discontinuity, &[]);
+    }
+
+    /// Restricts the next timestep to `step` unless a smaller bound was already set
+    pub(crate) fn bound_step(&mut self, step: Value) {
+        let prev_step = self.ctx.use_place(PlaceKind::BoundStep);
+        let smaller = self.ctx.ins().flt(step, prev_step);
+        let step = self.lower_select_with(smaller, |_| step, |_| prev_step);
+        self.ctx.def_place(PlaceKind::BoundStep, step);
+    }
+}
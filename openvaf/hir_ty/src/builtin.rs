@@ -263,11 +263,11 @@ bultins! {
 
 
     TRANSITION = const {
-        fn TRANSITION_NO_ARGS(Val(Integer)) -> Real;
-        fn TRANSITION_DELAY(Val(Integer),Val(Real)) -> Real;
-        fn TRANSITION_DELAY_RISET(Val(Integer),Val(Real)) -> Real;
-        fn TRANSITION_DELAY_RISET_FALLT(Val(Integer),Val(Real),Val(Real)) -> Real;
-        fn TRANSITION_DELAY_RISET_FALLT_TOL(Val(Integer),Val(Real),Val(Real), Val(Real)) -> Real;
+        fn TRANSITION_NO_ARGS(Val(Real)) -> Real;
+        fn TRANSITION_DELAY(Val(Real),Val(Real)) -> Real;
+        fn TRANSITION_DELAY_RISET(Val(Real),Val(Real),Val(Real)) -> Real;
+        fn TRANSITION_DELAY_RISET_FALLT(Val(Real),Val(Real),Val(Real),Val(Real)) -> Real;
+        fn TRANSITION_DELAY_RISET_FALLT_TOL(Val(Real),Val(Real),Val(Real),Val(Real), Val(Real)) -> Real;
     }
 
 
@@ -276,6 +276,25 @@ bultins! {
         fn LAST_CROSSING_DIRECTION(Val(Real),Val(Integer)) -> Real;
     }
 
+    CROSS = {
+        fn CROSS_NO_DIRECTION(Val(Real)) -> Void;
+        fn CROSS_DIRECTION(Val(Real),Val(Integer)) -> Void;
+        fn CROSS_DIRECTION_TIME_TOL(Val(Real),Val(Integer),Val(Real)) -> Void;
+        fn CROSS_DIRECTION_TIME_TOL_EXPR_TOL(Val(Real),Val(Integer),Val(Real),Val(Real)) -> Void;
+    }
+
+    ABOVE = {
+        fn ABOVE_NO_TOL(Val(Real)) -> Void;
+        fn ABOVE_TIME_TOL(Val(Real),Val(Real)) -> Void;
+        fn ABOVE_TIME_TOL_EXPR_TOL(Val(Real),Val(Real),Val(Real)) -> Void;
+    }
+
+    TIMER = {
+        fn TIMER_NO_PERIOD(Val(Real)) -> Void;
+        fn TIMER_PERIOD(Val(Real),Val(Real)) -> Void;
+        fn TIMER_PERIOD_TIME_TOL(Val(Real),Val(Real),Val(Real)) -> Void;
+    }
+
     fn BASIC_IO(Val(Integer)) -> Integer;
 
      FOPEN = {
@@ -335,17 +354,17 @@ bultins! {
 
 
     DIST_1_ARG = const {
-        fn DIST_1_ARG_SEED(Var(Integer),Val(Integer)) -> Real;
-        fn DIST_1_ARG_CONST_SEED(Param(Integer),Val(Integer)) -> Real;
-        fn DIST_1_ARG_CONST_NAME(Var(Integer),Val(Integer),Literal(String)) -> Real;
-        fn DIST_1_ARG_CONST_SEED_NAME(Param(Integer),Val(Integer),Literal(String)) -> Real;
+        fn DIST_1_ARG_SEED(Var(Integer),Val(Integer)) -> Integer;
+        fn DIST_1_ARG_CONST_SEED(Param(Integer),Val(Integer)) -> Integer;
+        fn DIST_1_ARG_CONST_NAME(Var(Integer),Val(Integer),Literal(String)) -> Integer;
+        fn DIST_1_ARG_CONST_SEED_NAME(Param(Integer),Val(Integer),Literal(String)) -> Integer;
     }
 
     DIST_2_ARG = const {
-        fn DIST_2_ARG_SEED(Var(Integer),Val(Integer),Val(Integer)) -> Real;
-        fn DIST_2_ARG_CONST_SEED(Param(Integer),Val(Real),Val(Integer)) -> Real;
-        fn DIST_2_ARG_CONST_NAME(Var(Integer),Val(Integer),Val(Integer),Literal(String)) -> Real;
-        fn DIST_2_ARG_CONST_SEED_NAME(Param(Integer),Val(Integer),Val(Integer),Literal(String)) -> Real;
+        fn DIST_2_ARG_SEED(Var(Integer),Val(Integer),Val(Integer)) -> Integer;
+        fn DIST_2_ARG_CONST_SEED(Param(Integer),Val(Integer),Val(Integer)) -> Integer;
+        fn DIST_2_ARG_CONST_NAME(Var(Integer),Val(Integer),Val(Integer),Literal(String)) -> Integer;
+        fn DIST_2_ARG_CONST_SEED_NAME(Param(Integer),Val(Integer),Val(Integer),Literal(String)) -> Integer;
     }
 
     SIMPROBE = const {
@@ -353,8 +372,13 @@ bultins! {
         fn SIMPROBE_DEFAULT(Val(String),Val(String),Val(Real))->Real;
     }
 
-    const fn TEST_PLUSARGS(Val(String)) -> Bool;
-    const fn VALUE_PLUSARGS(Val(String),Val(String))->Bool;
+    const fn TEST_PLUSARGS(Val(String)) -> Integer;
+
+    VALUE_PLUSARGS = const {
+        fn VALUE_PLUSARGS_INT(Val(String),Var(Integer))->Integer;
+        fn VALUE_PLUSARGS_REAL(Val(String),Var(Real))->Integer;
+        fn VALUE_PLUSARGS_STR(Val(String),Var(String))->Integer;
+    }
 
     fn ANALOG_NODE_ALIAS(Node,Val(String)) -> Integer;
 
@@ -370,7 +394,7 @@ bultins! {
     fn BOUND_STEP(Val(Real)) -> Void;
 }
 
-// TODO TABLE_MODEL
+const TABLE_MODEL: BuiltinInfo = BuiltinInfo::special_cased_pure(2, None);
 
 const DDX: BuiltinInfo = BuiltinInfo::special_cased_pure(2, Some(2));
 pub const DDX_TEMP: Signature = Signature(0);
@@ -4,7 +4,7 @@ use hir_def::BuiltIn;
 
 use crate::builtin::*;
 
-const BUILTIN_INFO: [BuiltinInfo; 111usize] = [
+const BUILTIN_INFO: [BuiltinInfo; 115usize] = [
     ABS,
     ACOS,
     ACOSH,
@@ -89,6 +89,7 @@ const BUILTIN_INFO: [BuiltinInfo; 111usize] = [
     PORT_CONNECTED,
     ANALOG_NODE_ALIAS,
     ANALOG_PORT_ALIAS,
+    TABLE_MODEL,
     TEST_PLUSARGS,
     VALUE_PLUSARGS,
     BOUND_STEP,
@@ -116,5 +117,8 @@ const BUILTIN_INFO: [BuiltinInfo; 111usize] = [
     LAST_CROSSING,
     SLEW,
     TRANSITION,
+    ABOVE,
+    CROSS,
+    TIMER,
 ];
 pub(crate) fn builtin_info(builtin: BuiltIn) -> BuiltinInfo { BUILTIN_INFO[builtin as u8 as usize] }
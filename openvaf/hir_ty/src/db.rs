@@ -3,8 +3,8 @@ use std::sync::Arc;
 use hir_def::db::HirDefDB;
 use hir_def::nameres::{ResolvedPath, ScopeDefItem};
 use hir_def::{
-    AliasParamId, BranchId, DefWithBodyId, DisciplineId, Lookup, NatureAttrId, NatureId, NodeId,
-    ParamId, ParamSysFun, Type,
+    AliasParamId, BranchId, DefWithBodyId, DisciplineId, Domain, Lookup, NatureAttrId, NatureId,
+    NodeId, ParamId, ParamSysFun, Type,
 };
 use stdx::Upcast;
 
@@ -39,6 +39,11 @@ pub trait HirTyDB: HirDefDB + Upcast<dyn HirDefDB> {
     #[salsa::transparent]
     fn node_discipline(&self, node: NodeId) -> Option<DisciplineId>;
 
+    /// Whether `node` carries a real value instead of a potential/flow pair.
+    /// This is the case for `wreal` nets and nets with a discrete discipline.
+    #[salsa::transparent]
+    fn node_is_real(&self, node: NodeId) -> bool;
+
     #[salsa::transparent]
     fn param_ty(&self, param: ParamId) -> Type;
 
@@ -100,6 +105,13 @@ fn node_discipline(db: &dyn HirTyDB, node: NodeId) -> Option<DisciplineId> {
     def_map.resolve_local_item_in_scope(def_map.root(), discipline).ok()
 }
 
+fn node_is_real(db: &dyn HirTyDB, node: NodeId) -> bool {
+    db.node_data(node).is_real
+        || db.node_discipline(node).map_or(false, |discipline| {
+            db.discipline_data(discipline).domain == Some(Domain::Discrete)
+        })
+}
+
 fn param_ty(db: &dyn HirTyDB, param: ParamId) -> Type {
     match db.param_data(param).ty.clone() {
         Some(ty) => ty,
@@ -275,6 +275,44 @@ impl Diagnostic for InferenceDiagnosticWrapped<'_> {
                         "help: expected one of the following\nbranch current access: I(branch), I(a,b)\nnode voltage: V(x)\nexplicit voltage: V(x,y)\ntemperature: $temperature".to_owned(),
                     ])
             }
+            InferenceDiagnostic::InvalidNetIndex { index, msb, lsb } => {
+                let src = self.parse.to_file_span(
+                    self.body_sm.expr_map_back[index].as_ref().unwrap().range(),
+                    self.sm,
+                );
+
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: src.file,
+                        range: src.range.into(),
+                        message: format!("expected a constant index within [{}:{}]", msb, lsb),
+                    }])
+                    .with_message("invalid index into vector net")
+                    .with_notes(vec![
+                        "help: vector nets can only be indexed with constant expressions of literals, genvars and parameters".to_owned(),
+                    ])
+            }
+            InferenceDiagnostic::ArrayIndexOutOfBounds { index, offset, len } => {
+                let src = self.parse.to_file_span(
+                    self.body_sm.expr_map_back[index].as_ref().unwrap().range(),
+                    self.sm,
+                );
+
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: src.file,
+                        range: src.range.into(),
+                        message: format!(
+                            "expected an index within [{offset}:{}]",
+                            offset as i64 + len as i64 - 1
+                        ),
+                    }])
+                    .with_message(format!(
+                        "array index is out of bounds for an array of length {len}"
+                    ))
+            }
             InferenceDiagnostic::NonStandardUnknown { e, .. } => {
                 let src = self
                     .parse
@@ -6,7 +6,7 @@ use ahash::AHashMap;
 use arena::ArenaMap;
 use hir_def::body::Body;
 use hir_def::db::HirDefDB;
-use hir_def::expr::{CaseCond, Literal};
+use hir_def::expr::{CaseCond, Event, Literal};
 use hir_def::nameres::diagnostics::PathResolveError;
 use hir_def::nameres::{NatureAccess, ResolvedPath, ScopeDefItem, ScopeDefItemKind};
 use hir_def::{
@@ -27,6 +27,7 @@ use crate::db::{Alias, HirTyDB};
 use crate::diagnostics::{ArrayTypeMismatch, SignatureMismatch, TypeMismatch};
 use crate::inference::fmt_parser::parse_real_fmt_spec;
 use crate::lower::{BranchTy, DisciplineAccess};
+use crate::table_model::num_inputs;
 use crate::types::{default_return_ty, BuiltinInfo, Signature, SignatureData, Ty, TyRequirement};
 
 mod fmt_parser;
@@ -42,6 +43,8 @@ pub enum ResolvedFun {
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum AssignDst {
     Var(VarId),
+    ArrayElement { var: VarId, index: ExprId },
+    RealNet(NodeId),
     FunVar { fun: FunctionId, arg: Option<LocalFunctionArgId> },
     Flow(BranchWrite),
     Potential(BranchWrite),
@@ -55,12 +58,18 @@ pub enum BranchWrite {
 
 impl AssignDst {
     pub fn ty(&self, db: &dyn HirDefDB) -> Type {
-        if let AssignDst::Var(var) = *self {
-            let var = var.lookup(db);
-            let tree = var.item_tree(db);
-            tree[var.id].ty.clone()
-        } else {
-            Type::Real
+        match *self {
+            AssignDst::Var(var) => {
+                let var = var.lookup(db);
+                let tree = var.item_tree(db);
+                tree[var.id].ty.clone()
+            }
+            AssignDst::ArrayElement { var, .. } => {
+                let var = var.lookup(db);
+                let tree = var.item_tree(db);
+                tree[var.id].ty.base_type().clone()
+            }
+            _ => Type::Real,
         }
     }
 }
@@ -149,6 +158,9 @@ impl Ctx<'_> {
                     }
                 }
             }
+            Stmt::EventControl { event: Event::Monitored(event), .. } => {
+                self.infere_expr(stmt, event);
+            }
             _ => (),
         };
 
@@ -195,9 +207,12 @@ impl Ctx<'_> {
         assignment_kind: ast::AssignOp,
     ) -> Option<Type> {
         let e = self.infere_expr(stmt, expr);
+        let element_dst = self.array_element_dst(expr);
 
         let (dst, ty) = match e? {
             Ty::Var(ty, var) => (AssignDst::Var(var), ty),
+            Ty::RealNet(node) => (AssignDst::RealNet(node), Type::Real),
+            Ty::Val(ty) if element_dst.is_some() => (element_dst?, ty),
             Ty::FunctionVar { fun, ty, arg } => (AssignDst::FunVar { fun, arg }, ty),
             Ty::Val(Type::Real)
                 if matches!(
@@ -248,7 +263,13 @@ impl Ctx<'_> {
 
         // check that the correct operator is used
         match (&dst, assignment_kind) {
-            (AssignDst::Var(_) | AssignDst::FunVar { .. }, ast::AssignOp::Contribute) => {
+            (
+                AssignDst::Var(_)
+                | AssignDst::ArrayElement { .. }
+                | AssignDst::FunVar { .. }
+                | AssignDst::RealNet(_),
+                ast::AssignOp::Contribute,
+            ) => {
                 self.result.diagnostics.push(InferenceDiagnostic::InvalidAssignDst {
                     e: expr,
                     maybe_different_operand: Some(ast::AssignOp::Assign),
@@ -269,6 +290,16 @@ impl Ctx<'_> {
         Some(ty)
     }
 
+    /// Array elements can only be assigned if the indexed array is a variable
+    fn array_element_dst(&self, expr: ExprId) -> Option<AssignDst> {
+        if let Expr::Index { base, index } = self.body.exprs[expr] {
+            if let Ty::Var(_, var) = self.result.expr_types[base] {
+                return Some(AssignDst::ArrayElement { var, index });
+            }
+        }
+        None
+    }
+
     fn infere_cond(&mut self, stmt: StmtId, expr: ExprId) {
         if let Some(ty) = self.infere_expr(stmt, expr) {
             self.expect::<false>(expr, None, ty, Cow::Borrowed(&[TyRequirement::Condition]));
@@ -284,10 +315,14 @@ impl Ctx<'_> {
             }
 
             Expr::Path { ref path, port: false } => match self.resolve_path(stmt, expr, path)? {
-                ScopeDefItem::BlockId(_) | ScopeDefItem::ModuleId(_) => Ty::Scope,
+                ScopeDefItem::BlockId(_)
+                | ScopeDefItem::ModuleId(_)
+                | ScopeDefItem::ParamsetId(_) => Ty::Scope,
                 ScopeDefItem::NatureId(nature) => Ty::Nature(nature),
                 ScopeDefItem::DisciplineId(discipline) => Ty::Discipline(discipline),
+                ScopeDefItem::NodeId(node) if self.db.node_is_real(node) => Ty::RealNet(node),
                 ScopeDefItem::NodeId(node) => Ty::Node(node),
+                ScopeDefItem::NodeBusId(bus) => Ty::NodeBus(bus),
                 ScopeDefItem::VarId(var) => Ty::Var(self.db.var_data(var).ty.clone(), var),
                 ScopeDefItem::ParamId(param) => Ty::Param(self.db.param_ty(param), param),
                 ScopeDefItem::AliasParamId(param) => match self.db.resolve_alias(param)? {
@@ -391,6 +426,7 @@ impl Ctx<'_> {
             }
             Expr::Array(ref args) if args.is_empty() => Ty::Val(Type::EmptyArray),
             Expr::Array(ref args) => self.infere_array(stmt, args)?,
+            Expr::Index { base, index } => self.infere_index(stmt, base, index)?,
             Expr::Literal(Literal::Float(_)) => Ty::Literal(Type::Real),
             Expr::Literal(Literal::Int(_)) => Ty::Literal(Type::Integer),
             // +/- inf can only appear in param bounds.
@@ -597,6 +633,11 @@ impl Ctx<'_> {
                 return (Some(Ty::Val(Type::Real)), true);
             }
 
+            BuiltIn::table_model => {
+                self.infere_table_model(stmt, expr, args);
+                return (Some(Ty::Val(Type::Real)), true);
+            }
+
             BuiltIn::limit => {
                 infere_args = &args[0..2];
                 Cow::Borrowed(TiSlice::from_ref(info.signatures))
@@ -633,6 +674,12 @@ impl Ctx<'_> {
             | BuiltIn::error
             | BuiltIn::info
             | BuiltIn::fatal => self.infere_display(stmt, args),
+            // the first argument is the file descriptor
+            BuiltIn::fwrite
+            | BuiltIn::fdisplay
+            | BuiltIn::fstrobe
+            | BuiltIn::fmonitor
+            | BuiltIn::fdebug => self.infere_display(stmt, &args[1..]),
 
             _ => (),
         }
@@ -903,6 +950,44 @@ impl Ctx<'_> {
         }
     }
 
+    fn infere_table_model(&mut self, stmt: StmtId, expr: ExprId, args: &[ExprId]) {
+        let arg_types: Vec<_> = args.iter().map(|arg| self.infere_expr(stmt, *arg)).collect();
+
+        // the inputs are followed by the data source which is either a file name
+        // or an array for each input and one array for the output values
+        let dims = num_inputs(&self.result, args).max(1);
+        let is_file = matches!(
+            arg_types.get(dims).and_then(|ty| ty.as_ref()?.to_value()),
+            Some(Type::String)
+        );
+        let num_data_args = if is_file { 1 } else { dims + 1 };
+        let min_args = dims + num_data_args;
+        // the control string is optional
+        let max_args = min_args + 1;
+
+        if args.len() < min_args || args.len() > max_args {
+            self.result.diagnostics.push(InferenceDiagnostic::ArgCntMismatch {
+                expected: if args.len() < min_args { min_args } else { max_args },
+                found: args.len(),
+                expr,
+                exact: false,
+            });
+            return;
+        }
+
+        for (i, (arg, ty)) in zip(args, arg_types).enumerate() {
+            let ty = if let Some(ty) = ty { ty } else { continue };
+            let req: &'static [TyRequirement] = if i < dims {
+                &[TyRequirement::Val(Type::Real)]
+            } else if i < min_args && !is_file {
+                &[TyRequirement::ArrayAnyLength { ty: Type::Real }]
+            } else {
+                &[TyRequirement::Literal(Type::String)]
+            };
+            self.expect::<false>(*arg, None, ty, Cow::Borrowed(req));
+        }
+    }
+
     fn infere_array(&mut self, stmt: StmtId, args: &[ExprId]) -> Option<Ty> {
         let infere_value_ty = |sel: &mut Self, arg| -> Option<Type> {
             sel.infere_expr(stmt, arg).and_then(|ty| {
@@ -963,7 +1048,62 @@ impl Ctx<'_> {
             }
         }
 
-        Some(Ty::Val(ty))
+        Some(Ty::Val(ty.to_dim(&[args.len() as u32])))
+    }
+
+    fn infere_index(&mut self, stmt: StmtId, base: ExprId, index: ExprId) -> Option<Ty> {
+        if let Some(ty) = self.infere_expr(stmt, index) {
+            self.expect::<false>(
+                index,
+                None,
+                ty,
+                Cow::Borrowed(&[TyRequirement::Val(Type::Integer)]),
+            );
+        }
+
+        let ty = self.infere_expr(stmt, base)?;
+        if let Ty::NodeBus(bus) = ty {
+            // valid accesses to vector net elements are already resolved during lowering
+            let bus = bus.lookup(self.db.upcast()).bus(self.db.upcast());
+            self.result.diagnostics.push(InferenceDiagnostic::InvalidNetIndex {
+                index,
+                msb: bus.msb,
+                lsb: bus.lsb,
+            });
+            return None;
+        }
+        self.expect::<true>(base, None, ty.clone(), Cow::Borrowed(&[TyRequirement::AnyArray]))?;
+        let offset = match ty {
+            Ty::Var(_, var) => self.db.var_data(var).array_offset,
+            Ty::Param(_, param) => self.db.param_data(param).array_offset,
+            _ => 0,
+        };
+        match ty.to_value()? {
+            Type::Array { ty, len } => {
+                if let Some(idx) = self.const_index(index) {
+                    if idx < offset || idx - offset >= len as i32 {
+                        self.result.diagnostics.push(InferenceDiagnostic::ArrayIndexOutOfBounds {
+                            index,
+                            offset,
+                            len,
+                        });
+                    }
+                }
+                Some(Ty::Val(*ty))
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the value of `index` if it is an integer literal (or a negated integer literal).
+    fn const_index(&self, index: ExprId) -> Option<i32> {
+        match self.body.exprs[index] {
+            Expr::Literal(Literal::Int(idx)) => Some(idx),
+            Expr::UnaryOp { expr, op: UnaryOp::Neg } => {
+                self.const_index(expr).and_then(i32::checked_neg)
+            }
+            _ => None,
+        }
     }
 
     fn infere_bin_op(
@@ -1261,6 +1401,16 @@ pub enum InferenceDiagnostic {
         e: ExprId,
         stmt: StmtId,
     },
+    InvalidNetIndex {
+        index: ExprId,
+        msb: i32,
+        lsb: i32,
+    },
+    ArrayIndexOutOfBounds {
+        index: ExprId,
+        offset: i32,
+        len: u32,
+    },
 }
 
 impl_from!(TypeMismatch,SignatureMismatch, ArrayTypeMismatch for InferenceDiagnostic);
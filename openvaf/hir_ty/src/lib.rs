@@ -3,6 +3,8 @@ pub mod db;
 pub mod diagnostics;
 pub mod inference;
 pub mod lower;
+pub mod noise_table;
+pub mod table_model;
 pub mod types;
 pub mod validation;
 
@@ -0,0 +1,121 @@
+//! Data of `noise_table` and `noise_table_log`.
+//!
+//! The table consists of (frequency, power) pairs. It is either specified inline as an array
+//! (`'{f1, pwr1, f2, pwr2, ...}`) or read from a file at compile time where each line contains
+//! one pair. The frequencies must be unique. Tables that are not in ascending order are sorted
+//! by frequency before they are interpolated.
+
+use basedb::FileId;
+use hir_def::body::Body;
+use hir_def::{Expr, ExprId, Literal};
+use stdx::impl_display;
+
+use crate::db::HirTyDB;
+use crate::table_model::{read_file, TableModelError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoiseTableError {
+    File(TableModelError),
+    NonConstantFile,
+    InvalidNumber { line: usize, val: String },
+    InvalidColumns { line: usize, found: usize },
+    OddLength { len: usize },
+    DuplicateFrequency { freq: String },
+    NoData,
+}
+
+impl_display! {
+    match NoiseTableError{
+        NoiseTableError::File(err) => "{}", err;
+        NoiseTableError::NonConstantFile => "the file name of a noise table must be a string literal";
+        NoiseTableError::InvalidNumber{line, val} => "'{}' in line {} is not a valid number", val, line;
+        NoiseTableError::InvalidColumns{line, found} => "line {} contains {} columns but a noise table file must contain exactly 2", line, found;
+        NoiseTableError::OddLength{len} => "a noise table must consist of (frequency, power) pairs but the array contains {} elements", len;
+        NoiseTableError::DuplicateFrequency{freq} => "the frequency {} was specified multiple times", freq;
+        NoiseTableError::NoData => "the noise table contains no data";
+    }
+}
+
+/// Reads the (frequency, power) pairs from the file `expr`. Files are searched like
+/// `$table_model` files. Each line contains one pair separated by whitespace (or a comma)
+/// and `#` starts a comment.
+pub fn read_noise_table_file(
+    db: &dyn HirTyDB,
+    root_file: FileId,
+    body: &Body,
+    expr: ExprId,
+) -> Result<Box<[(f64, f64)]>, NoiseTableError> {
+    let file = match body.exprs[expr] {
+        Expr::Literal(Literal::String(ref file)) => file,
+        _ => return Err(NoiseTableError::NonConstantFile),
+    };
+    let src = read_file(db, root_file, file).map_err(NoiseTableError::File)?;
+    parse_file(&src)
+}
+
+fn parse_file(src: &str) -> Result<Box<[(f64, f64)]>, NoiseTableError> {
+    let mut table = Vec::new();
+    for (i, line) in src.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let vals: Vec<_> = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|it| !it.is_empty())
+            .collect();
+        match *vals {
+            [] => continue,
+            [freq, pwr] => {
+                let parse = |val: &str| match val.parse::<f64>() {
+                    Ok(val) if val.is_finite() => Ok(val),
+                    _ => Err(NoiseTableError::InvalidNumber { line: i + 1, val: val.to_owned() }),
+                };
+                table.push((parse(freq)?, parse(pwr)?));
+            }
+            _ => return Err(NoiseTableError::InvalidColumns { line: i + 1, found: vals.len() }),
+        }
+    }
+    Ok(table.into_boxed_slice())
+}
+
+/// Checks that the frequencies of a table are unique. Returns whether the frequencies are
+/// in ascending order.
+pub fn check_frequencies(freqs: &[f64]) -> Result<bool, NoiseTableError> {
+    if freqs.is_empty() {
+        return Err(NoiseTableError::NoData);
+    }
+    let ascending = freqs.windows(2).all(|window| window[0] < window[1]);
+    if !ascending {
+        let mut sorted = freqs.to_owned();
+        sorted.sort_by(f64::total_cmp);
+        if let Some(window) = sorted.windows(2).find(|window| window[0] == window[1]) {
+            return Err(NoiseTableError::DuplicateFrequency { freq: window[0].to_string() });
+        }
+    }
+    Ok(ascending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file() {
+        let src = "# frequency power\n1 1e-20\n\n1e3, 2e-20 # comment\n";
+        assert_eq!(&*parse_file(src).unwrap(), &[(1.0, 1e-20), (1e3, 2e-20)]);
+        assert_eq!(parse_file("1 2 3"), Err(NoiseTableError::InvalidColumns { line: 1, found: 3 }));
+        assert_eq!(
+            parse_file("1 2\n1 x"),
+            Err(NoiseTableError::InvalidNumber { line: 2, val: "x".to_owned() })
+        );
+    }
+
+    #[test]
+    fn frequencies() {
+        assert_eq!(check_frequencies(&[1.0, 2.0, 3.0]), Ok(true));
+        assert_eq!(check_frequencies(&[2.0, 1.0, 3.0]), Ok(false));
+        assert_eq!(
+            check_frequencies(&[2.0, 1.0, 2.0]),
+            Err(NoiseTableError::DuplicateFrequency { freq: "2".to_owned() })
+        );
+        assert_eq!(check_frequencies(&[]), Err(NoiseTableError::NoData));
+    }
+}
@@ -0,0 +1,649 @@
+//! Data sources and control strings of `$table_model`.
+//!
+//! Table data is always read at compile time, either from a file or from array literals.
+//! The data points are organized into isolines: The points are grouped by their first
+//! coordinate, each group is grouped by the second coordinate and so on. During lowering
+//! each isoline is interpolated separately and the results are interpolated again along
+//! the previous dimension. This means that the data does not need to form a full grid.
+
+use std::cmp::Ordering;
+use std::io;
+
+use basedb::{FileId, FileReadError};
+use hir_def::body::Body;
+use hir_def::{Expr, ExprId, Literal, Type};
+use stdx::impl_display;
+use stdx::iter::zip;
+use syntax::ast::UnaryOp;
+
+use crate::db::HirTyDB;
+use crate::inference::InferenceResult;
+use crate::types::Ty;
+
+/// The coordinates and the value of each data point.
+type DataPoints = Vec<(Box<[f64]>, f64)>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interpolation {
+    /// `D`: the value of the closest data point
+    Discrete,
+    /// `1`
+    Linear,
+    /// `2`
+    Quadratic,
+    /// `3`: a natural cubic spline
+    Cubic,
+}
+
+impl Interpolation {
+    pub fn min_points(self) -> usize {
+        match self {
+            Interpolation::Discrete => 1,
+            Interpolation::Linear => 2,
+            Interpolation::Quadratic => 3,
+            Interpolation::Cubic => 4,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Extrapolation {
+    /// `C`: the value at the end of the table
+    Clamp,
+    /// `L`: continue with the slope at the end of the table
+    Linear,
+    /// `E`: inputs outside of the table are a fatal error
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DimensionControl {
+    pub interpolation: Interpolation,
+    pub lower: Extrapolation,
+    pub upper: Extrapolation,
+}
+
+impl Default for DimensionControl {
+    fn default() -> Self {
+        DimensionControl {
+            interpolation: Interpolation::Linear,
+            lower: Extrapolation::Linear,
+            upper: Extrapolation::Linear,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableData {
+    /// The last dimension: `ys[i]` is the output at `xs[i]`.
+    Values { xs: Box<[f64]>, ys: Box<[f64]> },
+    /// `isolines[i]` contains the data of the remaining dimensions at `xs[i]`.
+    Isolines { xs: Box<[f64]>, isolines: Box<[TableData]> },
+}
+
+impl TableData {
+    pub fn xs(&self) -> &[f64] {
+        match self {
+            TableData::Values { xs, .. } | TableData::Isolines { xs, .. } => xs,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableModelError {
+    InvalidControl(String),
+    ControlDimensionMismatch { expected: usize, found: usize },
+    InvalidColumn(String),
+    FileReadError { file: String, error: io::ErrorKind },
+    InvalidTextFormat { file: String },
+    InvalidNumber { line: usize, val: String },
+    MissingColumns { line: usize, expected: usize, found: usize },
+    NonConstantData,
+    ArrayLengthMismatch { expected: usize, found: usize },
+    DuplicatePoint { point: String },
+    NotEnoughPoints { required: usize, found: usize },
+    NoData,
+}
+
+impl_display! {
+    match TableModelError{
+        TableModelError::InvalidControl(control) => "invalid table control string '{}'", control;
+        TableModelError::ControlDimensionMismatch{expected, found} => "the control string specifies {} dimensions but the table has {} inputs", found, expected;
+        TableModelError::InvalidColumn(column) => "invalid dependent column '{}'", column;
+        TableModelError::FileReadError{file, error} => "failed to read '{}': {}", file, io::Error::from(*error);
+        TableModelError::InvalidTextFormat{file} => "failed to read '{}': file contents are not valid text", file;
+        TableModelError::InvalidNumber{line, val} => "'{}' in line {} is not a valid number", val, line;
+        TableModelError::MissingColumns{line, expected, found} => "line {} contains {} columns but at least {} are required", line, found, expected;
+        TableModelError::NonConstantData => "table data must be an array literal ('{{...}}) of constant numbers";
+        TableModelError::ArrayLengthMismatch{expected, found} => "all data arrays must have the same length: expected {} elements but found {}", expected, found;
+        TableModelError::DuplicatePoint{point} => "the data point ({}) was specified multiple times", point;
+        TableModelError::NotEnoughPoints{required, found} => "interpolation requires at least {} data points per dimension but an isoline only contains {}", required, found;
+        TableModelError::NoData => "the table contains no data points";
+    }
+}
+
+pub enum TableSource<'a> {
+    File(ExprId),
+    /// One array for each input followed by the output array.
+    Arrays(&'a [ExprId]),
+}
+
+/// The arguments of a `$table_model` call:
+/// `$table_model(inputs.., data_source [, control_string])`
+pub struct TableModelArgs<'a> {
+    pub inputs: &'a [ExprId],
+    pub source: TableSource<'a>,
+    pub control: Option<ExprId>,
+}
+
+impl<'a> TableModelArgs<'a> {
+    /// Splits the arguments of a `$table_model` call. Returns `None` if the arguments are
+    /// malformed (these errors are reported during type inference).
+    pub fn new(body: &Body, infer: &InferenceResult, args: &'a [ExprId]) -> Option<Self> {
+        let dims = num_inputs(infer, args);
+        if dims == 0 {
+            return None;
+        }
+
+        let (source, remaining) = match value_ty(infer, *args.get(dims)?) {
+            Some(Type::String) => (TableSource::File(args[dims]), &args[dims + 1..]),
+            _ => (TableSource::Arrays(args.get(dims..2 * dims + 1)?), &args[2 * dims + 1..]),
+        };
+
+        let control = match *remaining {
+            [] => None,
+            [control] => Some(control),
+            _ => return None,
+        };
+
+        let literals = control.into_iter().chain(match source {
+            TableSource::File(file) => Some(file),
+            TableSource::Arrays(_) => None,
+        });
+        for expr in literals {
+            if !matches!(body.exprs[expr], Expr::Literal(Literal::String(_))) {
+                return None;
+            }
+        }
+
+        Some(TableModelArgs { inputs: &args[..dims], source, control })
+    }
+}
+
+fn str_literal(body: &Body, expr: ExprId) -> &str {
+    match body.exprs[expr] {
+        Expr::Literal(Literal::String(ref lit)) => lit,
+        _ => unreachable!("checked by TableModelArgs::new"),
+    }
+}
+
+fn value_ty(infer: &InferenceResult, expr: ExprId) -> Option<Type> {
+    infer.expr_types.get(expr).and_then(Ty::to_value)
+}
+
+/// The table inputs are all arguments before the first string or array argument (the data source).
+pub(crate) fn num_inputs(infer: &InferenceResult, args: &[ExprId]) -> usize {
+    args.iter()
+        .position(|&arg| {
+            matches!(
+                value_ty(infer, arg),
+                Some(Type::String | Type::Array { .. } | Type::EmptyArray)
+            )
+        })
+        .unwrap_or(args.len())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableModel {
+    /// The inputs of all dimensions that are not ignored.
+    pub inputs: Box<[ExprId]>,
+    pub dimensions: Box<[DimensionControl]>,
+    pub data: TableData,
+}
+
+impl TableModel {
+    /// Reads the table data and parses the control string.
+    /// Errors are returned together with the argument that caused them.
+    pub fn new(
+        db: &dyn HirTyDB,
+        root_file: FileId,
+        body: &Body,
+        args: &TableModelArgs,
+    ) -> Result<TableModel, (ExprId, TableModelError)> {
+        let dims = args.inputs.len();
+        let (control, column) = match args.control {
+            Some(expr) => {
+                let src = str_literal(body, expr);
+                parse_control(src, dims).map_err(|err| (expr, err))?
+            }
+            None => (vec![Some(DimensionControl::default()); dims], 1),
+        };
+
+        let points = match args.source {
+            TableSource::File(expr) => {
+                let file = str_literal(body, expr);
+                let src = read_file(db, root_file, file).map_err(|err| (expr, err))?;
+                parse_file(&src, dims, column).map_err(|err| (expr, err))?
+            }
+            TableSource::Arrays(arrays) => {
+                if column != 1 {
+                    let err = TableModelError::InvalidColumn(column.to_string());
+                    return Err((args.control.unwrap(), err));
+                }
+                read_arrays(body, arrays)?
+            }
+        };
+
+        let inputs = args
+            .inputs
+            .iter()
+            .zip(&control)
+            .filter_map(|(input, dim)| dim.map(|_| *input))
+            .collect();
+        let dimensions: Box<[_]> = control.iter().filter_map(|dim| *dim).collect();
+
+        // the only expression all errors can be attributed to is the data source
+        let src_expr = match args.source {
+            TableSource::File(expr) => expr,
+            TableSource::Arrays(arrays) => arrays[arrays.len() - 1],
+        };
+        let data = build_table(points, &control, &dimensions).map_err(|err| (src_expr, err))?;
+
+        Ok(TableModel { inputs, dimensions, data })
+    }
+}
+
+/// Parses a control string of the form `"sub_control,sub_control..;column"`.
+/// Ignored dimensions (`I`) are represented as `None`.
+fn parse_control(
+    src: &str,
+    dims: usize,
+) -> Result<(Vec<Option<DimensionControl>>, usize), TableModelError> {
+    let (control, column) = match src.split_once(';') {
+        Some((control, column)) => {
+            let column = column.trim();
+            let column = match column.parse() {
+                Ok(column) if column > 0 => column,
+                _ => return Err(TableModelError::InvalidColumn(column.to_owned())),
+            };
+            (control, column)
+        }
+        None => (src, 1),
+    };
+
+    if control.trim().is_empty() {
+        return Ok((vec![Some(DimensionControl::default()); dims], column));
+    }
+
+    let control: Vec<_> = control
+        .split(',')
+        .map(|sub_control| {
+            let sub_control = sub_control.trim();
+            if sub_control == "I" {
+                return Ok(None);
+            }
+
+            let mut chars = sub_control.chars().peekable();
+            let interpolation = match chars.peek() {
+                Some('D') => Some(Interpolation::Discrete),
+                Some('1') => Some(Interpolation::Linear),
+                Some('2') => Some(Interpolation::Quadratic),
+                Some('3') => Some(Interpolation::Cubic),
+                _ => None,
+            };
+            if interpolation.is_some() {
+                chars.next();
+            }
+            let interpolation = interpolation.unwrap_or(Interpolation::Linear);
+
+            let mut extrapolation = chars.map(|c| match c {
+                'C' => Ok(Extrapolation::Clamp),
+                'L' => Ok(Extrapolation::Linear),
+                'E' => Ok(Extrapolation::Error),
+                _ => Err(TableModelError::InvalidControl(sub_control.to_owned())),
+            });
+            let lower = extrapolation.next().transpose()?.unwrap_or(Extrapolation::Linear);
+            let upper = extrapolation.next().transpose()?.unwrap_or(lower);
+            if extrapolation.next().is_some() {
+                return Err(TableModelError::InvalidControl(sub_control.to_owned()));
+            }
+
+            Ok(Some(DimensionControl { interpolation, lower, upper }))
+        })
+        .collect::<Result<_, _>>()?;
+
+    if control.len() != dims {
+        return Err(TableModelError::ControlDimensionMismatch {
+            expected: dims,
+            found: control.len(),
+        });
+    }
+
+    if control.iter().all(Option::is_none) {
+        return Err(TableModelError::InvalidControl(src.to_owned()));
+    }
+
+    Ok((control, column))
+}
+
+/// Table files are searched relative to the root file and the include directories.
+pub(crate) fn read_file(
+    db: &dyn HirTyDB,
+    root_file: FileId,
+    file: &str,
+) -> Result<String, TableModelError> {
+    let workdir = db.file_path(root_file).parent();
+    let include_dirs = db.include_dirs(root_file);
+    for dir in workdir.iter().chain(include_dirs.iter()) {
+        if let Some(path) = dir.join(file) {
+            match db.file_text(db.file_id(path)) {
+                Ok(contents) => return Ok(contents.to_string()),
+                Err(FileReadError::Io(io::ErrorKind::NotFound)) => (),
+                Err(FileReadError::Io(error)) => {
+                    return Err(TableModelError::FileReadError { file: file.to_owned(), error })
+                }
+                Err(FileReadError::InvalidTextFormat(_)) => {
+                    return Err(TableModelError::InvalidTextFormat { file: file.to_owned() })
+                }
+            }
+        }
+    }
+
+    Err(TableModelError::FileReadError { file: file.to_owned(), error: io::ErrorKind::NotFound })
+}
+
+/// Each line of a table file contains one data point. Columns are separated by whitespace
+/// (or commas) and `#` starts a comment. The first `dims` columns are the inputs and the
+/// dependent columns follow after them.
+fn parse_file(src: &str, dims: usize, column: usize) -> Result<DataPoints, TableModelError> {
+    let mut points = Vec::new();
+    for (i, line) in src.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let vals: Vec<_> = line
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|it| !it.is_empty())
+            .collect();
+        if vals.is_empty() {
+            continue;
+        }
+
+        if vals.len() < dims + column {
+            return Err(TableModelError::MissingColumns {
+                line: i + 1,
+                expected: dims + column,
+                found: vals.len(),
+            });
+        }
+
+        let parse = |val: &str| match val.parse::<f64>() {
+            Ok(val) if val.is_finite() => Ok(val),
+            _ => Err(TableModelError::InvalidNumber { line: i + 1, val: val.to_owned() }),
+        };
+        let coords = vals[..dims].iter().map(|val| parse(val)).collect::<Result<_, _>>()?;
+        let val = parse(vals[dims + column - 1])?;
+        points.push((coords, val));
+    }
+
+    Ok(points)
+}
+
+fn read_arrays(body: &Body, arrays: &[ExprId]) -> Result<DataPoints, (ExprId, TableModelError)> {
+    let mut columns = Vec::with_capacity(arrays.len());
+    for &array in arrays {
+        let vals = match body.exprs[array] {
+            Expr::Array(ref vals) => vals,
+            _ => return Err((array, TableModelError::NonConstantData)),
+        };
+        let vals = vals
+            .iter()
+            .map(|&val| const_val(body, val).ok_or((val, TableModelError::NonConstantData)))
+            .collect::<Result<Vec<_>, _>>()?;
+        if let Some(first) = columns.first().map(Vec::len) {
+            if vals.len() != first {
+                let err =
+                    TableModelError::ArrayLengthMismatch { expected: first, found: vals.len() };
+                return Err((array, err));
+            }
+        }
+        columns.push(vals)
+    }
+
+    let (output, inputs) = columns.split_last().unwrap();
+    let points = output
+        .iter()
+        .enumerate()
+        .map(|(i, val)| (inputs.iter().map(|column| column[i]).collect(), *val))
+        .collect();
+    Ok(points)
+}
+
+pub(crate) fn const_val(body: &Body, expr: ExprId) -> Option<f64> {
+    match body.exprs[expr] {
+        Expr::Literal(Literal::Int(val)) => Some(val.into()),
+        Expr::Literal(Literal::Float(val)) => Some(val.into()),
+        Expr::UnaryOp { expr, op: UnaryOp::Neg } => const_val(body, expr).map(|val| -val),
+        Expr::UnaryOp { expr, op: UnaryOp::Identity } => const_val(body, expr),
+        _ => None,
+    }
+}
+
+/// Sorts the data points into isolines. Ignored dimensions are removed. If multiple points
+/// only differ in ignored dimensions, the point with the smallest ignored coordinates is used.
+fn build_table(
+    mut points: DataPoints,
+    control: &[Option<DimensionControl>],
+    dimensions: &[DimensionControl],
+) -> Result<TableData, TableModelError> {
+    fn cmp(lhs: &[f64], rhs: &[f64]) -> Ordering {
+        zip(lhs, rhs).fold(Ordering::Equal, |res, (lhs, rhs)| res.then(lhs.total_cmp(rhs)))
+    }
+
+    if points.is_empty() {
+        return Err(TableModelError::NoData);
+    }
+
+    points.sort_by(|(lhs, _), (rhs, _)| cmp(lhs, rhs));
+    if let Some(window) = points.windows(2).find(|window| window[0].0 == window[1].0) {
+        let point: Vec<_> = window[0].0.iter().map(f64::to_string).collect();
+        return Err(TableModelError::DuplicatePoint { point: point.join(", ") });
+    }
+
+    let mut points: Vec<_> = points
+        .into_iter()
+        .map(|(coords, val)| {
+            let coords: Box<[_]> =
+                coords.iter().zip(control).filter_map(|(coord, dim)| dim.map(|_| *coord)).collect();
+            (coords, val)
+        })
+        .collect();
+    // the sort is stable so the first point of each group has the smallest ignored coordinates
+    points.sort_by(|(lhs, _), (rhs, _)| cmp(lhs, rhs));
+    points.dedup_by(|(lhs, _), (rhs, _)| lhs == rhs);
+
+    build_isolines(&points, dimensions, 0)
+}
+
+fn build_isolines(
+    points: &[(Box<[f64]>, f64)],
+    dimensions: &[DimensionControl],
+    dim: usize,
+) -> Result<TableData, TableModelError> {
+    let mut xs = Vec::new();
+    let mut groups = Vec::new();
+    let mut start = 0;
+    while start < points.len() {
+        let x = points[start].0[dim];
+        let len = points[start..].iter().position(|(coords, _)| coords[dim] != x);
+        let end = len.map_or(points.len(), |len| start + len);
+        xs.push(x);
+        groups.push(&points[start..end]);
+        start = end;
+    }
+
+    let required = dimensions[dim].interpolation.min_points();
+    if xs.len() < required {
+        return Err(TableModelError::NotEnoughPoints { required, found: xs.len() });
+    }
+
+    let xs = xs.into_boxed_slice();
+    if dim + 1 == dimensions.len() {
+        let ys = groups.iter().map(|group| group[0].1).collect();
+        Ok(TableData::Values { xs, ys })
+    } else {
+        let isolines = groups
+            .into_iter()
+            .map(|group| build_isolines(group, dimensions, dim + 1))
+            .collect::<Result<_, _>>()?;
+        Ok(TableData::Isolines { xs, isolines })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LINEAR: DimensionControl = DimensionControl {
+        interpolation: Interpolation::Linear,
+        lower: Extrapolation::Linear,
+        upper: Extrapolation::Linear,
+    };
+
+    #[test]
+    fn control() {
+        assert_eq!(parse_control("", 2), Ok((vec![Some(LINEAR); 2], 1)));
+        assert_eq!(parse_control(";3", 1), Ok((vec![Some(LINEAR)], 3)));
+        assert_eq!(
+            parse_control("3CE, I, D", 3),
+            Ok((
+                vec![
+                    Some(DimensionControl {
+                        interpolation: Interpolation::Cubic,
+                        lower: Extrapolation::Clamp,
+                        upper: Extrapolation::Error,
+                    }),
+                    None,
+                    Some(DimensionControl { interpolation: Interpolation::Discrete, ..LINEAR }),
+                ],
+                1
+            ))
+        );
+        // the upper extrapolation defaults to the lower extrapolation
+        assert_eq!(
+            parse_control("2C", 1),
+            Ok((
+                vec![Some(DimensionControl {
+                    interpolation: Interpolation::Quadratic,
+                    lower: Extrapolation::Clamp,
+                    upper: Extrapolation::Clamp,
+                })],
+                1
+            ))
+        );
+        assert_eq!(parse_control("1X", 1), Err(TableModelError::InvalidControl("1X".to_owned())));
+        assert_eq!(
+            parse_control("1LLL", 1),
+            Err(TableModelError::InvalidControl("1LLL".to_owned()))
+        );
+        assert_eq!(parse_control("I,I", 2), Err(TableModelError::InvalidControl("I,I".to_owned())));
+        assert_eq!(
+            parse_control("1,1", 1),
+            Err(TableModelError::ControlDimensionMismatch { expected: 1, found: 2 })
+        );
+        assert_eq!(parse_control("1;0", 1), Err(TableModelError::InvalidColumn("0".to_owned())));
+        assert_eq!(parse_control("1;x", 1), Err(TableModelError::InvalidColumn("x".to_owned())));
+    }
+
+    #[test]
+    fn file() {
+        let src = "# x y z\n0 1 2\n\n1, 3, 4 # comment\n";
+        assert_eq!(
+            parse_file(src, 1, 1),
+            Ok(vec![(vec![0.0].into_boxed_slice(), 1.0), (vec![1.0].into_boxed_slice(), 3.0)])
+        );
+        assert_eq!(
+            parse_file(src, 1, 2),
+            Ok(vec![(vec![0.0].into_boxed_slice(), 2.0), (vec![1.0].into_boxed_slice(), 4.0)])
+        );
+        assert_eq!(
+            parse_file(src, 2, 1),
+            Ok(vec![
+                (vec![0.0, 1.0].into_boxed_slice(), 2.0),
+                (vec![1.0, 3.0].into_boxed_slice(), 4.0)
+            ])
+        );
+        assert_eq!(
+            parse_file(src, 2, 2),
+            Err(TableModelError::MissingColumns { line: 2, expected: 4, found: 3 })
+        );
+        assert_eq!(
+            parse_file("0 1\n1 inf", 1, 1),
+            Err(TableModelError::InvalidNumber { line: 2, val: "inf".to_owned() })
+        );
+    }
+
+    fn point(coords: &[f64], val: f64) -> (Box<[f64]>, f64) {
+        (coords.to_owned().into_boxed_slice(), val)
+    }
+
+    fn values(xs: &[f64], ys: &[f64]) -> TableData {
+        TableData::Values {
+            xs: xs.to_owned().into_boxed_slice(),
+            ys: ys.to_owned().into_boxed_slice(),
+        }
+    }
+
+    #[test]
+    fn table() {
+        // the points are sorted
+        let points = vec![point(&[2.0], 4.0), point(&[0.0], 0.0), point(&[1.0], 1.0)];
+        assert_eq!(
+            build_table(points, &[Some(LINEAR)], &[LINEAR]),
+            Ok(values(&[0.0, 1.0, 2.0], &[0.0, 1.0, 4.0]))
+        );
+
+        // isolines do not need to form a full grid
+        let points = vec![
+            point(&[0.0, 0.0], 0.0),
+            point(&[0.0, 1.0], 1.0),
+            point(&[1.0, 0.0], 2.0),
+            point(&[1.0, 2.0], 3.0),
+            point(&[1.0, 1.0], 4.0),
+        ];
+        assert_eq!(
+            build_table(points, &[Some(LINEAR); 2], &[LINEAR; 2]),
+            Ok(TableData::Isolines {
+                xs: vec![0.0, 1.0].into_boxed_slice(),
+                isolines: vec![
+                    values(&[0.0, 1.0], &[0.0, 1.0]),
+                    values(&[0.0, 1.0, 2.0], &[2.0, 4.0, 3.0])
+                ]
+                .into_boxed_slice(),
+            })
+        );
+
+        // ignored dimensions use the point with the smallest ignored coordinate
+        let points = vec![
+            point(&[1.0, 0.0], 2.0),
+            point(&[0.0, 1.0], 1.0),
+            point(&[1.0, 1.0], 3.0),
+            point(&[0.0, 0.0], 0.0),
+        ];
+        assert_eq!(
+            build_table(points, &[Some(LINEAR), None], &[LINEAR]),
+            Ok(values(&[0.0, 1.0], &[0.0, 2.0]))
+        );
+
+        let points = vec![point(&[0.0], 0.0), point(&[1.0], 1.0), point(&[0.0], 2.0)];
+        assert_eq!(
+            build_table(points, &[Some(LINEAR)], &[LINEAR]),
+            Err(TableModelError::DuplicatePoint { point: "0".to_owned() })
+        );
+        let cubic = DimensionControl { interpolation: Interpolation::Cubic, ..LINEAR };
+        let points = vec![point(&[0.0], 0.0), point(&[1.0], 1.0), point(&[2.0], 2.0)];
+        assert_eq!(
+            build_table(points, &[Some(cubic)], &[cubic]),
+            Err(TableModelError::NotEnoughPoints { required: 4, found: 3 })
+        );
+        assert_eq!(build_table(vec![], &[Some(LINEAR)], &[LINEAR]), Err(TableModelError::NoData));
+    }
+}
@@ -2,8 +2,8 @@ use std::borrow::Cow;
 use std::ops::Deref;
 
 use hir_def::{
-    BranchId, DisciplineId, FunctionId, LocalFunctionArgId, NatureAttrId, NatureId, NodeId,
-    ParamId, Type, VarId,
+    BranchId, DisciplineId, FunctionId, LocalFunctionArgId, NatureAttrId, NatureId, NodeBusId,
+    NodeId, ParamId, Type, VarId,
 };
 use stdx::{impl_display, impl_idx_from, pretty};
 
@@ -13,6 +13,7 @@ pub enum TyRequirement {
     Condition,
     AnyVal,
     ArrayAnyLength { ty: Type },
+    AnyArray,
     Node,
     PortFlow,
     Nature,
@@ -57,6 +58,7 @@ impl_display! {
         TyRequirement::Condition => "{} value", Type::Bool;
         TyRequirement::AnyVal => "value";
         TyRequirement::ArrayAnyLength{ty} => "array ({})", ty;
+        TyRequirement::AnyArray => "array";
         TyRequirement::Node => "net reference";
         TyRequirement:: Nature => "nature reference";
         TyRequirement::Var(ty) => "{} variable reference", ty;
@@ -74,6 +76,9 @@ impl_display! {
 pub enum Ty {
     Val(Type),
     Node(NodeId),
+    // `wreal` nets and nets with a discrete discipline are read and assigned like variables
+    RealNet(NodeId),
+    NodeBus(NodeBusId),
     PortFlow(NodeId),
     Nature(NatureId),
     Discipline(DisciplineId),
@@ -95,6 +100,8 @@ impl_display! {
     match Ty{
         Ty::Val(ty) => "{} value",ty;
         Ty::Node(_) => "net reference";
+        Ty::RealNet(_) => "real net reference";
+        Ty::NodeBus(_) => "vector net reference";
         Ty::PortFlow(_) => "port-flow reference";
         Ty::Nature(_) => "nature reference";
         Ty::Discipline(_) => "discipline reference";
@@ -164,7 +171,8 @@ impl Ty {
                 | Ty::Param(_, _)
                 | Ty::InfLiteral
                 | Ty::Literal(_)
-                | Ty::FunctionVar { .. },
+                | Ty::FunctionVar { .. }
+                | Ty::RealNet(_),
                 TyRequirement::AnyVal,
             )
             | (Ty::InfLiteral, TyRequirement::Val(Type::Real))
@@ -176,6 +184,13 @@ impl Ty {
             | (Ty::Node(_), TyRequirement::Node)
             | (Ty::PortFlow(_), TyRequirement::PortFlow)
             | (Ty::Nature(_), TyRequirement::Nature)
+            | (
+                Ty::Val(Type::Array { .. })
+                | Ty::Var(Type::Array { .. }, _)
+                | Ty::Param(Type::Array { .. }, _)
+                | Ty::FunctionVar { ty: Type::Array { .. }, .. },
+                TyRequirement::AnyArray,
+            )
             | (Ty::Param(_, _), TyRequirement::AnyParam)
             | (Ty::UserFunction(_), TyRequirement::Function)
             | (Ty::Branch(_), TyRequirement::Branch) => true,
@@ -200,10 +215,15 @@ impl Ty {
                 | Ty::FunctionVar { ty, .. },
                 TyRequirement::Condition,
             ) => ty.is_assignable_to(&Type::Bool),
+            (Ty::RealNet(_), TyRequirement::Val(ty)) => equiv.compare_ty(&Type::Real, ty),
+            (Ty::RealNet(_), TyRequirement::Condition) => Type::Real.is_assignable_to(&Type::Bool),
 
             // TODO merge these match arms when there are box/deref patterns (not any time soon)
             (
-                Ty::Val(Type::Array { ty: ref ty1, .. }),
+                Ty::Val(Type::Array { ty: ref ty1, .. })
+                | Ty::Var(Type::Array { ty: ref ty1, .. }, _)
+                | Ty::Param(Type::Array { ty: ref ty1, .. }, _)
+                | Ty::FunctionVar { ty: Type::Array { ty: ref ty1, .. }, .. },
                 TyRequirement::ArrayAnyLength { ty: ty2 },
             ) => equiv.compare_ty(ty1, ty2),
 
@@ -234,7 +254,7 @@ impl Ty {
             | Ty::Param(ty, _)
             | Ty::Literal(ty)
             | Ty::FunctionVar { ty, .. } => Some(ty.clone()),
-            Ty::InfLiteral => Some(Type::Real),
+            Ty::InfLiteral | Ty::RealNet(_) => Some(Type::Real),
             _ => None,
         }
     }
@@ -324,8 +344,9 @@ pub struct Signature(pub u32);
 
 impl_idx_from!(Signature(u32));
 
+/// The signatures of a builtin function. Use `BuiltinInfo::from(builtin)` to obtain them.
 #[derive(Debug, PartialEq, Eq, Copy, Clone)]
-pub(crate) struct BuiltinInfo {
+pub struct BuiltinInfo {
     pub signatures: &'static [SignatureData],
     pub min_args: usize,
     pub max_args: Option<usize>,
@@ -1,11 +1,13 @@
 use basedb::diagnostics::{Diagnostic, Label, LabelStyle, Report};
-use basedb::lints::builtin::{const_simparam, trivial_probe, variant_const_simparam};
+use basedb::lints::builtin::{
+    approximate_small_signal, const_simparam, trivial_probe, variant_const_simparam,
+};
 use basedb::lints::{self, Lint, LintSrc};
 use basedb::{AstIdMap, BaseDB, FileId};
 pub use body::BodyValidationDiagnostic;
 use hir_def::body::BodySourceMap;
 use hir_def::{
-    DisciplineAttr, ExprId, ItemLoc, ItemTree, ItemTreeNode, Lookup, NatureAttr, NodeId,
+    BuiltIn, DisciplineAttr, ExprId, ItemLoc, ItemTree, ItemTreeNode, Lookup, NatureAttr, NodeId,
     NodeTypeDecl,
 };
 use syntax::name::Name;
@@ -15,6 +17,8 @@ pub use types::TypeValidationDiagnostic;
 
 use crate::db::HirTyDB;
 use crate::inference::BranchWrite;
+use crate::noise_table::NoiseTableError;
+use crate::table_model::TableModelError;
 use crate::validation::body::{BodyCtx, IllegalCtxAccess, IllegalCtxAccessKind};
 use crate::validation::types::DuplicateItem;
 
@@ -130,6 +134,10 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
                 let src = self.body_sm.lint_src(stmt, trivial_probe);
                 Some((trivial_probe, src))
             }
+            BodyValidationDiagnostic::ApproximateSmallSignal { stmt, .. } => {
+                let src = self.body_sm.lint_src(stmt, approximate_small_signal);
+                Some((approximate_small_signal, src))
+            }
             _ => None,
         }
     }
@@ -337,6 +345,15 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
                         "analysis function '{}' is not allowed in constants",
                         name
                     )),
+                    IllegalCtxAccessKind::EventFun { name } => res
+                        .with_message(format!(
+                            "event function '{}' is not allowed in {}",
+                            name, ctx
+                        ))
+                        .with_notes(vec![
+                            "help: event functions may only be used as the event of an event control '@(..)' in the main analog block"
+                                .to_owned(),
+                        ]),
                     IllegalCtxAccessKind::Var(var) => {
                         let name = var.lookup(self.db.upcast()).name(self.db.upcast());
                         let def = var.lookup(self.db.upcast()).ast_ptr(self.db.upcast()).range();
@@ -455,6 +472,20 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
                     .with_message(format!("'{name}' access of branch without {name}"))
                     .with_notes(vec![format!("help: this branches nodes have a discipline without the '{name}' attribute")])
             }
+            BodyValidationDiagnostic::ExpectedEventFun { expr } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "expected event".to_owned(),
+                    }])
+                    .with_message("expected an event function".to_owned())
+                    .with_notes(vec![
+                        "help: supported events are 'initial_step', 'final_step', 'cross', 'above' and 'timer'".to_owned(),
+                    ])
+            }
             BodyValidationDiagnostic::IncompatibleImplicitBranch { access, node1, node2 } => {
                 let node1_ = self.db.node_data(node1);
                 let node2_ = self.db.node_data(node2);
@@ -500,6 +531,113 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
                     format!("info: branches are open circuted by default: I({branch_probe}) <+ 0"),
                 ]);
 
+                res
+            }
+            BodyValidationDiagnostic::InvalidFilterArg { expr, roots } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                let (message, label, note) = if roots {
+                    (
+                        "the roots of a filter must be pairs of real and imaginary parts",
+                        "odd number of elements",
+                        "help: each root is specified as two consecutive elements, for example '{re0, im0, re1, im1}",
+                    )
+                } else {
+                    (
+                        "the coefficients of a filter can not be empty",
+                        "empty array",
+                        "help: specify at least one coefficient, for example '{1}",
+                    )
+                };
+                Report::error()
+                    .with_message(message.to_owned())
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: label.to_owned(),
+                    }])
+                    .with_notes(vec![note.to_owned()])
+            }
+            BodyValidationDiagnostic::InvalidNoiseTable { expr, ref err } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                let mut res =
+                    Report::error().with_message(err.to_string()).with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "invalid noise table".to_owned(),
+                    }]);
+                if let NoiseTableError::OddLength { .. } = err {
+                    res = res.with_notes(vec![
+                        "help: each entry is specified as two consecutive elements, for example '{f0, pwr0, f1, pwr1}".to_owned(),
+                    ])
+                }
+                res
+            }
+            BodyValidationDiagnostic::UnsortedNoiseTable { expr } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::warning()
+                    .with_message(
+                        "the frequencies of the noise table are not in ascending order".to_owned(),
+                    )
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "unsorted noise table".to_owned(),
+                    }])
+                    .with_notes(vec!["info: the table is sorted by frequency".to_owned()])
+            }
+            BodyValidationDiagnostic::ApproximateSmallSignal { expr, func, .. } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                let (label, note) = if func == BuiltIn::absdelay {
+                    (
+                        "exp(-jω·td) is replaced by a 4th order padé approximant",
+                        "the phase error is below 1% for ω·td < 4 and grows quickly for larger delays",
+                    )
+                } else {
+                    (
+                        "the sampled filter is replaced by its bilinear transform",
+                        "the response is exact at DC and accurate well below the nyquist frequency",
+                    )
+                };
+                Report::warning()
+                    .with_message(format!("the small signal response of {func:?} is approximated"))
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: label.to_owned(),
+                    }])
+                    .with_notes(vec![format!(
+                        "{note}\nhelp: transient analysis uses the exact response"
+                    )])
+            }
+            BodyValidationDiagnostic::InvalidTableModel { expr, ref err } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                let mut res =
+                    Report::error().with_message(err.to_string()).with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "invalid $table_model argument".to_owned(),
+                    }]);
+
+                match err {
+                    TableModelError::InvalidControl(_) => {
+                        res = res.with_notes(vec![
+                            "help: the control string contains one entry for each input, for example \"1LL,3C\"\nvalid entries: 'I' (ignore), 'D' (discrete), '1', '2' or '3' (degree) followed by up to two of 'C' (clamp), 'L' (linear) or 'E' (error)".to_owned(),
+                        ])
+                    }
+                    TableModelError::NonConstantData => {
+                        res = res.with_notes(vec![
+                            "help: table data must be known at compile time, use a table file for more complex data".to_owned(),
+                            "note: array parameters and variables are not supported as table data, their values may change after compilation".to_owned(),
+                        ])
+                    }
+                    _ => (),
+                }
+
                 res
             }
         }
@@ -702,6 +840,21 @@ impl Diagnostic for TypeValidationDiagnosticWrapped<'_> {
                         format!("help: add a discipline with 'electrical {name}'"),
                     ])
             }
+            TypeValidationDiagnostic::ArrayFunctionArg { decl, ref name } => {
+                let src = self.parse.to_file_span(self.map.get_syntax(decl).range(), self.sm);
+
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: src.file,
+                        range: src.range.into(),
+                        message: format!("'{name}' is declared as an array here"),
+                    }])
+                    .with_message(format!("function argument '{name}' can not be an array"))
+                    .with_notes(vec![
+                        "help: pass the array elements as separate arguments".to_owned()
+                    ])
+            }
             TypeValidationDiagnostic::IncompatibleBranch { branch, node1, node2 } => {
                 let branch = branch.lookup(self.db.upcast());
                 let branch_range = branch.ast_ptr(self.db.upcast()).range();
@@ -714,6 +867,37 @@ impl Diagnostic for TypeValidationDiagnosticWrapped<'_> {
                 }
                 .into_report(self.db, self.parse, self.map, self.sm)
             }
+            TypeValidationDiagnostic::IncompatibleParamsetAssignment {
+                param,
+                value,
+                ref expected,
+                ref found,
+            } => {
+                let src = value.lookup(self.db.upcast()).ast_ptr(self.db.upcast()).range();
+                let src = self.parse.to_file_span(src, self.sm);
+                let decl = param.lookup(self.db.upcast()).ast_ptr(self.db.upcast()).range();
+                let decl = self.parse.to_file_span(decl, self.sm);
+                let name = self.db.param_data(param).name.clone();
+
+                Report::error()
+                    .with_labels(vec![
+                        Label {
+                            style: LabelStyle::Primary,
+                            file_id: src.file,
+                            range: src.range.into(),
+                            message: format!("expected {expected}"),
+                        },
+                        Label {
+                            style: LabelStyle::Secondary,
+                            file_id: decl.file,
+                            range: decl.range.into(),
+                            message: format!("info: '{name}' was declared here"),
+                        },
+                    ])
+                    .with_message(format!(
+                        "a value of type {found} can not be assigned to parameter '{name}' of type {expected}"
+                    ))
+            }
         }
     }
 
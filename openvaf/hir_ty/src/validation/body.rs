@@ -2,11 +2,13 @@ use std::mem::replace;
 
 use ahash::{HashMap, HashSet};
 use hir_def::body::Body;
+use hir_def::expr::Event;
 use hir_def::{
     BranchId, BuiltIn, DefWithBodyId, DisciplineId, Expr, ExprId, FunctionArgLoc, Literal, Lookup,
-    NatureId, NodeId, ParamId, Path, Stmt, StmtId, VarId,
+    NatureId, NodeId, ParamId, Path, Stmt, StmtId, Type, VarId,
 };
 use stdx::impl_display;
+use stdx::iter::zip;
 use syntax::ast::AssignOp;
 use syntax::name::{AsIdent, Name};
 
@@ -18,6 +20,8 @@ use crate::builtin::{
 use crate::db::HirTyDB;
 use crate::inference::{BranchWrite, InferenceResult, ResolvedFun};
 use crate::lower::BranchKind;
+use crate::noise_table::{check_frequencies, read_noise_table_file, NoiseTableError};
+use crate::table_model::{const_val, TableModel, TableModelArgs, TableModelError};
 use crate::types::{Signature, Ty};
 
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -25,6 +29,7 @@ pub enum IllegalCtxAccessKind {
     NatureAccess,
     AnalogOperator { name: Name, is_standard: bool, non_const_dominator: Box<[ExprId]> },
     AnalysisFun { name: Name },
+    EventFun { name: Name },
     Var(VarId),
 }
 
@@ -68,6 +73,10 @@ pub enum BodyValidationDiagnostic {
 
     IllegalCtxAccess(IllegalCtxAccess),
 
+    ExpectedEventFun {
+        expr: ExprId,
+    },
+
     ConstSimparam {
         known: bool,
         expr: ExprId,
@@ -96,6 +105,31 @@ pub enum BodyValidationDiagnostic {
         node1: NodeId,
         node2: NodeId,
     },
+
+    InvalidTableModel {
+        expr: ExprId,
+        err: TableModelError,
+    },
+
+    InvalidFilterArg {
+        expr: ExprId,
+        roots: bool,
+    },
+
+    InvalidNoiseTable {
+        expr: ExprId,
+        err: NoiseTableError,
+    },
+
+    UnsortedNoiseTable {
+        expr: ExprId,
+    },
+
+    ApproximateSmallSignal {
+        expr: ExprId,
+        func: BuiltIn,
+        stmt: StmtId,
+    },
 }
 
 impl BodyValidationDiagnostic {
@@ -214,7 +248,10 @@ impl BodyValidator<'_> {
 
                 return;
             }
-            Stmt::EventControl { body, .. } => {
+            Stmt::EventControl { ref event, body } => {
+                if let Event::Monitored(event) = *event {
+                    self.validate_event(event, stmt);
+                }
                 let old = replace(&mut self.ctx, BodyCtx::EventControl);
                 self.validate_stmt(body);
                 self.ctx = old;
@@ -275,6 +312,29 @@ impl BodyValidator<'_> {
         None
     }
 
+    /// Validates the expression of a monitored event `@(cross(..))`. This is the only
+    /// place where event functions may be called.
+    fn validate_event(&mut self, event: ExprId, stmt: StmtId) {
+        let (body, infer) = (self.body, self.infer);
+        let mut validator =
+            ExprValidator { parent: self, cond_diagnostic_sink: None, write: false, stmt };
+        match (&body.exprs[event], infer.resolved_calls.get(&event)) {
+            (Expr::Call { fun, args }, Some(ResolvedFun::BuiltIn(call))) if call.is_event_fun() => {
+                if !validator.parent.ctx.allow_analog_operator() {
+                    let name = fun.as_ref().and_then(|p| p.as_ident()).unwrap();
+                    validator.report_illegal_access(IllegalCtxAccessKind::EventFun { name }, event)
+                }
+                for &arg in args {
+                    validator.validate_expr(arg)
+                }
+            }
+            _ => {
+                validator.validate_expr(event);
+                self.diagnostics.push(BodyValidationDiagnostic::ExpectedEventFun { expr: event })
+            }
+        }
+    }
+
     fn validate_expr(&mut self, expr: ExprId, stmt: StmtId) {
         ExprValidator { parent: self, cond_diagnostic_sink: None, write: false, stmt }
             .validate_expr(expr)
@@ -508,6 +568,15 @@ impl ExprValidator<'_, '_> {
                         }
                     }
 
+                    // real nets are lowered to the potential of the net
+                    Ty::RealNet(_) => {
+                        let allowed = if self.write {
+                            self.parent.ctx.allow_contribute()
+                        } else {
+                            self.parent.ctx.allow_nature_access()
+                        };
+                        self.check_access(|_| IllegalCtxAccessKind::NatureAccess, expr, allowed);
+                    }
                     Ty::Var(_, var) => {
                         self.check_access(
                             |__| IllegalCtxAccessKind::Var(var),
@@ -578,6 +647,14 @@ impl ExprValidator<'_, '_> {
                 )
             }
 
+            // the event expression itself is validated in `validate_event`
+            _ if call.is_event_fun() => self.report_illegal_access(
+                IllegalCtxAccessKind::EventFun {
+                    name: name.as_ref().and_then(|p| p.as_ident()).unwrap(),
+                },
+                expr,
+            ),
+
             _ if call.is_analysis_var() && !self.parent.ctx.allow_analysis_fun() => self
                 .report_illegal_access(
                     IllegalCtxAccessKind::AnalysisFun {
@@ -588,6 +665,17 @@ impl ExprValidator<'_, '_> {
             _ => (),
         }
 
+        if matches!(
+            call,
+            BuiltIn::absdelay | BuiltIn::zi_nd | BuiltIn::zi_np | BuiltIn::zi_zd | BuiltIn::zi_zp
+        ) {
+            self.report(BodyValidationDiagnostic::ApproximateSmallSignal {
+                expr,
+                func: call,
+                stmt: self.stmt,
+            })
+        }
+
         match (call, signature) {
             (BuiltIn::potential | BuiltIn::flow, Some(NATURE_ACCESS_NODES)) => {
                 let hi = self.parent.infer.expr_types[args[0]].unwrap_node();
@@ -682,7 +770,14 @@ impl ExprValidator<'_, '_> {
             (
                 BuiltIn::noise_table | BuiltIn::noise_table_log,
                 Some(NOISE_TABLE_INLINE | NOISE_TABLE_INLINE_NAME),
-            ) => self.validate_const_expr(args[0]),
+            ) => {
+                self.validate_const_expr(args[0]);
+                self.validate_noise_table(args[0], true);
+            }
+            (BuiltIn::noise_table | BuiltIn::noise_table_log, _) => {
+                self.validate_noise_table(args[0], false)
+            }
+            (BuiltIn::table_model, _) => self.validate_table_model(args),
             (func @ (BuiltIn::simparam | BuiltIn::simparam_str), _) => {
                 if self.parent.ctx == BodyCtx::Const {
                     let known = if let Expr::Literal(Literal::String(name)) =
@@ -727,16 +822,17 @@ impl ExprValidator<'_, '_> {
             }
 
             (
-                BuiltIn::laplace_nd
+                filter @ (BuiltIn::laplace_nd
                 | BuiltIn::laplace_np
                 | BuiltIn::laplace_zp
                 | BuiltIn::laplace_zd
                 | BuiltIn::zi_nd
                 | BuiltIn::zi_np
                 | BuiltIn::zi_zd
-                | BuiltIn::zi_zp,
+                | BuiltIn::zi_zp),
                 Some(_),
             ) => {
+                self.validate_filter(filter, args);
                 if let [_expr, const_args @ ..] = args {
                     args = &args[..1];
                     for arg in const_args {
@@ -753,6 +849,80 @@ impl ExprValidator<'_, '_> {
         }
     }
 
+    fn validate_filter(&mut self, filter: BuiltIn, args: &[ExprId]) {
+        let num_roots = matches!(
+            filter,
+            BuiltIn::laplace_zd | BuiltIn::laplace_zp | BuiltIn::zi_zd | BuiltIn::zi_zp
+        );
+        let den_roots = matches!(
+            filter,
+            BuiltIn::laplace_np | BuiltIn::laplace_zp | BuiltIn::zi_np | BuiltIn::zi_zp
+        );
+
+        for (&expr, roots) in zip(args.iter().skip(1), [num_roots, den_roots]) {
+            // malformed arguments are already reported during type inference
+            let len = match self.parent.infer.expr_types[expr].to_value() {
+                Some(Type::Array { len, .. }) => len,
+                Some(Type::EmptyArray) => 0,
+                _ => continue,
+            };
+
+            // an empty list of roots is a valid (constant) polynomial
+            let invalid = if roots { len % 2 != 0 } else { len == 0 };
+            if invalid {
+                self.report(BodyValidationDiagnostic::InvalidFilterArg { expr, roots });
+            }
+        }
+    }
+
+    fn validate_table_model(&mut self, args: &[ExprId]) {
+        let BodyValidator { db, owner, body, infer, .. } = *self.parent;
+        // malformed arguments are already reported during type inference
+        if let Some(args) = TableModelArgs::new(body, infer, args) {
+            let root_file = owner.file(db.upcast());
+            if let Err((expr, err)) = TableModel::new(db, root_file, body, &args) {
+                self.report(BodyValidationDiagnostic::InvalidTableModel { expr, err })
+            }
+        }
+    }
+
+    fn validate_noise_table(&mut self, expr: ExprId, inline: bool) {
+        let BodyValidator { db, owner, body, .. } = *self.parent;
+        let freqs = if inline {
+            let vals = match body.exprs[expr] {
+                Expr::Array(ref vals) => vals,
+                _ => return,
+            };
+            if vals.len() % 2 != 0 {
+                let err = NoiseTableError::OddLength { len: vals.len() };
+                self.report(BodyValidationDiagnostic::InvalidNoiseTable { expr, err });
+                return;
+            }
+            // non-constant frequencies can only be sorted at runtime
+            let freqs: Option<Vec<_>> =
+                vals.iter().step_by(2).map(|&val| const_val(body, val)).collect();
+            match freqs {
+                Some(freqs) => freqs,
+                None => return,
+            }
+        } else {
+            let root_file = owner.file(db.upcast());
+            match read_noise_table_file(db, root_file, body, expr) {
+                Ok(table) => table.iter().map(|(freq, _)| *freq).collect(),
+                Err(err) => {
+                    self.report(BodyValidationDiagnostic::InvalidNoiseTable { expr, err });
+                    return;
+                }
+            }
+        };
+
+        match check_frequencies(&freqs) {
+            Ok(true) => (),
+            Ok(false) => self.report(BodyValidationDiagnostic::UnsortedNoiseTable { expr }),
+            Err(err) => self.report(BodyValidationDiagnostic::InvalidNoiseTable { expr, err }),
+        }
+    }
+
     fn validate_const_expr(&mut self, expr: ExprId) {
         let old = replace(&mut self.parent.ctx, BodyCtx::Const);
         let sink = self.cond_diagnostic_sink.take();
@@ -4,9 +4,9 @@ use basedb::{AstId, ErasedAstId, FileId};
 use hir_def::nameres::diagnostics::PathResolveError;
 use hir_def::nameres::{DefMap, ScopeDefItem};
 use hir_def::{
-    AliasParamId, Branch, BranchId, BranchKind, DisciplineId, ItemLoc, ItemTree,
+    AliasParamId, Branch, BranchId, BranchKind, DisciplineId, FunctionId, ItemLoc, ItemTree,
     LocalDisciplineAttrId, LocalNatureAttrId, Lookup, ModuleId, ModuleLoc, NatureId, NodeId,
-    NodeTypeDecl, Path, ScopeId,
+    NodeTypeDecl, ParamId, ParamsetId, Path, ScopeId, Type,
 };
 use syntax::ast::ArgListOwner;
 use syntax::name::Name;
@@ -34,6 +34,8 @@ pub enum TypeValidationDiagnostic {
     NodeWithoutDiscipline { decl: ErasedAstId, name: Name },
     ExpectedPort { node: NodeId, src: ErasedAstId },
     IncompatibleBranch { branch: BranchId, node1: NodeId, node2: NodeId },
+    IncompatibleParamsetAssignment { param: ParamId, value: ParamId, expected: Type, found: Type },
+    ArrayFunctionArg { decl: ErasedAstId, name: Name },
 }
 
 impl TypeValidationDiagnostic {
@@ -65,11 +67,27 @@ impl TypeValidationCtx<'_> {
                 ScopeDefItem::NatureId(nature) => self.verify_nature(nature),
                 ScopeDefItem::DisciplineId(discipline) => self.verify_discipline(discipline),
                 ScopeDefItem::ModuleId(module) => self.verify_module(module),
+                ScopeDefItem::ParamsetId(paramset) => self.verify_paramset(paramset),
                 _ => (),
             }
         }
     }
 
+    fn verify_paramset(&mut self, paramset: ParamsetId) {
+        for &(param, value) in &self.db.paramset_data(paramset).overrides {
+            let expected = self.db.param_ty(param);
+            let found = self.db.param_ty(value);
+            if !found.is_assignable_to(&expected) {
+                self.report(TypeValidationDiagnostic::IncompatibleParamsetAssignment {
+                    param,
+                    value,
+                    expected,
+                    found,
+                });
+            }
+        }
+    }
+
     fn verify_module(&mut self, module: ModuleId) {
         let loc = module.lookup(self.db.upcast());
         let scope = loc.scope.local_scope;
@@ -78,11 +96,28 @@ impl TypeValidationCtx<'_> {
                 ScopeDefItem::NodeId(node) => self.verify_node(*node, loc),
                 ScopeDefItem::BranchId(branch) => self.verify_branch(*branch),
                 ScopeDefItem::AliasParamId(alias) => self.verify_alias(*alias),
+                ScopeDefItem::FunctionId(fun) => self.verify_function(*fun),
                 _ => (),
             }
         }
     }
 
+    /// Arrays are scalarized during lowering and can therefore not be passed to functions
+    fn verify_function(&mut self, fun: FunctionId) {
+        let loc = fun.lookup(self.db.upcast());
+        let tree = loc.item_tree(self.db.upcast());
+        for arg in &tree[loc.id].args {
+            if let Some(&decl) = arg.declarations.first() {
+                if matches!(tree[decl].ty, Type::Array { .. }) {
+                    self.report(TypeValidationDiagnostic::ArrayFunctionArg {
+                        decl: tree[decl].ast_id.into(),
+                        name: arg.name.clone(),
+                    });
+                }
+            }
+        }
+    }
+
     fn resolve_node(
         &mut self,
         node: &Path,
@@ -262,7 +297,18 @@ impl TypeValidationCtx<'_> {
                     subsequent: duplicates,
                 }))
             }
-        } else {
+        } else if let Some(discipline) = node_.default_discipline(self.tree) {
+            if let Err(err) = self
+                .def_map
+                .resolve_local_item_in_scope::<DisciplineId>(self.def_map.root(), &discipline)
+            {
+                self.report(TypeValidationDiagnostic::PathError {
+                    err,
+                    src: self.db.ast_id_map(self.root_file).get_syntax(node_.ast_id),
+                })
+            }
+        } else if !node_.is_real(self.tree) {
+            // wreal nets carry a plain real value and require no discipline
             self.report(TypeValidationDiagnostic::NodeWithoutDiscipline {
                 decl: node_.ast_id,
                 name: node_.name.clone(),
@@ -0,0 +1,113 @@
+use basedb::BaseDB;
+use hir::{Definition, Semantics};
+use syntax::{AstNode, TextRange, TextSize};
+
+use crate::signature_help::signatures;
+use crate::{FilePosition, SymbolKind};
+
+/// A single completion proposal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CompletionItem {
+    /// The text that is inserted.
+    pub label: String,
+    pub kind: SymbolKind,
+    /// Additional information shown next to the label (like the signature of a function).
+    pub detail: Option<String>,
+    /// The range that is replaced when the item is accepted (the partially typed name).
+    pub range: TextRange,
+}
+
+/// The system functions whose arguments must refer to an item of a certain kind.
+const ITEM_ARGS: [(&str, SymbolKind); 2] =
+    [("$param_given", SymbolKind::Parameter), ("$port_connected", SymbolKind::Node)];
+
+pub(crate) fn completions(sema: &Semantics, pos: FilePosition) -> Vec<CompletionItem> {
+    let text = match sema.db().file_text(pos.file) {
+        Ok(text) => text,
+        Err(_) => return Vec::new(),
+    };
+    let offset = usize::from(pos.offset).min(text.len());
+    if !text.is_char_boundary(offset) {
+        return Vec::new();
+    }
+
+    let before = text[..offset].trim_end_matches(is_ident_char);
+    let start = TextSize::of(before);
+    let prefix = &text[before.len()..offset];
+    let range = TextRange::new(start, TextSize::from(offset as u32));
+
+    let mut res: Vec<_> = if before.ends_with('`') {
+        macro_completions(sema, range)
+    } else {
+        let scope = match sema.token_before(pos.file, start).and_then(|token| token.parent()) {
+            Some(node) => sema.scope_at(&node),
+            None => sema.scope_at(sema.tree().syntax()),
+        };
+
+        // arguments of `$param_given(..)` and `$port_connected(..)` must be plain names
+        let required_kind = before
+            .trim_end()
+            .strip_suffix('(')
+            .and_then(|call| ITEM_ARGS.iter().find(|(name, _)| call.trim_end().ends_with(name)))
+            .map(|(_, kind)| *kind);
+
+        sema.visible_definitions(scope)
+            .into_iter()
+            .filter(|(name, def)| match required_kind {
+                Some(kind) => SymbolKind::from(*def) == kind,
+                // system functions are only proposed once a `$` has been typed
+                None => name.starts_with('$') == prefix.starts_with('$'),
+            })
+            .map(|(name, def)| CompletionItem {
+                label: name.to_string(),
+                kind: def.into(),
+                detail: Some(detail(sema, def)),
+                range,
+            })
+            .collect()
+    };
+
+    res.retain(|item| item.label.starts_with(prefix));
+    res.sort_by(|item1, item2| item1.label.cmp(&item2.label));
+    res
+}
+
+fn macro_completions(sema: &Semantics, range: TextRange) -> Vec<CompletionItem> {
+    let db = sema.db();
+    let mut res: Vec<CompletionItem> = Vec::new();
+    for (decl, head) in sema.macros() {
+        let detail = match head {
+            Some(head) => {
+                let text = db.file_text(head.file).ok();
+                text.map(|text| text[head.range].split_whitespace().collect::<Vec<_>>().join(" "))
+            }
+            None => Some("defined on the command line".to_owned()),
+        };
+        let item =
+            CompletionItem { label: decl.name.to_string(), kind: SymbolKind::Macro, detail, range };
+
+        // macros may be redefined, the last definition is used afterwards
+        match res.iter_mut().find(|it| it.label == item.label) {
+            Some(old) => *old = item,
+            None => res.push(item),
+        }
+    }
+    res
+}
+
+fn detail(sema: &Semantics, def: Definition) -> String {
+    let db = sema.db();
+    match signatures(db, def).into_iter().next() {
+        Some(signature) => signature.label,
+        None => match def {
+            Definition::Variable(var) => format!("{} variable", var.ty(db)),
+            Definition::Parameter(param) => format!("{} parameter", param.ty(db)),
+            Definition::FunctionArg(arg) => format!("{} function argument", arg.ty(db)),
+            _ => def.kind_name().to_owned(),
+        },
+    }
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
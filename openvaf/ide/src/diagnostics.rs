@@ -0,0 +1,83 @@
+use std::fmt::Display;
+
+use basedb::diagnostics::{DiagnosticSink, LabelStyle, Report};
+use basedb::FileId;
+use hir::CompilationDB;
+use syntax::{TextRange, TextSize};
+
+pub use basedb::diagnostics::Severity;
+
+use crate::FileRange;
+
+/// A diagnostic (error/warning) of a compilation unit. This is a flattened representation of
+/// the reports printed by the command line compiler that is convenient for editors.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub range: FileRange,
+    pub severity: Severity,
+    /// The code of the lint that produced this diagnostic (like `L001`).
+    pub code: Option<String>,
+    /// The message of the report followed by the message of the primary label and all notes.
+    pub message: String,
+    /// Secondary labels of the report.
+    pub related: Vec<(FileRange, String)>,
+}
+
+#[derive(Default)]
+struct ReportCollector {
+    reports: Vec<Report>,
+}
+
+impl DiagnosticSink for ReportCollector {
+    fn add_report(&mut self, report: Report) {
+        self.reports.push(report)
+    }
+
+    fn summary(&mut self, _target_name: &impl Display) -> bool {
+        self.reports.iter().any(|report| report.severity == Severity::Error)
+    }
+}
+
+pub(crate) fn diagnostics(db: &CompilationDB) -> Vec<Diagnostic> {
+    let unit = db.compilation_unit();
+    let mut sink = ReportCollector::default();
+    unit.diagnostics(db, &mut sink);
+    sink.reports.into_iter().map(|report| to_diagnostic(report, unit.root_file())).collect()
+}
+
+fn to_diagnostic(report: Report, root_file: FileId) -> Diagnostic {
+    let to_file_range = |file, range: std::ops::Range<usize>| FileRange {
+        file,
+        range: TextRange::new(TextSize::from(range.start as u32), TextSize::from(range.end as u32)),
+    };
+
+    let mut message = report.message;
+    let mut range = None;
+    let mut related = Vec::new();
+    for label in report.labels {
+        let label_range = to_file_range(label.file_id, label.range);
+        match label.style {
+            LabelStyle::Primary if range.is_none() => {
+                range = Some(label_range);
+                if !label.message.is_empty() {
+                    message.push('\n');
+                    message.push_str(&label.message);
+                }
+            }
+            _ => related.push((label_range, label.message)),
+        }
+    }
+
+    // reports without a primary label (like a missing root file) are shown at the start
+    // of the compilation unit
+    let range = range
+        .or_else(|| related.first().map(|(range, _)| *range))
+        .unwrap_or(FileRange { file: root_file, range: TextRange::default() });
+
+    for note in report.notes {
+        message.push('\n');
+        message.push_str(&note);
+    }
+
+    Diagnostic { range, severity: report.severity, code: report.code, message, related }
+}
@@ -0,0 +1,106 @@
+use basedb::FileId;
+use hir::Semantics;
+use syntax::ast::{self, AstNode};
+use syntax::{match_ast, SyntaxNode, TextRange};
+
+use crate::SymbolKind;
+
+/// An item declared in a file, used for outlines and breadcrumbs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DocumentSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The range of the entire declaration.
+    pub full_range: TextRange,
+    /// The range of the declared name.
+    pub focus_range: TextRange,
+    pub children: Vec<DocumentSymbol>,
+}
+
+pub(crate) fn document_symbols(sema: &Semantics, file: FileId) -> Vec<DocumentSymbol> {
+    let mut res = Vec::new();
+    collect(sema, file, sema.tree().syntax(), &mut res);
+    res
+}
+
+fn collect(sema: &Semantics, file: FileId, node: &SyntaxNode, dst: &mut Vec<DocumentSymbol>) {
+    for child in node.children() {
+        let (decls, is_container) = declarations(&child);
+        if decls.is_empty() {
+            collect(sema, file, &child, dst);
+            continue;
+        }
+
+        for (name, kind, full) in decls {
+            let mut children = Vec::new();
+            if is_container {
+                collect(sema, file, &child, &mut children);
+            }
+
+            let full_span = sema.file_span(full.text_range());
+            let focus_span = sema.file_span(name.syntax().text_range());
+            match (full_span, focus_span) {
+                (Some(full_span), Some(focus_span)) if focus_span.file == file => {
+                    // the declaration may have been expanded from a macro in a different file
+                    let full_range =
+                        if full_span.file == file { full_span.range } else { focus_span.range };
+                    dst.push(DocumentSymbol {
+                        name: name.text().to_string(),
+                        kind,
+                        full_range,
+                        focus_range: focus_span.range,
+                        children,
+                    })
+                }
+                // items that were declared in another file (that includes this one) are skipped
+                _ => dst.append(&mut children),
+            }
+        }
+    }
+}
+
+type Declaration = (ast::Name, SymbolKind, SyntaxNode);
+
+/// Returns the names declared by `node` (and the node spanning the entire declaration).
+/// The second return value indicates whether `node` may contain further declarations.
+fn declarations(node: &SyntaxNode) -> (Vec<Declaration>, bool) {
+    let single = |name: Option<ast::Name>, kind: SymbolKind| -> Vec<Declaration> {
+        name.map(|name| (name, kind, node.clone())).into_iter().collect()
+    };
+    let names = |names: ast::AstChildren<ast::Name>, kind: SymbolKind| -> Vec<Declaration> {
+        names.map(|name| (name, kind, node.clone())).collect()
+    };
+
+    match_ast! {
+        match node {
+            ast::ModuleDecl(it) => (single(it.name(), SymbolKind::Module), true),
+            ast::ParamsetDecl(it) => (single(it.name(), SymbolKind::Paramset), true),
+            ast::NatureDecl(it) => (single(it.name(), SymbolKind::Nature), false),
+            ast::DisciplineDecl(it) => (single(it.name(), SymbolKind::Discipline), false),
+            ast::Function(it) => (single(it.name(), SymbolKind::Function), true),
+            ast::BlockStmt(it) => {
+                let name = it.block_scope().and_then(|scope| scope.name());
+                (single(name, SymbolKind::Block), true)
+            },
+            ast::AliasParam(it) => (single(it.name(), SymbolKind::Parameter), false),
+            ast::ParamDecl(it) => {
+                let params = it
+                    .paras()
+                    .filter_map(|param| Some((param.name()?, SymbolKind::Parameter, param.syntax().clone())))
+                    .collect();
+                (params, false)
+            },
+            ast::VarDecl(it) => {
+                let vars = it
+                    .vars()
+                    .filter_map(|var| Some((var.name()?, SymbolKind::Variable, var.syntax().clone())))
+                    .collect();
+                (vars, false)
+            },
+            ast::NetDecl(it) => (names(it.names(), SymbolKind::Node), false),
+            ast::PortDecl(it) => (names(it.names(), SymbolKind::Node), false),
+            ast::BranchDecl(it) => (names(it.names(), SymbolKind::Branch), false),
+            _ => (Vec::new(), false),
+        }
+    }
+}
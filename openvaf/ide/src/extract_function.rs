@@ -0,0 +1,146 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use anyhow::{bail, Context, Result};
+use basedb::BaseDB;
+use hir::{Definition, Semantics, Type};
+use syntax::{ast, AstNode, TextRange, TextSize};
+
+use crate::{check_ident, FileRange, SourceChange, TextEdit};
+
+const INDENT: &str = "    ";
+
+pub(crate) fn extract_function(
+    sema: &Semantics,
+    range: FileRange,
+    name: &str,
+) -> Result<SourceChange> {
+    check_ident(name)?;
+    let db = sema.db();
+    let expr = selected_expr(sema, range).context("the selection is not an expression")?;
+    let module = expr
+        .syntax()
+        .ancestors()
+        .find_map(ast::ModuleDecl::cast)
+        .context("only expressions inside a module can be extracted")?;
+
+    if expr.syntax().parent().and_then(ast::Assign::cast).and_then(|it| it.lval()).as_ref()
+        == Some(&expr)
+    {
+        bail!("the left hand side of an assignment can not be extracted");
+    }
+
+    let ty = match sema.expr_type(&expr) {
+        Some(ty @ (Type::Real | Type::Integer)) => ty,
+        Some(ty) => bail!("analog functions can not return a value of type {ty}"),
+        None => bail!("only expressions inside analog blocks can be extracted"),
+    };
+
+    let conflict = sema
+        .visible_definitions(sema.scope_at(module.syntax()))
+        .into_iter()
+        .find(|(it, _)| &**it == name);
+    if let Some((_, def)) = conflict {
+        bail!("a {} called `{name}` already exists", def.kind_name());
+    }
+
+    // all variables used by the expression become arguments of the function
+    let mut args: Vec<(String, Type)> = Vec::new();
+    for token in expr.syntax().descendants_with_tokens().filter_map(|it| it.into_token()) {
+        let (arg, arg_ty) = match sema.resolve_token(&token) {
+            Some(Definition::Variable(var)) => (var.name(db).to_string(), var.ty(db)),
+            Some(Definition::FunctionArg(arg)) => (arg.name(db), arg.ty(db)),
+            Some(Definition::Node(_) | Definition::Branch(_) | Definition::NatureAccess(_)) => {
+                bail!("analog functions can not access nodes or branches")
+            }
+            Some(Definition::BuiltIn(builtin))
+                if builtin.is_analog_operator() || builtin.is_analog_operator_sysfun() =>
+            {
+                bail!("analog functions can not use the analog operator `{}`", token.text())
+            }
+            _ => continue,
+        };
+        if !matches!(arg_ty, Type::Real | Type::Integer) {
+            bail!("`{arg}` has type {arg_ty} which can not be passed to an analog function");
+        }
+        if args.iter().all(|(it, _)| *it != arg) {
+            args.push((arg, arg_ty));
+        }
+    }
+    if args.is_empty() {
+        bail!(
+            "analog functions require at least one argument but the expression uses no variables"
+        );
+    }
+
+    // the function is declared before the module item that contains the expression
+    let item = expr
+        .syntax()
+        .ancestors()
+        .find(|node| node.parent().as_ref() == Some(module.syntax()))
+        .context("the expression is not part of a module item")?;
+    let expr_span = sema.file_span(expr.syntax().text_range()).context("invalid selection")?;
+    let item_span = sema.file_span(item.text_range()).context("invalid selection")?;
+    if item_span.file != expr_span.file {
+        bail!("the module item that contains the expression was expanded from a macro");
+    }
+    let text = db.file_text(expr_span.file).ok().context("failed to read file")?;
+    let item_start = usize::from(item_span.range.start());
+    let line_start = text[..item_start].rfind('\n').map_or(0, |pos| pos + 1);
+    let indent = &text[line_start..item_start];
+    if !indent.trim().is_empty() {
+        bail!("the module item that contains the expression does not start on its own line");
+    }
+
+    let expr_text = &text[Range::<usize>::from(expr_span.range)];
+    let mut fun = format!("{indent}analog function {ty} {name};\n");
+    let names: Vec<_> = args.iter().map(|(arg, _)| arg.as_str()).collect();
+    let _ = writeln!(fun, "{indent}{INDENT}input {};", names.join(", "));
+    for arg_ty in [Type::Real, Type::Integer] {
+        let names: Vec<_> =
+            args.iter().filter(|(_, ty)| *ty == arg_ty).map(|(arg, _)| arg.as_str()).collect();
+        if !names.is_empty() {
+            let _ = writeln!(fun, "{indent}{INDENT}{arg_ty} {};", names.join(", "));
+        }
+    }
+    let _ = writeln!(fun, "{indent}{INDENT}begin");
+    let _ = writeln!(fun, "{indent}{INDENT}{INDENT}{name} = {expr_text};");
+    let _ = writeln!(fun, "{indent}{INDENT}end");
+    let _ = writeln!(fun, "{indent}endfunction\n");
+
+    let mut res = SourceChange::default();
+    let line_start = TextSize::from(line_start as u32);
+    res.insert(expr_span.file, TextEdit { range: TextRange::empty(line_start), insert: fun });
+    res.insert(
+        expr_span.file,
+        TextEdit { range: expr_span.range, insert: format!("{name}({})", names.join(", ")) },
+    );
+    Ok(res)
+}
+
+/// Finds the expression that is selected by `range`. Whitespace around the expression is
+/// ignored but the expression must have been written exactly like this in the file (not
+/// expanded from a macro).
+fn selected_expr(sema: &Semantics, range: FileRange) -> Option<ast::Expr> {
+    let mut tokens = sema
+        .tokens_in_file(range.file)
+        .filter(|(_, span)| range.range.contains_range(span.range))
+        .map(|(token, _)| token);
+    let first = tokens.next()?;
+    let last = tokens.last().unwrap_or_else(|| first.clone());
+    let selection = TextRange::new(first.text_range().start(), last.text_range().end());
+
+    let expr = first
+        .parent()?
+        .ancestors()
+        .filter_map(ast::Expr::cast)
+        .find(|expr| expr.syntax().text_range().contains_range(selection))?;
+    if expr.syntax().text_range() != selection {
+        return None;
+    }
+
+    let span = sema.file_span(selection)?;
+    let text = sema.db().file_text(span.file).ok()?;
+    let written = text.get(Range::<usize>::from(span.range))?;
+    (expr.syntax().text() == written).then_some(expr)
+}
@@ -0,0 +1,27 @@
+use hir::Semantics;
+
+use crate::{definition_at, FilePosition, NavigationTarget};
+
+pub(crate) fn goto_definition(sema: &Semantics, pos: FilePosition) -> Vec<NavigationTarget> {
+    let (def, _) = match definition_at(sema, pos) {
+        Some(res) => res,
+        None => return Vec::new(),
+    };
+
+    // builtins and definitions inside protected envelopes have no location
+    let (full, focus) = match sema.definition_spans(def) {
+        Some(spans) => spans,
+        None => return Vec::new(),
+    };
+
+    // the declaration may have been expanded from a macro in a different file
+    let full_range = if full.file == focus.file { full.range } else { focus.range };
+
+    vec![NavigationTarget {
+        file: focus.file,
+        full_range,
+        focus_range: focus.range,
+        name: def.name(sema.db()),
+        kind: def.into(),
+    }]
+}
@@ -0,0 +1,116 @@
+use std::fmt::Write;
+
+use hir::{Definition, Semantics};
+
+use crate::signature_help::function_args;
+use crate::{definition_at, FilePosition, FileRange};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HoverResult {
+    /// The range of the hovered identifier.
+    pub range: FileRange,
+    /// The hover text (markdown).
+    pub markup: String,
+}
+
+pub(crate) fn hover(sema: &Semantics, pos: FilePosition) -> Option<HoverResult> {
+    let (def, range) = definition_at(sema, pos)?;
+    let db = sema.db();
+    let name = def.name(db);
+
+    // Only show details if the declaration is not part of a protected envelope.
+    // Builtins are never declared in the source code and can always be shown.
+    let declaration = def.source(db);
+    let visible = match &declaration {
+        Some(source) => sema.file_span(source.text_range()).is_some(),
+        None => true,
+    };
+    let declaration = declaration.filter(|_| visible);
+    let decl_text = |node: &syntax::SyntaxNode| {
+        node.text().to_string().split_whitespace().collect::<Vec<_>>().join(" ")
+    };
+
+    let signature = match def {
+        Definition::Module(_) => format!("module {name}"),
+        Definition::Paramset(paramset) => match paramset.module(db) {
+            Some(module) => format!("paramset {name} {}", module.name(db)),
+            None => format!("paramset {name}"),
+        },
+        Definition::Block(_) => format!("begin : {name}"),
+        Definition::Nature(_) => format!("nature {name}"),
+        Definition::NatureAccess(nature) => {
+            format!("{name}  // access function of nature {}", nature.name(db))
+        }
+        Definition::NatureAttribute(_) => format!("{name}  // nature attribute"),
+        Definition::Discipline(discipline) => {
+            let mut res = format!("discipline {name}");
+            if let Some(potential) = discipline.potential(db) {
+                let _ = write!(res, "\n    potential {};", potential.name(db));
+            }
+            if let Some(flow) = discipline.flow(db) {
+                let _ = write!(res, "\n    flow {};", flow.name(db));
+            }
+            res
+        }
+        Definition::Node(node) => {
+            let direction = match (node.is_input(db), node.is_output(db)) {
+                (true, true) => "inout ",
+                (true, false) => "input ",
+                (false, true) => "output ",
+                (false, false) => "",
+            };
+            let discipline = match node.discipline(db) {
+                Some(discipline) => discipline.name(db),
+                None if node.is_real(db) => "wreal".to_owned(),
+                None => String::new(),
+            };
+            format!("{direction}{discipline} {name}").trim().to_owned()
+        }
+        Definition::Variable(var) => match &declaration {
+            Some(decl) => format!("{} {}", var.ty(db), decl_text(decl)),
+            None => format!("{} {name}", var.ty(db)),
+        },
+        Definition::Parameter(param) => match &declaration {
+            Some(decl) => format!("parameter {} {}", param.ty(db), decl_text(decl)),
+            None => format!("parameter {} {name}", param.ty(db)),
+        },
+        Definition::AliasParameter(_) => match &declaration {
+            Some(decl) => decl_text(decl),
+            None => format!("aliasparam {name}"),
+        },
+        Definition::Branch(_) => match &declaration {
+            Some(decl) => decl_text(decl),
+            None => format!("branch {name}"),
+        },
+        Definition::Function(fun) => {
+            let args = function_args(db, fun).join(", ");
+            format!("analog function {} {name}({args})", fun.return_ty(db))
+        }
+        Definition::FunctionArg(arg) => {
+            format!("{} {name}  // argument of {}", arg.ty(db), arg.function().name(db))
+        }
+        Definition::BuiltIn(_) | Definition::ParamSysFun(_) => {
+            format!("{name}  // {}", def.kind_name())
+        }
+    };
+
+    let mut markup = format!("```verilog-a\n{signature}\n```");
+    if visible {
+        let desc = def.attr(db, "desc");
+        let units = def.attr(db, "units").or_else(|| match def {
+            Definition::Nature(nature) => Some(nature.units(db)).filter(|it| !it.is_empty()),
+            _ => None,
+        });
+        if desc.is_some() || units.is_some() {
+            markup.push_str("\n\n---\n");
+        }
+        if let Some(desc) = desc {
+            let _ = write!(markup, "\n{desc}\n");
+        }
+        if let Some(units) = units {
+            let _ = write!(markup, "\nunits: `{units}`\n");
+        }
+    }
+
+    Some(HoverResult { range, markup })
+}
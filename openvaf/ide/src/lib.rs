@@ -0,0 +1,359 @@
+//! IDE features for Verilog-A (diagnostics, go-to-definition, hover, find-references,
+//! document symbols, completion, signature help and refactorings) built on top of the
+//! incremental [`CompilationDB`].
+//!
+//! The [`AnalysisHost`] owns a single [`Vfs`] that is shared by one database per compilation
+//! unit (root file). Editors overlay the contents of unsaved buffers onto the vfs with
+//! [`AnalysisHost::set_file_text`]. All queries are recomputed lazily (and incrementally) by
+//! salsa when they are requested afterwards.
+//!
+//! Files that are not a root file themselves (for example files that are only included)
+//! are analyzed in the context of the first compilation unit that reads them.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use anyhow::{bail, Context, Result};
+use basedb::line_index::LineIndex;
+use basedb::{AbsPathBuf, BaseDB, FileId, Vfs, VfsEntry, VfsPath};
+use hir::{CompilationDB, Definition, Semantics};
+use parking_lot::RwLock;
+use syntax::{SyntaxKind, TextRange, TextSize};
+
+pub use crate::completion::CompletionItem;
+pub use crate::diagnostics::{Diagnostic, Severity};
+pub use crate::document_symbols::DocumentSymbol;
+pub use crate::hover::HoverResult;
+pub use crate::signature_help::{Signature, SignatureHelp};
+
+mod completion;
+mod diagnostics;
+mod document_symbols;
+mod extract_function;
+mod goto_definition;
+mod hover;
+mod references;
+mod rename;
+mod signature_help;
+
+#[cfg(test)]
+mod tests;
+
+/// A position inside a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FilePosition {
+    pub file: FileId,
+    pub offset: TextSize,
+}
+
+/// A range inside a file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FileRange {
+    pub file: FileId,
+    pub range: TextRange,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Module,
+    Paramset,
+    Block,
+    Nature,
+    NatureAttribute,
+    Discipline,
+    Node,
+    Variable,
+    Parameter,
+    Branch,
+    Function,
+    FunctionArg,
+    BuiltIn,
+    Macro,
+}
+
+impl From<Definition> for SymbolKind {
+    fn from(def: Definition) -> SymbolKind {
+        match def {
+            Definition::Module(_) => SymbolKind::Module,
+            Definition::Paramset(_) => SymbolKind::Paramset,
+            Definition::Block(_) => SymbolKind::Block,
+            Definition::Nature(_) => SymbolKind::Nature,
+            Definition::NatureAttribute(_) => SymbolKind::NatureAttribute,
+            Definition::Discipline(_) => SymbolKind::Discipline,
+            Definition::Node(_) => SymbolKind::Node,
+            Definition::Variable(_) => SymbolKind::Variable,
+            Definition::Parameter(_) | Definition::AliasParameter(_) => SymbolKind::Parameter,
+            Definition::Branch(_) => SymbolKind::Branch,
+            Definition::Function(_) | Definition::NatureAccess(_) => SymbolKind::Function,
+            Definition::FunctionArg(_) => SymbolKind::FunctionArg,
+            Definition::BuiltIn(_) | Definition::ParamSysFun(_) => SymbolKind::BuiltIn,
+        }
+    }
+}
+
+/// The location of a definition that an editor can navigate to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NavigationTarget {
+    pub file: FileId,
+    /// The range of the entire declaration.
+    pub full_range: TextRange,
+    /// The range of the declared name.
+    pub focus_range: TextRange,
+    pub name: String,
+    pub kind: SymbolKind,
+}
+
+/// Replaces the text in `range` with `insert`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    pub range: TextRange,
+    pub insert: String,
+}
+
+/// The edits performed by a refactoring. The edits of a file never overlap.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SourceChange {
+    pub edits: BTreeMap<FileId, Vec<TextEdit>>,
+}
+
+impl SourceChange {
+    fn insert(&mut self, file: FileId, edit: TextEdit) {
+        let edits = self.edits.entry(file).or_default();
+        // tokens expanded from the same macro body map to the same location
+        if !edits.contains(&edit) {
+            edits.push(edit);
+        }
+    }
+
+    /// Applies the edits of `file` to its contents `text`.
+    pub fn apply(&self, file: FileId, text: &mut String) {
+        let mut edits: Vec<_> = self.edits.get(&file).into_iter().flatten().collect();
+        edits.sort_unstable_by_key(|edit| edit.range.start());
+        for edit in edits.into_iter().rev() {
+            text.replace_range(std::ops::Range::<usize>::from(edit.range), &edit.insert);
+        }
+    }
+}
+
+/// Settings that apply to all compilation units.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Config {
+    pub include_dirs: Vec<AbsPathBuf>,
+    /// Macros that are defined before preprocessing (`-D` on the command line).
+    pub macro_flags: Vec<String>,
+}
+
+pub struct AnalysisHost {
+    vfs: Arc<RwLock<Vfs>>,
+    config: Config,
+    roots: HashMap<FileId, CompilationDB>,
+}
+
+impl Default for AnalysisHost {
+    fn default() -> AnalysisHost {
+        AnalysisHost::new(Config::default())
+    }
+}
+
+impl AnalysisHost {
+    pub fn new(config: Config) -> AnalysisHost {
+        let mut vfs = Vfs::default();
+        vfs.insert_std_lib();
+        AnalysisHost { vfs: Arc::new(RwLock::new(vfs)), config, roots: HashMap::new() }
+    }
+
+    /// Changes the configuration. All compilation units are recreated from scratch.
+    pub fn set_config(&mut self, config: Config) -> Result<()> {
+        self.config = config;
+        let roots: Vec<_> = self.roots.drain().map(|(root, _)| root).collect();
+        for root in roots {
+            self.add_root(root)?;
+        }
+        Ok(())
+    }
+
+    pub fn file_id(&self, path: VfsPath) -> FileId {
+        self.vfs.write().ensure_file_id(path)
+    }
+
+    pub fn file_path(&self, file: FileId) -> VfsPath {
+        self.vfs.read().file_path(file)
+    }
+
+    /// Overlays the contents of `file` with `text` (the contents of an unsaved editor buffer).
+    pub fn set_file_text(&mut self, file: FileId, text: String) {
+        self.vfs.write().set_file_contents(file, text.into());
+        self.apply_vfs_changes();
+    }
+
+    /// Removes the overlay of `file` so that the contents are read from disk again.
+    pub fn clear_file_text(&mut self, file: FileId) {
+        self.vfs.write().set_file_contents(file, VfsEntry::default());
+        self.apply_vfs_changes();
+    }
+
+    fn apply_vfs_changes(&mut self) {
+        let changes = self.vfs.write().take_changes();
+        for db in self.roots.values_mut() {
+            let db: &mut dyn BaseDB = db;
+            for change in &changes {
+                db.invalidate_file(change.file_id);
+            }
+        }
+    }
+
+    /// Registers `file` as the root file of a compilation unit.
+    pub fn add_root(&mut self, file: FileId) -> Result<()> {
+        if self.roots.contains_key(&file) {
+            return Ok(());
+        }
+
+        let include_dirs =
+            self.config.include_dirs.iter().map(|dir| Ok(VfsPath::from(dir.clone())));
+        let macro_flags = self.config.macro_flags.iter().map(String::as_str);
+        let db = CompilationDB::with_vfs(
+            self.vfs.clone(),
+            file,
+            include_dirs,
+            macro_flags,
+            std::iter::empty(),
+        )?;
+        self.roots.insert(file, db);
+        Ok(())
+    }
+
+    pub fn remove_root(&mut self, file: FileId) {
+        self.roots.remove(&file);
+    }
+
+    pub fn is_root(&self, file: FileId) -> bool {
+        self.roots.contains_key(&file)
+    }
+
+    pub fn roots(&self) -> impl Iterator<Item = FileId> + '_ {
+        self.roots.keys().copied()
+    }
+
+    /// All files read by the compilation unit `root` (including the root file itself).
+    pub fn files(&self, root: FileId) -> Vec<FileId> {
+        let mut files: Vec<_> = match self.roots.get(&root) {
+            Some(db) => db.sourcemap(root).files().collect(),
+            None => return Vec::new(),
+        };
+        files.sort_unstable();
+        files.dedup();
+        files
+    }
+
+    /// Finds the compilation unit that `file` is analyzed in.
+    pub fn root_for(&self, file: FileId) -> Option<FileId> {
+        if self.roots.contains_key(&file) {
+            return Some(file);
+        }
+        let mut roots: Vec<_> = self.roots.iter().collect();
+        // make the choice deterministic
+        roots.sort_unstable_by_key(|(root, _)| **root);
+        roots
+            .into_iter()
+            .find_map(|(&root, db)| db.sourcemap(root).files().any(|it| it == file).then_some(root))
+    }
+
+    fn db_for(&self, file: FileId) -> Option<&CompilationDB> {
+        self.roots.get(&self.root_for(file)?)
+    }
+
+    fn with_semantics<T>(&self, file: FileId, f: impl FnOnce(&Semantics) -> T) -> Option<T> {
+        let db = self.db_for(file)?;
+        Some(f(&Semantics::new(db)))
+    }
+
+    pub fn file_text(&self, file: FileId) -> Option<Arc<str>> {
+        self.db_for(file)?.file_text(file).ok()
+    }
+
+    pub fn line_index(&self, file: FileId) -> Arc<LineIndex> {
+        match self.db_for(file) {
+            Some(db) => db.line_index(file),
+            None => Arc::new(LineIndex::new(self.vfs.read().file_contents_unchecked(file))),
+        }
+    }
+
+    /// All diagnostics of the compilation unit `root`. The diagnostics may point into any file
+    /// read by the compilation unit.
+    pub fn diagnostics(&self, root: FileId) -> Vec<Diagnostic> {
+        match self.roots.get(&root) {
+            Some(db) => diagnostics::diagnostics(db),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn goto_definition(&self, pos: FilePosition) -> Vec<NavigationTarget> {
+        self.with_semantics(pos.file, |sema| goto_definition::goto_definition(sema, pos))
+            .unwrap_or_default()
+    }
+
+    pub fn hover(&self, pos: FilePosition) -> Option<HoverResult> {
+        self.with_semantics(pos.file, |sema| hover::hover(sema, pos))?
+    }
+
+    pub fn references(&self, pos: FilePosition, include_declaration: bool) -> Vec<FileRange> {
+        self.with_semantics(pos.file, |sema| references::references(sema, pos, include_declaration))
+            .unwrap_or_default()
+    }
+
+    pub fn document_symbols(&self, file: FileId) -> Vec<DocumentSymbol> {
+        self.with_semantics(file, |sema| document_symbols::document_symbols(sema, file))
+            .unwrap_or_default()
+    }
+
+    /// Proposes the names (or macros) that can be inserted at `pos`.
+    pub fn completions(&self, pos: FilePosition) -> Vec<CompletionItem> {
+        self.with_semantics(pos.file, |sema| completion::completions(sema, pos)).unwrap_or_default()
+    }
+
+    pub fn signature_help(&self, pos: FilePosition) -> Option<SignatureHelp> {
+        self.with_semantics(pos.file, |sema| signature_help::signature_help(sema, pos))?
+    }
+
+    /// Returns the range of the identifier at `pos` if it can be renamed.
+    pub fn prepare_rename(&self, pos: FilePosition) -> Result<FileRange> {
+        self.with_semantics(pos.file, |sema| rename::prepare_rename(sema, pos))
+            .context("file is not part of any compilation unit")?
+    }
+
+    /// Renames the item at `pos` (and all references to it) to `new_name`.
+    pub fn rename(&self, pos: FilePosition, new_name: &str) -> Result<SourceChange> {
+        self.with_semantics(pos.file, |sema| rename::rename(sema, pos, new_name))
+            .context("file is not part of any compilation unit")?
+    }
+
+    /// Moves the expression in `range` into a new analog function called `name`.
+    pub fn extract_function(&self, range: FileRange, name: &str) -> Result<SourceChange> {
+        self.with_semantics(range.file, |sema| {
+            extract_function::extract_function(sema, range, name)
+        })
+        .context("file is not part of any compilation unit")?
+    }
+}
+
+/// Checks that `name` can be used as the name of a declaration.
+fn check_ident(name: &str) -> Result<()> {
+    let mut chars = name.chars();
+    let valid = chars.next().map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if !valid {
+        bail!("`{name}` is not a valid identifier");
+    }
+    if SyntaxKind::from_keyword(name).is_some() {
+        bail!("`{name}` is a keyword");
+    }
+    Ok(())
+}
+
+/// Resolves the identifier at `pos`.
+fn definition_at(sema: &Semantics, pos: FilePosition) -> Option<(Definition, FileRange)> {
+    let token = sema.token_at(pos.file, pos.offset)?;
+    let def = sema.resolve_token(&token)?;
+    let span = sema.file_span(token.text_range())?;
+    Some((def, FileRange { file: span.file, range: span.range }))
+}
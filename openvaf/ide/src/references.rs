@@ -0,0 +1,27 @@
+use hir::Semantics;
+
+use crate::{definition_at, FilePosition, FileRange};
+
+pub(crate) fn references(
+    sema: &Semantics,
+    pos: FilePosition,
+    include_declaration: bool,
+) -> Vec<FileRange> {
+    let (def, _) = match definition_at(sema, pos) {
+        Some(res) => res,
+        None => return Vec::new(),
+    };
+
+    let declaration = sema.definition_spans(def).map(|(_, focus)| focus);
+    let mut res: Vec<_> = sema
+        .references(def)
+        .into_iter()
+        .filter(|(_, span)| include_declaration || Some(*span) != declaration)
+        .map(|(_, span)| FileRange { file: span.file, range: span.range })
+        .collect();
+
+    // tokens expanded from the same macro body map to the same location
+    res.sort_unstable_by_key(|it| (it.file, it.range.start(), it.range.end()));
+    res.dedup();
+    res
+}
@@ -0,0 +1,106 @@
+use std::ops::Range;
+
+use anyhow::{bail, Context, Result};
+use basedb::BaseDB;
+use hir::{Definition, Semantics};
+use syntax::ast::{self, ArgListOwner};
+use syntax::name::AsName;
+use syntax::{AstNode, TextRange, TextSize};
+
+use crate::{check_ident, definition_at, FilePosition, FileRange, SourceChange, TextEdit};
+
+/// The system function whose arguments may also refer to a parameter with a string.
+const PARAM_GIVEN: &str = "$param_given";
+
+pub(crate) fn prepare_rename(sema: &Semantics, pos: FilePosition) -> Result<FileRange> {
+    let (def, range) = definition_at(sema, pos).context("no item to rename at this position")?;
+    check_renameable(def)?;
+    Ok(range)
+}
+
+pub(crate) fn rename(sema: &Semantics, pos: FilePosition, new_name: &str) -> Result<SourceChange> {
+    let (def, _) = definition_at(sema, pos).context("no item to rename at this position")?;
+    check_renameable(def)?;
+    check_ident(new_name)?;
+
+    let db = sema.db();
+    let old_name = def.name(db);
+    let mut res = SourceChange::default();
+    if old_name == new_name {
+        return Ok(res);
+    }
+
+    // renaming must not change what other names resolve to
+    if let Some(decl) = def.source(db) {
+        let scope = sema.scope_at(&decl);
+        let conflict = sema
+            .visible_definitions(scope)
+            .into_iter()
+            .find(|(name, other)| &**name == new_name && *other != def);
+        if let Some((_, other)) = conflict {
+            bail!("a {} called `{new_name}` is already visible here", other.kind_name());
+        }
+    }
+
+    for token in sema.reference_tokens(def) {
+        let span = sema
+            .file_span(token.text_range())
+            .with_context(|| format!("`{old_name}` is used inside a protected envelope"))?;
+        let text = db.file_text(span.file).ok();
+        let text = text.as_deref().and_then(|text| text.get(Range::<usize>::from(span.range)));
+        if text != Some(old_name.as_str()) {
+            bail!("`{old_name}` is created by a macro and can not be renamed");
+        }
+        res.insert(span.file, TextEdit { range: span.range, insert: new_name.to_owned() });
+    }
+
+    // `$param_given("name")` refers to the parameter by string
+    if let Definition::Parameter(_) = def {
+        for call in sema.tree().syntax().descendants().filter_map(ast::Call::cast) {
+            let is_param_given = match call.function_ref() {
+                Some(ast::FunctionRef::SysFun(fun)) => *fun.as_name() == *PARAM_GIVEN,
+                _ => false,
+            };
+            let arg = call.arg_list().and_then(|args| args.args().next());
+            let arg = match arg {
+                Some(arg) if is_param_given => arg,
+                _ => continue,
+            };
+            if arg.as_str_literal().as_deref() != Some(old_name.as_str()) {
+                continue;
+            }
+            let scope = sema.scope_at(call.syntax());
+            let resolved = sema
+                .visible_definitions(scope)
+                .into_iter()
+                .find_map(|(name, it)| (*name == *old_name).then_some(it));
+            if resolved != Some(def) {
+                continue;
+            }
+
+            let span = match sema.file_span(arg.syntax().text_range()) {
+                Some(span) => span,
+                None => continue,
+            };
+            // only replace the content of the string literal
+            let quote = TextSize::of('"');
+            let range = TextRange::new(span.range.start() + quote, span.range.end() - quote);
+            res.insert(span.file, TextEdit { range, insert: new_name.to_owned() });
+        }
+    }
+
+    Ok(res)
+}
+
+fn check_renameable(def: Definition) -> Result<()> {
+    match def {
+        Definition::Parameter(_)
+        | Definition::AliasParameter(_)
+        | Definition::Variable(_)
+        | Definition::Node(_)
+        | Definition::Branch(_)
+        | Definition::Function(_)
+        | Definition::FunctionArg(_) => Ok(()),
+        _ => bail!("{}s can not be renamed", def.kind_name()),
+    }
+}
@@ -0,0 +1,138 @@
+use hir::signatures::BuiltinInfo;
+use hir::{BuiltIn, CompilationDB, Definition, Function, Semantics, Type};
+use syntax::ast::{self, ArgListOwner, AstNode};
+use syntax::{SyntaxToken, TextRange, TextSize, T};
+
+use crate::FilePosition;
+
+/// The signatures of the function that is called at the cursor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignatureHelp {
+    pub signatures: Vec<Signature>,
+    pub active_signature: usize,
+    /// The argument that the cursor is placed in.
+    pub active_parameter: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Signature {
+    /// The entire signature (like `max(integer value, integer value) -> integer`).
+    pub label: String,
+    /// The ranges of the parameters inside `label`.
+    pub params: Vec<TextRange>,
+}
+
+impl Signature {
+    fn new(name: &str, params: impl IntoIterator<Item = String>, varargs: bool) -> Signature {
+        let mut label = format!("{name}(");
+        let mut ranges = Vec::new();
+        for param in params {
+            if !ranges.is_empty() {
+                label.push_str(", ");
+            }
+            let start = TextSize::of(&*label);
+            label.push_str(&param);
+            ranges.push(TextRange::at(start, TextSize::of(&*param)));
+        }
+        if varargs {
+            label.push_str(if ranges.is_empty() { "..." } else { ", ..." });
+        }
+        label.push(')');
+        Signature { label, params: ranges }
+    }
+
+    fn with_return_ty(mut self, ty: &Type) -> Signature {
+        if *ty != Type::Void {
+            self.label = format!("{} -> {ty}", self.label);
+        }
+        self
+    }
+}
+
+pub(crate) fn signature_help(sema: &Semantics, pos: FilePosition) -> Option<SignatureHelp> {
+    let token = sema.token_before(pos.file, pos.offset)?;
+    let (call, arg_list) = token.parent()?.ancestors().find_map(|node| {
+        let call = ast::Call::cast(node)?;
+        let arg_list = call.arg_list()?;
+        // the cursor must be placed between the parentheses
+        if !is_before(sema, arg_list.l_paren_token()?, pos) {
+            return None;
+        }
+        if let Some(r_paren) = arg_list.r_paren_token() {
+            if is_before(sema, r_paren, pos) {
+                return None;
+            }
+        }
+        Some((call, arg_list))
+    })?;
+
+    let fun = match call.function_ref()? {
+        ast::FunctionRef::Path(path) => path.syntax().last_token()?,
+        ast::FunctionRef::SysFun(sysfun) => sysfun.sysfun_token()?,
+    };
+    let def = sema.resolve_token(&fun)?;
+    let signatures = signatures(sema.db(), def);
+    if signatures.is_empty() {
+        return None;
+    }
+
+    let active_parameter = arg_list
+        .syntax()
+        .children_with_tokens()
+        .filter_map(|it| it.into_token())
+        .filter(|token| token.kind() == T![,] && is_before(sema, token.clone(), pos))
+        .count();
+    let active_signature =
+        signatures.iter().position(|sig| sig.params.len() > active_parameter).unwrap_or(0);
+
+    Some(SignatureHelp { signatures, active_signature, active_parameter })
+}
+
+/// Returns whether `token` ends before the cursor.
+fn is_before(sema: &Semantics, token: SyntaxToken, pos: FilePosition) -> bool {
+    match sema.file_span(token.text_range()) {
+        Some(span) => span.file == pos.file && span.range.end() <= pos.offset,
+        None => false,
+    }
+}
+
+/// The signatures that `def` can be called with. Items that can not be called return an
+/// empty list. Some builtins (like `ddx`) accept arguments that can not be described by a
+/// signature and also return an empty list.
+pub(crate) fn signatures(db: &CompilationDB, def: Definition) -> Vec<Signature> {
+    let name = def.name(db);
+    let builtin = match def {
+        Definition::BuiltIn(builtin) => builtin,
+        // access functions accept the same arguments as `potential` and `flow`
+        Definition::NatureAccess(_) => BuiltIn::potential,
+        Definition::Function(fun) => {
+            let sig = Signature::new(&name, function_args(db, fun), false);
+            return vec![sig.with_return_ty(&fun.return_ty(db))];
+        }
+        _ => return Vec::new(),
+    };
+
+    let info = BuiltinInfo::from(builtin);
+    let varargs = info.max_args.is_none();
+    info.signatures
+        .iter()
+        .map(|sig| {
+            let params = sig.args.iter().map(|arg| arg.to_string());
+            Signature::new(&name, params, varargs).with_return_ty(&sig.return_ty)
+        })
+        .collect()
+}
+
+/// The arguments of a user defined function (like `input real x`).
+pub(crate) fn function_args(db: &CompilationDB, fun: Function) -> Vec<String> {
+    fun.args(db)
+        .map(|arg| {
+            let direction = match (arg.is_input(db), arg.is_output(db)) {
+                (true, true) => "inout",
+                (false, true) => "output",
+                _ => "input",
+            };
+            format!("{direction} {} {}", arg.ty(db), arg.name(db))
+        })
+        .collect()
+}
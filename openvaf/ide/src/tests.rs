@@ -0,0 +1,298 @@
+use basedb::VfsPath;
+use syntax::{TextRange, TextSize};
+
+use crate::{AnalysisHost, FilePosition, FileRange, Severity, SourceChange, SymbolKind};
+
+const CURSOR: &str = "$0";
+
+/// Creates an [`AnalysisHost`] with a single root file. The position of the cursor
+/// is marked with `$0` inside `src`.
+fn fixture(src: &str) -> (AnalysisHost, FilePosition) {
+    let offset = src.find(CURSOR).unwrap_or(0);
+    let text = src.replacen(CURSOR, "", 1);
+    let mut host = AnalysisHost::default();
+    let file = host.file_id(VfsPath::new_virtual_path("/amp.va".to_owned()));
+    host.set_file_text(file, text);
+    host.add_root(file).unwrap();
+    (host, FilePosition { file, offset: TextSize::from(offset as u32) })
+}
+
+fn text_at(host: &AnalysisHost, file: basedb::FileId, range: syntax::TextRange) -> String {
+    host.file_text(file).unwrap()[range].to_owned()
+}
+
+const AMP: &str = r#"`include "disciplines.vams"
+module amp(inp, out);
+    input inp;
+    output out;
+    electrical inp, out;
+    (* desc = "voltage gain", units = "V/V" *) parameter real gain = 2.0 from (0:inf);
+    real tmp;
+    analog begin
+        tmp = ga$0in * V(inp);
+        I(out) <+ tmp * gain;
+    end
+endmodule
+"#;
+
+#[test]
+fn goto_parameter() {
+    let (host, pos) = fixture(AMP);
+    let targets = host.goto_definition(pos);
+    assert_eq!(targets.len(), 1);
+    let target = &targets[0];
+    assert_eq!(target.file, pos.file);
+    assert_eq!(target.kind, SymbolKind::Parameter);
+    assert_eq!(target.name, "gain");
+    assert_eq!(text_at(&host, target.file, target.focus_range), "gain");
+}
+
+#[test]
+fn goto_std_lib() {
+    let (host, _) = fixture(AMP);
+    let file = host.file_id(VfsPath::new_virtual_path("/amp.va".to_owned()));
+    let offset = AMP.replacen(CURSOR, "", 1).find("electrical").unwrap();
+    let targets =
+        host.goto_definition(FilePosition { file, offset: TextSize::from(offset as u32) });
+    assert_eq!(targets.len(), 1);
+    assert_eq!(targets[0].kind, SymbolKind::Discipline);
+    assert_ne!(targets[0].file, file);
+    assert_eq!(host.file_path(targets[0].file).name().as_deref(), Some("disciplines.vams"));
+}
+
+#[test]
+fn hover_parameter() {
+    let (host, pos) = fixture(AMP);
+    let hover = host.hover(pos).unwrap();
+    assert_eq!(text_at(&host, pos.file, hover.range.range), "gain");
+    assert!(hover.markup.contains("parameter real gain = 2.0 from (0:inf)"), "{}", hover.markup);
+    assert!(hover.markup.contains("voltage gain"), "{}", hover.markup);
+    assert!(hover.markup.contains("units: `V/V`"), "{}", hover.markup);
+}
+
+#[test]
+fn find_references() {
+    let (host, pos) = fixture(AMP);
+    let references = host.references(pos, true);
+    assert_eq!(references.len(), 3);
+    assert!(references.iter().all(|it| text_at(&host, it.file, it.range) == "gain"));
+    assert_eq!(host.references(pos, false).len(), 2);
+}
+
+#[test]
+fn document_symbols() {
+    let (host, pos) = fixture(AMP);
+    let symbols = host.document_symbols(pos.file);
+    assert_eq!(symbols.len(), 1);
+    assert_eq!(symbols[0].name, "amp");
+    assert_eq!(symbols[0].kind, SymbolKind::Module);
+    let children: Vec<_> =
+        symbols[0].children.iter().map(|it| (it.name.as_str(), it.kind)).collect();
+    assert_eq!(
+        children,
+        [
+            ("inp", SymbolKind::Node),
+            ("out", SymbolKind::Node),
+            ("inp", SymbolKind::Node),
+            ("out", SymbolKind::Node),
+            ("gain", SymbolKind::Parameter),
+            ("tmp", SymbolKind::Variable),
+        ]
+    );
+}
+
+#[test]
+fn diagnostics_on_type() {
+    let (mut host, pos) = fixture(AMP);
+    let errors = |host: &AnalysisHost| {
+        host.diagnostics(pos.file)
+            .into_iter()
+            .filter(|diag| diag.severity == Severity::Error)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(errors(&host), []);
+
+    let text = AMP.replacen(CURSOR, "", 1).replace("tmp * gain", "tmp * gian");
+    host.set_file_text(pos.file, text);
+    let errors = errors(&host);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(text_at(&host, errors[0].range.file, errors[0].range.range), "gian");
+}
+
+/// Returns [`AMP`] with the contents of the analog block replaced by `stmt`.
+fn amp_with_analog(stmt: &str) -> String {
+    let start = AMP.find("    analog begin").unwrap();
+    let end = AMP.find("endmodule").unwrap();
+    format!("{}    analog begin\n        {stmt}\n    end\n{}", &AMP[..start], &AMP[end..])
+}
+
+fn completion_labels(src: &str) -> Vec<(String, SymbolKind)> {
+    let (host, pos) = fixture(src);
+    host.completions(pos).into_iter().map(|item| (item.label, item.kind)).collect()
+}
+
+#[test]
+fn complete_in_scope() {
+    let labels = completion_labels(&amp_with_analog("tmp = ga$0;"));
+    assert_eq!(labels, [("gain".to_owned(), SymbolKind::Parameter)]);
+
+    let labels = completion_labels(&amp_with_analog("I(out) <+ V$0;"));
+    assert!(labels.contains(&("V".to_owned(), SymbolKind::Function)), "{labels:?}");
+
+    let (host, pos) = fixture(&amp_with_analog("tmp = $0;"));
+    let items = host.completions(pos);
+    assert!(items.iter().any(|item| item.label == "tmp"));
+    assert!(items.iter().any(|item| item.label == "exp"));
+    assert!(items.iter().all(|item| !item.label.starts_with('$')));
+}
+
+#[test]
+fn complete_sysfun() {
+    let (host, pos) = fixture(&amp_with_analog("tmp = $par$0;"));
+    let items = host.completions(pos);
+    assert!(items.iter().any(|item| item.label == "$param_given"), "{items:?}");
+    assert!(items.iter().all(|item| item.label.starts_with("$par")));
+    let range = items[0].range;
+    assert_eq!(text_at(&host, pos.file, range), "$par");
+}
+
+#[test]
+fn complete_param_given() {
+    let labels = completion_labels(&amp_with_analog("if ($param_given($0)) tmp = 1;"));
+    assert_eq!(labels, [("gain".to_owned(), SymbolKind::Parameter)]);
+}
+
+#[test]
+fn complete_macro() {
+    let src = format!("`define AMP_GAIN(x) 2.0 * x\n{}", amp_with_analog("tmp = `AMP_$0;"));
+    let (host, pos) = fixture(&src);
+    let items = host.completions(pos);
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0].label, "AMP_GAIN");
+    assert_eq!(items[0].kind, SymbolKind::Macro);
+    assert_eq!(items[0].detail.as_deref(), Some("`define AMP_GAIN(x)"));
+}
+
+#[test]
+fn signature_help_builtin() {
+    let (host, pos) = fixture(&amp_with_analog("tmp = max(gain, $0V(inp));"));
+    let help = host.signature_help(pos).unwrap();
+    assert_eq!(help.active_parameter, 1);
+    let labels: Vec<_> = help.signatures.iter().map(|sig| sig.label.as_str()).collect();
+    assert_eq!(
+        labels,
+        ["max(integer value, integer value) -> integer", "max(real value, real value) -> real"]
+    );
+    let sig = &help.signatures[help.active_signature];
+    assert_eq!(&sig.label[sig.params[1]], "integer value");
+}
+
+#[test]
+fn signature_help_nested() {
+    let (host, pos) = fixture(&amp_with_analog("tmp = max(gain, V($0inp));"));
+    let help = host.signature_help(pos).unwrap();
+    assert_eq!(help.active_parameter, 0);
+    assert!(help.signatures.iter().all(|sig| sig.label.starts_with("V(")), "{help:?}");
+    assert!(help.signatures.iter().any(|sig| sig.label == "V(net reference) -> real"));
+}
+
+/// Applies `change` to the contents of `file`.
+fn apply(host: &AnalysisHost, file: basedb::FileId, change: &SourceChange) -> String {
+    let mut text = host.file_text(file).unwrap().to_string();
+    change.apply(file, &mut text);
+    text
+}
+
+const RENAME: &str = r#"`include "disciplines.vams"
+module amp(inp, out);
+    input inp;
+    output out;
+    electrical inp, out;
+    parameter real gain = 2.0 from (0:inf);
+    aliasparam g = gain;
+    real tmp;
+    analog begin
+        if ($param_given(gain) || $param_given("gain")) tmp = ga$0in * V(inp);
+        I(out) <+ tmp * gain;
+    end
+endmodule
+"#;
+
+#[test]
+fn rename_parameter() {
+    let (host, pos) = fixture(RENAME);
+    let range = host.prepare_rename(pos).unwrap();
+    assert_eq!(text_at(&host, range.file, range.range), "gain");
+
+    let change = host.rename(pos, "av").unwrap();
+    assert_eq!(change.edits.len(), 1);
+    let expected = RENAME.replacen(CURSOR, "", 1).replace("gain", "av");
+    assert_eq!(apply(&host, pos.file, &change), expected);
+}
+
+#[test]
+fn rename_invalid() {
+    let (host, pos) = fixture(RENAME);
+    let err = host.rename(pos, "tmp").unwrap_err();
+    assert_eq!(err.to_string(), "a variable called `tmp` is already visible here");
+    let err = host.rename(pos, "begin").unwrap_err();
+    assert_eq!(err.to_string(), "`begin` is a keyword");
+    let err = host.rename(pos, "1gain").unwrap_err();
+    assert_eq!(err.to_string(), "`1gain` is not a valid identifier");
+
+    let (host, pos) = fixture(&amp_with_analog("I(out) <+ $0V(inp);"));
+    let err = host.prepare_rename(pos).unwrap_err();
+    assert_eq!(err.to_string(), "nature access functions can not be renamed");
+}
+
+/// Extracts the (first occurrence of) `expr` in `src` into the function `extracted`.
+fn extract(src: &str, expr: &str) -> (AnalysisHost, FileRange, anyhow::Result<SourceChange>) {
+    let (host, pos) = fixture(src);
+    // only search the analog block, the names also occur in the declarations
+    let analog = src.find("analog begin").unwrap();
+    let start = TextSize::from((analog + src[analog..].find(expr).unwrap()) as u32);
+    let range = FileRange { file: pos.file, range: TextRange::at(start, TextSize::of(expr)) };
+    let change = host.extract_function(range, "extracted");
+    (host, range, change)
+}
+
+#[test]
+fn extract_function() {
+    let src = amp_with_analog("tmp = tmp + 2.0 * (tmp - gain);");
+    let (host, range, change) = extract(&src, "2.0 * (tmp - gain)");
+    let fun = r#"    analog function real extracted;
+        input tmp;
+        real tmp;
+        begin
+            extracted = 2.0 * (tmp - gain);
+        end
+    endfunction
+
+"#;
+    let expected = amp_with_analog("tmp = tmp + extracted(tmp);").replacen(
+        "    analog begin",
+        &format!("{fun}    analog begin"),
+        1,
+    );
+    assert_eq!(apply(&host, range.file, &change.unwrap()), expected);
+}
+
+#[test]
+fn extract_function_invalid() {
+    let src = amp_with_analog("tmp = tmp + 2.0 * V(inp);");
+    let (_, _, change) = extract(&src, "2.0 * V(inp)");
+    assert_eq!(
+        change.unwrap_err().to_string(),
+        "analog functions can not access nodes or branches"
+    );
+
+    // the selection must cover an entire expression
+    let (_, _, change) = extract(&src, "tmp + 2.0");
+    assert_eq!(change.unwrap_err().to_string(), "the selection is not an expression");
+
+    let (_, _, change) = extract(&amp_with_analog("tmp = gain;"), "gain");
+    assert_eq!(
+        change.unwrap_err().to_string(),
+        "analog functions require at least one argument but the expression uses no variables"
+    );
+}
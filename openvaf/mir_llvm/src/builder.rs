@@ -38,6 +38,19 @@ impl<'ll> MemLoc<'ll> {
             indices: vec![cx.const_unsigned_int(0), cx.const_unsigned_int(idx)].into_boxed_slice(),
         }
     }
+
+    /// Returns the location of the `idx`th element of the array stored at this location.
+    /// `ty` is the type of the array elements.
+    pub fn array_element(
+        &self,
+        idx: u32,
+        ty: &'ll llvm::Type,
+        cx: &CodegenCx<'_, 'll>,
+    ) -> MemLoc<'ll> {
+        let mut indices = self.indices.to_vec();
+        indices.push(cx.const_unsigned_int(idx));
+        MemLoc { ptr: self.ptr, ptr_ty: self.ptr_ty, ty, indices: indices.into_boxed_slice() }
+    }
     /// # Safety
     ///
     /// ptr_ty, ty and indices must be valid for ptr
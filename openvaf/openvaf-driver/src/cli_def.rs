@@ -5,7 +5,9 @@ use anyhow::bail;
 use camino::Utf8Path;
 use clap::builder::{PossibleValue, PossibleValuesParser, ValueParser};
 use clap::{Arg, ArgAction, Command, ValueHint};
-use openvaf::{builtin_lints, get_target_names, host_triple, LintLevel};
+use openvaf::{
+    builtin_lints, get_target_names, host_triple, LintLevel, DEFAULT_MACRO_EXPANSION_LIMIT,
+};
 use path_absolutize::Absolutize;
 
 const ABOUT: &str = r"For further information visit https://openvaf.semimod.de.";
@@ -19,10 +21,13 @@ pub fn main_command() -> Command {
         .args([
             def_arg(),
             include_dir(),
+            decryption_key(),
+            macro_expansion_limit(),
             lint_arg(LintLevel::Allow),
             lint_arg(LintLevel::Warn),
             lint_arg(LintLevel::Deny),
             lints(),
+            error_format(),
             output(),
             batchmode(),
             dry_run(),
@@ -37,20 +42,91 @@ pub fn main_command() -> Command {
             dump_json(),
             input(),
         ])
+        .subcommand(fmt_command())
+        .subcommand(rename_command())
+        .subcommand(extract_function_command())
         .subcommand_required(false)
+        .subcommand_negates_reqs(true)
         .arg_required_else_help(true)
 }
 
+fn fmt_command() -> Command {
+    Command::new(FMT)
+        .about("Format Verilog-A source files.")
+        .args([
+            flag(CHECK, CHECK)
+                .help("Only check whether the files are formatted.")
+                .long_help("Only check whether the files are formatted.\nThe files that would be changed are printed and the exit code is 1 if any file is not formatted."),
+            input_file_path_arg(FILES)
+                .help("The files to format.")
+                .action(ArgAction::Append)
+                .num_args(1..)
+                .required(true),
+        ])
+}
+
+fn rename_command() -> Command {
+    Command::new(RENAME)
+        .about("Rename a parameter, variable, node, branch or analog function.")
+        .long_about("Rename a parameter, variable, node, branch or analog function.\nAll references (including aliasparam declarations and $param_given calls) are updated in place.")
+        .args([
+            include_dir(),
+            def_arg(),
+            input_file_path_arg(FILE).help("The root Verilog-A file.").required(true),
+            position_arg(POSITION)
+                .help("The position of the identifier to rename.")
+                .required(true),
+            Arg::new(NAME).value_name("NEW_NAME").help("The new name.").required(true),
+        ])
+}
+
+fn extract_function_command() -> Command {
+    Command::new(EXTRACT_FUNCTION).about("Extract an expression into a new analog function.").args(
+        [
+            include_dir(),
+            def_arg(),
+            input_file_path_arg(FILE).help("The root Verilog-A file.").required(true),
+            position_arg(START)
+                .help("The position of the first character of the expression.")
+                .required(true),
+            position_arg(END)
+                .help("The position of the last character of the expression.")
+                .required(true),
+            Arg::new(NAME).value_name("NAME").help("The name of the new function.").required(true),
+        ],
+    )
+}
+
+fn position_arg(name: &'static str) -> Arg {
+    let parse = |raw: &str| -> anyhow::Result<(u32, u32)> {
+        let parse_num = |num: &str| -> anyhow::Result<u32> {
+            match num.parse() {
+                Ok(num) if num != 0 => Ok(num),
+                _ => bail!("expected LINE:COLUMN (starting at 1)"),
+            }
+        };
+        match raw.split_once(':') {
+            Some((line, column)) => Ok((parse_num(line)?, parse_num(column)?)),
+            None => bail!("expected LINE:COLUMN"),
+        }
+    };
+
+    Arg::new(name).value_name("LINE:COLUMN").value_parser(parse)
+}
+
 pub const INTERFACE: &str = "interface";
 pub const BATCHMODE: &str = "batchmode";
 pub const DRYRUN: &str = "dry-run";
 pub const TARGET: &str = "target";
 pub const SUPPORTED_TARGETS: &str = "supported-targets";
 pub const LINTS: &str = "lints";
+pub const ERROR_FORMAT: &str = "error-format";
 pub const TARGET_CPU: &str = "target_cpu";
 pub const CODEGEN: &str = "codegen";
 pub const INPUT: &str = "input";
 pub const INCLUDE: &str = "include";
+pub const DECRYPTION_KEY: &str = "decryption-key";
+pub const MACRO_EXPANSION_LIMIT: &str = "macro-expansion-limit";
 pub const OUTPUT: &str = "output";
 pub const CACHE_DIR: &str = "cache-dir";
 pub const OPT_LVL: &str = "opt_lvl";
@@ -60,6 +136,16 @@ pub const DUMP_JSON: &str = "dump-json";
 pub const ALLOW: &str = "allow";
 pub const WARN: &str = "warn";
 pub const DENY: &str = "deny";
+pub const FMT: &str = "fmt";
+pub const CHECK: &str = "check";
+pub const FILES: &str = "files";
+pub const RENAME: &str = "rename";
+pub const EXTRACT_FUNCTION: &str = "extract-function";
+pub const FILE: &str = "file";
+pub const POSITION: &str = "position";
+pub const START: &str = "start";
+pub const END: &str = "end";
+pub const NAME: &str = "name";
 
 fn interface() -> Arg {
     Arg::new(INTERFACE)
@@ -119,6 +205,18 @@ fn lints() -> Arg {
         .long_help("Print a list of all known lints.\nOnly these values can be passed to --allow, --warn, and --deny.")
 }
 
+fn error_format() -> Arg {
+    Arg::new(ERROR_FORMAT)
+        .long(ERROR_FORMAT)
+        .help("Set how errors and warnings are reported.")
+        .long_help("Set how errors and warnings are reported.\n\npossible values\n\nhuman - human readable reports printed to stderr\njson - one JSON object per diagnostic printed to stdout\nsarif - a SARIF 2.1.0 log printed to stdout\n\njson and sarif diagnostics are printed to stderr instead if --print-expansion is used")
+        .value_name("FORMAT")
+        .value_parser(["human", "json", "sarif"])
+        .hide_possible_values(true)
+        .default_value("human")
+        .required(false)
+}
+
 fn target_cpu() -> Arg {
     Arg::new(TARGET_CPU)
         .long(TARGET_CPU)
@@ -156,6 +254,41 @@ fn include_dir() -> Arg {
         .action(ArgAction::Append)
 }
 
+fn decryption_key() -> Arg {
+    let parse = |raw: &str| {
+        let (name, path) = match raw.split_once('=') {
+            Some((name, path)) if !name.is_empty() => (name, Utf8Path::new(path)),
+            _ => bail!("expected NAME=FILE"),
+        };
+        match fs::metadata(path) {
+            Err(err) => bail!("{err}"),
+            Ok(info) if !info.is_file() => bail!("is not a file"),
+            _ => Ok((name.to_owned(), path.to_owned())),
+        }
+    };
+
+    Arg::new(DECRYPTION_KEY)
+        .long(DECRYPTION_KEY)
+        .help("Private key used to decrypt protected source code.")
+        .long_help("Private key used to decrypt protected source code (`pragma protect).\nNAME is matched against the key_keyname of the envelope.\nFILE must contain a PEM encoded RSA private key.")
+        .value_name("NAME=FILE")
+        .value_hint(ValueHint::Other)
+        .value_parser(ValueParser::new(parse))
+        .action(ArgAction::Append)
+        .required(false)
+}
+
+fn macro_expansion_limit() -> Arg {
+    Arg::new(MACRO_EXPANSION_LIMIT)
+        .long(MACRO_EXPANSION_LIMIT)
+        .help("Maximum number of nested macro expansions.")
+        .long_help(format!("Maximum number of nested macro expansions (default: {DEFAULT_MACRO_EXPANSION_LIMIT}).\nMacro calls that are nested deeper are reported as errors."))
+        .value_name("LIMIT")
+        .value_parser(clap::value_parser!(usize))
+        .required(false)
+        .value_hint(ValueHint::Other)
+}
+
 fn output() -> Arg {
     output_file_path_arg(OUTPUT)
         .long(OUTPUT)
@@ -263,7 +396,8 @@ fn expand() -> Arg {
         .long_help(
             "Aborts the compilation after the preprocessor finishes.
 The sourcecode with all macros (`define) expanded and preprocessor
-directives (`include) resolved is emitted to stdout.",
+directives (`include) resolved is emitted to stdout.
+Diagnostics are always printed to stderr.",
         )
 }
 
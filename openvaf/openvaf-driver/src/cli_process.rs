@@ -4,12 +4,16 @@ use std::process::exit;
 use anyhow::{bail, Context, Result};
 use camino::Utf8PathBuf;
 use clap::ArgMatches;
-use openvaf::{builtin_lints, get_target_names, host_triple, AbsPathBuf, LintLevel, OptLevel};
+use openvaf::{
+    builtin_lints, get_target_names, host_triple, AbsPathBuf, ErrorFormat, LintLevel, OptLevel,
+    DEFAULT_MACRO_EXPANSION_LIMIT,
+};
 use termcolor::{Color, ColorChoice, ColorSpec, WriteColor};
 
 use crate::cli_def::{
-    ALLOW, BATCHMODE, CACHE_DIR, CODEGEN, DEFINE, DENY, DRYRUN, INCLUDE, INPUT, LINTS, OPT_LVL,
-    OUTPUT, SUPPORTED_TARGETS, TARGET, TARGET_CPU, WARN,
+    ALLOW, BATCHMODE, CACHE_DIR, CODEGEN, DECRYPTION_KEY, DEFINE, DENY, DRYRUN, ERROR_FORMAT,
+    INCLUDE, INPUT, LINTS, MACRO_EXPANSION_LIMIT, OPT_LVL, OUTPUT, SUPPORTED_TARGETS, TARGET,
+    TARGET_CPU, WARN,
 };
 use crate::{CompilationDestination, Opts};
 
@@ -82,6 +86,10 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
 
     let include = include?;
 
+    let decryption_keys = matches
+        .get_many::<(String, Utf8PathBuf)>(DECRYPTION_KEY)
+        .map_or_else(Vec::new, |values| values.cloned().collect());
+
     let opt_lvl = match &**matches.get_one::<String>(OPT_LVL).unwrap() {
         "0" => OptLevel::None,
         "1" => OptLevel::Less,
@@ -90,6 +98,13 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
         lvl => bail!("unknown opt lvl {lvl}"),
     };
 
+    let error_format = match &**matches.get_one::<String>(ERROR_FORMAT).unwrap() {
+        "human" => ErrorFormat::Human,
+        "json" => ErrorFormat::Json,
+        "sarif" => ErrorFormat::Sarif,
+        format => bail!("unknown error format {format}"),
+    };
+
     let host = host_triple();
     let target = matches.get_one::<String>(TARGET).cloned().unwrap_or_else(|| host.to_owned());
     let default_cpu = if host != target { "generic" } else { "native" };
@@ -114,6 +129,12 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
         opt_lvl,
         target,
         target_cpu,
+        decryption_keys,
+        macro_expansion_limit: matches
+            .get_one::<usize>(MACRO_EXPANSION_LIMIT)
+            .copied()
+            .unwrap_or(DEFAULT_MACRO_EXPANSION_LIMIT),
+        error_format,
         dry_run: matches.get_flag(DRYRUN),
     })
 }
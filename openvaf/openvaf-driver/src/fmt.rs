@@ -0,0 +1,59 @@
+use std::fs;
+
+use camino::Utf8PathBuf;
+use clap::ArgMatches;
+use formatter::format;
+
+use crate::cli_def::{CHECK, FILES};
+use crate::{print_error, DATA_ERROR};
+
+/// Formats all files passed to `openvaf fmt` and returns the exit code.
+pub fn main(matches: &ArgMatches) -> i32 {
+    let check = matches.get_flag(CHECK);
+    let mut unformatted = false;
+    let mut failed = false;
+
+    for path in matches.get_many::<Utf8PathBuf>(FILES).into_iter().flatten() {
+        let src = match fs::read_to_string(path) {
+            Ok(src) => src,
+            Err(err) => {
+                print_error(&format!("failed to read {path}: {err}"));
+                failed = true;
+                continue;
+            }
+        };
+
+        let formatted = match format(&src) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                let offset = usize::from(err.range.start());
+                let line_start = src[..offset].rfind('\n').map_or(0, |pos| pos + 1);
+                let line = src[..offset].matches('\n').count() + 1;
+                let column = src[line_start..offset].chars().count() + 1;
+                print_error(&format!("{path}:{line}:{column}: {err}"));
+                failed = true;
+                continue;
+            }
+        };
+
+        if formatted == src {
+            continue;
+        }
+
+        if check {
+            println!("{path}");
+            unformatted = true;
+        } else if let Err(err) = fs::write(path, formatted) {
+            print_error(&format!("failed to write {path}: {err}"));
+            failed = true;
+        }
+    }
+
+    if failed {
+        DATA_ERROR
+    } else if unformatted {
+        1
+    } else {
+        0
+    }
+}
@@ -11,12 +11,14 @@ use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 use cli_def::{main_command, INPUT};
 use openvaf::{compile, expand, CompilationDestination, CompilationTermination, Opts};
 
-use crate::cli_def::{DUMP_JSON, PRINT_EXPANSION};
+use crate::cli_def::{DUMP_JSON, EXTRACT_FUNCTION, FMT, PRINT_EXPANSION, RENAME};
 use crate::cli_process::matches_to_opts;
 
 mod cli_def;
 mod cli_process;
 mod crash_report;
+mod fmt;
+mod refactor;
 
 static ARGS: Mutex<Option<Opts>> = Mutex::new(None);
 #[global_allocator]
@@ -25,6 +27,12 @@ static GLOBAL: MiMalloc = MiMalloc;
 pub fn main() {
     let matches = main_command().get_matches();
     crash_report::install_panic_handler();
+    match matches.subcommand() {
+        Some((FMT, matches)) => exit(fmt::main(matches)),
+        Some((RENAME, matches)) => exit(refactor::rename(matches)),
+        Some((EXTRACT_FUNCTION, matches)) => exit(refactor::extract_function(matches)),
+        _ => (),
+    }
     let input: Utf8PathBuf = matches.get_one(INPUT).cloned().unwrap_or_else(Utf8PathBuf::new);
     let env = env_logger::Env::default().filter("OPENVAF_LOG").write_style("OPENVAF_LOG_STYLE");
     env_logger::Builder::new()
@@ -59,6 +67,16 @@ pub fn main() {
 
 pub const DATA_ERROR: i32 = 65;
 
+pub(crate) fn print_error(msg: &str) {
+    let mut stderr = StandardStream::stderr(ColorChoice::Auto);
+    stderr.set_color(ColorSpec::new().set_fg(Some(Color::Red)).set_bold(true)).unwrap();
+    write!(&mut stderr, "error").unwrap();
+    stderr.set_color(ColorSpec::new().set_bold(true)).unwrap();
+    write!(&mut stderr, ":").unwrap();
+    stderr.set_color(&ColorSpec::new()).unwrap();
+    writeln!(&mut stderr, " {msg}").unwrap();
+}
+
 fn wrapped_main(matches: ArgMatches) -> Result<i32> {
     let print_expansion = matches.get_flag(PRINT_EXPANSION);
     let dump_json_ = matches.get_flag(DUMP_JSON);
@@ -0,0 +1,104 @@
+use std::fs;
+
+use anyhow::{bail, Context, Result};
+use basedb::{AbsPathBuf, FileId};
+use camino::Utf8PathBuf;
+use clap::ArgMatches;
+use ide::{AnalysisHost, Config, FilePosition, FileRange, SourceChange};
+use syntax::{TextRange, TextSize};
+
+use crate::cli_def::{DEFINE, END, FILE, INCLUDE, NAME, POSITION, START};
+use crate::{print_error, DATA_ERROR};
+
+/// `openvaf rename`: renames the item at a position and updates all references in place.
+pub fn rename(matches: &ArgMatches) -> i32 {
+    run(|| {
+        let (host, file) = analyze(matches)?;
+        let offset = offset(&host, file, matches, POSITION)?;
+        let new_name: &String = matches.get_one(NAME).unwrap();
+        let change = host.rename(FilePosition { file, offset }, new_name)?;
+        Ok((host, change))
+    })
+}
+
+/// `openvaf extract-function`: moves an expression into a new analog function.
+pub fn extract_function(matches: &ArgMatches) -> i32 {
+    run(|| {
+        let (host, file) = analyze(matches)?;
+        let start = offset(&host, file, matches, START)?;
+        let end = offset(&host, file, matches, END)?;
+        // the end position refers to the last character of the expression
+        let end = host
+            .file_text(file)
+            .and_then(|text| text[usize::from(end)..].chars().next())
+            .map_or(end, |c| end + TextSize::of(c));
+        if end < start {
+            bail!("the end of the expression lies before its start");
+        }
+        let name: &String = matches.get_one(NAME).unwrap();
+        let change =
+            host.extract_function(FileRange { file, range: TextRange::new(start, end) }, name)?;
+        Ok((host, change))
+    })
+}
+
+fn run(f: impl FnOnce() -> Result<(AnalysisHost, SourceChange)>) -> i32 {
+    let res = f().and_then(|(host, change)| apply(&host, &change));
+    match res {
+        Ok(()) => 0,
+        Err(err) => {
+            print_error(&format!("{err:#}"));
+            DATA_ERROR
+        }
+    }
+}
+
+fn analyze(matches: &ArgMatches) -> Result<(AnalysisHost, FileId)> {
+    let include_dirs = matches
+        .get_many::<Utf8PathBuf>(INCLUDE)
+        .into_iter()
+        .flatten()
+        .map(|path| Ok(AbsPathBuf::assert(path.canonicalize()?)))
+        .collect::<Result<_>>()?;
+    let macro_flags = matches.get_many::<String>(DEFINE).into_iter().flatten().cloned().collect();
+    let mut host = AnalysisHost::new(Config { include_dirs, macro_flags });
+
+    let path: &Utf8PathBuf = matches.get_one(FILE).unwrap();
+    let file = host.file_id(AbsPathBuf::assert(path.canonicalize()?).into());
+    host.add_root(file)?;
+    Ok((host, file))
+}
+
+/// Converts the `LINE:COLUMN` argument `arg` (starting at 1) to an offset in `file`.
+fn offset(host: &AnalysisHost, file: FileId, matches: &ArgMatches, arg: &str) -> Result<TextSize> {
+    let &(line, column): &(u32, u32) = matches.get_one(arg).unwrap();
+    let text = host.file_text(file).context("failed to read file")?;
+    let line_start = match line {
+        1 => 0,
+        _ => text
+            .match_indices('\n')
+            .nth(line as usize - 2)
+            .map(|(pos, _)| pos + 1)
+            .with_context(|| format!("{line}:{column} lies outside of the file"))?,
+    };
+    let line_text = text[line_start..].split('\n').next().unwrap_or_default();
+    let offset = line_text
+        .char_indices()
+        .map(|(pos, _)| pos)
+        .chain([line_text.len()])
+        .nth(column as usize - 1)
+        .with_context(|| format!("{line}:{column} lies outside of the file"))?;
+    Ok(TextSize::from((line_start + offset) as u32))
+}
+
+fn apply(host: &AnalysisHost, change: &SourceChange) -> Result<()> {
+    for &file in change.edits.keys() {
+        let path = host.file_path(file);
+        let fs_path = path.as_path().with_context(|| format!("{path} can not be modified"))?;
+        let mut text = host.file_text(file).context("failed to read file")?.to_string();
+        change.apply(file, &mut text);
+        fs::write(fs_path, text).with_context(|| format!("failed to write {path}"))?;
+        println!("{path}");
+    }
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! Conversions between the types of the language server protocol and [`ide`].
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use basedb::line_index::{LineColUtf16, LineIndex};
+use basedb::{AbsPathBuf, VfsPath};
+use ide::{Config, Severity, SymbolKind};
+use lsp_types::{CompletionItemKind, DiagnosticSeverity, Position, Range, Url};
+use serde::Deserialize;
+use syntax::{TextRange, TextSize};
+
+/// The `initializationOptions` sent by the client.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+struct InitializationOptions {
+    /// Directories that are searched for `` `include `` files.
+    include_dirs: Vec<PathBuf>,
+    /// Macros that are defined before preprocessing (like `-D` on the command line).
+    defines: Vec<String>,
+}
+
+pub(crate) fn config(options: Option<serde_json::Value>) -> Result<Config> {
+    let options: InitializationOptions = match options {
+        Some(options) => {
+            serde_json::from_value(options).context("invalid initialization options")?
+        }
+        None => InitializationOptions::default(),
+    };
+
+    let include_dirs = options
+        .include_dirs
+        .into_iter()
+        .map(|dir| {
+            let dir = dir
+                .canonicalize()
+                .with_context(|| format!("include directory {} does not exist", dir.display()))?;
+            Ok(AbsPathBuf::assert(dir))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Config { include_dirs, macro_flags: options.defines })
+}
+
+pub(crate) fn vfs_path(url: &Url) -> Option<VfsPath> {
+    let path = url.to_file_path().ok()?;
+    let path = AbsPathBuf::try_from(path).ok()?;
+    Some(path.into())
+}
+
+/// Returns `None` for virtual files (like the standard library) that can not be opened by
+/// the client.
+pub(crate) fn url(path: &VfsPath) -> Option<Url> {
+    Url::from_file_path(path.as_path()?).ok()
+}
+
+pub(crate) fn offset(line_index: &LineIndex, pos: Position) -> TextSize {
+    let last_line = line_index.newlines.len() as u32 - 1;
+    if pos.line > last_line {
+        return line_index.len;
+    }
+    let line_col = line_index.to_utf8(LineColUtf16 { line: pos.line, col: pos.character });
+    let line_end =
+        line_index.newlines.get(pos.line as usize + 1).copied().unwrap_or(line_index.len);
+    line_index.offset(line_col).min(line_end)
+}
+
+pub(crate) fn position(line_index: &LineIndex, offset: TextSize) -> Position {
+    let line_col = line_index.to_utf16(line_index.line_col(offset));
+    Position { line: line_col.line, character: line_col.col }
+}
+
+pub(crate) fn range(line_index: &LineIndex, range: TextRange) -> Range {
+    Range { start: position(line_index, range.start()), end: position(line_index, range.end()) }
+}
+
+pub(crate) fn severity(severity: Severity) -> DiagnosticSeverity {
+    match severity {
+        Severity::Bug | Severity::Error => DiagnosticSeverity::ERROR,
+        Severity::Warning => DiagnosticSeverity::WARNING,
+        Severity::Note => DiagnosticSeverity::INFORMATION,
+        Severity::Help => DiagnosticSeverity::HINT,
+    }
+}
+
+pub(crate) fn symbol_kind(kind: SymbolKind) -> lsp_types::SymbolKind {
+    match kind {
+        SymbolKind::Module => lsp_types::SymbolKind::MODULE,
+        SymbolKind::Paramset => lsp_types::SymbolKind::CLASS,
+        SymbolKind::Block => lsp_types::SymbolKind::NAMESPACE,
+        SymbolKind::Nature => lsp_types::SymbolKind::STRUCT,
+        SymbolKind::NatureAttribute => lsp_types::SymbolKind::FIELD,
+        SymbolKind::Discipline => lsp_types::SymbolKind::INTERFACE,
+        SymbolKind::Node => lsp_types::SymbolKind::PROPERTY,
+        SymbolKind::Variable | SymbolKind::FunctionArg => lsp_types::SymbolKind::VARIABLE,
+        SymbolKind::Parameter | SymbolKind::Macro => lsp_types::SymbolKind::CONSTANT,
+        SymbolKind::Branch => lsp_types::SymbolKind::EVENT,
+        SymbolKind::Function | SymbolKind::BuiltIn => lsp_types::SymbolKind::FUNCTION,
+    }
+}
+
+pub(crate) fn completion_kind(kind: SymbolKind) -> CompletionItemKind {
+    match kind {
+        SymbolKind::Module | SymbolKind::Paramset | SymbolKind::Block => CompletionItemKind::MODULE,
+        SymbolKind::Nature => CompletionItemKind::STRUCT,
+        SymbolKind::NatureAttribute => CompletionItemKind::FIELD,
+        SymbolKind::Discipline => CompletionItemKind::INTERFACE,
+        SymbolKind::Node => CompletionItemKind::PROPERTY,
+        SymbolKind::Variable | SymbolKind::FunctionArg => CompletionItemKind::VARIABLE,
+        SymbolKind::Parameter => CompletionItemKind::CONSTANT,
+        SymbolKind::Branch => CompletionItemKind::EVENT,
+        SymbolKind::Function | SymbolKind::BuiltIn => CompletionItemKind::FUNCTION,
+        SymbolKind::Macro => CompletionItemKind::SNIPPET,
+    }
+}
@@ -0,0 +1,70 @@
+//! A language server for Verilog-A. All analysis is performed by the [`ide`] crate,
+//! this binary only translates between the language server protocol and [`ide`].
+
+use std::process::exit;
+
+use anyhow::Result;
+use lsp_server::Connection;
+use lsp_types::{
+    CodeActionProviderCapability, CompletionOptions, HoverProviderCapability, InitializeParams,
+    OneOf, RenameOptions, ServerCapabilities, SignatureHelpOptions, TextDocumentSyncCapability,
+    TextDocumentSyncKind, WorkDoneProgressOptions,
+};
+
+use crate::server::Server;
+
+mod convert;
+mod server;
+#[cfg(test)]
+mod tests;
+
+pub fn main() {
+    let env = env_logger::Env::default().filter("OPENVAF_LOG").write_style("OPENVAF_LOG_STYLE");
+    env_logger::Builder::new()
+        .format_timestamp(None)
+        .filter(Some("salsa"), log::LevelFilter::Off)
+        .filter_level(log::LevelFilter::Warn)
+        .parse_env(env)
+        .init();
+
+    if let Err(err) = run() {
+        log::error!("{err:?}");
+        exit(1)
+    }
+}
+
+fn run() -> Result<()> {
+    // stdout is used for communication with the client
+    let (connection, io_threads) = Connection::stdio();
+
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        references_provider: Some(OneOf::Left(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["$".to_owned(), "`".to_owned(), "(".to_owned()]),
+            ..CompletionOptions::default()
+        }),
+        signature_help_provider: Some(SignatureHelpOptions {
+            trigger_characters: Some(vec!["(".to_owned(), ",".to_owned()]),
+            ..SignatureHelpOptions::default()
+        }),
+        rename_provider: Some(OneOf::Right(RenameOptions {
+            prepare_provider: Some(true),
+            work_done_progress_options: WorkDoneProgressOptions::default(),
+        })),
+        code_action_provider: Some(CodeActionProviderCapability::Simple(true)),
+        ..ServerCapabilities::default()
+    };
+    let params = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+
+    let config = convert::config(params.initialization_options)?;
+    Server::new(&connection, config).run()?;
+
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
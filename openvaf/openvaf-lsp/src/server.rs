@@ -0,0 +1,493 @@
+use std::collections::{HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+
+use anyhow::Result;
+use basedb::line_index::LineIndex;
+use basedb::FileId;
+use ide::{AnalysisHost, Config, FilePosition, FileRange, SourceChange};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, DidSaveTextDocument,
+    Notification as _, PublishDiagnostics,
+};
+use lsp_types::request::{
+    CodeActionRequest, Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest,
+    PrepareRenameRequest, References, Rename, Request as _, SignatureHelpRequest,
+};
+use lsp_types::{
+    CodeAction, CodeActionKind, CodeActionOrCommand, CodeActionParams, CodeActionResponse,
+    CompletionItem, CompletionParams, CompletionResponse, CompletionTextEdit,
+    DiagnosticRelatedInformation, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams,
+    GotoDefinitionResponse, Hover, HoverContents, HoverParams, Location, MarkupContent, MarkupKind,
+    NumberOrString, ParameterInformation, ParameterLabel, PrepareRenameResponse,
+    PublishDiagnosticsParams, ReferenceParams, RenameParams, SignatureHelp, SignatureHelpParams,
+    SignatureInformation, TextDocumentPositionParams, TextEdit, Url, WorkspaceEdit,
+};
+use salsa::Cancelled;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use syntax::TextRange;
+
+use crate::convert;
+
+pub(crate) struct Server<'a> {
+    connection: &'a Connection,
+    host: AnalysisHost,
+    /// Files that are currently opened in the editor.
+    open_files: HashSet<FileId>,
+    /// Files that diagnostics were last published for (so they can be cleared).
+    published: HashSet<Url>,
+}
+
+impl<'a> Server<'a> {
+    pub(crate) fn new(connection: &'a Connection, config: Config) -> Server<'a> {
+        Server {
+            connection,
+            host: AnalysisHost::new(config),
+            open_files: HashSet::new(),
+            published: HashSet::new(),
+        }
+    }
+
+    pub(crate) fn run(mut self) -> Result<()> {
+        for msg in &self.connection.receiver {
+            match msg {
+                Message::Request(req) => {
+                    if self.connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    self.on_request(req)?;
+                }
+                Message::Notification(not) => self.on_notification(not)?,
+                Message::Response(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn on_request(&mut self, req: Request) -> Result<()> {
+        let resp = match req.method.as_str() {
+            GotoDefinition::METHOD => handle(req, |params| self.goto_definition(params)),
+            HoverRequest::METHOD => handle(req, |params| self.hover(params)),
+            References::METHOD => handle(req, |params| self.references(params)),
+            DocumentSymbolRequest::METHOD => handle(req, |params| self.document_symbols(params)),
+            Completion::METHOD => handle(req, |params| self.completion(params)),
+            SignatureHelpRequest::METHOD => handle(req, |params| self.signature_help(params)),
+            PrepareRenameRequest::METHOD => try_handle(req, |params| self.prepare_rename(params)),
+            Rename::METHOD => try_handle(req, |params| self.rename(params)),
+            CodeActionRequest::METHOD => handle(req, |params| self.code_actions(params)),
+            _ => {
+                log::warn!("unsupported request {}", req.method);
+                let msg = format!("unsupported request {}", req.method);
+                Response::new_err(req.id, ErrorCode::MethodNotFound as i32, msg)
+            }
+        };
+        self.connection.sender.send(Message::Response(resp))?;
+        Ok(())
+    }
+
+    fn on_notification(&mut self, not: Notification) -> Result<()> {
+        let method = not.method.clone();
+        // a panic must not bring down the server, the diagnostics are simply not updated
+        match catch_panic(&method, || self.handle_notification(not)) {
+            Ok(res) => res,
+            Err(_) => Ok(()),
+        }
+    }
+
+    fn handle_notification(&mut self, not: Notification) -> Result<()> {
+        let changed = match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                parse_params(not).map(|params| self.did_open(params)).is_some()
+            }
+            DidChangeTextDocument::METHOD => {
+                parse_params(not).map(|params| self.did_change(params)).is_some()
+            }
+            DidCloseTextDocument::METHOD => {
+                parse_params(not).map(|params| self.did_close(params)).is_some()
+            }
+            DidSaveTextDocument::METHOD => {
+                self.did_save();
+                true
+            }
+            _ => {
+                log::debug!("ignored notification {}", not.method);
+                false
+            }
+        };
+
+        if changed {
+            self.update_roots();
+            self.publish_diagnostics()?;
+        }
+        Ok(())
+    }
+
+    fn did_open(&mut self, params: DidOpenTextDocumentParams) {
+        let doc = params.text_document;
+        if let Some(file) = self.file_id(&doc.uri) {
+            self.open_files.insert(file);
+            self.host.set_file_text(file, doc.text);
+        }
+    }
+
+    fn did_change(&mut self, mut params: DidChangeTextDocumentParams) {
+        // only full text synchronization is supported so the last change contains the whole text
+        let change = params.content_changes.pop();
+        if let (Some(file), Some(change)) = (self.file_id(&params.text_document.uri), change) {
+            self.host.set_file_text(file, change.text);
+        }
+    }
+
+    fn did_close(&mut self, params: DidCloseTextDocumentParams) {
+        if let Some(file) = self.file_id(&params.text_document.uri) {
+            self.open_files.remove(&file);
+            self.host.clear_file_text(file);
+            self.host.remove_root(file);
+        }
+    }
+
+    /// Open files that are not included by any compilation unit are analyzed on their own.
+    /// Compilation units whose root file is included by another compilation unit are removed
+    /// again (for example when the including file is opened after the included file).
+    fn update_roots(&mut self) {
+        let mut open_files: Vec<_> = self.open_files.iter().copied().collect();
+        open_files.sort_unstable();
+        for file in open_files {
+            if self.host.root_for(file).is_none() {
+                if let Err(err) = self.host.add_root(file) {
+                    log::error!("failed to analyze {}: {err:?}", self.host.file_path(file))
+                }
+            }
+        }
+
+        let mut roots: Vec<_> = self.host.roots().collect();
+        roots.sort_unstable();
+        for root in roots {
+            let included = self
+                .host
+                .roots()
+                .any(|other| other != root && self.host.files(other).contains(&root));
+            if included {
+                self.host.remove_root(root);
+            }
+        }
+    }
+
+    /// Files that are not open in the editor (but included by an open file) may have been
+    /// changed on disk. Their contents are therefore reread when any file is saved.
+    fn did_save(&mut self) {
+        let closed_files: Vec<_> = self
+            .host
+            .roots()
+            .flat_map(|root| self.host.files(root))
+            .filter(|file| {
+                // the standard library only exists in memory
+                !self.open_files.contains(file) && self.host.file_path(*file).as_path().is_some()
+            })
+            .collect();
+        for file in closed_files {
+            self.host.clear_file_text(file);
+        }
+    }
+
+    fn file_id(&self, url: &Url) -> Option<FileId> {
+        let path = convert::vfs_path(url)?;
+        Some(self.host.file_id(path))
+    }
+
+    fn location(&self, range: FileRange) -> Option<Location> {
+        let uri = convert::url(&self.host.file_path(range.file))?;
+        let line_index = self.host.line_index(range.file);
+        Some(Location { uri, range: convert::range(&line_index, range.range) })
+    }
+
+    fn file_position(&self, pos: TextDocumentPositionParams) -> Option<FilePosition> {
+        let file = self.file_id(&pos.text_document.uri)?;
+        let line_index = self.host.line_index(file);
+        Some(FilePosition { file, offset: convert::offset(&line_index, pos.position) })
+    }
+
+    fn publish_diagnostics(&mut self) -> Result<()> {
+        let mut diagnostics: HashMap<Url, Vec<lsp_types::Diagnostic>> = HashMap::new();
+        let roots: Vec<_> = self.host.roots().collect();
+        for root in roots {
+            for diag in self.host.diagnostics(root) {
+                // diagnostics inside the standard library can not be shown
+                let location = match self.location(diag.range) {
+                    Some(location) => location,
+                    None => continue,
+                };
+                let related_information = diag
+                    .related
+                    .into_iter()
+                    .filter_map(|(range, message)| {
+                        let location = self.location(range)?;
+                        Some(DiagnosticRelatedInformation { location, message })
+                    })
+                    .collect();
+                let diag = lsp_types::Diagnostic {
+                    range: location.range,
+                    severity: Some(convert::severity(diag.severity)),
+                    code: diag.code.map(NumberOrString::String),
+                    source: Some("openvaf".to_owned()),
+                    message: diag.message,
+                    related_information: Some(related_information),
+                    ..lsp_types::Diagnostic::default()
+                };
+                diagnostics.entry(location.uri).or_default().push(diag);
+            }
+        }
+
+        // files without diagnostics are sent an empty list to clear the old diagnostics
+        let published: HashSet<_> = diagnostics.keys().cloned().collect();
+        for uri in self.published.difference(&published) {
+            diagnostics.insert(uri.clone(), Vec::new());
+        }
+        self.published = published;
+
+        for (uri, diagnostics) in diagnostics {
+            let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+            let not = Notification::new(PublishDiagnostics::METHOD.to_owned(), params);
+            self.connection.sender.send(Message::Notification(not))?;
+        }
+        Ok(())
+    }
+
+    fn goto_definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let pos = self.file_position(params.text_document_position_params)?;
+        let locations = self
+            .host
+            .goto_definition(pos)
+            .into_iter()
+            .filter_map(|target| {
+                self.location(FileRange { file: target.file, range: target.focus_range })
+            })
+            .collect();
+        Some(GotoDefinitionResponse::Array(locations))
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let pos = self.file_position(params.text_document_position_params)?;
+        let hover = self.host.hover(pos)?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: hover.markup,
+            }),
+            range: self.location(hover.range).map(|location| location.range),
+        })
+    }
+
+    fn references(&self, params: ReferenceParams) -> Option<Vec<Location>> {
+        let pos = self.file_position(params.text_document_position)?;
+        let references = self.host.references(pos, params.context.include_declaration);
+        Some(references.into_iter().filter_map(|range| self.location(range)).collect())
+    }
+
+    fn document_symbols(&self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+        let file = self.file_id(&params.text_document.uri)?;
+        let line_index = self.host.line_index(file);
+        let symbols = self
+            .host
+            .document_symbols(file)
+            .into_iter()
+            .map(|symbol| to_lsp_symbol(&line_index, symbol))
+            .collect();
+        Some(DocumentSymbolResponse::Nested(symbols))
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let pos = self.file_position(params.text_document_position)?;
+        let line_index = self.host.line_index(pos.file);
+        let items = self
+            .host
+            .completions(pos)
+            .into_iter()
+            .map(|item| CompletionItem {
+                label: item.label.clone(),
+                kind: Some(convert::completion_kind(item.kind)),
+                detail: item.detail,
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit {
+                    range: convert::range(&line_index, item.range),
+                    new_text: item.label,
+                })),
+                ..CompletionItem::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+
+    fn signature_help(&self, params: SignatureHelpParams) -> Option<SignatureHelp> {
+        let pos = self.file_position(params.text_document_position_params)?;
+        let help = self.host.signature_help(pos)?;
+        let signatures = help
+            .signatures
+            .into_iter()
+            .map(|sig| {
+                let parameters = sig
+                    .params
+                    .iter()
+                    .map(|range| ParameterInformation {
+                        label: ParameterLabel::Simple(sig.label[*range].to_owned()),
+                        documentation: None,
+                    })
+                    .collect();
+                SignatureInformation {
+                    label: sig.label,
+                    documentation: None,
+                    parameters: Some(parameters),
+                    active_parameter: None,
+                }
+            })
+            .collect();
+        Some(SignatureHelp {
+            signatures,
+            active_signature: Some(help.active_signature as u32),
+            active_parameter: Some(help.active_parameter as u32),
+        })
+    }
+
+    fn prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> Result<Option<PrepareRenameResponse>> {
+        let pos = match self.file_position(params) {
+            Some(pos) => pos,
+            None => return Ok(None),
+        };
+        let range = self.host.prepare_rename(pos)?;
+        Ok(self.location(range).map(|location| PrepareRenameResponse::Range(location.range)))
+    }
+
+    fn rename(&self, params: RenameParams) -> Result<Option<WorkspaceEdit>> {
+        let pos = match self.file_position(params.text_document_position) {
+            Some(pos) => pos,
+            None => return Ok(None),
+        };
+        let change = self.host.rename(pos, &params.new_name)?;
+        Ok(Some(self.workspace_edit(change)))
+    }
+
+    fn code_actions(&self, params: CodeActionParams) -> Option<CodeActionResponse> {
+        let file = self.file_id(&params.text_document.uri)?;
+        let line_index = self.host.line_index(file);
+        let range = TextRange::new(
+            convert::offset(&line_index, params.range.start),
+            convert::offset(&line_index, params.range.end),
+        );
+        if range.is_empty() {
+            return Some(Vec::new());
+        }
+
+        // the editor can rename the function afterwards
+        let actions = match self.host.extract_function(FileRange { file, range }, EXTRACTED_NAME) {
+            Ok(change) => vec![CodeActionOrCommand::CodeAction(CodeAction {
+                title: "Extract into analog function".to_owned(),
+                kind: Some(CodeActionKind::REFACTOR_EXTRACT),
+                edit: Some(self.workspace_edit(change)),
+                ..CodeAction::default()
+            })],
+            Err(_) => Vec::new(),
+        };
+        Some(actions)
+    }
+
+    fn workspace_edit(&self, change: SourceChange) -> WorkspaceEdit {
+        let changes = change
+            .edits
+            .into_iter()
+            .filter_map(|(file, edits)| {
+                let uri = convert::url(&self.host.file_path(file))?;
+                let line_index = self.host.line_index(file);
+                let edits = edits
+                    .into_iter()
+                    .map(|edit| TextEdit {
+                        range: convert::range(&line_index, edit.range),
+                        new_text: edit.insert,
+                    })
+                    .collect();
+                Some((uri, edits))
+            })
+            .collect();
+        WorkspaceEdit::new(changes)
+    }
+}
+
+/// The name of functions created by the extract function code action.
+const EXTRACTED_NAME: &str = "extracted";
+
+fn to_lsp_symbol(line_index: &LineIndex, symbol: ide::DocumentSymbol) -> lsp_types::DocumentSymbol {
+    let children = symbol.children.into_iter().map(|it| to_lsp_symbol(line_index, it)).collect();
+    #[allow(deprecated)]
+    lsp_types::DocumentSymbol {
+        name: symbol.name,
+        detail: None,
+        kind: convert::symbol_kind(symbol.kind),
+        tags: None,
+        deprecated: None,
+        range: convert::range(line_index, symbol.full_range),
+        selection_range: convert::range(line_index, symbol.focus_range),
+        children: Some(children),
+    }
+}
+
+pub(crate) fn handle<P: DeserializeOwned, R: Serialize>(
+    req: Request,
+    f: impl FnOnce(P) -> R,
+) -> Response {
+    try_handle(req, |params| Ok(f(params)))
+}
+
+/// Like [`handle`] but failures are reported to the client (for example when a rename is not
+/// possible).
+fn try_handle<P: DeserializeOwned, R: Serialize>(
+    req: Request,
+    f: impl FnOnce(P) -> Result<R>,
+) -> Response {
+    let params = match serde_json::from_value(req.params) {
+        Ok(params) => params,
+        Err(err) => {
+            return Response::new_err(req.id, ErrorCode::InvalidParams as i32, err.to_string())
+        }
+    };
+    match catch_panic(&req.method, || f(params)) {
+        Ok(Ok(res)) => Response::new_ok(req.id, res),
+        Ok(Err(err)) => {
+            Response::new_err(req.id, ErrorCode::RequestFailed as i32, format!("{err:#}"))
+        }
+        Err((code, msg)) => Response::new_err(req.id, code as i32, msg),
+    }
+}
+
+/// Runs `f` and catches any panic so that a single request can not bring down the server.
+/// Cancelled queries are reported as `ContentModified` so that the client retries the request.
+fn catch_panic<T>(method: &str, f: impl FnOnce() -> T) -> Result<T, (ErrorCode, String)> {
+    let payload = match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(res) => return Ok(res),
+        Err(payload) => payload,
+    };
+
+    if let Some(cancelled) = payload.downcast_ref::<Cancelled>() {
+        log::debug!("{method} was cancelled: {cancelled}");
+        return Err((ErrorCode::ContentModified, cancelled.to_string()));
+    }
+
+    let msg = match payload.downcast_ref::<&str>() {
+        Some(msg) => msg,
+        None => payload.downcast_ref::<String>().map_or("unknown panic", String::as_str),
+    };
+    log::error!("{method} panicked: {msg}");
+    Err((ErrorCode::InternalError, format!("{method} panicked: {msg}")))
+}
+
+fn parse_params<P: DeserializeOwned>(not: Notification) -> Option<P> {
+    match serde_json::from_value(not.params) {
+        Ok(params) => Some(params),
+        Err(err) => {
+            log::error!("invalid parameters for {}: {err}", not.method);
+            None
+        }
+    }
+}
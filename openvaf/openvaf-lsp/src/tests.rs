@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::panic::resume_unwind;
+use std::path::PathBuf;
+use std::thread::{self, JoinHandle};
+
+use ide::Config;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Exit,
+    Notification as LspNotification, PublishDiagnostics,
+};
+use lsp_types::request::{
+    DocumentSymbolRequest, GotoDefinition, HoverRequest, Request as LspRequest, Shutdown,
+};
+use lsp_types::{
+    DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    DocumentSymbolParams, DocumentSymbolResponse, GotoDefinitionParams, GotoDefinitionResponse,
+    Hover, HoverContents, HoverParams, PartialResultParams, Position, PublishDiagnosticsParams,
+    TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+    TextDocumentPositionParams, Url, VersionedTextDocumentIdentifier, WorkDoneProgressParams,
+};
+use salsa::Cancelled;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::server::{handle, Server};
+
+/// A client that talks to a server running on another thread.
+struct TestClient {
+    connection: Connection,
+    server: Option<JoinHandle<()>>,
+    next_id: i32,
+    /// The last diagnostics published for each file.
+    diagnostics: HashMap<Url, Vec<lsp_types::Diagnostic>>,
+}
+
+impl TestClient {
+    fn new() -> TestClient {
+        let (server, connection) = Connection::memory();
+        let server = thread::spawn(move || Server::new(&server, Config::default()).run().unwrap());
+        TestClient { connection, server: Some(server), next_id: 0, diagnostics: HashMap::new() }
+    }
+
+    fn notify<N: LspNotification>(&self, params: N::Params) {
+        let not = Notification::new(N::METHOD.to_owned(), params);
+        self.connection.sender.send(Message::Notification(not)).unwrap();
+    }
+
+    /// Sends a request and waits for the response. All diagnostics that are published
+    /// in the meantime are recorded.
+    fn send(&mut self, method: &str, params: impl Serialize) -> Response {
+        self.next_id += 1;
+        let id = RequestId::from(self.next_id);
+        let req = Request::new(id.clone(), method.to_owned(), params);
+        self.connection.sender.send(Message::Request(req)).unwrap();
+        loop {
+            match self.connection.receiver.recv().unwrap() {
+                Message::Response(resp) if resp.id == id => return resp,
+                Message::Notification(not) if not.method == PublishDiagnostics::METHOD => {
+                    let params: PublishDiagnosticsParams =
+                        serde_json::from_value(not.params).unwrap();
+                    self.diagnostics.insert(params.uri, params.diagnostics);
+                }
+                msg => panic!("unexpected message {msg:?}"),
+            }
+        }
+    }
+
+    fn request<R: LspRequest>(&mut self, params: R::Params) -> R::Result
+    where
+        R::Result: DeserializeOwned,
+    {
+        let resp = self.send(R::METHOD, params);
+        if let Some(err) = resp.error {
+            panic!("{} failed: {}", R::METHOD, err.message)
+        }
+        serde_json::from_value(resp.result.unwrap()).unwrap()
+    }
+
+    fn open(&self, uri: &Url, text: &str) {
+        self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem {
+                uri: uri.clone(),
+                language_id: "verilog-a".to_owned(),
+                version: 0,
+                text: text.to_owned(),
+            },
+        });
+    }
+
+    fn change(&self, uri: &Url, text: &str) {
+        self.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier { uri: uri.clone(), version: 1 },
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: text.to_owned(),
+            }],
+        });
+    }
+
+    fn close(&self, uri: &Url) {
+        self.notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+        });
+    }
+
+    /// Waits until the server has processed all previous notifications and returns the
+    /// diagnostics of `uri`.
+    fn diagnostics(&mut self, uri: &Url) -> Vec<String> {
+        self.document_symbols(uri);
+        let diagnostics = self.diagnostics.get(uri).map_or(&[][..], Vec::as_slice);
+        diagnostics.iter().map(|diag| diag.message.clone()).collect()
+    }
+
+    fn document_symbols(&mut self, uri: &Url) -> Vec<String> {
+        let params = DocumentSymbolParams {
+            text_document: TextDocumentIdentifier { uri: uri.clone() },
+            work_done_progress_params: WorkDoneProgressParams::default(),
+            partial_result_params: PartialResultParams::default(),
+        };
+        match self.request::<DocumentSymbolRequest>(params) {
+            Some(DocumentSymbolResponse::Nested(symbols)) => {
+                symbols.into_iter().map(|symbol| symbol.name).collect()
+            }
+            // an empty list can not be distinguished from a flat response
+            Some(DocumentSymbolResponse::Flat(symbols)) if symbols.is_empty() => Vec::new(),
+            res => panic!("unexpected response {res:?}"),
+        }
+    }
+}
+
+impl Drop for TestClient {
+    fn drop(&mut self) {
+        if thread::panicking() {
+            return;
+        }
+        self.send(Shutdown::METHOD, ());
+        self.notify::<Exit>(());
+        if let Err(err) = self.server.take().unwrap().join() {
+            resume_unwind(err)
+        }
+    }
+}
+
+fn url(name: &str) -> Url {
+    let path: PathBuf = std::env::temp_dir().join("openvaf-lsp-test").join(name);
+    Url::from_file_path(path).unwrap()
+}
+
+fn position(uri: &Url, line: u32, character: u32) -> TextDocumentPositionParams {
+    TextDocumentPositionParams {
+        text_document: TextDocumentIdentifier { uri: uri.clone() },
+        position: Position { line, character },
+    }
+}
+
+const AMP: &str = r#"`include "disciplines.vams"
+module amp(inp, out);
+    input inp;
+    output out;
+    electrical inp, out;
+    (* desc = "voltage gain" *) parameter real gain = 2.0;
+    analog I(out) <+ gain * V(inp);
+endmodule
+"#;
+
+#[test]
+fn diagnostics_on_change() {
+    let mut client = TestClient::new();
+    let uri = url("amp.va");
+    client.open(&uri, &AMP.replace("gain * V", "gian * V"));
+    let diagnostics = client.diagnostics(&uri);
+    assert_eq!(diagnostics.len(), 1, "{diagnostics:?}");
+    assert!(diagnostics[0].contains("gian"), "{diagnostics:?}");
+
+    // the diagnostics are cleared once the error is fixed
+    client.change(&uri, AMP);
+    assert_eq!(client.diagnostics(&uri), Vec::<String>::new());
+}
+
+#[test]
+fn navigation() {
+    let mut client = TestClient::new();
+    let uri = url("amp.va");
+    client.open(&uri, AMP);
+    assert_eq!(client.document_symbols(&uri), vec!["amp"]);
+
+    let params = GotoDefinitionParams {
+        text_document_position_params: position(&uri, 6, 22),
+        work_done_progress_params: WorkDoneProgressParams::default(),
+        partial_result_params: PartialResultParams::default(),
+    };
+    let locations = match client.request::<GotoDefinition>(params) {
+        Some(GotoDefinitionResponse::Array(locations)) => locations,
+        res => panic!("unexpected response {res:?}"),
+    };
+    assert_eq!(locations.len(), 1);
+    assert_eq!(locations[0].uri, uri);
+    assert_eq!(locations[0].range.start, Position { line: 5, character: 47 });
+
+    let params = HoverParams {
+        text_document_position_params: position(&uri, 6, 22),
+        work_done_progress_params: WorkDoneProgressParams::default(),
+    };
+    let hover: Option<Hover> = client.request::<HoverRequest>(params);
+    match hover.map(|hover| hover.contents) {
+        Some(HoverContents::Markup(markup)) => {
+            assert!(markup.value.contains("voltage gain"), "{}", markup.value)
+        }
+        res => panic!("unexpected hover {res:?}"),
+    }
+}
+
+#[test]
+fn invalid_requests() {
+    let mut client = TestClient::new();
+    let resp = client.send("openvaf/unknown", ());
+    assert_eq!(resp.error.unwrap().code, ErrorCode::MethodNotFound as i32);
+    let resp = client.send(HoverRequest::METHOD, "invalid");
+    assert_eq!(resp.error.unwrap().code, ErrorCode::InvalidParams as i32);
+
+    // the server keeps working after a failed request
+    let uri = url("amp.va");
+    client.open(&uri, AMP);
+    assert_eq!(client.document_symbols(&uri), vec!["amp"]);
+}
+
+#[test]
+fn panics_are_isolated() {
+    let req = || Request::new(RequestId::from(1), HoverRequest::METHOD.to_owned(), ());
+
+    let resp = handle(req(), |()| -> () { panic!("broken") });
+    let err = resp.error.unwrap();
+    assert_eq!(err.code, ErrorCode::InternalError as i32);
+    assert!(err.message.contains("broken"), "{}", err.message);
+
+    // cancelled requests are retried by the client
+    let resp = handle(req(), |()| -> () { resume_unwind(Box::new(Cancelled::PendingWrite)) });
+    assert_eq!(resp.error.unwrap().code, ErrorCode::ContentModified as i32);
+}
+
+const INCLUDED: &str = r#"`include "disciplines.vams"
+module inc(a);
+    inout electrical a;
+    analog I(a) <+ unknown;
+endmodule
+"#;
+
+#[test]
+fn included_files_are_not_roots() {
+    let mut client = TestClient::new();
+    let inc = url("inc.va");
+    let main = url("main.va");
+
+    // a file that is not included anywhere is analyzed on its own
+    client.open(&inc, INCLUDED);
+    assert_eq!(client.diagnostics(&inc).len(), 1);
+
+    // once the including file is opened, the error is only reported by its compilation unit
+    client.open(&main, "`include \"inc.va\"\n");
+    assert_eq!(client.diagnostics(&inc).len(), 1);
+    assert_eq!(client.diagnostics(&main), Vec::<String>::new());
+
+    // after closing the including file the included file is analyzed on its own again
+    client.close(&main);
+    assert_eq!(client.diagnostics(&inc).len(), 1);
+}
@@ -1,11 +1,12 @@
-use std::fs::{create_dir_all, remove_file};
-use std::io::Write;
+use std::fs::{self, create_dir_all, remove_file};
+use std::io::{self, Write};
+use std::sync::Arc;
 use std::time::Instant;
 
 use anyhow::Context;
-use anyhow::Result;
-use basedb::diagnostics::{ConsoleSink, DiagnosticSink};
-use basedb::BaseDB;
+use anyhow::{bail, Result};
+use basedb::diagnostics::DiagnosticSink;
+use basedb::{BaseDB, DecryptionKey};
 use camino::Utf8PathBuf;
 use hir::CompilationDB;
 use linker::link;
@@ -13,14 +14,19 @@ use mir_llvm::LLVMBackend;
 use sim_back::collect_modules;
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
+use crate::sink::Sink;
+
 pub use basedb::lints::builtin as builtin_lints;
 pub use basedb::lints::LintLevel;
+pub use basedb::DEFAULT_MACRO_EXPANSION_LIMIT;
 pub use llvm::OptLevel;
 pub use paths::AbsPathBuf;
+pub use sink::ErrorFormat;
 pub use target::host_triple;
 pub use target::spec::{get_target_names, Target};
 
 mod cache;
+mod sink;
 
 #[derive(Debug, Clone)]
 pub enum CompilationDestination {
@@ -45,6 +51,11 @@ pub struct Opts {
     pub opt_lvl: OptLevel,
     pub target: Target,
     pub target_cpu: String,
+    /// Keys used to decrypt protected envelopes (`` `pragma protect ``) as `(name, PEM file)`.
+    pub decryption_keys: Vec<(String, Utf8PathBuf)>,
+    /// The maximum number of nested macro expansions.
+    pub macro_expansion_limit: usize,
+    pub error_format: ErrorFormat,
 }
 // pub fn dump_json(opts: &Opts) -> Result<CompilationTermination> {
 //     let input =
@@ -104,16 +115,36 @@ pub struct Opts {
 //     Ok(CompilationTermination::Compiled { lib_file: Utf8PathBuf::default() })
 // }
 
+fn set_preprocessor_opts(db: &mut CompilationDB, opts: &Opts) -> Result<()> {
+    let keys: Result<Arc<[_]>> = opts
+        .decryption_keys
+        .iter()
+        .map(|(name, path)| {
+            let pem = fs::read_to_string(path)
+                .with_context(|| format!("failed to read decryption key {path}"))?;
+            Ok(DecryptionKey { name: Arc::from(name.as_str()), pem: Arc::from(pem) })
+        })
+        .collect();
+    let root_file = db.compilation_unit().root_file();
+    db.set_decryption_keys(root_file, keys?);
+    db.set_macro_expansion_limit(root_file, opts.macro_expansion_limit);
+    Ok(())
+}
+
 pub fn expand(opts: &Opts) -> Result<CompilationTermination> {
     let start = Instant::now();
 
     let input =
         opts.input.canonicalize().with_context(|| format!("failed to resolve {}", opts.input))?;
     let input = AbsPathBuf::assert(input);
-    let db = CompilationDB::new_fs(input, &opts.include, &opts.defines, &opts.lints)?;
+    let mut db = CompilationDB::new_fs(input, &opts.include, &opts.defines, &opts.lints)?;
+    set_preprocessor_opts(&mut db, opts)?;
     let cu = db.compilation_unit();
 
     let preprocess = cu.preprocess(&db);
+    if preprocess.ts.iter().any(|token| preprocess.sm.is_protected(token.span.ctx)) {
+        bail!("{} contains protected source code which can not be printed", opts.input);
+    }
     for token in preprocess.ts.iter() {
         let span = token.span.to_file_span(&preprocess.sm);
         let text = db.file_text(span.file).unwrap();
@@ -121,10 +152,12 @@ pub fn expand(opts: &Opts) -> Result<CompilationTermination> {
     }
     println!();
 
-    let mut sink = ConsoleSink::new(&db);
+    // stdout is reserved for the expanded source
+    let mut sink = Sink::new_with(&db, opts.error_format, Box::new(io::stderr()));
     sink.add_diagnostics(&*preprocess.diagnostics, cu.root_file(), &db);
-
-    if sink.summary(&opts.input.file_name().unwrap()) {
+    let failed = sink.summary(&opts.input.file_name().unwrap());
+    sink.finish()?;
+    if failed {
         return Ok(CompilationTermination::FatalDiagnostic);
     }
 
@@ -144,7 +177,8 @@ pub fn compile(opts: &Opts) -> Result<CompilationTermination> {
     let input =
         opts.input.canonicalize().with_context(|| format!("failed to resolve {}", opts.input))?;
     let input = AbsPathBuf::assert(input);
-    let db = CompilationDB::new_fs(input, &opts.include, &opts.defines, &opts.lints)?;
+    let mut db = CompilationDB::new_fs(input, &opts.include, &opts.defines, &opts.lints)?;
+    set_preprocessor_opts(&mut db, opts)?;
 
     let lib_file = match &opts.output {
         CompilationDestination::Cache { cache_dir } => {
@@ -159,7 +193,10 @@ pub fn compile(opts: &Opts) -> Result<CompilationTermination> {
         CompilationDestination::Path { lib_file } => lib_file.clone(),
     };
 
-    let modules = if let Some(modules) = collect_modules(&db, false, &mut ConsoleSink::new(&db)) {
+    let mut sink = Sink::new(&db, opts.error_format);
+    let modules = collect_modules(&db, false, &mut sink);
+    sink.finish()?;
+    let modules = if let Some(modules) = modules {
         modules
     } else {
         return Ok(CompilationTermination::FatalDiagnostic);
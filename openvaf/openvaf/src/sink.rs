@@ -0,0 +1,76 @@
+use std::fmt::Display;
+use std::io::{self, Write};
+
+use anyhow::{Context, Result};
+use basedb::diagnostics::{ConsoleSink, Diagnostic, DiagnosticSink, JsonSink, Report, SarifSink};
+use basedb::{BaseDB, FileId};
+
+/// How diagnostics are reported to the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// Human readable reports printed to stderr.
+    Human,
+    /// One JSON object per diagnostic printed to stdout (stderr when the expanded
+    /// source is printed).
+    Json,
+    /// A SARIF log with all diagnostics printed to stdout (stderr when the expanded
+    /// source is printed).
+    Sarif,
+}
+
+/// The diagnostic sink selected by [`ErrorFormat`].
+pub(crate) enum Sink<'a> {
+    Human(Box<ConsoleSink<'a>>),
+    Json(JsonSink<'a>),
+    Sarif(SarifSink<'a>),
+}
+
+impl<'a> Sink<'a> {
+    pub fn new(db: &'a dyn BaseDB, format: ErrorFormat) -> Sink<'a> {
+        Sink::new_with(db, format, Box::new(io::stdout()))
+    }
+
+    /// Like [`Sink::new`] but machine readable diagnostics are written to `dst`.
+    /// Human readable reports are always printed to stderr.
+    pub fn new_with(db: &'a dyn BaseDB, format: ErrorFormat, dst: Box<dyn Write + 'a>) -> Sink<'a> {
+        match format {
+            ErrorFormat::Human => Sink::Human(Box::new(ConsoleSink::new(db))),
+            ErrorFormat::Json => Sink::Json(JsonSink::new_with(db, dst)),
+            ErrorFormat::Sarif => Sink::Sarif(SarifSink::new_with(db, dst)),
+        }
+    }
+
+    /// Emits any diagnostics that are buffered by the sink.
+    pub fn finish(self) -> Result<()> {
+        if let Sink::Sarif(sink) = self {
+            sink.finish().context("failed to write diagnostics")?;
+        }
+        Ok(())
+    }
+}
+
+impl DiagnosticSink for Sink<'_> {
+    fn add_report(&mut self, report: Report) {
+        match self {
+            Sink::Human(sink) => sink.add_report(report),
+            Sink::Json(sink) => sink.add_report(report),
+            Sink::Sarif(sink) => sink.add_report(report),
+        }
+    }
+
+    fn add_diagnostic(&mut self, diagnostic: &dyn Diagnostic, root_file: FileId, db: &dyn BaseDB) {
+        match self {
+            Sink::Human(sink) => sink.add_diagnostic(diagnostic, root_file, db),
+            Sink::Json(sink) => sink.add_diagnostic(diagnostic, root_file, db),
+            Sink::Sarif(sink) => sink.add_diagnostic(diagnostic, root_file, db),
+        }
+    }
+
+    fn summary(&mut self, target_name: &impl Display) -> bool {
+        match self {
+            Sink::Human(sink) => sink.summary(target_name),
+            Sink::Json(sink) => sink.summary(target_name),
+            Sink::Sarif(sink) => sink.summary(target_name),
+        }
+    }
+}
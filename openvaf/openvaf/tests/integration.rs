@@ -6,17 +6,22 @@ use expect_test::expect_file;
 use float_cmp::assert_approx_eq;
 use llvm::OptLevel;
 use mini_harness::{harness, Result};
-use openvaf::{CompilationDestination, CompilationTermination};
+use openvaf::{
+    CompilationDestination, CompilationTermination, ErrorFormat, DEFAULT_MACRO_EXPANSION_LIMIT,
+};
 use stdx::{ignore_dev_tests, openvaf_test_data, project_root};
 use target::spec::Target;
 
-use crate::load::{load_osdi_lib, EvalFlags, OsdiDescriptor};
+use crate::load::{
+    load_osdi_lib, osdi_str, test_hidden_state, EvalFlags, EvalRetFlags, OsdiDescriptor,
+    OsdiInstance, SIMPROBE_VAL,
+};
 use crate::mock_sim::{MockSimulation, ALPHA};
 
 mod load;
 mod mock_sim;
 
-fn compile_and_load(root_file: &Utf8Path) -> &'static OsdiDescriptor {
+fn compile_and_load_all(root_file: &Utf8Path) -> &'static [OsdiDescriptor] {
     let openvaf_opts = openvaf::Opts {
         defines: Vec::new(),
         codegen_opts: Vec::new(),
@@ -27,6 +32,9 @@ fn compile_and_load(root_file: &Utf8Path) -> &'static OsdiDescriptor {
         opt_lvl: OptLevel::Aggressive,
         target: Target::host_target().unwrap(),
         target_cpu: "native".to_owned(),
+        decryption_keys: Vec::new(),
+        macro_expansion_limit: DEFAULT_MACRO_EXPANSION_LIMIT,
+        error_format: ErrorFormat::Human,
         dry_run: false,
     };
 
@@ -37,7 +45,11 @@ fn compile_and_load(root_file: &Utf8Path) -> &'static OsdiDescriptor {
             panic!("openvaf: compilation of {root_file} failed");
         }
     };
-    let libs = unsafe { load_osdi_lib(&lib_file).unwrap() };
+    unsafe { load_osdi_lib(&lib_file).unwrap() }
+}
+
+fn compile_and_load(root_file: &Utf8Path) -> &'static OsdiDescriptor {
+    let libs = compile_and_load_all(root_file);
     assert_eq!(libs.len(), 1);
     &libs[0]
 }
@@ -212,8 +224,794 @@ fn test_noise() -> Result<()> {
     Ok(())
 }
 
+fn test_noise_table() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("noise_table.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+    instance.eval(&model, &mut sim, EvalFlags::empty());
+
+    // (frequency, linear table, log-log table)
+    let expected = [
+        (0.1, 2.0, 1.0),
+        (1.0, 2.0, 1.0),
+        (5.5, 11.0, 0.033057851),
+        (10.0, 20.0, 0.01),
+        (1e3, 20.0, 1e-4),
+    ];
+    for (freq, lin, log) in expected {
+        instance.load_noise(&model, &mut sim, freq);
+        assert_approx_eq!(sim.read_noise(0), lin);
+        assert_approx_eq!(sim.read_noise(1), log);
+        // unsorted tables are sorted by frequency
+        assert_approx_eq!(sim.read_noise(2), lin);
+        assert_approx_eq!(sim.read_noise(3), lin);
+    }
+    Ok(())
+}
+
+fn test_transition() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("transition.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    // three states for transition and slew each
+    assert_eq!(desc.num_states, 6);
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    let eval = |sim: &mut MockSimulation, abstime, va, vb, flags| {
+        sim.next_iter();
+        sim.abstime = abstime;
+        sim.set_voltage("a", va);
+        sim.set_voltage("b", vb);
+        let ret_flags = instance.eval(&model, sim, flags);
+        instance.load_dae(&model, sim);
+        let discontinuity = ret_flags.contains(EvalRetFlags::EVAL_RET_FLAG_DISCONTINUITY);
+        (sim.read_residual("a").0, sim.read_residual("b").0, instance.bound_step(), discontinuity)
+    };
+
+    // the input is passed through during DC/IC analysis
+    let (transition, slew, _, discontinuity) =
+        eval(&mut sim, 0.0, 1.0, 1.0, EvalFlags::ANALYSIS_IC);
+    assert_approx_eq!(transition, 1.0);
+    assert_approx_eq!(slew, 1.0);
+    assert!(!discontinuity);
+
+    // a new ramp starts at the current value, the slew rate is 1V/ns
+    let (transition, slew, bound_step, discontinuity) =
+        eval(&mut sim, 1e-9, 2.0, 3.0, EvalFlags::ANALYSIS_TRAN);
+    assert_approx_eq!(transition, 1.0);
+    assert_approx_eq!(slew, 2.0);
+    assert_approx_eq!(bound_step, 1e-9);
+    assert!(discontinuity);
+
+    let (transition, slew, _, discontinuity) =
+        eval(&mut sim, 1.5e-9, 2.0, 3.0, EvalFlags::ANALYSIS_TRAN);
+    assert_approx_eq!(transition, 1.5);
+    assert_approx_eq!(slew, 2.5);
+    assert!(!discontinuity);
+
+    // falling ramps use the fall time
+    let (transition, slew, _, discontinuity) =
+        eval(&mut sim, 4e-9, 0.0, 3.0, EvalFlags::ANALYSIS_TRAN);
+    assert_approx_eq!(transition, 2.0);
+    assert_approx_eq!(slew, 3.0);
+    assert!(discontinuity);
+
+    let (transition, _, _, discontinuity) =
+        eval(&mut sim, 5e-9, 0.0, 3.0, EvalFlags::ANALYSIS_TRAN);
+    assert_approx_eq!(transition, 1.0);
+    assert!(!discontinuity);
+    Ok(())
+}
+
+fn test_filters() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("filters.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    // the response of a laplace filter and the bilinear approximation of the
+    // z-domain filters at ω = 1/T
+    instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_AC);
+    instance.load_dae(&model, &mut sim);
+    let fixed = ["inp"];
+    let ac = |output| sim.ac_transfer(&fixed, "inp", output, 1e9);
+    let (re, im) = ac("lowpass");
+    float_cmp::assert_approx_eq!(f64, re, 0.5, epsilon = 1e-9);
+    float_cmp::assert_approx_eq!(f64, im, -0.5, epsilon = 1e-9);
+    let (re, im) = ac("avg");
+    float_cmp::assert_approx_eq!(f64, re, 0.8, epsilon = 1e-9);
+    float_cmp::assert_approx_eq!(f64, im, -0.4, epsilon = 1e-9);
+    let (re, im) = ac("iir");
+    float_cmp::assert_approx_eq!(f64, re, 1.75 / 3.25, epsilon = 1e-9);
+    float_cmp::assert_approx_eq!(f64, im, -1.0 / 3.25, epsilon = 1e-9);
+    let (re, im) = ac("zoh");
+    float_cmp::assert_approx_eq!(f64, re, 1.0, epsilon = 1e-9);
+    float_cmp::assert_approx_eq!(f64, im, 0.0, epsilon = 1e-9);
+
+    // during transient analysis the z-domain filters are sampled every 1ns,
+    // the outputs are contributed as V(out) - y
+    let eval = |sim: &mut MockSimulation, abstime, input, flags| {
+        sim.next_iter();
+        sim.abstime = abstime;
+        sim.set_voltage("inp", input);
+        instance.eval(&model, sim, flags);
+        instance.load_dae(&model, sim);
+        let outputs = ["zoh", "avg", "iir", "smooth"].map(|node| -sim.read_residual(node).0);
+        (outputs, instance.bound_step())
+    };
+
+    eval(&mut sim, 0.0, 1.0, EvalFlags::ANALYSIS_IC | EvalFlags::ANALYSIS_TRAN);
+    // all filters except `zoh` (first sample at 0.5ns) are sampled at t = 0
+    let ([zoh, avg, iir, smooth], _) = eval(&mut sim, 0.0, 1.0, EvalFlags::ANALYSIS_TRAN);
+    assert_approx_eq!(zoh, 1.0);
+    assert_approx_eq!(avg, 1.0);
+    assert_approx_eq!(iir, 1.0);
+    assert_approx_eq!(smooth, 1.0);
+
+    let ([zoh, avg, iir, smooth], bound_step) =
+        eval(&mut sim, 0.5e-9, 2.0, EvalFlags::ANALYSIS_TRAN);
+    assert_approx_eq!(zoh, 2.0);
+    assert_approx_eq!(avg, 1.0);
+    assert_approx_eq!(iir, 1.0);
+    assert_approx_eq!(smooth, 1.0);
+    assert_approx_eq!(bound_step, 0.5e-9);
+
+    // y[k] = 0.5x[k] + 0.5x[k-1] and y[k] = 0.5x[k] + 0.5y[k-1],
+    // `smooth` starts a 0.2ns ramp towards the new sample
+    let ([zoh, avg, iir, smooth], bound_step) = eval(&mut sim, 1e-9, 3.0, EvalFlags::ANALYSIS_TRAN);
+    assert_approx_eq!(zoh, 2.0);
+    assert_approx_eq!(avg, 2.0);
+    assert_approx_eq!(iir, 2.0);
+    assert_approx_eq!(smooth, 1.0);
+    assert_approx_eq!(bound_step, 0.2e-9);
+
+    let ([zoh, avg, iir, smooth], _) = eval(&mut sim, 1.1e-9, 3.0, EvalFlags::ANALYSIS_TRAN);
+    assert_approx_eq!(zoh, 2.0);
+    assert_approx_eq!(avg, 2.0);
+    assert_approx_eq!(iir, 2.0);
+    assert_approx_eq!(smooth, 2.0);
+
+    // a missed sample time (1.5ns for `zoh`) is sampled at the next timestep
+    let ([zoh, avg, iir, smooth], bound_step) = eval(&mut sim, 2e-9, 3.0, EvalFlags::ANALYSIS_TRAN);
+    assert_approx_eq!(zoh, 3.0);
+    assert_approx_eq!(avg, 3.0);
+    assert_approx_eq!(iir, 2.5);
+    assert_approx_eq!(smooth, 3.0);
+    assert_approx_eq!(bound_step, 0.5e-9);
+    Ok(())
+}
+
+fn test_events() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("events.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    // cross, last_crossing and timer states and one state for each held variable
+    assert_eq!(desc.num_states, 8);
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    let eval = |sim: &mut MockSimulation, abstime, va, vb, flags| {
+        sim.next_iter();
+        sim.abstime = abstime;
+        sim.set_voltage("a", va);
+        sim.set_voltage("b", vb);
+        let ret_flags = instance.eval(&model, sim, flags);
+        instance.load_dae(&model, sim);
+        let res = [sim.read_residual("a").0, sim.read_residual("b").0, sim.read_residual("c").0];
+        let discontinuity = ret_flags.contains(EvalRetFlags::EVAL_RET_FLAG_DISCONTINUITY);
+        (res, instance.bound_step(), discontinuity)
+    };
+
+    // events never fire during DC analysis
+    let ([held, ticks, crossed_at], _, discontinuity) =
+        eval(&mut sim, 0.0, -1.0, 1.0, EvalFlags::ANALYSIS_DC);
+    assert_approx_eq!(held, 0.0);
+    assert_approx_eq!(ticks, 0.0);
+    assert_approx_eq!(crossed_at, -1e9);
+    assert!(!discontinuity);
+
+    // the timer fires at 0 and the timestep is bound to the extrapolated crossing
+    let ([held, ticks, _], bound_step, discontinuity) =
+        eval(&mut sim, 0.5e-9, -0.5, 2.0, EvalFlags::ANALYSIS_TRAN);
+    assert_approx_eq!(held, 0.0);
+    assert_approx_eq!(ticks, 1.0);
+    assert_approx_eq!(bound_step, 0.5e-9);
+    assert!(discontinuity);
+
+    // the crossing is interpolated between both timesteps
+    let ([held, ticks, crossed_at], _, discontinuity) =
+        eval(&mut sim, 1e-9, 0.5, 3.0, EvalFlags::ANALYSIS_TRAN);
+    assert_approx_eq!(held, 3.0);
+    assert_approx_eq!(ticks, 2.0);
+    assert_approx_eq!(crossed_at, 0.75);
+    assert!(discontinuity);
+
+    // all values are held until the next event
+    let ([held, ticks, crossed_at], bound_step, discontinuity) =
+        eval(&mut sim, 1.5e-9, 1.0, 4.0, EvalFlags::ANALYSIS_TRAN);
+    assert_approx_eq!(held, 3.0);
+    assert_approx_eq!(ticks, 2.0);
+    assert_approx_eq!(crossed_at, 0.75);
+    assert_approx_eq!(bound_step, 0.5e-9);
+    assert!(!discontinuity);
+    Ok(())
+}
+
+fn test_absdelay() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("absdelay.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    let eval = |sim: &mut MockSimulation, abstime, va, flags| {
+        sim.next_iter();
+        sim.abstime = abstime;
+        sim.set_voltage("a", va);
+        instance.eval(&model, sim, flags);
+        instance.load_dae(&model, sim);
+        sim.read_residual("b").0
+    };
+
+    // the input is passed through at the operating point
+    assert_approx_eq!(eval(&mut sim, 0.0, 1.0, EvalFlags::ANALYSIS_DC), 1.0);
+
+    // the operating point is used until the delay has passed
+    assert_approx_eq!(eval(&mut sim, 0.5e-9, 2.0, EvalFlags::ANALYSIS_TRAN), 1.0);
+    assert_approx_eq!(eval(&mut sim, 1e-9, 3.0, EvalFlags::ANALYSIS_TRAN), 1.0);
+
+    // afterwards the history is interpolated
+    assert_approx_eq!(eval(&mut sim, 1.5e-9, 4.0, EvalFlags::ANALYSIS_TRAN), 2.0);
+    assert_approx_eq!(eval(&mut sim, 1.75e-9, 5.0, EvalFlags::ANALYSIS_TRAN), 2.5);
+
+    // rejected timesteps are removed from the history
+    assert_approx_eq!(eval(&mut sim, 1.6e-9, 6.0, EvalFlags::ANALYSIS_TRAN), 2.2);
+
+    // the history grows as required when many timesteps fall into the delay
+    for i in 0..=4000 {
+        let abstime = 2e-9 + i as f64 * 0.5e-12;
+        let delayed = eval(&mut sim, abstime, abstime * 1e9, EvalFlags::ANALYSIS_TRAN);
+        if abstime >= 3e-9 {
+            float_cmp::assert_approx_eq!(f64, delayed, abstime * 1e9 - 1.0, epsilon = 1e-9);
+        }
+    }
+    Ok(())
+}
+
+fn test_file_io() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("file_io.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let dir = test_output_dir("file_io")?;
+    let fd_file = dir.join("fd.log");
+    let mcd_file = dir.join("mcd.log");
+    let model = desc.new_model();
+    model.set_str_param(param_by_name(desc, "fd_file"), fd_file.to_str().unwrap());
+    model.set_str_param(param_by_name(desc, "mcd_file"), mcd_file.to_str().unwrap());
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+    sim.set_voltage("a", 1.5);
+    instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+
+    let fd_out = std::fs::read_to_string(&fd_file)?;
+    // pending strobes are written when their file is closed
+    let mcd_out = std::fs::read_to_string(&mcd_file)?;
+    std::fs::remove_dir_all(&dir)?;
+    assert_eq!(fd_out, "42 1.5\n");
+    assert_eq!(mcd_out, "mcd!\n");
+    Ok(())
+}
+
+fn test_strobe() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("strobe.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let dir = test_output_dir("strobe")?;
+    let file = dir.join("strobe.log");
+    let model = desc.new_model();
+    model.set_str_param(param_by_name(desc, "file"), file.to_str().unwrap());
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    let mut eval = |abstime, va, flags| {
+        sim.next_iter();
+        sim.abstime = abstime;
+        sim.set_voltage("a", va);
+        instance.eval(&model, &mut sim, flags);
+    };
+
+    // only the message of the last iteration of each timestep is written
+    eval(0.0, 1.0, EvalFlags::ANALYSIS_DC);
+    eval(0.0, 2.0, EvalFlags::ANALYSIS_DC);
+    eval(1e-9, 2.0, EvalFlags::ANALYSIS_TRAN);
+    eval(1e-9, 3.0, EvalFlags::ANALYSIS_TRAN);
+    // the messages of rejected timesteps are discarded
+    eval(0.5e-9, 4.0, EvalFlags::ANALYSIS_TRAN);
+    eval(2e-9, 4.0, EvalFlags::ANALYSIS_TRAN);
+    // the last timestep is written (and the file closed) when the instance is freed
+    drop(instance);
+
+    let out = std::fs::read_to_string(&file)?;
+    std::fs::remove_dir_all(&dir)?;
+    // $fmonitor skips messages that did not change
+    assert_eq!(out, "strobe 2\nmonitor 2\nstrobe 4\nmonitor 4\nstrobe 4\n");
+    Ok(())
+}
+
+/// Creates an empty directory for the files written by a test
+fn test_output_dir(name: &str) -> std::io::Result<std::path::PathBuf> {
+    let dir = std::env::temp_dir().join(format!("openvaf_{name}_{}", std::process::id()));
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn param_by_name(desc: &OsdiDescriptor, name: &str) -> u32 {
+    desc.params().iter().position(|param| unsafe { osdi_str(*param.name) } == name).unwrap() as u32
+}
+
+fn test_random() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("random.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+    let mut instance2 = model.new_instance();
+    let mut sim2 = instance2.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    let eval = |instance: &mut OsdiInstance, sim: &mut MockSimulation| {
+        sim.next_iter();
+        instance.eval(&model, sim, EvalFlags::ANALYSIS_DC);
+        instance.load_dae(&model, sim);
+        ["a", "b", "c", "d", "e", "f", "g"].map(|node| sim.read_residual(node).0)
+    };
+
+    let [x1, x2, y, r, ar, inst, global] = eval(&mut instance, &mut sim);
+    assert!((1.0..2.0).contains(&x1));
+    assert!((1.0..2.0).contains(&x2));
+    // the seed variable is updated by the first call
+    assert_ne!(x1, x2);
+    assert!((0.0..=10.0).contains(&y));
+    assert_eq!(y.fract(), 0.0);
+
+    // the results only depend on the seed
+    let [x1_, x2_, y_, r_, ar_, inst_, global_] = eval(&mut instance, &mut sim);
+    assert_eq!([x1, x2, y, ar, inst, global], [x1_, x2_, y_, ar_, inst_, global_]);
+    // without a seed the random sequence continues
+    assert_ne!(r, r_);
+
+    // the "instance" type string produces a separate value for each instance
+    let [_, _, _, _, ar2, inst2, global2] = eval(&mut instance2, &mut sim2);
+    assert!((1.0..2.0).contains(&inst2));
+    assert_ne!(ar, ar2);
+    assert_ne!(inst, inst2);
+    assert_eq!(global, global2);
+    Ok(())
+}
+
+fn test_generate() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("generate.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    sim.next_iter();
+    sim.set_voltage("a", 1.0);
+    sim.set_voltage("b", 0.0);
+    sim.set_voltage("seg[0].n", 0.5);
+    sim.set_voltage("seg[1].n", 0.25);
+    sim.set_voltage("seg[2].n", 0.5);
+    instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+    instance.load_dae(&model, &mut sim);
+
+    // every iteration of the loop contributes its own segment
+    assert_eq!(sim.read_residual("seg[0].n").0, 0.0);
+    assert_eq!(sim.read_residual("seg[1].n").0, -1.0);
+    assert_eq!(sim.read_residual("seg[2].n").0, 0.0);
+    // the unnamed loop contributes (1 + 2) * V(a, b)
+    assert_eq!(sim.read_residual("a").0, 6.5);
+    assert_eq!(sim.read_residual("b").0, -5.5);
+    Ok(())
+}
+
+fn test_paramset() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("paramset.va");
+    let descriptors = compile_and_load_all(main_file.as_path().try_into().unwrap());
+    assert_eq!(descriptors.len(), 2);
+    let find = |name: &str| {
+        descriptors.iter().find(|desc| unsafe { osdi_str(desc.name) } == name).unwrap()
+    };
+
+    let eval = |desc: &'static OsdiDescriptor, scale: Option<f64>| -> Result<f64> {
+        let model = desc.new_model();
+        if let Some(scale) = scale {
+            model.set_real_param(desc.num_instance_params, scale);
+        }
+        model.process_params()?;
+        let mut instance = model.new_instance();
+        let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+        sim.next_iter();
+        sim.set_voltage("a", 1.0);
+        sim.set_voltage("b", 0.0);
+        instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+        instance.load_dae(&model, &mut sim);
+        Ok(sim.read_residual("a").0)
+    };
+
+    let res = find("res");
+    assert_approx_eq!(eval(res, None)?, 1.0);
+
+    // only the parameters declared in the paramset are exposed
+    let corner = find("res_corner");
+    assert_eq!(corner.num_params, corner.num_instance_params + 1);
+    let names: Vec<_> =
+        corner.params().iter().map(|param| unsafe { osdi_str(*param.name) }).collect();
+    assert!(names.contains(&"scale"));
+    assert!(!names.contains(&"r") && !names.contains(&"n"));
+
+    // the defaults of the module parameters are replaced by the paramset
+    assert_approx_eq!(eval(corner, None)?, 0.1);
+    assert_approx_eq!(eval(corner, Some(1.0))?, 0.2);
+    Ok(())
+}
+
+fn test_arrays() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("arrays.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let param = |name: &str| {
+        desc.params().iter().position(|param| unsafe { osdi_str(*param.name) } == name).unwrap()
+            as u32
+    };
+    let eval = |gains: Option<&[f64]>, sel: i32| -> Result<f64> {
+        let model = desc.new_model();
+        if let Some(gains) = gains {
+            model.set_real_array_param(param("gains"), gains);
+        }
+        model.set_int_param(param("sel"), sel);
+        model.process_params()?;
+        let mut instance = model.new_instance();
+        let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+        sim.next_iter();
+        sim.set_voltage("a", 1.0);
+        sim.set_voltage("b", 0.0);
+        instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+        instance.load_dae(&model, &mut sim);
+        Ok(sim.read_residual("a").0)
+    };
+
+    // the default value of the array parameter
+    assert_eq!(eval(None, 1)?, 7.0);
+    assert_eq!(eval(None, 0)?, 2.0);
+    // every element of the array parameter can be set by the simulator
+    assert_eq!(eval(Some(&[4.0, 5.0, 6.0]), 1)?, 16.0);
+    Ok(())
+}
+
+fn test_vector_nets() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("vector_nets.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    sim.next_iter();
+    sim.set_voltage("a", 1.0);
+    sim.set_voltage("b", 0.0);
+    sim.set_voltage("n[0]", 1.0);
+    sim.set_voltage("n[1]", 0.5);
+    sim.set_voltage("n[2]", 0.5);
+    sim.set_voltage("n[3]", 0.0);
+    instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+    instance.load_dae(&model, &mut sim);
+
+    // every element of the vector net is a separate node
+    assert_eq!(sim.read_residual("n[0]").0, 0.5);
+    assert_eq!(sim.read_residual("n[1]").0, -0.5);
+    assert_eq!(sim.read_residual("n[2]").0, 1.5);
+    assert_eq!(sim.read_residual("n[3]").0, -1.5);
+    Ok(())
+}
+
+fn test_instance() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("instance.va");
+    let descriptors = compile_and_load_all(main_file.as_path().try_into().unwrap());
+    // instantiated modules are still compiled on their own
+    assert_eq!(descriptors.len(), 2);
+    let desc = descriptors.iter().find(|desc| unsafe { osdi_str(desc.name) } == "divider").unwrap();
+    // the instances are flattened into the parent module
+    assert_eq!(desc.num_terminals, 2);
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    sim.next_iter();
+    sim.set_voltage("a", 1.0);
+    sim.set_voltage("b", 0.0);
+    sim.set_voltage("mid", 0.5);
+    instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+    instance.load_dae(&model, &mut sim);
+
+    assert_eq!(sim.read_residual("a").0, 0.5);
+    assert_eq!(sim.read_residual("mid").0, -0.25);
+    assert_eq!(sim.read_residual("b").0, -0.25);
+    Ok(())
+}
+
+fn test_node_alias() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("node_alias.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    // the aliased node x is not part of the system anymore
+    assert_eq!(desc.num_nodes, 3);
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    sim.next_iter();
+    sim.set_voltage("a", 1.0);
+    sim.set_voltage("b", 0.0);
+    sim.set_voltage("y", 0.0);
+    instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+    instance.load_dae(&model, &mut sim);
+
+    assert_eq!(sim.read_residual("a").0, 0.5);
+    assert_eq!(sim.read_residual("b").0, -0.5);
+    Ok(())
+}
+
+fn test_simprobe() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("simprobe.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let eval = |probe_missing| -> Result<(f64, EvalRetFlags)> {
+        let model = desc.new_model();
+        model.set_int_param(param_by_name(desc, "probe_missing"), probe_missing);
+        model.process_params()?;
+        let mut instance = model.new_instance();
+        let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+        sim.next_iter();
+        let ret_flags = instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+        instance.load_dae(&model, &mut sim);
+        Ok((sim.read_residual("a").0, ret_flags))
+    };
+
+    // the value returned by the simulator plus the default of the failed probe
+    let (res, ret_flags) = eval(0)?;
+    assert_eq!(res, SIMPROBE_VAL + 2.0);
+    assert!(!ret_flags.contains(EvalRetFlags::EVAL_RET_FLAG_FATAL));
+
+    // a failed probe without a default is fatal
+    let (_, ret_flags) = eval(1)?;
+    assert!(ret_flags.contains(EvalRetFlags::EVAL_RET_FLAG_FATAL));
+    Ok(())
+}
+
+fn test_wreal() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("wreal.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    // real nets are mapped to terminals
+    assert_eq!(desc.num_terminals, 5);
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    sim.next_iter();
+    sim.set_voltage("a", 1.0);
+    sim.set_voltage("b", 0.0);
+    sim.set_voltage("in", 1.5);
+    sim.set_voltage("en", 1.0);
+    sim.set_voltage("out", 0.0);
+    instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+    instance.load_dae(&model, &mut sim);
+
+    assert_eq!(sim.read_residual("a").0, 1.5);
+    assert_eq!(sim.read_residual("b").0, -1.5);
+    // out is driven by an ideal voltage source, the residual is `out - V(out)`
+    assert_eq!(sim.read_residual("flow(out)").0, 3.0);
+
+    // the assignment in the else branch replaces the driven value
+    sim.next_iter();
+    sim.set_voltage("a", 1.0);
+    sim.set_voltage("b", 0.0);
+    sim.set_voltage("in", 1.5);
+    sim.set_voltage("en", 0.0);
+    sim.set_voltage("out", 0.5);
+    instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+    instance.load_dae(&model, &mut sim);
+
+    assert_eq!(sim.read_residual("a").0, 1.5);
+    assert_eq!(sim.read_residual("b").0, -1.5);
+    assert_eq!(sim.read_residual("flow(out)").0, -0.5);
+    Ok(())
+}
+
+fn test_plusargs() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("plusargs.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    sim.next_iter();
+    sim.set_voltage("a", 1.0);
+    sim.set_voltage("b", 0.0);
+    instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+    instance.load_dae(&model, &mut sim);
+
+    // the test loader provides +debug, +model=2 and +scale=0.5, "model%d" can not be parsed
+    assert_eq!(sim.read_residual("a").0, 3.0);
+    assert_eq!(sim.read_residual("b").0, -3.0);
+    Ok(())
+}
+
+fn test_table_model() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("table_model.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    sim.next_iter();
+    sim.set_voltage("a", 2.5);
+    sim.set_voltage("b", 1.5);
+    sim.set_voltage("c", 0.5);
+    sim.set_voltage("d", 0.25);
+    sim.set_voltage("e", 1.5);
+    sim.set_voltage("f", 1.5);
+    let ret_flags = instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+    instance.load_dae(&model, &mut sim);
+    assert!(!ret_flags.contains(EvalRetFlags::EVAL_RET_FLAG_FATAL));
+
+    // linear extrapolation continues the last segment
+    assert_approx_eq!(sim.read_residual("a").0, 5.5);
+    assert_approx_eq!(sim.read_jacobian("a", "a").0, 3.0);
+    // the second derivatives of the spline are 2.4 at x = 1 and x = 2
+    assert_approx_eq!(sim.read_residual("b").0, 2.2);
+    assert_approx_eq!(sim.read_jacobian("b", "b").0, 3.0);
+    assert_approx_eq!(sim.read_residual("e").0, 15.0);
+    assert_approx_eq!(sim.read_jacobian("e", "e").0, 10.0);
+    assert_approx_eq!(sim.read_residual("c").0, 1.25);
+    assert_approx_eq!(sim.read_jacobian("c", "c").0, 2.0);
+    assert_approx_eq!(sim.read_jacobian("c", "d").0, 1.0);
+    assert_approx_eq!(sim.read_residual("f").0, 2.25);
+    assert_approx_eq!(sim.read_jacobian("f", "f").0, 3.0);
+
+    // extrapolation with `E` is a fatal error
+    sim.next_iter();
+    sim.set_voltage("f", 3.5);
+    let ret_flags = instance.eval(&model, &mut sim, EvalFlags::ANALYSIS_DC);
+    assert!(ret_flags.contains(EvalRetFlags::EVAL_RET_FLAG_FATAL));
+    Ok(())
+}
+
 harness! {
     // TODO: run this in CI, somehow this test is flakey tough regarding the linker invocation (and really slow)
     Test::from_dir("integration", &integration_test, &ignore_dev_tests, &project_root().join("integration_tests")),
-    [Test::new("$limit", &test_limit),Test::new("noise", &test_noise)]
+    [Test::new("$limit", &test_limit),Test::new("noise", &test_noise),Test::new("noise_table", &test_noise_table),Test::new("transition", &test_transition),Test::new("filters", &test_filters),Test::new("events", &test_events),Test::new("absdelay", &test_absdelay),Test::new("file_io", &test_file_io),Test::new("strobe", &test_strobe),Test::new("random", &test_random),Test::new("hidden_state", &test_hidden_state),Test::new("generate", &test_generate),Test::new("paramset", &test_paramset),Test::new("arrays", &test_arrays),Test::new("vector_nets", &test_vector_nets),Test::new("instance", &test_instance),Test::new("node_alias", &test_node_alias),Test::new("simprobe", &test_simprobe),Test::new("plusargs", &test_plusargs),Test::new("wreal", &test_wreal),Test::new("table_model", &test_table_model)]
 }
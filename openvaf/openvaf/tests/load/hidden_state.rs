@@ -0,0 +1,54 @@
+//! Checks that hidden state variables are saved in the instance data at the end of each
+//! evaluation and restored from the last accepted timestep when a timestep is rejected.
+
+use mini_harness::Result;
+use stdx::openvaf_test_data;
+
+use crate::compile_and_load;
+use crate::load::EvalFlags;
+use crate::mock_sim::MockSimulation;
+
+pub fn test_hidden_state() -> Result<()> {
+    // skipping in CI for now as we don't have a toolchain there
+    // currently
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("hidden_state.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+
+    let eval = |sim: &mut MockSimulation, abstime, va, flags| {
+        sim.next_iter();
+        sim.abstime = abstime;
+        sim.set_voltage("a", va);
+        instance.eval(&model, sim, flags);
+        instance.load_dae(&model, sim);
+        ["b", "c", "d"].map(|node| sim.read_residual(node).0)
+    };
+
+    // the initial values are used by the first evaluation
+    assert_eq!(eval(&mut sim, 0.0, 1.0, EvalFlags::ANALYSIS_DC), [1.0, 1.0, 0.0]);
+    // outside of transient analysis the state persists between iterations
+    assert_eq!(eval(&mut sim, 0.0, 1.0, EvalFlags::ANALYSIS_DC), [2.0, 1.0, 1.0]);
+
+    // the state is committed when the time advances...
+    assert_eq!(eval(&mut sim, 1e-9, 3.0, EvalFlags::ANALYSIS_TRAN), [3.0, 3.0, 1.0]);
+    // ... but not between iterations of the same timestep
+    assert_eq!(eval(&mut sim, 1e-9, 3.0, EvalFlags::ANALYSIS_TRAN), [3.0, 3.0, 1.0]);
+    // a rejected timestep restores the state of the last accepted timestep
+    assert_eq!(eval(&mut sim, 0.5e-9, 2.0, EvalFlags::ANALYSIS_TRAN), [3.0, 2.0, 1.0]);
+    assert_eq!(eval(&mut sim, 2e-9, 0.0, EvalFlags::ANALYSIS_TRAN), [4.0, 2.0, 2.0]);
+
+    // the peak of the first evaluation at 3ns is never committed because the timestep
+    // is evaluated again (without being accepted) with a lower input
+    assert_eq!(eval(&mut sim, 3e-9, 5.0, EvalFlags::ANALYSIS_TRAN), [5.0, 5.0, 0.0]);
+    assert_eq!(eval(&mut sim, 3e-9, 1.0, EvalFlags::ANALYSIS_TRAN), [5.0, 2.0, 0.0]);
+    // only the last evaluation of the accepted timestep is committed
+    assert_eq!(eval(&mut sim, 4e-9, 0.0, EvalFlags::ANALYSIS_TRAN), [6.0, 2.0, 1.0]);
+    Ok(())
+}
@@ -1,10 +1,11 @@
 use std::alloc::{alloc_zeroed, handle_alloc_error, Layout};
 use std::cell::Cell;
-use std::ffi::{c_char, CStr};
+use std::ffi::{c_char, CStr, CString};
 use std::fmt::Debug;
 use std::mem::{align_of, swap};
 use std::panic::catch_unwind;
 use std::process::exit;
+use std::sync::Mutex;
 use std::{ptr, slice};
 
 use anyhow::{bail, Result};
@@ -15,9 +16,11 @@ use libloading::Library;
 use stdx::format_to;
 use stdx::iter::zip;
 
+mod hidden_state;
 #[allow(warnings)]
 mod osdi_0_3;
 
+pub use hidden_state::test_hidden_state;
 pub use osdi_0_3::*;
 
 impl OsdiDescriptor {
@@ -176,6 +179,38 @@ impl OsdiModel {
         unsafe { ptr.write(val) };
     }
 
+    pub fn set_int_param(&self, param: u32, val: i32) {
+        let ptr = self.descriptor.access(ptr::null_mut(), self.data, param, ACCESS_FLAG_SET);
+        let ptr = ptr as *mut i32;
+        if ptr.is_null() {
+            unreachable!("invalid parameter access")
+        }
+        unsafe { ptr.write(val) };
+    }
+
+    /// The string is leaked because the model only stores a pointer to it
+    pub fn set_str_param(&self, param: u32, val: &str) {
+        let ptr = self.descriptor.access(ptr::null_mut(), self.data, param, ACCESS_FLAG_SET);
+        let ptr = ptr as *mut *mut c_char;
+        if ptr.is_null() {
+            unreachable!("invalid parameter access")
+        }
+        let val = CString::new(val).expect("parameters must not contain null bytes");
+        unsafe { ptr.write(val.into_raw()) };
+    }
+
+    /// Array parameters are stored contiguously, `vals` must contain every element.
+    pub fn set_real_array_param(&self, param: u32, vals: &[f64]) {
+        let ptr = self.descriptor.access(ptr::null_mut(), self.data, param, ACCESS_FLAG_SET);
+        let ptr = ptr as *mut f64;
+        if ptr.is_null() {
+            unreachable!("invalid parameter access")
+        }
+        for (i, val) in vals.iter().enumerate() {
+            unsafe { ptr.add(i).write(*val) };
+        }
+    }
+
     pub fn new_instance(&self) -> OsdiInstance {
         OsdiInstance {
             descriptor: self.descriptor,
@@ -191,10 +226,25 @@ pub struct OsdiInstance {
 
 impl Drop for OsdiInstance {
     fn drop(&mut self) {
+        if let Some(free_instance) = free_instance_fn(self.descriptor) {
+            unsafe { free_instance(b"foo\0".as_ptr() as *mut c_void, self.data) }
+        }
         unsafe { dealloc(self.data, self.descriptor.instance_size as usize) }
     }
 }
 
+type FreeInstanceFn = unsafe extern "C" fn(*mut c_void, *mut c_void);
+
+/// The `OSDI_FREE_INSTANCE` functions of all loaded libraries keyed by the address of their
+/// descriptor
+static FREE_INSTANCE: Mutex<Vec<(usize, FreeInstanceFn)>> = Mutex::new(Vec::new());
+
+fn free_instance_fn(descriptor: &OsdiDescriptor) -> Option<FreeInstanceFn> {
+    let addr = descriptor as *const OsdiDescriptor as usize;
+    let registry = FREE_INSTANCE.lock().unwrap();
+    registry.iter().find(|(descriptor, _)| *descriptor == addr).map(|(_, fun)| *fun)
+}
+
 impl OsdiInstance {
     pub fn matrix_ptrs_resist(&self) -> &[Cell<*mut f64>] {
         let ptr = self.data as *mut u8;
@@ -215,6 +265,22 @@ impl OsdiInstance {
         }
     }
 
+    pub fn state_idx(&self) -> &[Cell<u32>] {
+        let ptr = self.data as *mut u8;
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe {
+            let ptr = ptr.add(self.descriptor.state_idx_off as usize) as *mut Cell<u32>;
+            slice::from_raw_parts_mut(ptr, self.descriptor.num_states as usize)
+        }
+    }
+
+    pub fn bound_step(&self) -> f64 {
+        assert_ne!(self.descriptor.bound_step_offset, u32::MAX);
+        let ptr = self.data as *mut u8;
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe { *(ptr.add(self.descriptor.bound_step_offset as usize) as *mut f64) }
+    }
+
     pub fn collapsed(&self) -> &[bool] {
         let ptr = self.data as *mut u8;
         // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
@@ -338,11 +404,35 @@ pub unsafe fn load_osdi_lib(path: &Utf8Path) -> Result<&'static [OsdiDescriptor]
     let descriptors: &[OsdiDescriptor] =
         slice::from_raw_parts(descriptors, *num_descriptors as usize);
 
+    if let Ok(free_instance) = lib.get::<*const FreeInstanceFn>(b"OSDI_FREE_INSTANCE\0") {
+        let free_instance = slice::from_raw_parts(*free_instance, descriptors.len());
+        let mut registry = FREE_INSTANCE.lock().unwrap();
+        for (descriptor, fun) in zip(descriptors, free_instance) {
+            registry.push((descriptor as *const OsdiDescriptor as usize, *fun));
+        }
+    }
+
     if let Ok(osdi_log_ptr) =
         lib.get::<*mut unsafe extern "C" fn(*mut c_void, *const c_char, u32)>(b"osdi_log\0")
     {
         osdi_log_ptr.write(osdi_log)
     }
+    if let Ok(osdi_plusarg_ptr) =
+        lib.get::<*mut unsafe extern "C" fn(*mut c_void, *const c_char, u32) -> *const c_char>(
+            b"osdi_plusarg\0",
+        )
+    {
+        osdi_plusarg_ptr.write(osdi_plusarg)
+    }
+    if let Ok(osdi_simprobe_ptr) = lib.get::<*mut unsafe extern "C" fn(
+        *mut c_void,
+        *const c_char,
+        *const c_char,
+        *mut f64,
+    ) -> i32>(b"osdi_simprobe\0")
+    {
+        osdi_simprobe_ptr.write(osdi_simprobe)
+    }
     if let Ok(osdi_lim_table) = lib.get(b"OSDI_LIM_TABLE\0") {
         let lim_table_base: *mut OsdiLimFunction = *osdi_lim_table;
         let lim_table_len: &u32 = *lib.get(b"OSDI_LIM_TABLE_LEN\0")?;
@@ -363,6 +453,38 @@ unsafe extern "C" fn osdi_log(handle: *mut c_void, msg: *const c_char, lvl: u32)
     let _ = catch_unwind(|| osdi_log_impl(handle, msg, lvl));
 }
 
+/// The plusargs passed to all models loaded by the tests
+const PLUSARGS: [&[u8]; 3] = [b"debug\0", b"model=2\0", b"scale=0.5\0"];
+
+unsafe extern "C" fn osdi_plusarg(
+    _handle: *mut c_void,
+    prefix: *const c_char,
+    len: u32,
+) -> *const c_char {
+    let prefix = slice::from_raw_parts(prefix as *const u8, len as usize);
+    match PLUSARGS.iter().find(|arg| arg.starts_with(prefix)) {
+        Some(arg) => arg[len as usize..].as_ptr() as *const c_char,
+        None => ptr::null(),
+    }
+}
+
+/// The value of the quantity `v` of the instance `sensor`, all other probes fail
+pub const SIMPROBE_VAL: f64 = 4.0;
+
+unsafe extern "C" fn osdi_simprobe(
+    _handle: *mut c_void,
+    inst: *const c_char,
+    name: *const c_char,
+    val: *mut f64,
+) -> i32 {
+    if osdi_str(inst as *mut c_char) == "sensor" && osdi_str(name as *mut c_char) == "v" {
+        *val = SIMPROBE_VAL;
+        0
+    } else {
+        1
+    }
+}
+
 unsafe extern "C" fn osdi_pnjlim(
     init: bool,
     check: *mut bool,
@@ -550,5 +672,6 @@ bitflags! {
         const EVAL_RET_FLAG_FATAL = EVAL_RET_FLAG_FATAL;
         const EVAL_RET_FLAG_FINISH = EVAL_RET_FLAG_FINISH;
         const EVAL_RET_FLAG_STOP = EVAL_RET_FLAG_STOP;
+        const EVAL_RET_FLAG_DISCONTINUITY = EVAL_RET_FLAG_DISCONTINUITY;
     }
 }
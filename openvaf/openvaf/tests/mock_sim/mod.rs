@@ -25,6 +25,7 @@ pub struct MockSimulation {
     pub state_1: Vec<f64>,
     pub state_2: Vec<f64>,
     pub noise_dense: Vec<f64>,
+    pub abstime: f64,
 }
 impl MockSimulation {
     fn new() -> MockSimulation {
@@ -47,6 +48,7 @@ impl MockSimulation {
             state_1: Vec::new(),
             state_2: Vec::new(),
             noise_dense: Vec::new(),
+            abstime: 0.0,
         }
     }
 
@@ -94,6 +96,71 @@ impl MockSimulation {
         unsafe { (self.jacobian_resist[i].get().read(), self.jacobian_react[i].get().read()) }
     }
 
+    /// Solves the small signal equations `(G + jωC) x = 0` for a unit excitation at `input`.
+    /// The voltages of `input` and the `fixed` nodes are not solved for.
+    /// Returns the complex voltage at `output`.
+    pub fn ac_transfer(&self, fixed: &[&str], input: &str, output: &str, omega: f64) -> (f64, f64) {
+        let input = self.nodes.get_index_of(input).unwrap() as u32;
+        let unknowns: Vec<u32> = (1..self.nodes.len() as u32)
+            .filter(|&node| node != input && !fixed.contains(&self.nodes[node as usize]))
+            .collect();
+        let n = unknowns.len();
+
+        // the augmented matrix [G + jωC | -(G + jωC)[:, input]]
+        let mut mat = vec![vec![(0.0, 0.0); n + 1]; n];
+        for (i, &(hi, lo)) in self.jacobian_info.iter().enumerate() {
+            let Some(row) = unknowns.iter().position(|&node| node == hi) else { continue };
+            let (resist, react) = unsafe {
+                (self.jacobian_resist[i].get().read(), self.jacobian_react[i].get().read())
+            };
+            if lo == input {
+                mat[row][n] = (-resist, -omega * react);
+            } else if let Some(col) = unknowns.iter().position(|&node| node == lo) {
+                mat[row][col] = (resist, omega * react);
+            }
+        }
+
+        let mul = |(a_re, a_im): (f64, f64), (b_re, b_im): (f64, f64)| {
+            (a_re * b_re - a_im * b_im, a_re * b_im + a_im * b_re)
+        };
+        let div = |a: (f64, f64), (b_re, b_im): (f64, f64)| {
+            let abs_sq = b_re * b_re + b_im * b_im;
+            let (re, im) = mul(a, (b_re, -b_im));
+            (re / abs_sq, im / abs_sq)
+        };
+        let abs_sq = |(re, im): (f64, f64)| re * re + im * im;
+
+        // gaussian elimination with partial pivoting
+        for k in 0..n {
+            let pivot = (k..n).max_by(|&a, &b| abs_sq(mat[a][k]).total_cmp(&abs_sq(mat[b][k])));
+            mat.swap(k, pivot.unwrap());
+            let (pivot_rows, rows) = mat.split_at_mut(k + 1);
+            let pivot_row = &pivot_rows[k];
+            for row in rows {
+                let factor = div(row[k], pivot_row[k]);
+                for (dst, &val) in zip(&mut row[k..], &pivot_row[k..]) {
+                    let (re, im) = mul(factor, val);
+                    dst.0 -= re;
+                    dst.1 -= im;
+                }
+            }
+        }
+
+        let mut solution = vec![(0.0, 0.0); n];
+        for k in (0..n).rev() {
+            let mut rhs = mat[k][n];
+            for col in k + 1..n {
+                let (re, im) = mul(mat[k][col], solution[col]);
+                rhs.0 -= re;
+                rhs.1 -= im;
+            }
+            solution[k] = div(rhs, mat[k][k]);
+        }
+
+        let output = self.nodes.get_index_of(output).unwrap() as u32;
+        solution[unknowns.iter().position(|&node| node == output).unwrap()]
+    }
+
     fn build_jacobian(&mut self) {
         self.jacobian_resist =
             (0..self.jacobian_info.len()).map(|_| UnsafeCell::new(0.0)).collect::<Vec<_>>().leak();
@@ -175,6 +242,9 @@ impl OsdiInstance {
                 }
             }
         }
+        for (i, state_idx) in self.state_idx().iter().enumerate() {
+            state_idx.set(i as u32)
+        }
         sim.state_1.resize(self.descriptor.num_states as usize, 0.0);
         sim.state_2.resize(self.descriptor.num_states as usize, 0.0);
         sim.noise_dense.resize(self.descriptor.num_noise_src as usize, 0.0);
@@ -240,7 +310,7 @@ impl OsdiInstance {
         };
         let mut sim_info = OsdiSimInfo {
             paras: sim_params,
-            abstime: 0.0,
+            abstime: sim.abstime,
             prev_solve: sim.solve.as_ptr() as *mut f64,
             prev_state: sim.state_1.as_mut_ptr(),
             next_state: sim.state_2.as_mut_ptr(),
@@ -50,6 +50,7 @@
 #define EVAL_RET_FLAG_FATAL 2
 #define EVAL_RET_FLAG_FINISH 4
 #define EVAL_RET_FLAG_STOP 8
+#define EVAL_RET_FLAG_DISCONTINUITY 16
 
 
 #define LOG_LVL_MASK 7
@@ -196,3 +197,12 @@ typedef struct OsdiDescriptor {
 
 
 
+
+// Extension: OpenVAF additionally exports
+//
+//   void (*OSDI_FREE_INSTANCE[])(void *handle, void *inst);
+//
+// with one entry for each entry in OSDI_DESCRIPTORS. It releases the resources
+// acquired by an instance (the history of absdelay and the files opened with
+// $fopen) and must be called before the memory of the instance is freed.
+// Pending $fstrobe messages are written (and logged with handle) beforehand.
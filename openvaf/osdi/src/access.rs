@@ -79,8 +79,12 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             }
 
             LLVMPositionBuilderAtEnd(llbuilder, model_bb);
-            let switch_model =
-                LLVMBuildSwitch(llbuilder, param_id, opvar_bb, model_data.params.len() as u32);
+            let switch_model = LLVMBuildSwitch(
+                llbuilder,
+                param_id,
+                opvar_bb,
+                model_data.num_exposed_params() as u32,
+            );
 
             // inst param model default values
             for param_idx in 0..inst_data.params.len() {
@@ -105,8 +109,8 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                 LLVMBuildRet(llbuilder, ptr);
             }
 
-            // model params
-            for param_idx in 0..model_data.params.len() {
+            // model params (hidden paramset parameters are not accessible)
+            for param_idx in 0..model_data.num_exposed_params() {
                 let bb = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
                 LLVMPositionBuilderAtEnd(llbuilder, bb);
                 let case = cx.const_unsigned_int((inst_data.params.len() + param_idx) as u32);
@@ -139,7 +143,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                 let bb = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
                 LLVMPositionBuilderAtEnd(llbuilder, bb);
                 let case = cx.const_unsigned_int(
-                    (model_data.params.len() + inst_data.params.len() + opvar_idx) as u32,
+                    (model_data.num_exposed_params() + inst_data.params.len() + opvar_idx) as u32,
                 );
                 LLVMAddCase(switch_opvar, case, bb);
                 let (ptr, _) = self.nth_opvar_ptr(opvar_idx as u32, inst, model, llbuilder);
@@ -1,6 +1,6 @@
-use hir::CompilationDB;
+use hir::{CompilationDB, Type};
 use hir_lower::fmt::{DisplayKind, FmtArg, FmtArgKind};
-use hir_lower::{CallBackKind, HirInterner};
+use hir_lower::{CallBackKind, HirInterner, RandomKind};
 use lasso::Rodeo;
 use llvm::Linkage;
 use llvm::{
@@ -122,7 +122,11 @@ impl<'a> OsdiModule<'a> {
         module: &'a CompiledModule,
         lim_table: &'a TiSet<OsdiLimId, OsdiLimFunction>,
     ) -> Self {
-        let sym = base_n::encode(module.info.module.uuid(db) as u128, base_n::CASE_INSENSITIVE);
+        let mut sym = base_n::encode(module.info.module.uuid(db) as u128, base_n::CASE_INSENSITIVE);
+        if let Some(paramset) = module.info.paramset {
+            sym.push('_');
+            sym.push_str(&base_n::encode(paramset.uuid(db) as u128, base_n::CASE_INSENSITIVE));
+        }
         let CompiledModule {
             info,
             dae_system,
@@ -210,7 +214,119 @@ pub fn general_callbacks<'ll>(
                         num_state: 0,
                     }
                 }
+                CallBackKind::SimProbe => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("simprobe")
+                        .expect("stdlib function simprobe is missing");
+                    let fun_ty = builder
+                        .cx
+                        .ty_func(&[ptr_ty, ptr_ty, ptr_ty, ptr_ty], builder.cx.ty_double());
+                    CallbackFun {
+                        fun_ty,
+                        fun,
+                        state: vec![handle, ret_flags].into_boxed_slice(),
+                        num_state: 0,
+                    }
+                }
+                CallBackKind::SimProbeOpt => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("simprobe_opt")
+                        .expect("stdlib function simprobe_opt is missing");
+                    let fun_ty = builder.cx.ty_func(
+                        &[ptr_ty, ptr_ty, ptr_ty, builder.cx.ty_double()],
+                        builder.cx.ty_double(),
+                    );
+                    CallbackFun {
+                        fun_ty,
+                        fun,
+                        state: vec![handle].into_boxed_slice(),
+                        num_state: 0,
+                    }
+                }
+                CallBackKind::TestPlusArgs => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("test_plusargs")
+                        .expect("stdlib function test_plusargs is missing");
+                    let fun_ty = builder.cx.ty_func(&[ptr_ty, ptr_ty], builder.cx.ty_int());
+                    CallbackFun {
+                        fun_ty,
+                        fun,
+                        state: vec![handle].into_boxed_slice(),
+                        num_state: 0,
+                    }
+                }
+                CallBackKind::ValuePlusArgs(ty) => {
+                    let name = format!("value_plusargs_{}", ty);
+                    let fun = builder
+                        .cx
+                        .get_func_by_name(&name)
+                        .unwrap_or_else(|| panic!("stdlib function {name} is missing"));
+                    let val_ty = match ty {
+                        Type::Real => builder.cx.ty_double(),
+                        Type::Integer => builder.cx.ty_int(),
+                        Type::String => ptr_ty,
+                        _ => unreachable!("invalid $value$plusargs type {ty}"),
+                    };
+                    let fun_ty = builder.cx.ty_func(&[ptr_ty, ptr_ty, val_ty], val_ty);
+                    CallbackFun {
+                        fun_ty,
+                        fun,
+                        state: vec![handle].into_boxed_slice(),
+                        num_state: 0,
+                    }
+                }
+                CallBackKind::ParsePlusArgs(ty) => {
+                    let name = format!("parse_plusargs_{}", ty);
+                    let fun = builder
+                        .cx
+                        .get_func_by_name(&name)
+                        .unwrap_or_else(|| panic!("stdlib function {name} is missing"));
+                    let fun_ty = builder.cx.ty_func(&[ptr_ty, ptr_ty], builder.cx.ty_int());
+                    CallbackFun {
+                        fun_ty,
+                        fun,
+                        state: vec![handle].into_boxed_slice(),
+                        num_state: 0,
+                    }
+                }
                 // If these derivative were non zero they would have been removed
+                CallBackKind::TableLookup(breakpoints) => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("osdi_table_lookup")
+                        .expect("stdlib function osdi_table_lookup is missing");
+                    let fun_ty = builder.cx.ty_func(
+                        &[ptr_ty, builder.cx.ty_int(), builder.cx.ty_double()],
+                        builder.cx.ty_int(),
+                    );
+                    let breakpoints: Vec<_> = breakpoints
+                        .iter()
+                        .map(|&val| builder.cx.const_real(val.into()))
+                        .collect();
+                    let len = builder.cx.const_unsigned_int(breakpoints.len() as u32);
+                    let breakpoints = builder.cx.const_arr_ptr(builder.cx.ty_double(), &breakpoints);
+                    CallbackFun {
+                        fun_ty,
+                        fun,
+                        state: vec![breakpoints, len].into_boxed_slice(),
+                        num_state: 0,
+                    }
+                }
+                CallBackKind::TableValue(vals) => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("osdi_table_value")
+                        .expect("stdlib function osdi_table_value is missing");
+                    let fun_ty =
+                        builder.cx.ty_func(&[ptr_ty, builder.cx.ty_int()], builder.cx.ty_double());
+                    let vals: Vec<_> =
+                        vals.iter().map(|&val| builder.cx.const_real(val.into())).collect();
+                    let vals = builder.cx.const_arr_ptr(builder.cx.ty_double(), &vals);
+                    CallbackFun { fun_ty, fun, state: vec![vals].into_boxed_slice(), num_state: 0 }
+                }
                 CallBackKind::Derivative(_) | CallBackKind::NodeDerivative(_) => {
                     let zero = builder.cx.const_real(0.0);
                     builder.cx.const_callback(&[builder.cx.ty_double()], zero)
@@ -219,12 +335,26 @@ pub fn general_callbacks<'ll>(
                 | CallBackKind::CollapseHint(_, _)
                 | CallBackKind::BuiltinLimit { .. }
                 | CallBackKind::StoreLimit(_)
+                | CallBackKind::StoreOperatorState(_)
                 | CallBackKind::LimDiscontinuity
+                | CallBackKind::Discontinuity
                 | CallBackKind::Analysis
-                | CallBackKind::NoiseTable(_)
+                | CallBackKind::NoiseTable { .. }
                 | CallBackKind::WhiteNoise { .. }
                 | CallBackKind::FlickerNoise { .. }
-                | CallBackKind::TimeDerivative => return None,
+                | CallBackKind::TimeDerivative
+                | CallBackKind::LaplaceFilter { .. }
+                | CallBackKind::Absdelay(_)
+                | CallBackKind::FileOpen
+                | CallBackKind::FileClose
+                | CallBackKind::FileFlush
+                | CallBackKind::Print {
+                    kind: DisplayKind::File | DisplayKind::FileStrobe { .. },
+                    ..
+                }
+                // the seeds are stored in the instance/model data
+                | CallBackKind::Random { .. }
+                | CallBackKind::ReadRandomSeed(_) => return None,
 
                 CallBackKind::Print { kind, arg_tys } => {
                     let (fun, fun_ty) = print_callback(builder.cx, *kind, arg_tys);
@@ -236,12 +366,65 @@ pub fn general_callbacks<'ll>(
         .collect()
 }
 
-fn print_callback<'ll>(
+/// Creates the callback of a random number generator. `seed` points to the seed of the call.
+/// `instance_id` points to the id that is mixed into the seed of random numbers with the
+/// `"instance"` type string. It is `None` outside of instances, where all random numbers are
+/// global.
+pub fn random_callback<'ll>(
+    cx: &CodegenCx<'_, 'll>,
+    call: &CallBackKind,
+    seed: &'ll llvm::Value,
+    instance_id: Option<&'ll llvm::Value>,
+    handle: &'ll llvm::Value,
+) -> CallbackFun<'ll> {
+    let ptr_ty = cx.ty_ptr();
+    let int_ty = cx.ty_int();
+    let instance_id = |global: bool| match instance_id {
+        Some(id) if !global => id,
+        _ => cx.const_null_ptr(),
+    };
+    let (name, args, ret, state) = match *call {
+        CallBackKind::Random { kind: RandomKind::Random, .. } => {
+            ("osdi_random", vec![ptr_ty, int_ty], int_ty, vec![seed])
+        }
+        CallBackKind::Random { kind: RandomKind::Arandom { global }, .. } => (
+            "osdi_arandom_next",
+            vec![ptr_ty, ptr_ty, ptr_ty, int_ty, int_ty],
+            int_ty,
+            vec![handle, seed, instance_id(global), cx.const_unsigned_int(global as u32)],
+        ),
+        CallBackKind::Random { kind: RandomKind::Dist { dist, real, global }, .. } => {
+            // must match the order of OsdiDistribution in stdlib.c
+            let state = vec![seed, instance_id(global), cx.const_unsigned_int(dist as u32)];
+            let ty = if real { cx.ty_double() } else { int_ty };
+            let name = if real { "osdi_rdist" } else { "osdi_dist" };
+            (name, vec![ptr_ty, ptr_ty, int_ty, int_ty, ty, ty], ty, state)
+        }
+        CallBackKind::ReadRandomSeed(_) => ("osdi_read_seed", vec![ptr_ty], int_ty, vec![seed]),
+        _ => unreachable!("{call:?} is not a random number generator"),
+    };
+    let fun =
+        cx.get_func_by_name(name).unwrap_or_else(|| panic!("stdlib function {name} is missing"));
+    let fun_ty = cx.ty_func(&args, ret);
+    CallbackFun { fun_ty, fun, state: state.into_boxed_slice(), num_state: 0 }
+}
+
+pub fn print_callback<'ll>(
     cx: &CodegenCx<'_, 'll>,
     kind: hir_lower::fmt::DisplayKind,
     arg_tys: &[FmtArg],
 ) -> (&'ll llvm::Value, &'ll llvm::Type) {
-    let mut args = vec![cx.ty_ptr(), cx.ty_ptr()];
+    // file output additionally receives the file handles of the instance and the descriptor,
+    // strobes also receive the sim info to determine the current timestep
+    let is_file = kind.is_file();
+    let mut args = match kind {
+        DisplayKind::File => vec![cx.ty_ptr(), cx.ty_ptr(), cx.ty_int(), cx.ty_ptr()],
+        DisplayKind::FileStrobe { .. } => {
+            vec![cx.ty_ptr(), cx.ty_ptr(), cx.ty_ptr(), cx.ty_int(), cx.ty_ptr()]
+        }
+        _ => vec![cx.ty_ptr(), cx.ty_ptr()],
+    };
+    let fmt_pos = args.len() as u32 - 1;
     args.extend(arg_tys.iter().map(|arg| lltype(&arg.ty, cx)));
     let fun_ty = cx.ty_func(&args, cx.ty_void());
     let name = cx.local_callback_name();
@@ -256,8 +439,9 @@ fn print_callback<'ll>(
 
         LLVMPositionBuilderAtEnd(llbuilder, entry_bb);
         let handle = LLVMGetParam(fun, 0);
-        let fmt_lit = LLVMGetParam(fun, 1);
-        let mut args = vec![cx.const_null_ptr(), cx.const_usize(0), LLVMGetParam(fun, 1)];
+        let fmt_lit = LLVMGetParam(fun, fmt_pos);
+        let file = is_file.then(|| (LLVMGetParam(fun, 1), LLVMGetParam(fun, fmt_pos - 1)));
+        let mut args = vec![cx.const_null_ptr(), cx.const_usize(0), fmt_lit];
 
         let exp_table = cx.get_declared_value("EXP").expect("constant EXP missing from stdlib");
         let exp_table_ty = cx.ty_array(cx.ty_double(), 11);
@@ -272,7 +456,7 @@ fn print_callback<'ll>(
         let mut free = Vec::new();
 
         for (i, arg) in arg_tys.iter().enumerate() {
-            let val = LLVMGetParam(fun, i as u32 + 2);
+            let val = LLVMGetParam(fun, i as u32 + fmt_pos + 1);
             match arg.kind {
                 FmtArgKind::Binary => {
                     let formatted_str = LLVMBuildCall2(
@@ -318,7 +502,9 @@ fn print_callback<'ll>(
                 FmtArgKind::Other => args.push(val),
             }
         }
-        args.extend((1..(2 + arg_tys.len())).map(|arg| LLVMGetParam(fun, arg as u32)));
+        args.extend(
+            (fmt_pos..(fmt_pos + 1 + arg_tys.len() as u32)).map(|arg| LLVMGetParam(fun, arg)),
+        );
         let (fun_ty, fun) = cx.intrinsic("snprintf").unwrap();
         let len = LLVMBuildCall2(llbuilder, fun_ty, fun, args.as_ptr(), args.len() as u32, UNNAMED);
         let is_err = LLVMBuildICmp(llbuilder, IntPredicate::IntSLT, len, cx.const_int(0), UNNAMED);
@@ -349,7 +535,10 @@ fn print_callback<'ll>(
         let flags = LLVMBuildPhi(llbuilder, cx.ty_int(), UNNAMED);
         let lvl = match kind {
             DisplayKind::Debug => LOG_LVL_DEBUG,
-            DisplayKind::Display | DisplayKind::Monitor => LOG_LVL_DISPLAY,
+            DisplayKind::Display
+            | DisplayKind::Monitor
+            | DisplayKind::File
+            | DisplayKind::FileStrobe { .. } => LOG_LVL_DISPLAY,
             DisplayKind::Info => LOG_LVL_INFO,
             DisplayKind::Warn => LOG_LVL_WARN,
             DisplayKind::Error => LOG_LVL_ERR,
@@ -361,10 +550,44 @@ fn print_callback<'ll>(
         LLVMAddIncoming(flags, [lvl, lvl_and_err].as_ptr(), [write_bb, err_bb].as_ptr(), 2);
         let msg = LLVMBuildPhi(llbuilder, cx.ty_ptr(), UNNAMED);
         LLVMAddIncoming(msg, [ptr, fmt_lit].as_ptr(), [write_bb, err_bb].as_ptr(), 2);
-        let fun_ptr = cx.get_declared_value("osdi_log").expect("symbol osdi_log is missing");
-        let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_ptr(), cx.ty_int()], cx.ty_void());
-        let fun = LLVMBuildLoad2(llbuilder, cx.ty_ptr(), fun_ptr, UNNAMED);
-        LLVMBuildCall2(llbuilder, fun_ty, fun, [handle, msg, flags].as_ptr(), 3, UNNAMED);
+        if let (Some((files, fd)), DisplayKind::FileStrobe { slot, monitor }) = (file, kind) {
+            let sim_info = LLVMGetParam(fun, 2);
+            let fun = cx
+                .get_func_by_name("osdi_strobe_file")
+                .expect("stdlib function osdi_strobe_file is missing");
+            let fun_ty = cx.ty_func(
+                &[
+                    cx.ty_ptr(),
+                    cx.ty_ptr(),
+                    cx.ty_ptr(),
+                    cx.ty_int(),
+                    cx.ty_int(),
+                    cx.ty_int(),
+                    cx.ty_ptr(),
+                    cx.ty_int(),
+                ],
+                cx.ty_void(),
+            );
+            let slot = cx.const_unsigned_int(slot);
+            let monitor = cx.const_unsigned_int(monitor as u32);
+            let args = [handle, files, sim_info, slot, monitor, fd, msg, flags];
+            LLVMBuildCall2(llbuilder, fun_ty, fun, args.as_ptr(), args.len() as u32, UNNAMED);
+        } else if let Some((files, fd)) = file {
+            let fun = cx
+                .get_func_by_name("osdi_write_file")
+                .expect("stdlib function osdi_write_file is missing");
+            let fun_ty = cx.ty_func(
+                &[cx.ty_ptr(), cx.ty_ptr(), cx.ty_int(), cx.ty_ptr(), cx.ty_int()],
+                cx.ty_void(),
+            );
+            let args = [handle, files, fd, msg, flags];
+            LLVMBuildCall2(llbuilder, fun_ty, fun, args.as_ptr(), 5, UNNAMED);
+        } else {
+            let fun_ptr = cx.get_declared_value("osdi_log").expect("symbol osdi_log is missing");
+            let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_ptr(), cx.ty_int()], cx.ty_void());
+            let fun = LLVMBuildLoad2(llbuilder, cx.ty_ptr(), fun_ptr, UNNAMED);
+            LLVMBuildCall2(llbuilder, fun_ty, fun, [handle, msg, flags].as_ptr(), 3, UNNAMED);
+        }
         llvm::LLVMBuildRetVoid(llbuilder);
         llvm::LLVMDisposeBuilder(llbuilder);
     }
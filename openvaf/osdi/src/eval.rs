@@ -1,4 +1,5 @@
-use hir_lower::{CallBackKind, CurrentKind, LimitState, ParamKind};
+use hir_lower::fmt::DisplayKind;
+use hir_lower::{CallBackKind, CurrentKind, LimitState, OperatorState, ParamKind};
 use llvm::IntPredicate::{IntNE, IntULT};
 use llvm::{
     LLVMAppendBasicBlockInContext, LLVMBuildAlloca, LLVMBuildAnd, LLVMBuildBr, LLVMBuildCall2,
@@ -12,15 +13,17 @@ use sim_back::SimUnknownKind;
 use typed_index_collections::TiVec;
 
 use crate::bitfield::{is_flag_set, is_flag_set_mem, is_flag_unset};
-use crate::compilation_unit::{general_callbacks, OsdiCompilationUnit};
+use crate::compilation_unit::{
+    general_callbacks, print_callback, random_callback, OsdiCompilationUnit,
+};
 use crate::inst_data::OsdiInstanceParam;
 use crate::metadata::osdi_0_3::{
-    ANALYSIS_IC, CALC_NOISE, CALC_OP, CALC_REACT_JACOBIAN, CALC_REACT_LIM_RHS, CALC_REACT_RESIDUAL,
-    CALC_RESIST_JACOBIAN, CALC_RESIST_LIM_RHS, CALC_RESIST_RESIDUAL, ENABLE_LIM, EVAL_RET_FLAG_LIM,
-    INIT_LIM,
+    ANALYSIS_IC, ANALYSIS_TRAN, CALC_NOISE, CALC_OP, CALC_REACT_JACOBIAN, CALC_REACT_LIM_RHS,
+    CALC_REACT_RESIDUAL, CALC_RESIST_JACOBIAN, CALC_RESIST_LIM_RHS, CALC_RESIST_RESIDUAL,
+    ENABLE_LIM, EVAL_RET_FLAG_LIM, INIT_LIM,
 };
 use crate::metadata::OsdiLimFunction;
-use crate::OsdiLimId;
+use crate::{lltype, OsdiLimId};
 
 impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
     pub fn eval_prototype(&self) -> &'ll llvm::Value {
@@ -92,10 +95,27 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             }
         };
 
-        let state_idx: TiVec<LimitState, _> = (0..intern.lim_state.len())
-            .map(|i| unsafe { inst_data.read_state_idx(cx, i.into(), instance, builder.llbuilder) })
+        let state_idx: TiVec<LimitState, _> = (0..intern.lim_state.len() as u32)
+            .map(|i| unsafe { inst_data.read_state_idx(cx, i, instance, builder.llbuilder) })
+            .collect();
+
+        // operator states are placed behind the limit states
+        let num_lim_states = intern.lim_state.len() as u32;
+        let operator_state_idx: TiVec<OperatorState, _> = (0..intern.operator_states.len() as u32)
+            .map(|i| unsafe {
+                inst_data.read_state_idx(cx, num_lim_states + i, instance, builder.llbuilder)
+            })
             .collect();
 
+        let abstime = unsafe {
+            MemLoc::struct_gep(sim_info, sim_info_ty, cx.ty_double(), ABSTIME_OFFSET, cx)
+                .read(builder.llbuilder)
+        };
+        let (hidden_state_stored, hidden_state) = unsafe {
+            let is_tran = is_flag_set_mem(cx, ANALYSIS_TRAN, &flags, builder.llbuilder);
+            self.restore_hidden_state(instance, model, abstime, is_tran, builder.llbuilder)
+        };
+
         let true_ = cx.const_bool(true);
         let mut params: TiVec<_, _> = intern
             .params
@@ -114,6 +134,13 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                                 .unwrap_or_else(|| model_data.param_loc(cx, param, model).unwrap())
                                 .into()
                         }
+                        ParamKind::ParamElement { param, idx } => {
+                            let loc = inst_data
+                                .param_loc(cx, OsdiInstanceParam::User(param), instance)
+                                .unwrap_or_else(|| model_data.param_loc(cx, param, model).unwrap());
+                            let ty = lltype(param.ty(self.db).base_type(), cx);
+                            return loc.array_element(idx, ty, cx).into();
+                        }
                         ParamKind::Voltage { hi, lo } => {
                             let hi = get_prev_solve(SimUnknownKind::KirchoffLaw(hi));
                             if let Some(lo) = lo {
@@ -182,7 +209,10 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                                 builder.llbuilder,
                             )
                             .unwrap(),
-                        ParamKind::HiddenState(_) => unreachable!(), // TODO  hidden state
+                        ParamKind::HiddenState(_) | ParamKind::HiddenStateElement { .. } => {
+                            hidden_state[inst_data.hidden_state_vars.get_index_of(kind).unwrap()]
+                        }
+                        ParamKind::HiddenStateInitialized => hidden_state_stored,
                         ParamKind::EnableIntegration => {
                             let flags = flags.read(builder.llbuilder);
                             let is_not_dc =
@@ -191,26 +221,33 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                                 is_flag_unset(cx, ANALYSIS_IC, flags, builder.llbuilder);
                             LLVMBuildAnd(builder.llbuilder, is_not_dc, is_not_ic, UNNAMED)
                         }
+                        ParamKind::TransientAnalysis => {
+                            is_flag_set_mem(cx, ANALYSIS_TRAN, &flags, builder.llbuilder)
+                        }
                         ParamKind::PrevState(state) => {
-                            let idx =
-                                inst_data.read_state_idx(cx, state, instance, builder.llbuilder);
                             return MemLoc {
                                 ptr: prev_state,
                                 ptr_ty: cx.ty_double(),
                                 ty: cx.ty_double(),
-                                indices: vec![idx].into_boxed_slice(),
+                                indices: vec![state_idx[state]].into_boxed_slice(),
                             }
                             .into();
                         }
                         ParamKind::NewState(state) => {
-                            let idx =
-                                inst_data.read_state_idx(cx, state, instance, builder.llbuilder);
-
                             return MemLoc {
                                 ptr: next_state,
                                 ptr_ty: cx.ty_double(),
                                 ty: cx.ty_double(),
-                                indices: vec![idx].into_boxed_slice(),
+                                indices: vec![state_idx[state]].into_boxed_slice(),
+                            }
+                            .into();
+                        }
+                        ParamKind::PrevOperatorState(state) => {
+                            return MemLoc {
+                                ptr: prev_state,
+                                ptr_ty: cx.ty_double(),
+                                ty: cx.ty_double(),
+                                indices: vec![operator_state_idx[state]].into_boxed_slice(),
                             }
                             .into();
                         }
@@ -256,6 +293,20 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                         num_state: 0,
                     }
                 }
+                CallBackKind::StoreOperatorState(state) => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("store_lim")
+                        .expect("stdlib function store_lim is missing");
+                    let fun_ty =
+                        cx.ty_func(&[cx.ty_ptr(), cx.ty_int(), cx.ty_double()], cx.ty_double());
+                    CallbackFun {
+                        fun_ty,
+                        fun,
+                        state: Box::new([sim_info, operator_state_idx[state]]),
+                        num_state: 0,
+                    }
+                }
                 CallBackKind::LimDiscontinuity => {
                     let fun = builder
                         .cx
@@ -264,6 +315,14 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                     let fun_ty = cx.ty_func(&[cx.ty_ptr()], cx.ty_void());
                     CallbackFun { fun_ty, fun, state: Box::new([ret_flags]), num_state: 0 }
                 }
+                CallBackKind::Discontinuity => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("discontinuity")
+                        .expect("stdlib function discontinuity is missing");
+                    let fun_ty = cx.ty_func(&[cx.ty_ptr()], cx.ty_void());
+                    CallbackFun { fun_ty, fun, state: Box::new([ret_flags]), num_state: 0 }
+                }
                 CallBackKind::Analysis => {
                     let fun = builder
                         .cx
@@ -272,6 +331,87 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                     let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_ptr()], cx.ty_int());
                     CallbackFun { fun_ty, fun, state: Box::new([sim_info]), num_state: 0 }
                 }
+                CallBackKind::Absdelay(line) => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("absdelay")
+                        .expect("stdlib function absdelay is missing");
+                    let fun_ty = cx.ty_func(
+                        &[
+                            cx.ty_ptr(),
+                            cx.ty_ptr(),
+                            cx.ty_ptr(),
+                            cx.ty_ptr(),
+                            cx.ty_double(),
+                            cx.ty_double(),
+                            cx.ty_double(),
+                        ],
+                        cx.ty_double(),
+                    );
+                    let buffer = unsafe {
+                        inst_data.delay_buffer_ptr(cx, line, instance, builder.llbuilder)
+                    };
+                    CallbackFun {
+                        fun_ty,
+                        fun,
+                        state: Box::new([sim_info, handle, ret_flags, buffer]),
+                        num_state: 0,
+                    }
+                }
+                CallBackKind::Print { kind: DisplayKind::File, ref arg_tys } => {
+                    let (fun, fun_ty) = print_callback(cx, DisplayKind::File, arg_tys);
+                    let files = unsafe { inst_data.files_ptr(instance, builder.llbuilder) };
+                    CallbackFun { fun_ty, fun, state: Box::new([handle, files]), num_state: 0 }
+                }
+                CallBackKind::Print {
+                    kind: kind @ DisplayKind::FileStrobe { .. },
+                    ref arg_tys,
+                } => {
+                    let (fun, fun_ty) = print_callback(cx, kind, arg_tys);
+                    let files = unsafe { inst_data.files_ptr(instance, builder.llbuilder) };
+                    CallbackFun {
+                        fun_ty,
+                        fun,
+                        state: Box::new([handle, files, sim_info]),
+                        num_state: 0,
+                    }
+                }
+                CallBackKind::FileOpen => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("osdi_fopen")
+                        .expect("stdlib function osdi_fopen is missing");
+                    let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_ptr(), cx.ty_ptr()], cx.ty_int());
+                    let files = unsafe { inst_data.files_ptr(instance, builder.llbuilder) };
+                    CallbackFun { fun_ty, fun, state: Box::new([files]), num_state: 0 }
+                }
+                CallBackKind::FileClose => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("osdi_fclose")
+                        .expect("stdlib function osdi_fclose is missing");
+                    let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_ptr(), cx.ty_int()], cx.ty_void());
+                    let files = unsafe { inst_data.files_ptr(instance, builder.llbuilder) };
+                    CallbackFun { fun_ty, fun, state: Box::new([handle, files]), num_state: 0 }
+                }
+                CallBackKind::FileFlush => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("osdi_fflush")
+                        .expect("stdlib function osdi_fflush is missing");
+                    let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_int()], cx.ty_void());
+                    let files = unsafe { inst_data.files_ptr(instance, builder.llbuilder) };
+                    CallbackFun { fun_ty, fun, state: Box::new([files]), num_state: 0 }
+                }
+                CallBackKind::Random { seed, .. } | CallBackKind::ReadRandomSeed(seed) => {
+                    let (seed, id) = unsafe {
+                        (
+                            inst_data.random_seed_ptr(cx, seed, instance, builder.llbuilder),
+                            inst_data.random_instance_id_ptr(cx, instance, builder.llbuilder),
+                        )
+                    };
+                    random_callback(cx, kind, seed, Some(id), handle)
+                }
                 _ => continue,
             };
             builder.callbacks[func] = Some(cb);
@@ -332,6 +472,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             Self::build_store_results(&builder, llfunc, &flags, CALC_NOISE, &store_noise);
 
             inst_data.store_bound_step(instance, &builder);
+            inst_data.store_hidden_state(cx, instance, abstime, &builder);
 
             let ret_flags = builder.load(cx.ty_int(), ret_flags);
             builder.ret(ret_flags);
@@ -1,11 +1,15 @@
+use std::iter::once;
+
 use ahash::RandomState;
 use hir::{CompilationDB, ParamSysFun, Parameter, Variable};
-use hir_lower::{HirInterner, LimitState, ParamKind, PlaceKind};
+use hir_lower::fmt::DisplayKind;
+use hir_lower::{CallBackKind, DelayLine, HirInterner, ParamKind, PlaceKind, RandomSeed};
 use indexmap::IndexMap;
 use llvm::{
-    IntPredicate, LLVMBuildFAdd, LLVMBuildFSub, LLVMBuildGEP2, LLVMBuildICmp, LLVMBuildIntCast2,
-    LLVMBuildLoad2, LLVMBuildStore, LLVMBuildStructGEP2, LLVMConstInt, LLVMOffsetOfElement,
-    LLVMSetFastMath, TargetData, UNNAMED,
+    IntPredicate, LLVMBuildAnd, LLVMBuildFAdd, LLVMBuildFCmp, LLVMBuildFSub, LLVMBuildGEP2,
+    LLVMBuildICmp, LLVMBuildIntCast2, LLVMBuildLoad2, LLVMBuildNot, LLVMBuildOr, LLVMBuildSelect,
+    LLVMBuildStore, LLVMBuildStructGEP2, LLVMConstInt, LLVMOffsetOfElement, LLVMSetFastMath,
+    LLVMStructGetTypeAtIndex, RealPredicate, TargetData, UNNAMED,
 };
 use mir::{strip_optbarrier, Const, Function, Param, ValueDef, F_ZERO};
 use mir_llvm::{CodegenCx, MemLoc};
@@ -25,7 +29,7 @@ pub enum OsdiInstanceParam {
     User(Parameter),
 }
 
-pub const NUM_CONST_FIELDS: u32 = 8;
+pub const NUM_CONST_FIELDS: u32 = 12;
 pub const PARAM_GIVEN: u32 = 0;
 pub const JACOBIAN_PTR_RESIST: u32 = 1;
 pub const JACOBIAN_PTR_REACT: u32 = 2;
@@ -34,6 +38,20 @@ pub const COLLAPSED: u32 = 4;
 pub const TEMPERATURE: u32 = 5;
 pub const CONNECTED: u32 = 6;
 pub const STATE_IDX: u32 = 7;
+pub const DELAY_BUFFERS: u32 = 8;
+pub const FILES: u32 = 9;
+pub const RANDOM_SEEDS: u32 = 10;
+pub const HIDDEN_STATE: u32 = 11;
+
+/// Fields of the hidden state struct: the time of the last evaluation, whether any hidden state
+/// was stored yet and the committed value of each hidden state variable
+const HIDDEN_STATE_TIME: u32 = 0;
+const HIDDEN_STATE_STORED: u32 = 1;
+const HIDDEN_STATE_VALS: u32 = 2;
+
+/// Number of multi channel descriptors and file descriptors that can be open at the same time
+/// (per instance). Must match `OSDI_NUM_FILES` in `stdlib.c`
+pub const NUM_FILES: u32 = 32;
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum EvalOutput {
@@ -44,8 +62,6 @@ pub enum EvalOutput {
 }
 
 impl EvalOutput {
-    const NONE: EvalOutput = EvalOutput::Cache(CacheSlot(u32::MAX));
-
     fn new<'ll>(
         module: &OsdiModule<'_>,
         val: mir::Value,
@@ -160,7 +176,7 @@ impl MatrixEntry {
 pub struct NoiseSource {
     pub factor: EvalOutput,
     /// content of values depend on kind of noise source
-    pub args: [EvalOutput; 2],
+    pub args: Box<[EvalOutput]>,
 }
 
 impl NoiseSource {
@@ -174,16 +190,20 @@ impl NoiseSource {
             val = strip_optbarrier(module.eval, val);
             EvalOutput::new(module, val, slots, false, ty_real)
         };
-        let args = match source.kind {
-            dae::NoiseSourceKind::WhiteNoise { pwr } => [get_output(pwr), EvalOutput::NONE],
-            dae::NoiseSourceKind::FlickerNoise { pwr, exp } => [get_output(pwr), get_output(exp)],
-            dae::NoiseSourceKind::NoiseTable { .. } => [EvalOutput::NONE; 2],
+        let args: Box<[_]> = match source.kind {
+            dae::NoiseSourceKind::WhiteNoise { pwr } => Box::new([get_output(pwr)]),
+            dae::NoiseSourceKind::FlickerNoise { pwr, exp } => {
+                Box::new([get_output(pwr), get_output(exp)])
+            }
+            dae::NoiseSourceKind::NoiseTable { ref vals, .. } => {
+                vals.iter().map(|&val| get_output(val)).collect()
+            }
         };
         NoiseSource { args, factor: get_output(source.factor) }
     }
 
-    pub fn eval_outputs(&self) -> [EvalOutput; 3] {
-        [self.factor, self.args[0], self.args[1]]
+    pub fn eval_outputs(&self) -> impl Iterator<Item = EvalOutput> + '_ {
+        once(self.factor).chain(self.args.iter().copied())
     }
 }
 
@@ -198,6 +218,13 @@ pub struct OsdiInstanceData<'ll> {
     pub node_mapping: &'ll llvm::Type,
     pub state_idx: &'ll llvm::Type,
     pub collapsed: &'ll llvm::Type,
+    pub delay_buffers: &'ll llvm::Type,
+    /// `None` if the module does not use file io
+    pub files: Option<&'ll llvm::Type>,
+    pub random_seeds: &'ll llvm::Type,
+    /// index of the instance id in `random_seeds`
+    pub random_instance_id: u32,
+    pub hidden_state: &'ll llvm::Type,
 
     // llvm types for dynamic instance data struct fields
     pub params: IndexMap<OsdiInstanceParam, &'ll llvm::Type, RandomState>,
@@ -207,6 +234,8 @@ pub struct OsdiInstanceData<'ll> {
     pub residual: TiVec<SimUnknown, Residual>,
     pub noise: Vec<NoiseSource>,
     pub opvars: IndexMap<Variable, EvalOutput, RandomState>,
+    /// The value of each hidden state variable (element) at the end of the last evaluation
+    pub hidden_state_vars: IndexMap<ParamKind, EvalOutput, RandomState>,
     pub jacobian: TiVec<MatrixEntryId, MatrixEntry>,
     pub bound_step: Option<EvalOutputSlot>,
 }
@@ -247,6 +276,20 @@ impl<'ll> OsdiInstanceData<'ll> {
                 (*var, pos)
             })
             .collect();
+        let mut hidden_state_tys = Vec::new();
+        let hidden_state_vars: IndexMap<_, _, _> = module
+            .info
+            .hidden_state
+            .iter()
+            .flat_map(|&var| PlaceKind::var_places(db, var))
+            .map(|place| {
+                let val = module.intern.outputs[&place].unwrap_unchecked();
+                let ty = lltype(&place.ty(db), cx);
+                hidden_state_tys.push(ty);
+                let pos = EvalOutput::new(module, val, &mut eval_outputs, false, ty);
+                (place.hidden_state().unwrap(), pos)
+            })
+            .collect();
         let residual = module
             .dae_system
             .residual
@@ -284,7 +327,48 @@ impl<'ll> OsdiInstanceData<'ll> {
         let cache_slots: TiVec<_, _> =
             module.init.cache_slots.raw.values().map(|ty| lltype(ty, cx)).collect();
 
-        let state_idx = cx.ty_array(cx.ty_int(), module.intern.lim_state.len() as u32);
+        let num_states = module.intern.lim_state.len() + module.intern.operator_states.len();
+        let state_idx = cx.ty_array(cx.ty_int(), num_states as u32);
+
+        // ring buffer of (time, value) pairs allocated by `absdelay` in `stdlib.c`:
+        // start, len, capacity, times, values
+        let delay_buffer =
+            cx.ty_struct("osdi_delay_buffer", &[ty_u32, ty_u32, ty_u32, cx.ty_ptr(), cx.ty_ptr()]);
+        let delay_buffers = cx.ty_array(delay_buffer, module.intern.num_delay_lines);
+
+        // file handles of multi channel descriptors and file descriptors followed by the pending
+        // messages of strobes, see `OsdiFiles` in `stdlib.c`
+        let uses_files = module.intern.callbacks.iter().any(|cb| {
+            matches!(
+                cb,
+                CallBackKind::FileOpen
+                    | CallBackKind::FileClose
+                    | CallBackKind::FileFlush
+                    | CallBackKind::Print {
+                        kind: DisplayKind::File | DisplayKind::FileStrobe { .. },
+                        ..
+                    }
+            )
+        });
+        let files = uses_files.then(|| {
+            let file_table = cx.ty_array(cx.ty_ptr(), NUM_FILES);
+            let strobe =
+                cx.ty_struct("osdi_strobe", &[cx.ty_ptr(), cx.ty_ptr(), ty_f64, ty_u32, ty_u32]);
+            let strobes = cx.ty_array(strobe, module.intern.num_strobes);
+            let fields = [file_table, file_table, ty_u32, ty_u32, strobes];
+            cx.ty_struct(&format!("osdi_files_{}", &module.sym), &fields)
+        });
+        // the element after the seeds stores the id of the instance that is mixed into the seeds
+        // of random numbers with the "instance" type string
+        let random_instance_id = module.intern.num_random_seeds;
+        let random_seeds = cx.ty_array(cx.ty_int(), random_instance_id + 1);
+        let hidden_state_fields: Vec<_> = if hidden_state_vars.is_empty() {
+            Vec::new()
+        } else {
+            [ty_f64, cx.ty_c_bool()].into_iter().chain(hidden_state_tys).collect()
+        };
+        let hidden_state =
+            cx.ty_struct(&format!("osdi_hidden_state_{}", &module.sym), &hidden_state_fields);
         let static_fields: [_; NUM_CONST_FIELDS as usize] = [
             param_given,
             jacobian_ptr,
@@ -294,6 +378,10 @@ impl<'ll> OsdiInstanceData<'ll> {
             temperature,
             connected_ports,
             state_idx,
+            delay_buffers,
+            files.unwrap_or_else(|| cx.ty_array(cx.ty_ptr(), 0)),
+            random_seeds,
+            hidden_state,
         ];
 
         let fields: Vec<_> = static_fields
@@ -315,12 +403,18 @@ impl<'ll> OsdiInstanceData<'ll> {
             node_mapping,
             state_idx,
             collapsed,
+            delay_buffers,
+            files,
+            random_seeds,
+            random_instance_id,
+            hidden_state,
             params,
             eval_outputs,
             cache_slots,
             residual,
             noise,
             opvars,
+            hidden_state_vars,
             jacobian,
             bound_step,
         }
@@ -409,6 +503,21 @@ impl<'ll> OsdiInstanceData<'ll> {
         LLVMBuildStore(llbuilder, val, ptr)
     }
 
+    pub unsafe fn store_nth_param_element(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        param_id: u32,
+        idx: u32,
+        ptr: &'ll llvm::Value,
+        val: &'ll llvm::Value,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> &'ll llvm::Value {
+        let (ptr, ty) = self.nth_param_ptr(param_id, ptr, llbuilder);
+        let indices = [cx.const_unsigned_int(0), cx.const_unsigned_int(idx)];
+        let ptr = LLVMBuildGEP2(llbuilder, ty, ptr, indices.as_ptr(), 2, UNNAMED);
+        LLVMBuildStore(llbuilder, val, ptr)
+    }
+
     pub unsafe fn read_nth_param(
         &self,
         pos: u32,
@@ -603,17 +712,95 @@ impl<'ll> OsdiInstanceData<'ll> {
     pub unsafe fn read_state_idx(
         &self,
         cx: &CodegenCx<'_, 'll>,
-        idx: LimitState,
+        idx: u32,
         ptr: &'ll llvm::Value,
         llbuilder: &llvm::Builder<'ll>,
     ) -> &'ll llvm::Value {
         let ptr = LLVMBuildStructGEP2(llbuilder, self.ty, ptr, STATE_IDX, UNNAMED);
         let zero = cx.const_int(0);
-        let state = cx.const_unsigned_int(idx.into());
+        let state = cx.const_unsigned_int(idx);
         let ptr = LLVMBuildGEP2(llbuilder, self.state_idx, ptr, [zero, state].as_ptr(), 2, UNNAMED);
         LLVMBuildLoad2(llbuilder, cx.ty_int(), ptr, UNNAMED)
     }
 
+    pub unsafe fn delay_buffer_ptr(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        line: DelayLine,
+        ptr: &'ll llvm::Value,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> &'ll llvm::Value {
+        let ptr = LLVMBuildStructGEP2(llbuilder, self.ty, ptr, DELAY_BUFFERS, UNNAMED);
+        let zero = cx.const_int(0);
+        let line = cx.const_unsigned_int(line.into());
+        LLVMBuildGEP2(llbuilder, self.delay_buffers, ptr, [zero, line].as_ptr(), 2, UNNAMED)
+    }
+
+    pub unsafe fn files_ptr(
+        &self,
+        ptr: &'ll llvm::Value,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> &'ll llvm::Value {
+        LLVMBuildStructGEP2(llbuilder, self.ty, ptr, FILES, UNNAMED)
+    }
+
+    pub unsafe fn random_seed_ptr(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        seed: RandomSeed,
+        ptr: &'ll llvm::Value,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> &'ll llvm::Value {
+        let ptr = LLVMBuildStructGEP2(llbuilder, self.ty, ptr, RANDOM_SEEDS, UNNAMED);
+        let zero = cx.const_int(0);
+        let seed = cx.const_unsigned_int(seed.into());
+        LLVMBuildGEP2(llbuilder, self.random_seeds, ptr, [zero, seed].as_ptr(), 2, UNNAMED)
+    }
+
+    pub unsafe fn random_instance_id_ptr(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        ptr: &'ll llvm::Value,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> &'ll llvm::Value {
+        let ptr = LLVMBuildStructGEP2(llbuilder, self.ty, ptr, RANDOM_SEEDS, UNNAMED);
+        let zero = cx.const_int(0);
+        let id = cx.const_unsigned_int(self.random_instance_id);
+        LLVMBuildGEP2(llbuilder, self.random_seeds, ptr, [zero, id].as_ptr(), 2, UNNAMED)
+    }
+
+    unsafe fn hidden_state_ptr(
+        &self,
+        field: u32,
+        ptr: &'ll llvm::Value,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> &'ll llvm::Value {
+        let ptr = LLVMBuildStructGEP2(llbuilder, self.ty, ptr, HIDDEN_STATE, UNNAMED);
+        LLVMBuildStructGEP2(llbuilder, self.hidden_state, ptr, field, UNNAMED)
+    }
+
+    /// Stores the value of the hidden state variables at the end of an evaluation at `abstime`.
+    /// These values are committed by [`OsdiCompilationUnit::restore_hidden_state`].
+    pub unsafe fn store_hidden_state(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        ptr: &'ll llvm::Value,
+        abstime: &'ll llvm::Value,
+        builder: &mir_llvm::Builder<'_, '_, 'll>,
+    ) {
+        if self.hidden_state_vars.is_empty() {
+            return;
+        }
+
+        for &output in self.hidden_state_vars.values() {
+            self.store_eval_output(output, ptr, builder);
+        }
+        let time_ptr = self.hidden_state_ptr(HIDDEN_STATE_TIME, ptr, builder.llbuilder);
+        builder.store(time_ptr, abstime);
+        let stored_ptr = self.hidden_state_ptr(HIDDEN_STATE_STORED, ptr, builder.llbuilder);
+        builder.store(stored_ptr, cx.const_c_bool(true));
+    }
+
     pub unsafe fn read_node_voltage(
         &self,
         cx: &CodegenCx<'_, 'll>,
@@ -863,6 +1050,57 @@ impl<'ll> OsdiInstanceData<'ll> {
 }
 
 impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
+    /// Restores the hidden state at the start of an evaluation at `abstime`. Returns whether
+    /// any hidden state was stored yet and the value of each hidden state variable.
+    ///
+    /// The values stored by the last evaluation are only committed once the timestep they
+    /// belong to has been accepted, i.e. the next evaluation happens at a later time. If a
+    /// timestep is rejected the values from the last accepted timestep are used instead.
+    /// Outside of transient analysis the values of the last evaluation are always committed.
+    pub unsafe fn restore_hidden_state(
+        &self,
+        inst_ptr: &'ll llvm::Value,
+        model_ptr: &'ll llvm::Value,
+        abstime: &'ll llvm::Value,
+        is_tran: &'ll llvm::Value,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> (&'ll llvm::Value, Vec<&'ll llvm::Value>) {
+        let OsdiCompilationUnit { inst_data, cx, .. } = self;
+        if inst_data.hidden_state_vars.is_empty() {
+            return (cx.const_bool(false), Vec::new());
+        }
+
+        let time_ptr = inst_data.hidden_state_ptr(HIDDEN_STATE_TIME, inst_ptr, llbuilder);
+        let time = LLVMBuildLoad2(llbuilder, cx.ty_double(), time_ptr, UNNAMED);
+        let stored_ptr = inst_data.hidden_state_ptr(HIDDEN_STATE_STORED, inst_ptr, llbuilder);
+        let stored = LLVMBuildLoad2(llbuilder, cx.ty_c_bool(), stored_ptr, UNNAMED);
+        let stored =
+            LLVMBuildICmp(llbuilder, IntPredicate::IntNE, stored, cx.const_c_bool(false), UNNAMED);
+
+        let accepted = LLVMBuildFCmp(llbuilder, RealPredicate::RealOGT, abstime, time, UNNAMED);
+        let is_not_tran = LLVMBuildNot(llbuilder, is_tran, UNNAMED);
+        let commit = LLVMBuildOr(llbuilder, is_not_tran, accepted, UNNAMED);
+        let commit = LLVMBuildAnd(llbuilder, stored, commit, UNNAMED);
+
+        let vals = inst_data
+            .hidden_state_vars
+            .values()
+            .enumerate()
+            .map(|(i, &output)| {
+                let field = HIDDEN_STATE_VALS + i as u32;
+                let ptr = inst_data.hidden_state_ptr(field, inst_ptr, llbuilder);
+                let ty = LLVMStructGetTypeAtIndex(inst_data.hidden_state, field);
+                let committed = LLVMBuildLoad2(llbuilder, ty, ptr, UNNAMED);
+                let last = self.load_eval_output(output, inst_ptr, model_ptr, llbuilder);
+                let val = LLVMBuildSelect(llbuilder, commit, last, committed, UNNAMED);
+                LLVMBuildStore(llbuilder, val, ptr);
+                val
+            })
+            .collect();
+
+        (stored, vals)
+    }
+
     pub unsafe fn load_eval_output(
         &self,
         output: EvalOutput,
@@ -901,17 +1139,21 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                         .param_ptr(OsdiInstanceParam::Builtin(func), inst_ptr, llbuilder)
                         .unwrap(),
 
-                    ParamKind::HiddenState(_) => todo!("hidden state"),
-
                     ParamKind::Voltage { .. }
                     | ParamKind::Current(_)
                     | ParamKind::PortConnected { .. }
                     | ParamKind::ParamGiven { .. }
+                    | ParamKind::ParamElement { .. }
                     | ParamKind::Abstime
                     | ParamKind::EnableIntegration
+                    | ParamKind::TransientAnalysis
                     | ParamKind::EnableLim
                     | ParamKind::PrevState(_)
                     | ParamKind::NewState(_)
+                    | ParamKind::PrevOperatorState(_)
+                    | ParamKind::HiddenState(_)
+                    | ParamKind::HiddenStateElement { .. }
+                    | ParamKind::HiddenStateInitialized
                     | ParamKind::ImplicitUnknown(_) => unreachable!(),
                 }
             }
@@ -975,17 +1217,21 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                         .param_ptr(OsdiInstanceParam::Builtin(func), inst_ptr, llbuilder)
                         .unwrap(),
 
-                    ParamKind::HiddenState(_) => todo!("hidden state"),
-
                     ParamKind::Voltage { .. }
                     | ParamKind::Current(_)
                     | ParamKind::PortConnected { .. }
                     | ParamKind::ParamGiven { .. }
+                    | ParamKind::ParamElement { .. }
                     | ParamKind::EnableIntegration { .. }
+                    | ParamKind::TransientAnalysis
                     | ParamKind::Abstime
                     | ParamKind::EnableLim
                     | ParamKind::PrevState(_)
                     | ParamKind::NewState(_)
+                    | ParamKind::PrevOperatorState(_)
+                    | ParamKind::HiddenState(_)
+                    | ParamKind::HiddenStateElement { .. }
+                    | ParamKind::HiddenStateInitialized
                     | ParamKind::ImplicitUnknown(_) => unreachable!(),
                 }
             }
@@ -168,16 +168,18 @@ pub fn compile(
         let cx = new_codegen(back, &llmod, &literals);
         let tys = OsdiTys::new(&cx, target_data);
 
-        let descriptors: Vec<_> = modules
+        let (descriptors, free_instance): (Vec<_>, Vec<_>) = modules
             .iter()
             .map(|module| {
                 let cguint = OsdiCompilationUnit::new(&db, module, &cx, &tys, false);
                 let descriptor = cguint.descriptor(target_data, &db);
-                descriptor.to_ll_val(&cx, &tys)
+                (descriptor.to_ll_val(&cx, &tys), cguint.free_instance())
             })
-            .collect();
+            .unzip();
 
         cx.export_array("OSDI_DESCRIPTORS", tys.osdi_descriptor, &descriptors, true, false);
+        // extension: releases the resources of an instance, parallel to OSDI_DESCRIPTORS
+        cx.export_array("OSDI_FREE_INSTANCE", cx.ty_ptr(), &free_instance, true, false);
         cx.export_val(
             "OSDI_NUM_DESCRIPTORS",
             cx.ty_int(),
@@ -208,14 +210,18 @@ pub fn compile(
             );
         }
 
-        let osdi_log =
-            cx.get_declared_value("osdi_log").expect("symbol osdi_log missing from std lib");
-        let val = cx.const_null_ptr();
-        unsafe {
-            llvm::LLVMSetInitializer(osdi_log, val);
-            llvm::LLVMSetLinkage(osdi_log, llvm::Linkage::ExternalLinkage);
-            llvm::LLVMSetUnnamedAddress(osdi_log, llvm::UnnamedAddr::No);
-            llvm::LLVMSetDLLStorageClass(osdi_log, llvm::DLLStorageClass::Export);
+        // callbacks that may be set by the simulator
+        for name in ["osdi_log", "osdi_arandom", "osdi_simprobe", "osdi_plusarg"] {
+            let callback = cx
+                .get_declared_value(name)
+                .unwrap_or_else(|| panic!("symbol {name} missing from std lib"));
+            let val = cx.const_null_ptr();
+            unsafe {
+                llvm::LLVMSetInitializer(callback, val);
+                llvm::LLVMSetLinkage(callback, llvm::Linkage::ExternalLinkage);
+                llvm::LLVMSetUnnamedAddress(callback, llvm::UnnamedAddr::No);
+                llvm::LLVMSetDLLStorageClass(callback, llvm::DLLStorageClass::Export);
+            }
         }
 
         debug_assert!(llmod.verify_and_print());
@@ -235,7 +241,7 @@ pub fn compile(
 
 impl OsdiModule<'_> {
     fn intern_names(&self, literals: &mut Rodeo, db: &CompilationDB) {
-        literals.get_or_intern(&*self.info.module.name(db));
+        literals.get_or_intern(&*self.info.name(db));
         self.intern_node_strs(literals, db);
         literals.get_or_intern_static("Multiplier (Verilog-A $mfactor)");
         literals.get_or_intern_static("deg");
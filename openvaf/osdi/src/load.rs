@@ -1,14 +1,16 @@
 use llvm::{
-    LLVMAppendBasicBlockInContext, LLVMBuildCall2, LLVMBuildFAdd, LLVMBuildFDiv, LLVMBuildFMul,
-    LLVMBuildFSub, LLVMBuildGEP2, LLVMBuildRetVoid, LLVMBuildStore, LLVMCreateBuilderInContext,
-    LLVMDisposeBuilder, LLVMGetParam, LLVMPositionBuilderAtEnd, LLVMSetFastMath,
-    LLVMSetPartialFastMath, UNNAMED,
+    LLVMAppendBasicBlockInContext, LLVMBuildCall2, LLVMBuildFAdd, LLVMBuildFCmp, LLVMBuildFDiv,
+    LLVMBuildFMul, LLVMBuildFSub, LLVMBuildGEP2, LLVMBuildRetVoid, LLVMBuildSelect, LLVMBuildStore,
+    LLVMCreateBuilderInContext, LLVMDisposeBuilder, LLVMGetParam, LLVMPositionBuilderAtEnd,
+    LLVMSetFastMath, LLVMSetPartialFastMath, RealPredicate, UNNAMED,
 };
+use mir::Const;
 use sim_back::dae::NoiseSourceKind;
 use stdx::iter::zip;
 use typed_index_collections::TiVec;
 
 use crate::compilation_unit::OsdiCompilationUnit;
+use crate::inst_data::EvalOutput;
 
 #[derive(Debug, Clone, Copy)]
 pub enum JacobianLoadType {
@@ -81,7 +83,29 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                         LLVMSetFastMath(pwr);
                         pwr
                     }
-                    NoiseSourceKind::NoiseTable { .. } => unimplemented!("noise tables"),
+                    NoiseSourceKind::NoiseTable { log, .. } => {
+                        let mut pairs: Vec<_> = eval_outputs.args.chunks_exact(2).collect();
+                        // tables with constant frequencies are sorted at compile time
+                        let const_freqs: Option<Vec<_>> = pairs
+                            .iter()
+                            .map(|pair| match pair[0] {
+                                EvalOutput::Const(Const::Float(freq), _) => Some(f64::from(freq)),
+                                _ => None,
+                            })
+                            .collect();
+                        let sorted = const_freqs.is_some();
+                        if let Some(freqs) = const_freqs {
+                            let mut order: Vec<_> = (0..pairs.len()).collect();
+                            order.sort_by(|&i, &j| freqs[i].total_cmp(&freqs[j]));
+                            pairs = order.into_iter().map(|i| pairs[i]).collect();
+                        }
+                        let table: Vec<_> = pairs
+                            .iter()
+                            .flat_map(|pair| pair.iter())
+                            .map(|&arg| self.load_eval_output(arg, inst, model, llbuilder))
+                            .collect();
+                        self.interpolate_noise_table(&table, freq, log, sorted, llbuilder)
+                    }
                 };
                 pwr = LLVMBuildFMul(llbuilder, pwr, fac, UNNAMED);
                 LLVMSetFastMath(pwr);
@@ -104,6 +128,75 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         llfunc
     }
 
+    /// Piecewise linear interpolation of the (frequency, power) pairs in `table` at `freq`.
+    /// For `noise_table_log` the interpolation is performed in log-log scale. Outside of the
+    /// table the power of the closest entry is used. If the table is not `sorted` by frequency
+    /// (because the frequencies are only known at runtime) it is sorted with a sorting network.
+    unsafe fn interpolate_noise_table(
+        &self,
+        table: &[&'ll llvm::Value],
+        freq: &'ll llvm::Value,
+        log: bool,
+        sorted: bool,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> &'ll llvm::Value {
+        let cx = self.cx;
+        if table.is_empty() {
+            return cx.const_real(0.0);
+        }
+
+        let call_intrinsic = |name, args: &[_]| {
+            let (ty, fun) =
+                cx.intrinsic(name).unwrap_or_else(|| unreachable!("intrinsic {} not found", name));
+            LLVMBuildCall2(llbuilder, ty, fun, args.as_ptr(), args.len() as u32, UNNAMED)
+        };
+        let scale = |val| if log { call_intrinsic("llvm.log10.f64", &[val]) } else { val };
+
+        let x = scale(freq);
+        let mut points: Vec<_> =
+            table.chunks_exact(2).map(|pair| (scale(pair[0]), scale(pair[1]))).collect();
+
+        if !sorted {
+            // odd-even transposition sort
+            for pass in 0..points.len() {
+                for i in (pass % 2..points.len() - 1).step_by(2) {
+                    let [(x0, y0), (x1, y1)] = [points[i], points[i + 1]];
+                    let swap = LLVMBuildFCmp(llbuilder, RealPredicate::RealOGT, x0, x1, UNNAMED);
+                    points[i] = (
+                        LLVMBuildSelect(llbuilder, swap, x1, x0, UNNAMED),
+                        LLVMBuildSelect(llbuilder, swap, y1, y0, UNNAMED),
+                    );
+                    points[i + 1] = (
+                        LLVMBuildSelect(llbuilder, swap, x0, x1, UNNAMED),
+                        LLVMBuildSelect(llbuilder, swap, y0, y1, UNNAMED),
+                    );
+                }
+            }
+        }
+
+        // the last segment that starts below the frequency is selected
+        let mut res = points[0].1;
+        for segment in points.windows(2) {
+            let [(x0, y0), (x1, y1)] = [segment[0], segment[1]];
+            let dy = LLVMBuildFSub(llbuilder, y1, y0, UNNAMED);
+            let dx = LLVMBuildFSub(llbuilder, x1, x0, UNNAMED);
+            let slope = LLVMBuildFDiv(llbuilder, dy, dx, UNNAMED);
+            let offset = LLVMBuildFSub(llbuilder, x, x0, UNNAMED);
+            let val = LLVMBuildFMul(llbuilder, offset, slope, UNNAMED);
+            let val = LLVMBuildFAdd(llbuilder, y0, val, UNNAMED);
+            let in_segment = LLVMBuildFCmp(llbuilder, RealPredicate::RealOGT, x, x0, UNNAMED);
+            res = LLVMBuildSelect(llbuilder, in_segment, val, res, UNNAMED);
+        }
+        let (x_end, y_end) = points[points.len() - 1];
+        let after_end = LLVMBuildFCmp(llbuilder, RealPredicate::RealOGE, x, x_end, UNNAMED);
+        res = LLVMBuildSelect(llbuilder, after_end, y_end, res, UNNAMED);
+
+        if log {
+            res = call_intrinsic("llvm.pow.f64", &[cx.const_real(10.0), res]);
+        }
+        res
+    }
+
     pub fn load_residual(&self, reactive: bool) -> &'ll llvm::Value {
         let OsdiCompilationUnit { inst_data, cx, module, .. } = self;
         let ptr_ty = cx.ty_ptr();
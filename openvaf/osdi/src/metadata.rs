@@ -98,7 +98,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
         let model_params = model_data.params.keys().filter_map(|param| {
             let param_info = &module.info.params[param];
-            if param_info.is_instance {
+            if param_info.is_instance || param_info.hidden {
                 return None;
             }
             let ty = param.ty(self.db);
@@ -268,7 +268,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                 .collect();
 
             OsdiDescriptor {
-                name: module.info.module.name(db),
+                name: module.info.name(db),
                 num_nodes: module.dae_system.unknowns.len() as u32,
                 num_terminals: module.info.module.ports(db).len() as u32,
                 nodes: self.nodes(target_data, db),
@@ -283,7 +283,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                 num_noise_src: noise_sources.len() as u32,
                 noise_sources,
 
-                num_params: model_data.params.len() as u32 + inst_data.params.len() as u32,
+                num_params: model_data.num_exposed_params() as u32 + inst_data.params.len() as u32,
                 num_instance_params: inst_data.params.len() as u32,
                 num_opvars: inst_data.opvars.len() as u32,
                 param_opvar: self.param_opvar(),
@@ -305,7 +305,8 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                 load_jacobian_resist: self.load_jacobian(JacobianLoadType::Resist),
                 load_jacobian_react: self.load_jacobian(JacobianLoadType::React),
                 load_jacobian_tran: self.load_jacobian(JacobianLoadType::Tran),
-                num_states: self.module.intern.lim_state.len() as u32,
+                num_states: (self.module.intern.lim_state.len()
+                    + self.module.intern.operator_states.len()) as u32,
                 load_limit_rhs_resist: self.load_lim_rhs(false),
                 load_limit_rhs_react: self.load_lim_rhs(true),
             }
@@ -331,7 +332,7 @@ fn sim_unknown_info(unknown: SimUnknownKind, db: &CompilationDB) -> (String, Str
     match unknown {
         SimUnknownKind::KirchoffLaw(node) => {
             name = node.name(db).to_string();
-            discipline = Some(node.discipline(db));
+            discipline = node.discipline(db);
             is_flow = false;
         }
 
@@ -341,7 +342,7 @@ fn sim_unknown_info(unknown: SimUnknownKind, db: &CompilationDB) -> (String, Str
             } else {
                 format!("flow({})", &hi.name(db))
             };
-            discipline = Some(hi.discipline(db));
+            discipline = hi.discipline(db);
             is_flow = true;
         }
         SimUnknownKind::Current(CurrentKind::Branch(br)) => {
@@ -351,7 +352,7 @@ fn sim_unknown_info(unknown: SimUnknownKind, db: &CompilationDB) -> (String, Str
         }
         SimUnknownKind::Current(CurrentKind::Port(node)) => {
             name = format!("flow(<{}>)", &node.name(db));
-            discipline = Some(node.discipline(db));
+            discipline = node.discipline(db);
             is_flow = true;
         }
         SimUnknownKind::Implicit(equ) => {
@@ -361,17 +362,10 @@ fn sim_unknown_info(unknown: SimUnknownKind, db: &CompilationDB) -> (String, Str
         }
     };
 
-    // its valid to have disciplines without pot/flow nature but then we can't
-    // have branches for those so its ok to unwrap here
+    // real nets have no discipline and discrete disciplines usually have no natures
     let units = discipline
-        .map(|discipline| {
-            let nature = if is_flow {
-                discipline.flow(db).unwrap()
-            } else {
-                discipline.potential(db).unwrap()
-            };
-            nature.units(db)
-        })
+        .and_then(|discipline| if is_flow { discipline.flow(db) } else { discipline.potential(db) })
+        .map(|nature| nature.units(db))
         .unwrap_or_default();
 
     (name, units, is_flow)
@@ -1,18 +1,25 @@
 use ahash::RandomState;
 use hir::{CompilationDB, Parameter};
+use hir_lower::RandomSeed;
 use indexmap::IndexMap;
-use llvm::{LLVMBuildLoad2, LLVMBuildStore, LLVMBuildStructGEP2, Value, UNNAMED};
+use llvm::{LLVMBuildGEP2, LLVMBuildLoad2, LLVMBuildStore, LLVMBuildStructGEP2, Value, UNNAMED};
 use mir_llvm::{CodegenCx, MemLoc};
 
 use crate::compilation_unit::OsdiModule;
 use crate::inst_data::{OsdiInstanceData, OsdiInstanceParam};
 use crate::{bitfield, lltype};
 
-const NUM_CONST_FIELDS: u32 = 1;
+const NUM_CONST_FIELDS: u32 = 2;
+const RANDOM_SEEDS: u32 = 1;
 
 pub struct OsdiModelData<'ll> {
     pub param_given: &'ll llvm::Type,
+    /// seeds of the random numbers drawn while setting up the model
+    pub random_seeds: &'ll llvm::Type,
     pub params: IndexMap<Parameter, &'ll llvm::Type, RandomState>,
+    /// Number of hidden (paramset) parameters. These are placed after all other parameters and
+    /// are not accessible to the simulator.
+    pub num_hidden_params: usize,
     pub ty: &'ll llvm::Type,
 }
 
@@ -37,9 +44,17 @@ impl<'ll> OsdiModelData<'ll> {
             })
             .collect();
 
+        let num_hidden_params =
+            params.keys().filter(|param| cgunit.info.params[*param].hidden).count();
+        debug_assert!(params
+            .keys()
+            .skip(params.len() - num_hidden_params)
+            .all(|param| cgunit.info.params[param].hidden));
         let param_given = bitfield::arr_ty((inst_params.len() + params.len()) as u32, cx);
 
-        let mut fields: Vec<_> = vec![param_given];
+        let random_seeds = cx.ty_array(cx.ty_int(), cgunit.model_param_intern.num_random_seeds);
+
+        let mut fields: Vec<_> = vec![param_given, random_seeds];
         fields.extend(params.values().copied());
         fields.extend(inst_params.values());
 
@@ -47,7 +62,12 @@ impl<'ll> OsdiModelData<'ll> {
         let name = format!("osdi_model_data_{name}");
         let ty = cx.ty_struct(&name, &fields);
 
-        OsdiModelData { param_given, params, ty }
+        OsdiModelData { param_given, random_seeds, params, num_hidden_params, ty }
+    }
+
+    /// The number of model parameters that are exposed to the simulator.
+    pub fn num_exposed_params(&self) -> usize {
+        self.params.len() - self.num_hidden_params
     }
 
     pub fn nth_param_loc(
@@ -111,6 +131,19 @@ impl<'ll> OsdiModelData<'ll> {
         (ptr, ty)
     }
 
+    pub unsafe fn random_seed_ptr(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        seed: RandomSeed,
+        ptr: &'ll llvm::Value,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> &'ll llvm::Value {
+        let ptr = LLVMBuildStructGEP2(llbuilder, self.ty, ptr, RANDOM_SEEDS, UNNAMED);
+        let zero = cx.const_int(0);
+        let seed = cx.const_unsigned_int(seed.into());
+        LLVMBuildGEP2(llbuilder, self.random_seeds, ptr, [zero, seed].as_ptr(), 2, UNNAMED)
+    }
+
     // pub unsafe fn read_param(
     //     &self,
     //     param: ParamId,
@@ -133,6 +166,21 @@ impl<'ll> OsdiModelData<'ll> {
         LLVMBuildStore(llbuilder, val, ptr);
     }
 
+    pub unsafe fn store_nth_param_element(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        param: u32,
+        idx: u32,
+        ptr: &'ll Value,
+        val: &'ll llvm::Value,
+        llbuilder: &llvm::Builder<'ll>,
+    ) {
+        let (ptr, ty) = self.nth_param_ptr(param, ptr, llbuilder);
+        let indices = [cx.const_unsigned_int(0), cx.const_unsigned_int(idx)];
+        let ptr = LLVMBuildGEP2(llbuilder, ty, ptr, indices.as_ptr(), 2, UNNAMED);
+        LLVMBuildStore(llbuilder, val, ptr);
+    }
+
     // pub unsafe fn read_nth_param(
     //     &self,
     //     param: u32,
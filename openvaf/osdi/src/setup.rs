@@ -1,17 +1,19 @@
-use hir_lower::{CallBackKind, ParamInfoKind, ParamKind, PlaceKind};
+use hir::{CompilationDB, Parameter, Type};
+use hir_lower::{CallBackKind, HirInterner, ParamInfoKind, ParamKind, PlaceKind};
 
 use llvm::IntPredicate::IntSLT;
 use llvm::{
-    LLVMAppendBasicBlockInContext, LLVMBuildBr, LLVMBuildCondBr, LLVMBuildRetVoid,
-    LLVMCreateBuilderInContext, LLVMDisposeBuilder, LLVMGetParam, LLVMPositionBuilderAtEnd,
-    UNNAMED,
+    LLVMAppendBasicBlockInContext, LLVMBuildBr, LLVMBuildCall2, LLVMBuildCondBr,
+    LLVMBuildExtractValue, LLVMBuildRetVoid, LLVMCreateBuilderInContext, LLVMDisposeBuilder,
+    LLVMGetParam, LLVMPositionBuilderAtEnd, UNNAMED,
 };
 use mir::ControlFlowGraph;
 use mir_llvm::{Builder, BuilderVal, CallbackFun, CodegenCx};
 use sim_back::SimUnknownKind;
 
-use crate::compilation_unit::{general_callbacks, OsdiCompilationUnit};
+use crate::compilation_unit::{general_callbacks, random_callback, OsdiCompilationUnit};
 use crate::inst_data::OsdiInstanceParam;
+use crate::lltype;
 
 impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
     fn mark_collapsed(&self) -> (&'ll llvm::Value, &'ll llvm::Type) {
@@ -78,9 +80,10 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         for (i, param) in model_data.params.keys().copied().enumerate() {
             let i = i as u32;
 
-            let dst = intern.params.unwrap_index(&ParamKind::Param(param));
             let loc = model_data.nth_param_loc(cx, i, model);
-            builder.params[dst] = BuilderVal::Load(Box::new(loc));
+            unsafe {
+                bind_param(&mut builder, self.db, intern, param, BuilderVal::Load(Box::new(loc)))
+            }
 
             let dst = intern.params.unwrap_index(&ParamKind::ParamGiven { param });
             let is_given =
@@ -107,8 +110,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                     }
                 }
                 OsdiInstanceParam::User(param) => {
-                    let dst = intern.params.unwrap_index(&ParamKind::Param(param));
-                    builder.params[dst] = BuilderVal::Eager(val);
+                    unsafe { bind_param(&mut builder, self.db, intern, param, val.into()) }
                     let dst = intern.params.unwrap_index(&ParamKind::ParamGiven { param });
                     builder.params[dst] = BuilderVal::Eager(is_given);
                 }
@@ -140,8 +142,15 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
         builder.callbacks = general_callbacks(intern, &mut builder, ret_flags, handle, simparam);
         for (call_id, call) in intern.callbacks.iter_enumerated() {
-            if let CallBackKind::ParamInfo(ParamInfoKind::Invalid, param) = call {
-                if !self.module.info.params[param].is_instance {
+            if let CallBackKind::Random { seed, .. } | CallBackKind::ReadRandomSeed(seed) = *call {
+                // the model has no instance id so all random numbers are global
+                let seed =
+                    unsafe { model_data.random_seed_ptr(cx, seed, model, builder.llbuilder) };
+                builder.callbacks[call_id] = Some(random_callback(cx, call, seed, None, handle));
+            } else if let CallBackKind::ParamInfo(ParamInfoKind::Invalid, param) = call {
+                // hidden parameters have no id that could be reported to the simulator
+                let info = &self.module.info.params[param];
+                if !info.is_instance && !info.hidden {
                     let id =
                         model_data.params.get_index_of(param).unwrap() + inst_data.params.len();
                     let err_param = cx.const_unsigned_int(id as u32);
@@ -173,13 +182,26 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
         // store parameters
         for (i, param) in model_data.params.keys().enumerate() {
-            let val = intern.outputs[&PlaceKind::Param(*param)].unwrap_unchecked();
-            let inst = func.dfg.value_def(val).unwrap_inst();
-            let bb = func.layout.inst_block(inst).unwrap();
-            builder.select_bb_before_terminator(bb);
-            unsafe {
-                let val = builder.values[val].get(&builder);
-                model_data.store_nth_param(i as u32, model, val, builder.llbuilder);
+            for (output, elem) in param_outputs(self.db, *param) {
+                let val = intern.outputs[&output].unwrap_unchecked();
+                let inst = func.dfg.value_def(val).unwrap_inst();
+                let bb = func.layout.inst_block(inst).unwrap();
+                builder.select_bb_before_terminator(bb);
+                unsafe {
+                    let val = builder.values[val].get(&builder);
+                    if let Some(idx) = elem {
+                        model_data.store_nth_param_element(
+                            cx,
+                            i as u32,
+                            idx,
+                            model,
+                            val,
+                            builder.llbuilder,
+                        );
+                    } else {
+                        model_data.store_nth_param(i as u32, model, val, builder.llbuilder);
+                    }
+                }
             }
         }
 
@@ -232,6 +254,19 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         let ret_flags = unsafe { builder.alloca(cx.ty_int()) };
         unsafe { builder.store(ret_flags, cx.const_int(0)) };
 
+        if inst_data.files.is_some() {
+            let fun = cx
+                .get_func_by_name("osdi_init_files")
+                .expect("stdlib function osdi_init_files is missing");
+            let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_int()], cx.ty_void());
+            unsafe {
+                let files = inst_data.files_ptr(instance, builder.llbuilder);
+                let num_strobes = cx.const_unsigned_int(module.intern.num_strobes);
+                let args = [files, num_strobes];
+                LLVMBuildCall2(builder.llbuilder, fun_ty, fun, args.as_ptr(), 2, UNNAMED);
+            }
+        }
+
         builder.params = vec![BuilderVal::Undef; intern.params.len()].into();
 
         let true_ = cx.const_bool(true);
@@ -265,8 +300,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                     }
                 }
                 OsdiInstanceParam::User(param) => {
-                    let dst = intern.params.unwrap_index(&ParamKind::Param(param));
-                    builder.params[dst] = BuilderVal::Eager(val);
+                    unsafe { bind_param(&mut builder, self.db, intern, param, val.into()) }
                     let dst = intern.params.unwrap_index(&ParamKind::ParamGiven { param });
                     builder.params[dst] = BuilderVal::Eager(is_given);
                 }
@@ -276,9 +310,9 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         for (i, param) in model_data.params.keys().copied().enumerate() {
             let i = i as u32;
 
-            if let Some(dst) = intern.params.index(&ParamKind::Param(param)) {
-                let loc = model_data.nth_param_loc(cx, i, model);
-                builder.params[dst] = BuilderVal::Load(Box::new(loc));
+            let loc = model_data.nth_param_loc(cx, i, model);
+            unsafe {
+                bind_param(&mut builder, self.db, intern, param, BuilderVal::Load(Box::new(loc)))
             }
 
             if let Some(dst) = intern.params.index(&ParamKind::ParamGiven { param }) {
@@ -369,6 +403,15 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                         num_state: 2,
                     }
                 }
+                CallBackKind::Random { seed, .. } | CallBackKind::ReadRandomSeed(seed) => {
+                    let (seed, id) = unsafe {
+                        (
+                            inst_data.random_seed_ptr(cx, *seed, instance, builder.llbuilder),
+                            inst_data.random_instance_id_ptr(cx, instance, builder.llbuilder),
+                        )
+                    };
+                    random_callback(cx, call, seed, Some(id), handle)
+                }
                 _ => continue,
             };
 
@@ -383,20 +426,32 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
         // store parameters
         for (i, param) in inst_data.params.keys().enumerate() {
-            let val = match param {
+            let param = match param {
                 OsdiInstanceParam::Builtin(_) => continue,
-                OsdiInstanceParam::User(param) => {
-                    intern.outputs[&PlaceKind::Param(*param)].unwrap_unchecked()
-                }
+                OsdiInstanceParam::User(param) => *param,
             };
 
-            let inst = func.dfg.value_def(val).unwrap_inst();
-            let bb = func.layout.inst_block(inst).unwrap();
-            builder.select_bb_before_terminator(bb);
+            for (output, elem) in param_outputs(self.db, param) {
+                let val = intern.outputs[&output].unwrap_unchecked();
+                let inst = func.dfg.value_def(val).unwrap_inst();
+                let bb = func.layout.inst_block(inst).unwrap();
+                builder.select_bb_before_terminator(bb);
 
-            unsafe {
-                let val = builder.values[val].get(&builder);
-                inst_data.store_nth_param(i as u32, instance, val, builder.llbuilder);
+                unsafe {
+                    let val = builder.values[val].get(&builder);
+                    if let Some(idx) = elem {
+                        inst_data.store_nth_param_element(
+                            cx,
+                            i as u32,
+                            idx,
+                            instance,
+                            val,
+                            builder.llbuilder,
+                        );
+                    } else {
+                        inst_data.store_nth_param(i as u32, instance, val, builder.llbuilder);
+                    }
+                }
             }
         }
 
@@ -441,4 +496,93 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
         llfunc
     }
+
+    /// Generates `free_instance_{sym}(void *handle, void *inst)` which releases the resources
+    /// acquired by the standard library during evaluation: the histories of `absdelay` are freed,
+    /// pending strobes are written and open files are closed.
+    pub fn free_instance(&self) -> &'ll llvm::Value {
+        let OsdiCompilationUnit { inst_data, cx, module, .. } = self;
+        let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_ptr()], cx.ty_void());
+        let name = &format!("free_instance_{}", &module.sym);
+        let llfunc = cx.declare_int_c_fn(name, fun_ty);
+
+        unsafe {
+            let entry = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+            let llbuilder = LLVMCreateBuilderInContext(cx.llcx);
+            LLVMPositionBuilderAtEnd(llbuilder, entry);
+            let handle = LLVMGetParam(llfunc, 0);
+            let instance = LLVMGetParam(llfunc, 1);
+
+            let num_delay_lines = module.intern.num_delay_lines;
+            if num_delay_lines != 0 {
+                let fun = cx
+                    .get_func_by_name("osdi_free_delay_buffers")
+                    .expect("stdlib function osdi_free_delay_buffers is missing");
+                let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_int()], cx.ty_void());
+                let buffers = inst_data.delay_buffer_ptr(cx, 0u32.into(), instance, llbuilder);
+                let args = [buffers, cx.const_unsigned_int(num_delay_lines)];
+                LLVMBuildCall2(llbuilder, fun_ty, fun, args.as_ptr(), args.len() as u32, UNNAMED);
+            }
+
+            if inst_data.files.is_some() {
+                let fun = cx
+                    .get_func_by_name("osdi_free_files")
+                    .expect("stdlib function osdi_free_files is missing");
+                let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_ptr()], cx.ty_void());
+                let args = [handle, inst_data.files_ptr(instance, llbuilder)];
+                LLVMBuildCall2(llbuilder, fun_ty, fun, args.as_ptr(), args.len() as u32, UNNAMED);
+            }
+
+            LLVMBuildRetVoid(llbuilder);
+            LLVMDisposeBuilder(llbuilder);
+        }
+
+        llfunc
+    }
+}
+
+/// Binds `val` (the value of `param`) to the function parameter that represents it.
+/// Array parameters are lowered element-wise, so each element is bound to its own
+/// [`ParamKind::ParamElement`] instead.
+unsafe fn bind_param<'ll>(
+    builder: &mut Builder<'_, '_, 'll>,
+    db: &CompilationDB,
+    intern: &HirInterner,
+    param: Parameter,
+    val: BuilderVal<'ll>,
+) {
+    let (ty, len) = match param.ty(db) {
+        Type::Array { ty, len } => (ty, len),
+        _ => {
+            if let Some(dst) = intern.params.index(&ParamKind::Param(param)) {
+                builder.params[dst] = val;
+            }
+            return;
+        }
+    };
+
+    let ty = lltype(ty.base_type(), builder.cx);
+    for idx in 0..len {
+        if let Some(dst) = intern.params.index(&ParamKind::ParamElement { param, idx }) {
+            builder.params[dst] = match &val {
+                BuilderVal::Eager(val) => {
+                    LLVMBuildExtractValue(builder.llbuilder, val, idx, UNNAMED).into()
+                }
+                BuilderVal::Load(loc) => {
+                    BuilderVal::Load(Box::new(loc.array_element(idx, ty, builder.cx)))
+                }
+                BuilderVal::Undef | BuilderVal::Call(_) => unreachable!(),
+            };
+        }
+    }
+}
+
+/// The outputs that hold the final value of `param` and the array element they correspond to.
+fn param_outputs(db: &CompilationDB, param: Parameter) -> Vec<(PlaceKind, Option<u32>)> {
+    match param.ty(db) {
+        Type::Array { len, .. } => {
+            (0..len).map(|idx| (PlaceKind::ParamElement { param, idx }, Some(idx))).collect()
+        }
+        _ => vec![(PlaceKind::Param(param), None)],
+    }
 }
@@ -1,5 +1,6 @@
 #ifdef NO_STD
 typedef int uint32_t;
+typedef int int32_t;
 typedef unsigned char bool;
 typedef __SIZE_TYPE__ size_t;
 extern size_t strlen (const char *__s);
@@ -8,7 +9,17 @@ extern void *memcpy (void *__restrict __dest, const void *__restrict __src,
 extern void *malloc (size_t __size);
 extern void *realloc (void *__ptr, size_t __size);
 extern double log(double);
+extern double exp(double);
+extern double sqrt(double);
 extern int strcmp(const char*, const char*);
+extern int strncmp(const char*, const char*, size_t);
+extern long strtol(const char *__nptr, char **__endptr, int __base);
+extern double strtod(const char *__nptr, char **__endptr);
+extern void free(void *__ptr);
+extern void *fopen(const char *__filename, const char *__modes);
+extern int fclose(void *__stream);
+extern int fflush(void *__stream);
+extern int fputs(const char *__s, void *__stream);
 #define NULL ((void*)0)
 #else
 #include <math.h>
@@ -90,6 +101,169 @@ char *simparam_str(void *params_, void *handle, uint32_t *flags, char *name) {
   return "�";
 }
 
+// Provided by the simulator to implement $simprobe. Writes the value of the
+// quantity name of the instance inst to val and returns zero on success. If
+// the simulator does not provide this function every probe fails: $simprobe
+// without a default value reports a fatal error and $simprobe with a default
+// value returns the default.
+typedef int32_t (*osdi_simprobe_ptr)(void *handle, char *inst, char *name,
+                                     double *val);
+extern osdi_simprobe_ptr osdi_simprobe;
+
+double simprobe(void *handle, uint32_t *flags, char *inst, char *name) {
+  double val = 0.0;
+  if (osdi_simprobe != NULL && osdi_simprobe(handle, inst, name, &val) == 0) {
+    return val;
+  }
+  *flags |= EVAL_RET_FLAG_FATAL;
+
+  char *msg = concat("$simprobe failed to probe ", name);
+  if (msg == NULL) {
+    osdi_log(handle, "$simprobe failed to probe %s", LOG_LVL_FATAL | LOG_FMT_ERR);
+  } else {
+    osdi_log(handle, msg, LOG_LVL_FATAL);
+  }
+  return 0.0;
+}
+
+double simprobe_opt(void *handle, char *inst, char *name, double default_val) {
+  double val = 0.0;
+  if (osdi_simprobe != NULL && osdi_simprobe(handle, inst, name, &val) == 0) {
+    return val;
+  }
+  return default_val;
+}
+
+// Provided by the simulator to implement $test$plusargs and $value$plusargs.
+// Returns a pointer to the text that follows the first len characters of
+// prefix in the first plusarg (without the leading +) that starts with these
+// characters or NULL if there is no such plusarg. If the simulator does not
+// provide this function, no plusarg is ever found.
+typedef char *(*osdi_plusarg_ptr)(void *handle, char *prefix, uint32_t len);
+extern osdi_plusarg_ptr osdi_plusarg;
+
+// the plusarg that is searched for ends at the first % of the format string
+static char *find_plusarg(void *handle, char *fmt) {
+  if (osdi_plusarg == NULL) {
+    return NULL;
+  }
+  uint32_t len = 0;
+  while (fmt[len] != 0 && fmt[len] != '%') {
+    len++;
+  }
+  return osdi_plusarg(handle, fmt, len);
+}
+
+static char plusarg_conversion(char *fmt) {
+  while (*fmt != 0 && *fmt != '%') {
+    fmt++;
+  }
+  return *fmt == '%' ? fmt[1] : 0;
+}
+
+static bool is_real_conversion(char conv) {
+  return conv == 'e' || conv == 'E' || conv == 'f' || conv == 'F' ||
+         conv == 'g' || conv == 'G';
+}
+
+static int plusarg_base(char conv) {
+  switch (conv) {
+  case 'h':
+  case 'H':
+  case 'x':
+  case 'X':
+    return 16;
+  case 'o':
+  case 'O':
+    return 8;
+  case 'b':
+  case 'B':
+    return 2;
+  default:
+    return 10;
+  }
+}
+
+// The parse_plusarg functions return 0 if the plusarg is missing or can not be
+// parsed. Otherwise the value is written to dst and 1 is returned.
+
+static int32_t parse_plusarg_integer(void *handle, char *fmt, int32_t *dst) {
+  char *val = find_plusarg(handle, fmt);
+  if (val == NULL) {
+    return 0;
+  }
+  char conv = plusarg_conversion(fmt);
+  char *end;
+  int32_t res;
+  if (is_real_conversion(conv)) {
+    res = (int32_t)strtod(val, &end);
+  } else {
+    res = (int32_t)strtol(val, &end, plusarg_base(conv));
+  }
+  if (end == val) {
+    return 0;
+  }
+  *dst = res;
+  return 1;
+}
+
+static int32_t parse_plusarg_real(void *handle, char *fmt, double *dst) {
+  char *val = find_plusarg(handle, fmt);
+  if (val == NULL) {
+    return 0;
+  }
+  char conv = plusarg_conversion(fmt);
+  char *end;
+  double res;
+  if (is_real_conversion(conv) || conv == 0) {
+    res = strtod(val, &end);
+  } else {
+    res = (double)strtol(val, &end, plusarg_base(conv));
+  }
+  if (end == val) {
+    return 0;
+  }
+  *dst = res;
+  return 1;
+}
+
+int32_t test_plusargs(void *handle, char *fmt) {
+  return find_plusarg(handle, fmt) != NULL;
+}
+
+// The value_plusargs functions return the old value if the plusarg is missing
+// or can not be parsed. The parse_plusargs functions return whether the value
+// was found and could be parsed (the return value of $value$plusargs).
+
+int32_t value_plusargs_integer(void *handle, char *fmt, int32_t old) {
+  parse_plusarg_integer(handle, fmt, &old);
+  return old;
+}
+
+int32_t parse_plusargs_integer(void *handle, char *fmt) {
+  int32_t val;
+  return parse_plusarg_integer(handle, fmt, &val);
+}
+
+double value_plusargs_real(void *handle, char *fmt, double old) {
+  parse_plusarg_real(handle, fmt, &old);
+  return old;
+}
+
+int32_t parse_plusargs_real(void *handle, char *fmt) {
+  double val;
+  return parse_plusarg_real(handle, fmt, &val);
+}
+
+char *value_plusargs_string(void *handle, char *fmt, char *old) {
+  char *val = find_plusarg(handle, fmt);
+  return val == NULL ? old : val;
+}
+
+int32_t parse_plusargs_string(void *handle, char *fmt) {
+  return test_plusargs(handle, fmt);
+}
+
 void push_error(OsdiInitError **dst, uint32_t *len, uint32_t *cap,
                 OsdiInitError err) {
   if (*dst == NULL) {
@@ -159,6 +333,8 @@ char *fmt_binary(int val) {
 
 void lim_discontinuity(int *flags) { *flags |= EVAL_RET_FLAG_LIM; }
 
+void discontinuity(int *flags) { *flags |= EVAL_RET_FLAG_DISCONTINUITY; }
+
 double store_lim(void *sim_info_, int idx, double val) {
   OsdiSimInfo *sim_info = (OsdiSimInfo *)sim_info_;
   sim_info->next_state[idx] = val;
@@ -186,3 +362,599 @@ double store_delay(void *sim_info_, double *dst, double val) {
 
   return *dst;
 }
+
+// Number of entries allocated for the history of an absdelay operator when the
+// first timestep is recorded. The buffer doubles in size whenever it is full.
+#define DELAY_BUFFER_INIT_CAP 64
+
+// History of an absdelay operator. The entries form a ring buffer that is sorted
+// by time. The buffer is allocated when the first value is recorded (the instance
+// data is zero initialized) and released by osdi_free_delay_buffers.
+// Must match the layout of osdi_delay_buffer in inst_data.rs
+typedef struct DelayBuffer {
+  uint32_t start;
+  uint32_t len;
+  uint32_t cap;
+  double *time;
+  double *val;
+} DelayBuffer;
+
+static uint32_t delay_buffer_idx(DelayBuffer *buf, uint32_t i) {
+  return (buf->start + i) % buf->cap;
+}
+
+// Doubles the capacity of a full buffer. Returns false if the allocation failed.
+static bool delay_buffer_grow(DelayBuffer *buf) {
+  uint32_t cap = buf->cap == 0 ? DELAY_BUFFER_INIT_CAP : 2 * buf->cap;
+  double *time = malloc(cap * sizeof(double));
+  double *val = malloc(cap * sizeof(double));
+  if (time == NULL || val == NULL || cap < buf->cap) {
+    free(time);
+    free(val);
+    return 0;
+  }
+
+  for (uint32_t i = 0; i < buf->len; i++) {
+    uint32_t idx = delay_buffer_idx(buf, i);
+    time[i] = buf->time[idx];
+    val[i] = buf->val[idx];
+  }
+  free(buf->time);
+  free(buf->val);
+  buf->time = time;
+  buf->val = val;
+  buf->start = 0;
+  buf->cap = cap;
+  return 1;
+}
+
+void osdi_free_delay_buffers(DelayBuffer *bufs, uint32_t len) {
+  for (uint32_t i = 0; i < len; i++) {
+    free(bufs[i].time);
+    free(bufs[i].val);
+    bufs[i] = (DelayBuffer){0};
+  }
+}
+
+double absdelay(void *sim_info_, void *handle, uint32_t *flags, DelayBuffer *buf,
+                double val, double delay, double max_delay) {
+  OsdiSimInfo *sim_info = (OsdiSimInfo *)sim_info_;
+  double time = sim_info->abstime;
+
+  // entries at (or after) the current time belong to previous newton
+  // iterations or rejected timesteps
+  while (buf->len > 0 &&
+         buf->time[delay_buffer_idx(buf, buf->len - 1)] >= time) {
+    buf->len--;
+  }
+
+  // entries older than the maximum delay are no longer required (one is kept
+  // for interpolation)
+  while (buf->len > 1 &&
+         buf->time[delay_buffer_idx(buf, 1)] <= time - max_delay) {
+    buf->start = delay_buffer_idx(buf, 1);
+    buf->len--;
+  }
+
+  if (buf->len == buf->cap && !delay_buffer_grow(buf)) {
+    // dropping entries would silently produce wrong results
+    *flags |= EVAL_RET_FLAG_FATAL;
+    osdi_log(handle,
+             "absdelay: failed to allocate memory for the history of the input",
+             LOG_LVL_FATAL);
+    return val;
+  }
+
+  uint32_t last = delay_buffer_idx(buf, buf->len);
+  buf->time[last] = time;
+  buf->val[last] = val;
+  buf->len++;
+
+  if (!(sim_info->flags & ANALYSIS_TRAN) || delay <= 0.0) {
+    return val;
+  }
+
+  // find the last entry before the delayed time
+  double delayed_time = time - delay;
+  uint32_t i = buf->len - 1;
+  while (i > 0 && buf->time[delay_buffer_idx(buf, i)] > delayed_time) {
+    i--;
+  }
+
+  uint32_t lo = delay_buffer_idx(buf, i);
+  if (buf->time[lo] >= delayed_time) {
+    // the delayed time lies before the start of the simulation
+    return buf->val[lo];
+  }
+
+  uint32_t hi = delay_buffer_idx(buf, i + 1);
+  double fac = (delayed_time - buf->time[lo]) / (buf->time[hi] - buf->time[lo]);
+  return buf->val[lo] + fac * (buf->val[hi] - buf->val[lo]);
+}
+
+// Must match NUM_FILES in inst_data.rs
+#define OSDI_NUM_FILES 32
+// set for file descriptors, unset for multi channel descriptors
+#define FD_BIT 0x80000000u
+#define FD_STDOUT (FD_BIT | 1)
+#define FD_STDERR (FD_BIT | 2)
+
+// Marks initialized file tables (see osdi_init_files)
+#define OSDI_FILES_INIT 0x4f534446u
+
+// Message of a $fstrobe or $fmonitor call that is written at the end of the
+// timestep in which it was created (once a later timestep is evaluated).
+typedef struct OsdiStrobe {
+  // pending message or NULL
+  char *msg;
+  // last message written by $fmonitor
+  char *last;
+  double time;
+  uint32_t fd;
+  uint32_t monitor;
+} OsdiStrobe;
+
+// Files opened by an instance. Channel 0 of the multi channel descriptors and the
+// file descriptors 0-2 are reserved for the standard streams.
+// Must match the layout of osdi_files in inst_data.rs
+typedef struct OsdiFiles {
+  void *mcd[OSDI_NUM_FILES];
+  void *fd[OSDI_NUM_FILES];
+  uint32_t init;
+  uint32_t num_strobes;
+  OsdiStrobe strobes[];
+} OsdiFiles;
+
+static void osdi_write_strobe(void *handle, OsdiFiles *files, OsdiStrobe *strobe);
+
+// Called by setup_instance before any file is accessed. Files that were opened
+// before (setup_instance may be called multiple times) are kept open.
+void osdi_init_files(OsdiFiles *files, uint32_t num_strobes) {
+  if (files->init == OSDI_FILES_INIT) {
+    return;
+  }
+  for (uint32_t i = 0; i < OSDI_NUM_FILES; i++) {
+    files->mcd[i] = NULL;
+    files->fd[i] = NULL;
+  }
+  for (uint32_t i = 0; i < num_strobes; i++) {
+    files->strobes[i] = (OsdiStrobe){0};
+  }
+  files->num_strobes = num_strobes;
+  files->init = OSDI_FILES_INIT;
+}
+
+// Called when an instance is freed: writes all pending strobes and closes all
+// files that are still open.
+void osdi_free_files(void *handle, OsdiFiles *files) {
+  if (files->init != OSDI_FILES_INIT) {
+    return;
+  }
+  for (uint32_t i = 0; i < files->num_strobes; i++) {
+    OsdiStrobe *strobe = &files->strobes[i];
+    if (strobe->msg != NULL) {
+      osdi_write_strobe(handle, files, strobe);
+    }
+    free(strobe->last);
+    strobe->last = NULL;
+  }
+  for (uint32_t i = 0; i < OSDI_NUM_FILES; i++) {
+    if (files->mcd[i] != NULL) {
+      fclose(files->mcd[i]);
+      files->mcd[i] = NULL;
+    }
+    if (files->fd[i] != NULL) {
+      fclose(files->fd[i]);
+      files->fd[i] = NULL;
+    }
+  }
+  files->init = 0;
+}
+
+uint32_t osdi_fopen(OsdiFiles *files, char *name, char *mode) {
+  bool is_mcd = mode[0] == '\0';
+  void **table = is_mcd ? files->mcd : files->fd;
+  // the highest bit of a multi channel descriptor is reserved for FD_BIT
+  uint32_t start = is_mcd ? 1 : 3;
+  uint32_t end = is_mcd ? OSDI_NUM_FILES - 1 : OSDI_NUM_FILES;
+  for (uint32_t i = start; i < end; i++) {
+    if (table[i] == NULL) {
+      void *file = fopen(name, is_mcd ? "w" : mode);
+      if (file == NULL) {
+        return 0;
+      }
+      table[i] = file;
+      return is_mcd ? 1u << i : FD_BIT | i;
+    }
+  }
+  return 0;
+}
+
+// Returns the file of a file descriptor or NULL if the descriptor is invalid
+static void *osdi_fd_file(OsdiFiles *files, uint32_t fd) {
+  uint32_t idx = fd & ~FD_BIT;
+  if (idx < OSDI_NUM_FILES) {
+    return files->fd[idx];
+  }
+  return NULL;
+}
+
+void osdi_fclose(void *handle, OsdiFiles *files, uint32_t fd) {
+  // the end of the timestep is not known in advance, so pending strobes are
+  // written before their file is closed
+  for (uint32_t i = 0; i < files->num_strobes; i++) {
+    OsdiStrobe *strobe = &files->strobes[i];
+    bool same_file = (strobe->fd & FD_BIT) ? strobe->fd == fd
+                                            : !(fd & FD_BIT) && (strobe->fd & fd);
+    if (strobe->msg != NULL && same_file) {
+      osdi_write_strobe(handle, files, strobe);
+    }
+  }
+
+  if (fd & FD_BIT) {
+    void *file = osdi_fd_file(files, fd);
+    if (file != NULL) {
+      fclose(file);
+      files->fd[fd & ~FD_BIT] = NULL;
+    }
+    return;
+  }
+  for (uint32_t i = 1; i < OSDI_NUM_FILES - 1; i++) {
+    if ((fd & (1u << i)) && files->mcd[i] != NULL) {
+      fclose(files->mcd[i]);
+      files->mcd[i] = NULL;
+    }
+  }
+}
+
+// A descriptor of zero flushes all files
+void osdi_fflush(OsdiFiles *files, uint32_t fd) {
+  if (fd & FD_BIT) {
+    void *file = osdi_fd_file(files, fd);
+    if (file != NULL) {
+      fflush(file);
+    }
+    return;
+  }
+  for (uint32_t i = 0; i < OSDI_NUM_FILES; i++) {
+    if (files->mcd[i] != NULL && (fd == 0 || (fd & (1u << i)))) {
+      fflush(files->mcd[i]);
+    }
+    if (files->fd[i] != NULL && fd == 0) {
+      fflush(files->fd[i]);
+    }
+  }
+}
+
+// Writes a formatted message (created by a $fdisplay callback) to the files of a
+// descriptor. Output to the standard streams is passed to osdi_log which takes
+// ownership of msg.
+void osdi_write_file(void *handle, OsdiFiles *files, uint32_t fd, char *msg,
+                     uint32_t flags) {
+  if (flags & LOG_FMT_ERR) {
+    // msg is the format literal
+    osdi_log(handle, msg, LOG_LVL_ERR | LOG_FMT_ERR);
+    return;
+  }
+
+  if (fd == FD_STDERR) {
+    osdi_log(handle, msg, LOG_LVL_ERR);
+    return;
+  }
+
+  bool to_stdout = fd == FD_STDOUT || (!(fd & FD_BIT) && (fd & 1));
+  if (fd & FD_BIT) {
+    void *file = osdi_fd_file(files, fd);
+    if (file != NULL) {
+      fputs(msg, file);
+    }
+  } else {
+    for (uint32_t i = 1; i < OSDI_NUM_FILES; i++) {
+      if ((fd & (1u << i)) && files->mcd[i] != NULL) {
+        fputs(msg, files->mcd[i]);
+      }
+    }
+  }
+
+  if (to_stdout) {
+    osdi_log(handle, msg, LOG_LVL_DISPLAY);
+  } else {
+    free(msg);
+  }
+}
+
+// Writes the pending message of a strobe. $fmonitor only writes messages that
+// differ from the previous one.
+static void osdi_write_strobe(void *handle, OsdiFiles *files, OsdiStrobe *strobe) {
+  char *msg = strobe->msg;
+  strobe->msg = NULL;
+  if (strobe->monitor) {
+    if (strobe->last != NULL && strcmp(strobe->last, msg) == 0) {
+      free(msg);
+      return;
+    }
+    size_t len = strlen(msg) + 1;
+    char *last = malloc(len);
+    if (last != NULL) {
+      memcpy(last, msg, len);
+    }
+    free(strobe->last);
+    strobe->last = last;
+  }
+  osdi_write_file(handle, files, strobe->fd, msg, 0);
+}
+
+// Stores the message of a $fstrobe/$fmonitor call (created by a callback like
+// osdi_write_file). Messages of previous newton iterations and rejected
+// timesteps are replaced, those of previous timesteps are written.
+void osdi_strobe_file(void *handle, OsdiFiles *files, void *sim_info_, uint32_t slot,
+                      uint32_t monitor, uint32_t fd, char *msg, uint32_t flags) {
+  if (flags & LOG_FMT_ERR) {
+    // msg is the format literal
+    osdi_log(handle, msg, LOG_LVL_ERR | LOG_FMT_ERR);
+    return;
+  }
+
+  double time = ((OsdiSimInfo *)sim_info_)->abstime;
+  OsdiStrobe *strobe = &files->strobes[slot];
+  if (strobe->msg != NULL) {
+    if (strobe->time < time) {
+      osdi_write_strobe(handle, files, strobe);
+    } else {
+      free(strobe->msg);
+    }
+  }
+  strobe->msg = msg;
+  strobe->time = time;
+  strobe->fd = fd;
+  strobe->monitor = monitor;
+}
+
+// Random number generators of $random, $arandom, $dist_* and $rdist_*.
+// The algorithms are those of IEEE 1364-2005 (section 17.9.3) so that results
+// match other Verilog simulators for the same seed.
+
+// Must match the order of hir_lower::Distribution
+enum OsdiDistribution {
+  DIST_UNIFORM,
+  DIST_NORMAL,
+  DIST_EXPONENTIAL,
+  DIST_POISSON,
+  DIST_CHI_SQUARE,
+  DIST_T,
+  DIST_ERLANG,
+};
+
+// Provided by the simulator to generate the values of $arandom. May update the
+// seed. global is zero if all instances require a separate value.
+typedef int32_t (*osdi_arandom_ptr)(void *handle, int32_t *seed, uint32_t global);
+extern osdi_arandom_ptr osdi_arandom;
+
+static double rand_uniform(int32_t *seed, double start, double end) {
+  union {
+    float s;
+    unsigned int stemp;
+  } u;
+  double d = 0.00000011920928955078125;
+  double a, b, c;
+
+  if (*seed == 0) {
+    *seed = 259341593;
+  }
+  if (start >= end) {
+    a = 0.0;
+    b = 2147483647.0;
+  } else {
+    a = start;
+    b = end;
+  }
+  *seed = (int32_t)(69069u * (unsigned int)*seed + 1u);
+  u.stemp = (unsigned int)*seed;
+  u.stemp = (u.stemp >> 9) | 0x3f800000;
+  c = (double)u.s;
+  c = c + (c * d);
+  c = ((b - a) * (c - 1.0)) + a;
+  return c;
+}
+
+static double rand_normal(int32_t *seed, double mean, double deviation) {
+  double v1 = 0.0, v2, s = 1.0;
+  while ((s >= 1.0) || (s == 0.0)) {
+    v1 = rand_uniform(seed, -1.0, 1.0);
+    v2 = rand_uniform(seed, -1.0, 1.0);
+    s = v1 * v1 + v2 * v2;
+  }
+  s = v1 * sqrt(-2.0 * log(s) / s);
+  return deviation * s + mean;
+}
+
+static double rand_exponential(int32_t *seed, double mean) {
+  double n = rand_uniform(seed, 0.0, 1.0);
+  if (n != 0.0) {
+    n = -log(n) * mean;
+  }
+  return n;
+}
+
+static double rand_poisson(int32_t *seed, double mean) {
+  int32_t n = 0;
+  double p = exp(-mean), q = 1.0;
+  while (1) {
+    q = q * rand_uniform(seed, 0.0, 1.0);
+    if (p >= q) {
+      return n;
+    }
+    n++;
+  }
+}
+
+static double rand_chi_square(int32_t *seed, int32_t deg_of_free) {
+  double x = 0.0;
+  if (deg_of_free % 2) {
+    x = rand_normal(seed, 0.0, 1.0);
+    x = x * x;
+  }
+  for (int32_t k = 2; k <= deg_of_free; k += 2) {
+    x = x + 2 * rand_exponential(seed, 1.0);
+  }
+  return x;
+}
+
+static double rand_t(int32_t *seed, int32_t deg_of_free) {
+  double chi2 = rand_chi_square(seed, deg_of_free);
+  double root = sqrt(chi2 / (double)deg_of_free);
+  return rand_normal(seed, 0.0, 1.0) / root;
+}
+
+static double rand_erlang(int32_t *seed, int32_t k, double mean) {
+  double x = 1.0;
+  for (int32_t i = 1; i <= k; i++) {
+    x = x * rand_uniform(seed, 0.0, 1.0);
+  }
+  return -mean * log(x) / (double)k;
+}
+
+static int32_t rand_round(double r) {
+  if (r >= 0) {
+    return (int32_t)(r + 0.5);
+  }
+  return -(int32_t)(-r + 0.5);
+}
+
+static int32_t rand_dist_uniform(int32_t *seed, int32_t start, int32_t end) {
+  double r;
+  int32_t i;
+  if (start >= end) {
+    return start;
+  }
+  if (end != 2147483647) {
+    end++;
+    r = rand_uniform(seed, start, end);
+    i = r >= 0 ? (int32_t)r : (int32_t)(r - 1);
+    if (i < start) {
+      i = start;
+    }
+    if (i >= end) {
+      i = end - 1;
+    }
+  } else if (start != -2147483647 - 1) {
+    start--;
+    r = rand_uniform(seed, start, end) + 1.0;
+    i = r >= 0 ? (int32_t)r : (int32_t)(r - 1);
+    if (i <= start) {
+      i = start + 1;
+    }
+    if (i > end) {
+      i = end;
+    }
+  } else {
+    r = (rand_uniform(seed, start, end) + 2147483648.0) / 4294967295.0;
+    r = r * 4294967296.0 - 2147483648.0;
+    i = r >= 0 ? (int32_t)r : (int32_t)(r - 1);
+  }
+  return i;
+}
+
+// Draws a value of a distribution. Invalid parameters produce zero.
+static double rand_dist(int32_t *seed, uint32_t dist, double a, double b) {
+  switch (dist) {
+  case DIST_UNIFORM:
+    return rand_uniform(seed, a, b);
+  case DIST_NORMAL:
+    return rand_normal(seed, a, b);
+  case DIST_EXPONENTIAL:
+    return a > 0 ? rand_exponential(seed, a) : 0.0;
+  case DIST_POISSON:
+    return a > 0 ? rand_poisson(seed, a) : 0.0;
+  case DIST_CHI_SQUARE:
+    return a > 0 ? rand_chi_square(seed, (int32_t)a) : 0.0;
+  case DIST_T:
+    return a > 0 ? rand_t(seed, (int32_t)a) : 0.0;
+  case DIST_ERLANG:
+    return a > 0 ? rand_erlang(seed, (int32_t)a, b) : 0.0;
+  default:
+    return 0.0;
+  }
+}
+
+// The generators are started from seed and the updated seed is stored in slot
+// (the seed of the call in the instance or model data).
+//
+// For the "instance" type string id points to the id of the instance (stored
+// in the instance data) which is mixed into the seed so that each instance
+// draws separate values. id is NULL for global random numbers.
+
+static uint32_t osdi_num_random_instances = 0;
+
+static int32_t instance_seed(uint32_t *id, int32_t seed) {
+  if (id == NULL) {
+    return seed;
+  }
+  // instance data is zero initialized so ids are assigned on first use
+  if (*id == 0) {
+    *id = __atomic_add_fetch(&osdi_num_random_instances, 1, __ATOMIC_RELAXED);
+  }
+  return seed ^ (int32_t)(*id * 2654435761u);
+}
+
+int32_t osdi_random(int32_t *slot, int32_t seed) {
+  int32_t res = rand_dist_uniform(&seed, -2147483647 - 1, 2147483647);
+  *slot = seed;
+  return res;
+}
+
+int32_t osdi_arandom_next(void *handle, int32_t *slot, uint32_t *id,
+                          uint32_t global, int32_t seed) {
+  if (osdi_arandom == NULL) {
+    return osdi_random(slot, instance_seed(id, seed));
+  }
+  int32_t res = osdi_arandom(handle, &seed, global);
+  *slot = seed;
+  return res;
+}
+
+int32_t osdi_dist(int32_t *slot, uint32_t *id, uint32_t dist, int32_t seed,
+                  int32_t a, int32_t b) {
+  int32_t res;
+  seed = instance_seed(id, seed);
+  if (dist == DIST_UNIFORM) {
+    res = rand_dist_uniform(&seed, a, b);
+  } else {
+    res = rand_round(rand_dist(&seed, dist, a, b));
+  }
+  *slot = seed;
+  return res;
+}
+
+double osdi_rdist(int32_t *slot, uint32_t *id, uint32_t dist, int32_t seed,
+                  double a, double b) {
+  seed = instance_seed(id, seed);
+  double res = rand_dist(&seed, dist, a, b);
+  *slot = seed;
+  return res;
+}
+
+int32_t osdi_read_seed(int32_t *slot) { return *slot; }
+
+// returns the region of a $table_model dimension that contains x: 0 below the
+// first breakpoint, i from breakpoint i - 1 up to breakpoint i and len above the
+// last breakpoint (the last breakpoint itself still belongs to region len - 1)
+int32_t osdi_table_lookup(const double *breakpoints, uint32_t len, double x) {
+  // NaN is placed above the table
+  if (!(x <= breakpoints[len - 1])) {
+    return len;
+  }
+  uint32_t lo = 0;
+  uint32_t hi = len - 1;
+  while (lo < hi) {
+    uint32_t mid = lo + (hi - lo) / 2;
+    if (breakpoints[mid] <= x) {
+      lo = mid + 1;
+    } else {
+      hi = mid;
+    }
+  }
+  return lo;
+}
+
+double osdi_table_value(const double *vals, int32_t idx) { return vals[idx]; }
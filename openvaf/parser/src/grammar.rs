@@ -51,6 +51,10 @@ pub(crate) fn source_file(p: &mut Parser) {
                 error_range.take();
                 items::module(p, m)
             }
+            PARAMSET_KW => {
+                error_range.take();
+                items::paramset(p, m)
+            }
             _ => {
                 error_range = if let Some(error_range) = error_range {
                     m.abandon(p);
@@ -60,7 +64,12 @@ pub(crate) fn source_file(p: &mut Parser) {
                     }
                     Some(error_range.undo_completion(p).complete(p, ERROR))
                 } else {
-                    let err = p.unexpected_tokens_msg(vec![DISCIPLINE_KW, NATURE_KW, MODULE_KW]);
+                    let err = p.unexpected_tokens_msg(vec![
+                        DISCIPLINE_KW,
+                        NATURE_KW,
+                        MODULE_KW,
+                        PARAMSET_KW,
+                    ]);
                     p.error(err);
                     p.bump_any();
                     while !p.at_ts(ITEM_RECOVERY_SET) {
@@ -101,7 +101,7 @@ fn atom_expr(p: &mut Parser) -> Option<CompletedMarker> {
 
     let done = match p.current() {
         T!['('] => paren_expr(p),
-        // T!["'{"] => array_expr(p), TODO properly implement arrays
+        T!["'{"] => array_expr(p),
         T![~] | T![!] | T![-] | T![+] => {
             let m = p.start();
             p.bump_ts(TokenSet::new(&[T![~], T![!], T![-], T![+]]));
@@ -114,7 +114,12 @@ fn atom_expr(p: &mut Parser) -> Option<CompletedMarker> {
                 call(p, m)
             } else {
                 let m = m.precede(p);
-                m.complete(p, PATH_EXPR)
+                let path = m.complete(p, PATH_EXPR);
+                if p.at(T!['[']) {
+                    index_expr(p, path)
+                } else {
+                    path
+                }
             }
         }
         SYSFUN => sys_fun_call(p),
@@ -159,21 +164,27 @@ fn paren_expr(p: &mut Parser) -> CompletedMarker {
     m.complete(p, PAREN_EXPR)
 }
 
-// fn array_expr(p: &mut Parser) -> CompletedMarker {
-//     let m = p.start();
-//     p.bump(T!["'{"]);
-//     while !p.at(EOF) && !p.at(T![']']) {
-//         // test array_attrs
-//         // const A: &[i64] = &[1, #[cfg(test)] 2];
-//         if expr(p).is_none() {
-//             break;
-//         }
-
-//         if !p.at(T!['}']) && !p.expect(T![,]) {
-//             break;
-//         }
-//     }
-//     p.expect(T!['}']);
-
-//     m.complete(p, ARRAY_EXPR)
-// }
+fn index_expr(p: &mut Parser, base: CompletedMarker) -> CompletedMarker {
+    let m = base.precede(p);
+    p.bump(T!['[']);
+    expr(p);
+    p.expect(T![']']);
+    m.complete(p, INDEX_EXPR)
+}
+
+fn array_expr(p: &mut Parser) -> CompletedMarker {
+    let m = p.start();
+    p.bump(T!["'{"]);
+    while !p.at(EOF) && !p.at(T!['}']) {
+        if expr(p).is_none() {
+            break;
+        }
+
+        if !p.at(T!['}']) && !p.expect(T![,]) {
+            break;
+        }
+    }
+    p.expect(T!['}']);
+
+    m.complete(p, ARRAY_EXPR)
+}
@@ -5,7 +5,7 @@ pub(super) use module::module;
 use module::MODULE_ITEM_OR_ATTR_RECOVERY;
 
 pub(super) const ITEM_RECOVERY_SET: TokenSet =
-    TokenSet::new(&[DISCIPLINE_KW, NATURE_KW, MODULE_KW, EOF]);
+    TokenSet::new(&[DISCIPLINE_KW, NATURE_KW, MODULE_KW, PARAMSET_KW, EOF]);
 
 const DISCIPLINE_RECOVERY_SET: TokenSet =
     ITEM_RECOVERY_SET.union(TokenSet::unique(ENDDISCIPLINE_KW));
@@ -54,6 +54,62 @@ pub(super) fn nature(p: &mut Parser, m: Marker) {
     m.complete(p, NATURE_DECL);
 }
 
+const PARAMSET_RECOVERY_SET: TokenSet = ITEM_RECOVERY_SET.union(TokenSet::unique(ENDPARAMSET_KW));
+const PARAMSET_ITEM_RECOVERY: TokenSet =
+    PARAMSET_RECOVERY_SET.union(TokenSet::new(&[PARAMETER_KW, LOCALPARAM_KW, T![.], T!["(*"]]));
+
+pub(super) fn paramset(p: &mut Parser, m: Marker) {
+    p.bump(T![paramset]);
+    name_r(p, TokenSet::new(&[IDENT, T![;]]));
+    name_ref_r(p, TokenSet::unique(T![;]));
+    p.expect(T![;]);
+    let mut error_range: Option<CompletedMarker> = None;
+    while !p.at_ts(PARAMSET_RECOVERY_SET) {
+        let m = p.start();
+        attrs(p, PARAMSET_ITEM_RECOVERY);
+        match p.current() {
+            PARAMETER_KW | LOCALPARAM_KW => {
+                error_range.take();
+                parameter_decl(p, m)
+            }
+            T![.] => {
+                error_range.take();
+                paramset_stmt(p, m)
+            }
+            _ => {
+                error_range = if let Some(error_range) = error_range {
+                    m.abandon(p);
+                    p.bump_any();
+                    while !p.at_ts(PARAMSET_ITEM_RECOVERY) {
+                        p.bump_any();
+                    }
+                    Some(error_range.undo_completion(p).complete(p, ERROR))
+                } else {
+                    let err = p.unexpected_tokens_msg(vec![PARAM_DECL, PARAMSET_STMT]);
+                    p.error(err);
+                    p.bump_any();
+                    while !p.at_ts(PARAMSET_ITEM_RECOVERY) {
+                        p.bump_any();
+                    }
+                    Some(m.complete(p, ERROR))
+                }
+            }
+        }
+    }
+    p.expect(ENDPARAMSET_KW);
+    m.complete(p, PARAMSET_DECL);
+}
+
+fn paramset_stmt(p: &mut Parser, m: Marker) {
+    p.bump(T![.]);
+    parameter(p);
+    if !p.eat(T![;]) {
+        let err = p.unexpected_token_msg(T![;]);
+        p.err_recover(err, PARAMSET_ITEM_RECOVERY);
+    }
+    m.complete(p, PARAMSET_STMT);
+}
+
 pub(super) fn decl_list(
     p: &mut Parser,
     terminator: SyntaxKind,
@@ -86,7 +142,8 @@ pub(super) fn var_decl(p: &mut Parser, m: Marker) {
 
 fn var(p: &mut Parser) -> bool {
     let m = p.start();
-    name_r(p, TokenSet::new(&[T![,], T![=], T![;]]));
+    name_r(p, TokenSet::new(&[T![,], T![=], T![;], T!['[']]));
+    array_dim(p);
     if p.eat(T![=]) {
         expr(p);
     }
@@ -105,7 +162,8 @@ pub(super) fn parameter_decl(p: &mut Parser, m: Marker) {
 const PARAM_RECOVER: TokenSet = MODULE_ITEM_OR_ATTR_RECOVERY.union(TokenSet::new(&[T![,], T![;]]));
 fn parameter(p: &mut Parser) -> bool {
     let m = p.start();
-    name_r(p, TokenSet::new(&[T![,], T![;]]));
+    name_r(p, TokenSet::new(&[T![,], T![;], T!['[']]));
+    array_dim(p);
     p.expect(T![=]);
     expr(p);
     while !p.at_ts(PARAM_RECOVER) {
@@ -115,6 +173,20 @@ fn parameter(p: &mut Parser) -> bool {
     true
 }
 
+/// Parses the optional `[msb:lsb]` dimension of an array variable/parameter or a vector net
+fn array_dim(p: &mut Parser) {
+    if !p.at(T!['[']) {
+        return;
+    }
+    let m = p.start();
+    p.bump(T!['[']);
+    expr(p);
+    p.expect(T![:]);
+    expr(p);
+    p.expect(T![']']);
+    m.complete(p, RANGE);
+}
+
 fn constraint(p: &mut Parser) {
     let m = p.start();
     if !p.expect_ts_r(TokenSet::new(&[FROM_KW, EXCLUDE_KW]), PARAM_RECOVER) {
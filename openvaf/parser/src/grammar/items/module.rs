@@ -1,5 +1,5 @@
 use super::*;
-use crate::grammar::stmts::{STMT_RECOVER, STMT_TS};
+use crate::grammar::stmts::{expr_or_assign_stmt, STMT_RECOVER, STMT_TS};
 
 const MODULE_ITEM_RECOVERY: TokenSet = DIRECTION_TS.union(TokenSet::new(&[
     NET_TYPE,
@@ -11,6 +11,11 @@ const MODULE_ITEM_RECOVERY: TokenSet = DIRECTION_TS.union(TokenSet::new(&[
     INTEGER_KW,
     PARAMETER_KW,
     LOCALPARAM_KW,
+    GENVAR_KW,
+    GENERATE_KW,
+    ENDGENERATE_KW,
+    FOR_KW,
+    END_KW,
     ENDMODULE_KW,
     EOF,
 ]));
@@ -27,7 +32,7 @@ pub(crate) fn module(p: &mut Parser, m: Marker) {
         m.complete(p, MODULE_PORTS);
     }
     p.expect(T![;]);
-    module_items(p);
+    module_items(p, ENDMODULE_KW);
 
     p.expect(ENDMODULE_KW);
 
@@ -82,6 +87,7 @@ fn port_decl<const MODULE_HEAD: bool>(p: &mut Parser, m: Marker) {
         eat_name_ref(p);
     }
     p.eat(NET_TYPE);
+    array_dim(p);
 
     if MODULE_HEAD {
         decl_list(p, T![')'], module_port, MODULE_PORT_RECOVERY);
@@ -102,9 +108,9 @@ fn module_port(p: &mut Parser) -> bool {
     !(p.at(T![,]) && p.nth_at_ts(1, MODULE_PORT_RECOVERY))
 }
 
-fn module_items(p: &mut Parser) {
+fn module_items(p: &mut Parser, end: SyntaxKind) {
     let mut error_range: Option<CompletedMarker> = None;
-    while !p.at_ts(ITEM_RECOVERY_SET.union(TokenSet::unique(ENDMODULE_KW))) {
+    while !p.at_ts(ITEM_RECOVERY_SET.union(TokenSet::new(&[ENDMODULE_KW, end]))) {
         let m = p.start();
         attrs(p, MODULE_ITEM_RECOVERY);
 
@@ -119,6 +125,9 @@ fn module_items(p: &mut Parser) {
             NET_TYPE => {
                 net_decl::<true>(p, m);
             }
+            IDENT if p.nth_at(1, T![#]) || (p.nth_at(1, IDENT) && p.nth_at(2, T!['('])) => {
+                module_inst(p, m);
+            }
             IDENT => {
                 net_decl::<false>(p, m);
             }
@@ -133,6 +142,9 @@ fn module_items(p: &mut Parser) {
             }
             INTEGER_KW | REAL_KW | STRING_KW => var_decl(p, m),
             INPUT_KW | OUTPUT_KW | INOUT_KW => port_decl::<false>(p, m),
+            GENVAR_KW => genvar_decl(p, m),
+            GENERATE_KW => generate_region(p, m),
+            FOR_KW => generate_for(p, m),
             _ => {
                 error_range = if let Some(error_range) = error_range {
                     m.abandon(p);
@@ -160,6 +172,95 @@ fn module_items(p: &mut Parser) {
     }
 }
 
+fn genvar_decl(p: &mut Parser, m: Marker) {
+    p.bump(GENVAR_KW);
+    decl_list(p, T![;], decl_name, MODULE_ITEM_OR_ATTR_RECOVERY);
+    p.eat(T![;]);
+    m.complete(p, GENVAR_DECL);
+}
+
+fn generate_region(p: &mut Parser, m: Marker) {
+    p.bump(GENERATE_KW);
+    module_items(p, ENDGENERATE_KW);
+    p.expect(ENDGENERATE_KW);
+    m.complete(p, GENERATE_REGION);
+}
+
+fn generate_for(p: &mut Parser, m: Marker) {
+    p.bump(FOR_KW);
+    p.expect(T!['(']);
+
+    // init
+    let stmt = p.start();
+    attrs(p, STMT_RECOVER.union(TokenSet::new(&[IDENT])));
+    expr_or_assign_stmt::<true>(p, stmt);
+
+    // condition
+    expr(p);
+    p.expect(T![;]);
+
+    // incr
+    let stmt = p.start();
+    attrs(p, STMT_RECOVER.union(TokenSet::new(&[IDENT])));
+    expr_or_assign_stmt::<false>(p, stmt);
+
+    p.expect(T![')']);
+
+    p.expect(BEGIN_KW);
+    if p.at(T![:]) {
+        let m = p.start();
+        p.bump(T![:]);
+        name(p);
+        m.complete(p, BLOCK_SCOPE);
+    }
+    module_items(p, END_KW);
+    p.expect(END_KW);
+    m.complete(p, GENERATE_FOR);
+}
+
+const INST_ARGS_RECOVERY: TokenSet = TokenSet::new(&[T![;], T![')'], ENDMODULE_KW, EOF]);
+
+fn module_inst(p: &mut Parser, m: Marker) {
+    name_ref_r(p, TokenSet::new(&[T![#], IDENT]));
+    if p.at(T![#]) {
+        let m = p.start();
+        p.bump(T![#]);
+        inst_args(p);
+        m.complete(p, INST_PARAMS);
+    }
+    name_r(p, TokenSet::new(&[T!['('], T![;]]));
+    let ports = p.start();
+    inst_args(p);
+    ports.complete(p, INST_PORTS);
+    p.expect(T![;]);
+    m.complete(p, MODULE_INST);
+}
+
+/// Parses the parameter overrides or port connections of a module instance. These are either
+/// ordered (`(a, b)`) or named (`(.p(a), .n(b))`).
+fn inst_args(p: &mut Parser) {
+    p.expect(T!['(']);
+    while !p.at_ts(INST_ARGS_RECOVERY) {
+        let m = p.start();
+        if p.eat(T![.]) {
+            name_r(p, TokenSet::new(&[T!['('], T![,], T![')']]));
+            p.expect(T!['(']);
+            if !p.at(T![')']) {
+                expr(p);
+            }
+            p.expect(T![')']);
+        } else if expr(p).is_none() {
+            m.abandon(p);
+            break;
+        }
+        m.complete(p, INST_ARG);
+        if !p.at(T![')']) && !p.expect(T![,]) {
+            break;
+        }
+    }
+    p.expect(T![')']);
+}
+
 fn net_decl<const NET_TYPE_FIRST: bool>(p: &mut Parser, m: Marker) {
     //direction and type ar both optional since only one is required
     if NET_TYPE_FIRST {
@@ -171,6 +272,7 @@ fn net_decl<const NET_TYPE_FIRST: bool>(p: &mut Parser, m: Marker) {
         name_ref_r(p, MODULE_ITEM_OR_ATTR_RECOVERY.union(TokenSet::unique(T![;])))
     }
 
+    array_dim(p);
     net_dec_list(p);
     p.eat(T![;]);
     m.complete(p, NET_DECL);
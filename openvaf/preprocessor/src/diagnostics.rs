@@ -3,19 +3,61 @@ use std::io;
 use stdx::impl_display;
 use vfs::{InvalidTextFormatErr, VfsPath};
 
+use crate::protect::ProtectError;
 use crate::sourcemap::CtxSpan;
 
 #[derive(Debug, PartialEq, Clone, Eq)]
 pub enum PreprocessorDiagnostic {
-    MacroArgumentCountMismatch { expected: usize, found: usize, span: CtxSpan },
-    MacroNotFound { name: String, span: CtxSpan },
-    MacroRecursion { name: String, span: CtxSpan },
-    FileNotFound { file: String, error: io::ErrorKind, span: Option<CtxSpan> },
-    InvalidTextFormat { span: Option<CtxSpan>, file: VfsPath, err: InvalidTextFormatErr },
-    UnexpectedEof { expected: &'static str, span: CtxSpan },
-    MissingOrUnexpectedToken { expected: &'static str, expected_at: CtxSpan, span: CtxSpan },
+    MacroArgumentCountMismatch {
+        expected: usize,
+        found: usize,
+        span: CtxSpan,
+    },
+    MacroNotFound {
+        name: String,
+        span: CtxSpan,
+    },
+    /// `chain` contains the names and call sites of the macros that were being expanded
+    /// (outermost first). `limit` is set if the nesting limit was exceeded without recursion.
+    MacroRecursion {
+        name: String,
+        span: CtxSpan,
+        chain: Vec<(String, CtxSpan)>,
+        limit: Option<usize>,
+    },
+    FileNotFound {
+        file: String,
+        error: io::ErrorKind,
+        span: Option<CtxSpan>,
+    },
+    InvalidTextFormat {
+        span: Option<CtxSpan>,
+        file: VfsPath,
+        err: InvalidTextFormatErr,
+    },
+    UnexpectedEof {
+        expected: &'static str,
+        span: CtxSpan,
+    },
+    MissingOrUnexpectedToken {
+        expected: &'static str,
+        expected_at: CtxSpan,
+        span: CtxSpan,
+    },
     UnexpectedToken(CtxSpan),
-    MacroOverwritten { old: CtxSpan, new: CtxSpan, name: String },
+    MacroOverwritten {
+        old: CtxSpan,
+        new: CtxSpan,
+        name: String,
+    },
+    ProtectedEnvelope {
+        error: ProtectError,
+        span: CtxSpan,
+    },
+    InvalidDirective {
+        expected: &'static str,
+        span: CtxSpan,
+    },
 }
 
 use PreprocessorDiagnostic::*;
@@ -23,12 +65,15 @@ impl_display! {
     match PreprocessorDiagnostic{
         MacroArgumentCountMismatch { expected, found, ..} => "argument mismatch expected {} but found {}!", expected, found;
         MacroNotFound{name,..} =>  "macro '`{}' has not been declared", name;
-        MacroRecursion { name,..} => "macro '`{}' was called recursively",name;
+        MacroRecursion { name, limit: None, ..} => "macro '`{}' was called recursively",name;
+        MacroRecursion { name, limit: Some(limit), ..} => "macro '`{}' exceeds the nesting limit of {} macro expansions", name, limit;
         FileNotFound { file, error, .. } => "failed to read '{}': {}", file, std::io::Error::from(*error);
         InvalidTextFormat {  file, ..} => "failed to read {}: file contents are not valid text", file;
         UnexpectedEof { expected ,..} => "unexpected EOF, expected {}",expected;
         MissingOrUnexpectedToken { expected, ..} => "unexpected token, expected '{}'", expected;
         UnexpectedToken(_) => "encountered unexpected token!";
         MacroOverwritten { name, .. } => "macro '`{}' was overwritten", name;
+        ProtectedEnvelope { error, .. } => "failed to decrypt protected envelope: {}", error;
+        InvalidDirective { .. } => "invalid compiler directive";
     }
 }
@@ -212,9 +212,15 @@ fn parse_macro_token<'a>(
     }
 
     if p.at(PreprocessorToken::CompilerDirective) {
-        if p.compiler_directive() == CompilerDirective::Macro {
+        let directive = p.compiler_directive();
+        if directive == CompilerDirective::Macro {
             let (call, range) = parse_macro_call(p, err, args, sm, end);
             dst.push(ParsedToken { range, kind: ParsedTokenKind::MacroCall(call) });
+        } else if matches!(directive, CompilerDirective::File | CompilerDirective::Line) {
+            // expanded when the macro is called
+            let range = p.current_range();
+            p.bump();
+            dst.push(ParsedToken { range, kind: ParsedTokenKind::BuiltinMacro(directive) });
         } else {
             // TODO nicer error?
             err.push(PreprocessorDiagnostic::UnexpectedToken(CtxSpan {
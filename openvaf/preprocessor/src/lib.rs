@@ -5,12 +5,14 @@ use sourcemap::{CtxSpan, SourceMap};
 use vfs::{FileId, FileReadError, VfsPath};
 
 use crate::processor::Processor;
+pub use crate::protect::{DecryptionKey, ProtectError};
 // use tracing::trace_span;
 
 pub mod diagnostics;
 mod grammar;
 mod parser;
 mod processor;
+mod protect;
 pub mod sourcemap;
 
 mod scoped_arc_arena;
@@ -27,6 +29,29 @@ pub struct Preprocess {
     pub ts: Arc<Vec<Token>>,
     pub sm: Arc<SourceMap>,
     pub diagnostics: Arc<Diagnostics>,
+    /// The compiler directives that are in effect starting at the token with the given index
+    /// (sorted by index). Tokens before the first entry use [`Directives::default`].
+    pub directives: Arc<Vec<(usize, Directives)>>,
+}
+
+/// The state of the compiler directives that affect the source text that follows them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Directives {
+    /// The discipline of nets declared without a discipline (`` `default_discipline ``)
+    pub default_discipline: Option<Arc<str>>,
+    /// The default rise/fall time of `transition` in seconds (`` `default_transition ``)
+    pub default_transition: Option<f64>,
+    /// The time unit of `` `timescale `` in seconds
+    pub time_unit: f64,
+}
+
+// the numbers are always finite
+impl Eq for Directives {}
+
+impl Default for Directives {
+    fn default() -> Directives {
+        Directives { default_discipline: None, default_transition: None, time_unit: 1.0 }
+    }
 }
 
 /// # Panics
@@ -36,10 +61,10 @@ pub fn preprocess(sources: &dyn SourceProvider, file: FileId) -> Preprocess {
     // let _scope = span.enter();
 
     let storage = ScopedTextArea::new();
-    let (ts, diagnostics, sm) = match Processor::new(&storage, file, sources) {
+    let (ts, diagnostics, sm, directives) = match Processor::new(&storage, file, sources) {
         Ok(mut processor) => {
             let (ts, diagnostics) = processor.run(file);
-            (ts, diagnostics, processor.source_map)
+            (ts, diagnostics, processor.source_map, processor.directives)
         }
         Err(FileReadError::Io(error)) => (
             vec![],
@@ -49,6 +74,7 @@ pub fn preprocess(sources: &dyn SourceProvider, file: FileId) -> Preprocess {
                 span: None,
             }],
             SourceMap::new(file, 0.into()),
+            vec![],
         ),
         Err(FileReadError::InvalidTextFormat(err)) => (
             vec![],
@@ -58,15 +84,33 @@ pub fn preprocess(sources: &dyn SourceProvider, file: FileId) -> Preprocess {
                 err,
             }],
             SourceMap::new(file, 0.into()),
+            vec![],
         ),
     };
 
-    Preprocess { ts: Arc::new(ts), diagnostics: Arc::new(diagnostics), sm: Arc::new(sm) }
+    Preprocess {
+        ts: Arc::new(ts),
+        diagnostics: Arc::new(diagnostics),
+        sm: Arc::new(sm),
+        directives: Arc::new(directives),
+    }
 }
 
+/// The default for [`SourceProvider::macro_expansion_limit`]
+pub const DEFAULT_MACRO_EXPANSION_LIMIT: usize = 128;
+
 pub trait SourceProvider {
     fn include_dirs(&self, root_file: FileId) -> Arc<[VfsPath]>;
     fn macro_flags(&self, file_root: FileId) -> Arc<[Arc<str>]>;
+    /// The maximum number of nested macro expansions.
+    /// Macro calls nested deeper than this are reported as recursive.
+    fn macro_expansion_limit(&self, _file_root: FileId) -> usize {
+        DEFAULT_MACRO_EXPANSION_LIMIT
+    }
+    /// The keys used to decrypt protected envelopes (`` `pragma protect ``).
+    fn decryption_keys(&self, _file_root: FileId) -> Arc<[DecryptionKey]> {
+        Arc::new([])
+    }
 
     fn file_text(&self, file: FileId) -> Result<Arc<str>, FileReadError>;
     fn file_path(&self, file: FileId) -> VfsPath;
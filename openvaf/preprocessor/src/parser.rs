@@ -304,9 +304,38 @@ impl<'a, 'd> Parser<'a, 'd> {
             "`else" => CompilerDirective::Else,
             "`elsif" => CompilerDirective::ElseIf,
             "`endif" => CompilerDirective::EndIf,
+            "`undef" => CompilerDirective::Undef,
+            "`undefineall" => CompilerDirective::UndefineAll,
+            "`resetall" => CompilerDirective::ResetAll,
+            "`__FILE__" => CompilerDirective::File,
+            "`__LINE__" => CompilerDirective::Line,
+            "`default_discipline" => CompilerDirective::DefaultDiscipline,
+            "`default_transition" => CompilerDirective::DefaultTransition,
+            "`timescale" => CompilerDirective::Timescale,
+            "`pragma" => CompilerDirective::Pragma,
             _ => CompilerDirective::Macro,
         }
     }
+
+    /// Skips all tokens until the end of the current line.
+    pub(crate) fn bump_line(&mut self) {
+        let line_end = self.src[self.offset.into()..]
+            .find('\n')
+            .map_or(TextSize::of(self.src), |pos| self.offset + TextSize::from(pos as u32));
+        self.bump_to(line_end)
+    }
+
+    /// Skips all tokens that start before `end`.
+    pub(crate) fn bump_to(&mut self, end: TextSize) {
+        while !self.at(PreprocessorToken::Eof) && self.offset < end {
+            self.bump()
+        }
+    }
+
+    /// Returns the source text starting at the current token.
+    pub(crate) fn remaining_text(&self) -> &'a str {
+        &self.src[self.offset.into()..]
+    }
 }
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -330,5 +359,16 @@ pub enum CompilerDirective {
     Else,
     ElseIf,
     EndIf,
+    Undef,
+    UndefineAll,
+    ResetAll,
+    /// `` `__FILE__ `` expands to the path of the current file
+    File,
+    /// `` `__LINE__ `` expands to the current line number
+    Line,
+    DefaultDiscipline,
+    DefaultTransition,
+    Timescale,
+    Pragma,
     Macro,
 }
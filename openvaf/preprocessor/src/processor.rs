@@ -11,12 +11,14 @@ use typed_index_collections::{TiSlice, TiVec};
 use vfs::{FileId, VfsPath};
 
 use crate::diagnostics::PreprocessorDiagnostic::{
-    self, MacroArgumentCountMismatch, MacroNotFound, UnexpectedToken,
+    self, InvalidDirective, MacroArgumentCountMismatch, MacroNotFound, MacroRecursion,
+    ProtectedEnvelope, UnexpectedToken,
 };
 use crate::grammar::{parse_condition, parse_define, parse_include, parse_macro_call};
 use crate::parser::{CompilerDirective, Parser, PreprocessorToken};
-use crate::sourcemap::{CtxSpan, FileSpan, SourceContext, SourceMap};
-use crate::{Diagnostics, FileReadError, ScopedTextArea, SourceProvider, Token};
+use crate::protect::{self, DecryptionKey, ProtectError, BEGIN_PROTECTED, END_PROTECTED};
+use crate::sourcemap::{CtxSpan, FileSpan, MacroDecl, SourceContext, SourceMap};
+use crate::{Diagnostics, Directives, FileReadError, ScopedTextArea, SourceProvider, Token};
 
 pub(crate) struct Processor<'a> {
     pub(crate) source_map: SourceMap,
@@ -24,6 +26,16 @@ pub(crate) struct Processor<'a> {
     arena: &'a ScopedTextArea,
     macros: AHashMap<&'a str, Macro<'a>>,
     include_dirs: Arc<[VfsPath]>,
+    /// The outermost macro call that is currently being expanded.
+    /// `` `__FILE__ `` and `` `__LINE__ `` inside macros refer to this location.
+    expansion_site: CtxSpan,
+    /// The names and call sites of all macros that are currently being expanded
+    /// (innermost last).
+    expansion_stack: Vec<(&'a str, CtxSpan)>,
+    expansion_limit: usize,
+    decryption_keys: Arc<[DecryptionKey]>,
+    /// The compiler directives in effect starting at the output token with the given index.
+    pub(crate) directives: Vec<(usize, Directives)>,
 }
 
 impl<'a> Processor<'a> {
@@ -44,12 +56,21 @@ impl<'a> Processor<'a> {
                 )
             })
             .collect();
+        let mut source_map = SourceMap::new(root_file, TextSize::of(src));
+        for name in sources.macro_flags(root_file).iter() {
+            source_map.add_macro(MacroDecl { name: Box::from(&**name), head: None, arg_cnt: 0 });
+        }
         let res = Self {
-            source_map: SourceMap::new(root_file, TextSize::of(src)),
+            source_map,
             macros,
             arena: storage,
             sources,
             include_dirs: sources.include_dirs(root_file),
+            expansion_site: CtxSpan::dummy(),
+            expansion_stack: Vec::new(),
+            expansion_limit: sources.macro_expansion_limit(root_file),
+            decryption_keys: sources.decryption_keys(root_file),
+            directives: Vec::new(),
         };
         Ok(res)
     }
@@ -114,6 +135,8 @@ impl<'a> Processor<'a> {
         diagnostics: &mut Diagnostics,
     ) {
         let span = def.head_span();
+        let decl = MacroDecl { name: name.into(), head: Some(span), arg_cnt: def.arg_cnt };
+        self.source_map.add_macro(decl);
         if let Some(old) = self.macros.insert(name, def) {
             diagnostics.push(PreprocessorDiagnostic::MacroOverwritten {
                 old: old.head_span(),
@@ -137,9 +160,42 @@ impl<'a> Processor<'a> {
                 dst.extend(&args[arg]);
             }
             ParsedTokenKind::MacroCall(ref call) => self.call_macro(call, span, args, dst, errors),
+            ParsedTokenKind::BuiltinMacro(directive) => {
+                self.expand_builtin_macro(directive, span, self.expansion_site, dst)
+            }
         }
     }
 
+    /// Expands `` `__FILE__ `` or `` `__LINE__ `` (called at `call_site`) for `location`.
+    /// The generated text is stored in the source map so that the resulting token still maps
+    /// back to the directive.
+    fn expand_builtin_macro(
+        &mut self,
+        directive: CompilerDirective,
+        call_site: CtxSpan,
+        location: CtxSpan,
+        dst: &mut Vec<Token>,
+    ) {
+        let location = location.to_file_span(&self.source_map);
+        let (kind, text) = match directive {
+            CompilerDirective::File => {
+                let path = self.sources.file_path(location.file).to_string();
+                let path = path.replace('\\', "\\\\").replace('"', "\\\"");
+                (SyntaxKind::STR_LIT, format!("\"{}\"", path))
+            }
+            CompilerDirective::Line => {
+                let src = location.file_text(self.sources);
+                let line = src[..location.range.start().into()].matches('\n').count() + 1;
+                (SyntaxKind::INT_NUMBER, line.to_string())
+            }
+            _ => unreachable!("{:?} is not a builtin macro", directive),
+        };
+
+        let range = TextRange::up_to(TextSize::of(&*text));
+        let ctx = self.source_map.add_generated_ctx(text.into(), call_site);
+        dst.push(Token { kind, span: CtxSpan { range, ctx } })
+    }
+
     pub(crate) fn call_macro(
         &mut self,
         call: &MacroCall<'a>,
@@ -148,9 +204,12 @@ impl<'a> Processor<'a> {
         dst: &mut Vec<Token>,
         errors: &mut Diagnostics,
     ) {
-        // TODO track recursion
-        //
-        let parent_ctx_span = self.source_map.ctx_data(span.ctx).decl.range.start();
+        // generated text does not exist in any file and therefore has no offset
+        let parent_ctx_span = if self.source_map.generated_text(span.ctx).is_some() {
+            0.into()
+        } else {
+            self.source_map.ctx_data(span.ctx).decl.range.start()
+        };
         if let Some(def) = self.macros.get(&call.name).cloned() {
             let new_args: TiVec<_, _> = call
                 .arg_bindings
@@ -166,24 +225,157 @@ impl<'a> Processor<'a> {
                 })
                 .collect();
 
-            if new_args.len() == def.arg_cnt {
-                let ctx = self.source_map.add_ctx(def.span.to_file_span(&self.source_map), span);
-                for ParsedToken { kind, range } in &def.body {
-                    let span = CtxSpan { range: range - def.span.range.start(), ctx };
-                    self.process_macro_token(kind, span, &new_args, dst, errors)
-                }
-            } else {
+            let recursive = self.expansion_stack.iter().any(|(name, _)| *name == call.name);
+            if new_args.len() != def.arg_cnt {
                 errors.push(MacroArgumentCountMismatch {
                     expected: def.arg_cnt,
                     found: new_args.len(),
                     span,
                 })
+            } else if recursive || self.expansion_stack.len() >= self.expansion_limit {
+                let chain = self
+                    .expansion_stack
+                    .iter()
+                    .map(|&(name, span)| (name.to_owned(), span))
+                    .collect();
+                errors.push(MacroRecursion {
+                    name: call.name.to_owned(),
+                    span,
+                    chain,
+                    limit: (!recursive).then_some(self.expansion_limit),
+                })
+            } else {
+                let (ctx, def_start) = match self.source_map.generated_text(def.span.ctx) {
+                    // macros defined in decrypted source code are expanded from that text
+                    Some(text) => {
+                        let text = text.clone();
+                        let ctx = if self.source_map.is_protected(def.span.ctx) {
+                            self.source_map.add_protected_ctx(text, span)
+                        } else {
+                            self.source_map.add_generated_ctx(text, span)
+                        };
+                        (ctx, 0.into())
+                    }
+                    None => {
+                        let decl = def.span.to_file_span(&self.source_map);
+                        (self.source_map.add_ctx(decl, span), def.span.range.start())
+                    }
+                };
+                self.expansion_stack.push((call.name, span));
+                for ParsedToken { kind, range } in &def.body {
+                    let span = CtxSpan { range: range - def_start, ctx };
+                    self.process_macro_token(kind, span, &new_args, dst, errors)
+                }
+                self.expansion_stack.pop();
             }
         } else {
             errors.push(MacroNotFound { name: call.name.to_owned(), span })
         }
     }
 
+    /// Decrypts and processes protected envelopes (`` `pragma protect begin_protected ``).
+    /// All other pragmas are ignored.
+    fn process_pragma(&mut self, p: &mut Parser<'a, '_>, err: &mut Diagnostics) {
+        let src = p.remaining_text();
+        let line = src.lines().next().unwrap_or_default();
+        let mut words = line[p.current_text().len()..]
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|word| !word.is_empty());
+        if words.next() != Some("protect") || words.next() != Some(BEGIN_PROTECTED) {
+            p.bump_line();
+            return;
+        }
+
+        let (len, res) = match src.find(END_PROTECTED) {
+            Some(end) => {
+                let len = TextSize::of(&src[..end]) + TextSize::of(END_PROTECTED);
+                (len, protect::decrypt(&src[..end], &self.decryption_keys))
+            }
+            None => (TextSize::of(src), Err(ProtectError::MissingEndProtected)),
+        };
+        let span = CtxSpan { range: TextRange::at(p.current_range().start(), len), ctx: p.ctx() };
+        p.bump_to(span.range.end());
+
+        match res {
+            Ok(text) => {
+                // the decrypted text is only stored in the source map,
+                // diagnostics inside the envelope point to the envelope itself
+                let text: Arc<str> = text.into();
+                let src = self.arena.ensure(text.clone());
+                let ctx = self.source_map.add_protected_ctx(text, span);
+                let parser = Parser::new(src, ctx, p.working_dir.clone(), p.dst, err);
+                self.process_file(parser, err);
+            }
+            Err(error) => err.push(ProtectedEnvelope { error, span }),
+        }
+    }
+
+    fn current_directives(&self) -> Directives {
+        self.directives
+            .last()
+            .map_or_else(Directives::default, |(_, directives)| directives.clone())
+    }
+
+    /// The directives apply to all tokens that are emitted after this call.
+    fn set_directives(&mut self, directives: Directives, dst: &[Token]) {
+        if directives == self.current_directives() {
+            return;
+        }
+        match self.directives.last_mut() {
+            Some((pos, last)) if *pos == dst.len() => *last = directives,
+            _ => self.directives.push((dst.len(), directives)),
+        }
+    }
+
+    /// Processes `` `default_discipline ``, `` `default_transition `` and `` `timescale ``.
+    /// Their argument extends until the end of the line.
+    fn process_directive(
+        &mut self,
+        directive: CompilerDirective,
+        p: &mut Parser<'a, '_>,
+        err: &mut Diagnostics,
+    ) {
+        let line = p.remaining_text().lines().next().unwrap_or_default();
+        let line = line.split("//").next().unwrap_or_default().trim_end();
+        let arg = line[p.current_text().len()..].trim();
+        let span = CtxSpan {
+            range: TextRange::at(p.current_range().start(), TextSize::of(line)),
+            ctx: p.ctx(),
+        };
+        p.bump_line();
+
+        let mut directives = self.current_directives();
+        let (valid, expected) = match directive {
+            CompilerDirective::DefaultDiscipline => {
+                // the optional qualifier and scope are not supported and therefore ignored
+                let discipline = arg.split_whitespace().next();
+                directives.default_discipline = discipline.map(Arc::from);
+                (discipline.map_or(true, is_identifier), "a discipline")
+            }
+            CompilerDirective::DefaultTransition => {
+                let val = arg.parse::<f64>().ok().filter(|val| val.is_finite() && *val >= 0.0);
+                directives.default_transition = val.map(|val| val * directives.time_unit);
+                (val.is_some(), "a non-negative transition time")
+            }
+            CompilerDirective::Timescale => {
+                let mut parts = arg.split('/').map(parse_time_unit);
+                let (unit, precision) = (parts.next().flatten(), parts.next().flatten());
+                directives.time_unit = unit.unwrap_or(directives.time_unit);
+                (
+                    unit.is_some() && precision.is_some() && parts.next().is_none(),
+                    "a time unit and precision like 1ns / 1ps",
+                )
+            }
+            _ => unreachable!(),
+        };
+
+        if valid {
+            self.set_directives(directives, p.dst)
+        } else {
+            err.push(InvalidDirective { expected, span })
+        }
+    }
+
     pub(crate) fn process_file(&mut self, mut p: Parser<'a, '_>, err: &mut Diagnostics) {
         while !p.at(PreprocessorToken::Eof) {
             self.process_token(&mut p, err)
@@ -239,8 +431,33 @@ impl<'a> Processor<'a> {
                     let (call, range) =
                         parse_macro_call(p, err, &[], &mut self.source_map, p.end());
                     let span = CtxSpan { range, ctx: p.ctx() };
+                    self.expansion_site = span;
                     self.call_macro(&call, span, TiSlice::from_ref(&[]), p.dst, err);
                 }
+                directive @ (CompilerDirective::File | CompilerDirective::Line) => {
+                    let span = p.current_span();
+                    self.expand_builtin_macro(directive, span, span, p.dst);
+                    p.bump();
+                }
+                CompilerDirective::Undef => {
+                    p.bump();
+                    let name = p.current_text();
+                    if p.expect(PreprocessorToken::SimpleIdent, "an identifier", err) {
+                        self.macros.remove(name);
+                    }
+                }
+                CompilerDirective::UndefineAll => {
+                    p.bump();
+                    self.macros.clear();
+                }
+                CompilerDirective::ResetAll => {
+                    p.bump();
+                    self.set_directives(Directives::default(), p.dst);
+                }
+                directive @ (CompilerDirective::DefaultDiscipline
+                | CompilerDirective::DefaultTransition
+                | CompilerDirective::Timescale) => self.process_directive(directive, p, err),
+                CompilerDirective::Pragma => self.process_pragma(p, err),
 
                 _ => {
                     err.push(UnexpectedToken(p.current_span()));
@@ -253,6 +470,34 @@ impl<'a> Processor<'a> {
     }
 }
 
+fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().map_or(false, |c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Parses a time unit of `` `timescale `` (like `10ns`) and returns its value in seconds.
+fn parse_time_unit(src: &str) -> Option<f64> {
+    let src = src.trim();
+    let unit_start = src.find(|c: char| !c.is_ascii_digit())?;
+    let magnitude = match &src[..unit_start] {
+        "1" => 1.0,
+        "10" => 10.0,
+        "100" => 100.0,
+        _ => return None,
+    };
+    let unit = match src[unit_start..].trim_start() {
+        "s" => 1.0,
+        "ms" => 1e-3,
+        "us" => 1e-6,
+        "ns" => 1e-9,
+        "ps" => 1e-12,
+        "fs" => 1e-15,
+        _ => return None,
+    };
+    Some(magnitude * unit)
+}
+
 pub(crate) type MacroArgs<'s> = TiVec<MacroArg, (Vec<ParsedToken<'s>>, TextRange)>;
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
@@ -272,6 +517,8 @@ pub(crate) enum ParsedTokenKind<'s> {
     ResolvedToken(SyntaxKind),
     ArgumentReference(MacroArg),
     MacroCall(MacroCall<'s>),
+    /// `` `__FILE__ `` or `` `__LINE__ `` inside a macro body
+    BuiltinMacro(CompilerDirective),
 }
 
 impl From<SyntaxKind> for ParsedTokenKind<'static> {
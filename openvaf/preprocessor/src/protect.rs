@@ -0,0 +1,248 @@
+//! Decryption of IEEE P1735 (`` `pragma protect ``) envelopes.
+//!
+//! Only the digital envelope scheme commonly used for Verilog-A models is supported:
+//! The session key is stored in a `key_block` encrypted with RSA (PKCS#1 v1.5 padding).
+//! The source code is stored in the `data_block` encrypted with AES-CBC. The first block of the
+//! decoded `data_block` is the initialization vector. All blocks must be base64 encoded.
+
+use std::fmt;
+use std::sync::Arc;
+
+use aes::{Aes128, Aes256};
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use cbc::cipher::block_padding::Pkcs7;
+use cbc::cipher::{BlockDecryptMut, KeyIvInit};
+use pkcs1::DecodeRsaPrivateKey;
+use pkcs8::DecodePrivateKey;
+use stdx::impl_display;
+
+use self::rsa::RsaPrivateKey;
+
+mod rsa;
+
+pub(crate) const BEGIN_PROTECTED: &str = "begin_protected";
+pub(crate) const END_PROTECTED: &str = "`pragma protect end_protected";
+
+/// A private RSA key that is used to decrypt the `key_block`s of protected envelopes.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct DecryptionKey {
+    /// The name of the key, matched against `key_keyname` of the envelope.
+    pub name: Arc<str>,
+    /// The PEM encoded (PKCS#1 or PKCS#8) private key.
+    pub pem: Arc<str>,
+}
+
+// the key itself is never printed
+impl fmt::Debug for DecryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DecryptionKey").field("name", &self.name).finish_non_exhaustive()
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum ProtectError {
+    MissingEndProtected,
+    MissingBlock(&'static str),
+    NoMatchingKey { key_names: Vec<String> },
+    InvalidKey { name: String },
+    UnsupportedEncoding(String),
+    UnsupportedMethod(String),
+    InvalidEncoding(&'static str),
+    DecryptionFailed(&'static str),
+    InvalidText,
+}
+
+impl_display! {
+    match ProtectError{
+        ProtectError::MissingEndProtected => "'`pragma protect end_protected' is missing";
+        ProtectError::MissingBlock(block) => "the {} is missing", block;
+        ProtectError::NoMatchingKey { key_names } => "no decryption key was provided for any of the keys [{}]", key_names.join(", ");
+        ProtectError::InvalidKey{name} => "decryption key '{}' is not a valid PEM encoded RSA private key", name;
+        ProtectError::UnsupportedEncoding(encoding) => "unsupported encoding '{}'", encoding;
+        ProtectError::UnsupportedMethod(method) => "unsupported encryption method '{}'", method;
+        ProtectError::InvalidEncoding(block) => "the {} is not valid base64", block;
+        ProtectError::DecryptionFailed(block) => "failed to decrypt the {}", block;
+        ProtectError::InvalidText => "the decrypted source code is not valid UTF-8";
+    }
+}
+
+#[derive(Default)]
+struct KeyBlock<'a> {
+    owner: Option<&'a str>,
+    name: Option<&'a str>,
+    method: Option<&'a str>,
+    data: String,
+}
+
+#[derive(Default)]
+struct Envelope<'a> {
+    key_blocks: Vec<KeyBlock<'a>>,
+    data_method: Option<&'a str>,
+    data: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Block {
+    Key,
+    Data,
+}
+
+impl<'a> Envelope<'a> {
+    /// Parses the pragmas (and blocks) between `begin_protected` and `end_protected`
+    fn parse(src: &'a str) -> Result<Envelope<'a>, ProtectError> {
+        let mut res = Envelope::default();
+        let mut key = KeyBlock::default();
+        // the block that the current (non-pragma) lines belong to
+        let mut block = None;
+
+        for line in src.lines() {
+            let line = line.trim();
+            let pragma = match line.strip_prefix("`pragma") {
+                Some(pragma) => pragma.trim_start(),
+                None => {
+                    match block {
+                        Some(Block::Key) => res.key_blocks.last_mut().unwrap().data.push_str(line),
+                        Some(Block::Data) => {
+                            res.data.get_or_insert_with(String::new).push_str(line)
+                        }
+                        None => (),
+                    }
+                    continue;
+                }
+            };
+            block = None;
+            let pragma = match pragma.strip_prefix("protect") {
+                Some(pragma) => pragma,
+                None => continue,
+            };
+
+            for (keyword, value) in parse_pragma_expressions(pragma) {
+                match keyword {
+                    "key_keyowner" => key.owner = Some(value),
+                    "key_keyname" => key.name = Some(value),
+                    "key_method" => key.method = Some(value),
+                    "data_method" => res.data_method = Some(value),
+                    "encoding" => {
+                        let value = value.trim_start_matches('(').trim_end_matches(')');
+                        for (keyword, value) in parse_pragma_expressions(value) {
+                            if keyword == "enctype" && !value.eq_ignore_ascii_case("base64") {
+                                return Err(ProtectError::UnsupportedEncoding(value.to_owned()));
+                            }
+                        }
+                    }
+                    "key_block" => {
+                        res.key_blocks.push(std::mem::take(&mut key));
+                        block = Some(Block::Key);
+                    }
+                    "data_block" => block = Some(Block::Data),
+                    // other pragmas (like `encrypt_agent` or `author`) are purely informational
+                    _ => (),
+                }
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+/// Splits the `keyword[=value], ...` list of a pragma.
+/// Quotes around values are removed and parenthesized values are returned as is.
+fn parse_pragma_expressions(mut src: &str) -> Vec<(&str, &str)> {
+    let mut res = Vec::new();
+    loop {
+        src = src.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        if src.is_empty() {
+            return res;
+        }
+
+        let keyword_end =
+            src.find(|c: char| c == '=' || c == ',' || c.is_whitespace()).unwrap_or(src.len());
+        let keyword = &src[..keyword_end];
+        src = src[keyword_end..].trim_start();
+
+        let value = match src.strip_prefix('=') {
+            Some(value) => {
+                let value = value.trim_start();
+                let end = if let Some(quoted) = value.strip_prefix('"') {
+                    quoted.find('"').map_or(value.len(), |end| end + 2)
+                } else if value.starts_with('(') {
+                    value.find(')').map_or(value.len(), |end| end + 1)
+                } else {
+                    value.find(|c: char| c == ',' || c.is_whitespace()).unwrap_or(value.len())
+                };
+                src = &value[end..];
+                value[..end].trim_matches('"')
+            }
+            None => "",
+        };
+        res.push((keyword, value));
+    }
+}
+
+fn decode_base64(data: &str, block: &'static str) -> Result<Vec<u8>, ProtectError> {
+    BASE64.decode(data).map_err(|_| ProtectError::InvalidEncoding(block))
+}
+
+/// Decrypts the source code inside a protected envelope.
+/// `src` is the text between `` `pragma protect begin_protected `` and `` `pragma protect end_protected ``.
+pub(crate) fn decrypt(src: &str, keys: &[DecryptionKey]) -> Result<String, ProtectError> {
+    let envelope = Envelope::parse(src)?;
+    if envelope.key_blocks.is_empty() {
+        return Err(ProtectError::MissingBlock("key_block"));
+    }
+
+    let (key_block, key) = envelope
+        .key_blocks
+        .iter()
+        .find_map(|block| {
+            let key = keys.iter().find(|key| Some(&*key.name) == block.name)?;
+            Some((block, key))
+        })
+        .ok_or_else(|| ProtectError::NoMatchingKey {
+            key_names: envelope
+                .key_blocks
+                .iter()
+                .map(|block| match (block.owner, block.name) {
+                    (Some(owner), Some(name)) => format!("{}:{}", owner, name),
+                    (None, Some(name)) => name.to_owned(),
+                    (_, None) => "<unnamed>".to_owned(),
+                })
+                .collect(),
+        })?;
+
+    let method = key_block.method.unwrap_or("rsa");
+    if !method.eq_ignore_ascii_case("rsa") {
+        return Err(ProtectError::UnsupportedMethod(method.to_owned()));
+    }
+
+    let private_key = RsaPrivateKey::from_pkcs8_pem(&key.pem)
+        .or_else(|_| RsaPrivateKey::from_pkcs1_pem(&key.pem))
+        .map_err(|_| ProtectError::InvalidKey { name: key.name.to_string() })?;
+    let session_key = decode_base64(&key_block.data, "key_block")?;
+    let session_key =
+        private_key.decrypt(&session_key).ok_or(ProtectError::DecryptionFailed("key_block"))?;
+
+    let data = envelope.data.as_ref().ok_or(ProtectError::MissingBlock("data_block"))?;
+    let data = decode_base64(data, "data_block")?;
+    if data.len() < 16 {
+        return Err(ProtectError::DecryptionFailed("data_block"));
+    }
+    let (iv, data) = data.split_at(16);
+
+    let method = envelope.data_method.unwrap_or("aes128-cbc");
+    let plaintext = if method.eq_ignore_ascii_case("aes128-cbc") {
+        cbc::Decryptor::<Aes128>::new_from_slices(&session_key, iv)
+            .map_err(|_| ProtectError::DecryptionFailed("key_block"))?
+            .decrypt_padded_vec_mut::<Pkcs7>(data)
+    } else if method.eq_ignore_ascii_case("aes256-cbc") {
+        cbc::Decryptor::<Aes256>::new_from_slices(&session_key, iv)
+            .map_err(|_| ProtectError::DecryptionFailed("key_block"))?
+            .decrypt_padded_vec_mut::<Pkcs7>(data)
+    } else {
+        return Err(ProtectError::UnsupportedMethod(method.to_owned()));
+    };
+    let plaintext = plaintext.map_err(|_| ProtectError::DecryptionFailed("data_block"))?;
+
+    String::from_utf8(plaintext).map_err(|_| ProtectError::InvalidText)
+}
@@ -0,0 +1,95 @@
+//! RSA decryption with PKCS#1 v1.5 padding (RFC 8017, section 7.2.2).
+//!
+//! Only decryption is required for protected envelopes. The modular exponentiation and the
+//! removal of the padding run in constant time so that the decryption does not leak
+//! information about the private key or the plaintext through timing.
+
+use crypto_bigint::modular::runtime_mod::{DynResidue, DynResidueParams};
+use crypto_bigint::{Encoding, U4096};
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq, ConstantTimeLess};
+
+/// The size of the largest supported modulus in bytes (4096 bits)
+const MAX_SIZE: usize = U4096::BYTES;
+
+/// The smallest modulus that can hold the padding (RFC 8017 requires `k >= 11`)
+const MIN_SIZE: usize = 11;
+
+pub(super) struct RsaPrivateKey {
+    modulus: U4096,
+    private_exponent: U4096,
+    /// The size of the modulus in bytes
+    size: usize,
+}
+
+impl TryFrom<pkcs8::PrivateKeyInfo<'_>> for RsaPrivateKey {
+    type Error = pkcs8::Error;
+
+    fn try_from(info: pkcs8::PrivateKeyInfo<'_>) -> pkcs8::Result<Self> {
+        info.algorithm.assert_algorithm_oid(pkcs1::ALGORITHM_OID)?;
+        let key = pkcs1::RsaPrivateKey::try_from(info.private_key)?;
+
+        let modulus = key.modulus.as_bytes();
+        let private_exponent = key.private_exponent.as_bytes();
+        let size = modulus.len();
+        // montgomery multiplication requires an odd modulus
+        let is_odd = modulus.last().map_or(false, |byte| byte & 1 == 1);
+        if !(MIN_SIZE..=MAX_SIZE).contains(&size) || !is_odd || private_exponent.len() > size {
+            return Err(pkcs8::Error::KeyMalformed);
+        }
+
+        Ok(RsaPrivateKey {
+            modulus: uint_from_be_bytes(modulus),
+            private_exponent: uint_from_be_bytes(private_exponent),
+            size,
+        })
+    }
+}
+
+impl RsaPrivateKey {
+    /// Decrypts `ciphertext` and removes the PKCS#1 v1.5 padding.
+    pub(super) fn decrypt(&self, ciphertext: &[u8]) -> Option<Vec<u8>> {
+        if ciphertext.len() != self.size {
+            return None;
+        }
+        let ciphertext = uint_from_be_bytes(ciphertext);
+        if ciphertext >= self.modulus {
+            return None;
+        }
+
+        let params = DynResidueParams::new(&self.modulus);
+        let message = DynResidue::new(&ciphertext, params)
+            .pow_bounded_exp(&self.private_exponent, self.size * 8)
+            .retrieve()
+            .to_be_bytes();
+        unpad(&message[MAX_SIZE - self.size..])
+    }
+}
+
+/// Zero extends a big endian integer of at most [`MAX_SIZE`] bytes.
+fn uint_from_be_bytes(bytes: &[u8]) -> U4096 {
+    let mut buf = [0u8; MAX_SIZE];
+    buf[MAX_SIZE - bytes.len()..].copy_from_slice(bytes);
+    U4096::from_be_slice(&buf)
+}
+
+/// Removes the padding `0x00 || 0x02 || PS || 0x00 || M` where `PS` consists of at least
+/// eight non-zero bytes. Only the length of `M` (which is public) affects the timing.
+fn unpad(encoded: &[u8]) -> Option<Vec<u8>> {
+    let valid_header = encoded[0].ct_eq(&0) & encoded[1].ct_eq(&2);
+
+    // the index of the zero byte that separates the padding from the message
+    let mut separator = 0u32;
+    let mut searching = Choice::from(1);
+    for (i, byte) in encoded.iter().enumerate().skip(2) {
+        let is_zero = byte.ct_eq(&0);
+        separator.conditional_assign(&(i as u32), searching & is_zero);
+        searching &= !is_zero;
+    }
+
+    let valid = valid_header & !searching & !separator.ct_lt(&10);
+    if bool::from(valid) {
+        Some(encoded[separator as usize + 1..].to_vec())
+    } else {
+        None
+    }
+}
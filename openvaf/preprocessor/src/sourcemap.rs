@@ -127,15 +127,25 @@ impl CtxSpan {
         sm.lookup_expansion(*self)
     }
 
+    /// Maps this span to the file it originates from. Spans inside text generated by the
+    /// preprocessor (like `` `__LINE__ ``) are mapped to the directive that produced the text.
     #[must_use]
     pub fn to_file_span(self, sm: &SourceMap) -> FileSpan {
-        sm.ctx_tree[self.ctx].decl.with_subrange(self.range)
+        sm.file_span(self.ctx, self.range)
     }
 }
 
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
 pub struct SourceMap {
     ctx_tree: TiVec<SourceContext, SourceContextData>,
+    /// The text of contexts that were generated by the preprocessor instead of being read
+    /// from a file (sorted by context).
+    generated: Vec<(SourceContext, Arc<str>)>,
+    /// Generated contexts that contain decrypted source code (sorted).
+    /// See [`SourceMap::is_protected`].
+    protected: Vec<SourceContext>,
+    /// All macros that were defined while preprocessing (in the order they were defined).
+    macros: Vec<MacroDecl>,
     // ranges: Vec<(TextRange, SourceContext, isize)>,
 }
 
@@ -147,6 +157,9 @@ impl SourceMap {
                 call_site: None,
             }]
             .into(),
+            generated: Vec::new(),
+            protected: Vec::new(),
+            macros: Vec::new(),
         }
     }
 
@@ -228,7 +241,7 @@ impl SourceMap {
     pub fn to_file_spans(&self, spans: &mut [CtxSpan]) -> (FileId, Vec<TextRange>) {
         let ctx = self.to_same_ctx(spans);
         let decl = self.ctx_data(ctx).decl;
-        let ranges = spans.iter_mut().map(|span| decl.with_subrange(span.range).range).collect();
+        let ranges = spans.iter_mut().map(|span| self.file_span(ctx, span.range).range).collect();
         (decl.file, ranges)
     }
 
@@ -236,9 +249,91 @@ impl SourceMap {
         &self.ctx_tree[ctx]
     }
 
+    /// Returns the text of `ctx` if it was generated by the preprocessor.
+    /// The text of all other contexts is the text of the file they were declared in.
+    pub fn generated_text(&self, ctx: SourceContext) -> Option<&Arc<str>> {
+        let pos = self.generated.binary_search_by_key(&ctx, |(ctx, _)| *ctx).ok()?;
+        Some(&self.generated[pos].1)
+    }
+
+    /// Iterates over all files that contributed to the preprocessed token stream
+    /// (the root file, included files and files that declare expanded macros).
+    /// Files may be visited multiple times.
+    pub fn files(&self) -> impl Iterator<Item = FileId> + '_ {
+        self.ctx_tree.iter().map(|data| data.decl.file)
+    }
+
+    /// All macros that were defined while preprocessing (including macros that were
+    /// undefined later).
+    pub fn macros(&self) -> &[MacroDecl] {
+        &self.macros
+    }
+
+    pub(crate) fn add_macro(&mut self, decl: MacroDecl) {
+        self.macros.push(decl)
+    }
+
+    fn file_span(&self, ctx: SourceContext, range: TextRange) -> FileSpan {
+        let decl = self.ctx_tree[ctx].decl;
+        if self.generated_text(ctx).is_some() {
+            // generated text does not exist in any file
+            decl
+        } else {
+            decl.with_subrange(range)
+        }
+    }
+
     pub(crate) fn add_ctx(&mut self, decl: FileSpan, call_site: CtxSpan) -> SourceContext {
         self.ctx_tree.push_and_get_key(SourceContextData { decl, call_site: Some(call_site) })
     }
+
+    /// Adds a context for `text` generated by the preprocessor at `call_site`.
+    pub(crate) fn add_generated_ctx(
+        &mut self,
+        text: Arc<str>,
+        call_site: CtxSpan,
+    ) -> SourceContext {
+        let decl = call_site.to_file_span(self);
+        let ctx = self.add_ctx(decl, call_site);
+        self.generated.push((ctx, text));
+        ctx
+    }
+
+    /// Adds a context for source code that was decrypted from the protected envelope at
+    /// `call_site`.
+    pub(crate) fn add_protected_ctx(
+        &mut self,
+        text: Arc<str>,
+        call_site: CtxSpan,
+    ) -> SourceContext {
+        let ctx = self.add_generated_ctx(text, call_site);
+        self.protected.push(ctx);
+        ctx
+    }
+
+    /// Returns whether `ctx` contains (or was expanded from) decrypted source code.
+    /// The text of these contexts must never be shown to the user.
+    pub fn is_protected(&self, mut ctx: SourceContext) -> bool {
+        loop {
+            if self.protected.binary_search(&ctx).is_ok() {
+                return true;
+            }
+            match self.ctx_tree[ctx].call_site {
+                Some(call_site) => ctx = call_site.ctx,
+                None => return false,
+            }
+        }
+    }
+}
+
+/// A macro defined with `` `define `` (or on the command line).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MacroDecl {
+    pub name: Box<str>,
+    /// The head of the `` `define `` directive (the name and arguments of the macro).
+    /// `None` for macros defined on the command line.
+    pub head: Option<CtxSpan>,
+    pub arg_cnt: usize,
 }
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Hash)]
@@ -4,11 +4,17 @@ use std::{cell::RefCell, path::PathBuf};
 use expect_test::expect_file;
 use vfs::{FileId, Vfs, VfsPath};
 
-use crate::{preprocess, Preprocess, SourceProvider};
+use crate::diagnostics::PreprocessorDiagnostic;
+use crate::{
+    preprocess, DecryptionKey, Directives, Preprocess, ProtectError, SourceProvider,
+    DEFAULT_MACRO_EXPANSION_LIMIT,
+};
 
 struct TestSourceProvider {
     vfs: RefCell<Vfs>,
     include_dirs: Arc<[VfsPath]>,
+    decryption_keys: Arc<[DecryptionKey]>,
+    macro_expansion_limit: usize,
 }
 
 impl TestSourceProvider {
@@ -16,7 +22,12 @@ impl TestSourceProvider {
         let mut vfs = Vfs::default();
         vfs.insert_std_lib();
         include_dirs.push(VfsPath::new_virtual_path("/std".to_owned()));
-        Self { vfs: RefCell::new(vfs), include_dirs: Arc::from(include_dirs) }
+        Self {
+            vfs: RefCell::new(vfs),
+            include_dirs: Arc::from(include_dirs),
+            decryption_keys: Arc::new([]),
+            macro_expansion_limit: DEFAULT_MACRO_EXPANSION_LIMIT,
+        }
     }
 }
 
@@ -29,6 +40,14 @@ impl SourceProvider for TestSourceProvider {
         Arc::new([])
     }
 
+    fn decryption_keys(&self, _file_root: FileId) -> Arc<[DecryptionKey]> {
+        self.decryption_keys.clone()
+    }
+
+    fn macro_expansion_limit(&self, _file_root: FileId) -> usize {
+        self.macro_expansion_limit
+    }
+
     fn file_text(&self, file: FileId) -> Result<Arc<str>, crate::FileReadError> {
         let vfs = self.vfs.borrow();
         vfs.file_contents(file).map(Arc::from)
@@ -44,7 +63,7 @@ impl SourceProvider for TestSourceProvider {
 }
 
 fn check_prepocessor(sources: TestSourceProvider, root_file: FileId, test_name: &'static str) {
-    let Preprocess { ts, diagnostics, sm } = preprocess(&sources, root_file);
+    let Preprocess { ts, diagnostics, sm, .. } = preprocess(&sources, root_file);
     assert_eq!(diagnostics.as_slice(), &[]);
     let actual_tokens: String = ts.iter().map(|token| format!("{:?}\n", token.kind,)).collect();
     let expected = PathBuf::from(".").join("test_data").join(format!("{}.tokens", test_name));
@@ -181,3 +200,251 @@ fn source_map_triple_replacement() {
         "source_map_triple_replacement",
     )
 }
+
+#[test]
+fn builtin_directives() {
+    const SRC: &str = r#"
+`timescale 1ns / 1ps
+`default_discipline electrical
+`default_transition 0
+`define FOO 1
+`undef FOO
+`ifdef FOO
+ERROR
+`endif
+`define BAR 2
+`undefineall
+`ifdef BAR
+ERROR
+`endif
+`resetall
+`define LINE `__LINE__
+a = `__LINE__;
+b = `__FILE__;
+c = `LINE;
+"#;
+
+    let sources = TestSourceProvider::new(vec![]);
+    let file = sources.vfs.borrow_mut().add_virt_file("/directives.va", SRC.to_owned().into());
+    let Preprocess { ts, diagnostics, sm, .. } = preprocess(&sources, file);
+    assert_eq!(diagnostics.as_slice(), &[]);
+
+    let vfs = sources.vfs.borrow();
+    let actual: Vec<String> = ts
+        .iter()
+        .filter(|token| !token.kind.is_trivia())
+        .map(|token| match sm.generated_text(token.span.ctx) {
+            Some(text) => text[token.span.range].to_owned(),
+            None => {
+                let filespan = token.span.to_file_span(&sm);
+                vfs.file_contents(filespan.file).unwrap()[filespan.range].to_owned()
+            }
+        })
+        .collect();
+    assert_eq!(actual.join(" "), r#"a = 17 ; b = "/directives.va" ; c = 19 ;"#);
+
+    // generated tokens map back to the directive
+    let line = ts.iter().find(|token| sm.generated_text(token.span.ctx).is_some()).unwrap();
+    let filespan = line.span.to_file_span(&sm);
+    assert_eq!(&vfs.file_contents(filespan.file).unwrap()[filespan.range], "`__LINE__");
+}
+
+#[test]
+fn directive_state() {
+    const SRC: &str = r#"
+`default_transition 1
+`timescale 10ns / 1ps
+`default_transition 2 // in units of 10ns
+`default_discipline electrical
+a
+`resetall
+b
+`timescale 1ns
+`default_discipline 1
+"#;
+
+    let sources = TestSourceProvider::new(vec![]);
+    let file = sources.vfs.borrow_mut().add_virt_file("/state.va", SRC.to_owned().into());
+    let Preprocess { ts, diagnostics, sm, directives } = preprocess(&sources, file);
+
+    let invalid: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| match diagnostic {
+            PreprocessorDiagnostic::InvalidDirective { span, .. } => &SRC[span.to_file_span(&sm).range],
+            diagnostic => unreachable!("unexpected diagnostic {:?}", diagnostic),
+        })
+        .collect();
+    assert_eq!(invalid, ["`timescale 1ns", "`default_discipline 1"]);
+
+    let state = |name: &str| {
+        let token = ts.iter().position(|token| &SRC[token.span.to_file_span(&sm).range] == name).unwrap();
+        let i = directives.partition_point(|(start, _)| *start <= token);
+        i.checked_sub(1).map_or_else(Directives::default, |i| directives[i].1.clone())
+    };
+    let a = state("a");
+    assert_eq!(a.default_discipline.as_deref(), Some("electrical"));
+    assert_eq!(a.default_transition, Some(2e-8));
+    assert_eq!(a.time_unit, 1e-8);
+    assert_eq!(state("b"), Directives::default());
+}
+
+#[test]
+fn macro_recursion() {
+    const SRC: &str = r#"
+`define DIRECT 1 + `DIRECT
+`define INDIRECT_A(x) `INDIRECT_B(x)
+`define INDIRECT_B(x) x * `INDIRECT_A(x)
+`define NESTED(x) (x)
+a = `DIRECT;
+b = `INDIRECT_A(2);
+c = `NESTED(`NESTED(3));
+"#;
+
+    let sources = TestSourceProvider::new(vec![]);
+    let file = sources.vfs.borrow_mut().add_virt_file("/recursion.va", SRC.to_owned().into());
+    let Preprocess { diagnostics, sm, .. } = preprocess(&sources, file);
+
+    let vfs = sources.vfs.borrow();
+    let src = vfs.file_contents(file).unwrap();
+    let actual: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| match diagnostic {
+            PreprocessorDiagnostic::MacroRecursion { name, span, chain, limit } => {
+                let expansion: Vec<_> = span
+                    .lookup_expansion(&sm)
+                    .into_iter()
+                    .map(|span| &src[span.range])
+                    .collect();
+                assert_eq!(expansion.len(), chain.len() + 1);
+                for ((call, call_span), expansion) in chain.iter().rev().zip(&expansion[1..]) {
+                    assert_eq!(&src[call_span.lookup_expansion(&sm)[0].range], *expansion);
+                    assert!(expansion.starts_with(&format!("`{call}")));
+                }
+                assert_eq!(*limit, None);
+                let chain: Vec<_> = chain.iter().map(|(call, _)| call.as_str()).collect();
+                (name.as_str(), chain, expansion)
+            }
+            _ => unreachable!("unexpected diagnostic {:?}", diagnostic),
+        })
+        .collect();
+
+    assert_eq!(
+        actual,
+        vec![
+            ("DIRECT", vec!["DIRECT"], vec!["`DIRECT", "`DIRECT"]),
+            (
+                "INDIRECT_A",
+                vec!["INDIRECT_A", "INDIRECT_B"],
+                vec!["`INDIRECT_A(x)", "`INDIRECT_B(x)", "`INDIRECT_A(2)"]
+            ),
+        ]
+    );
+}
+
+#[test]
+fn macro_expansion_limit() {
+    const SRC: &str = r#"
+`define A0 1
+`define A1 `A0
+`define A2 `A1
+`define A3 `A2
+a = `A2;
+b = `A3;
+"#;
+
+    let mut sources = TestSourceProvider::new(vec![]);
+    sources.macro_expansion_limit = 3;
+    let file = sources.vfs.borrow_mut().add_virt_file("/limit.va", SRC.to_owned().into());
+    let Preprocess { diagnostics, .. } = preprocess(&sources, file);
+
+    let actual: Vec<_> = diagnostics
+        .iter()
+        .map(|diagnostic| match diagnostic {
+            PreprocessorDiagnostic::MacroRecursion { name, chain, limit, .. } => {
+                let chain: Vec<_> = chain.iter().map(|(call, _)| call.as_str()).collect();
+                (name.as_str(), chain, *limit)
+            }
+            _ => unreachable!("unexpected diagnostic {:?}", diagnostic),
+        })
+        .collect();
+    assert_eq!(actual, vec![("A0", vec!["A3", "A2", "A1"], Some(3))]);
+}
+
+#[test]
+fn macro_declarations() {
+    const SRC: &str = r#"
+`define GAIN 2
+`ifdef GAIN
+`define SCALE(x, y) (x) * (y)
+`else
+`define UNUSED
+`endif
+`undef GAIN
+"#;
+
+    let sources = TestSourceProvider::new(vec![]);
+    let file = sources.vfs.borrow_mut().add_virt_file("/macros.va", SRC.to_owned().into());
+    let Preprocess { diagnostics, sm, .. } = preprocess(&sources, file);
+    assert_eq!(diagnostics.as_slice(), &[]);
+
+    let vfs = sources.vfs.borrow();
+    let src = vfs.file_contents(file).unwrap();
+    let actual: Vec<_> = sm
+        .macros()
+        .iter()
+        .map(|decl| {
+            let head = decl.head.unwrap().to_file_span(&sm);
+            (&*decl.name, decl.arg_cnt, &src[head.range])
+        })
+        .collect();
+
+    assert_eq!(actual, vec![("GAIN", 0, "`define GAIN"), ("SCALE", 2, "`define SCALE(x, y)")]);
+}
+
+#[test]
+fn protected_envelope() {
+    const SRC: &str = include_str!("test_data/protected.va");
+    const KEY: &str = include_str!("test_data/protected_key.pem");
+
+    let mut sources = TestSourceProvider::new(vec![]);
+    let file = sources.vfs.borrow_mut().add_virt_file("/protected.va", SRC.to_owned().into());
+
+    // without the key only the envelope itself is reported
+    let Preprocess { ts, diagnostics, sm, .. } = preprocess(&sources, file);
+    match diagnostics.as_slice() {
+        [PreprocessorDiagnostic::ProtectedEnvelope { error, span }, PreprocessorDiagnostic::MacroNotFound { .. }] => {
+            assert_eq!(error, &ProtectError::NoMatchingKey { key_names: vec!["OpenVAF:TEST_KEY".to_owned()] });
+            let span = span.to_file_span(&sm);
+            assert!(SRC[span.range].starts_with("`pragma protect begin_protected"));
+            assert!(SRC[span.range].ends_with("`pragma protect end_protected"));
+        }
+        diagnostics => unreachable!("unexpected diagnostics {:?}", diagnostics),
+    }
+    assert!(ts.iter().all(|token| !sm.is_protected(token.span.ctx)));
+
+    sources.decryption_keys = Arc::new([DecryptionKey { name: "TEST_KEY".into(), pem: KEY.into() }]);
+    let Preprocess { ts, diagnostics, sm, .. } = preprocess(&sources, file);
+    assert_eq!(diagnostics.as_slice(), &[]);
+
+    let vfs = sources.vfs.borrow();
+    let tokens: Vec<_> = ts.iter().filter(|token| !token.kind.is_trivia()).collect();
+    let actual: Vec<String> = tokens
+        .iter()
+        .map(|token| match sm.generated_text(token.span.ctx) {
+            Some(text) => text[token.span.range].to_owned(),
+            None => {
+                let filespan = token.span.to_file_span(&sm);
+                vfs.file_contents(filespan.file).unwrap()[filespan.range].to_owned()
+            }
+        })
+        .collect();
+    assert_eq!(actual.join(" "), "a = 1 ; y = 2.0 * x ; b = 2.0 ;");
+
+    // the decrypted tokens (including the expansion of macros defined in the envelope) are
+    // protected and point to the envelope
+    let protected: Vec<_> = tokens.iter().map(|token| sm.is_protected(token.span.ctx)).collect();
+    let expected: Vec<_> = actual.iter().enumerate().map(|(i, _)| (4..10).contains(&i) || i == 12).collect();
+    assert_eq!(protected, expected);
+    let filespan = tokens[4].span.to_file_span(&sm);
+    assert!(SRC[filespan.range].starts_with("`pragma protect begin_protected"));
+}
@@ -41,16 +41,18 @@ impl<'a> Context<'a> {
                 | PlaceKind::ImplicitResidual { .. }
                 | PlaceKind::CollapseImplicitEquation(_)
                 | PlaceKind::IsVoltageSrc(_) => true,
-                PlaceKind::Var(var) => module.op_vars.contains_key(&var),
+                PlaceKind::Var(var) => {
+                    module.op_vars.contains_key(&var) || module.hidden_state.contains(&var)
+                }
+                PlaceKind::VarElement { var, .. } => module.hidden_state.contains(&var),
                 _ => false,
             },
-            &mut module.op_vars.keys().copied(),
+            &mut module.op_vars.keys().chain(&module.hidden_state).copied(),
         )
         .with_equations()
         .with_tagged_writes()
         .build(literals);
-        // TODO hidden state
-        intern.insert_var_init(db, &mut func, literals);
+        intern.insert_var_init(db, &mut func, literals, &|var| module.hidden_state.contains(&var));
 
         Context {
             output_values: BitSet::new_empty(func.dfg.num_values()),
@@ -114,9 +116,16 @@ impl<'a> Context<'a> {
                 .extend(self.intern.outputs.values().copied().filter_map(PackedOption::expand));
         } else {
             for (kind, val) in self.intern.outputs.iter() {
-                if matches!(kind, PlaceKind::Var(var) if self.module.op_vars.contains_key(var))
-                    || matches!(kind, PlaceKind::CollapseImplicitEquation(_) | PlaceKind::BoundStep)
-                {
+                let is_output = match kind {
+                    PlaceKind::Var(var) => {
+                        self.module.op_vars.contains_key(var)
+                            || self.module.hidden_state.contains(var)
+                    }
+                    PlaceKind::VarElement { var, .. } => self.module.hidden_state.contains(var),
+                    PlaceKind::CollapseImplicitEquation(_) | PlaceKind::BoundStep => true,
+                    _ => false,
+                };
+                if is_output {
                     self.output_values.insert(val.unwrap_unchecked());
                 }
             }
@@ -85,7 +85,15 @@ impl<'a> CompiledModule<'a> {
             .iter()
             .filter_map(|(param, info)| info.is_instance.then_some(*param))
             .collect();
-        init.intern.insert_param_init(db, &mut init.func, literals, false, true, &inst_params);
+        init.intern.insert_param_init(
+            db,
+            &mut init.func,
+            literals,
+            false,
+            true,
+            &inst_params,
+            &module.param_defaults,
+        );
 
         let mut model_param_setup = Function::default();
         let model_params: Vec<_> = module.params.keys().copied().collect();
@@ -97,6 +105,7 @@ impl<'a> CompiledModule<'a> {
             false,
             true,
             &model_params,
+            &module.param_defaults,
         );
         cx.cfg.compute(&model_param_setup);
         simplify_cfg(&mut model_param_setup, &mut cx.cfg);
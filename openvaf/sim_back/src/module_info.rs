@@ -1,14 +1,16 @@
-use ahash::AHashSet;
-use hir::diagnostics::{BaseDB, ConsoleSink, Diagnostic, FileId, Label, LabelStyle, Report};
+use std::iter::once;
+
+use ahash::{AHashMap, AHashSet};
+use hir::diagnostics::{BaseDB, Diagnostic, FileId, Label, LabelStyle, Report};
 use hir::{
-    CompilationDB, CompilationUnit, DiagnosticSink, Module, ParamSysFun, Parameter,
-    ResolvedAliasParameter, ScopeDef, Variable,
+    CompilationDB, CompilationUnit, DiagnosticSink, Module, ParamSysFun, Parameter, Paramset,
+    ResolvedAliasParameter, ScopeDef, Type, Variable,
 };
-use indexmap::IndexMap;
+use indexmap::{IndexMap, IndexSet};
 use smol_str::SmolStr;
 use syntax::ast::{self, Expr};
 use syntax::sourcemap::FileSpan;
-use syntax::AstNode;
+use syntax::{AstNode, TextRange};
 
 #[cfg(test)]
 mod tests;
@@ -16,7 +18,7 @@ mod tests;
 pub fn collect_modules(
     db: &CompilationDB,
     all_vars_opvars: bool,
-    sink: &mut ConsoleSink,
+    sink: &mut impl DiagnosticSink,
 ) -> Option<Vec<ModuleInfo>> {
     let cu = db.compilation_unit();
     let name = cu.name(db);
@@ -27,12 +29,31 @@ pub fn collect_modules(
         return None;
     }
 
-    let res = cu
+    // attributes of a module are shared with all paramsets that instantiate it, make sure
+    // that their diagnostics are only reported once
+    let mut resolved_attrs = AHashSet::new();
+    let mut res: Vec<_> = cu
         .modules(db)
         .into_iter()
-        .map(|module| ModuleInfo::collect(db, cu, module, sink, all_vars_opvars))
+        .map(|module| {
+            ModuleInfo::collect(db, cu, module, None, sink, &mut resolved_attrs, all_vars_opvars)
+        })
         .collect();
 
+    res.extend(cu.paramsets(db).into_iter().filter_map(|paramset| {
+        let module = paramset.module(db)?;
+        let info = ModuleInfo::collect(
+            db,
+            cu,
+            module,
+            Some(paramset),
+            sink,
+            &mut resolved_attrs,
+            all_vars_opvars,
+        );
+        Some(info)
+    }));
+
     if sink.summary(&name) {
         return None;
     }
@@ -42,158 +63,222 @@ pub fn collect_modules(
 
 pub struct ModuleInfo {
     pub module: Module,
+    /// The paramset this module is compiled for. The parameters declared in the paramset are
+    /// exposed while all parameters of `module` are hidden.
+    pub paramset: Option<Paramset>,
     pub params: IndexMap<Parameter, ParamInfo, ahash::RandomState>,
+    /// Parameters whose default value is replaced by the value of another parameter. These are
+    /// the assignments of a paramset.
+    pub param_defaults: AHashMap<Parameter, Parameter>,
     pub sys_fun_alias: IndexMap<ParamSysFun, Vec<SmolStr>, ahash::RandomState>,
     pub op_vars: IndexMap<Variable, OpVar, ahash::RandomState>,
+    /// Variables marked with the `hidden_state` attribute. These keep their value between
+    /// evaluations instead of being reset to their initial value.
+    pub hidden_state: IndexSet<Variable, ahash::RandomState>,
 }
 
 impl ModuleInfo {
+    /// The name under which this module is exposed to the simulator.
+    pub fn name(&self, db: &CompilationDB) -> String {
+        match self.paramset {
+            Some(paramset) => paramset.name(db),
+            None => self.module.name(db),
+        }
+    }
+
     fn collect(
         db: &CompilationDB,
         cu: CompilationUnit,
         module: Module,
-        sink: &mut ConsoleSink,
+        paramset: Option<Paramset>,
+        sink: &mut impl DiagnosticSink,
+        resolved_attrs: &mut AHashSet<TextRange>,
         all_vars_opvars: bool,
     ) -> ModuleInfo {
         let mut params: IndexMap<Parameter, ParamInfo, ahash::RandomState> = IndexMap::default();
         let mut sys_fun_alias: IndexMap<ParamSysFun, Vec<SmolStr>, ahash::RandomState> =
             IndexMap::default();
         let mut op_vars = IndexMap::default();
+        let mut hidden_state = IndexSet::default();
 
         let ast = cu.ast(db);
 
-        let mut resolved_attrs = AHashSet::new();
-        let mut declarations = module.rec_declarations(db);
         let mut add_diagnostic = |attr: ast::Attr, diag: &dyn Diagnostic| {
             if resolved_attrs.insert(attr.syntax().text_range()) {
                 sink.add_diagnostic(diag, cu.root_file(), db)
             }
         };
-        while let Some((name, dec)) = declarations.next() {
-            match dec {
-                ScopeDef::Variable(var) => {
-                    // 3.2.1 Output variables
-                    //
-                    // operating point variables must fulfill two properties
-                    // * have a description or units attribute
-                    // * belong to a module (not a block/function) -> no path
-
-                    // check for units or description
-                    let units = var.get_attr(db, &ast, "units");
-                    let desc = var.get_attr(db, &ast, "desc");
-                    if units.is_none() && desc.is_none() && !all_vars_opvars {
-                        continue;
+        // paramset parameters are collected first so that they precede the hidden module parameters
+        let paramset_declarations = paramset.map(|paramset| (paramset.rec_declarations(db), false));
+        let module_declarations = (module.rec_declarations(db), paramset.is_some());
+        for (mut declarations, hidden) in
+            paramset_declarations.into_iter().chain(once(module_declarations))
+        {
+            while let Some((name, dec)) = declarations.next() {
+                match dec {
+                    ScopeDef::Variable(var) => {
+                        if var.get_attr(db, &ast, "hidden_state").is_some() {
+                            hidden_state.insert(var);
+                        }
+
+                        // 3.2.1 Output variables
+                        //
+                        // operating point variables must fulfill two properties
+                        // * have a description or units attribute
+                        // * belong to a module (not a block/function) -> no path
+
+                        // check for units or description
+                        let units = var.get_attr(db, &ast, "units");
+                        let desc = var.get_attr(db, &ast, "desc");
+                        if units.is_none() && desc.is_none() && !all_vars_opvars {
+                            continue;
+                        }
+
+                        // check that we are not in a block
+                        let name_len = name.len();
+                        let path = declarations.to_path(name);
+                        if path.len() != name_len {
+                            continue;
+                        }
+
+                        // array variables are lowered element-wise and can
+                        // therefore not be exposed as a single operating point variable
+                        if matches!(var.ty(db), Type::Array { .. }) {
+                            if let Some(attr) = units.or(desc) {
+                                add_diagnostic(attr.clone(), &ArrayOpVar { attr, name: path });
+                            }
+                            continue;
+                        }
+
+                        let units = units
+                            .and_then(|attr| {
+                                let lit = attr.val().and_then(|e| e.as_str_literal());
+                                if lit.is_none() {
+                                    add_diagnostic(attr.clone(), &IllegalAttr { attr });
+                                }
+                                lit
+                            })
+                            .unwrap_or_default();
+                        let desc = desc
+                            .and_then(|attr| {
+                                let lit = attr.val().and_then(|e| e.as_str_literal());
+                                if lit.is_none() {
+                                    add_diagnostic(attr.clone(), &IllegalAttr { attr });
+                                }
+                                lit
+                            })
+                            .unwrap_or_default();
+                        op_vars.insert(var, OpVar { unit: units, description: desc });
                     }
 
-                    // check that we are not in a block
-                    let name_len = name.len();
-                    let path = declarations.to_path(name);
-                    if path.len() != name_len {
-                        continue;
+                    ScopeDef::Parameter(param) if hidden => {
+                        params.insert(
+                            param,
+                            ParamInfo {
+                                name: declarations.to_path(name),
+                                hidden: true,
+                                ..ParamInfo::default()
+                            },
+                        );
                     }
-                    let units = units
-                        .and_then(|attr| {
-                            let lit = attr.val().and_then(|e| e.as_str_literal());
-                            if lit.is_none() {
-                                add_diagnostic(attr.clone(), &IllegalAttr { attr });
-                            }
-                            lit
-                        })
-                        .unwrap_or_default();
-                    let desc = desc
-                        .and_then(|attr| {
-                            let lit = attr.val().and_then(|e| e.as_str_literal());
-                            if lit.is_none() {
-                                add_diagnostic(attr.clone(), &IllegalAttr { attr });
-                            }
-                            lit
-                        })
-                        .unwrap_or_default();
-                    op_vars.insert(var, OpVar { unit: units, description: desc });
-                }
 
-                ScopeDef::Parameter(param) => {
-                    let units = param
-                        .get_attr(db, &ast, "units")
-                        .and_then(|attr| {
-                            let lit = attr.val().and_then(|e| e.as_str_literal());
-                            if lit.is_none() {
-                                add_diagnostic(attr.clone(), &IllegalAttr { attr });
-                            }
-                            lit
-                        })
-                        .unwrap_or_default();
+                    ScopeDef::Parameter(param) => {
+                        let units = param
+                            .get_attr(db, &ast, "units")
+                            .and_then(|attr| {
+                                let lit = attr.val().and_then(|e| e.as_str_literal());
+                                if lit.is_none() {
+                                    add_diagnostic(attr.clone(), &IllegalAttr { attr });
+                                }
+                                lit
+                            })
+                            .unwrap_or_default();
 
-                    let desc = param
-                        .get_attr(db, &ast, "desc")
-                        .and_then(|attr| {
-                            let lit = attr.val().and_then(|e| e.as_str_literal());
-                            if lit.is_none() {
-                                add_diagnostic(attr.clone(), &IllegalAttr { attr });
-                            }
-                            lit
-                        })
-                        .unwrap_or_default();
+                        let desc = param
+                            .get_attr(db, &ast, "desc")
+                            .and_then(|attr| {
+                                let lit = attr.val().and_then(|e| e.as_str_literal());
+                                if lit.is_none() {
+                                    add_diagnostic(attr.clone(), &IllegalAttr { attr });
+                                }
+                                lit
+                            })
+                            .unwrap_or_default();
+
+                        let group = param
+                            .get_attr(db, &ast, "group")
+                            .and_then(|attr| {
+                                let lit = attr.val().and_then(|e| e.as_str_literal());
+                                if lit.is_none() {
+                                    add_diagnostic(attr.clone(), &IllegalAttr { attr });
+                                }
+                                lit
+                            })
+                            .unwrap_or_default();
 
-                    let group = param
-                        .get_attr(db, &ast, "group")
-                        .and_then(|attr| {
+                        let type_attr = param.get_attr(db, &ast, "type");
+                        let type_ = param.get_attr(db, &ast, "type").and_then(|attr| {
                             let lit = attr.val().and_then(|e| e.as_str_literal());
                             if lit.is_none() {
                                 add_diagnostic(attr.clone(), &IllegalAttr { attr });
                             }
                             lit
-                        })
-                        .unwrap_or_default();
-
-                    let type_attr = param.get_attr(db, &ast, "type");
-                    let type_ = param.get_attr(db, &ast, "type").and_then(|attr| {
-                        let lit = attr.val().and_then(|e| e.as_str_literal());
-                        if lit.is_none() {
-                            add_diagnostic(attr.clone(), &IllegalAttr { attr });
-                        }
-                        lit
-                    });
-                    let is_instance = match type_.as_deref() {
-                        Some("instance") => true,
-                        Some("model") | None => false,
-                        Some(found) => {
-                            let attr = type_attr.unwrap();
-                            add_diagnostic(
-                                attr.clone(),
-                                &UnknownType { expr: attr.val().unwrap(), found },
-                            );
-                            false
-                        }
-                    };
-
-                    params.insert(
-                        param,
-                        ParamInfo {
-                            name: declarations.to_path(name),
-                            alias: Vec::new(),
-                            unit: units,
-                            description: desc,
-                            group,
-                            is_instance,
-                        },
-                    );
-                }
+                        });
+                        let is_instance = match type_.as_deref() {
+                            Some("instance") => true,
+                            Some("model") | None => false,
+                            Some(found) => {
+                                let attr = type_attr.unwrap();
+                                add_diagnostic(
+                                    attr.clone(),
+                                    &UnknownType { expr: attr.val().unwrap(), found },
+                                );
+                                false
+                            }
+                        };
 
-                ScopeDef::AliasParameter(alias) => match alias.resolve(db).unwrap() {
-                    ResolvedAliasParameter::Parameter(param) => {
-                        params.entry(param).or_default().alias.push(declarations.to_path(name))
+                        params.insert(
+                            param,
+                            ParamInfo {
+                                name: declarations.to_path(name),
+                                alias: Vec::new(),
+                                unit: units,
+                                description: desc,
+                                group,
+                                is_instance,
+                                hidden: false,
+                            },
+                        );
                     }
-                    ResolvedAliasParameter::SystemParameter(sys_fun) => {
-                        sys_fun_alias.entry(sys_fun).or_default().push(declarations.to_path(name))
-                    }
-                },
 
-                _ => (),
+                    ScopeDef::AliasParameter(alias) => match alias.resolve(db).unwrap() {
+                        ResolvedAliasParameter::Parameter(param) => {
+                            params.entry(param).or_default().alias.push(declarations.to_path(name))
+                        }
+                        ResolvedAliasParameter::SystemParameter(sys_fun) => sys_fun_alias
+                            .entry(sys_fun)
+                            .or_default()
+                            .push(declarations.to_path(name)),
+                    },
+
+                    _ => (),
+                }
             }
         }
 
-        ModuleInfo { module, params, op_vars, sys_fun_alias }
+        let param_defaults = paramset
+            .map(|paramset| paramset.overrides(db).into_iter().collect())
+            .unwrap_or_default();
+
+        ModuleInfo {
+            module,
+            paramset,
+            params,
+            param_defaults,
+            op_vars,
+            sys_fun_alias,
+            hidden_state,
+        }
     }
 }
 
@@ -220,6 +305,33 @@ impl Diagnostic for IllegalAttr {
     }
 }
 
+struct ArrayOpVar {
+    attr: ast::Attr,
+    name: SmolStr,
+}
+
+impl Diagnostic for ArrayOpVar {
+    fn build_report(&self, root_file: FileId, db: &dyn BaseDB) -> Report {
+        let FileSpan { range, file } = db
+            .parse(root_file)
+            .to_file_span(self.attr.syntax().text_range(), &db.sourcemap(root_file));
+        Report::warning()
+            .with_message(format!(
+                "array variable '{}' can not be an operating point variable",
+                self.name
+            ))
+            .with_labels(vec![Label {
+                style: LabelStyle::Primary,
+                file_id: file,
+                range: range.into(),
+                message: "the variable is not exposed to the simulator".to_owned(),
+            }])
+            .with_notes(vec![
+                "help: assign the elements to scalar variables to expose them".to_owned()
+            ])
+    }
+}
+
 struct UnknownType<'a> {
     expr: Expr,
     found: &'a str,
@@ -253,6 +365,8 @@ pub struct ParamInfo {
     pub description: String,
     pub group: String,
     pub is_instance: bool,
+    /// Hidden parameters are not exposed to the simulator and always use their default value.
+    pub hidden: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
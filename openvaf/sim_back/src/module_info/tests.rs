@@ -95,6 +95,7 @@ fn parameters() {
                     description: "hmm",
                     group: "foo",
                     is_instance: true,
+                    hidden: false,
                 },
             ),
             (
@@ -106,6 +107,7 @@ fn parameters() {
                     description: "hmm",
                     group: "foo",
                     is_instance: true,
+                    hidden: false,
                 },
             ),
             (
@@ -117,6 +119,7 @@ fn parameters() {
                     description: "",
                     group: "",
                     is_instance: false,
+                    hidden: false,
                 },
             ),
         ]
@@ -171,3 +174,34 @@ fn opvars() {
     "#]]
     .assert_debug_eq(&params);
 }
+
+#[test]
+fn array_opvar() {
+    let src = indoc! {r#"
+        module test;
+            (* units="m" *) real scalar, vector[0:1];
+        endmodule
+    "#};
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let mut buf = Buffer::no_color();
+    let modules = {
+        let mut sink = ConsoleSink::buffer(&db, &mut buf);
+        sink.annonymize_paths();
+        super::collect_modules(&db, false, &mut sink).unwrap()
+    };
+    let op_vars: Vec<_> = modules[0].op_vars.keys().map(|var| var.name(&db)).collect();
+    assert_eq!(op_vars, ["scalar"]);
+    expect_test::expect![[r#"
+        warning: array variable 'vector' can not be an operating point variable
+          --> /root.va:2:8
+          |
+        2 |     (* units="m" *) real scalar, vector[0:1];
+          |        ^^^^^^^^^ the variable is not exposed to the simulator
+          |
+          = help: assign the elements to scalar variables to expose them
+
+        warning: `root.va` generated 1 warning
+
+    "#]]
+    .assert_eq(&String::from_utf8(buf.into_inner()).unwrap());
+}
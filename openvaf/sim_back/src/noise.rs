@@ -1,14 +1,22 @@
 use lasso::Spur;
 use mir::Value;
-use stdx::Ieee64;
 
 use crate::dae::SimUnknown;
 
 #[derive(Debug, Clone)]
 pub enum NoiseSourceKind {
-    WhiteNoise { pwr: Value },
-    FlickerNoise { pwr: Value, exp: Value },
-    NoiseTable { log: bool, vals: Box<[(Ieee64, Ieee64)]> },
+    WhiteNoise {
+        pwr: Value,
+    },
+    FlickerNoise {
+        pwr: Value,
+        exp: Value,
+    },
+    /// `vals` contains the (frequency, power) pairs of the table
+    NoiseTable {
+        log: bool,
+        vals: Box<[Value]>,
+    },
 }
 
 #[derive(Debug)]
@@ -29,7 +37,11 @@ impl NoiseSource {
                 *pwr = f(*pwr);
                 *exp = f(*exp);
             }
-            NoiseSourceKind::NoiseTable { .. } => (),
+            NoiseSourceKind::NoiseTable { vals, .. } => {
+                for val in vals.iter_mut() {
+                    *val = f(*val);
+                }
+            }
         }
     }
 }
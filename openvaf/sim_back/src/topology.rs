@@ -36,6 +36,7 @@ use crate::util::strip_optbarrier_if_const;
 use crate::BranchWrite;
 
 mod builder;
+mod filter;
 mod lineralize;
 mod small_signal_network;
 #[cfg(test)]
@@ -133,10 +134,14 @@ impl Noise {
                     name,
                 )
             }
-            CallBackKind::NoiseTable(ref table) => (
-                NoiseSourceKind::NoiseTable { log: table.log, vals: table.vals.clone() },
-                table.name,
-            ),
+            CallBackKind::NoiseTable { name, log, .. } => {
+                let vals = func.dfg.instr_args(inst).to_vec();
+                let vals = vals
+                    .into_iter()
+                    .map(|val| ssa_builder.define_at_exit(func, F_ZERO, val, inst))
+                    .collect();
+                (NoiseSourceKind::NoiseTable { log, vals }, name)
+            }
             _ => unreachable!(),
         };
         Noise { name, kind, factor }
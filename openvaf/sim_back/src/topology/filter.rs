@@ -0,0 +1,104 @@
+//! State space realisation of laplace filters.
+//!
+//! A filter `y = N(s)/D(s) u` with `n = max(deg N, deg D)` is realised with `n + 1` internal
+//! unknowns `x_k` that represent the derivatives `wᵏ` of an auxiliary signal `w`:
+//!
+//! ```text
+//! ddt(x_k) - x_{k+1} = 0     for k < n
+//! Σ d_k x_k - u = 0          (D(s)w = u)
+//! y = Σ n_k x_k              (y = N(s)w)
+//! ```
+//!
+//! At DC all derivatives vanish so the output is simply `n_0/d_0 u`. The realisation does
+//! not require the filter to be proper (deg N <= deg D) or the leading coefficient of the
+//! denominator to be non-zero.
+
+use std::cmp::max;
+use std::iter::once;
+
+use hir_lower::{HirInterner, ImplicitEquationKind, ParamKind, PlaceKind};
+use mir::builder::InstBuilder;
+use mir::cursor::{Cursor, FuncCursor};
+use mir::{Function, Inst, FALSE, F_ZERO, TRUE};
+use mir_build::SSAVariableBuilder;
+use stdx::iter::zip;
+
+use crate::topology::{Contribution, Topology};
+use crate::util::add;
+
+/// Realises the laplace filter `inst` with a set of implicit equations.
+pub(super) fn build_filter(
+    func: &mut Function,
+    topology: &mut Topology,
+    inst: Inst,
+    num_len: u32,
+    den_len: u32,
+    intern: &mut HirInterner,
+    ssa_builder: &mut SSAVariableBuilder,
+) {
+    let args = func.dfg.instr_args(inst).to_owned();
+    let (input, coeffs) = args.split_first().unwrap();
+    let (num, den) = coeffs.split_at(num_len as usize);
+    debug_assert_eq!(den.len(), den_len as usize);
+    let order = max(num_len, den_len) as usize - 1;
+
+    let equations: Vec<_> = (0..=order)
+        .map(|_| {
+            let eq =
+                intern.implicit_equations.push_and_get_key(ImplicitEquationKind::LaplaceFilter);
+            let val = intern.ensure_param(&mut *func, ParamKind::ImplicitUnknown(eq));
+            (eq, val)
+        })
+        .collect();
+
+    // The unknowns are rotated so that each equation has a (non-zero) entry on the
+    // diagonal at DC: x_0 belongs to the last equation and x_{k+1} to the k-th equation
+    let states: Vec<_> =
+        once(equations[order].1).chain(equations[..order].iter().map(|&(_, val)| val)).collect();
+
+    // the output of the filter y = Σ n_k x_k
+    let res = func.dfg.first_result(inst);
+    let mut cursor = FuncCursor::new(func).after_inst(inst);
+    let mut out = F_ZERO;
+    for (&coeff, &state) in zip(num, &states) {
+        let val = cursor.ins().fmul(coeff, state);
+        add(&mut cursor, &mut out, val, false);
+    }
+    func.dfg.replace_uses(res, out);
+
+    // the equations are only valid if the filter was actually evaluated,
+    // otherwise the unknowns are collapsed (fixed to zero)
+    let collapse = ssa_builder.define_at_exit(func, TRUE, FALSE, inst);
+    if collapse != FALSE {
+        debug_assert_ne!(collapse, TRUE);
+        for &(eq, _) in &equations {
+            intern.outputs.insert(PlaceKind::CollapseImplicitEquation(eq), collapse.into());
+        }
+    }
+
+    let input = ssa_builder.define_at_exit(func, F_ZERO, *input, inst);
+    let den: Vec<_> =
+        den.iter().map(|&val| ssa_builder.define_at_exit(&mut *func, F_ZERO, val, inst)).collect();
+
+    // ddt(x_k) - x_{k+1} = 0
+    for (k, &(eq, unknown)) in equations[..order].iter().enumerate() {
+        let mut cursor = FuncCursor::new(func).at_exit();
+        let resist = cursor.ins().fneg(states[k + 1]);
+        let react = cursor.ins().optbarrier(states[k]);
+        let contribution =
+            Contribution { unknown: Some(unknown), resist, react, ..Contribution::default() };
+        topology.new_implicit_equation(eq, contribution);
+    }
+
+    // Σ d_k x_k - u = 0
+    let (eq, unknown) = equations[order];
+    let mut cursor = FuncCursor::new(func).at_exit();
+    let mut resist = F_ZERO;
+    for (&coeff, &state) in zip(&den, &states) {
+        let val = cursor.ins().fmul(coeff, state);
+        add(&mut cursor, &mut resist, val, false);
+    }
+    add(&mut cursor, &mut resist, input, true);
+    let contribution = Contribution { unknown: Some(unknown), resist, ..Contribution::default() };
+    topology.new_implicit_equation(eq, contribution);
+}
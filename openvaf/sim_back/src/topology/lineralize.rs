@@ -14,6 +14,7 @@ use mir::{
 };
 use typed_indexmap::TiSet;
 
+use crate::topology::filter::build_filter;
 use crate::topology::{Contribution, Noise};
 use crate::util::{add, update_optbarrier};
 
@@ -31,6 +32,9 @@ pub(super) enum Evaluation {
     },
     /// This operator is not used and can be ignored
     Dead,
+    /// The analog operator is a laplace filter that is realised
+    /// with a set of separate equations
+    Filter { num_len: u32, den_len: u32 },
 }
 
 impl<'a> super::Builder<'a> {
@@ -42,7 +46,6 @@ impl<'a> super::Builder<'a> {
     ) {
         let mut ssa_builder = mir_build::SSAVariableBuilder::new(self.cfg);
         for (operator_inst, evaluation) in analog_operators {
-            let arg0 = self.func.dfg.instr_args(operator_inst)[0];
             let cb = self.func.dfg.func_ref(operator_inst).unwrap();
             let is_noise = intern.callbacks[cb].is_noise();
             match evaluation {
@@ -121,6 +124,7 @@ impl<'a> super::Builder<'a> {
                             ..Contribution::default()
                         }
                     } else {
+                        let arg0 = self.func.dfg.instr_args(operator_inst)[0];
                         let arg0 =
                             ssa_builder.define_at_exit(self.func, F_ZERO, arg0, operator_inst);
                         Contribution {
@@ -133,6 +137,17 @@ impl<'a> super::Builder<'a> {
 
                     self.topology.new_implicit_equation(eq, contributions);
                 }
+                Evaluation::Filter { num_len, den_len } => {
+                    build_filter(
+                        self.func,
+                        self.topology,
+                        operator_inst,
+                        num_len,
+                        den_len,
+                        intern,
+                        &mut ssa_builder,
+                    );
+                }
             }
             // not needed anymore, wipe the callback
             self.func.dfg.zap_inst(operator_inst);
@@ -177,9 +192,24 @@ impl<'a> super::Builder<'a> {
                         ));
                     }
                 }
+                CallBackKind::LaplaceFilter { num_len, den_len } => {
+                    for inst in take(uses) {
+                        if self.func.layout.inst_block(inst).is_none() {
+                            continue;
+                        }
+                        // unlike ddt a filter produces a non-zero output for constant inputs
+                        // so the operator can only be removed if its unused
+                        if self.func.dfg.instr_safe_to_remove(inst) {
+                            self.func.dfg.zap_inst(inst);
+                            self.func.layout.remove_inst(inst);
+                            continue;
+                        }
+                        analog_operators.push((inst, Evaluation::Filter { num_len, den_len }));
+                    }
+                }
                 CallBackKind::WhiteNoise { .. }
                 | CallBackKind::FlickerNoise { .. }
-                | CallBackKind::NoiseTable(_) => {
+                | CallBackKind::NoiseTable { .. } => {
                     for inst in take(uses) {
                         analog_operators.push((
                             inst,
@@ -245,7 +275,11 @@ impl<'a> super::Builder<'a> {
                 // noise is always zero when these are evaluated
                 // TODO: complex noise power (would allow us to avoid creating an extra node here)
                 InstructionData::Call { func_ref, .. }
-                    if noise && callbacks[func_ref] != CallBackKind::TimeDerivative => {}
+                    if noise
+                        && !matches!(
+                            callbacks[func_ref],
+                            CallBackKind::TimeDerivative | CallBackKind::LaplaceFilter { .. }
+                        ) => {}
                 InstructionData::Binary { opcode: Opcode::Fmul, args } => {
                     if is_op_dependent(args[0]) && is_op_dependent(args[1]) {
                         return Evaluation::Equation;
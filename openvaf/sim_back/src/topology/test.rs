@@ -221,3 +221,23 @@ fn psp103() {
 
     assert(src);
 }
+
+#[test]
+fn laplace_filter() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module laplace_filter(inout a, inout c);
+            electrical a, c;
+            parameter real tau=1e-9;
+            analog begin
+                I(a, c) <+ laplace_nd(V(a, c), '{1.0}, '{1.0, tau});
+                I(a, c) <+ laplace_zp(V(a), '{}, '{-1e9, 0.0, -2e9, 0.0});
+            end
+        endmodule
+    "#};
+
+    let (func, topology, _) = compile(src);
+    assert!(func.validate());
+    // a filter of order n is realised with n + 1 internal unknowns
+    assert_eq!(topology.implicit_equations.len(), 5);
+}
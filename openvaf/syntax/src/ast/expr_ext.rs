@@ -295,6 +295,12 @@ impl ast::StrLit {
             .replace("\\\r\n", "\r\n")
     }
 }
+impl ast::IndexExpr {
+    pub fn index(&self) -> Option<ast::Expr> {
+        support::children(self.syntax()).nth(1)
+    }
+}
+
 impl ast::SelectExpr {
     pub fn then_val(&self) -> Option<ast::Expr> {
         support::children(self.syntax()).nth(1)
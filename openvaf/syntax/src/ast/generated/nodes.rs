@@ -147,6 +147,7 @@ impl EventStmt {
     pub fn final_step_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![final_step])
     }
+    pub fn event(&self) -> Option<Expr> { support::child(&self.syntax) }
     pub fn r_paren_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![')']) }
     pub fn stmt(&self) -> Option<Stmt> { support::child(&self.syntax) }
 }
@@ -251,6 +252,15 @@ impl ArrayExpr {
     pub fn r_curly_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T!['}']) }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IndexExpr {
+    pub(crate) syntax: SyntaxNode,
+}
+impl IndexExpr {
+    pub fn base(&self) -> Option<Expr> { support::child(&self.syntax) }
+    pub fn l_brack_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T!['[']) }
+    pub fn r_brack_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![']']) }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct Call {
     pub(crate) syntax: SyntaxNode,
 }
@@ -402,6 +412,7 @@ impl NetDecl {
     pub fn net_type_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![net_type])
     }
+    pub fn range(&self) -> Option<Range> { support::child(&self.syntax) }
     pub fn names(&self) -> AstChildren<Name> { support::children(&self.syntax) }
     pub fn semicolon_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![;]) }
 }
@@ -459,6 +470,115 @@ impl AliasParam {
     pub fn semicolon_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![;]) }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GenvarDecl {
+    pub(crate) syntax: SyntaxNode,
+}
+impl ast::AttrsOwner for GenvarDecl {}
+impl GenvarDecl {
+    pub fn genvar_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![genvar]) }
+    pub fn names(&self) -> AstChildren<Name> { support::children(&self.syntax) }
+    pub fn semicolon_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![;]) }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GenerateRegion {
+    pub(crate) syntax: SyntaxNode,
+}
+impl ast::AttrsOwner for GenerateRegion {}
+impl GenerateRegion {
+    pub fn generate_token(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, T![generate])
+    }
+    pub fn module_items(&self) -> AstChildren<ModuleItem> { support::children(&self.syntax) }
+    pub fn endgenerate_token(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, T![endgenerate])
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GenerateFor {
+    pub(crate) syntax: SyntaxNode,
+}
+impl ast::AttrsOwner for GenerateFor {}
+impl GenerateFor {
+    pub fn for_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![for]) }
+    pub fn l_paren_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T!['(']) }
+    pub fn semicolon_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![;]) }
+    pub fn condition(&self) -> Option<Expr> { support::child(&self.syntax) }
+    pub fn r_paren_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![')']) }
+    pub fn begin_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![begin]) }
+    pub fn block_scope(&self) -> Option<BlockScope> { support::child(&self.syntax) }
+    pub fn module_items(&self) -> AstChildren<ModuleItem> { support::children(&self.syntax) }
+    pub fn end_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![end]) }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ModuleInst {
+    pub(crate) syntax: SyntaxNode,
+}
+impl ast::AttrsOwner for ModuleInst {}
+impl ModuleInst {
+    pub fn module(&self) -> Option<NameRef> { support::child(&self.syntax) }
+    pub fn inst_params(&self) -> Option<InstParams> { support::child(&self.syntax) }
+    pub fn name(&self) -> Option<Name> { support::child(&self.syntax) }
+    pub fn inst_ports(&self) -> Option<InstPorts> { support::child(&self.syntax) }
+    pub fn semicolon_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![;]) }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InstParams {
+    pub(crate) syntax: SyntaxNode,
+}
+impl InstParams {
+    pub fn pound_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![#]) }
+    pub fn l_paren_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T!['(']) }
+    pub fn args(&self) -> AstChildren<InstArg> { support::children(&self.syntax) }
+    pub fn r_paren_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![')']) }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InstPorts {
+    pub(crate) syntax: SyntaxNode,
+}
+impl InstPorts {
+    pub fn l_paren_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T!['(']) }
+    pub fn args(&self) -> AstChildren<InstArg> { support::children(&self.syntax) }
+    pub fn r_paren_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![')']) }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InstArg {
+    pub(crate) syntax: SyntaxNode,
+}
+impl InstArg {
+    pub fn dot_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![.]) }
+    pub fn name(&self) -> Option<Name> { support::child(&self.syntax) }
+    pub fn l_paren_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T!['(']) }
+    pub fn expr(&self) -> Option<Expr> { support::child(&self.syntax) }
+    pub fn r_paren_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![')']) }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParamsetDecl {
+    pub(crate) syntax: SyntaxNode,
+}
+impl ast::AttrsOwner for ParamsetDecl {}
+impl ParamsetDecl {
+    pub fn paramset_token(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, T![paramset])
+    }
+    pub fn name(&self) -> Option<Name> { support::child(&self.syntax) }
+    pub fn module(&self) -> Option<NameRef> { support::child(&self.syntax) }
+    pub fn semicolon_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![;]) }
+    pub fn paramset_items(&self) -> AstChildren<ParamsetItem> { support::children(&self.syntax) }
+    pub fn endparamset_token(&self) -> Option<SyntaxToken> {
+        support::token(&self.syntax, T![endparamset])
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParamsetStmt {
+    pub(crate) syntax: SyntaxNode,
+}
+impl ast::AttrsOwner for ParamsetStmt {}
+impl ParamsetStmt {
+    pub fn dot_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![.]) }
+    pub fn param(&self) -> Option<Param> { support::child(&self.syntax) }
+    pub fn semicolon_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![;]) }
+}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub struct ModulePort {
     pub(crate) syntax: SyntaxNode,
 }
@@ -474,6 +594,7 @@ impl PortDecl {
     pub fn net_type_token(&self) -> Option<SyntaxToken> {
         support::token(&self.syntax, T![net_type])
     }
+    pub fn range(&self) -> Option<Range> { support::child(&self.syntax) }
     pub fn names(&self) -> AstChildren<Name> { support::children(&self.syntax) }
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -482,6 +603,7 @@ pub struct Var {
 }
 impl Var {
     pub fn name(&self) -> Option<Name> { support::child(&self.syntax) }
+    pub fn range(&self) -> Option<Range> { support::child(&self.syntax) }
     pub fn eq_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![=]) }
     pub fn default(&self) -> Option<Expr> { support::child(&self.syntax) }
 }
@@ -491,6 +613,7 @@ pub struct Param {
 }
 impl Param {
     pub fn name(&self) -> Option<Name> { support::child(&self.syntax) }
+    pub fn range(&self) -> Option<Range> { support::child(&self.syntax) }
     pub fn eq_token(&self) -> Option<SyntaxToken> { support::token(&self.syntax, T![=]) }
     pub fn default(&self) -> Option<Expr> { support::child(&self.syntax) }
     pub fn constraints(&self) -> AstChildren<Constraint> { support::children(&self.syntax) }
@@ -541,6 +664,7 @@ pub enum Expr {
     BinExpr(BinExpr),
     ParenExpr(ParenExpr),
     ArrayExpr(ArrayExpr),
+    IndexExpr(IndexExpr),
     Call(Call),
     SelectExpr(SelectExpr),
     PathExpr(PathExpr),
@@ -577,6 +701,7 @@ pub enum Item {
     DisciplineDecl(DisciplineDecl),
     NatureDecl(NatureDecl),
     ModuleDecl(ModuleDecl),
+    ParamsetDecl(ParamsetDecl),
 }
 impl ast::AttrsOwner for Item {}
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -589,6 +714,10 @@ pub enum ModuleItem {
     VarDecl(VarDecl),
     ParamDecl(ParamDecl),
     AliasParam(AliasParam),
+    GenvarDecl(GenvarDecl),
+    GenerateRegion(GenerateRegion),
+    GenerateFor(GenerateFor),
+    ModuleInst(ModuleInst),
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ModulePortKind {
@@ -596,6 +725,12 @@ pub enum ModulePortKind {
     Name(Name),
 }
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ParamsetItem {
+    ParamDecl(ParamDecl),
+    ParamsetStmt(ParamsetStmt),
+}
+impl ast::AttrsOwner for ParamsetItem {}
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ParamRef {
     Path(Path),
     SysFun(SysFun),
@@ -883,6 +1018,17 @@ impl AstNode for ArrayExpr {
     }
     fn syntax(&self) -> &SyntaxNode { &self.syntax }
 }
+impl AstNode for IndexExpr {
+    fn can_cast(kind: SyntaxKind) -> bool { kind == INDEX_EXPR }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode { &self.syntax }
+}
 impl AstNode for Call {
     fn can_cast(kind: SyntaxKind) -> bool { kind == CALL }
     fn cast(syntax: SyntaxNode) -> Option<Self> {
@@ -1092,6 +1238,105 @@ impl AstNode for AliasParam {
     }
     fn syntax(&self) -> &SyntaxNode { &self.syntax }
 }
+impl AstNode for GenvarDecl {
+    fn can_cast(kind: SyntaxKind) -> bool { kind == GENVAR_DECL }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode { &self.syntax }
+}
+impl AstNode for GenerateRegion {
+    fn can_cast(kind: SyntaxKind) -> bool { kind == GENERATE_REGION }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode { &self.syntax }
+}
+impl AstNode for GenerateFor {
+    fn can_cast(kind: SyntaxKind) -> bool { kind == GENERATE_FOR }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode { &self.syntax }
+}
+impl AstNode for ModuleInst {
+    fn can_cast(kind: SyntaxKind) -> bool { kind == MODULE_INST }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode { &self.syntax }
+}
+impl AstNode for InstParams {
+    fn can_cast(kind: SyntaxKind) -> bool { kind == INST_PARAMS }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode { &self.syntax }
+}
+impl AstNode for InstPorts {
+    fn can_cast(kind: SyntaxKind) -> bool { kind == INST_PORTS }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode { &self.syntax }
+}
+impl AstNode for InstArg {
+    fn can_cast(kind: SyntaxKind) -> bool { kind == INST_ARG }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode { &self.syntax }
+}
+impl AstNode for ParamsetDecl {
+    fn can_cast(kind: SyntaxKind) -> bool { kind == PARAMSET_DECL }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode { &self.syntax }
+}
+impl AstNode for ParamsetStmt {
+    fn can_cast(kind: SyntaxKind) -> bool { kind == PARAMSET_STMT }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        if Self::can_cast(syntax.kind()) {
+            Some(Self { syntax })
+        } else {
+            None
+        }
+    }
+    fn syntax(&self) -> &SyntaxNode { &self.syntax }
+}
 impl AstNode for ModulePort {
     fn can_cast(kind: SyntaxKind) -> bool { kind == MODULE_PORT }
     fn cast(syntax: SyntaxNode) -> Option<Self> {
@@ -1192,6 +1437,9 @@ impl From<ParenExpr> for Expr {
 impl From<ArrayExpr> for Expr {
     fn from(node: ArrayExpr) -> Expr { Expr::ArrayExpr(node) }
 }
+impl From<IndexExpr> for Expr {
+    fn from(node: IndexExpr) -> Expr { Expr::IndexExpr(node) }
+}
 impl From<Call> for Expr {
     fn from(node: Call) -> Expr { Expr::Call(node) }
 }
@@ -1210,8 +1458,8 @@ impl From<Literal> for Expr {
 impl AstNode for Expr {
     fn can_cast(kind: SyntaxKind) -> bool {
         match kind {
-            PREFIX_EXPR | BIN_EXPR | PAREN_EXPR | ARRAY_EXPR | CALL | SELECT_EXPR | PATH_EXPR
-            | PORT_FLOW => true,
+            PREFIX_EXPR | BIN_EXPR | PAREN_EXPR | ARRAY_EXPR | INDEX_EXPR | CALL | SELECT_EXPR
+            | PATH_EXPR | PORT_FLOW => true,
             _ => Literal::can_cast(kind),
         }
     }
@@ -1221,6 +1469,7 @@ impl AstNode for Expr {
             BIN_EXPR => Expr::BinExpr(BinExpr { syntax }),
             PAREN_EXPR => Expr::ParenExpr(ParenExpr { syntax }),
             ARRAY_EXPR => Expr::ArrayExpr(ArrayExpr { syntax }),
+            INDEX_EXPR => Expr::IndexExpr(IndexExpr { syntax }),
             CALL => Expr::Call(Call { syntax }),
             SELECT_EXPR => Expr::SelectExpr(SelectExpr { syntax }),
             PATH_EXPR => Expr::PathExpr(PathExpr { syntax }),
@@ -1235,6 +1484,7 @@ impl AstNode for Expr {
             Expr::BinExpr(it) => &it.syntax,
             Expr::ParenExpr(it) => &it.syntax,
             Expr::ArrayExpr(it) => &it.syntax,
+            Expr::IndexExpr(it) => &it.syntax,
             Expr::Call(it) => &it.syntax,
             Expr::SelectExpr(it) => &it.syntax,
             Expr::PathExpr(it) => &it.syntax,
@@ -1376,10 +1626,13 @@ impl From<NatureDecl> for Item {
 impl From<ModuleDecl> for Item {
     fn from(node: ModuleDecl) -> Item { Item::ModuleDecl(node) }
 }
+impl From<ParamsetDecl> for Item {
+    fn from(node: ParamsetDecl) -> Item { Item::ParamsetDecl(node) }
+}
 impl AstNode for Item {
     fn can_cast(kind: SyntaxKind) -> bool {
         match kind {
-            DISCIPLINE_DECL | NATURE_DECL | MODULE_DECL => true,
+            DISCIPLINE_DECL | NATURE_DECL | MODULE_DECL | PARAMSET_DECL => true,
             _ => false,
         }
     }
@@ -1388,6 +1641,7 @@ impl AstNode for Item {
             DISCIPLINE_DECL => Item::DisciplineDecl(DisciplineDecl { syntax }),
             NATURE_DECL => Item::NatureDecl(NatureDecl { syntax }),
             MODULE_DECL => Item::ModuleDecl(ModuleDecl { syntax }),
+            PARAMSET_DECL => Item::ParamsetDecl(ParamsetDecl { syntax }),
             _ => return None,
         };
         Some(res)
@@ -1397,6 +1651,7 @@ impl AstNode for Item {
             Item::DisciplineDecl(it) => &it.syntax,
             Item::NatureDecl(it) => &it.syntax,
             Item::ModuleDecl(it) => &it.syntax,
+            Item::ParamsetDecl(it) => &it.syntax,
         }
     }
 }
@@ -1424,11 +1679,24 @@ impl From<ParamDecl> for ModuleItem {
 impl From<AliasParam> for ModuleItem {
     fn from(node: AliasParam) -> ModuleItem { ModuleItem::AliasParam(node) }
 }
+impl From<GenvarDecl> for ModuleItem {
+    fn from(node: GenvarDecl) -> ModuleItem { ModuleItem::GenvarDecl(node) }
+}
+impl From<GenerateRegion> for ModuleItem {
+    fn from(node: GenerateRegion) -> ModuleItem { ModuleItem::GenerateRegion(node) }
+}
+impl From<GenerateFor> for ModuleItem {
+    fn from(node: GenerateFor) -> ModuleItem { ModuleItem::GenerateFor(node) }
+}
+impl From<ModuleInst> for ModuleItem {
+    fn from(node: ModuleInst) -> ModuleItem { ModuleItem::ModuleInst(node) }
+}
 impl AstNode for ModuleItem {
     fn can_cast(kind: SyntaxKind) -> bool {
         match kind {
             BODY_PORT_DECL | NET_DECL | ANALOG_BEHAVIOUR | FUNCTION | BRANCH_DECL | VAR_DECL
-            | PARAM_DECL | ALIAS_PARAM => true,
+            | PARAM_DECL | ALIAS_PARAM | GENVAR_DECL | GENERATE_REGION | GENERATE_FOR
+            | MODULE_INST => true,
             _ => false,
         }
     }
@@ -1442,6 +1710,10 @@ impl AstNode for ModuleItem {
             VAR_DECL => ModuleItem::VarDecl(VarDecl { syntax }),
             PARAM_DECL => ModuleItem::ParamDecl(ParamDecl { syntax }),
             ALIAS_PARAM => ModuleItem::AliasParam(AliasParam { syntax }),
+            GENVAR_DECL => ModuleItem::GenvarDecl(GenvarDecl { syntax }),
+            GENERATE_REGION => ModuleItem::GenerateRegion(GenerateRegion { syntax }),
+            GENERATE_FOR => ModuleItem::GenerateFor(GenerateFor { syntax }),
+            MODULE_INST => ModuleItem::ModuleInst(ModuleInst { syntax }),
             _ => return None,
         };
         Some(res)
@@ -1456,6 +1728,10 @@ impl AstNode for ModuleItem {
             ModuleItem::VarDecl(it) => &it.syntax,
             ModuleItem::ParamDecl(it) => &it.syntax,
             ModuleItem::AliasParam(it) => &it.syntax,
+            ModuleItem::GenvarDecl(it) => &it.syntax,
+            ModuleItem::GenerateRegion(it) => &it.syntax,
+            ModuleItem::GenerateFor(it) => &it.syntax,
+            ModuleItem::ModuleInst(it) => &it.syntax,
         }
     }
 }
@@ -1487,6 +1763,34 @@ impl AstNode for ModulePortKind {
         }
     }
 }
+impl From<ParamDecl> for ParamsetItem {
+    fn from(node: ParamDecl) -> ParamsetItem { ParamsetItem::ParamDecl(node) }
+}
+impl From<ParamsetStmt> for ParamsetItem {
+    fn from(node: ParamsetStmt) -> ParamsetItem { ParamsetItem::ParamsetStmt(node) }
+}
+impl AstNode for ParamsetItem {
+    fn can_cast(kind: SyntaxKind) -> bool {
+        match kind {
+            PARAM_DECL | PARAMSET_STMT => true,
+            _ => false,
+        }
+    }
+    fn cast(syntax: SyntaxNode) -> Option<Self> {
+        let res = match syntax.kind() {
+            PARAM_DECL => ParamsetItem::ParamDecl(ParamDecl { syntax }),
+            PARAMSET_STMT => ParamsetItem::ParamsetStmt(ParamsetStmt { syntax }),
+            _ => return None,
+        };
+        Some(res)
+    }
+    fn syntax(&self) -> &SyntaxNode {
+        match self {
+            ParamsetItem::ParamDecl(it) => &it.syntax,
+            ParamsetItem::ParamsetStmt(it) => &it.syntax,
+        }
+    }
+}
 impl From<Path> for ParamRef {
     fn from(node: Path) -> ParamRef { ParamRef::Path(node) }
 }
@@ -1587,6 +1891,11 @@ impl std::fmt::Display for ModulePortKind {
         std::fmt::Display::fmt(self.syntax(), f)
     }
 }
+impl std::fmt::Display for ParamsetItem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.syntax(), f)
+    }
+}
 impl std::fmt::Display for ParamRef {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.syntax(), f)
@@ -1722,6 +2031,11 @@ impl std::fmt::Display for ArrayExpr {
         std::fmt::Display::fmt(self.syntax(), f)
     }
 }
+impl std::fmt::Display for IndexExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.syntax(), f)
+    }
+}
 impl std::fmt::Display for Call {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.syntax(), f)
@@ -1817,6 +2131,51 @@ impl std::fmt::Display for AliasParam {
         std::fmt::Display::fmt(self.syntax(), f)
     }
 }
+impl std::fmt::Display for GenvarDecl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.syntax(), f)
+    }
+}
+impl std::fmt::Display for GenerateRegion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.syntax(), f)
+    }
+}
+impl std::fmt::Display for GenerateFor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.syntax(), f)
+    }
+}
+impl std::fmt::Display for ModuleInst {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.syntax(), f)
+    }
+}
+impl std::fmt::Display for InstParams {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.syntax(), f)
+    }
+}
+impl std::fmt::Display for InstPorts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.syntax(), f)
+    }
+}
+impl std::fmt::Display for InstArg {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.syntax(), f)
+    }
+}
+impl std::fmt::Display for ParamsetDecl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.syntax(), f)
+    }
+}
+impl std::fmt::Display for ParamsetStmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(self.syntax(), f)
+    }
+}
 impl std::fmt::Display for ModulePort {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         std::fmt::Display::fmt(self.syntax(), f)
@@ -135,6 +135,48 @@ impl ast::Range {
     pub fn end(&self) -> Option<ast::Expr> {
         support::children(self.syntax()).nth(1)
     }
+
+    /// The lower bound and the number of elements of an array dimension `[lo:hi]` (or
+    /// `[hi:lo]`). The bounds must be constant integer expressions of literals.
+    pub fn array_dim(&self) -> Option<(i32, u32)> {
+        let start = const_int(self.start()?)?;
+        let end = const_int(self.end()?)?;
+        let lo = i32::try_from(start.min(end)).ok()?;
+        let len = u32::try_from(start.max(end) - start.min(end) + 1).ok()?;
+        Some((lo, len))
+    }
+}
+
+/// Folds an integer expression that only consists of literals (for example `2*4-1`).
+fn const_int(expr: ast::Expr) -> Option<i64> {
+    match expr {
+        ast::Expr::Literal(lit) => match lit.kind() {
+            ast::LiteralKind::IntNumber(num) => Some(num.value().into()),
+            _ => None,
+        },
+        ast::Expr::ParenExpr(expr) => const_int(expr.expr()?),
+        ast::Expr::PrefixExpr(expr) => {
+            let arg = const_int(expr.expr()?)?;
+            match expr.op_kind()? {
+                ast::UnaryOp::Neg => arg.checked_neg(),
+                ast::UnaryOp::Identity => Some(arg),
+                _ => None,
+            }
+        }
+        ast::Expr::BinExpr(expr) => {
+            let lhs = const_int(expr.lhs()?)?;
+            let rhs = const_int(expr.rhs()?)?;
+            match expr.op_kind()? {
+                ast::BinaryOp::Addition => lhs.checked_add(rhs),
+                ast::BinaryOp::Subtraction => lhs.checked_sub(rhs),
+                ast::BinaryOp::Multiplication => lhs.checked_mul(rhs),
+                ast::BinaryOp::Division => lhs.checked_div(rhs),
+                ast::BinaryOp::Remainder => lhs.checked_rem(rhs),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
 }
 
 impl ast::IfStmt {
@@ -228,6 +270,16 @@ impl ForStmt {
     }
 }
 
+impl ast::GenerateFor {
+    pub fn init(&self) -> Option<Stmt> {
+        support::child(self.syntax())
+    }
+
+    pub fn incr(&self) -> Option<Stmt> {
+        support::children(self.syntax()).nth(1)
+    }
+}
+
 impl EventStmt {
     pub fn sim_phases(&self) -> AstChildTokens<StrLit> {
         support::child_token(self.syntax())
@@ -108,6 +108,10 @@ pub enum SyntaxError {
         range: TextRange,
         ty: TextRange,
     },
+
+    IllegalArrayDimension {
+        range: TextRange,
+    },
 }
 
 use SyntaxError::*;
@@ -137,5 +141,6 @@ impl_display! {
         IllegalNetType{found,..} => "{} nets are currently not supported!",found;
         RangeConstraintForNonNumericParameter{param,..} => "non-numeric parameter '{}' has range bounds", param;
         PortNotDeclaredInModule{name,..} => "port '{name}' was not declared in the module head";
+        IllegalArrayDimension{..} => "unsupported array dimension";
     }
 }
@@ -15,7 +15,10 @@ pub use ast::AstNode;
 pub use error::SyntaxError;
 pub use preprocessor::diagnostics::PreprocessorDiagnostic;
 use preprocessor::sourcemap::{CtxSpan, FileSpan, SourceContext};
-pub use preprocessor::{preprocess, sourcemap, Preprocess, SourceProvider};
+pub use preprocessor::{
+    preprocess, sourcemap, DecryptionKey, Directives, Preprocess, ProtectError, SourceProvider,
+    DEFAULT_MACRO_EXPANSION_LIMIT,
+};
 pub use ptr::{AstPtr, SyntaxNodePtr};
 pub use rowan::{
     Direction, GreenNode, NodeOrToken, SyntaxText, TextRange, TextSize, TokenAtOffset, WalkEvent,
@@ -35,6 +38,8 @@ pub struct Parse<T> {
     green: GreenNode,
     errors: Arc<Vec<SyntaxError>>,
     pub ctx_map: Arc<Vec<(TextRange, SourceContext, TextSize)>>,
+    /// The compiler directives in effect starting at the given position (sorted by position).
+    directives: Arc<Vec<(TextSize, Directives)>>,
     _ty: PhantomData<fn() -> T>,
 }
 
@@ -44,6 +49,7 @@ impl<T> Clone for Parse<T> {
             green: self.green.clone(),
             errors: self.errors.clone(),
             ctx_map: self.ctx_map.clone(),
+            directives: self.directives.clone(),
             _ty: PhantomData,
         }
     }
@@ -54,8 +60,15 @@ impl<T> Parse<T> {
         green: GreenNode,
         errors: Vec<SyntaxError>,
         ctx_map: Vec<(TextRange, SourceContext, TextSize)>,
+        directives: Vec<(TextSize, Directives)>,
     ) -> Parse<T> {
-        Parse { green, errors: Arc::new(errors), ctx_map: Arc::new(ctx_map), _ty: PhantomData }
+        Parse {
+            green,
+            errors: Arc::new(errors),
+            ctx_map: Arc::new(ctx_map),
+            directives: Arc::new(directives),
+            _ty: PhantomData,
+        }
     }
 
     pub fn syntax_node(&self) -> SyntaxNode {
@@ -108,11 +121,23 @@ impl<T> Parse<T> {
     pub fn to_file_span(&self, range: TextRange, sm: &sourcemap::SourceMap) -> FileSpan {
         self.to_ctx_span(range, sm).to_file_span(sm)
     }
+
+    /// Returns the compiler directives (like `` `default_discipline ``) in effect at `pos`.
+    pub fn directives(&self, pos: TextSize) -> Directives {
+        let i = self.directives.partition_point(|(start, _)| *start <= pos);
+        i.checked_sub(1).map_or_else(Directives::default, |i| self.directives[i].1.clone())
+    }
 }
 
 impl<T: AstNode> Parse<T> {
     pub fn to_syntax(self) -> Parse<SyntaxNode> {
-        Parse { green: self.green, errors: self.errors, ctx_map: self.ctx_map, _ty: PhantomData }
+        Parse {
+            green: self.green,
+            errors: self.errors,
+            ctx_map: self.ctx_map,
+            directives: self.directives,
+            _ty: PhantomData,
+        }
     }
 
     pub fn tree(&self) -> T {
@@ -139,6 +164,7 @@ impl Parse<SyntaxNode> {
                 green: self.green,
                 errors: self.errors,
                 ctx_map: self.ctx_map,
+                directives: self.directives,
                 _ty: PhantomData,
             })
         } else {
@@ -197,7 +223,19 @@ impl SourceFile {
 
         assert_eq!(root.kind(), SyntaxKind::SOURCE_FILE);
 
-        Parse::new(green, errors, ctx_map)
+        // the text of the syntax tree is the text of all tokens
+        let mut directives = Vec::with_capacity(preprocess.directives.len());
+        let mut changes = preprocess.directives.iter().peekable();
+        let mut pos = TextSize::from(0);
+        for (i, token) in preprocess.ts.iter().enumerate() {
+            while let Some((_, state)) = changes.next_if(|(start, _)| *start == i) {
+                directives.push((pos, state.clone()));
+            }
+            pos += token.span.range.len();
+        }
+        directives.extend(changes.map(|(_, state)| (pos, state.clone())));
+
+        Parse::new(green, errors, ctx_map, directives)
     }
 }
 
@@ -48,7 +48,14 @@ impl<'a> SyntaxTreeBuilder<'a> {
         let span = self.tokens[self.token_pos].span;
         self.panic &= !matches!(
             kind,
-            T![;] | T![end] | T![endnature] | T![endmodule] | T![enddiscipline] | T![endfunction]
+            T![;]
+                | T![end]
+                | T![endnature]
+                | T![endmodule]
+                | T![enddiscipline]
+                | T![endfunction]
+                | T![endgenerate]
+                | T![endparamset]
         ) || self.err_depth != u32::MAX;
         self.do_token(kind, span);
     }
@@ -217,12 +224,20 @@ impl<'a> SyntaxTreeBuilder<'a> {
             // We are in a different ctx and therefore the text comes from somewhere else...
             // Switch the src code
             // Unwrap is okay here because the file was already read succesffully by he preprocessor or the SourceContext wouldn't exist
-            let decl = self.sm.ctx_data(span.ctx).decl;
-            let src = self.db.file_text(decl.file).unwrap();
-            self.current_src = src;
+            self.current_src = match self.sm.generated_text(span.ctx) {
+                Some(text) => text.clone(),
+                None => {
+                    let decl = self.sm.ctx_data(span.ctx).decl;
+                    self.db.file_text(decl.file).unwrap()
+                }
+            };
         }
 
-        let range = span.to_file_span(self.sm).range;
+        let range = if self.sm.generated_text(span.ctx).is_some() {
+            span.range
+        } else {
+            span.to_file_span(self.sm).range
+        };
         let text = &self.current_src[range];
         self.text_pos += range.len();
         self.token_pos += 1;
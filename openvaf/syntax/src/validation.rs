@@ -24,6 +24,8 @@ pub(crate) fn validate(root: &SyntaxNode, errors: &mut Vec<SyntaxError>) {
                 ast::Name(name) => validate_name(name,errors),
                 ast::ModuleDecl(module) => validate_module(module,errors),
                 ast::ParamDecl(param) => validate_param(param, errors),
+                ast::Var(var) => validate_array_dim(var.range(), errors),
+                ast::Param(param) => validate_array_dim(param.range(), errors),
                 _ => validate_net_type_token(node,errors)
             }
         }
@@ -52,10 +54,18 @@ fn validate_param(param_decl: ast::ParamDecl, errors: &mut Vec<SyntaxError>) {
     }
 }
 
+fn validate_array_dim(range: Option<ast::Range>, errors: &mut Vec<SyntaxError>) {
+    if let Some(range) = range {
+        if range.array_dim().is_none() {
+            errors.push(SyntaxError::IllegalArrayDimension { range: range.syntax().text_range() })
+        }
+    }
+}
+
 fn validate_net_type_token(node: SyntaxNode, errors: &mut Vec<SyntaxError>) {
     if matches!(node.kind(), SyntaxKind::NET_DECL | SyntaxKind::PORT_DECL) {
         if let Some(token) = support::token(&node, NET_TYPE) {
-            if token.text() != kw::raw::ground {
+            if !matches!(token.text(), kw::raw::ground | kw_comp::raw::wreal) {
                 errors.push(SyntaxError::IllegalNetType {
                     found: token.text().to_owned(),
                     range: token.text_range(),
@@ -95,6 +95,11 @@ pub(crate) const KINDS_SRC: KindsSrc = KindsSrc {
         "initial",
         "final_step",
         "aliasparam",
+        "generate",
+        "endgenerate",
+        "genvar",
+        "paramset",
+        "endparamset",
     ],
     literals: &["INT_NUMBER", "STD_REAL_NUMBER", "SI_REAL_NUMBER", "STR_LIT"],
     tokens: &["ERROR", "IDENT", "SYSFUN", "NET_TYPE", "WHITESPACE", "COMMENT"],
@@ -123,11 +128,19 @@ pub(crate) const KINDS_SRC: KindsSrc = KindsSrc {
         "FOR_STMT",
         "FUNCTION",
         "FUNCTION_ARG",
+        "GENERATE_FOR",
+        "GENERATE_REGION",
+        "GENVAR_DECL",
         "IF_STMT",
+        "INDEX_EXPR",
         "LITERAL",
         "MODULE_DECL",
         "MODULE_PORT",
         "MODULE_PORTS",
+        "MODULE_INST",
+        "INST_PARAMS",
+        "INST_PORTS",
+        "INST_ARG",
         "NAME",
         "NAME_REF",
         "SYS_FUN",
@@ -139,6 +152,8 @@ pub(crate) const KINDS_SRC: KindsSrc = KindsSrc {
         "PARAM",
         "ALIAS_PARAM",
         "PARAM_DECL",
+        "PARAMSET_DECL",
+        "PARAMSET_STMT",
         "PAREN_EXPR",
         "PATH",
         "PATH_EXPR",
@@ -24,29 +24,9 @@ const ANALOG_OPERATORS: [&str; 17] = [
     "transition",
 ];
 
-const UNSUPPORTED: [&str; 50] = [
-    "simprobe",
-    "analog_node_alias",
-    "analog_port_alias",
-    "test_plusargs",
-    "value_plusargs",
-    "zi_nd",
-    "zi_np",
-    "zi_zd",
-    "zi_zp",
-    "laplace_nd",
-    "laplace_np",
-    "laplace_zd",
-    "laplace_zp",
-    "last_crossing",
-    "slew",
-    "transition",
-    "fclose",
-    "fopen",
-    "fdisplay",
-    "fwrite",
-    "fstrobe",
-    "fmonitor",
+const EVENT_FUNS: [&str; 3] = ["above", "cross", "timer"];
+
+const UNSUPPORTED: [&str; 10] = [
     "fgets",
     "fscanf",
     "swrite",
@@ -55,26 +35,8 @@ const UNSUPPORTED: [&str; 50] = [
     "rewind",
     "fseek",
     "ftell",
-    "fflush",
     "ferror",
     "feof",
-    "fdebug",
-    "dist_chi_square",
-    "dist_exponential",
-    "dist_poisson",
-    "dist_uniform",
-    "dist_erlang",
-    "dist_normal",
-    "dist_t",
-    "random",
-    "arandom",
-    "rdist_chi_square",
-    "rdist_exponential",
-    "rdist_poisson",
-    "rdist_uniform",
-    "rdist_erlang",
-    "rdist_normal",
-    "rdist_t",
 ];
 
 const ANALOG_OPERATORS_SYSFUN: [&str; 1] = ["$limit"];
@@ -191,7 +153,7 @@ const SYSFUNS: [&str; 81] = [
     "$port_connected",
     "$analog_node_alias",
     "$analog_port_alias",
-    // "$table_model",
+    "$table_model",
     "$test$plusargs",
     "$value$plusargs",
     "$bound_step",
@@ -208,6 +170,7 @@ fn generate_builtins() {
         .chain(ANALYSIS_FUNS)
         .chain(ANALOG_OPERATORS_SYSFUN)
         .chain(ANALOG_OPERATORS)
+        .chain(EVENT_FUNS)
         .map(|builtin| {
             let is_sysfun = builtin.starts_with('$');
 
@@ -228,6 +191,7 @@ fn generate_builtins() {
 
     let analysis_funs = ANALYSIS_FUNS.into_iter().map(|op| format_ident!("{}", op));
     let analog_operators = ANALOG_OPERATORS.into_iter().map(|op| format_ident!("{}", op));
+    let event_funs = EVENT_FUNS.into_iter().map(|op| format_ident!("{}", op));
     let unsupported = UNSUPPORTED.into_iter().map(|op| format_ident!("{}", op));
     let analog_operators_sysfun =
         ANALOG_OPERATORS_SYSFUN.into_iter().map(|op| format_ident!("{}", &op[1..]));
@@ -273,6 +237,14 @@ fn generate_builtins() {
                 }
             }
 
+            #[allow(clippy::match_like_matches_macro)]
+            pub fn is_event_fun(self)->bool{
+                match self{
+                    #(BuiltIn::#event_funs)|* =>true,
+                    _ => false
+                }
+            }
+
             #[allow(clippy::match_like_matches_macro)]
             pub fn is_unsupported(self)->bool{
                 match self{
@@ -68,6 +68,7 @@ pub fn stub_callbacks<'ll>(
                 | CallBackKind::BuiltinLimit { .. }
                 | CallBackKind::StoreLimit(_)
                 | CallBackKind::LimDiscontinuity
+                | CallBackKind::Discontinuity
                 | CallBackKind::CollapseHint(_, _) => return None,
                 CallBackKind::Analysis => cx.const_callback(&[cx.ty_ptr()], cx.const_int(1)),
             };
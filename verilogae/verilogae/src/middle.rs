@@ -1,4 +1,4 @@
-use ahash::AHashSet;
+use ahash::{AHashMap, AHashSet};
 use bitset::{BitSet, SparseBitMatrix};
 use hir_lower::{CallBackKind, HirInterner, MirBuilder, ParamKind, PlaceKind};
 use lasso::Rodeo;
@@ -134,7 +134,7 @@ pub fn build_param_init_mir(
     let mut intern = HirInterner::default();
 
     let params: Vec<_> = info.params.keys().copied().collect();
-    intern.insert_param_init(db, &mut func, literals, true, false, &params);
+    intern.insert_param_init(db, &mut func, literals, true, false, &params, &AHashMap::new());
 
     (func, intern)
 }